@@ -11,6 +11,15 @@ export const MOCK_PROJECT_A: Project = {
 	preferred_key: 'systemExecutable',
 	ok_with_force_push: true,
 	force_push_protection: false,
+	upstream_integration_policy: {
+		safelyUpdatable: 'rebase',
+		conflicted: 'merge',
+		integrated: 'delete'
+	},
+	ai_provider: { kind: 'openAi' },
+	commit_message_style: { template: null, validate: false },
+	disabled_git_hooks: [],
+	trusted_claude_policy: null,
 	omit_certificate_check: false,
 	use_diff_context: true,
 	is_open: false,
@@ -27,7 +27,8 @@ export const MOCK_APP_SETTINGS: AppSettings = {
 		notifyOnPermissionRequest: false,
 		dangerouslyAllowAllPermissions: false,
 		autoCommitAfterCompletion: true,
-		useConfiguredModel: false
+		useConfiguredModel: false,
+		isolateSessionsInWorktrees: false
 	},
 	reviews: {
 		autoFillPrDescriptionFromCommit: true
@@ -36,5 +37,8 @@ export const MOCK_APP_SETTINGS: AppSettings = {
 		useNativeTitleBar: false,
 		cliIsManagedByPackageManager: false,
 		checkForUpdatesIntervalInSeconds: 3600
+	},
+	secrets: {
+		store: 'keychain'
 	}
 };
@@ -31,7 +31,14 @@ type ClaudeCodeActionSource = {
 };
 
 type CursorActionSource = {
-	ClaudeCode: string;
+	Cursor: string;
+};
+
+type AgentActionSource = {
+	Agent: {
+		name: string;
+		session_id: string;
+	};
 };
 
 export type ActionSource =
@@ -40,7 +47,8 @@ export type ActionSource =
 	| 'Unknown'
 	| MCPActionSource
 	| ClaudeCodeActionSource
-	| CursorActionSource;
+	| CursorActionSource
+	| AgentActionSource;
 
 export function isStringActionSource(
 	source: ActionSource
@@ -68,6 +76,10 @@ export function isCursorActionSource(source: ActionSource): source is CursorActi
 	return typeof source === 'object' && source !== null && 'Cursor' in source;
 }
 
+export function isAgentActionSource(source: ActionSource): source is AgentActionSource {
+	return typeof source === 'object' && source !== null && 'Agent' in source;
+}
+
 /** Represents a snapshot of an automatic action taken by a GitButler automation.  */
 export class ButlerAction {
 	/** UUID identifier of the action */
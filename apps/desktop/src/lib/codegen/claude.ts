@@ -2,7 +2,11 @@ import {
 	type ClaudeCheckResult,
 	type ClaudeMessage,
 	type ClaudePermissionRequest,
+	type ClaudePermissionDecisionRecord,
 	type ClaudeSessionDetails,
+	type ClaudeTurn,
+	type ClaudeUsageReport,
+	type PendingWorktree,
 	type ThinkingLevel,
 	type ModelType,
 	type PermissionMode,
@@ -91,6 +95,34 @@ export class ClaudeCodeService {
 		return this.api.endpoints.getSessionDetails.fetch;
 	}
 
+	usage(projectId: string) {
+		return this.api.endpoints.getUsage.useQuery({ projectId });
+	}
+
+	permissionDecisions(projectId: string, sessionId: string) {
+		return this.api.endpoints.getPermissionDecisions.useQuery({ projectId, sessionId });
+	}
+
+	pendingWorktrees(projectId: string) {
+		return this.api.endpoints.listPendingWorktrees.useQuery({ projectId });
+	}
+
+	turns(projectId: string, sessionId: string) {
+		return this.api.endpoints.listTurns.useQuery({ projectId, sessionId });
+	}
+
+	get exportSessionMarkdown() {
+		return this.api.endpoints.exportSessionMarkdown.fetch;
+	}
+
+	get restoreTurn() {
+		return this.api.endpoints.restoreTurn.mutate;
+	}
+
+	get setSessionBudget() {
+		return this.api.endpoints.setSessionBudget.mutate;
+	}
+
 	promptTemplates(projectId: string) {
 		return this.api.endpoints.listPromptTemplates.useQuery({ projectId });
 	}
@@ -188,6 +220,13 @@ function injectEndpoints(api: ClientState['backendApi']) {
 							if (payload.source === 'gitButler' && payload.type === 'commitCreated') {
 								lifecycleApi.dispatch(api.util.invalidateTags([invalidatesList(ReduxTag.HeadSha)]));
 							}
+							if (payload.source === 'system' && payload.type === 'worktreeIntegration') {
+								lifecycleApi.dispatch(
+									api.util.invalidateTags([
+										invalidatesItem(ReduxTag.ClaudePendingWorktrees, arg.projectId)
+									])
+								);
+							}
 							lifecycleApi.updateCachedData((events) => {
 								events.push(event.payload);
 							});
@@ -243,6 +282,75 @@ function injectEndpoints(api: ClientState['backendApi']) {
 					invalidatesItem(ReduxTag.ClaudePermissionRequests, args.projectId)
 				]
 			}),
+			getPermissionDecisions: build.query<
+				ClaudePermissionDecisionRecord[],
+				{ projectId: string; sessionId: string }
+			>({
+				extraOptions: { command: 'claude_get_permission_decisions' },
+				query: (args) => args,
+				providesTags: (_result, _error, args) => [
+					...providesItem(ReduxTag.ClaudePermissionRequests, args.projectId)
+				]
+			}),
+			listPendingWorktrees: build.query<PendingWorktree[], { projectId: string }>({
+				extraOptions: { command: 'claude_list_pending_worktrees' },
+				query: (args) => args,
+				providesTags: (_result, _error, args) => [
+					...providesItem(ReduxTag.ClaudePendingWorktrees, args.projectId)
+				]
+			}),
+			listTurns: build.query<ClaudeTurn[], { projectId: string; sessionId: string }>({
+				extraOptions: { command: 'claude_list_turns' },
+				query: (args) => args,
+				providesTags: [providesList(ReduxTag.WorktreeChanges)]
+			}),
+			exportSessionMarkdown: build.query<string, { projectId: string; sessionId: string }>({
+				extraOptions: { command: 'claude_export_session_markdown' },
+				query: (args) => args
+			}),
+			restoreTurn: build.mutation<
+				undefined,
+				{
+					projectId: string;
+					stackId: string;
+					sessionId: string;
+					messageId: string;
+				}
+			>({
+				extraOptions: {
+					command: 'claude_restore_turn',
+					actionName: 'Restore Claude Turn'
+				},
+				query: (args) => args,
+				invalidatesTags: [
+					invalidatesList(ReduxTag.WorktreeChanges),
+					invalidatesList(ReduxTag.HeadSha)
+				]
+			}),
+			getUsage: build.query<ClaudeUsageReport, { projectId: string }>({
+				extraOptions: { command: 'claude_get_usage' },
+				query: (args) => args,
+				providesTags: (_result, _error, args) => [
+					...providesItem(ReduxTag.ClaudeUsage, args.projectId)
+				]
+			}),
+			setSessionBudget: build.mutation<
+				undefined,
+				{
+					projectId: string;
+					sessionId: string;
+					budgetUsd: number | null;
+				}
+			>({
+				extraOptions: {
+					command: 'claude_set_session_budget',
+					actionName: 'Set Session Budget'
+				},
+				query: (args) => args,
+				invalidatesTags: (_result, _error, args) => [
+					invalidatesItem(ReduxTag.ClaudeUsage, args.projectId)
+				]
+			}),
 			cancelSession: build.mutation<
 				boolean,
 				{
@@ -10,7 +10,8 @@ import type {
 	ClaudeTodo,
 	PromptAttachment,
 	GitButlerUpdate,
-	SystemMessage
+	SystemMessage,
+	WorktreeIntegration
 } from '$lib/codegen/types';
 
 export type Message = { createdAt: string } &
@@ -170,7 +171,8 @@ export function formatMessages(
 				payload.type === 'userAbort' ||
 				payload.type === 'unhandledException' ||
 				payload.type === 'compactStart' ||
-				payload.type === 'compactFinished'
+				payload.type === 'compactFinished' ||
+				payload.type === 'budgetExceeded'
 			) {
 				wrapUpAgentSide();
 			}
@@ -212,6 +214,33 @@ export function formatMessages(
 					toolCallsPendingApproval: []
 				});
 			}
+			if (payload.type === 'budgetExceeded') {
+				out.push({
+					source: 'claude',
+					createdAt: message.createdAt,
+					message: `I've stopped as this session exceeded its budget of $${payload.budgetUsd.toFixed(2)}, an estimated $${payload.costUsd.toFixed(2)} has been spent.`,
+					toolCalls: [],
+					toolCallsPendingApproval: []
+				});
+			}
+			if (payload.type === 'permissionPolicyIgnored') {
+				out.push({
+					source: 'claude',
+					createdAt: message.createdAt,
+					message: payload.message,
+					toolCalls: [],
+					toolCallsPendingApproval: []
+				});
+			}
+			if (payload.type === 'worktreeIntegration') {
+				out.push({
+					source: 'claude',
+					createdAt: message.createdAt,
+					message: formatWorktreeIntegration(payload),
+					toolCalls: [],
+					toolCallsPendingApproval: []
+				});
+			}
 			if (payload.type === 'compactFinished') {
 				out.push({
 					source: 'claude',
@@ -257,6 +286,31 @@ export function formatMessages(
 	return out;
 }
 
+function formatWorktreeIntegration(integration: WorktreeIntegration): string {
+	if (integration.integrated) {
+		return `Integrated the changes from my worktree into \`${integration.branchName}\`.`;
+	}
+	const reason = (() => {
+		switch (integration.status.type) {
+			case 'noMergeBaseFound':
+				return 'no merge base with the branch was found';
+			case 'worktreeIsBare':
+				return 'the worktree is bare';
+			case 'causesWorkspaceConflicts':
+				return 'they would conflict with other branches in the workspace';
+			case 'integratable': {
+				const { cherry_pick_conflicts, commits_above_conflict, working_dir_conflicts } =
+					integration.status.data;
+				if (cherry_pick_conflicts || commits_above_conflict || working_dir_conflicts) {
+					return 'they would cause conflicts';
+				}
+				return 'auto-commit after completion is disabled';
+			}
+		}
+	})();
+	return `The changes in my worktree \`${integration.worktreeId}\` were not integrated into \`${integration.branchName}\` as ${reason}. They will be kept for the next message, or can be integrated with \`but worktree integrate\`.`;
+}
+
 function previousEventLoginFailureQuery(events: ClaudeMessage[], event: ClaudeMessage): boolean {
 	const idx = events.findIndex((e) => e === event);
 	if (idx <= 0) return false;
@@ -433,7 +487,8 @@ export function currentStatus(events: ClaudeMessage[], isActive: boolean): Claud
 		(lastEvent.payload.type === 'userAbort' ||
 			lastEvent.payload.type === 'claudeExit' ||
 			lastEvent.payload.type === 'unhandledException' ||
-			lastEvent.payload.type === 'compactFinished')
+			lastEvent.payload.type === 'compactFinished' ||
+			lastEvent.payload.type === 'budgetExceeded')
 	) {
 		// Once we have the TODOs, if all the TODOs are completed, we can change
 		// this to conditionally return 'enabled' or 'completed'
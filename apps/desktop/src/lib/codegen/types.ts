@@ -1,3 +1,4 @@
+import type { TreeChange } from '$lib/hunks/change';
 import type { Message, MessageParam, Usage } from '@anthropic-ai/sdk/resources/index.mjs';
 
 /**
@@ -136,6 +137,8 @@ export type ClaudeOutput = {
 export type UserInput = {
 	message: string;
 	attachments?: PromptAttachment[];
+	/** The oplog snapshot of the state right before Claude received the message */
+	snapshotId?: string | null;
 };
 
 /**
@@ -160,8 +163,57 @@ export type SystemMessage =
 	| {
 			type: 'compactFinished';
 			summary: string;
+	  }
+	| {
+			type: 'budgetExceeded';
+			budgetUsd: number;
+			costUsd: number;
+	  }
+	| ({
+			type: 'worktreeIntegration';
+	  } & WorktreeIntegration)
+	| {
+			type: 'permissionPolicyIgnored';
+			message: string;
+	  };
+
+export type WorktreeIntegrationStatus =
+	| { type: 'noMergeBaseFound' }
+	| { type: 'worktreeIsBare' }
+	| { type: 'causesWorkspaceConflicts' }
+	| {
+			type: 'integratable';
+			data: {
+				cherry_pick_conflicts: boolean;
+				commits_above_conflict: boolean;
+				working_dir_conflicts: boolean;
+			};
 	  };
 
+/**
+ * The result of integrating the changes a session made in a worktree of its own into its branch.
+ */
+export type WorktreeIntegration = {
+	worktreeId: string;
+	branchName: string;
+	status: WorktreeIntegrationStatus;
+	/** Whether the changes were integrated, in which case the worktree was removed. */
+	integrated: boolean;
+};
+
+/**
+ * A worktree that the session of a stack ran in, and whose changes weren't integrated into its branch yet.
+ */
+export type PendingWorktree = {
+	stackId: string;
+	worktreeId: string;
+	path: string;
+	/** The branch the changes will be integrated into, if known. */
+	branchName: string | null;
+	/** Whether the worktree has uncommitted changes or commits of its own. */
+	hasChanges: boolean;
+};
+
 /**
  * System messages from GitButler about the Claude session state.
  */
@@ -171,6 +223,53 @@ export type GitButlerUpdate = {
 	branchName?: string;
 	commitIds: string[];
 };
+/**
+ * Accumulated token usage and estimated cost of Claude messages.
+ */
+export type ClaudeUsage = {
+	messages: number;
+	inputTokens: number;
+	outputTokens: number;
+	cacheCreationInputTokens: number;
+	cacheReadInputTokens: number;
+	costUsd: number;
+};
+
+export type ClaudeSessionUsage = {
+	sessionId: string;
+	stackId: string | null;
+	budgetUsd: number | null;
+	usage: ClaudeUsage;
+};
+
+export type ClaudeStackUsage = {
+	stackId: string;
+	usage: ClaudeUsage;
+};
+
+/**
+ * The usage of all Claude sessions in a project, per session and per stack.
+ */
+export type ClaudeUsageReport = {
+	sessions: ClaudeSessionUsage[];
+	stacks: ClaudeStackUsage[];
+	total: ClaudeUsage;
+};
+
+/**
+ * A prompt of the user, and the changes made to the workspace in response to it.
+ */
+export type ClaudeTurn = {
+	/** The ID of the message with the prompt */
+	messageId: string;
+	createdAt: string;
+	prompt: string;
+	/** The oplog snapshot of the state right before the turn, if one was recorded */
+	snapshotId: string | null;
+	commitIds: string[];
+	changes: TreeChange[];
+};
+
 /**
  * Details about a Claude session, extracted from the Claude transcript.
  * This data is derived just in time, i.e. not persisted by GitButler.
@@ -217,6 +316,22 @@ export type ClaudePermissionRequest = {
 	useWildcard?: boolean;
 };
 
+/**
+ * A permission decision about a single tool call, as recorded in the audit log of a session.
+ */
+export type ClaudePermissionDecisionRecord = {
+	/** The tool_use_id of the tool call */
+	id: string;
+	/** The stable session ID */
+	sessionId: string;
+	createdAt: string;
+	toolName: string;
+	input: unknown;
+	decision: 'allow' | 'deny';
+	/** Whether the repository policy, earlier decisions or the user decided, or nobody did in time */
+	source: 'policy' | 'session' | 'user' | 'unanswered';
+};
+
 export type ClaudeTodo = {
 	status: 'pending' | 'in_progress' | 'completed';
 	content: string;
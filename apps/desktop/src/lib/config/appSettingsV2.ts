@@ -90,6 +90,8 @@ export type AppSettings = {
 	reviews: Reviews;
 	/** UI settings */
 	ui: UiSettings;
+	/** Settings related to storing secrets */
+	secrets: Secrets;
 };
 
 export type ForgeIntegrations = {
@@ -144,6 +146,8 @@ export type Claude = {
 	autoCommitAfterCompletion: boolean;
 	/** Whether to use the configured model in .claude/settings.json instead of passing --model. */
 	useConfiguredModel: boolean;
+	/** Whether to run the session of each stack in a worktree of its own, and integrate its changes when it finishes. */
+	isolateSessionsInWorktrees: boolean;
 };
 
 export type Reviews = {
@@ -160,3 +164,8 @@ export type UiSettings = {
 	/** The duration between two update checks in seconds. If `0`, no update checks will be performed. */
 	checkForUpdatesIntervalInSeconds: number;
 };
+
+export type Secrets = {
+	/** The backend to store secrets in. Can be overridden with the `GITBUTLER_SECRETS_STORE` environment variable. */
+	store: 'keychain' | 'encrypted-file' | 'environment';
+};
@@ -42,7 +42,10 @@ export type Operation =
 	| 'AutoHandleChangesBefore'
 	| 'AutoHandleChangesAfter'
 	| 'SplitBranch'
-	| 'OnDemandSnapshot';
+	| 'SplitCommit'
+	| 'InteractiveRebase'
+	| 'OnDemandSnapshot'
+	| 'ClaudeTurn';
 
 export class Trailer {
 	key!: string;
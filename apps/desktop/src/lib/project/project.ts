@@ -31,6 +31,12 @@ export type Project = {
 	preferred_key: AuthKey;
 	ok_with_force_push: boolean;
 	force_push_protection: boolean;
+	upstream_integration_policy: UpstreamIntegrationPolicy;
+	ai_provider: AiProvider;
+	commit_message_style: CommitMessageStyle;
+	disabled_git_hooks: GitHook[];
+	/** The hash of the Claude permission policy of the repository whose `allow` rules the user trusted. */
+	trusted_claude_policy: string | null;
 	omit_certificate_check: boolean | undefined;
 	use_diff_context: boolean | undefined;
 	// Produced just for the frontend to determine if the project is open in any window.
@@ -45,6 +51,51 @@ export type Project = {
 	forge_review_template_path: string | null;
 };
 
+export type IntegrationAction = 'rebase' | 'merge' | 'unapply' | 'delete' | 'leave';
+
+/**
+ * Decides how each stack is updated when upstream changes are integrated automatically,
+ * e.g. with `but pull --auto`.
+ */
+export type UpstreamIntegrationPolicy = {
+	safelyUpdatable: IntegrationAction;
+	conflicted: IntegrationAction;
+	integrated: IntegrationAction;
+};
+
+/**
+ * The language model provider used by the AI features of a project.
+ * Credentials are read from the secret store or environment variables.
+ */
+export type AiProvider =
+	| { kind: 'openAi' }
+	| { kind: 'openAiCompatible'; baseUrl: string; model: string }
+	| { kind: 'anthropic'; baseUrl: string | null; model: string }
+	| { kind: 'ollama'; baseUrl: string | null; model: string };
+
+/**
+ * How commit messages of a project should look, in addition to what is learned from its history.
+ */
+export type CommitMessageStyle = {
+	template: string | null;
+	validate: boolean;
+};
+
+/**
+ * A Git hook that GitButler runs at the equivalent point of its own operations.
+ *
+ * `reference-transaction` only runs in the `committed` state, so it can't veto reference updates.
+ */
+export type GitHook =
+	| 'pre-commit'
+	| 'prepare-commit-msg'
+	| 'commit-msg'
+	| 'post-commit'
+	| 'pre-push'
+	| 'post-rewrite'
+	| 'post-checkout'
+	| 'reference-transaction';
+
 export function vscodePath(path: string) {
 	return path.includes('\\') ? '/' + path.replace('\\', '/') : path;
 }
@@ -52,6 +52,7 @@ export class CodegenAnalytics {
 				notifyOnCompletion: settings?.claude?.notifyOnCompletion ?? false,
 				notifyOnPermissionRequest: settings?.claude?.notifyOnPermissionRequest ?? false,
 				autoCommitAfterCompletion: settings?.claude?.autoCommitAfterCompletion ?? true,
+				isolateSessionsInWorktrees: settings?.claude?.isolateSessionsInWorktrees ?? false,
 				dangerouslySkipPermissions: settings?.claude?.dangerouslyAllowAllPermissions ?? false,
 				tokensUsed: usage.tokens,
 				totalMessagesSent: totalMessagesSent,
@@ -30,6 +30,8 @@ export enum ReduxTag {
 	ClaudeCodeTranscript = 'ClaudeCodeTranscript',
 	ClaudePermissionRequests = 'ClaudePermissionPrompts',
 	ClaudeSessionDetails = 'ClaudeSessionDetails',
+	ClaudeUsage = 'ClaudeUsage',
+	ClaudePendingWorktrees = 'ClaudePendingWorktrees',
 	ClaudeStackActive = 'ClaudeStackActive',
 	InitalEditListing = 'InitialEditListing',
 	EditChangesSinceInitial = 'EditChangesSinceInitial',
@@ -8,6 +8,16 @@ export type StackStatus = {
 type NameAndBranchStatus = {
 	name: string;
 	status: BranchStatus;
+	/** The commits that would conflict when rebased onto the new target. */
+	conflicts: CommitConflict[];
+};
+
+export type CommitConflict = {
+	commitId: string;
+	/** The first line of the commit message. */
+	subject: string;
+	/** The paths that would conflict. */
+	paths: string[];
 };
 
 export type BranchStatus =
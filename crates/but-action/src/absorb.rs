@@ -8,7 +8,7 @@ use but_tools::{
 };
 use gix::hashtable::hash_map::HashMap;
 
-use crate::OpenAiProvider;
+use crate::LlmProvider;
 
 /// Absorb file changes into existing commits in the project.
 ///
@@ -29,7 +29,7 @@ use crate::OpenAiProvider;
 pub(crate) fn absorb(
     emitter: std::sync::Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     changes: Vec<but_core::TreeChange>,
 ) -> anyhow::Result<()> {
     let paths = changes
@@ -99,13 +99,7 @@ pub(crate) fn absorb(
     ");
 
     // Now we trigger the tool calling loop to absorb the remaining changes.
-    crate::openai::tool_calling_loop(
-        openai,
-        system_message,
-        vec![prompt.into()],
-        &mut toolset,
-        None,
-    )?;
+    crate::llm::tool_calling_loop(llm, system_message, vec![prompt.into()], &mut toolset, None)?;
 
     Ok(())
 }
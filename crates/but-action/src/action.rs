@@ -1,12 +1,13 @@
 use std::{fmt::Debug, str::FromStr};
 
 use but_ctx::Context;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{ActionHandler, Outcome};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct McpClientInfo {
     name: String,
     version: String,
@@ -21,24 +22,33 @@ impl From<rmcp::model::Implementation> for McpClientInfo {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub enum Source {
     ButCli,
     GitButler,
     Mcp(Option<McpClientInfo>),
     ClaudeCode(String),
     Cursor(String),
+    /// An agent that uses the generic agent hook protocol.
+    Agent {
+        /// The name of the agent, like `codex`.
+        name: String,
+        /// The ID of the session as assigned by the agent.
+        session_id: String,
+    },
     #[default]
     Unknown,
 }
 
 /// Represents a snapshot of an automatic action taken by a GitButler automation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ButlerAction {
     /// UUID identifier of the action
+    #[schemars(with = "String")]
     pub id: Uuid,
     /// The time when the action was performed.
+    #[schemars(with = "String")]
     created_at: chrono::NaiveDateTime,
     /// The prompt that was used to generate the changes that were made, if applicable
     external_prompt: Option<String>,
@@ -48,9 +58,11 @@ pub struct ButlerAction {
     handler: ActionHandler,
     /// A GitBulter Oplog snapshot ID before the action was performed.
     #[serde(serialize_with = "but_serde::object_id::serialize")]
+    #[schemars(with = "String")]
     snapshot_before: gix::ObjectId,
     /// A GitBulter Oplog snapshot ID after the action was performed.
     #[serde(serialize_with = "but_serde::object_id::serialize")]
+    #[schemars(with = "String")]
     snapshot_after: gix::ObjectId,
     /// The outcome of the action, if it was successful.
     response: Option<Outcome>,
@@ -171,7 +183,7 @@ pub fn list_actions(ctx: &mut Context, offset: i64, limit: i64) -> anyhow::Resul
     Ok(ActionListing { total, actions })
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionListing {
     pub total: i64,
@@ -2,12 +2,12 @@ use anyhow::Context as _;
 use but_ctx::Context;
 use but_tools::{emit::Emitter, workspace::commit_toolset};
 
-use crate::OpenAiProvider;
+use crate::LlmProvider;
 
 pub(crate) fn auto_commit(
     emitter: std::sync::Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     changes: Vec<but_core::TreeChange>,
 ) -> anyhow::Result<()> {
     let paths = changes
@@ -52,13 +52,7 @@ pub(crate) fn auto_commit(
         </project_status>
     ");
 
-    crate::openai::tool_calling_loop(
-        openai,
-        system_message,
-        vec![prompt.into()],
-        &mut toolset,
-        None,
-    )?;
+    crate::llm::tool_calling_loop(llm, system_message, vec![prompt.into()], &mut toolset, None)?;
 
     Ok(())
 }
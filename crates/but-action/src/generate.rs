@@ -1,55 +1,27 @@
-use async_openai::{
-    Client,
-    config::OpenAIConfig,
-    types::chat::{
-        ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessage,
-        CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema,
-    },
-};
-use schemars::{JsonSchema, schema_for};
-
-use crate::OpenAiProvider;
-
-#[expect(dead_code)]
-pub(crate) fn commit_message_blocking(
-    openai: &OpenAiProvider,
+use anyhow::Context as _;
+use gitbutler_repo::commit_style::CommitStyle;
+use schemars::JsonSchema;
+
+use crate::{ChatMessage, LlmProvider, structured_output_blocking};
+
+/// Generate a commit message for `diff` that follows the `style` learned from the repository history,
+/// and the `template` configured for the project.
+///
+/// If the generated message doesn't match `style`, the model is asked once more to fix it.
+pub fn commit_message(
+    llm: &dyn LlmProvider,
     external_summary: &str,
     external_prompt: &str,
     diff: &str,
+    style: &CommitStyle,
+    template: Option<&str>,
 ) -> anyhow::Result<String> {
-    let change_summary_owned = external_summary.to_string();
-    let external_prompt_owned = external_prompt.to_string();
-    let diff_owned = diff.to_string();
-    let client = openai.client()?;
-
-    std::thread::spawn(move || {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(commit_message(
-                &client,
-                &change_summary_owned,
-                &external_prompt_owned,
-                &diff_owned,
-            ))
-    })
-    .join()
-    .unwrap()
-}
-
-pub async fn commit_message(
-    // openai_provider: OpenAiProvider,
-    client: &Client<OpenAIConfig>,
-    external_summary: &str,
-    external_prompt: &str,
-    diff: &str,
-) -> anyhow::Result<String> {
-    let system_message =
-        "You are a version control assistant that helps with Git branch committing.".to_string();
+    let instructions = commit_message_instructions(style, template);
     let user_message = format!(
         r#"Extract the git commit data from the prompt, summary and diff output.
 Return the commit message. Determine from this AI prompt, summary and diff output what the git commit data should be.
 
-{DEFAULT_COMMIT_MESSAGE_INSTRUCTIONS}
+{instructions}
 
 Here is the data:
 
@@ -64,39 +36,60 @@ unified diff:
 "#
     );
 
-    let schema = schema_for!(StructuredOutput);
-    let schema_json = serde_json::to_value(schema).unwrap();
-    let response_format = ResponseFormat::JsonSchema {
-        json_schema: ResponseFormatJsonSchema {
-            description: None,
-            name: "commit_message".into(),
-            schema: Some(schema_json),
-            strict: Some(true),
-        },
+    let message = request_commit_message(llm, user_message.clone())?;
+    let problems = style.check(&message);
+    if problems.is_empty() {
+        return Ok(message);
+    }
+
+    let problems: String = problems
+        .iter()
+        .map(|problem| format!("- {problem}\n"))
+        .collect();
+    let user_message = format!(
+        r#"{user_message}
+This commit message was generated before, but doesn't match the style of the repository:
+
+{message}
+
+Fix these problems:
+
+{problems}"#
+    );
+    request_commit_message(llm, user_message)
+}
+
+/// Combine the learned `style` and the configured `template` into instructions for writing a commit message,
+/// or use the default instructions if neither is available.
+fn commit_message_instructions(style: &CommitStyle, template: Option<&str>) -> String {
+    let template = template.filter(|template| !template.trim().is_empty());
+    let Some(style_instructions) = style.instructions() else {
+        return match template {
+            Some(template) => format!(
+                "{DEFAULT_COMMIT_MESSAGE_INSTRUCTIONS}\n\nThe message must follow this template:\n\n{template}"
+            ),
+            None => DEFAULT_COMMIT_MESSAGE_INSTRUCTIONS.to_owned(),
+        };
     };
 
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-5-mini")
-        .messages([
-            ChatCompletionRequestSystemMessage::from(system_message).into(),
-            ChatCompletionRequestUserMessage::from(user_message).into(),
-        ])
-        .response_format(response_format)
-        .build()?;
-
-    let response = client.chat().create(request).await?;
-    let response_string = response
-        .choices
-        .first()
-        .unwrap()
-        .message
-        .content
-        .as_ref()
-        .unwrap();
-
-    let structured_output: StructuredOutput = serde_json::from_str(response_string)
-        .map_err(|e| anyhow::anyhow!("Failed to parse response: {}", e))?;
+    let mut out = format!(
+        "The message should be a short summary line, followed by two newlines, then a short paragraph explaining WHY the change was needed based off the prompt.\n\n\
+         Follow the conventions of the repository:\n\n{style_instructions}"
+    );
+    if let Some(template) = template {
+        out.push_str(&format!(
+            "\nThe message must follow this template:\n\n{template}"
+        ));
+    }
+    out
+}
 
+fn request_commit_message(llm: &dyn LlmProvider, user_message: String) -> anyhow::Result<String> {
+    let system_message =
+        "You are a version control assistant that helps with Git branch committing.";
+    let structured_output: StructuredOutput =
+        structured_output_blocking(llm, system_message, vec![ChatMessage::User(user_message)])?
+            .context("The model didn't answer with a commit message")?;
     Ok(structured_output.commit_message)
 }
 
@@ -107,14 +100,13 @@ pub struct StructuredOutput {
     pub commit_message: String,
 }
 
-pub async fn branch_name(
-    client: &Client<OpenAIConfig>,
+pub fn branch_name(
+    llm: &dyn LlmProvider,
     commit_messages: &[String],
     diffs: &[String],
     existing_branch_names: &[String],
 ) -> anyhow::Result<String> {
-    let system_message =
-        "You are a version control assistant that helps with Git branch naming.".to_string();
+    let system_message = "You are a version control assistant that helps with Git branch naming.";
     let user_message = format!(
         "Generate a concise and descriptive branch name based on the provided commit messages.
         Keep the branch name short, ideally under 50 characters. Only user lowercase letters, numbers, and hyphens.
@@ -145,40 +137,9 @@ pub async fn branch_name(
         diffs.join("\n==================\n")
     );
 
-    let schema = schema_for!(GenerateBranchNameOutput);
-    let schema_json = serde_json::to_value(schema)?;
-    let response_format = ResponseFormat::JsonSchema {
-        json_schema: ResponseFormatJsonSchema {
-            description: None,
-            name: "branch_name".into(),
-            schema: Some(schema_json),
-            strict: Some(false),
-        },
-    };
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-5-mini")
-        .messages([
-            ChatCompletionRequestSystemMessage::from(system_message).into(),
-            ChatCompletionRequestUserMessage::from(user_message).into(),
-        ])
-        .response_format(response_format)
-        .build()?;
-
-    let response = client.chat().create(request).await?;
-    let choice = response
-        .choices
-        .first()
-        .ok_or_else(|| anyhow::anyhow!("No choices returned from OpenAI response"))?;
-
-    let response_string = choice
-        .message
-        .content
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No content in OpenAI response message"))?;
-
-    let structured_output: GenerateBranchNameOutput = serde_json::from_str(response_string)
-        .map_err(|e| anyhow::anyhow!("Failed to parse response: {}", e))?;
+    let structured_output: GenerateBranchNameOutput =
+        structured_output_blocking(llm, system_message, vec![ChatMessage::User(user_message)])?
+            .context("The model didn't answer with a branch name")?;
 
     Ok(structured_output.branch_name)
 }
@@ -4,7 +4,7 @@ use but_tools::workspace::ProjectStatus;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::{ChatMessage, OpenAiProvider, openai};
+use crate::{ChatMessage, LlmProvider};
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum BranchSuggestion {
@@ -65,7 +65,7 @@ pub struct Grouping {
 
 #[expect(dead_code)]
 pub(crate) fn group(
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     project_status: &ProjectStatus,
 ) -> anyhow::Result<Grouping> {
     let system_message ="
@@ -101,7 +101,7 @@ pub(crate) fn group(
     let messages = vec![ChatMessage::User(user_message)];
 
     let grouping =
-        openai::structured_output_blocking::<Grouping>(openai, system_message, messages)?
+        crate::llm::structured_output_blocking::<Grouping>(llm, system_message, messages)?
             .ok_or_else(|| anyhow::anyhow!("Failed to get grouping from OpenAI"))?;
 
     Ok(grouping)
@@ -15,6 +15,7 @@ use gitbutler_branch::BranchCreateRequest;
 use gitbutler_project::{Project, ProjectId};
 use gitbutler_stack::{Target, VirtualBranchesHandle};
 pub use openai::{CredentialsKind, OpenAiProvider};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 mod absorb;
@@ -24,6 +25,7 @@ mod branch_changes;
 pub mod cli;
 mod generate;
 mod grouping;
+mod llm;
 mod openai;
 pub mod rename_branch;
 pub mod reword;
@@ -32,9 +34,13 @@ mod workflow;
 pub use action::{ActionListing, Source, list_actions};
 use but_core::ref_metadata::StackId;
 use but_meta::VirtualBranchesTomlMetadata;
+pub use llm::{
+    ANTHROPIC_KEY_HANDLE, AnthropicProvider, ChatResponse, LlmProvider, OllamaProvider, ToolCall,
+    provider_for_project, structured_output_blocking, tool_calling_loop, tool_calling_loop_stream,
+};
 pub use openai::{
-    ChatMessage, ToolCallContent, ToolResponseContent, structured_output_blocking,
-    tool_calling_loop, tool_calling_loop_stream,
+    ChatMessage, OPENAI_COMPATIBLE_KEY_HANDLE, OPENAI_KEY_HANDLE, ToolCallContent,
+    ToolResponseContent,
 };
 use strum::EnumString;
 use uuid::Uuid;
@@ -45,7 +51,7 @@ pub fn freestyle(
     message_id: String,
     emitter: Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     chat_messages: Vec<openai::ChatMessage>,
     model: Option<String>,
 ) -> anyhow::Result<String> {
@@ -127,8 +133,8 @@ pub fn freestyle(
             (emitter)(&name, payload);
         }
     });
-    let (response, _) = crate::openai::tool_calling_loop_stream(
-        openai,
+    let (response, _) = crate::llm::tool_calling_loop_stream(
+        llm,
         system_message,
         internal_chat_messages,
         &mut toolset,
@@ -142,28 +148,28 @@ pub fn freestyle(
 pub fn absorb(
     emitter: Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    absorb::absorb(emitter, ctx, openai, changes)
+    absorb::absorb(emitter, ctx, llm, changes)
 }
 
 pub fn branch_changes(
     emitter: Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    branch_changes::branch_changes(emitter, ctx, openai, changes)
+    branch_changes::branch_changes(emitter, ctx, llm, changes)
 }
 
 pub fn auto_commit(
     emitter: Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    auto_commit::auto_commit(emitter, ctx, openai, changes)
+    auto_commit::auto_commit(emitter, ctx, llm, changes)
 }
 
 pub fn handle_changes(
@@ -261,7 +267,9 @@ fn stacks_creating_if_none(
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, EnumString, Default)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, EnumString, Default, JsonSchema,
+)]
 #[serde(rename_all = "camelCase")]
 pub enum ActionHandler {
     #[default]
@@ -274,15 +282,16 @@ impl Display for ActionHandler {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Outcome {
     pub updated_branches: Vec<UpdatedBranch>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdatedBranch {
+    #[schemars(with = "String")]
     pub stack_id: StackId,
     pub branch_name: String,
     pub new_commits: Vec<String>,
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use but_secret::Sensitive;
+use but_tools::tool::Tool;
+use serde_json::{Value, json};
+
+use super::{ChatResponse, LlmProvider, ToolCall, post_json};
+use crate::ChatMessage;
+
+pub const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const MAX_TOKENS: u32 = 8192;
+/// The name of the tool the model is forced to call to produce structured output.
+const STRUCTURED_RESPONSE_TOOL: &str = "structured_response";
+
+/// A provider using the [Anthropic Messages API](https://docs.anthropic.com/en/api/messages).
+///
+/// Answers aren't streamed, so the whole text is passed to `on_token` at once.
+#[derive(Debug, Clone)]
+pub struct AnthropicProvider {
+    base_url: String,
+    model: String,
+    api_key: Sensitive<String>,
+}
+
+impl AnthropicProvider {
+    /// Use `model` at `base_url`, or at the Anthropic API if unset.
+    pub fn new(
+        base_url: Option<String>,
+        model: impl Into<String>,
+        api_key: Sensitive<String>,
+    ) -> Self {
+        AnthropicProvider {
+            base_url: base_url.unwrap_or_else(|| ANTHROPIC_API_BASE.to_owned()),
+            model: model.into(),
+            api_key,
+        }
+    }
+
+    fn messages(&self, system_message: &str, chat_messages: Vec<ChatMessage>) -> Value {
+        json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "system": system_message,
+            "messages": to_anthropic_messages(chat_messages),
+        })
+    }
+
+    fn send(&self, body: Value) -> Result<Vec<Value>> {
+        let response = post_json(
+            format!("{}/v1/messages", self.base_url.trim_end_matches('/')),
+            vec![
+                ("x-api-key", self.api_key.0.clone()),
+                ("anthropic-version", ANTHROPIC_VERSION.to_owned()),
+            ],
+            body,
+        )?;
+        Ok(match response.get("content") {
+            Some(Value::Array(blocks)) => blocks.clone(),
+            _ => Vec::new(),
+        })
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn structured_output(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        schema: Value,
+    ) -> Result<Option<Value>> {
+        let mut body = self.messages(system_message, chat_messages);
+        body["tools"] = json!([{
+            "name": STRUCTURED_RESPONSE_TOOL,
+            "description": "Respond with the requested structured data.",
+            "input_schema": schema,
+        }]);
+        body["tool_choice"] = json!({ "type": "tool", "name": STRUCTURED_RESPONSE_TOOL });
+
+        Ok(self.send(body)?.into_iter().find_map(|mut block| {
+            (block["type"] == "tool_use" && block["name"] == STRUCTURED_RESPONSE_TOOL)
+                .then(|| block["input"].take())
+        }))
+    }
+
+    fn chat(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        tools: &[Arc<dyn Tool>],
+        _model: Option<String>,
+        on_token: Arc<dyn Fn(&str) + Send + Sync>,
+    ) -> Result<ChatResponse> {
+        let mut body = self.messages(system_message, chat_messages);
+        if !tools.is_empty() {
+            body["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name(),
+                        "description": tool.description(),
+                        "input_schema": tool.parameters(),
+                    })
+                })
+                .collect();
+        }
+
+        let mut response = ChatResponse::default();
+        for block in self.send(body)? {
+            match block["type"].as_str() {
+                Some("text") => {
+                    if let Some(text) = block["text"].as_str() {
+                        response.text.get_or_insert_default().push_str(text);
+                    }
+                }
+                Some("tool_use") => response.tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_owned(),
+                    name: block["name"].as_str().unwrap_or_default().to_owned(),
+                    arguments: block["input"].to_string(),
+                }),
+                _ => {}
+            }
+        }
+        if let Some(text) = &response.text {
+            on_token(text);
+        }
+        Ok(response)
+    }
+}
+
+/// Convert `chat_messages` into alternating user and assistant messages, with tool calls as
+/// `tool_use` blocks of the assistant and their responses as `tool_result` blocks of the user.
+fn to_anthropic_messages(chat_messages: Vec<ChatMessage>) -> Vec<Value> {
+    let mut messages: Vec<(&str, Vec<Value>)> = Vec::new();
+    for message in chat_messages {
+        let (role, block) = match message {
+            ChatMessage::User(text) | ChatMessage::Assistant(text) if text.is_empty() => continue,
+            ChatMessage::User(text) => ("user", json!({ "type": "text", "text": text })),
+            ChatMessage::Assistant(text) => ("assistant", json!({ "type": "text", "text": text })),
+            ChatMessage::ToolCall(call) => (
+                "assistant",
+                json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.name,
+                    "input": serde_json::from_str::<Value>(&call.arguments)
+                        .ok()
+                        .filter(Value::is_object)
+                        .unwrap_or_else(|| json!({})),
+                }),
+            ),
+            ChatMessage::ToolResponse(response) => (
+                "user",
+                json!({
+                    "type": "tool_result",
+                    "tool_use_id": response.id,
+                    "content": response.result,
+                }),
+            ),
+        };
+        match messages.last_mut() {
+            Some((last_role, blocks)) if *last_role == role => blocks.push(block),
+            _ => messages.push((role, vec![block])),
+        }
+    }
+    messages
+        .into_iter()
+        .map(|(role, content)| json!({ "role": role, "content": content }))
+        .collect()
+}
@@ -0,0 +1,246 @@
+//! Language model providers, and the structured output and tool calling loops built on top of them.
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+use but_secret::{Sensitive, secret};
+use but_tools::tool::{Tool, Toolset};
+use gitbutler_project::{AiProvider, Project};
+use schemars::{JsonSchema, schema_for};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    ChatMessage, CredentialsKind, OpenAiProvider, ToolCallContent, ToolResponseContent,
+    openai::OPENAI_COMPATIBLE_KEY_HANDLE,
+};
+
+mod anthropic;
+mod ollama;
+
+pub use anthropic::AnthropicProvider;
+pub use ollama::OllamaProvider;
+
+/// A language model API that can answer a conversation with structured output, or by calling tools.
+pub trait LlmProvider: Send + Sync {
+    /// Answer `chat_messages` with a value matching the JSON `schema`,
+    /// or return `None` if the model had nothing to say.
+    fn structured_output(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        schema: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>>;
+
+    /// Answer `chat_messages`, possibly by asking for some of the `tools` to be called.
+    ///
+    /// `model` is the model requested by the caller, which providers that are configured
+    /// with a model ignore. `on_token` receives the text of the answer as it comes in.
+    fn chat(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        tools: &[Arc<dyn Tool>],
+        model: Option<String>,
+        on_token: Arc<dyn Fn(&str) + Send + Sync>,
+    ) -> Result<ChatResponse>;
+
+    /// The kind of credentials requests are authenticated with, for providers that support more than one.
+    fn credentials_kind(&self) -> Option<CredentialsKind> {
+        None
+    }
+}
+
+/// The answer of a language model to a conversation.
+#[derive(Debug, Default)]
+pub struct ChatResponse {
+    /// The text of the answer, if there was any.
+    pub text: Option<String>,
+    /// The tools the model wants to have called before it continues.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// A request of the language model to call a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// The arguments as JSON object.
+    pub arguments: String,
+}
+
+/// The handle of the global secret that holds the user's Anthropic key.
+pub const ANTHROPIC_KEY_HANDLE: &str = "aiAnthropicKey";
+
+/// Create the language model provider configured for `project`.
+///
+/// `preferred_creds` decides which credentials are used if the project uses OpenAI itself.
+pub fn provider_for_project(
+    project: &Project,
+    preferred_creds: Option<CredentialsKind>,
+) -> Result<Box<dyn LlmProvider>> {
+    Ok(match &project.ai_provider {
+        AiProvider::OpenAi => Box::new(OpenAiProvider::with(preferred_creds).context(
+            "No valid credentials found for AI provider. Please configure your GitButler account credentials.",
+        )?),
+        AiProvider::OpenAiCompatible { base_url, model } => Box::new(OpenAiProvider::compatible(
+            base_url,
+            model,
+            secret_or_env_var(OPENAI_COMPATIBLE_KEY_HANDLE, "OPENAI_COMPATIBLE_API_KEY"),
+        )),
+        AiProvider::Anthropic { base_url, model } => Box::new(AnthropicProvider::new(
+            base_url.clone(),
+            model,
+            secret_or_env_var(ANTHROPIC_KEY_HANDLE, "ANTHROPIC_API_KEY").context(
+                "No Anthropic key found. Add it in the GitButler settings or set the ANTHROPIC_API_KEY environment variable",
+            )?,
+        )),
+        AiProvider::Ollama { base_url, model } => {
+            Box::new(OllamaProvider::new(base_url.clone(), model))
+        }
+    })
+}
+
+/// Read the key stored as global `secret`, or the one in the `env_var` environment variable.
+fn secret_or_env_var(secret: &str, env_var: &str) -> Option<Sensitive<String>> {
+    secret::retrieve(secret, secret::Namespace::Global)
+        .inspect_err(|err| tracing::warn!("Failed to read the '{secret}' secret: {err}"))
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var(env_var).ok().map(Sensitive))
+}
+
+pub fn structured_output_blocking<T: DeserializeOwned + JsonSchema>(
+    provider: &dyn LlmProvider,
+    system_message: &str,
+    chat_messages: Vec<ChatMessage>,
+) -> Result<Option<T>> {
+    let schema = serde_json::to_value(schema_for!(T))?;
+    provider
+        .structured_output(system_message, chat_messages, schema)?
+        .map(|value| {
+            serde_json::from_value(value).context("The model answered with unexpected JSON")
+        })
+        .transpose()
+}
+
+pub fn tool_calling_loop(
+    provider: &dyn LlmProvider,
+    system_message: &str,
+    chat_messages: Vec<ChatMessage>,
+    tool_set: &mut impl Toolset,
+    model: Option<String>,
+) -> Result<String> {
+    let (response, _) = tool_calling_loop_stream(
+        provider,
+        system_message,
+        chat_messages,
+        tool_set,
+        model,
+        Arc::new(|_| {}),
+    )?;
+    Ok(response)
+}
+
+/// Let the model answer `chat_messages`, calling the tools in `tool_set` it asks for until it stops asking.
+///
+/// Returns all text the model answered with, along with the conversation including the tool calls.
+pub fn tool_calling_loop_stream(
+    provider: &dyn LlmProvider,
+    system_message: &str,
+    chat_messages: Vec<ChatMessage>,
+    tool_set: &mut impl Toolset,
+    model: Option<String>,
+    on_token: Arc<dyn Fn(&str) + Send + Sync + 'static>,
+) -> Result<(String, Vec<ChatMessage>)> {
+    let tools = tool_set.list();
+    let mut messages = chat_messages;
+    let mut text_response_buffer = vec![];
+
+    loop {
+        let ChatResponse { text, tool_calls } = provider.chat(
+            system_message,
+            messages.clone(),
+            &tools,
+            model.clone(),
+            on_token.clone(),
+        )?;
+
+        if let Some(text) = text {
+            text_response_buffer.push(text.clone());
+            messages.push(ChatMessage::Assistant(text));
+        }
+
+        if tool_calls.is_empty() {
+            break;
+        }
+
+        let mut tool_response_messages = vec![];
+        for ToolCall {
+            id,
+            name,
+            arguments,
+        } in tool_calls
+        {
+            let tool_response = tool_set.call_tool(&name, &arguments);
+            let result = serde_json::to_string(&tool_response)
+                .context("Failed to serialize tool response")?;
+
+            messages.push(ChatMessage::ToolCall(ToolCallContent {
+                id: id.clone(),
+                name,
+                arguments,
+            }));
+            tool_response_messages.push(ChatMessage::ToolResponse(ToolResponseContent {
+                id,
+                result,
+            }));
+        }
+        messages.extend(tool_response_messages);
+    }
+
+    let text_response = text_response_buffer
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    Ok((text_response, messages))
+}
+
+/// Run the future created by `make_future` to completion on its own runtime, in a separate thread
+/// so it also works when called from within another runtime.
+pub(crate) fn block_on<T, F>(make_future: impl FnOnce() -> F + Send + 'static) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+    T: Send + 'static,
+{
+    std::thread::spawn(move || tokio::runtime::Runtime::new()?.block_on(make_future()))
+        .join()
+        .map_err(|_| anyhow::anyhow!("The language model request panicked"))?
+}
+
+/// POST `body` as JSON to `url` with the additional `headers`, and return the JSON response.
+fn post_json(
+    url: String,
+    headers: Vec<(&'static str, String)>,
+    body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    block_on(move || async move {
+        let mut request = reqwest::Client::new()
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body)?);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {url}"))?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            bail!("{url} responded with {status}: {text}");
+        }
+        serde_json::from_str(&text).with_context(|| format!("{url} responded with invalid JSON"))
+    })
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use but_tools::tool::Tool;
+use serde_json::{Value, json};
+
+use super::{ChatResponse, LlmProvider, ToolCall, post_json};
+use crate::ChatMessage;
+
+pub const OLLAMA_API_BASE: &str = "http://localhost:11434";
+
+/// A provider using the chat API of an [Ollama](https://ollama.com) server.
+///
+/// Answers aren't streamed, so the whole text is passed to `on_token` at once.
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    base_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    /// Use `model` on the server at `base_url`, or on the local one if unset.
+    pub fn new(base_url: Option<String>, model: impl Into<String>) -> Self {
+        OllamaProvider {
+            base_url: base_url.unwrap_or_else(|| OLLAMA_API_BASE.to_owned()),
+            model: model.into(),
+        }
+    }
+
+    fn chat_request(&self, system_message: &str, chat_messages: Vec<ChatMessage>) -> Value {
+        json!({
+            "model": self.model,
+            "stream": false,
+            "messages": to_ollama_messages(system_message, chat_messages),
+        })
+    }
+
+    /// Send `body` and return the message the model answered with.
+    fn send(&self, body: Value) -> Result<Value> {
+        let mut response = post_json(
+            format!("{}/api/chat", self.base_url.trim_end_matches('/')),
+            Vec::new(),
+            body,
+        )?;
+        Ok(response["message"].take())
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn structured_output(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        schema: Value,
+    ) -> Result<Option<Value>> {
+        let mut body = self.chat_request(system_message, chat_messages);
+        body["format"] = schema;
+
+        match self.send(body)?["content"].as_str() {
+            Some(content) if !content.trim().is_empty() => Ok(Some(
+                serde_json::from_str(content).context("Ollama answered with invalid JSON")?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    fn chat(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        tools: &[Arc<dyn Tool>],
+        _model: Option<String>,
+        on_token: Arc<dyn Fn(&str) + Send + Sync>,
+    ) -> Result<ChatResponse> {
+        let mut body = self.chat_request(system_message, chat_messages);
+        if !tools.is_empty() {
+            body["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name(),
+                            "description": tool.description(),
+                            "parameters": tool.parameters(),
+                        },
+                    })
+                })
+                .collect();
+        }
+
+        let message = self.send(body)?;
+        let text = message["content"]
+            .as_str()
+            .filter(|text| !text.is_empty())
+            .map(ToOwned::to_owned);
+        if let Some(text) = &text {
+            on_token(text);
+        }
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|call| ToolCall {
+                // Ollama doesn't always identify tool calls, but the conversation needs it.
+                id: call["id"]
+                    .as_str()
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+                name: call["function"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_owned(),
+                arguments: match &call["function"]["arguments"] {
+                    Value::String(arguments) => arguments.clone(),
+                    arguments => arguments.to_string(),
+                },
+            })
+            .collect();
+        Ok(ChatResponse { text, tool_calls })
+    }
+}
+
+/// Convert `chat_messages` into Ollama messages after the `system_message`, with consecutive
+/// tool calls merged into one assistant message and tool responses naming their tool.
+fn to_ollama_messages(system_message: &str, chat_messages: Vec<ChatMessage>) -> Vec<Value> {
+    let mut messages = vec![json!({ "role": "system", "content": system_message })];
+    let mut tool_names = HashMap::new();
+    for message in chat_messages {
+        match message {
+            ChatMessage::User(content) => {
+                messages.push(json!({ "role": "user", "content": content }));
+            }
+            ChatMessage::Assistant(content) => {
+                messages.push(json!({ "role": "assistant", "content": content }));
+            }
+            ChatMessage::ToolCall(call) => {
+                let tool_call = json!({
+                    "function": {
+                        "name": call.name,
+                        "arguments": serde_json::from_str::<Value>(&call.arguments)
+                            .unwrap_or_else(|_| json!({})),
+                    },
+                });
+                tool_names.insert(call.id, call.name);
+                match messages
+                    .last_mut()
+                    .and_then(|last| last.get_mut("tool_calls"))
+                    .and_then(Value::as_array_mut)
+                {
+                    Some(tool_calls) => tool_calls.push(tool_call),
+                    None => messages.push(json!({
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [tool_call],
+                    })),
+                }
+            }
+            ChatMessage::ToolResponse(response) => messages.push(json!({
+                "role": "tool",
+                "content": response.result,
+                "tool_name": tool_names.get(&response.id).cloned().unwrap_or_default(),
+            })),
+        }
+    }
+    messages
+}
@@ -1,36 +1,52 @@
-use std::{collections::HashMap, fmt::Display, ops::Deref, sync::Arc};
+use std::{collections::BTreeMap, fmt::Display, ops::Deref, sync::Arc};
 
 use anyhow::{Context as _, Result};
 use async_openai::{
     Client,
     config::OpenAIConfig,
     types::chat::{
-        ChatCompletionMessageToolCalls, ChatCompletionRequestAssistantMessageContent,
+        ChatCompletionMessageToolCalls, ChatCompletionRequestAssistantMessage,
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestToolMessageContent, ChatCompletionRequestUserMessageContent,
+        ChatCompletionRequestToolMessageContent, ChatCompletionTools,
         CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema,
     },
 };
 use but_secret::{Sensitive, secret};
-use but_tools::tool::Toolset;
+use but_tools::tool::Tool;
 use futures::StreamExt;
 use reqwest::header::{HeaderMap, HeaderValue};
-use schemars::{JsonSchema, schema_for};
-use serde::de::DeserializeOwned;
-use tokio::sync::Mutex;
+
+use crate::llm::{ChatResponse, LlmProvider, ToolCall, block_on};
 
 #[derive(Debug, Clone, serde::Serialize, strum::Display)]
 pub enum CredentialsKind {
     EnvVarOpenAiKey,
     OwnOpenAiKey,
     GitButlerProxied,
+    /// A server implementing the OpenAI API at a custom base URL, with an optional key.
+    OpenAiCompatible,
 }
 
 pub const GB_OPENAI_API_BASE: &str = "https://app.gitbutler.com/api/proxy/openai";
 
+/// The handle of the global secret that holds the user's own OpenAI key.
+pub const OPENAI_KEY_HANDLE: &str = "aiOpenAIKey";
+
+/// The handle of the global secret that holds the key for OpenAI compatible servers.
+///
+/// It's separate from [`OPENAI_KEY_HANDLE`] so OpenAI credentials are never sent to other servers.
+pub const OPENAI_COMPATIBLE_KEY_HANDLE: &str = "aiOpenAICompatibleKey";
+
+/// The model to use if neither the provider nor the caller chose one.
+const DEFAULT_MODEL: &str = "gpt-5-mini";
+
 #[derive(Debug, Clone)]
 pub struct OpenAiProvider {
     credentials: (CredentialsKind, Sensitive<String>),
+    /// The base URL of an OpenAI compatible server.
+    api_base: Option<String>,
+    /// The model to use for all requests, overriding the one requested by callers.
+    model: Option<String>,
 }
 
 impl OpenAiProvider {
@@ -40,6 +56,9 @@ impl OpenAiProvider {
                 CredentialsKind::EnvVarOpenAiKey => OpenAiProvider::openai_env_var_creds(),
                 CredentialsKind::OwnOpenAiKey => OpenAiProvider::openai_own_key_creds(),
                 CredentialsKind::GitButlerProxied => OpenAiProvider::gitbutler_proxied_creds(),
+                CredentialsKind::OpenAiCompatible => Err(anyhow::anyhow!(
+                    "OpenAI compatible providers need a base URL and model, use `OpenAiProvider::compatible()`"
+                )),
             }
         } else {
             OpenAiProvider::gitbutler_proxied_creds()
@@ -49,7 +68,11 @@ impl OpenAiProvider {
         };
 
         match credentials {
-            Ok(credentials) => Some(Self { credentials }),
+            Ok(credentials) => Some(Self {
+                credentials,
+                api_base: None,
+                model: None,
+            }),
             Err(e) => {
                 tracing::error!("Failed to retrieve OpenAI credentials: {}", e);
                 None
@@ -57,6 +80,25 @@ impl OpenAiProvider {
         }
     }
 
+    /// Talk to the server implementing the OpenAI API at `api_base`, like a self-hosted vLLM,
+    /// using `model` for all requests.
+    ///
+    /// If there is no `api_key`, requests aren't authenticated.
+    pub fn compatible(
+        api_base: impl Into<String>,
+        model: impl Into<String>,
+        api_key: Option<Sensitive<String>>,
+    ) -> Self {
+        Self {
+            credentials: (
+                CredentialsKind::OpenAiCompatible,
+                api_key.unwrap_or_else(|| Sensitive(String::new())),
+            ),
+            api_base: Some(api_base.into()),
+            model: Some(model.into()),
+        }
+    }
+
     pub fn client(&self) -> Result<Client<OpenAIConfig>> {
         match &self.credentials {
             (CredentialsKind::EnvVarOpenAiKey, _) => Ok(Client::with_config(OpenAIConfig::new())),
@@ -79,6 +121,14 @@ impl OpenAiProvider {
                     .build()?;
                 Ok(Client::with_config(config).with_http_client(http_client))
             }
+            (CredentialsKind::OpenAiCompatible, key) => {
+                // Always set the key, as the default one is read from `OPENAI_API_KEY`.
+                let mut config = OpenAIConfig::new().with_api_key(key.0.clone());
+                if let Some(api_base) = &self.api_base {
+                    config = config.with_api_base(api_base);
+                }
+                Ok(Client::with_config(config))
+            }
         }
     }
 
@@ -86,6 +136,13 @@ impl OpenAiProvider {
         self.credentials.0.clone()
     }
 
+    fn model_for(&self, requested: Option<String>) -> String {
+        self.model
+            .clone()
+            .or(requested)
+            .unwrap_or_else(|| DEFAULT_MODEL.to_owned())
+    }
+
     fn gitbutler_proxied_creds() -> Result<(CredentialsKind, Sensitive<String>)> {
         let creds = secret::retrieve("gitbutler_access_token", secret::Namespace::BuildKind)?
             .ok_or(anyhow::anyhow!(
@@ -95,10 +152,11 @@ impl OpenAiProvider {
     }
 
     fn openai_own_key_creds() -> Result<(CredentialsKind, Sensitive<String>)> {
-        let creds =
-            secret::retrieve("aiOpenAIKey", secret::Namespace::Global)?.ok_or(anyhow::anyhow!(
+        let creds = secret::retrieve(OPENAI_KEY_HANDLE, secret::Namespace::Global)?.ok_or(
+            anyhow::anyhow!(
                 "No OpenAI own key configured. Add this through the GitButler settings"
-            ))?;
+            ),
+        )?;
         Ok((CredentialsKind::OwnOpenAiKey, creds))
     }
 
@@ -115,50 +173,64 @@ impl OpenAiProvider {
     }
 }
 
-pub fn structured_output_blocking<
-    T: serde::Serialize + DeserializeOwned + JsonSchema + std::marker::Send + 'static,
->(
-    openai: &OpenAiProvider,
-    system_message: &str,
-    chat_messages: Vec<ChatMessage>,
-) -> anyhow::Result<Option<T>> {
-    let client = openai.client()?;
-    let mut messages: Vec<ChatCompletionRequestMessage> =
-        vec![ChatCompletionRequestSystemMessage::from(system_message).into()];
+impl LlmProvider for OpenAiProvider {
+    fn structured_output(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        schema: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>> {
+        let client = self.client()?;
+        let messages = to_openai_messages(system_message, chat_messages);
+        let model = self.model_for(None);
+        block_on(move || async move { structured_output(&client, model, messages, schema).await })
+    }
 
-    messages.extend(
-        chat_messages
-            .into_iter()
-            .map(ChatCompletionRequestMessage::from)
-            .collect::<Vec<_>>(),
-    );
-
-    std::thread::spawn(move || {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(structured_output::<T>(&client, messages))
-    })
-    .join()
-    .unwrap()
+    fn chat(
+        &self,
+        system_message: &str,
+        chat_messages: Vec<ChatMessage>,
+        tools: &[Arc<dyn Tool>],
+        model: Option<String>,
+        on_token: Arc<dyn Fn(&str) + Send + Sync>,
+    ) -> Result<ChatResponse> {
+        let client = self.client()?;
+        let messages = to_openai_messages(system_message, chat_messages);
+        let tools = tools
+            .iter()
+            .map(|t| t.deref().try_into())
+            .collect::<Result<Vec<ChatCompletionTools>, _>>()?;
+        let model = self.model_for(model);
+        block_on(move || async move {
+            tool_calling_stream(&client, messages, tools, model, move |token| {
+                on_token(token)
+            })
+            .await
+        })
+    }
+
+    fn credentials_kind(&self) -> Option<CredentialsKind> {
+        Some(self.credentials.0.clone())
+    }
 }
 
-pub async fn structured_output<T: serde::Serialize + DeserializeOwned + JsonSchema>(
+async fn structured_output(
     client: &Client<OpenAIConfig>,
+    model: String,
     messages: Vec<ChatCompletionRequestMessage>,
-) -> anyhow::Result<Option<T>> {
-    let schema = schema_for!(T);
-    let schema_value = serde_json::to_value(&schema)?;
+    schema: serde_json::Value,
+) -> Result<Option<serde_json::Value>> {
     let response_format = ResponseFormat::JsonSchema {
         json_schema: ResponseFormatJsonSchema {
             description: None,
             name: "structured_response".into(),
-            schema: Some(schema_value),
+            schema: Some(schema),
             strict: Some(false),
         },
     };
 
     let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-5-mini")
+        .model(model)
         .messages(messages)
         .response_format(response_format)
         .build()?;
@@ -167,103 +239,31 @@ pub async fn structured_output<T: serde::Serialize + DeserializeOwned + JsonSche
 
     for choice in response.choices {
         if let Some(content) = choice.message.content {
-            return Ok(Some(serde_json::from_str::<T>(&content)?));
+            return Ok(Some(serde_json::from_str(&content)?));
         }
     }
 
     Ok(None)
 }
 
-pub fn tool_calling_blocking(
-    client: &OpenAiProvider,
-    messages: Vec<ChatCompletionRequestMessage>,
-    tools: Vec<async_openai::types::chat::ChatCompletionTools>,
-    model: Option<String>,
-) -> anyhow::Result<async_openai::types::chat::CreateChatCompletionResponse> {
-    let client = client.client()?;
-    let messages_owned = messages.clone();
-    let tools_owned = tools.clone();
-
-    std::thread::spawn(move || {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(tool_calling(&client, messages_owned, tools_owned, model))
-    })
-    .join()
-    .unwrap()
-}
-
-pub async fn tool_calling(
-    client: &Client<OpenAIConfig>,
-    messages: Vec<ChatCompletionRequestMessage>,
-    tools: Vec<async_openai::types::chat::ChatCompletionTools>,
-    model: Option<String>,
-) -> anyhow::Result<async_openai::types::chat::CreateChatCompletionResponse> {
-    let model = model.unwrap_or_else(|| "gpt-5-mini".to_string());
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(model)
-        .messages(messages.clone())
-        .tools(tools)
-        .build()?;
-
-    let response = client.chat().create(request).await?;
-
-    Ok(response)
-}
-
-pub struct ToolCall {
-    pub id: String,
-    pub name: String,
-    pub arguments: String,
-}
-
-type StreamToolCallResult = (Option<Vec<ToolCall>>, Option<String>);
-
-pub fn tool_calling_stream_blocking(
-    client: &OpenAiProvider,
-    messages: Vec<ChatCompletionRequestMessage>,
-    tools: Vec<async_openai::types::chat::ChatCompletionTools>,
-    model: Option<String>,
-    on_token: impl Fn(&str) + Send + Sync + 'static,
-) -> anyhow::Result<StreamToolCallResult> {
-    let client = client.client()?;
-    let messages_owned = messages.clone();
-    let tools_owned = tools.clone();
-
-    std::thread::spawn(move || {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(tool_calling_stream(
-                &client,
-                messages_owned,
-                tools_owned,
-                model,
-                on_token,
-            ))
-    })
-    .join()
-    .unwrap()
-}
-
-pub async fn tool_calling_stream(
+async fn tool_calling_stream(
     client: &Client<OpenAIConfig>,
     messages: Vec<ChatCompletionRequestMessage>,
-    tools: Vec<async_openai::types::chat::ChatCompletionTools>,
-    model: Option<String>,
+    tools: Vec<ChatCompletionTools>,
+    model: String,
     on_token: impl Fn(&str) + Send + Sync + 'static,
-) -> anyhow::Result<StreamToolCallResult> {
-    let model = model.unwrap_or_else(|| "gpt-5-mini".to_string());
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(model)
-        .messages(messages.clone())
-        .tools(tools)
-        .build()?;
+) -> Result<ChatResponse> {
+    let mut request = CreateChatCompletionRequestArgs::default();
+    request.model(model).messages(messages);
+    // Some OpenAI compatible servers reject an empty list of tools.
+    if !tools.is_empty() {
+        request.tools(tools);
+    }
+    let request = request.build()?;
 
     let mut stream = client.chat().create_stream(request).await?;
 
-    let tool_call_states: Arc<Mutex<HashMap<(u32, u32), ToolCall>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-
+    let mut tool_call_states: BTreeMap<(u32, u32), ToolCall> = BTreeMap::new();
     let mut response_text: Option<String> = None;
 
     while let Some(result) = stream.next().await {
@@ -273,22 +273,14 @@ pub async fn tool_calling_stream(
             if let Some(tool_calls) = &chat_choice.delta.tool_calls {
                 for tool_call_chunk in tool_calls.iter() {
                     let key = (chat_choice.index, tool_call_chunk.index);
-                    let states = tool_call_states.clone();
-                    let tool_call_data = tool_call_chunk.clone();
-
-                    let mut states_lock = states.lock().await;
-                    let state = states_lock.entry(key).or_insert_with(|| ToolCall {
-                        id: tool_call_data.id.clone().unwrap_or_default(),
-                        name: tool_call_data
+                    let state = tool_call_states.entry(key).or_insert_with(|| ToolCall {
+                        id: tool_call_chunk.id.clone().unwrap_or_default(),
+                        name: tool_call_chunk
                             .function
                             .as_ref()
                             .and_then(|f| f.name.clone())
                             .unwrap_or_default(),
-                        arguments: tool_call_data
-                            .function
-                            .as_ref()
-                            .and_then(|f| f.arguments.clone())
-                            .unwrap_or_default(),
+                        arguments: String::new(),
                     });
 
                     if let Some(arguments) = tool_call_chunk
@@ -308,40 +300,56 @@ pub async fn tool_calling_stream(
                     async_openai::types::chat::FinishReason::ToolCalls
                 )
             {
-                let tool_call_states_clone = tool_call_states.clone();
-
-                let tool_calls_to_process = {
-                    let states_lock = tool_call_states_clone.lock().await;
-                    states_lock
-                        .values()
-                        .map(|state| ToolCall {
-                            id: state.id.clone(),
-                            name: state.name.clone(),
-                            arguments: state.arguments.clone(),
-                        })
-                        .collect::<Vec<ToolCall>>()
-                };
-
-                return Ok((Some(tool_calls_to_process), response_text));
+                break;
             }
 
             // If there is any text content in the response, call the on_token callback
             if let Some(content) = &chat_choice.delta.content {
-                if response_text.is_none() {
-                    response_text = Some(String::new());
-                }
+                response_text.get_or_insert_default().push_str(content);
+                on_token(content);
+            }
+        }
+    }
 
-                if let Some(text) = response_text.as_mut() {
-                    text.push_str(content);
-                }
+    // Not all OpenAI compatible servers finish with the `tool_calls` reason, so any tool call
+    // that was streamed is returned.
+    Ok(ChatResponse {
+        text: response_text,
+        tool_calls: tool_call_states.into_values().collect(),
+    })
+}
 
-                let content_str = content.as_str();
-                on_token(content_str);
-            }
+/// Convert `chat_messages` into OpenAI messages after the `system_message`, with consecutive
+/// tool calls merged into one assistant message as the API expects their responses to follow it.
+fn to_openai_messages(
+    system_message: &str,
+    chat_messages: Vec<ChatMessage>,
+) -> Vec<ChatCompletionRequestMessage> {
+    let mut messages: Vec<ChatCompletionRequestMessage> =
+        vec![ChatCompletionRequestSystemMessage::from(system_message).into()];
+
+    for message in chat_messages {
+        let message = ChatCompletionRequestMessage::from(message);
+        if let (
+            ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+                content: None,
+                tool_calls: Some(previous_calls),
+                ..
+            }),
+            ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+                content: None,
+                tool_calls: Some(calls),
+                ..
+            }),
+        ) = (messages.last_mut().expect("system message"), &message)
+        {
+            previous_calls.extend(calls.iter().cloned());
+            continue;
         }
+        messages.push(message);
     }
 
-    Ok((None, response_text))
+    messages
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -403,55 +411,6 @@ impl From<ChatMessage> for ChatCompletionRequestMessage {
     }
 }
 
-fn from_openai_chat_messages(messages: Vec<ChatCompletionRequestMessage>) -> Vec<ChatMessage> {
-    let mut chat_messages = Vec::new();
-
-    for m in messages.into_iter() {
-        match m {
-            ChatCompletionRequestMessage::User(content) => {
-                if let ChatCompletionRequestUserMessageContent::Text(text) = content.content {
-                    chat_messages.push(ChatMessage::User(text));
-                }
-            }
-            ChatCompletionRequestMessage::Assistant(assistant_msg) => {
-                if let Some(tool_calls) = &assistant_msg.tool_calls {
-                    for tool_call in tool_calls {
-                        if let ChatCompletionMessageToolCalls::Function(func_call) = tool_call {
-                            chat_messages.push(ChatMessage::ToolCall(ToolCallContent {
-                                id: func_call.id.clone(),
-                                name: func_call.function.name.clone(),
-                                arguments: func_call.function.arguments.clone(),
-                            }));
-                        } else {
-                            tracing::warn!(
-                                ?tool_call,
-                                "Encountered unexpected non-function tool call"
-                            );
-                        }
-                    }
-                }
-
-                if let Some(ChatCompletionRequestAssistantMessageContent::Text(text)) =
-                    assistant_msg.content
-                {
-                    chat_messages.push(ChatMessage::Assistant(text));
-                }
-            }
-            ChatCompletionRequestMessage::Tool(tool_msg) => {
-                if let ChatCompletionRequestToolMessageContent::Text(text) = tool_msg.content {
-                    chat_messages.push(ChatMessage::ToolResponse(ToolResponseContent {
-                        id: tool_msg.tool_call_id.clone(),
-                        result: text,
-                    }));
-                }
-            }
-            _ => (),
-        }
-    }
-
-    chat_messages
-}
-
 fn clamp_result_content(result: &ToolResponseContent) -> String {
     if result.result.len() > 500 {
         "Result too big to be displayed".to_string()
@@ -500,274 +459,3 @@ impl From<String> for ChatMessage {
         ChatMessage::User(msg)
     }
 }
-
-pub fn tool_calling_loop(
-    provider: &OpenAiProvider,
-    system_message: &str,
-    chat_messages: Vec<ChatMessage>,
-    tool_set: &mut impl Toolset,
-    model: Option<String>,
-) -> Result<String> {
-    let mut messages: Vec<ChatCompletionRequestMessage> =
-        vec![ChatCompletionRequestSystemMessage::from(system_message).into()];
-
-    messages.extend(
-        chat_messages
-            .into_iter()
-            .map(ChatCompletionRequestMessage::from)
-            .collect::<Vec<_>>(),
-    );
-
-    let open_ai_tools = tool_set
-        .list()
-        .iter()
-        .map(|t| t.deref().try_into())
-        .collect::<Result<Vec<async_openai::types::chat::ChatCompletionTools>, _>>()?;
-
-    let mut response = tool_calling_blocking(
-        provider,
-        messages.clone(),
-        open_ai_tools.clone(),
-        model.clone(),
-    )?;
-
-    let mut text_response_buffer = vec![];
-    if let Some(text_response) = response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.content.clone())
-    {
-        text_response_buffer.push(text_response.clone());
-        messages.push(ChatCompletionRequestMessage::Assistant(
-            async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                content: Some(ChatCompletionRequestAssistantMessageContent::Text(
-                    text_response,
-                )),
-                ..Default::default()
-            },
-        ));
-    }
-
-    while let Some(tool_calls) = response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.tool_calls.as_ref())
-    {
-        let mut tool_calls_messages: Vec<ChatCompletionMessageToolCalls> = vec![];
-        let mut tool_response_messages: Vec<ChatCompletionRequestMessage> = vec![];
-
-        for call in tool_calls {
-            // Extract function call from the enum
-            let (id, function_name, function_args) = match call {
-                ChatCompletionMessageToolCalls::Function(func_call) => (
-                    func_call.id.clone(),
-                    func_call.function.name.clone(),
-                    func_call.function.arguments.clone(),
-                ),
-                ChatCompletionMessageToolCalls::Custom(custom) => {
-                    tracing::warn!(?custom, "Encountered unexpected custom tool call");
-                    continue;
-                }
-            };
-
-            let tool_response = tool_set.call_tool(&function_name, &function_args);
-            let tool_response_str = serde_json::to_string(&tool_response)
-                .context("Failed to serialize tool response")?;
-
-            tool_calls_messages.push(ChatCompletionMessageToolCalls::Function(
-                async_openai::types::chat::ChatCompletionMessageToolCall {
-                    id: id.clone(),
-                    function: async_openai::types::chat::FunctionCall {
-                        name: function_name,
-                        arguments: function_args,
-                    },
-                },
-            ));
-
-            tool_response_messages.push(ChatCompletionRequestMessage::Tool(
-                async_openai::types::chat::ChatCompletionRequestToolMessage {
-                    tool_call_id: id.clone(),
-                    content: ChatCompletionRequestToolMessageContent::Text(tool_response_str),
-                },
-            ));
-        }
-
-        messages.push(ChatCompletionRequestMessage::Assistant(
-            async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                tool_calls: Some(tool_calls_messages),
-                ..Default::default()
-            },
-        ));
-
-        messages.extend(tool_response_messages);
-
-        response = crate::openai::tool_calling_blocking(
-            provider,
-            messages.clone(),
-            open_ai_tools.clone(),
-            model.clone(),
-        )?;
-
-        if let Some(text_response) = response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.clone())
-        {
-            text_response_buffer.push(text_response.clone());
-            messages.push(ChatCompletionRequestMessage::Assistant(
-                async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                    content: Some(ChatCompletionRequestAssistantMessageContent::Text(
-                        text_response,
-                    )),
-                    ..Default::default()
-                },
-            ));
-        }
-    }
-
-    let response = text_response_buffer
-        .into_iter()
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<String>>()
-        .join("\n\n");
-
-    Ok(response)
-}
-
-pub fn tool_calling_loop_stream(
-    provider: &OpenAiProvider,
-    system_message: &str,
-    chat_messages: Vec<ChatMessage>,
-    tool_set: &mut impl Toolset,
-    model: Option<String>,
-    on_token: Arc<dyn Fn(&str) + Send + Sync + 'static>,
-) -> anyhow::Result<(String, Vec<ChatMessage>)> {
-    let mut messages: Vec<ChatCompletionRequestMessage> =
-        vec![ChatCompletionRequestSystemMessage::from(system_message).into()];
-
-    messages.extend(
-        chat_messages
-            .into_iter()
-            .map(ChatCompletionRequestMessage::from)
-            .collect::<Vec<_>>(),
-    );
-
-    let open_ai_tools = tool_set
-        .list()
-        .iter()
-        .map(|t| t.deref().try_into())
-        .collect::<Result<Vec<async_openai::types::chat::ChatCompletionTools>, _>>()?;
-
-    let on_token_cb = {
-        let on_token = on_token.clone();
-        Box::new(move |token: &str| on_token(token)) as Box<dyn Fn(&str) + Send + Sync + 'static>
-    };
-
-    let mut response = tool_calling_stream_blocking(
-        provider,
-        messages.clone(),
-        open_ai_tools.clone(),
-        model.clone(),
-        on_token_cb,
-    )?;
-
-    let mut text_response_buffer = vec![];
-    if let Some(text_response) = response.1.clone() {
-        text_response_buffer.push(text_response.clone());
-        messages.push(ChatCompletionRequestMessage::Assistant(
-            async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                content: Some(ChatCompletionRequestAssistantMessageContent::Text(
-                    text_response,
-                )),
-                ..Default::default()
-            },
-        ));
-    }
-
-    while let Some(tool_calls) = response.0 {
-        let mut tool_calls_messages: Vec<
-            async_openai::types::chat::ChatCompletionMessageToolCalls,
-        > = vec![];
-        let mut tool_response_messages: Vec<
-            async_openai::types::chat::ChatCompletionRequestMessage,
-        > = vec![];
-
-        for call in tool_calls {
-            let ToolCall {
-                id,
-                name: function_name,
-                arguments: function_args,
-            } = call;
-
-            let tool_response = tool_set.call_tool(&function_name, &function_args);
-
-            let tool_response_str = serde_json::to_string(&tool_response)
-                .context("Failed to serialize tool response")?;
-
-            tool_calls_messages.push(
-                async_openai::types::chat::ChatCompletionMessageToolCalls::Function(
-                    async_openai::types::chat::ChatCompletionMessageToolCall {
-                        id: id.clone(),
-                        function: async_openai::types::chat::FunctionCall {
-                            name: function_name,
-                            arguments: function_args,
-                        },
-                    },
-                ),
-            );
-
-            tool_response_messages.push(
-                async_openai::types::chat::ChatCompletionRequestMessage::Tool(
-                    async_openai::types::chat::ChatCompletionRequestToolMessage {
-                        tool_call_id: id.clone(),
-                        content: ChatCompletionRequestToolMessageContent::Text(tool_response_str),
-                    },
-                ),
-            );
-        }
-
-        messages.push(
-            async_openai::types::chat::ChatCompletionRequestMessage::Assistant(
-                async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                    tool_calls: Some(tool_calls_messages),
-                    ..Default::default()
-                },
-            ),
-        );
-
-        messages.extend(tool_response_messages);
-
-        let on_token_cb = {
-            let on_token = on_token.clone();
-            Box::new(move |token: &str| on_token(token))
-                as Box<dyn Fn(&str) + Send + Sync + 'static>
-        };
-        response = tool_calling_stream_blocking(
-            provider,
-            messages.clone(),
-            open_ai_tools.clone(),
-            model.clone(),
-            on_token_cb,
-        )?;
-
-        if let Some(text_response) = response.1.clone() {
-            text_response_buffer.push(text_response.clone());
-            messages.push(ChatCompletionRequestMessage::Assistant(
-                async_openai::types::chat::ChatCompletionRequestAssistantMessage {
-                    content: Some(ChatCompletionRequestAssistantMessageContent::Text(
-                        text_response,
-                    )),
-                    ..Default::default()
-                },
-            ));
-        }
-    }
-
-    let chat_messages = from_openai_chat_messages(messages);
-    let text_response = text_response_buffer
-        .into_iter()
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<String>>()
-        .join("\n\n");
-    Ok((text_response, chat_messages))
-}
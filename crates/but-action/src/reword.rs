@@ -1,13 +1,16 @@
-use async_openai::{Client, config::OpenAIConfig};
 use but_ctx::Context;
 use but_meta::VirtualBranchesTomlMetadata;
 use but_oxidize::{ObjectIdExt, OidExt};
 use but_settings::AppSettings;
 use but_workspace::legacy::{StacksFilter, ui::StackEntry};
 use gitbutler_project::Project;
+use gitbutler_repo::commit_style::CommitStyle;
 use uuid::Uuid;
 
-use crate::workflow::{self, Workflow};
+use crate::{
+    LlmProvider,
+    workflow::{self, Workflow},
+};
 
 #[derive(Debug, Clone)]
 pub struct CommitEvent {
@@ -20,32 +23,32 @@ pub struct CommitEvent {
     pub trigger: Uuid,
 }
 
-pub async fn commit(
-    client: &Client<OpenAIConfig>,
+pub fn commit(
+    llm: &dyn LlmProvider,
     event: CommitEvent,
 ) -> anyhow::Result<Option<(gix::ObjectId, String)>> {
-    let (diff, sync_ctx) = {
-        let ctx = Context::new_from_legacy_project(event.project.clone())?;
+    let mut ctx = Context::new_from_legacy_project(event.project.clone())?;
+    let diff = {
         let repo = &ctx.clone_repo_for_merging_non_persisting()?;
         let changes = but_core::diff::ui::commit_changes_with_line_stats_by_worktree_dir(
             repo,
             event.commit_id,
         )?;
-        (
-            changes
-                .try_to_unidiff(repo, ctx.settings().context_lines)?
-                .to_string(),
-            ctx.into_sync(),
-        )
+        changes
+            .try_to_unidiff(repo, ctx.settings().context_lines)?
+            .to_string()
     };
+    let style = CommitStyle::from_target_branch(&ctx)
+        .inspect_err(|err| tracing::warn!("Failed to learn the commit message style: {err:#}"))
+        .unwrap_or_default();
     let message = crate::generate::commit_message(
-        client,
+        llm,
         &event.external_summary,
         &event.external_prompt,
         &diff,
-    )
-    .await?;
-    let mut ctx = sync_ctx.into_thread_local();
+        &style,
+        event.project.commit_message_style.template.as_deref(),
+    )?;
     let stacks = stacks(&ctx)?;
     let stack_id = stacks
         .iter()
@@ -0,0 +1,464 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use but_action::{
+    AnthropicProvider, ChatMessage, LlmProvider, OllamaProvider, OpenAiProvider, ToolCallContent,
+    ToolResponseContent, provider_for_project, structured_output_blocking, tool_calling_loop,
+    tool_calling_loop_stream,
+};
+use but_ctx::Context;
+use but_secret::Sensitive;
+use but_tools::{emit::Emitter, tool::Tool, tool::Toolset};
+use gitbutler_project::{AiProvider, Project, ProjectId};
+use gix::ObjectId;
+use serde_json::{Value, json};
+
+mod server;
+use server::{MockServer, Response};
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct Answer {
+    answer: u32,
+}
+
+mod openai_compatible {
+    use super::*;
+
+    #[test]
+    fn structured_output() -> anyhow::Result<()> {
+        let server = MockServer::start([Response::json(completion(r#"{"answer": 42}"#))]);
+        let provider = OpenAiProvider::compatible(
+            format!("{}/v1", server.url),
+            "local-model",
+            Some(Sensitive("secret".into())),
+        );
+
+        let answer =
+            structured_output_blocking::<Answer>(&provider, "Be precise.", vec!["6 * 7?".into()])?;
+        assert_eq!(answer.map(|a| a.answer), Some(42));
+
+        let requests = server.finish();
+        let request = &requests[0];
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert_eq!(request.body["model"], "local-model");
+        assert_eq!(
+            request.body["response_format"]["json_schema"]["schema"]["required"],
+            json!(["answer"])
+        );
+        assert_eq!(
+            request.body["messages"],
+            json!([
+                { "role": "system", "content": "Be precise." },
+                { "role": "user", "content": "6 * 7?" },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tool_calling_loop_runs_tools_until_the_model_answers() -> anyhow::Result<()> {
+        let server = MockServer::start([
+            Response::event_stream([
+                // Self-hosted servers tend to send the arguments at once.
+                chunk(
+                    json!({ "tool_calls": [{
+                        "index": 0,
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "add", "arguments": r#"{"a": 1, "b": 2}"# },
+                    }]}),
+                    None,
+                ),
+                chunk(json!({}), Some("tool_calls")),
+            ]),
+            Response::event_stream([
+                chunk(json!({ "role": "assistant", "content": "It's " }), None),
+                chunk(json!({ "content": "3" }), None),
+                chunk(json!({}), Some("stop")),
+            ]),
+        ]);
+        let provider = OpenAiProvider::compatible(&server.url, "local-model", None);
+        let mut tools = Calculator::default();
+        let tokens = Arc::new(Mutex::new(String::new()));
+
+        let (answer, messages) = tool_calling_loop_stream(
+            &provider,
+            "Use the tools.",
+            vec!["1 + 2?".into()],
+            &mut tools,
+            Some("ignored-model".into()),
+            Arc::new({
+                let tokens = tokens.clone();
+                move |token| tokens.lock().unwrap().push_str(token)
+            }),
+        )?;
+        assert_eq!(answer, "It's 3");
+        assert_eq!(*tokens.lock().unwrap(), "It's 3");
+        assert_eq!(tools.calls, [("add".to_owned(), json!({"a": 1, "b": 2}))]);
+        assert_eq!(
+            serde_json::to_value(&messages)?,
+            serde_json::to_value([
+                ChatMessage::User("1 + 2?".into()),
+                ChatMessage::ToolCall(ToolCallContent {
+                    id: "call_1".into(),
+                    name: "add".into(),
+                    arguments: r#"{"a": 1, "b": 2}"#.into(),
+                }),
+                ChatMessage::ToolResponse(ToolResponseContent {
+                    id: "call_1".into(),
+                    result: "3".into(),
+                }),
+                ChatMessage::Assistant("It's 3".into()),
+            ])?
+        );
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].body["model"], "local-model",
+            "the configured model wins"
+        );
+        assert_eq!(requests[0].body["tools"][0]["function"]["name"], "add");
+        let followup = &requests[1].body["messages"];
+        assert_eq!(followup[2]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(followup[3]["role"], "tool");
+        assert_eq!(followup[3]["content"], "3");
+        Ok(())
+    }
+
+    #[test]
+    fn openai_credentials_are_never_sent() -> anyhow::Result<()> {
+        // No other test uses OpenAI credentials from the environment.
+        unsafe { std::env::set_var("OPENAI_API_KEY", "openai-secret") };
+        let server = MockServer::start([Response::json(completion(r#"{"answer": 42}"#))]);
+        let provider = OpenAiProvider::compatible(&server.url, "local-model", None);
+
+        structured_output_blocking::<Answer>(&provider, "", vec!["6 * 7?".into()])?;
+        let requests = server.finish();
+        let authorization = requests[0].header("authorization").unwrap_or_default();
+        assert!(
+            !authorization.contains("openai-secret"),
+            "a self-hosted server must not see the OpenAI key: {authorization}"
+        );
+        Ok(())
+    }
+
+    fn completion(content: &str) -> Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "local-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop",
+            }],
+        })
+    }
+
+    fn chunk(delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "local-model",
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        })
+    }
+}
+
+mod anthropic {
+    use super::*;
+
+    #[test]
+    fn structured_output_is_a_forced_tool_call() -> anyhow::Result<()> {
+        let server = MockServer::start([Response::json(message([json!({
+            "type": "tool_use",
+            "id": "toolu_1",
+            "name": "structured_response",
+            "input": { "answer": 42 },
+        })]))]);
+        let provider = anthropic(&server);
+
+        let answer =
+            structured_output_blocking::<Answer>(&provider, "Be precise.", vec!["6 * 7?".into()])?;
+        assert_eq!(answer.map(|a| a.answer), Some(42));
+
+        let requests = server.finish();
+        let request = &requests[0];
+        assert_eq!(request.path, "/v1/messages");
+        assert_eq!(request.header("x-api-key"), Some("secret"));
+        assert!(request.header("anthropic-version").is_some());
+        assert_eq!(request.body["model"], "claude-test");
+        assert_eq!(request.body["system"], "Be precise.");
+        assert_eq!(
+            request.body["tool_choice"],
+            json!({ "type": "tool", "name": "structured_response" })
+        );
+        assert_eq!(
+            request.body["tools"][0]["input_schema"]["required"],
+            json!(["answer"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tool_calling_loop_sends_tool_results_as_user_message() -> anyhow::Result<()> {
+        let server = MockServer::start([
+            Response::json(message([
+                json!({ "type": "text", "text": "Let me add." }),
+                json!({
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "add",
+                    "input": { "a": 1, "b": 2 },
+                }),
+            ])),
+            Response::json(message([json!({ "type": "text", "text": "It's 3" })])),
+        ]);
+        let provider = anthropic(&server);
+        let mut tools = Calculator::default();
+
+        let answer = tool_calling_loop(
+            &provider,
+            "Use the tools.",
+            vec!["1 + 2?".into()],
+            &mut tools,
+            None,
+        )?;
+        assert_eq!(answer, "Let me add.\n\nIt's 3");
+        assert_eq!(tools.calls, [("add".to_owned(), json!({"a": 1, "b": 2}))]);
+
+        let requests = server.finish();
+        assert_eq!(requests[0].body["tools"][0]["name"], "add");
+        assert_eq!(
+            requests[1].body["messages"],
+            json!([
+                { "role": "user", "content": [{ "type": "text", "text": "1 + 2?" }] },
+                { "role": "assistant", "content": [
+                    { "type": "text", "text": "Let me add." },
+                    { "type": "tool_use", "id": "toolu_1", "name": "add", "input": { "a": 1, "b": 2 } },
+                ]},
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "toolu_1", "content": "3" },
+                ]},
+            ])
+        );
+        Ok(())
+    }
+
+    fn anthropic(server: &MockServer) -> AnthropicProvider {
+        AnthropicProvider::new(
+            Some(server.url.clone()),
+            "claude-test",
+            Sensitive("secret".into()),
+        )
+    }
+
+    fn message<const N: usize>(content: [Value; N]) -> Value {
+        json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-test",
+            "content": content,
+            "stop_reason": "end_turn",
+        })
+    }
+}
+
+mod ollama {
+    use super::*;
+
+    #[test]
+    fn structured_output_uses_the_schema_as_format() -> anyhow::Result<()> {
+        let server = MockServer::start([Response::json(message(json!({
+            "role": "assistant",
+            "content": r#"{"answer": 42}"#,
+        })))]);
+        let provider = OllamaProvider::new(Some(server.url.clone()), "llama-test");
+
+        let answer =
+            structured_output_blocking::<Answer>(&provider, "Be precise.", vec!["6 * 7?".into()])?;
+        assert_eq!(answer.map(|a| a.answer), Some(42));
+
+        let requests = server.finish();
+        let request = &requests[0];
+        assert_eq!(request.path, "/api/chat");
+        assert_eq!(request.body["model"], "llama-test");
+        assert_eq!(request.body["stream"], false);
+        assert_eq!(request.body["format"]["required"], json!(["answer"]));
+        assert_eq!(request.body["messages"][0]["role"], "system");
+        Ok(())
+    }
+
+    #[test]
+    fn tool_calling_loop_names_the_tool_of_each_result() -> anyhow::Result<()> {
+        let server = MockServer::start([
+            Response::json(message(json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } }],
+            }))),
+            Response::json(message(json!({ "role": "assistant", "content": "It's 3" }))),
+        ]);
+        let provider = OllamaProvider::new(Some(server.url.clone()), "llama-test");
+        let mut tools = Calculator::default();
+
+        let answer = tool_calling_loop(
+            &provider,
+            "Use the tools.",
+            vec!["1 + 2?".into()],
+            &mut tools,
+            None,
+        )?;
+        assert_eq!(answer, "It's 3");
+        assert_eq!(tools.calls, [("add".to_owned(), json!({"a": 1, "b": 2}))]);
+
+        let requests = server.finish();
+        assert_eq!(requests[0].body["tools"][0]["function"]["name"], "add");
+        let followup = &requests[1].body["messages"];
+        assert_eq!(
+            followup[2]["tool_calls"],
+            json!([{ "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } }])
+        );
+        assert_eq!(
+            followup[3],
+            json!({ "role": "tool", "content": "3", "tool_name": "add" })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_contain_the_response() {
+        let server = MockServer::start([Response::error(
+            404,
+            json!({ "error": "model 'llama-test' not found" }),
+        )]);
+        let provider = OllamaProvider::new(Some(server.url.clone()), "llama-test");
+
+        let err = structured_output_blocking::<Answer>(&provider, "", vec!["?".into()])
+            .expect_err("the server failed");
+        assert!(
+            err.to_string().contains("model 'llama-test' not found"),
+            "{err:#}"
+        );
+        server.finish();
+    }
+
+    pub(super) fn message(message: Value) -> Value {
+        json!({ "model": "llama-test", "message": message, "done": true })
+    }
+}
+
+#[test]
+fn projects_choose_their_provider() -> anyhow::Result<()> {
+    let server = MockServer::start([Response::json(ollama::message(json!({
+        "role": "assistant",
+        "content": r#"{"answer": 42}"#,
+    })))]);
+    let mut project = Project::default_with_id(ProjectId::generate());
+    project.ai_provider = AiProvider::Ollama {
+        base_url: Some(server.url.clone()),
+        model: "llama-test".into(),
+    };
+
+    let provider: Box<dyn LlmProvider> = provider_for_project(&project, None)?;
+    let answer = structured_output_blocking::<Answer>(&*provider, "", vec!["6 * 7?".into()])?;
+    assert_eq!(answer.map(|a| a.answer), Some(42));
+    assert_eq!(server.finish()[0].body["model"], "llama-test");
+    Ok(())
+}
+
+#[test]
+fn project_settings_are_tagged_by_kind() -> anyhow::Result<()> {
+    let provider: AiProvider = serde_json::from_value(json!({
+        "kind": "openAiCompatible",
+        "baseUrl": "http://localhost:8000/v1",
+        "model": "local-model",
+    }))?;
+    assert_eq!(
+        provider,
+        AiProvider::OpenAiCompatible {
+            base_url: "http://localhost:8000/v1".into(),
+            model: "local-model".into(),
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(AiProvider::default())?,
+        json!({ "kind": "openAi" })
+    );
+    Ok(())
+}
+
+/// A toolset with an `add` tool, recording its calls.
+struct Calculator {
+    tools: BTreeMap<String, Arc<dyn Tool>>,
+    calls: Vec<(String, Value)>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        let mut calculator = Calculator {
+            tools: BTreeMap::new(),
+            calls: Vec::new(),
+        };
+        calculator.register_tool(Add);
+        calculator
+    }
+}
+
+impl Toolset for Calculator {
+    fn register_tool<T: Tool>(&mut self, tool: T) {
+        self.tools.insert(tool.name(), Arc::new(tool));
+    }
+
+    fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.get(name).cloned()
+    }
+
+    fn list(&self) -> Vec<Arc<dyn Tool>> {
+        self.tools.values().cloned().collect()
+    }
+
+    fn call_tool(&mut self, name: &str, parameters: &str) -> Value {
+        let parameters: Value = serde_json::from_str(parameters).expect("valid arguments");
+        let sum = parameters["a"].as_u64().unwrap_or(0) + parameters["b"].as_u64().unwrap_or(0);
+        self.calls.push((name.to_owned(), parameters));
+        json!(sum)
+    }
+}
+
+struct Add;
+
+impl Tool for Add {
+    fn name(&self) -> String {
+        "add".into()
+    }
+
+    fn description(&self) -> String {
+        "Add two numbers".into()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "a": { "type": "integer" }, "b": { "type": "integer" } },
+            "required": ["a", "b"],
+        })
+    }
+
+    fn call(
+        self: Arc<Self>,
+        _parameters: Value,
+        _ctx: &mut Context,
+        _emitter: Arc<Emitter>,
+        _commit_mapping: &mut HashMap<ObjectId, ObjectId>,
+    ) -> anyhow::Result<Value> {
+        unreachable!("the toolset computes the result")
+    }
+}
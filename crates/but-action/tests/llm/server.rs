@@ -0,0 +1,150 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use serde_json::Value;
+
+/// A request received by the [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub path: String,
+    /// Header names are lower-case.
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find_map(|(n, v)| (n == name).then_some(v.as_str()))
+    }
+}
+
+/// A canned response of the [`MockServer`].
+pub struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    pub fn json(body: Value) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    pub fn error(status: u16, body: Value) -> Self {
+        Response {
+            status,
+            ..Response::json(body)
+        }
+    }
+
+    /// Server-sent `events` as used by streaming OpenAI responses.
+    pub fn event_stream(events: impl IntoIterator<Item = Value>) -> Self {
+        let mut body: String = events
+            .into_iter()
+            .map(|event| format!("data: {event}\n\n"))
+            .collect();
+        body.push_str("data: [DONE]\n\n");
+        Response {
+            status: 200,
+            content_type: "text/event-stream",
+            body,
+        }
+    }
+}
+
+/// A local HTTP server answering one request per connection with the next canned response,
+/// remembering all requests it received.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(responses: impl IntoIterator<Item = Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("can bind to a local port");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses: Vec<_> = responses.into_iter().collect();
+        let handle = std::thread::spawn({
+            let requests = requests.clone();
+            move || {
+                for response in responses {
+                    let (stream, _) = listener.accept().expect("a client connects");
+                    let mut reader = BufReader::new(stream);
+                    requests.lock().unwrap().push(read_request(&mut reader));
+
+                    let mut stream = reader.into_inner();
+                    write!(
+                        stream,
+                        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        response.status,
+                        response.content_type,
+                        response.body.len(),
+                        response.body
+                    )
+                    .expect("the response can be written");
+                }
+            }
+        });
+        MockServer {
+            url,
+            requests,
+            handle: Some(handle),
+        }
+    }
+
+    /// Wait until all responses were sent, and return the requests that were received.
+    pub fn finish(mut self) -> Vec<Request> {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("the server doesn't panic");
+        }
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Request {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_owned();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_owned()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find_map(|(name, value)| (name == "content-length").then(|| value.parse().unwrap()))
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    Request {
+        path,
+        headers,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    }
+}
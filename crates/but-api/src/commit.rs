@@ -3,7 +3,7 @@ use bstr::{BString, ByteSlice};
 use but_api_macros::but_api;
 use but_oplog::legacy::{OperationKind, SnapshotDetails};
 use but_rebase::graph_rebase::{GraphExt, LookupStep as _, mutate::InsertSide};
-use but_workspace::commit::move_changes_between_commits;
+use but_workspace::commit::{move_changes_between_commits, split_commit};
 use tracing::instrument;
 
 /// Rewords a commit
@@ -53,9 +53,11 @@ pub fn commit_reword(
     res
 }
 
-mod ui {
+/// Types used as parameters of the commit APIs.
+pub mod ui {
     use serde::{Deserialize, Serialize};
 
+    /// Describes where a blank commit should be inserted relative to.
     #[derive(Debug, Clone, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase", tag = "type", content = "subject")]
     pub enum RelativeTo {
@@ -88,6 +90,22 @@ mod ui {
             }
         }
     }
+
+    /// One of the commits a commit should be split into.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SplitPiece {
+        /// The message of the new commit.
+        pub message: String,
+        /// The changes of the source commit to put into the new commit.
+        pub changes: Vec<but_core::DiffSpec>,
+    }
+
+    impl From<SplitPiece> for but_workspace::commit::split::SplitPiece {
+        fn from(SplitPiece { message, changes }: SplitPiece) -> Self {
+            Self { message, changes }
+        }
+    }
 }
 
 /// Inserts a blank commit relative to either a commit or a reference
@@ -200,3 +218,59 @@ pub fn commit_move_changes_between(
     };
     res
 }
+
+/// Splits a commit into one commit per piece, in place.
+///
+/// The first new commit keeps the change-id of the source commit, and changes not selected by any
+/// piece stay in the last one.
+///
+/// Returns the IDs of the new commits, from the bottom-most to the top-most one.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn commit_split_only(
+    ctx: &but_ctx::Context,
+    commit_id: json::HexHash,
+    pieces: Vec<ui::SplitPiece>,
+) -> anyhow::Result<json::UISplitCommitResult> {
+    let mut guard = ctx.exclusive_worktree_access();
+    let (repo, _, graph) = ctx.graph_and_meta_mut_and_repo_from_head(guard.write_permission())?;
+    let editor = graph.to_editor(&repo)?;
+
+    let outcome = split_commit(
+        editor,
+        commit_id.into(),
+        pieces.into_iter().map(Into::into).collect(),
+        ctx.settings.context_lines,
+    )?;
+    let materialized = outcome.rebase.materialize()?;
+    let new_commits = outcome
+        .selectors
+        .into_iter()
+        .map(|selector| materialized.lookup_pick(selector).map(Into::into))
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(json::UISplitCommitResult { new_commits })
+}
+
+/// Splits a commit into one commit per piece, in place, with oplog support.
+///
+/// Returns the IDs of the new commits, from the bottom-most to the top-most one.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn commit_split(
+    ctx: &but_ctx::Context,
+    commit_id: json::HexHash,
+    pieces: Vec<ui::SplitPiece>,
+) -> anyhow::Result<json::UISplitCommitResult> {
+    let maybe_oplog_entry = but_oplog::UnmaterializedOplogSnapshot::from_details(
+        ctx,
+        SnapshotDetails::new(OperationKind::SplitCommit),
+    )
+    .ok();
+
+    let res = commit_split_only(ctx, commit_id, pieces);
+    if let Some(snapshot) = maybe_oplog_entry.filter(|_| res.is_ok()) {
+        snapshot.commit(ctx).ok();
+    };
+    res
+}
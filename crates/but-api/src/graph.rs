@@ -0,0 +1,40 @@
+use but_api_macros::but_api;
+use but_ctx::Context;
+use tracing::instrument;
+
+/// Return the graph of the workspace as seen from `HEAD` in its stable JSON format,
+/// including the workspace projection if there is one.
+///
+/// If `anonymize` is `true`, branch and tag names are replaced to make the result suitable for sharing.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn graph_json(
+    ctx: &Context,
+    anonymize: bool,
+) -> anyhow::Result<but_graph::export::json::Graph> {
+    let graph = graph_from_head(ctx, anonymize)?;
+    let workspace = graph.to_workspace()?;
+    Ok(but_graph::export::json::Graph::new(
+        &graph,
+        Some(&workspace),
+    ))
+}
+
+/// Return the graph of the workspace as seen from `HEAD` as Mermaid `gitGraph` diagram.
+///
+/// If `anonymize` is `true`, branch and tag names are replaced to make the result suitable for sharing.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn graph_mermaid(ctx: &Context, anonymize: bool) -> anyhow::Result<String> {
+    Ok(graph_from_head(ctx, anonymize)?.mermaid_graph())
+}
+
+fn graph_from_head(ctx: &Context, anonymize: bool) -> anyhow::Result<but_graph::Graph> {
+    let guard = ctx.shared_worktree_access();
+    let (repo, _meta, mut graph) =
+        ctx.graph_and_meta_and_repo_from_head(ctx.repo.get()?.clone(), guard.read_permission())?;
+    if anonymize {
+        graph.anonymize(&repo.remote_names())?;
+    }
+    Ok(graph)
+}
@@ -423,3 +423,12 @@ pub struct UIMoveChangesResult {
     /// Commits that have been mapped from one thing to another
     pub replaced_commits: Vec<(HexHash, HexHash)>,
 }
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// UI type for a split commit result
+pub struct UISplitCommitResult {
+    /// The commits the source commit was split into, from the bottom-most to the top-most one.
+    #[schemars(with = "Vec<String>")]
+    pub new_commits: Vec<HexHash>,
+}
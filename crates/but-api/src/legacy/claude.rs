@@ -123,6 +123,106 @@ pub fn claude_update_permission_request(
     but_claude::db::update_permission_request(&mut ctx, &request_id, decision, use_wildcard)
 }
 
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_get_usage(project_id: ProjectId) -> Result<but_claude::usage::UsageReport> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    but_claude::usage::report(&mut ctx)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_set_session_budget(
+    project_id: ProjectId,
+    session_id: String,
+    budget_usd: Option<f64>,
+) -> Result<()> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    let session_id = uuid::Uuid::parse_str(&session_id).map_err(anyhow::Error::from)?;
+    but_claude::usage::set_budget(&mut ctx, session_id, budget_usd)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_get_permission_decisions(
+    project_id: ProjectId,
+    session_id: String,
+) -> Result<Vec<but_claude::permissions::audit::DecisionRecord>> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    let session_id = uuid::Uuid::parse_str(&session_id).map_err(anyhow::Error::from)?;
+    but_claude::permissions::audit::list(&mut ctx, session_id)
+}
+
+/// Trust the Claude permission policy of the project as it is now, and return its hash,
+/// or `None` if there is no policy.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_trust_permission_policy(project_id: ProjectId) -> Result<Option<String>> {
+    let project = gitbutler_project::get(project_id)?;
+    but_claude::permissions::policy::trust(&project)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_list_turns(
+    project_id: ProjectId,
+    session_id: String,
+) -> Result<Vec<but_claude::turns::Turn>> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    let session_id = uuid::Uuid::parse_str(&session_id).map_err(anyhow::Error::from)?;
+    but_claude::turns::list(&mut ctx, session_id)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_export_session_markdown(project_id: ProjectId, session_id: String) -> Result<String> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    let session_id = uuid::Uuid::parse_str(&session_id).map_err(anyhow::Error::from)?;
+    but_claude::turns::export_markdown(&mut ctx, session_id)
+}
+
+#[but_api]
+#[instrument(err(Debug))]
+pub fn claude_list_pending_worktrees(
+    project_id: ProjectId,
+) -> Result<Vec<but_claude::worktree::PendingWorktree>> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    but_claude::worktree::list_pending(&mut ctx)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreTurnParams {
+    pub project_id: ProjectId,
+    pub stack_id: StackId,
+    pub session_id: String,
+    /// The ID of the message that started the turn.
+    pub message_id: String,
+}
+
+/// Restore the workspace to the state right before a turn of the session of a stack.
+/// The session must not be running.
+pub async fn claude_restore_turn(claude: &Claude, params: RestoreTurnParams) -> Result<()> {
+    if claude
+        .instance_by_stack
+        .is_stack_active(params.stack_id)
+        .await
+    {
+        anyhow::bail!("Claude is still working on this stack, stop it before restoring a turn");
+    }
+    let project = gitbutler_project::get(params.project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project.clone())?;
+    let session_id = uuid::Uuid::parse_str(&params.session_id).map_err(anyhow::Error::from)?;
+    let message_id = uuid::Uuid::parse_str(&params.message_id).map_err(anyhow::Error::from)?;
+    but_claude::turns::restore_before(&mut ctx, session_id, message_id)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CancelSessionParams {
@@ -3,7 +3,10 @@ use std::borrow::Cow;
 use anyhow::{Context as _, Result, anyhow};
 use but_api_macros::but_api;
 use but_ctx::Context;
-use gitbutler_branch_actions::{internal::PushResult, stack::CreateSeriesRequest};
+use gitbutler_branch_actions::{
+    internal::PushResult,
+    stack::{CreateSeriesRequest, StacksPushResult},
+};
 use gitbutler_oplog::SnapshotExt;
 use gitbutler_project::ProjectId;
 use gitbutler_stack::StackId;
@@ -240,6 +243,27 @@ pub fn push_stack(
     )
 }
 
+/// Push all branches of the stacks with `stack_ids` in a single push, which is atomic if the remote supports it.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn push_stacks(
+    project_id: ProjectId,
+    stack_ids: Vec<StackId>,
+    with_force: bool,
+    skip_force_push_protection: bool,
+    run_hooks: bool,
+) -> Result<StacksPushResult> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = Context::new_from_legacy_project(project)?;
+    gitbutler_branch_actions::stack::push_stacks(
+        &mut ctx,
+        &stack_ids,
+        with_force,
+        skip_force_push_protection,
+        run_hooks,
+    )
+}
+
 #[but_api]
 #[instrument(err(Debug))]
 pub fn push_stack_to_review(
@@ -22,6 +22,12 @@ pub mod commit;
 /// Functions that show what changed in various Git entities, like trees, commits and the worktree.
 pub mod diff;
 
+/// Exports of the commit graph for use by external tools.
+pub mod graph;
+
+/// Functions to manage where secrets are stored.
+pub mod secret;
+
 /// Types meant to be serialised to JSON, without degenerating information despite the need to be UTF-8 encodable.
 /// EXPERIMENTAL
 pub mod json;
@@ -0,0 +1,46 @@
+use but_api_macros::but_api;
+use but_secret::secret::{self, Namespace, Store};
+use tracing::instrument;
+
+/// Secrets that are stored under a fixed handle, independently of any account.
+#[cfg(feature = "legacy")]
+const STATIC_HANDLES: &[(&str, Namespace)] = &[
+    (
+        gitbutler_user::User::ACCESS_TOKEN_HANDLE,
+        Namespace::BuildKind,
+    ),
+    (
+        gitbutler_user::User::GITHUB_ACCESS_TOKEN_HANDLE,
+        Namespace::BuildKind,
+    ),
+    (but_action::OPENAI_KEY_HANDLE, Namespace::Global),
+    (but_action::OPENAI_COMPATIBLE_KEY_HANDLE, Namespace::Global),
+    (but_action::ANTHROPIC_KEY_HANDLE, Namespace::Global),
+];
+/// Only legacy functionality stores secrets under a fixed handle.
+#[cfg(not(feature = "legacy"))]
+const STATIC_HANDLES: &[(&str, Namespace)] = &[];
+
+/// Move all secrets known to GitButler from the `from` store to the `to` store, and return their handles.
+///
+/// This includes the tokens of all known forge accounts. Note that the `to` store should be configured
+/// in the settings afterwards for the secrets to be found there.
+#[but_api]
+#[instrument(err(Debug))]
+pub fn secret_migrate(from: Store, to: Store) -> anyhow::Result<Vec<String>> {
+    let storage = but_forge_storage::Controller::from_path(but_path::app_data_dir()?);
+    let account_handles: Vec<String> = storage
+        .github_accounts()?
+        .iter()
+        .map(|account| account.access_token_key().to_owned())
+        .collect();
+    secret::migrate(
+        STATIC_HANDLES.iter().copied().chain(
+            account_handles
+                .iter()
+                .map(|handle| (handle.as_str(), Namespace::BuildKind)),
+        ),
+        from,
+        to,
+    )
+}
@@ -1,4 +1,4 @@
-use but_action::OpenAiProvider;
+use but_action::LlmProvider;
 use but_ctx::Context;
 use but_tools::emit::Emittable;
 use gitbutler_project::ProjectId;
@@ -95,7 +95,7 @@ pub struct ButBot<'a> {
     emitter: std::sync::Arc<but_tools::emit::Emitter>,
     message_id: String,
     project_id: ProjectId,
-    openai: &'a OpenAiProvider,
+    llm: &'a dyn LlmProvider,
     chat_messages: Vec<but_action::ChatMessage>,
     text_response_buffer: Vec<String>,
 }
@@ -106,7 +106,7 @@ impl<'a> ButBot<'a> {
         emitter: std::sync::Arc<but_tools::emit::Emitter>,
         message_id: String,
         project_id: ProjectId,
-        openai: &'a OpenAiProvider,
+        llm: &'a dyn LlmProvider,
         chat_messages: Vec<but_action::ChatMessage>,
     ) -> Self {
         Self {
@@ -115,7 +115,7 @@ impl<'a> ButBot<'a> {
             emitter,
             message_id,
             project_id,
-            openai,
+            llm,
             chat_messages,
             text_response_buffer: vec![],
         }
@@ -144,7 +144,7 @@ Take a look at the conversation, specifically, the last user request below, and
         ))];
 
         let response = but_action::structured_output_blocking::<ButButRouteResponse>(
-            self.openai,
+            self.llm,
             routing_sys_prompt,
             messages,
         )?;
@@ -198,7 +198,7 @@ Reference relevant resources from the project status (e.g. branches, commits, fi
         ];
 
         but_action::tool_calling_loop(
-            self.openai,
+            self.llm,
             &self.state.sys_prompt.clone(),
             internal_chat_messages,
             &mut self.state,
@@ -253,7 +253,7 @@ Based on the conversation below and the project status, please update the status
         ];
 
         but_action::tool_calling_loop(
-            self.openai,
+            self.llm,
             &self.state.sys_prompt.clone(),
             internal_chat_messages,
             &mut self.state,
@@ -313,7 +313,7 @@ Based on the conversation below and the project status, please update the status
             });
 
         let (response, _) = but_action::tool_calling_loop_stream(
-            self.openai,
+            self.llm,
             SYS_PROMPT,
             internal_chat_messages,
             &mut toolset,
@@ -401,7 +401,7 @@ If you need to perform actions, do so, and be concise in the description of the
             });
 
         let (response, _) = but_action::tool_calling_loop_stream(
-            self.openai,
+            self.llm,
             SYS_PROMPT,
             internal_chat_messages,
             &mut toolset,
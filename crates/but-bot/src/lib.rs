@@ -1,4 +1,4 @@
-use but_action::OpenAiProvider;
+use but_action::LlmProvider;
 use but_ctx::Context;
 use but_tools::emit::Emitter;
 use gitbutler_project::ProjectId;
@@ -15,10 +15,10 @@ pub fn bot(
     message_id: String,
     emitter: std::sync::Arc<Emitter>,
     ctx: &mut Context,
-    openai: &OpenAiProvider,
+    llm: &dyn LlmProvider,
     chat_messages: Vec<but_action::ChatMessage>,
 ) -> anyhow::Result<String> {
-    let mut but_bot = ButBot::new(ctx, emitter, message_id, project_id, openai, chat_messages);
+    let mut but_bot = ButBot::new(ctx, emitter, message_id, project_id, llm, chat_messages);
     let mut graph = AgentGraph::default();
     graph.start(&mut but_bot)
 }
@@ -22,6 +22,7 @@
 use std::{
     collections::HashMap,
     io::{BufRead, BufReader, PipeReader, Read as _},
+    path::{Path, PathBuf},
     process::ExitStatus,
     sync::Arc,
 };
@@ -38,7 +39,7 @@ use tokio::{
     process::{Child, Command},
     sync::{
         Mutex,
-        mpsc::{UnboundedSender, unbounded_channel},
+        mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
     },
 };
 
@@ -51,7 +52,9 @@ use crate::{
     claude_settings::ClaudeSettings,
     db::{self, list_messages_by_session},
     rules::{create_claude_assignment_rule, list_claude_assignment_rules},
-    send_claude_message,
+    send_claude_message, turns,
+    usage::{self, BudgetExceeded, MessageUsage},
+    worktree::{self, ISOLATED_WORKTREE_ENV, SessionWorktree},
 };
 
 /// Holds the CC instances. Currently keyed by stackId, since our current model
@@ -226,6 +229,30 @@ impl Claudes {
             (summary, session_id, session)
         };
 
+        let exceeded = {
+            let mut ctx = sync_ctx.clone().into_thread_local();
+            usage::check_budget(&mut ctx, session_id)?
+        };
+        if let Some(exceeded) = exceeded {
+            self.requests.lock().await.remove(&stack_id);
+            send_claude_message(
+                sync_ctx.clone(),
+                broadcaster.clone(),
+                session_id,
+                stack_id,
+                MessagePayload::System(SystemMessage::BudgetExceeded(exceeded)),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // Isolated sessions change the workspace only once their worktree is integrated after the turn,
+        // so snapshotting the workspace still captures the state before the turn, see `turns`.
+        let snapshot_id = {
+            let ctx = sync_ctx.clone().into_thread_local();
+            turns::snapshot_before_turn(&ctx, session_id, stack_id, &user_params.message)
+        };
+
         // Store the original message for UI display (without inlined file content)
         // while Claude gets the enhanced message with file content inlined
         send_claude_message(
@@ -236,40 +263,63 @@ impl Claudes {
             MessagePayload::User(UserInput {
                 message: user_params.message.clone(), // Original user message for display
                 attachments: user_params.attachments.clone(),
+                snapshot_id,
             }),
         )
         .await?;
 
         let (read_stdout, writer) = std::io::pipe()?;
+        let (send_over_budget, mut recv_over_budget) = unbounded_channel();
         let response_streamer = spawn_response_streaming(
             sync_ctx.clone(),
             broadcaster.clone(),
             read_stdout,
             session_id,
             stack_id,
+            send_over_budget,
         );
 
         let (read_stderr, write_stderr) = std::io::pipe()?;
         // Clone so the reference to ctx can be immediately dropped
         let project_workdir = sync_ctx.legacy_project.worktree_dir()?.to_owned();
+        let worktree = if sync_ctx.settings.claude.isolate_sessions_in_worktrees {
+            Some(
+                self.checkout_worktree(&sync_ctx, stack_id, &session, &project_workdir)
+                    .await?,
+            )
+        } else {
+            None
+        };
         let mut handle = spawn_command(
             writer,
             write_stderr,
             session,
-            project_workdir,
+            project_workdir.clone(),
+            worktree.as_ref(),
             sync_ctx.clone(),
             user_params,
             summary_to_resume,
             stack_id,
         )
         .await?;
-        let cmd_exit = tokio::select! {
-            status = handle.wait() => Exit::WithStatus(status),
-            _ = recv_kill.recv() => Exit::ByUser
-        };
+        let cmd_exit = wait_for_exit(&mut handle, &mut recv_kill, &mut recv_over_budget).await;
         // My understanding is that it is not great to abort things like this,
         // but it's "good enough" for now.
         response_streamer.abort();
+        if let Some(worktree) = worktree {
+            // Integrate before the stack becomes available again, so no new turn starts in the worktree meanwhile.
+            let completed = matches!(&cmd_exit, Exit::WithStatus(Ok(status)) if status.success());
+            self.finish_worktree_turn(
+                &sync_ctx,
+                broadcaster.clone(),
+                stack_id,
+                session_id,
+                &project_workdir,
+                worktree,
+                completed,
+            )
+            .await?;
+        }
         self.requests.lock().await.remove(&stack_id);
 
         handle_exit(
@@ -313,6 +363,67 @@ impl Claudes {
 
         Ok(())
     }
+
+    /// Return the worktree the session of the stack with `stack_id` runs in, creating it if needed,
+    /// and make sure the session can be resumed in it.
+    async fn checkout_worktree(
+        &self,
+        sync_ctx: &ThreadSafeContext,
+        stack_id: StackId,
+        session: &crate::ClaudeSession,
+        project_workdir: &Path,
+    ) -> Result<SessionWorktree> {
+        let worktree = {
+            let mut ctx = sync_ctx.clone().into_thread_local();
+            worktree::checkout(&mut ctx, stack_id)?
+        };
+        worktree::copy_transcripts(session, project_workdir, &worktree.path).await?;
+        Ok(worktree)
+    }
+
+    /// Bring the results of a turn that ran in `worktree` back into the project.
+    ///
+    /// The changes are only integrated into the branch if the turn `completed` and changes
+    /// should be committed after completion. Otherwise they remain in the worktree for the next turn.
+    #[allow(clippy::too_many_arguments)]
+    async fn finish_worktree_turn(
+        &self,
+        sync_ctx: &ThreadSafeContext,
+        broadcaster: Arc<Mutex<Broadcaster>>,
+        stack_id: StackId,
+        session_id: uuid::Uuid,
+        project_workdir: &Path,
+        worktree: SessionWorktree,
+        completed: bool,
+    ) -> Result<()> {
+        let (session, integration) = {
+            let mut ctx = sync_ctx.clone().into_thread_local();
+            let session = db::get_session_by_id(&mut ctx, session_id)?;
+            let integration = if completed {
+                let apply = ctx.settings().claude.auto_commit_after_completion;
+                worktree::integrate(&mut ctx, &worktree, apply)?
+            } else {
+                None
+            };
+            (session, integration)
+        };
+        // Keep the transcripts with the project, so the session can be resumed in any worktree.
+        if let Some(session) = session {
+            worktree::copy_transcripts(&session, &worktree.path, project_workdir).await?;
+        }
+
+        let Some(integration) = integration else {
+            return Ok(());
+        };
+        send_claude_message(
+            sync_ctx.clone(),
+            broadcaster,
+            session_id,
+            stack_id,
+            MessagePayload::System(SystemMessage::WorktreeIntegration(integration)),
+        )
+        .await
+    }
 }
 
 async fn handle_exit(
@@ -342,24 +453,7 @@ async fn handle_exit(
             .await?;
         }
         Exit::ByUser => {
-            // On *nix try to kill claude more gently.
-            #[cfg(unix)]
-            {
-                use nix::{
-                    sys::signal::{self, Signal},
-                    unistd::Pid,
-                };
-                if let Some(pid) = handle.id() {
-                    signal::kill(Pid::from_raw(pid as i32), Signal::SIGINT)?;
-                    handle.wait().await?;
-                } else {
-                    handle.kill().await?;
-                }
-            }
-            #[cfg(not(unix))]
-            {
-                handle.kill().await?;
-            }
+            interrupt(&mut handle).await?;
             send_claude_message(
                 ctx,
                 broadcaster.clone(),
@@ -369,6 +463,52 @@ async fn handle_exit(
             )
             .await?;
         }
+        Exit::OverBudget(exceeded) => {
+            interrupt(&mut handle).await?;
+            send_claude_message(
+                ctx,
+                broadcaster.clone(),
+                session_id,
+                stack_id,
+                MessagePayload::System(crate::SystemMessage::BudgetExceeded(exceeded)),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Wait until the claude process exits by itself, is stopped by the user or its session is over budget.
+async fn wait_for_exit(
+    handle: &mut Child,
+    recv_kill: &mut UnboundedReceiver<()>,
+    recv_over_budget: &mut UnboundedReceiver<BudgetExceeded>,
+) -> Exit {
+    tokio::select! {
+        status = handle.wait() => Exit::WithStatus(status),
+        _ = recv_kill.recv() => Exit::ByUser,
+        Some(exceeded) = recv_over_budget.recv() => Exit::OverBudget(exceeded),
+    }
+}
+
+/// Stop the claude process, on *nix more gently by interrupting it first.
+async fn interrupt(handle: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use nix::{
+            sys::signal::{self, Signal},
+            unistd::Pid,
+        };
+        if let Some(pid) = handle.id() {
+            signal::kill(Pid::from_raw(pid as i32), Signal::SIGINT)?;
+            handle.wait().await?;
+        } else {
+            handle.kill().await?;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        handle.kill().await?;
     }
     Ok(())
 }
@@ -376,6 +516,7 @@ async fn handle_exit(
 enum Exit {
     WithStatus(std::io::Result<ExitStatus>),
     ByUser,
+    OverBudget(BudgetExceeded),
 }
 
 /// Spawns the actual claude code command
@@ -384,7 +525,8 @@ async fn spawn_command(
     writer: std::io::PipeWriter,
     write_stderr: std::io::PipeWriter,
     session: crate::ClaudeSession,
-    project_path: std::path::PathBuf,
+    project_path: PathBuf,
+    worktree: Option<&SessionWorktree>,
     sync_ctx: ThreadSafeContext,
     user_params: ClaudeUserParams,
     summary_to_resume: Option<String>,
@@ -393,12 +535,14 @@ async fn spawn_command(
     // Write and obtain our own claude hooks path.
     let settings = fmt_claude_settings()?;
 
+    // Claude runs in the worktree of the session if it's isolated, or in the project otherwise.
+    let workdir = worktree.map_or(project_path.as_path(), |worktree| worktree.path.as_path());
+
     let claude_executable = sync_ctx.settings.claude.executable.clone();
-    let cc_settings = ClaudeSettings::open(&project_path).await;
+    let cc_settings = ClaudeSettings::open(workdir).await;
 
     // Determine what session ID Claude will use - needed for MCP server configuration
-    let transcript_current_id =
-        Transcript::current_valid_session_id(&project_path, &session).await?;
+    let transcript_current_id = Transcript::current_valid_session_id(workdir, &session).await?;
     let claude_session_id = if summary_to_resume.is_some() {
         // If resuming after compaction, Claude will use a new random ID
         uuid::Uuid::new_v4()
@@ -410,7 +554,7 @@ async fn spawn_command(
         session.id
     };
 
-    let mcp_config = ClaudeMcpConfig::open(&cc_settings, &project_path).await;
+    let mcp_config = ClaudeMcpConfig::open(&cc_settings, workdir).await;
     let disabled_mcp_servers = user_params
         .disabled_mcp_servers
         .iter()
@@ -418,7 +562,7 @@ async fn spawn_command(
         .map(String::as_str)
         .collect::<Vec<&str>>();
     let mcp_config = &mcp_config
-        .mcp_servers_with_security(claude_session_id)
+        .mcp_servers_with_security(claude_session_id, &project_path, workdir)
         .exclude(&disabled_mcp_servers);
     tracing::info!(
         "spawn_command mcp_servers: {:?}",
@@ -436,11 +580,16 @@ async fn spawn_command(
 
     command.stdout(writer);
     command.stderr(write_stderr);
-    command.current_dir(&project_path);
+    command.current_dir(workdir);
 
     command.envs(cc_settings.env());
 
-    command.args(["--settings", &settings]);
+    if worktree.is_some() {
+        // Sessions in worktrees of their own don't share files, so there is nothing for the hooks to do.
+        command.env(ISOLATED_WORKTREE_ENV, "1");
+    } else {
+        command.args(["--settings", &settings]);
+    }
 
     // Mcp configuration. We now use --strict-mcp-config because we collect the
     // set of MCP configurations ourselves so we can then filter out ones that
@@ -493,7 +642,7 @@ async fn spawn_command(
         command.args(["--resume", &format!("{}", claude_session_id)]);
     } else {
         // Start new session - ensure there isn't an existing invalid transcript
-        let path = Transcript::get_transcript_path(&project_path, session.id)?;
+        let path = Transcript::get_transcript_path(workdir, session.id)?;
         if fs::try_exists(&path).await? {
             fs::remove_file(&path).await?;
         }
@@ -505,7 +654,11 @@ async fn spawn_command(
         let mut ctx = sync_ctx.clone().into_thread_local();
         format_branch_info(&mut ctx, stack_id)
     };
-    let system_prompt = format!("{}\n\n{}", system_prompt(), branch_info);
+    let mut system_prompt = format!("{}\n\n{}", system_prompt(), branch_info);
+    if let Some(worktree) = worktree {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(&worktree::system_prompt(worktree));
+    }
     command.args(["--append-system-prompt", &system_prompt]);
 
     if !user_params.add_dirs.is_empty() {
@@ -846,12 +999,16 @@ fn upsert_session(
 
 /// Spawns the thread that manages reading the CC stdout and saves the events to
 /// the db and streams them to the client.
+///
+/// The usage of each message is recorded as well, and `over_budget` is notified
+/// once the session exceeds its budget.
 fn spawn_response_streaming(
     sync_ctx: ThreadSafeContext,
     broadcaster: Arc<Mutex<Broadcaster>>,
     read_stdout: PipeReader,
     session_id: uuid::Uuid,
     stack_id: StackId,
+    over_budget: UnboundedSender<BudgetExceeded>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
@@ -884,6 +1041,19 @@ fn spawn_response_streaming(
                     }
                     first = false;
                 }
+
+                if let Some(message_usage) = MessageUsage::from_stream_event(&parsed_event) {
+                    let exceeded =
+                        usage::record(&mut ctx, session_id, Some(stack_id), &message_usage)
+                            .and_then(|()| usage::check_budget(&mut ctx, session_id));
+                    match exceeded {
+                        Ok(Some(exceeded)) => {
+                            over_budget.send(exceeded).ok();
+                        }
+                        Ok(None) => {}
+                        Err(err) => tracing::warn!("Failed to record Claude usage: {err}"),
+                    }
+                }
             }
 
             let message_content = MessagePayload::Claude(ClaudeOutput {
@@ -1060,3 +1230,84 @@ pub async fn check_claude_available(claude_executable: &str) -> ClaudeCheckResul
         _ => ClaudeCheckResult::NotAvailable,
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write as _;
+
+    use nix::{errno::Errno, sys::signal, unistd::Pid};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn claude_is_stopped_once_the_session_is_over_budget() -> Result<()> {
+        let (repo, _tmp) = but_testsupport::writable_scenario("stacked-branches");
+        let mut ctx = Context::from_repo(repo)?;
+        let session_id = uuid::Uuid::new_v4();
+        let stack_id = StackId::generate();
+        db::save_new_session(&mut ctx, session_id)?;
+        usage::set_budget(&mut ctx, session_id, Some(1.0))?;
+        let sync_ctx = ctx.into_sync();
+        let broadcaster = Arc::new(Mutex::new(Broadcaster::new()));
+
+        // A stand-in for Claude that would keep working if it wasn't stopped.
+        let mut claude = Command::new("sleep").arg("60").spawn()?;
+        let pid = Pid::from_raw(claude.id().expect("running") as i32);
+
+        let (read_stdout, mut write_stdout) = std::io::pipe()?;
+        let (send_over_budget, mut recv_over_budget) = unbounded_channel();
+        let response_streamer = spawn_response_streaming(
+            sync_ctx.clone(),
+            broadcaster.clone(),
+            read_stdout,
+            session_id,
+            stack_id,
+            send_over_budget,
+        );
+        let event = serde_json::json!({
+            "type": "assistant",
+            "session_id": session_id,
+            "message": {
+                "id": "msg_1",
+                "model": "claude-sonnet-4-5",
+                "usage": { "input_tokens": 1_000_000, "output_tokens": 0 }
+            }
+        });
+        writeln!(write_stdout, "{event}")?;
+
+        let (_send_kill, mut recv_kill) = unbounded_channel();
+        let exit = wait_for_exit(&mut claude, &mut recv_kill, &mut recv_over_budget).await;
+        response_streamer.abort();
+        assert!(
+            matches!(exit, Exit::OverBudget(exceeded) if exceeded.cost_usd == 3.0),
+            "the streamed usage of $3 exceeds the budget of $1"
+        );
+
+        let (read_stderr, _write_stderr) = std::io::pipe()?;
+        handle_exit(
+            sync_ctx.clone(),
+            broadcaster,
+            stack_id,
+            session_id,
+            read_stderr,
+            claude,
+            exit,
+        )
+        .await?;
+        assert_eq!(
+            signal::kill(pid, None),
+            Err(Errno::ESRCH),
+            "the process was stopped"
+        );
+
+        let messages = db::list_messages_by_session(&mut sync_ctx.into_thread_local(), session_id)?;
+        assert!(
+            matches!(
+                messages.last().map(|message| message.content()),
+                Some(MessagePayload::System(SystemMessage::BudgetExceeded(_)))
+            ),
+            "the user learns why the session stopped"
+        );
+        Ok(())
+    }
+}
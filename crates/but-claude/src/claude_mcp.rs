@@ -114,7 +114,14 @@ impl ClaudeMcpConfig {
         out
     }
 
-    pub fn mcp_servers_with_security(&self, current_session_id: uuid::Uuid) -> McpConfig {
+    /// Return all MCP servers, along with the GitButler security server for the session with `current_session_id`,
+    /// which operates on the project at `project_path` and runs in `workdir`.
+    pub fn mcp_servers_with_security(
+        &self,
+        current_session_id: uuid::Uuid,
+        project_path: &Path,
+        workdir: &Path,
+    ) -> McpConfig {
         let cli_path = get_cli_path()
             .map(|p| p.to_string_lossy().into_owned())
             .unwrap_or("but".into());
@@ -126,10 +133,14 @@ impl ClaudeMcpConfig {
                 command: Some(cli_path),
                 url: None,
                 args: Some(vec![
+                    "-C".to_owned(),
+                    project_path.to_string_lossy().into_owned(),
                     "claude".to_owned(),
                     "permission-prompt-mcp".to_owned(),
                     "--session-id".to_owned(),
                     current_session_id.to_string(),
+                    "--workdir".to_owned(),
+                    workdir.to_string_lossy().into_owned(),
                 ]),
                 env: Some(HashMap::new()),
                 headers: None,
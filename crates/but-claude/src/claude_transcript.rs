@@ -8,7 +8,7 @@ use anyhow::{Context as _, Result};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 
-use crate::ClaudeSession;
+use crate::{ClaudeSession, usage::MessageUsage};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserMessage {
@@ -203,6 +203,33 @@ impl Transcript {
         None
     }
 
+    /// The usage of all messages Claude produced, in order of appearance.
+    /// Messages with multiple content blocks appear once per block, the last reported usage wins.
+    pub fn usage(&self) -> Vec<MessageUsage> {
+        let mut usage: Vec<MessageUsage> = Vec::new();
+        let mut index_by_id = HashMap::new();
+        for record in &self.records {
+            if let Record::Assistant {
+                message: Some(msg), ..
+            } = record
+                && let Some(message_usage) = MessageUsage::from_parts(
+                    msg.id.as_deref(),
+                    msg.model.as_deref(),
+                    msg.usage.as_ref(),
+                )
+            {
+                match index_by_id.get(&message_usage.message_id) {
+                    Some(&idx) => usage[idx] = message_usage,
+                    None => {
+                        index_by_id.insert(message_usage.message_id.clone(), usage.len());
+                        usage.push(message_usage);
+                    }
+                }
+            }
+        }
+        usage
+    }
+
     async fn transcript_exists_and_likely_valid(
         project_path: &Path,
         session_id: uuid::Uuid,
@@ -1,5 +1,7 @@
 use anyhow::Result;
+use but_core::ref_metadata::StackId;
 use but_ctx::Context;
+use but_worktrees::WorktreeId;
 use uuid::Uuid;
 
 use crate::{ClaudePermissionRequest, ClaudeSession};
@@ -25,6 +27,7 @@ pub fn save_new_session_with_gui_flag(
         in_gui,
         approved_permissions: vec![],
         denied_permissions: vec![],
+        budget_usd: None,
     };
     ctx.db
         .get_mut()?
@@ -85,6 +88,19 @@ pub fn update_session_permissions(
     Ok(())
 }
 
+/// Sets the budget in USD for a given session in the database, or removes it if `None`.
+pub fn set_session_budget(
+    ctx: &mut Context,
+    session_id: Uuid,
+    budget_usd: Option<f64>,
+) -> anyhow::Result<()> {
+    ctx.db
+        .get_mut()?
+        .claude_sessions()
+        .update_budget(&session_id.to_string(), budget_usd)?;
+    Ok(())
+}
+
 /// Lists all known Claude sessions
 pub fn list_all_sessions(ctx: &mut Context) -> anyhow::Result<Vec<ClaudeSession>> {
     let sessions = ctx.db.get_mut()?.claude_sessions().list()?;
@@ -216,6 +232,110 @@ pub fn update_permission_request(
     Ok(())
 }
 
+/// Saves the usage of a message, replacing the usage previously saved for the same message.
+pub fn save_usage(ctx: &mut Context, usage: crate::usage::UsageRecord) -> anyhow::Result<()> {
+    ctx.db.get_mut()?.claude_usage().upsert(usage.try_into()?)?;
+    Ok(())
+}
+
+/// Lists the usage of all messages of all sessions, oldest first.
+pub fn list_usage(ctx: &mut Context) -> anyhow::Result<Vec<crate::usage::UsageRecord>> {
+    let usage = ctx.db.get_mut()?.claude_usage().list()?;
+    usage
+        .into_iter()
+        .map(|u| u.try_into())
+        .collect::<Result<_, _>>()
+}
+
+/// Lists the usage of all messages associated with a given session ID, oldest first.
+pub fn list_usage_by_session(
+    ctx: &mut Context,
+    session_id: Uuid,
+) -> anyhow::Result<Vec<crate::usage::UsageRecord>> {
+    let usage = ctx
+        .db
+        .get_mut()?
+        .claude_usage()
+        .list_by_session(&session_id.to_string())?;
+    usage
+        .into_iter()
+        .map(|u| u.try_into())
+        .collect::<Result<_, _>>()
+}
+
+/// Saves a permission decision, replacing the decision previously saved for the same tool call.
+pub fn save_permission_decision(
+    ctx: &mut Context,
+    decision: crate::permissions::audit::DecisionRecord,
+) -> anyhow::Result<()> {
+    ctx.db
+        .get_mut()?
+        .claude_permission_decisions()
+        .upsert(decision.try_into()?)?;
+    Ok(())
+}
+
+/// Lists the permission decisions made in a given session, oldest first.
+pub fn list_permission_decisions_by_session(
+    ctx: &mut Context,
+    session_id: Uuid,
+) -> anyhow::Result<Vec<crate::permissions::audit::DecisionRecord>> {
+    let decisions = ctx
+        .db
+        .get_mut()?
+        .claude_permission_decisions()
+        .list_by_session(&session_id.to_string())?;
+    decisions
+        .into_iter()
+        .map(|d| d.try_into())
+        .collect::<Result<_, _>>()
+}
+
+/// Remembers that the isolated session of the stack with `stack_id` runs in the worktree with `worktree_id`.
+pub fn save_worktree(
+    ctx: &mut Context,
+    stack_id: StackId,
+    worktree_id: &WorktreeId,
+) -> anyhow::Result<()> {
+    ctx.db
+        .get_mut()?
+        .claude_worktrees()
+        .upsert(but_db::ClaudeWorktree {
+            stack_id: stack_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+        })?;
+    Ok(())
+}
+
+/// Returns the worktree that the isolated session of the stack with `stack_id` runs in, if there is one.
+pub fn get_worktree(ctx: &mut Context, stack_id: StackId) -> anyhow::Result<Option<WorktreeId>> {
+    let worktree = ctx
+        .db
+        .get_mut()?
+        .claude_worktrees()
+        .get(&stack_id.to_string())?;
+    Ok(worktree.map(|w| WorktreeId::from_bstr(w.worktree_id)))
+}
+
+/// Lists the worktrees that the isolated sessions of all stacks run in, oldest first.
+pub fn list_worktrees(ctx: &mut Context) -> anyhow::Result<Vec<(StackId, WorktreeId)>> {
+    let worktrees = ctx.db.get_mut()?.claude_worktrees().list()?;
+    worktrees
+        .into_iter()
+        .map(|w| Ok((w.stack_id.parse()?, WorktreeId::from_bstr(w.worktree_id))))
+        .collect()
+}
+
+/// Forgets the worktree of the stack with `stack_id`, usually after its changes were integrated.
+pub fn delete_worktree(ctx: &mut Context, stack_id: StackId) -> anyhow::Result<()> {
+    ctx.db
+        .get_mut()?
+        .claude_worktrees()
+        .delete(&stack_id.to_string())?;
+    Ok(())
+}
+
 impl TryFrom<but_db::ClaudeSession> for crate::ClaudeSession {
     type Error = anyhow::Error;
     fn try_from(value: but_db::ClaudeSession) -> Result<Self, Self::Error> {
@@ -233,6 +353,7 @@ impl TryFrom<but_db::ClaudeSession> for crate::ClaudeSession {
             in_gui: value.in_gui,
             approved_permissions,
             denied_permissions,
+            budget_usd: value.budget_usd,
         })
     }
 }
@@ -252,6 +373,75 @@ impl TryFrom<crate::ClaudeSession> for but_db::ClaudeSession {
             in_gui: value.in_gui,
             approved_permissions,
             denied_permissions,
+            budget_usd: value.budget_usd,
+        })
+    }
+}
+
+impl TryFrom<but_db::ClaudeUsage> for crate::usage::UsageRecord {
+    type Error = anyhow::Error;
+    fn try_from(value: but_db::ClaudeUsage) -> Result<Self, Self::Error> {
+        Ok(crate::usage::UsageRecord {
+            message_id: value.message_id,
+            session_id: Uuid::parse_str(&value.session_id)?,
+            stack_id: value.stack_id.map(|id| id.parse()).transpose()?,
+            model: value.model,
+            created_at: value.created_at,
+            tokens: crate::usage::TokenUsage {
+                input_tokens: value.input_tokens.try_into()?,
+                output_tokens: value.output_tokens.try_into()?,
+                cache_creation_input_tokens: value.cache_creation_input_tokens.try_into()?,
+                cache_read_input_tokens: value.cache_read_input_tokens.try_into()?,
+            },
+            cost_usd: value.cost_usd,
+        })
+    }
+}
+
+impl TryFrom<crate::usage::UsageRecord> for but_db::ClaudeUsage {
+    type Error = anyhow::Error;
+    fn try_from(value: crate::usage::UsageRecord) -> Result<Self, Self::Error> {
+        Ok(but_db::ClaudeUsage {
+            message_id: value.message_id,
+            session_id: value.session_id.to_string(),
+            stack_id: value.stack_id.map(|id| id.to_string()),
+            model: value.model,
+            created_at: value.created_at,
+            input_tokens: value.tokens.input_tokens.try_into()?,
+            output_tokens: value.tokens.output_tokens.try_into()?,
+            cache_creation_input_tokens: value.tokens.cache_creation_input_tokens.try_into()?,
+            cache_read_input_tokens: value.tokens.cache_read_input_tokens.try_into()?,
+            cost_usd: value.cost_usd,
+        })
+    }
+}
+
+impl TryFrom<but_db::ClaudePermissionDecision> for crate::permissions::audit::DecisionRecord {
+    type Error = anyhow::Error;
+    fn try_from(value: but_db::ClaudePermissionDecision) -> Result<Self, Self::Error> {
+        Ok(crate::permissions::audit::DecisionRecord {
+            id: value.id,
+            session_id: Uuid::parse_str(&value.session_id)?,
+            created_at: value.created_at,
+            tool_name: value.tool_name,
+            input: serde_json::from_str(&value.input)?,
+            decision: value.decision.parse()?,
+            source: value.source.parse()?,
+        })
+    }
+}
+
+impl TryFrom<crate::permissions::audit::DecisionRecord> for but_db::ClaudePermissionDecision {
+    type Error = anyhow::Error;
+    fn try_from(value: crate::permissions::audit::DecisionRecord) -> Result<Self, Self::Error> {
+        Ok(but_db::ClaudePermissionDecision {
+            id: value.id,
+            session_id: value.session_id.to_string(),
+            created_at: value.created_at,
+            tool_name: value.tool_name,
+            input: serde_json::to_string(&value.input)?,
+            decision: value.decision.to_string(),
+            source: value.source.to_string(),
         })
     }
 }
@@ -0,0 +1,413 @@
+//! An agent-agnostic hook protocol.
+//!
+//! Any coding agent that can run a shell command on its lifecycle events, like Codex or Aider,
+//! can get its own lane by piping a JSON event into `but agent hook --protocol generic`.
+//! Each agent session is assigned to its own stack, the files it edits are locked against
+//! concurrent edits of other sessions, and its changes are committed when it stops.
+//!
+//! The `event` field determines the kind of event, all events identify the agent and its session:
+//!
+//! ```json
+//! { "event": "session-start", "agent": "codex", "session_id": "abc", "cwd": "/repo" }
+//! { "event": "file-edit", "agent": "codex", "session_id": "abc", "cwd": "/repo",
+//!   "file_path": "src/lib.rs", "stage": "after",
+//!   "patch": [{ "old_start": 1, "old_lines": 2, "new_start": 1, "new_lines": 3 }] }
+//! { "event": "stop", "agent": "codex", "session_id": "abc", "cwd": "/repo", "prompt": "Fix the bug" }
+//! ```
+//!
+//! A `file-edit` with `"stage": "before"` locks the file for the session, and is optional.
+//! With `"stage": "after"`, the default, the hunks of the file that intersect with the `patch` are
+//! assigned to the stack of the session and the lock is released. Without a `patch`, all hunks of the file are assigned.
+//!
+//! Session IDs that aren't UUIDs are mapped to one that is stable for the agent and its session ID.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, anyhow};
+use but_action::Source;
+use but_core::{HunkHeader, ref_metadata::StackId};
+use but_ctx::Context;
+use gitbutler_project::Project;
+use gitbutler_stack::VirtualBranchesHandle;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    ClearLocksGuard, CommittedBranch, assign_edit_to_session, commit_session_changes, file_lock,
+    get_or_create_session, list_stacks,
+};
+
+/// A lifecycle event of a coding agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum AgentEvent {
+    /// The agent started a new session, or resumed one.
+    SessionStart(SessionStart),
+    /// The agent is about to edit, or has edited, a file.
+    FileEdit(FileEdit),
+    /// The agent finished its turn.
+    Stop(Stop),
+}
+
+/// Identifies the agent and session an event belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentSession {
+    /// The name of the agent, like `codex` or `aider`.
+    pub agent: String,
+    /// The ID of the session as assigned by the agent.
+    pub session_id: String,
+    /// The directory the agent works in, which must be inside the worktree of a project.
+    pub cwd: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStart {
+    #[serde(flatten)]
+    pub session: AgentSession,
+}
+
+/// Whether a [`FileEdit`] event is sent before or after the file is edited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EditStage {
+    Before,
+    #[default]
+    After,
+}
+
+/// A hunk of a structured patch of an edited file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+impl From<PatchHunk> for HunkHeader {
+    fn from(hunk: PatchHunk) -> Self {
+        HunkHeader {
+            old_start: hunk.old_start,
+            old_lines: hunk.old_lines,
+            new_start: hunk.new_start,
+            new_lines: hunk.new_lines,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEdit {
+    #[serde(flatten)]
+    pub session: AgentSession,
+    /// The path of the edited file, either absolute or relative to `cwd`.
+    pub file_path: PathBuf,
+    #[serde(default)]
+    pub stage: EditStage,
+    /// The hunks of the edit, or empty if unknown or if the file was created.
+    #[serde(default)]
+    pub patch: Vec<PatchHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stop {
+    #[serde(flatten)]
+    pub session: AgentSession,
+    /// The prompt of the user that started the turn, used to describe the commits.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// A summary of what the agent did, used to describe the commits.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// The response to an [`AgentEvent`], written as JSON to stdout.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentHookOutput {
+    /// Whether the agent should continue.
+    #[serde(rename = "continue")]
+    pub do_continue: bool,
+    /// A message for the user or the agent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The stack the session is assigned to, if it was determined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack_id: Option<StackId>,
+    /// The branches that received commits when the session stopped.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub committed: Vec<CommittedBranch>,
+}
+
+impl AgentHookOutput {
+    fn proceed() -> Self {
+        AgentHookOutput {
+            do_continue: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Read an [`AgentEvent`] from `read` and handle it.
+pub async fn handle(read: impl std::io::Read) -> Result<AgentHookOutput> {
+    let event: AgentEvent = serde_json::from_reader(read)
+        .map_err(|e| anyhow!("Failed to parse agent event JSON: {e}"))?;
+    match event {
+        AgentEvent::SessionStart(event) => handle_session_start(event),
+        AgentEvent::FileEdit(event) => handle_file_edit(event),
+        AgentEvent::Stop(event) => handle_stop(event).await,
+    }
+}
+
+fn handle_session_start(event: SessionStart) -> Result<AgentHookOutput> {
+    let (ctx, _project) = &mut open(&event.session)?;
+    let session_id = session_uuid(&event.session).to_string();
+
+    let stacks = list_stacks(ctx)?;
+    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
+    let stack_id = get_or_create_session(ctx, &session_id, stacks, vb_state)?;
+
+    let branch_name = list_stacks(ctx)?
+        .into_iter()
+        .find(|stack| stack.id == Some(stack_id))
+        .and_then(|stack| stack.name().map(ToString::to_string));
+    Ok(AgentHookOutput {
+        message: branch_name
+            .map(|name| format!("Changes of this session are assigned to the branch '{name}'")),
+        stack_id: Some(stack_id),
+        ..AgentHookOutput::proceed()
+    })
+}
+
+fn handle_file_edit(event: FileEdit) -> Result<AgentHookOutput> {
+    let (ctx, project) = &mut open(&event.session)?;
+    let session_id = session_uuid(&event.session).to_string();
+    let file_path = relative_path(
+        project.worktree_dir()?,
+        &event.session.cwd,
+        &event.file_path,
+    )?;
+
+    match event.stage {
+        EditStage::Before => {
+            file_lock::obtain(ctx, session_id, file_path)?;
+            Ok(AgentHookOutput::proceed())
+        }
+        EditStage::After => {
+            let defer = ClearLocksGuard {
+                ctx,
+                session_id: session_id.clone(),
+                file_path: Some(file_path.clone()),
+            };
+            let headers: Vec<HunkHeader> = event.patch.into_iter().map(Into::into).collect();
+            let stack_id = assign_edit_to_session(defer.ctx, &session_id, &file_path, &headers)?;
+            Ok(AgentHookOutput {
+                stack_id: Some(stack_id),
+                ..AgentHookOutput::proceed()
+            })
+        }
+    }
+}
+
+async fn handle_stop(event: Stop) -> Result<AgentHookOutput> {
+    let (ctx, project) = &mut open(&event.session)?;
+    let session_id = session_uuid(&event.session).to_string();
+    let defer = ClearLocksGuard {
+        ctx,
+        session_id: session_id.clone(),
+        file_path: None,
+    };
+
+    if !defer.ctx.settings().claude.auto_commit_after_completion {
+        return Ok(AgentHookOutput {
+            message: Some("Auto-commit after completion is disabled".into()),
+            ..AgentHookOutput::proceed()
+        });
+    }
+
+    let changes =
+        but_core::diff::ui::worktree_changes_by_worktree_dir(project.worktree_dir()?.into())?
+            .changes;
+    if changes.is_empty() {
+        return Ok(AgentHookOutput {
+            message: Some("No changes detected".into()),
+            ..AgentHookOutput::proceed()
+        });
+    }
+
+    let committed = commit_session_changes(
+        defer.ctx,
+        &session_id,
+        event.summary.as_deref().unwrap_or_default(),
+        event.prompt.as_deref().unwrap_or_default(),
+        Source::Agent {
+            name: event.session.agent.clone(),
+            session_id: event.session.session_id.clone(),
+        },
+    )
+    .await?;
+    Ok(AgentHookOutput {
+        stack_id: committed.first().map(|branch| branch.stack_id),
+        committed,
+        ..AgentHookOutput::proceed()
+    })
+}
+
+/// Open the project that contains the working directory of `session`.
+fn open(session: &AgentSession) -> Result<(Context, Project)> {
+    let repo = gix::discover(&session.cwd)
+        .with_context(|| format!("No repository found at '{}'", session.cwd.display()))?;
+    let project = Project::from_path(
+        repo.workdir()
+            .ok_or(anyhow!("No worktree found for repo"))?,
+    )?;
+    let ctx = Context::new_from_legacy_project(project.clone())?;
+    Ok((ctx, project))
+}
+
+/// The UUID under which the session is tracked, which is the session ID itself if it is a UUID.
+fn session_uuid(session: &AgentSession) -> Uuid {
+    Uuid::parse_str(&session.session_id).unwrap_or_else(|_| {
+        let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
+        hasher.update(session.agent.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(session.session_id.as_bytes());
+        let digest = hasher
+            .try_finalize()
+            .expect("hashing in memory doesn't fail");
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest.as_bytes()[..16]);
+        uuid::Builder::from_sha1_bytes(bytes).into_uuid()
+    })
+}
+
+/// Turn `file_path`, absolute or relative to `cwd`, into a path relative to `worktree_dir` with `/` as separator,
+/// like paths in Git.
+fn relative_path(worktree_dir: &Path, cwd: &Path, file_path: &Path) -> Result<String> {
+    let absolute = if file_path.is_absolute() {
+        file_path.to_owned()
+    } else {
+        cwd.join(file_path)
+    };
+    let relative = absolute.strip_prefix(worktree_dir).with_context(|| {
+        format!(
+            "'{}' is not inside the worktree at '{}'",
+            absolute.display(),
+            worktree_dir.display()
+        )
+    })?;
+    Ok(gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative)).to_string())
+}
+
+/// Write the output of an agent hook to stdout, or the error to stderr.
+pub trait OutputAgentJson {
+    fn output_agent_json(self) -> Self;
+}
+
+impl OutputAgentJson for Result<AgentHookOutput> {
+    fn output_agent_json(self) -> Self {
+        match &self {
+            Ok(output) => println!("{}", serde_json::to_string(output).unwrap_or_default()),
+            Err(e) => eprintln!(
+                "{}",
+                serde_json::to_string(&AgentHookOutput {
+                    do_continue: false,
+                    message: Some(e.to_string()),
+                    ..Default::default()
+                })
+                .unwrap_or_default()
+            ),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_events() {
+        let event: AgentEvent = serde_json::from_str(
+            r#"{"event": "file-edit", "agent": "codex", "session_id": "abc", "cwd": "/repo",
+                "file_path": "src/lib.rs", "patch": [{"old_start": 1, "old_lines": 2, "new_start": 1, "new_lines": 3}]}"#,
+        )
+        .unwrap();
+        let session = AgentSession {
+            agent: "codex".into(),
+            session_id: "abc".into(),
+            cwd: "/repo".into(),
+        };
+        assert_eq!(
+            event,
+            AgentEvent::FileEdit(FileEdit {
+                session: session.clone(),
+                file_path: "src/lib.rs".into(),
+                stage: EditStage::After,
+                patch: vec![PatchHunk {
+                    old_start: 1,
+                    old_lines: 2,
+                    new_start: 1,
+                    new_lines: 3
+                }],
+            }),
+            "edits are assumed to have happened, and the patch is optional"
+        );
+
+        let event: AgentEvent = serde_json::from_str(
+            r#"{"event": "stop", "agent": "codex", "session_id": "abc", "cwd": "/repo"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            AgentEvent::Stop(Stop {
+                session,
+                prompt: None,
+                summary: None,
+            })
+        );
+    }
+
+    #[test]
+    fn session_ids_are_mapped_to_stable_uuids() {
+        let session = |agent: &str, session_id: &str| AgentSession {
+            agent: agent.into(),
+            session_id: session_id.into(),
+            cwd: "/repo".into(),
+        };
+        let uuid = "1b4e28ba-2fa1-41d2-883f-0016d3cca427";
+        assert_eq!(
+            session_uuid(&session("codex", uuid)).to_string(),
+            uuid,
+            "UUIDs are used as is"
+        );
+        assert_eq!(
+            session_uuid(&session("aider", "main")),
+            session_uuid(&session("aider", "main")),
+            "other IDs are mapped to the same UUID every time"
+        );
+        assert_ne!(
+            session_uuid(&session("aider", "main")),
+            session_uuid(&session("codex", "main")),
+            "the agent is part of the identity of a session"
+        );
+    }
+
+    #[test]
+    fn relative_paths() -> Result<()> {
+        let worktree_dir = Path::new("/repo");
+        assert_eq!(
+            relative_path(worktree_dir, &worktree_dir.join("src"), Path::new("lib.rs"))?,
+            "src/lib.rs",
+            "paths are relative to the working directory of the agent, with `/` as separator"
+        );
+        assert_eq!(
+            relative_path(worktree_dir, Path::new("/"), Path::new("/repo/README.md"))?,
+            "README.md"
+        );
+        assert!(
+            relative_path(worktree_dir, Path::new("/elsewhere"), Path::new("lib.rs")).is_err(),
+            "files outside of the worktree can't be assigned"
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,7 @@
 use std::{collections::HashMap, path::Path, str::FromStr};
 
 use anyhow::{Context as _, Result, anyhow};
-use but_action::{
-    ActionHandler, OpenAiProvider, Source, rename_branch::RenameBranchParams, reword::CommitEvent,
-};
+use but_action::{ActionHandler, Source, rename_branch::RenameBranchParams, reword::CommitEvent};
 use but_ctx::{Context, access::WorktreeWritePermission};
 use but_hunk_assignment::HunkAssignmentRequest;
 use but_meta::VirtualBranchesTomlMetadata;
@@ -16,6 +14,7 @@ use gitbutler_project::Project;
 use gitbutler_stack::VirtualBranchesHandle;
 use serde::{Deserialize, Serialize};
 
+pub mod agent;
 mod file_lock;
 use but_core::{HunkHeader, ref_metadata::StackId};
 use uuid::Uuid;
@@ -85,6 +84,10 @@ pub struct ClaudeStopInput {
 }
 
 pub async fn handle_stop(read: impl std::io::Read) -> anyhow::Result<ClaudeHookOutput> {
+    if crate::worktree::in_isolated_worktree() {
+        return Ok(ClaudeHookOutput::isolated());
+    }
+
     let input: ClaudeStopInput = serde_json::from_reader(read)
         .map_err(|e| anyhow::anyhow!("Failed to parse input JSON: {}", e))?;
 
@@ -96,6 +99,18 @@ pub async fn handle_stop(read: impl std::io::Read) -> anyhow::Result<ClaudeHookO
             .ok_or(anyhow!("No worktree found for repo"))?,
     )?;
 
+    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
+    let session_id = original_session_id(ctx, input.session_id.clone())?;
+    let exit_early = should_exit_early(ctx, &input.session_id)?;
+
+    // Sessions in the GUI record their usage while streaming the output of Claude.
+    if !exit_early
+        && let Err(err) =
+            crate::usage::record_transcript(ctx, Uuid::parse_str(&session_id)?, &transcript)
+    {
+        tracing::warn!("Failed to record Claude usage: {err}");
+    }
+
     let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(
         project.clone().worktree_dir()?.into(),
     )?
@@ -118,10 +133,7 @@ pub async fn handle_stop(read: impl std::io::Read) -> anyhow::Result<ClaudeHookO
     let summary = transcript.summary().unwrap_or_default();
     let prompt = transcript.prompt().unwrap_or_default();
 
-    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-    let session_id = original_session_id(ctx, input.session_id.clone())?;
-
-    if should_exit_early(ctx, &input.session_id)? {
+    if exit_early {
         return Ok(ClaudeHookOutput {
             do_continue: true,
             stop_reason: "Session running in GUI, skipping hook".to_string(),
@@ -143,50 +155,107 @@ pub async fn handle_stop(read: impl std::io::Read) -> anyhow::Result<ClaudeHookO
         });
     }
 
-    let vb_state = &VirtualBranchesHandle::new(defer.ctx.project_data_dir());
+    let committed = commit_session_changes(
+        defer.ctx,
+        &session_id,
+        &summary,
+        &prompt,
+        Source::ClaudeCode(session_id.clone()),
+    )
+    .await?;
+
+    // Write commit notification messages to the database
+    // These will be broadcasted by the main process after Claude completes
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
+    for branch in committed {
+        let commit_message = crate::MessagePayload::GitButler(
+            crate::GitButlerUpdate::CommitCreated(crate::CommitCreatedDetails {
+                stack_id: Some(branch.stack_id.to_string()),
+                branch_name: Some(branch.branch_name),
+                commit_ids: Some(branch.commit_ids),
+            }),
+        );
+        crate::db::save_new_message(defer.ctx, session_uuid, commit_message)?;
+    }
+
+    // For now, we just return a response indicating that the tool call was handled
+    Ok(ClaudeHookOutput {
+        do_continue: true,
+        stop_reason: String::default(),
+        suppress_output: true,
+    })
+}
+
+/// A branch that received new commits from the changes of an agent session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedBranch {
+    pub stack_id: StackId,
+    /// The name of the branch, after it was possibly renamed to match its only commit.
+    pub branch_name: String,
+    /// The IDs of the new commits, after their messages were possibly rewritten.
+    pub commit_ids: Vec<String>,
+}
+
+/// Commit the uncommitted changes of the agent session with `session_id` to the stack of the session,
+/// creating the session and its stack if needed.
+///
+/// If an AI provider is available, the messages of the new commits are generated from `summary` and `prompt`,
+/// and branches that received their first commit are named after it.
+pub async fn commit_session_changes(
+    ctx: &mut Context,
+    session_id: &str,
+    summary: &str,
+    prompt: &str,
+    source: Source,
+) -> Result<Vec<CommittedBranch>> {
+    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
 
-    let stacks = list_stacks(defer.ctx)?;
+    let stacks = list_stacks(ctx)?;
 
     // If the session stopped, but there's no session persisted in the database, we create a new one.
     // If the session is already persisted, we just retrieve it.
-    let stack_id = get_or_create_session(defer.ctx, &session_id, stacks, vb_state)?;
+    let stack_id = get_or_create_session(ctx, session_id, stacks, vb_state)?;
 
     let (id, outcome) = but_action::handle_changes(
-        defer.ctx,
-        &summary,
-        Some(prompt.clone()),
+        ctx,
+        summary,
+        Some(prompt.to_owned()),
         ActionHandler::HandleChangesSimple,
-        Source::ClaudeCode(session_id.clone()),
+        source,
         Some(stack_id),
     )?;
 
-    let stacks = list_stacks(defer.ctx)?;
+    let stacks = list_stacks(ctx)?;
+    let project = ctx.legacy_project.clone();
 
     // Trigger commit message generation for newly created commits
     // TODO: Maybe this can be done in the main app process i.e. the GitButler GUI, if available
     // Alternatively, and probably better - we could spawn a new process to do this
+    let llm = but_action::provider_for_project(&project, None)
+        .inspect_err(|err| tracing::warn!("No language model to reword commits with: {err:#}"))
+        .ok();
 
-    if let Some(openai_client) =
-        OpenAiProvider::with(None).and_then(|provider| provider.client().ok())
-    {
-        for branch in &outcome.updated_branches {
-            let mut commit_message_mapping = HashMap::new();
+    let mut committed = Vec::new();
+    for branch in &outcome.updated_branches {
+        let mut commit_message_mapping = HashMap::new();
+        let mut final_branch_name = branch.branch_name.clone();
 
-            let eligibility = is_branch_eligible_for_rename(defer.ctx, &stacks, branch)?;
+        if let Some(llm) = &llm {
+            let eligibility = is_branch_eligible_for_rename(ctx, &stacks, branch)?;
 
             for commit in &branch.new_commits {
                 if let Ok(commit_id) = gix::ObjectId::from_str(commit) {
                     let commit_event = CommitEvent {
-                        external_summary: summary.clone(),
-                        external_prompt: prompt.clone(),
+                        external_summary: summary.to_owned(),
+                        external_prompt: prompt.to_owned(),
                         branch_name: branch.branch_name.clone(),
                         commit_id,
                         project: project.clone(),
-                        app_settings: defer.ctx.settings().clone(),
+                        app_settings: ctx.settings().clone(),
                         trigger: id,
                     };
-                    let reword_result = but_action::reword::commit(&openai_client, commit_event)
-                        .await
+                    let reword_result = but_action::reword::commit(&**llm, commit_event)
                         .ok()
                         .unwrap_or_default();
 
@@ -197,70 +266,47 @@ pub async fn handle_stop(read: impl std::io::Read) -> anyhow::Result<ClaudeHookO
                 }
             }
 
-            let final_branch_name = match eligibility {
-                RenameEligibility::Eligible { commit_id } => {
-                    let reword_result = commit_message_mapping.get(&commit_id).cloned();
-
-                    if let Some((commit_id, commit_message)) = reword_result {
-                        let params = RenameBranchParams {
-                            commit_id,
-                            commit_message,
-                            stack_id: branch.stack_id,
-                            current_branch_name: branch.branch_name.clone(),
-                        };
-                        but_action::rename_branch::rename_branch(
-                            defer.ctx,
-                            &openai_client,
-                            params,
-                            id,
-                        )
-                        .await
-                        .ok()
-                        .unwrap_or_else(|| branch.branch_name.clone())
-                    } else {
-                        branch.branch_name.clone()
-                    }
+            if let RenameEligibility::Eligible { commit_id } = eligibility
+                && let Some((commit_id, commit_message)) =
+                    commit_message_mapping.get(&commit_id).cloned()
+            {
+                let params = RenameBranchParams {
+                    commit_id,
+                    commit_message,
+                    stack_id: branch.stack_id,
+                    current_branch_name: branch.branch_name.clone(),
+                };
+                if let Ok(new_name) =
+                    but_action::rename_branch::rename_branch(ctx, &**llm, params, id)
+                {
+                    final_branch_name = new_name;
                 }
-                RenameEligibility::NotEligible => branch.branch_name.clone(),
-            };
-
-            // Build final commit IDs list - using reworded IDs if available, original otherwise
-            let final_commit_ids: Vec<String> = branch
-                .new_commits
-                .iter()
-                .map(|commit| {
-                    if let Ok(commit_id) = gix::ObjectId::from_str(commit) {
-                        commit_message_mapping
-                            .get(&commit_id)
-                            .map(|(new_id, _)| new_id.to_string())
-                            .unwrap_or_else(|| commit.clone())
-                    } else {
-                        commit.clone()
-                    }
-                })
-                .collect();
-
-            // Write commit notification messages to the database
-            // These will be broadcasted by the main process after Claude completes
-            let session_uuid = uuid::Uuid::parse_str(&session_id)?;
-            let commit_message = crate::MessagePayload::GitButler(
-                crate::GitButlerUpdate::CommitCreated(crate::CommitCreatedDetails {
-                    stack_id: Some(branch.stack_id.to_string()),
-                    branch_name: Some(final_branch_name),
-                    commit_ids: Some(final_commit_ids),
-                }),
-            );
-
-            crate::db::save_new_message(defer.ctx, session_uuid, commit_message)?;
+            }
         }
-    }
 
-    // For now, we just return a response indicating that the tool call was handled
-    Ok(ClaudeHookOutput {
-        do_continue: true,
-        stop_reason: String::default(),
-        suppress_output: true,
-    })
+        // Build final commit IDs list - using reworded IDs if available, original otherwise
+        let commit_ids: Vec<String> = branch
+            .new_commits
+            .iter()
+            .map(|commit| {
+                if let Ok(commit_id) = gix::ObjectId::from_str(commit) {
+                    commit_message_mapping
+                        .get(&commit_id)
+                        .map(|(new_id, _)| new_id.to_string())
+                        .unwrap_or_else(|| commit.clone())
+                } else {
+                    commit.clone()
+                }
+            })
+            .collect();
+
+        committed.push(CommittedBranch {
+            stack_id: branch.stack_id,
+            branch_name: final_branch_name,
+            commit_ids,
+        });
+    }
+    Ok(committed)
 }
 
 pub enum RenameEligibility {
@@ -341,6 +387,10 @@ pub struct ClaudePreToolUseInput {
 }
 
 pub fn handle_pre_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeHookOutput> {
+    if crate::worktree::in_isolated_worktree() {
+        return Ok(ClaudeHookOutput::isolated());
+    }
+
     let mut input: ClaudePreToolUseInput = serde_json::from_reader(read)
         .map_err(|e| anyhow::anyhow!("Failed to parse input JSON: {}", e))?;
 
@@ -369,6 +419,14 @@ pub fn handle_pre_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeHo
         });
     }
 
+    if let Some(exceeded) = check_budget(ctx, &session_id, &input.transcript_path)? {
+        return Ok(ClaudeHookOutput {
+            do_continue: false,
+            stop_reason: exceeded.to_string(),
+            suppress_output: false,
+        });
+    }
+
     file_lock::obtain(ctx, session_id, input.tool_input.file_path.clone())?;
 
     Ok(ClaudeHookOutput {
@@ -379,6 +437,10 @@ pub fn handle_pre_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeHo
 }
 
 pub fn handle_post_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeHookOutput> {
+    if crate::worktree::in_isolated_worktree() {
+        return Ok(ClaudeHookOutput::isolated());
+    }
+
     let mut input: ClaudePostToolUseInput = serde_json::from_reader(read)
         .map_err(|e| anyhow::anyhow!("Failed to parse input JSON: {}", e))?;
 
@@ -422,32 +484,47 @@ pub fn handle_post_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeH
         file_path: Some(input.tool_response.file_path.clone()),
     };
 
-    let stacks = list_stacks(defer.ctx)?;
+    assign_edit_to_session(defer.ctx, &session_id, &relative_file_path, &hook_headers)?;
+
+    Ok(ClaudeHookOutput {
+        do_continue: true,
+        stop_reason: String::default(),
+        suppress_output: true,
+    })
+}
 
-    let vb_state = &VirtualBranchesHandle::new(defer.ctx.project_data_dir());
+/// Assign the uncommitted hunks of `file_path`, relative to the worktree, that intersect with `headers`
+/// to the stack of the agent session with `session_id`, creating the session and its stack if needed.
+/// If `headers` is empty the file is assumed to be new, and all of its hunks are assigned.
+pub fn assign_edit_to_session(
+    ctx: &mut Context,
+    session_id: &str,
+    file_path: &str,
+    headers: &[HunkHeader],
+) -> Result<StackId> {
+    let stacks = list_stacks(ctx)?;
 
-    let stack_id = get_or_create_session(defer.ctx, &session_id, stacks, vb_state)?;
+    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
 
-    let changes =
-        but_core::diff::ui::worktree_changes_by_worktree_dir(project.worktree_dir()?.into())?
-            .changes;
-    let (assignments, _assignments_error) = but_hunk_assignment::assignments_with_fallback(
-        defer.ctx,
-        true,
-        Some(changes.clone()),
-        None,
-    )?;
+    let stack_id = get_or_create_session(ctx, session_id, stacks, vb_state)?;
+
+    let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(
+        ctx.legacy_project.worktree_dir()?.into(),
+    )?
+    .changes;
+    let (assignments, _assignments_error) =
+        but_hunk_assignment::assignments_with_fallback(ctx, true, Some(changes), None)?;
 
     let assignment_reqs: Vec<HunkAssignmentRequest> = assignments
         .into_iter()
         .filter(|a| a.stack_id.is_none())
         .filter(|a| {
-            // If the hook_headers is empty, we probably created a file.
-            if hook_headers.is_empty() {
-                a.path.to_lowercase() == relative_file_path.to_lowercase()
-            } else if a.path.to_lowercase() == relative_file_path.to_lowercase() {
+            // If the headers are empty, we probably created a file.
+            if headers.is_empty() {
+                a.path.to_lowercase() == file_path.to_lowercase()
+            } else if a.path.to_lowercase() == file_path.to_lowercase() {
                 if let Some(a) = a.hunk_header {
-                    hook_headers
+                    headers
                         .iter()
                         .any(|h| h.new_range().intersects(a.new_range()))
                 } else {
@@ -464,13 +541,28 @@ pub fn handle_post_tool_call(read: impl std::io::Read) -> anyhow::Result<ClaudeH
         })
         .collect();
 
-    let _rejections = but_hunk_assignment::assign(defer.ctx, assignment_reqs, None)?;
+    let _rejections = but_hunk_assignment::assign(ctx, assignment_reqs, None)?;
+    Ok(stack_id)
+}
 
-    Ok(ClaudeHookOutput {
-        do_continue: true,
-        stop_reason: String::default(),
-        suppress_output: true,
-    })
+/// Record the usage in the transcript at `transcript_path` and return information about the exceeded budget,
+/// if the session with the stable `session_id` has a budget that it exceeded.
+/// The transcript is only read if the session has a budget.
+fn check_budget(
+    ctx: &mut Context,
+    session_id: &str,
+    transcript_path: &str,
+) -> Result<Option<crate::usage::BudgetExceeded>> {
+    let session_id = Uuid::parse_str(session_id)?;
+    let has_budget = crate::db::get_session_by_id(ctx, session_id)?
+        .and_then(|session| session.budget_usd())
+        .is_some();
+    if !has_budget {
+        return Ok(None);
+    }
+    let transcript = Transcript::from_file(Path::new(transcript_path))?;
+    crate::usage::record_transcript(ctx, session_id, &transcript)?;
+    crate::usage::check_budget(ctx, session_id)
 }
 
 fn original_session_id(ctx: &mut Context, current_id: String) -> Result<String> {
@@ -545,6 +637,17 @@ pub struct ClaudeHookOutput {
     suppress_output: bool,
 }
 
+impl ClaudeHookOutput {
+    /// The output for sessions that run in a worktree of their own, which don't share files with other sessions.
+    fn isolated() -> Self {
+        ClaudeHookOutput {
+            do_continue: true,
+            stop_reason: "Session running in its own worktree, skipping hook".to_string(),
+            suppress_output: true,
+        }
+    }
+}
+
 pub(crate) struct ClearLocksGuard<'a> {
     pub ctx: &'a mut Context,
     session_id: String,
@@ -609,3 +712,60 @@ fn should_exit_early(ctx: &mut Context, session_id: &str) -> anyhow::Result<bool
 
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_tool_hook_stops_sessions_over_budget() -> Result<()> {
+        let (repo, tmp) = but_testsupport::writable_scenario("stacked-branches");
+        let mut ctx = Context::from_repo(repo)?;
+        let workdir = ctx.workdir()?.expect("non-bare");
+        let session_id = Uuid::new_v4();
+        crate::db::save_new_session(&mut ctx, session_id)?;
+        crate::usage::set_budget(&mut ctx, session_id, Some(5.0))?;
+
+        let transcript_path = tmp.path().join("transcript.jsonl");
+        let write_transcript = |input_tokens: u64| {
+            let record = serde_json::json!({
+                "type": "assistant",
+                "message": {
+                    "id": "msg_1",
+                    "model": "claude-sonnet-4-5",
+                    "usage": { "input_tokens": input_tokens, "output_tokens": 0 }
+                }
+            });
+            std::fs::write(&transcript_path, format!("{record}\n"))
+        };
+        let pre_tool_call = || {
+            let input = serde_json::json!({
+                "session_id": session_id,
+                "transcript_path": transcript_path,
+                "hook_event_name": "PreToolUse",
+                "tool_name": "Edit",
+                "tool_input": { "file_path": workdir.join("file.txt") }
+            });
+            handle_pre_tool_call(input.to_string().as_bytes())
+                .and_then(|output| Ok(serde_json::to_value(output)?))
+        };
+
+        write_transcript(1_000_000)?;
+        let output = pre_tool_call()?;
+        assert_eq!(output["continue"], true, "$3 of $5 were spent");
+
+        write_transcript(2_000_000)?;
+        let output = pre_tool_call()?;
+        assert_eq!(output["continue"], false);
+        assert_eq!(
+            output["stopReason"],
+            "The session budget of $5.00 was exceeded, $6.00 have been spent"
+        );
+        assert_eq!(
+            crate::usage::session_usage(&mut ctx, session_id)?.cost_usd,
+            6.0,
+            "the usage of the transcript was recorded, with the latest usage of each message"
+        );
+        Ok(())
+    }
+}
@@ -88,6 +88,7 @@ impl From<UserInput> for crate::UserInput {
             attachments: value
                 .attachments
                 .map(|atts| atts.into_iter().map(|a| a.into()).collect()),
+            snapshot_id: None,
         }
     }
 }
@@ -27,6 +27,9 @@ pub mod notifications;
 pub mod permissions;
 pub mod prompt_templates;
 mod rules;
+pub mod turns;
+pub mod usage;
+pub mod worktree;
 
 pub use permissions::Permission;
 
@@ -64,6 +67,9 @@ pub struct ClaudeSession {
     approved_permissions: Vec<Permission>,
     /// Permissions that have been denied for this session.
     denied_permissions: Vec<Permission>,
+    /// The maximum estimated cost in USD the session may incur before it is stopped, if any.
+    #[serde(default)]
+    budget_usd: Option<f64>,
 }
 
 impl ClaudeSession {
@@ -74,6 +80,10 @@ impl ClaudeSession {
     pub fn denied_permissions(&self) -> &[Permission] {
         &self.denied_permissions
     }
+
+    pub fn budget_usd(&self) -> Option<f64> {
+        self.budget_usd
+    }
 }
 
 /// Represents a message in a Claude session, referencing the stable session ID.
@@ -169,6 +179,9 @@ pub struct UserInput {
     /// Optional attached file references
     #[serde(default)]
     pub attachments: Option<Vec<PromptAttachment>>,
+    /// The oplog snapshot of the state right before Claude received the message, see [`turns`].
+    #[serde(default)]
+    pub snapshot_id: Option<String>,
 }
 
 /// Details about commits created by Claude.
@@ -205,6 +218,14 @@ pub enum SystemMessage {
     },
     /// Deprecated and will be removed, see `GitButlerUpdate::CommitCreated`.
     CommitCreated(CommitCreatedDetails),
+    /// Claude code was stopped or not started as the session exceeded its budget.
+    BudgetExceeded(usage::BudgetExceeded),
+    /// The changes a session made in a worktree of its own were integrated into its branch, or could not be.
+    WorktreeIntegration(worktree::WorktreeIntegration),
+    /// The permission policy of the repository was ignored, entirely or in part, for the reason in `message`.
+    PermissionPolicyIgnored {
+        message: String,
+    },
 }
 
 /// Details about a Claude session, extracted from the Claude transcript.
@@ -16,9 +16,19 @@ use rmcp::{
     schemars, tool, tool_handler, tool_router,
 };
 
-use crate::permissions::{PermissionCheck, Permissions};
+use crate::{
+    MessagePayload, SystemMessage,
+    permissions::{
+        PermissionCheck, Permissions,
+        audit::{self, Decision, DecisionRecord, DecisionSource},
+        policy,
+    },
+};
 
-pub async fn start(repo_path: &Path, session_id_str: &str) -> Result<()> {
+/// Start the permission server of the session whose current ID is `session_id_str`, in the project at `repo_path`.
+///
+/// `workdir` is the directory the session runs in, if it isn't the project itself.
+pub async fn start(repo_path: &Path, session_id_str: &str, workdir: Option<&Path>) -> Result<()> {
     let project = Project::from_path(repo_path).expect("Failed to create project from path");
     let client_info = Arc::new(Mutex::new(None));
     let transport = (tokio::io::stdin(), tokio::io::stdout());
@@ -38,10 +48,35 @@ pub async fn start(repo_path: &Path, session_id_str: &str) -> Result<()> {
         current_session_id
     );
 
+    let policy = match load_policy(&project, workdir) {
+        Ok(policy) => {
+            if policy.ignored_allow_rules {
+                report_ignored_policy(
+                    ctx,
+                    session.id,
+                    "The permission policy of the repository isn't trusted, so only its deny rules apply. Run `but claude trust-policy` to trust it as it is now.".into(),
+                );
+            }
+            policy.permissions
+        }
+        Err(err) => {
+            tracing::error!("Ignoring the permission policy: {err:#}");
+            report_ignored_policy(
+                ctx,
+                session.id,
+                format!(
+                    "The permission policy of the repository is ignored, so its tool calls are asked for instead: {err:#}"
+                ),
+            );
+            Permissions::default()
+        }
+    };
+
     // Use the stable session.id, not the current_id
     let server = Mcp {
         project,
         tool_router: Mcp::tool_router(),
+        policy: Arc::new(policy),
         runtime_permissions: Default::default(),
         session_id: session.id,
     };
@@ -55,10 +90,29 @@ pub async fn start(repo_path: &Path, session_id_str: &str) -> Result<()> {
     Ok(())
 }
 
+/// Load the permission policy of `project` once for the whole session, which runs in `workdir` or the project.
+fn load_policy(project: &Project, workdir: Option<&Path>) -> Result<policy::LoadedPolicy> {
+    let workdir = match workdir {
+        Some(workdir) => workdir.canonicalize()?,
+        None => project.worktree_dir()?.canonicalize()?,
+    };
+    policy::load(&workdir, project.trusted_claude_policy.as_deref())
+}
+
+/// Tell the user of the session with `session_id` why the permission policy doesn't apply as written.
+fn report_ignored_policy(ctx: &mut Context, session_id: uuid::Uuid, message: String) {
+    let payload = MessagePayload::System(SystemMessage::PermissionPolicyIgnored { message });
+    if let Err(err) = crate::db::save_new_message(ctx, session_id, payload) {
+        tracing::warn!("Failed to report the ignored permission policy: {err}");
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Mcp {
     project: Project,
     tool_router: ToolRouter<Self>,
+    /// The permission policy of the repository, loaded when the server starts.
+    policy: Arc<Permissions>,
     runtime_permissions: Arc<Mutex<Permissions>>,
     session_id: uuid::Uuid,
 }
@@ -107,19 +161,29 @@ impl Mcp {
         let session = crate::db::get_session_by_id(ctx, self.session_id)?
             .ok_or_else(|| anyhow::anyhow!("Session not found: {}", self.session_id))?;
 
-        // Merge runtime and session permissions
+        // Merge the repository policy with runtime and session permissions
+        let policy_perms = &*self.policy;
         let runtime_perms = self.runtime_permissions.lock().unwrap();
         let session_perms =
             Permissions::from_slices(session.approved_permissions(), session.denied_permissions());
-        let combined_perms = Permissions::merge([&*runtime_perms, &session_perms]);
+        let combined_perms = Permissions::merge([policy_perms, &*runtime_perms, &session_perms]);
         drop(runtime_perms); // Release the lock
 
-        // Check the combined permissions
+        // Check the combined permissions, attributing the outcome to the policy if it decides it on its own
         let result = combined_perms.check(&req).unwrap_or_default();
-        match result {
-            PermissionCheck::Approved => return Ok(true),
-            PermissionCheck::Denied => return Ok(false),
-            PermissionCheck::Ask => (), // Continue to ask the user
+        let decision = match result {
+            PermissionCheck::Approved => Some(Decision::Allow),
+            PermissionCheck::Denied => Some(Decision::Deny),
+            PermissionCheck::Ask => None, // Continue to ask the user
+        };
+        if let Some(decision) = decision {
+            let source = match (policy_perms.check(&req).unwrap_or_default(), decision) {
+                (PermissionCheck::Approved, Decision::Allow)
+                | (PermissionCheck::Denied, Decision::Deny) => DecisionSource::Policy,
+                _ => DecisionSource::Session,
+            };
+            self.record_decision(ctx, &req, decision, source);
+            return Ok(decision == Decision::Allow);
         }
 
         // Send notification for permission request
@@ -144,6 +208,7 @@ impl Mcp {
             std::time::Duration::from_millis(500),
         )?;
         let mut approved_state = false;
+        let mut answered = false;
         let start_time = std::time::Instant::now();
 
         for item in rx {
@@ -163,6 +228,7 @@ impl Mcp {
                             let decision: crate::PermissionDecision =
                                 serde_json::from_str(&decision_str)?;
                             approved_state = decision.is_allowed();
+                            answered = true;
 
                             // Handle the decision - persist to settings/session/database and update runtime permissions
                             let project_path = self.project.worktree_dir()?.canonicalize()?;
@@ -192,8 +258,29 @@ impl Mcp {
                 }
             }
         }
+
+        let (decision, source) = match (answered, approved_state) {
+            (true, true) => (Decision::Allow, DecisionSource::User),
+            (true, false) => (Decision::Deny, DecisionSource::User),
+            (false, _) => (Decision::Deny, DecisionSource::Unanswered),
+        };
+        self.record_decision(ctx, &req, decision, source);
         Ok(approved_state)
     }
+
+    /// Add a decision to the audit log, which must not prevent the tool call from proceeding if it fails.
+    fn record_decision(
+        &self,
+        ctx: &mut Context,
+        req: &crate::ClaudePermissionRequest,
+        decision: Decision,
+        source: DecisionSource,
+    ) {
+        let record = DecisionRecord::new(self.session_id, req, decision, source);
+        if let Err(e) = audit::record(ctx, record) {
+            tracing::warn!("Failed to record permission decision: {}", e);
+        }
+    }
 }
 
 impl From<McpPermissionRequest> for crate::ClaudePermissionRequest {
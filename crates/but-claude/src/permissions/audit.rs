@@ -0,0 +1,173 @@
+//! An audit log of the permission decisions made during Claude sessions.
+//!
+//! Every tool call Claude asks permission for is recorded along with the decision, and what made it,
+//! whether that was the policy of the repository, an earlier decision, or the user.
+
+use anyhow::Result;
+use but_ctx::Context;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{ClaudePermissionRequest, db};
+
+/// Whether a tool call was allowed.
+#[derive(
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+/// What made a [`Decision`].
+#[derive(
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum DecisionSource {
+    /// The permission policy of the repository, see [`crate::permissions::policy`].
+    Policy,
+    /// Permissions granted or denied earlier, for the session, the project or globally.
+    Session,
+    /// The user, when asked.
+    User,
+    /// Nobody, as no decision was made in time, which denies the tool call.
+    Unanswered,
+}
+
+/// A permission decision about a single tool call.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionRecord {
+    /// The ID of the tool call.
+    pub id: String,
+    /// The stable ID of the session.
+    #[schemars(with = "String")]
+    pub session_id: Uuid,
+    #[schemars(with = "String")]
+    pub created_at: chrono::NaiveDateTime,
+    pub tool_name: String,
+    pub input: serde_json::Value,
+    pub decision: Decision,
+    pub source: DecisionSource,
+}
+
+impl DecisionRecord {
+    /// Create a record of `decision` about `request`, made now.
+    pub fn new(
+        session_id: Uuid,
+        request: &ClaudePermissionRequest,
+        decision: Decision,
+        source: DecisionSource,
+    ) -> Self {
+        Self {
+            id: request.id.clone(),
+            session_id,
+            created_at: chrono::Utc::now().naive_utc(),
+            tool_name: request.tool_name.clone(),
+            input: request.input.clone(),
+            decision,
+            source,
+        }
+    }
+}
+
+/// Add `record` to the audit log, replacing an earlier decision about the same tool call.
+pub fn record(ctx: &mut Context, record: DecisionRecord) -> Result<()> {
+    db::save_permission_decision(ctx, record)
+}
+
+/// List the decisions made during the session with the stable `session_id`, oldest first.
+pub fn list(ctx: &mut Context, session_id: Uuid) -> Result<Vec<DecisionRecord>> {
+    db::list_permission_decisions_by_session(ctx, session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request(id: &str) -> ClaudePermissionRequest {
+        ClaudePermissionRequest {
+            id: id.into(),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: chrono::Utc::now().naive_utc(),
+            tool_name: "Bash".into(),
+            input: json!({"command": "cargo test"}),
+            decision: None,
+            use_wildcard: false,
+        }
+    }
+
+    #[test]
+    fn decisions_are_listed_by_session_oldest_first() -> Result<()> {
+        let (repo, _tmp) = but_testsupport::writable_scenario("stacked-branches");
+        let mut ctx = Context::from_repo(repo)?;
+        let session_id = Uuid::new_v4();
+
+        let decisions = [
+            (Decision::Allow, DecisionSource::Policy),
+            (Decision::Allow, DecisionSource::Session),
+            (Decision::Deny, DecisionSource::User),
+            (Decision::Deny, DecisionSource::Unanswered),
+        ];
+        for (index, (decision, source)) in decisions.into_iter().enumerate() {
+            let request = request(&format!("toolu_{index}"));
+            record(
+                &mut ctx,
+                DecisionRecord::new(session_id, &request, decision, source),
+            )?;
+        }
+        record(
+            &mut ctx,
+            DecisionRecord::new(
+                Uuid::new_v4(),
+                &request("toolu_other"),
+                Decision::Allow,
+                DecisionSource::User,
+            ),
+        )?;
+
+        let listed = list(&mut ctx, session_id)?;
+        assert_eq!(
+            listed
+                .iter()
+                .map(|record| (record.id.as_str(), record.decision, record.source))
+                .collect::<Vec<_>>(),
+            [
+                ("toolu_0", Decision::Allow, DecisionSource::Policy),
+                ("toolu_1", Decision::Allow, DecisionSource::Session),
+                ("toolu_2", Decision::Deny, DecisionSource::User),
+                ("toolu_3", Decision::Deny, DecisionSource::Unanswered),
+            ],
+            "decisions of other sessions aren't listed"
+        );
+        assert!(listed.iter().all(|record| record.session_id == session_id
+            && record.tool_name == "Bash"
+            && record.input == json!({"command": "cargo test"})));
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
+pub mod audit;
 mod bash;
 mod patterns;
+pub mod policy;
 mod settings;
 
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, bail};
 use bash::split_bash_commands;
 pub use patterns::SerializationContext;
 use patterns::*;
@@ -46,6 +48,35 @@ impl Permission {
         }
     }
 
+    /// Parse a permission `rule` as written in Claude settings, the inverse of [`Self::serialize()`].
+    /// Paths are resolved relative to the locations in `ctx`.
+    pub fn parse(rule: &str, ctx: &SerializationContext) -> Result<Self> {
+        let rule = rule.trim();
+        if rule.starts_with("mcp__") {
+            return Ok(Self::Mcp(McpPattern::new(rule.to_owned())));
+        }
+
+        let (tool_name, pattern) = match rule.split_once('(') {
+            Some((tool_name, rest)) => {
+                let pattern = rest
+                    .strip_suffix(')')
+                    .with_context(|| format!("Permission rule '{rule}' lacks a closing ')'"))?;
+                (tool_name, Some(pattern))
+            }
+            None => (rule, None),
+        };
+        Ok(match (tool_name, pattern) {
+            ("Bash", pattern) => Self::Bash(pattern.map(BashPattern::parse)),
+            ("Edit", pattern) => Self::Edit(pattern.map(|p| PathPattern::parse(p, ctx))),
+            ("Write", pattern) => Self::Write(pattern.map(|p| PathPattern::parse(p, ctx))),
+            ("WebFetch", pattern) => Self::WebFetch(pattern.map(UrlPattern::parse).transpose()?),
+            (tool_name, None) if !tool_name.is_empty() => Self::Other {
+                tool_name: tool_name.to_owned(),
+            },
+            _ => bail!("Unsupported permission rule '{rule}'"),
+        })
+    }
+
     /// Create Permissions from a ClaudePermissionRequest
     /// This creates the most specific permissions possible based on the request.
     /// Returns a Vec because bash commands with && or || may contain multiple commands.
@@ -224,6 +255,7 @@ fn matches_pattern_rule(permissions: &[Permission], tool_name: &str, term: &str)
             Permission::WebFetch(Some(pattern)) => {
                 tool_name == "WebFetch" && pattern.matches(term).unwrap_or(false)
             }
+            Permission::Mcp(pattern) => tool_name.starts_with("mcp__") && pattern.matches(term),
             _ => false,
         };
 
@@ -549,4 +581,47 @@ mod test {
             assert_eq!(perm.serialize(&ctx).unwrap(), "CustomTool");
         }
     }
+
+    mod permission_parsing {
+        use super::*;
+
+        #[track_caller]
+        fn assert_round_trip(rule: &str, for_global: bool) {
+            let ctx = create_test_context(for_global);
+            let perm = Permission::parse(rule, &ctx).unwrap();
+            assert_eq!(perm.serialize(&ctx).unwrap(), rule);
+        }
+
+        #[test]
+        fn round_trips() {
+            for rule in [
+                "Bash",
+                "Bash(git status)",
+                "Bash(cargo test:*)",
+                "Edit",
+                "Edit(//etc/config.conf)",
+                "Edit(~/.config/app/settings.json)",
+                "Write(/src/**/*.rs)",
+                "WebFetch",
+                "WebFetch(domain:example.com)",
+                "mcp__but-security",
+                "WebSearch",
+            ] {
+                assert_round_trip(rule, false);
+            }
+            assert_round_trip("Write(/config.json)", true);
+        }
+
+        #[test]
+        fn paths_are_resolved() {
+            let ctx = create_test_context(false);
+            let perm = Permission::parse("Edit(src/main.rs)", &ctx).unwrap();
+            let Permission::Edit(Some(pattern)) = perm else {
+                panic!("expected an edit pattern");
+            };
+            assert!(pattern.matches(std::path::Path::new(
+                "/home/testuser/projects/myproject/src/main.rs"
+            )));
+        }
+    }
 }
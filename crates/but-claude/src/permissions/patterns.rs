@@ -44,6 +44,14 @@ impl BashPattern {
         }
     }
 
+    /// The inverse of [`Self::serialize()`].
+    pub fn parse(pattern: &str) -> Self {
+        match pattern.strip_suffix(":*") {
+            Some(base) => Self::new(base.to_owned(), false),
+            None => Self::new(pattern.to_owned(), true),
+        }
+    }
+
     pub fn serialize(&self) -> String {
         if self.exact {
             self.base.to_owned()
@@ -100,6 +108,30 @@ impl PathPattern {
         }
     }
 
+    /// The inverse of [`Self::serialize()`], which turns `pattern` into an absolute glob.
+    pub fn parse(pattern: &str, ctx: &SerializationContext) -> Self {
+        if let Some(absolute) = pattern.strip_prefix("//") {
+            Self::new(Path::new("/").join(absolute), PathPatternKind::Absolute)
+        } else if let Some(home_relative) = pattern.strip_prefix("~/") {
+            Self::new(
+                ctx.home_path.join(home_relative),
+                PathPatternKind::HomeRelative,
+            )
+        } else if let Some(settings_relative) = pattern.strip_prefix('/') {
+            let base = if ctx.for_global {
+                &ctx.global_claude_dir
+            } else {
+                &ctx.project_path
+            };
+            Self::new(
+                base.join(settings_relative),
+                PathPatternKind::SettingsRelative,
+            )
+        } else {
+            Self::new(ctx.project_path.join(pattern), PathPatternKind::CwdRelative)
+        }
+    }
+
     pub fn serialize(&self, ctx: &SerializationContext) -> Result<String> {
         match self.kind {
             PathPatternKind::Absolute => Ok(format!(
@@ -199,6 +231,14 @@ impl UrlPattern {
         ))
     }
 
+    /// The inverse of [`Self::serialize()`].
+    pub fn parse(pattern: &str) -> Result<UrlPattern> {
+        let domain = pattern
+            .strip_prefix("domain:")
+            .with_context(|| format!("Unsupported URL pattern '{pattern}'"))?;
+        Ok(UrlPattern::Domain(domain.to_owned()))
+    }
+
     pub fn serialize(&self) -> String {
         match self {
             Self::Domain(a) => format!("domain:{a}"),
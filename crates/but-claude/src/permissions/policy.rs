@@ -0,0 +1,273 @@
+//! A permission policy that is checked into the repository.
+//!
+//! The policy pre-approves or denies tool calls for everyone working on the repository, and is merged
+//! with the permissions of the session. It lists rules in the format of Claude settings:
+//!
+//! ```json
+//! {
+//!     "allow": ["Bash(cargo test:*)", "Edit(/src/**)", "WebFetch(domain:docs.rs)", "mcp__gitbutler"],
+//!     "deny": ["Bash(git push:*)", "Edit(/.github/**)"]
+//! }
+//! ```
+//!
+//! Paths starting with a single `/`, and relative paths, are relative to the directory the session runs in,
+//! which is the root of the repository or of the worktree of the session.
+//!
+//! As a cloned repository could pre-approve anything this way, the `allow` rules only apply once the user
+//! [trusted](trust()) the policy, and until it changes. The `deny` rules always apply.
+
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+use gitbutler_project::Project;
+use serde::Deserialize;
+
+use crate::permissions::{Permission, Permissions, SerializationContext};
+
+/// The path of the policy file, relative to the root of the repository.
+pub const POLICY_PATH: &str = ".gitbutler/claude-permissions.json";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Policy {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+/// The permission policy of a repository, as far as it applies.
+#[derive(Debug, Default)]
+pub struct LoadedPolicy {
+    /// The rules that apply, which only include the `allow` rules if the user trusted the policy.
+    pub permissions: Permissions,
+    /// `true` if the policy has `allow` rules that were ignored as the user didn't trust it, or it changed since.
+    pub ignored_allow_rules: bool,
+}
+
+/// Load the permission policy of the worktree at `workdir`, which is empty if there is no policy file.
+///
+/// Paths in rules are resolved against `workdir`. The `allow` rules are only honoured if the policy has
+/// `trusted_hash`, the hash stored by [`trust()`].
+pub fn load(workdir: &Path, trusted_hash: Option<&str>) -> Result<LoadedPolicy> {
+    let path = workdir.join(POLICY_PATH);
+    if !path.try_exists()? {
+        return Ok(LoadedPolicy::default());
+    }
+    let contents = std::fs::read_to_string(&path)?;
+    let trusted = trusted_hash == Some(hash(&contents)?.as_str());
+    parse(&contents, &serialization_context(workdir)?, trusted)
+        .with_context(|| format!("Invalid permission policy at {}", path.display()))
+}
+
+/// Trust the permission policy of `project` as it is now, so its `allow` rules are honoured until it changes.
+///
+/// Return the hash of the trusted policy, or `None` if there is no policy file.
+pub fn trust(project: &Project) -> Result<Option<String>> {
+    let workdir = project.worktree_dir()?;
+    let path = workdir.join(POLICY_PATH);
+    if !path.try_exists()? {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&path)?;
+    parse(&contents, &serialization_context(workdir)?, true)
+        .with_context(|| format!("Invalid permission policy at {}", path.display()))?;
+    let hash = hash(&contents)?;
+    gitbutler_project::update(gitbutler_project::UpdateRequest {
+        trusted_claude_policy: Some(hash.clone()),
+        ..gitbutler_project::UpdateRequest::default_with_id(project.id)
+    })?;
+    Ok(Some(hash))
+}
+
+fn serialization_context(workdir: &Path) -> Result<SerializationContext> {
+    let home_path =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+    let global_claude_dir = home_path.join(".claude");
+    Ok(SerializationContext::new(
+        &home_path,
+        workdir,
+        global_claude_dir,
+        false,
+    ))
+}
+
+/// The hash of the policy file with `contents`, which is the ID of its blob.
+fn hash(contents: &str) -> Result<String> {
+    Ok(gix::objs::compute_hash(
+        gix::hash::Kind::Sha1,
+        gix::object::Kind::Blob,
+        contents.as_bytes(),
+    )?
+    .to_string())
+}
+
+fn parse(contents: &str, ctx: &SerializationContext, trusted: bool) -> Result<LoadedPolicy> {
+    let policy: Policy = serde_json_lenient::from_str_lenient(contents)?;
+    let parse_rules = |rules: &[String]| {
+        rules
+            .iter()
+            .map(|rule| Permission::parse(rule, ctx))
+            .collect::<Result<Vec<_>>>()
+    };
+    let allow = parse_rules(&policy.allow)?;
+    let deny = parse_rules(&policy.deny)?;
+    let ignored_allow_rules = !trusted && !allow.is_empty();
+    Ok(LoadedPolicy {
+        permissions: Permissions::new(if trusted { allow } else { Vec::new() }, deny),
+        ignored_allow_rules,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ClaudePermissionRequest, permissions::PermissionCheck};
+
+    fn ctx() -> SerializationContext {
+        SerializationContext::new(
+            "/home/testuser",
+            "/home/testuser/projects/myproject",
+            "/home/testuser/.claude",
+            false,
+        )
+    }
+
+    fn request(tool_name: &str, input: serde_json::Value) -> ClaudePermissionRequest {
+        ClaudePermissionRequest {
+            id: "toolu_1".into(),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: chrono::Utc::now().naive_utc(),
+            tool_name: tool_name.into(),
+            input,
+            decision: None,
+            use_wildcard: false,
+        }
+    }
+
+    #[test]
+    fn approves_and_denies_tool_calls() -> Result<()> {
+        let policy = parse(
+            r#"{
+                // Comments are allowed
+                "allow": ["Bash(cargo test:*)", "Edit(/src/**)", "WebFetch(domain:docs.rs)", "mcp__gitbutler", "WebSearch"],
+                "deny": ["Bash(git push:*)", "Edit(//etc/**)"],
+            }"#,
+            &ctx(),
+            true,
+        )?
+        .permissions;
+
+        let check = |tool_name: &str, input: serde_json::Value| {
+            policy.check(&request(tool_name, input)).unwrap()
+        };
+        assert!(matches!(
+            check("Bash", json!({"command": "cargo test -p but-claude"})),
+            PermissionCheck::Approved
+        ));
+        assert!(matches!(
+            check("Bash", json!({"command": "git push --force"})),
+            PermissionCheck::Denied
+        ));
+        assert!(matches!(
+            check("Bash", json!({"command": "cargo test && git push"})),
+            PermissionCheck::Denied
+        ));
+        assert!(matches!(
+            check("Bash", json!({"command": "rm -rf target"})),
+            PermissionCheck::Ask
+        ));
+        assert!(matches!(
+            check(
+                "Edit",
+                json!({"file_path": "/home/testuser/projects/myproject/src/lib.rs"})
+            ),
+            PermissionCheck::Approved
+        ));
+        assert!(matches!(
+            check("Edit", json!({"file_path": "/etc/hosts"})),
+            PermissionCheck::Denied
+        ));
+        assert!(matches!(
+            check("WebFetch", json!({"url": "https://docs.rs/anyhow"})),
+            PermissionCheck::Approved
+        ));
+        assert!(matches!(
+            check("mcp__gitbutler__commit", json!({})),
+            PermissionCheck::Approved
+        ));
+        assert!(matches!(
+            check("mcp__other__tool", json!({})),
+            PermissionCheck::Ask
+        ));
+        assert!(matches!(
+            check("WebSearch", json!({"query": "gitbutler"})),
+            PermissionCheck::Approved
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_lists_are_empty() -> Result<()> {
+        let policy = parse(r#"{"deny": ["Bash"]}"#, &ctx(), true)?;
+        assert!(policy.permissions.approved().is_empty());
+        assert_eq!(policy.permissions.denied().len(), 1);
+        assert!(!policy.ignored_allow_rules, "there were no rules to ignore");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(parse(r#"{"allow": ["Bash(git status"]}"#, &ctx(), true).is_err());
+        assert!(parse(r#"{"allow": ["WebFetch(https://docs.rs)"]}"#, &ctx(), true).is_err());
+        assert!(parse(r#"{"allow": ["Search(foo)"]}"#, &ctx(), true).is_err());
+        assert!(
+            parse(r#"{"allow": ["Search(foo)"]}"#, &ctx(), false).is_err(),
+            "ignored rules are still validated"
+        );
+    }
+
+    #[test]
+    fn allow_rules_apply_only_while_the_policy_is_trusted() -> Result<()> {
+        let workdir = tempfile::tempdir()?;
+        let contents = r#"{"allow": ["Edit(/src/**)"], "deny": ["Bash(git push:*)"]}"#;
+        std::fs::create_dir_all(workdir.path().join(".gitbutler"))?;
+        std::fs::write(workdir.path().join(POLICY_PATH), contents)?;
+        let edit = request(
+            "Edit",
+            json!({"file_path": workdir.path().join("src/lib.rs")}),
+        );
+        let push = request("Bash", json!({"command": "git push"}));
+
+        let untrusted = load(workdir.path(), None)?;
+        assert!(untrusted.ignored_allow_rules);
+        assert!(matches!(
+            untrusted.permissions.check(&edit)?,
+            PermissionCheck::Ask
+        ));
+        assert!(
+            matches!(untrusted.permissions.check(&push)?, PermissionCheck::Denied),
+            "deny rules always apply"
+        );
+
+        let trusted_hash = hash(contents)?;
+        let trusted = load(workdir.path(), Some(&trusted_hash))?;
+        assert!(!trusted.ignored_allow_rules);
+        assert!(
+            matches!(trusted.permissions.check(&edit)?, PermissionCheck::Approved),
+            "paths are relative to the directory the session runs in"
+        );
+
+        std::fs::write(
+            workdir.path().join(POLICY_PATH),
+            r#"{"allow": ["Bash"], "deny": ["Bash(git push:*)"]}"#,
+        )?;
+        let changed = load(workdir.path(), Some(&trusted_hash))?;
+        assert!(
+            changed.ignored_allow_rules,
+            "a changed policy has to be trusted again"
+        );
+        assert!(changed.permissions.approved().is_empty());
+        Ok(())
+    }
+}
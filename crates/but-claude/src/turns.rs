@@ -0,0 +1,450 @@
+//! The turns of a Claude session, and the changes each of them made.
+//!
+//! A turn starts with a prompt of the user, and lasts until the next one. Right before Claude receives
+//! the prompt, an oplog snapshot is created and its ID is stored along with the prompt. The changes of a
+//! turn are the difference between its snapshot and the snapshot of the next turn, or the current state
+//! of the workspace for the last turn. Restoring the snapshot of a turn rolls back the turn, along with
+//! all the turns that followed it.
+//!
+//! Note that changes made to the workspace by other means while a turn was running, for instance by
+//! sessions of other stacks, are attributed to the turn as well.
+//!
+//! Sessions that are isolated in a worktree only change the workspace when their changes are integrated
+//! at the end of a turn, so the snapshots of the workspace still delimit their turns. Changes that couldn't
+//! be integrated remain in the worktree and are attributed to the turn that integrates them eventually.
+//! As restoring a snapshot doesn't affect the worktree of the session, a turn can't be restored while the
+//! session has changes in its worktree that weren't integrated yet.
+
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, Result, bail};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use but_oxidize::OidExt as _;
+use gitbutler_oplog::{
+    OplogExt as _,
+    entry::{OperationKind, SnapshotDetails, Trailer},
+};
+use gix::bstr::ByteSlice as _;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    ClaudeMessage, GitButlerUpdate, MessagePayload, db, rules::list_claude_assignment_rules,
+    worktree,
+};
+
+/// The number of lines of context around the changes in exported diffs.
+const CONTEXT_LINES: u32 = 3;
+
+/// A prompt of the user, and everything that happened in response to it.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Turn {
+    /// The ID of the message with the prompt.
+    pub message_id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub prompt: String,
+    /// The oplog snapshot of the state right before the turn, or `None` if no snapshot was recorded.
+    pub snapshot_id: Option<String>,
+    /// The IDs of the commits created during the turn.
+    pub commit_ids: Vec<String>,
+    /// The changes made to the workspace during the turn.
+    pub changes: Vec<but_core::ui::TreeChange>,
+}
+
+/// Create an oplog snapshot of the state right before Claude receives `prompt` for the session
+/// with the stable `session_id`, and return its ID.
+///
+/// Failing to create the snapshot must not prevent the turn, so this is logged and `None` is returned.
+pub(crate) fn snapshot_before_turn(
+    ctx: &Context,
+    session_id: Uuid,
+    stack_id: StackId,
+    prompt: &str,
+) -> Option<String> {
+    let mut details = SnapshotDetails::new(OperationKind::ClaudeTurn).with_trailers(vec![
+        Trailer {
+            key: "session_id".into(),
+            value: session_id.to_string(),
+        },
+        Trailer {
+            key: "stack_id".into(),
+            value: stack_id.to_string(),
+        },
+    ]);
+    details.body = prompt.lines().next().map(ToOwned::to_owned);
+    let mut guard = ctx.exclusive_worktree_access();
+    match ctx.create_snapshot(details, guard.write_permission()) {
+        Ok(oid) if oid.is_zero() => None,
+        Ok(oid) => Some(oid.to_string()),
+        Err(err) => {
+            tracing::warn!("Failed to snapshot the workspace before a Claude turn: {err}");
+            None
+        }
+    }
+}
+
+/// List the turns of the session with the stable `session_id`, oldest first.
+pub fn list(ctx: &mut Context, session_id: Uuid) -> Result<Vec<Turn>> {
+    Ok(collect(ctx, session_id)?
+        .into_iter()
+        .map(|(turn, _)| turn)
+        .collect())
+}
+
+/// Restore the workspace to the state right before the turn that started with the message with `message_id`.
+/// This rolls back the turn, and all turns that followed it.
+pub fn restore_before(ctx: &mut Context, session_id: Uuid, message_id: Uuid) -> Result<()> {
+    let turns = list_without_changes(ctx, session_id)?;
+    let turn = turns
+        .iter()
+        .find(|turn| turn.message_id == message_id)
+        .with_context(|| format!("Session {session_id} has no turn {message_id}"))?;
+    let Some(snapshot_id) = &turn.snapshot_id else {
+        bail!("No snapshot was recorded before turn {message_id}, so it can't be restored");
+    };
+    let stack_ids: Vec<_> = list_claude_assignment_rules(ctx)?
+        .into_iter()
+        .filter(|rule| rule.session_id == session_id)
+        .map(|rule| rule.stack_id)
+        .collect();
+    if let Some(pending) = worktree::list_pending(ctx)?
+        .into_iter()
+        .find(|pending| pending.has_changes && stack_ids.contains(&pending.stack_id))
+    {
+        bail!(
+            "Session {session_id} has changes in its worktree at {} that weren't integrated yet, \
+             integrate or discard them before restoring a turn",
+            pending.path.display()
+        );
+    }
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.restore_snapshot(snapshot_id.parse()?, guard.write_permission())?;
+    Ok(())
+}
+
+/// Render the session with the stable `session_id` as markdown, with the changes of each turn as inline diffs.
+pub fn export_markdown(ctx: &mut Context, session_id: Uuid) -> Result<String> {
+    let turns = collect(ctx, session_id)?;
+    let repo = ctx.repo.get()?;
+
+    let mut out = String::new();
+    writeln!(out, "# Claude session {session_id}")?;
+    for (index, (turn, messages)) in turns.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "## Turn {} ({})",
+            index + 1,
+            turn.created_at.format("%Y-%m-%d %H:%M:%S")
+        )?;
+        writeln!(out)?;
+        for line in turn.prompt.lines() {
+            writeln!(out, "> {line}")?;
+        }
+
+        for message in messages {
+            let MessagePayload::Claude(output) = message.content() else {
+                continue;
+            };
+            if output.data["type"].as_str() != Some("assistant") {
+                continue;
+            }
+            let Some(blocks) = output.data["message"]["content"].as_array() else {
+                continue;
+            };
+            for block in blocks {
+                match block["type"].as_str() {
+                    Some("text") => {
+                        if let Some(text) = block["text"].as_str() {
+                            writeln!(out)?;
+                            writeln!(out, "{}", text.trim_end())?;
+                        }
+                    }
+                    Some("tool_use") => {
+                        if let Some(name) = block["name"].as_str() {
+                            writeln!(out)?;
+                            writeln!(out, "*Used `{name}`*")?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if !turn.commit_ids.is_empty() {
+            writeln!(out)?;
+            let commits: Vec<_> = turn
+                .commit_ids
+                .iter()
+                .map(|id| format!("`{}`", &id[..id.len().min(7)]))
+                .collect();
+            writeln!(out, "Commits: {}", commits.join(", "))?;
+        }
+
+        if turn.changes.is_empty() {
+            continue;
+        }
+        writeln!(out)?;
+        writeln!(out, "### Changes")?;
+        for change in &turn.changes {
+            let change: but_core::TreeChange = change.clone().into();
+            writeln!(out)?;
+            match change.unified_diff(&repo, CONTEXT_LINES)? {
+                Some(diff) => {
+                    writeln!(out, "```diff")?;
+                    write!(out, "{}", diff.to_str_lossy())?;
+                    if !diff.ends_with(b"\n") {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "```")?;
+                }
+                None => writeln!(out, "`{}` changed", change.path.to_str_lossy())?,
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Collect the turns of the session along with the messages that followed each prompt.
+fn collect(ctx: &mut Context, session_id: Uuid) -> Result<Vec<(Turn, Vec<ClaudeMessage>)>> {
+    let mut turns = split_into_turns(db::list_messages_by_session(ctx, session_id)?);
+
+    let snapshot_ids: Vec<_> = turns
+        .iter()
+        .map(|(turn, _)| turn.snapshot_id.clone())
+        .collect();
+    let mut current_tree = None;
+    for (index, (turn, _)) in turns.iter_mut().enumerate() {
+        let Some(before) = &turn.snapshot_id else {
+            continue;
+        };
+        let after = match snapshot_ids[index + 1..].iter().flatten().next() {
+            Some(after) => snapshot_worktree(ctx, after)?,
+            None => match current_tree {
+                Some(tree) => tree,
+                None => *current_tree.insert(current_worktree(ctx)?),
+            },
+        };
+        let before = snapshot_worktree(ctx, before)?;
+        let repo = ctx.repo.get()?;
+        turn.changes = but_core::diff::tree_changes(&repo, Some(before), after)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+    }
+    Ok(turns)
+}
+
+/// Like [`list()`], but without computing the changes of each turn.
+fn list_without_changes(ctx: &mut Context, session_id: Uuid) -> Result<Vec<Turn>> {
+    Ok(
+        split_into_turns(db::list_messages_by_session(ctx, session_id)?)
+            .into_iter()
+            .map(|(turn, _)| turn)
+            .collect(),
+    )
+}
+
+/// Split `messages` of a session at each prompt of the user. Messages before the first prompt are dropped.
+fn split_into_turns(messages: Vec<ClaudeMessage>) -> Vec<(Turn, Vec<ClaudeMessage>)> {
+    let mut turns: Vec<(Turn, Vec<ClaudeMessage>)> = Vec::new();
+    for message in messages {
+        match message.content() {
+            MessagePayload::User(input) => {
+                let turn = Turn {
+                    message_id: message.id,
+                    created_at: message.created_at(),
+                    prompt: input.message.clone(),
+                    snapshot_id: input.snapshot_id.clone(),
+                    commit_ids: Vec::new(),
+                    changes: Vec::new(),
+                };
+                turns.push((turn, Vec::new()));
+            }
+            payload => {
+                let Some((turn, messages)) = turns.last_mut() else {
+                    continue;
+                };
+                if let MessagePayload::GitButler(GitButlerUpdate::CommitCreated(details)) = payload
+                {
+                    turn.commit_ids
+                        .extend(details.commit_ids.iter().flatten().cloned());
+                }
+                messages.push(message);
+            }
+        }
+    }
+    turns
+}
+
+/// The tree of the worktree as captured by the snapshot with `snapshot_id`.
+fn snapshot_worktree(ctx: &Context, snapshot_id: &str) -> Result<gix::ObjectId> {
+    ctx.snapshot_workspace_tree(gix::ObjectId::from_hex(snapshot_id.as_bytes())?)
+}
+
+/// The tree of the worktree in its current state.
+fn current_worktree(ctx: &Context) -> Result<gix::ObjectId> {
+    let guard = ctx.exclusive_worktree_access();
+    let snapshot_tree = ctx.prepare_snapshot(guard.read_permission())?;
+    if snapshot_tree.is_zero() {
+        bail!("Snapshots are suppressed, so the current state of the worktree can't be captured");
+    }
+    let repo = ctx.repo.get()?;
+    let worktree = repo
+        .find_tree(snapshot_tree.to_gix())?
+        .find_entry("worktree")
+        .context("Failed to find worktree tree in snapshot")?
+        .object_id();
+    Ok(worktree)
+}
+
+#[cfg(test)]
+mod tests {
+    use gitbutler_stack::VirtualBranchesHandle;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ClaudeOutput, CommitCreatedDetails, UserInput};
+
+    fn message(payload: MessagePayload) -> ClaudeMessage {
+        ClaudeMessage {
+            id: Uuid::new_v4(),
+            session_id: Uuid::nil(),
+            created_at: chrono::Utc::now().naive_utc(),
+            payload,
+        }
+    }
+
+    fn prompt(text: &str, snapshot_id: Option<&str>) -> ClaudeMessage {
+        message(MessagePayload::User(UserInput {
+            message: text.into(),
+            attachments: None,
+            snapshot_id: snapshot_id.map(Into::into),
+        }))
+    }
+
+    #[test]
+    fn messages_are_split_at_each_prompt() {
+        let messages = vec![
+            message(MessagePayload::Claude(ClaudeOutput {
+                data: json!({"type": "system"}),
+            })),
+            prompt("first", Some("1111111111111111111111111111111111111111")),
+            message(MessagePayload::Claude(ClaudeOutput {
+                data: json!({"type": "assistant"}),
+            })),
+            message(MessagePayload::GitButler(GitButlerUpdate::CommitCreated(
+                CommitCreatedDetails {
+                    stack_id: None,
+                    branch_name: None,
+                    commit_ids: Some(vec!["abc".into(), "def".into()]),
+                },
+            ))),
+            prompt("second", None),
+        ];
+
+        let turns = split_into_turns(messages);
+        assert_eq!(
+            turns.len(),
+            2,
+            "the message before the first prompt is dropped"
+        );
+        let (first, first_messages) = &turns[0];
+        assert_eq!(first.prompt, "first");
+        assert_eq!(
+            first.snapshot_id.as_deref(),
+            Some("1111111111111111111111111111111111111111")
+        );
+        assert_eq!(first.commit_ids, ["abc", "def"]);
+        assert_eq!(first_messages.len(), 2);
+        let (second, second_messages) = &turns[1];
+        assert_eq!(second.prompt, "second");
+        assert_eq!(second.snapshot_id, None);
+        assert!(second.commit_ids.is_empty());
+        assert!(second_messages.is_empty());
+    }
+
+    #[test]
+    fn turns_have_their_own_changes_and_can_be_restored() -> Result<()> {
+        let (repo, _tmp) = but_testsupport::writable_scenario("stacked-branches");
+        let mut ctx = Context::from_repo(repo)?;
+        ctx.settings.feature_flags.cv3 = false;
+        {
+            let guard = ctx.shared_worktree_access();
+            let meta = ctx.legacy_meta(guard.read_permission())?;
+            meta.write_reconciled(&*ctx.repo.get()?)?;
+        }
+        let stack_id = VirtualBranchesHandle::new(ctx.project_data_dir())
+            .list_stacks_in_workspace()?
+            .first()
+            .expect("the scenario has one stack")
+            .id;
+        let workdir = ctx.workdir()?.expect("non-bare");
+        let session_id = Uuid::new_v4();
+        db::save_new_session(&mut ctx, session_id)?;
+
+        let first = start_turn(&mut ctx, session_id, stack_id, "add qux")?;
+        std::fs::write(workdir.join("qux.txt"), "qux\n")?;
+        start_turn(&mut ctx, session_id, stack_id, "change file")?;
+        std::fs::write(workdir.join("file.txt"), "changed\n")?;
+
+        let turns = list(&mut ctx, session_id)?;
+        let changed_paths: Vec<Vec<_>> = turns
+            .iter()
+            .map(|turn| {
+                turn.changes
+                    .iter()
+                    .map(|change| change.path_bytes.to_string())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            changed_paths,
+            [["qux.txt"], ["file.txt"]],
+            "each turn only has the changes it made"
+        );
+
+        let markdown = export_markdown(&mut ctx, session_id)?;
+        for expected in ["## Turn 1", "> add qux", "+qux", "## Turn 2", "+changed"] {
+            assert!(
+                markdown.contains(expected),
+                "{expected:?} is missing in:\n{markdown}"
+            );
+        }
+
+        restore_before(&mut ctx, session_id, first)?;
+        assert!(
+            !workdir.join("qux.txt").exists(),
+            "the changes of the first turn are rolled back"
+        );
+        assert_eq!(
+            std::fs::read_to_string(workdir.join("file.txt"))?,
+            "initial content\n",
+            "along with the ones of the turns that followed it"
+        );
+        Ok(())
+    }
+
+    /// Snapshot the workspace and record the prompt of a turn like the bridge does, and return the ID of its message.
+    fn start_turn(
+        ctx: &mut Context,
+        session_id: Uuid,
+        stack_id: StackId,
+        prompt: &str,
+    ) -> Result<Uuid> {
+        let snapshot_id = snapshot_before_turn(ctx, session_id, stack_id, prompt);
+        assert!(snapshot_id.is_some(), "the turn was snapshotted");
+        let message = db::save_new_message(
+            ctx,
+            session_id,
+            MessagePayload::User(UserInput {
+                message: prompt.into(),
+                attachments: None,
+                snapshot_id,
+            }),
+        )?;
+        Ok(message.id)
+    }
+}
@@ -0,0 +1,566 @@
+//! Token usage and cost accounting for Claude sessions.
+//!
+//! Claude reports the tokens consumed by each message it produces, both in its `stream-json` output
+//! and in the transcript it writes to disk. We persist this usage per message, attributed to the
+//! session and the stack it worked on, so the cost of agent work can be summed up per branch.
+//!
+//! The cost is estimated from the token counts using the list prices of the model that produced
+//! the message, as Claude only reports the total cost of a run once it is finished, which is too
+//! late to enforce a budget.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db;
+
+/// The number of tokens a single message consumed, as reported by Claude.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+/// The usage of a single message Claude produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageUsage {
+    /// The ID the Claude API assigned to the message.
+    pub message_id: String,
+    /// The model that produced the message, if known.
+    pub model: Option<String>,
+    pub tokens: TokenUsage,
+}
+
+impl MessageUsage {
+    /// Extract the usage from an `assistant` message `event` of the Claude Code `stream-json` output.
+    /// Returns `None` for all other events, or if the message doesn't report its usage.
+    pub fn from_stream_event(event: &serde_json::Value) -> Option<Self> {
+        if event["type"].as_str() != Some("assistant") {
+            return None;
+        }
+        let message = &event["message"];
+        Self::from_parts(
+            message["id"].as_str(),
+            message["model"].as_str(),
+            message.get("usage"),
+        )
+    }
+
+    pub(crate) fn from_parts(
+        message_id: Option<&str>,
+        model: Option<&str>,
+        usage: Option<&serde_json::Value>,
+    ) -> Option<Self> {
+        let tokens = serde_json::from_value(usage?.clone()).ok()?;
+        Some(MessageUsage {
+            message_id: message_id?.to_owned(),
+            model: model.map(ToOwned::to_owned),
+            tokens,
+        })
+    }
+
+    /// Estimate the cost of this message in USD based on the list prices of its model.
+    pub fn estimated_cost_usd(&self) -> f64 {
+        let price = Price::for_model(self.model.as_deref());
+        let tokens = &self.tokens;
+        (tokens.input_tokens as f64 * price.input
+            + tokens.output_tokens as f64 * price.output
+            + tokens.cache_creation_input_tokens as f64 * price.input * CACHE_WRITE_FACTOR
+            + tokens.cache_read_input_tokens as f64 * price.input * CACHE_READ_FACTOR)
+            / 1_000_000.0
+    }
+}
+
+/// Prices in USD per million tokens.
+#[derive(Debug, Clone, Copy)]
+struct Price {
+    input: f64,
+    output: f64,
+}
+
+/// Writing to the prompt cache costs more than regular input tokens.
+const CACHE_WRITE_FACTOR: f64 = 1.25;
+/// Reading from the prompt cache costs a fraction of regular input tokens.
+const CACHE_READ_FACTOR: f64 = 0.1;
+
+/// List prices by model name fragment, the first fragment contained in the model name wins.
+const PRICES: &[(&str, Price)] = &[
+    ("opus-4-5", Price::new(5.0, 25.0)),
+    ("opus", Price::new(15.0, 75.0)),
+    ("sonnet", Price::new(3.0, 15.0)),
+    ("haiku-4-5", Price::new(1.0, 5.0)),
+    ("haiku", Price::new(0.8, 4.0)),
+];
+
+impl Price {
+    const fn new(input: f64, output: f64) -> Self {
+        Price { input, output }
+    }
+
+    /// Unknown models are assumed to be priced like Sonnet, the default model of Claude Code.
+    fn for_model(model: Option<&str>) -> Self {
+        model
+            .and_then(|model| {
+                PRICES
+                    .iter()
+                    .find(|(fragment, _)| model.contains(fragment))
+                    .map(|(_, price)| *price)
+            })
+            .unwrap_or(Price::new(3.0, 15.0))
+    }
+}
+
+/// The usage of a message as persisted in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageRecord {
+    pub message_id: String,
+    /// The stable ID of the session the message belongs to.
+    pub session_id: Uuid,
+    /// The stack the session was working on, if known.
+    pub stack_id: Option<StackId>,
+    pub model: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub tokens: TokenUsage,
+    pub cost_usd: f64,
+}
+
+/// Accumulated usage over any number of messages.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Usage {
+    /// The number of messages the usage was accumulated from.
+    pub messages: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// The estimated cost in USD.
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    fn add(&mut self, record: &UsageRecord) {
+        self.messages += 1;
+        self.input_tokens += record.tokens.input_tokens;
+        self.output_tokens += record.tokens.output_tokens;
+        self.cache_creation_input_tokens += record.tokens.cache_creation_input_tokens;
+        self.cache_read_input_tokens += record.tokens.cache_read_input_tokens;
+        self.cost_usd += record.cost_usd;
+    }
+}
+
+impl<'a> FromIterator<&'a UsageRecord> for Usage {
+    fn from_iter<T: IntoIterator<Item = &'a UsageRecord>>(iter: T) -> Self {
+        let mut usage = Usage::default();
+        for record in iter {
+            usage.add(record);
+        }
+        usage
+    }
+}
+
+/// The usage of a single session.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    #[schemars(with = "String")]
+    pub session_id: Uuid,
+    /// The stack the session most recently worked on, if known.
+    #[schemars(with = "Option<String>")]
+    pub stack_id: Option<StackId>,
+    /// The budget of the session in USD, if one is set.
+    pub budget_usd: Option<f64>,
+    pub usage: Usage,
+}
+
+/// The usage of all sessions that worked on a stack.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StackUsage {
+    #[schemars(with = "String")]
+    pub stack_id: StackId,
+    pub usage: Usage,
+}
+
+/// The usage of all Claude sessions in a project.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    pub sessions: Vec<SessionUsage>,
+    pub stacks: Vec<StackUsage>,
+    pub total: Usage,
+}
+
+/// Information about a session that spent more than its budget.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetExceeded {
+    pub budget_usd: f64,
+    pub cost_usd: f64,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The session budget of ${:.2} was exceeded, ${:.2} have been spent",
+            self.budget_usd, self.cost_usd
+        )
+    }
+}
+
+/// Persist `usage` for the session with the stable `session_id` that worked on `stack_id`.
+pub fn record(
+    ctx: &mut Context,
+    session_id: Uuid,
+    stack_id: Option<StackId>,
+    usage: &MessageUsage,
+) -> Result<()> {
+    db::save_usage(
+        ctx,
+        UsageRecord {
+            message_id: usage.message_id.clone(),
+            session_id,
+            stack_id,
+            model: usage.model.clone(),
+            created_at: chrono::Utc::now().naive_utc(),
+            tokens: usage.tokens,
+            cost_usd: usage.estimated_cost_usd(),
+        },
+    )
+}
+
+/// Persist the usage of all messages in `transcript`, which belongs to the session with the stable `session_id`.
+pub fn record_transcript(
+    ctx: &mut Context,
+    session_id: Uuid,
+    transcript: &crate::Transcript,
+) -> Result<()> {
+    let stack_id = crate::rules::list_claude_assignment_rules(ctx)?
+        .into_iter()
+        .find(|rule| rule.session_id == session_id)
+        .map(|rule| rule.stack_id);
+    for usage in transcript.usage() {
+        record(ctx, session_id, stack_id, &usage)?;
+    }
+    Ok(())
+}
+
+/// Return the accumulated usage of the session with the stable `session_id`.
+pub fn session_usage(ctx: &mut Context, session_id: Uuid) -> Result<Usage> {
+    Ok(db::list_usage_by_session(ctx, session_id)?.iter().collect())
+}
+
+/// Return the usage of all sessions in the project, along with the usage per stack.
+pub fn report(ctx: &mut Context) -> Result<UsageReport> {
+    let records = db::list_usage(ctx)?;
+    let budgets: BTreeMap<_, _> = db::list_all_sessions(ctx)?
+        .into_iter()
+        .map(|session| (session.id, session.budget_usd()))
+        .collect();
+
+    let mut by_session = BTreeMap::<Uuid, Vec<&UsageRecord>>::new();
+    let mut by_stack = BTreeMap::<StackId, Vec<&UsageRecord>>::new();
+    for record in &records {
+        by_session
+            .entry(record.session_id)
+            .or_default()
+            .push(record);
+        if let Some(stack_id) = record.stack_id {
+            by_stack.entry(stack_id).or_default().push(record);
+        }
+    }
+
+    let mut sessions: Vec<_> = by_session
+        .into_iter()
+        .map(|(session_id, records)| SessionUsage {
+            session_id,
+            stack_id: records.iter().rev().find_map(|record| record.stack_id),
+            budget_usd: budgets.get(&session_id).copied().flatten(),
+            usage: records.into_iter().collect(),
+        })
+        .collect();
+    sessions.sort_by(|a, b| b.usage.cost_usd.total_cmp(&a.usage.cost_usd));
+    let mut stacks: Vec<_> = by_stack
+        .into_iter()
+        .map(|(stack_id, records)| StackUsage {
+            stack_id,
+            usage: records.into_iter().collect(),
+        })
+        .collect();
+    stacks.sort_by(|a, b| b.usage.cost_usd.total_cmp(&a.usage.cost_usd));
+
+    Ok(UsageReport {
+        sessions,
+        stacks,
+        total: records.iter().collect(),
+    })
+}
+
+/// Set the budget of the session with the stable `session_id` to `budget_usd`, or remove it if `None`.
+pub fn set_budget(ctx: &mut Context, session_id: Uuid, budget_usd: Option<f64>) -> Result<()> {
+    if let Some(budget) = budget_usd
+        && !(budget.is_finite() && budget > 0.0)
+    {
+        bail!("The budget must be a positive amount of USD, got {budget}");
+    }
+    if db::get_session_by_id(ctx, session_id)?.is_none() {
+        bail!("Could not find Claude session {session_id}");
+    }
+    db::set_session_budget(ctx, session_id, budget_usd)
+}
+
+/// Return information about the exceeded budget if the session with the stable `session_id` has a budget
+/// and spent at least as much.
+pub fn check_budget(ctx: &mut Context, session_id: Uuid) -> Result<Option<BudgetExceeded>> {
+    let Some(budget_usd) = db::get_session_by_id(ctx, session_id)?.and_then(|s| s.budget_usd())
+    else {
+        return Ok(None);
+    };
+    let cost_usd = session_usage(ctx, session_id)?.cost_usd;
+    Ok((cost_usd >= budget_usd).then_some(BudgetExceeded {
+        budget_usd,
+        cost_usd,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A context for an empty project, which doesn't need a workspace as usage is only kept in the database.
+    fn project() -> Result<(Context, tempfile::TempDir)> {
+        let (repo, tmp) = but_testsupport::writable_scenario("stacked-branches");
+        Ok((Context::from_repo(repo)?, tmp))
+    }
+
+    /// Save usage of `input_tokens` Sonnet tokens, worth $3 per million, `minutes` after a fixed point in time.
+    fn save(
+        ctx: &mut Context,
+        session_id: Uuid,
+        stack_id: StackId,
+        message_id: &str,
+        input_tokens: u64,
+        minutes: i64,
+    ) -> Result<()> {
+        let usage = MessageUsage {
+            message_id: message_id.into(),
+            model: Some("claude-sonnet-4-5".into()),
+            tokens: TokenUsage {
+                input_tokens,
+                ..Default::default()
+            },
+        };
+        db::save_usage(
+            ctx,
+            UsageRecord {
+                message_id: usage.message_id.clone(),
+                session_id,
+                stack_id: Some(stack_id),
+                model: usage.model.clone(),
+                created_at: chrono::DateTime::from_timestamp(1_700_000_000 + minutes * 60, 0)
+                    .unwrap()
+                    .naive_utc(),
+                tokens: usage.tokens,
+                cost_usd: usage.estimated_cost_usd(),
+            },
+        )
+    }
+
+    #[test]
+    fn report_aggregates_per_session_and_per_stack() -> Result<()> {
+        let (mut ctx, _tmp) = project()?;
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let (stack_a, stack_b) = (StackId::generate(), StackId::generate());
+        db::save_new_session(&mut ctx, first)?;
+        db::save_new_session(&mut ctx, second)?;
+        set_budget(&mut ctx, second, Some(20.0))?;
+
+        save(&mut ctx, first, stack_a, "msg_1", 1_000_000, 0)?;
+        save(&mut ctx, second, stack_a, "msg_2", 2_000_000, 1)?;
+        save(&mut ctx, first, stack_b, "msg_3", 500_000, 2)?;
+        save(&mut ctx, second, stack_a, "msg_4", 2_000_000, 3)?;
+        save(&mut ctx, second, stack_a, "msg_4", 3_000_000, 4)?;
+
+        let report = report(&mut ctx)?;
+        let sessions: Vec<_> = report
+            .sessions
+            .iter()
+            .map(|s| {
+                (
+                    s.session_id,
+                    s.stack_id,
+                    s.budget_usd,
+                    s.usage.messages,
+                    s.usage.cost_usd,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sessions,
+            [
+                (second, Some(stack_a), Some(20.0), 2, 15.0),
+                (first, Some(stack_b), None, 2, 4.5),
+            ],
+            "the most expensive session comes first, a message is only counted once with its latest usage, \
+             and sessions are attributed to the stack they worked on last"
+        );
+        let stacks: Vec<_> = report
+            .stacks
+            .iter()
+            .map(|s| (s.stack_id, s.usage.messages, s.usage.cost_usd))
+            .collect();
+        assert_eq!(
+            stacks,
+            [(stack_a, 3, 18.0), (stack_b, 1, 1.5)],
+            "stacks sum up the usage of all sessions that worked on them"
+        );
+        assert_eq!(report.total.messages, 4);
+        assert_eq!(report.total.input_tokens, 6_500_000);
+        assert_eq!(report.total.cost_usd, 19.5);
+        Ok(())
+    }
+
+    #[test]
+    fn budgets_are_exceeded_once_the_session_spent_them() -> Result<()> {
+        let (mut ctx, _tmp) = project()?;
+        let session_id = Uuid::new_v4();
+        let stack_id = StackId::generate();
+        assert!(
+            set_budget(&mut ctx, session_id, Some(1.0)).is_err(),
+            "unknown sessions can't have a budget"
+        );
+        db::save_new_session(&mut ctx, session_id)?;
+        for invalid in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(set_budget(&mut ctx, session_id, Some(invalid)).is_err());
+        }
+
+        let million_input_tokens = |message_id: &str| MessageUsage {
+            message_id: message_id.into(),
+            model: Some("claude-sonnet-4-5".into()),
+            tokens: TokenUsage {
+                input_tokens: 1_000_000,
+                ..Default::default()
+            },
+        };
+        record(
+            &mut ctx,
+            session_id,
+            Some(stack_id),
+            &million_input_tokens("msg_1"),
+        )?;
+        assert_eq!(
+            check_budget(&mut ctx, session_id)?,
+            None,
+            "sessions without budget can spend as much as they like"
+        );
+
+        set_budget(&mut ctx, session_id, Some(5.0))?;
+        assert_eq!(
+            check_budget(&mut ctx, session_id)?,
+            None,
+            "$3 of $5 were spent"
+        );
+
+        record(
+            &mut ctx,
+            session_id,
+            Some(stack_id),
+            &million_input_tokens("msg_2"),
+        )?;
+        assert_eq!(
+            check_budget(&mut ctx, session_id)?,
+            Some(BudgetExceeded {
+                budget_usd: 5.0,
+                cost_usd: 6.0
+            })
+        );
+
+        set_budget(&mut ctx, session_id, None)?;
+        assert_eq!(
+            check_budget(&mut ctx, session_id)?,
+            None,
+            "the budget was removed"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn usage_from_assistant_stream_event() {
+        let event = serde_json::json!({
+            "type": "assistant",
+            "session_id": "7b3f0d1e-0000-0000-0000-000000000000",
+            "message": {
+                "id": "msg_01",
+                "model": "claude-sonnet-4-5-20250929",
+                "usage": {
+                    "input_tokens": 1000,
+                    "output_tokens": 2000,
+                    "cache_creation_input_tokens": 4000,
+                    "cache_read_input_tokens": 10000,
+                    "service_tier": "standard"
+                }
+            }
+        });
+        let usage = MessageUsage::from_stream_event(&event).expect("usage is reported");
+        assert_eq!(
+            usage,
+            MessageUsage {
+                message_id: "msg_01".into(),
+                model: Some("claude-sonnet-4-5-20250929".into()),
+                tokens: TokenUsage {
+                    input_tokens: 1000,
+                    output_tokens: 2000,
+                    cache_creation_input_tokens: 4000,
+                    cache_read_input_tokens: 10000,
+                },
+            }
+        );
+        // 1000 * 3 + 2000 * 15 + 4000 * 3.75 + 10000 * 0.3 per million tokens.
+        assert!((usage.estimated_cost_usd() - 0.051).abs() < 1e-9);
+    }
+
+    #[test]
+    fn other_stream_events_have_no_usage() {
+        let result = serde_json::json!({
+            "type": "result",
+            "total_cost_usd": 0.5,
+            "usage": { "input_tokens": 1, "output_tokens": 1 }
+        });
+        assert_eq!(MessageUsage::from_stream_event(&result), None);
+
+        let without_usage = serde_json::json!({
+            "type": "assistant",
+            "message": { "id": "msg_01" }
+        });
+        assert_eq!(MessageUsage::from_stream_event(&without_usage), None);
+    }
+
+    #[test]
+    fn prices_depend_on_the_model() {
+        let cost = |model: Option<&str>| {
+            MessageUsage {
+                message_id: "msg".into(),
+                model: model.map(Into::into),
+                tokens: TokenUsage {
+                    output_tokens: 1_000_000,
+                    ..Default::default()
+                },
+            }
+            .estimated_cost_usd()
+        };
+        assert_eq!(cost(Some("claude-opus-4-1-20250805")), 75.0);
+        assert_eq!(cost(Some("claude-opus-4-5-20251101")), 25.0);
+        assert_eq!(cost(Some("claude-haiku-4-5-20251001")), 5.0);
+        assert_eq!(cost(Some("claude-3-5-haiku-20241022")), 4.0);
+        assert_eq!(cost(None), 15.0, "unknown models are priced like Sonnet");
+    }
+}
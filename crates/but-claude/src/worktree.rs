@@ -0,0 +1,429 @@
+//! Running Claude sessions in worktrees of their own.
+//!
+//! When [`but_settings::app_settings::Claude::isolate_sessions_in_worktrees`] is enabled, the session
+//! of each stack runs in a linked worktree that is checked out from the top branch of the stack,
+//! instead of in the main worktree. Sessions then never share files, and thus never wait for each
+//! other's file locks. Once a turn ends, the changes in the worktree are integrated into the branch,
+//! as long as that doesn't cause conflicts.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use but_worktrees::{
+    WorktreeId,
+    integrate::{WorktreeIntegrationStatus, worktree_integrate, worktree_integration_status},
+};
+use gix::bstr::ByteSlice;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{ClaudeSession, Transcript, db};
+
+/// Set for Claude processes that run in a worktree of their own. Hooks do nothing for them,
+/// as there are no other sessions to share files with.
+pub(crate) const ISOLATED_WORKTREE_ENV: &str = "GITBUTLER_ISOLATED_WORKTREE";
+
+/// Returns true if the current process was started by a Claude session that runs in a worktree of its own.
+pub(crate) fn in_isolated_worktree() -> bool {
+    std::env::var(ISOLATED_WORKTREE_ENV).is_ok_and(|value| value == "1")
+}
+
+/// The worktree the session of a stack runs in.
+#[derive(Debug, Clone)]
+pub(crate) struct SessionWorktree {
+    pub id: WorktreeId,
+    /// The stack whose session runs in the worktree.
+    pub stack_id: StackId,
+    /// The canonicalized path of the worktree, the directory Claude runs in.
+    pub path: PathBuf,
+    /// The branch the worktree was checked out from, and that its changes are integrated into.
+    pub target: gix::refs::FullName,
+    /// The commit the worktree was checked out at.
+    pub base: Option<gix::ObjectId>,
+}
+
+/// The result of integrating the changes of a [`SessionWorktree`] into its branch.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeIntegration {
+    pub worktree_id: WorktreeId,
+    pub branch_name: String,
+    /// The status of the worktree before it was integrated.
+    pub status: WorktreeIntegrationStatus,
+    /// Whether the changes were integrated, in which case the worktree was removed.
+    pub integrated: bool,
+}
+
+/// A worktree that the session of a stack ran in, and whose changes weren't integrated into its branch yet.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingWorktree {
+    pub stack_id: StackId,
+    pub worktree_id: WorktreeId,
+    /// The path of the worktree.
+    pub path: PathBuf,
+    /// The branch the changes will be integrated into, if known.
+    pub branch_name: Option<String>,
+    /// Whether the worktree has uncommitted changes or commits of its own.
+    pub has_changes: bool,
+}
+
+/// Return the worktree the session of the stack with `stack_id` ran in last, if it still exists,
+/// or create a new worktree from the top branch of the stack.
+/// The worktree is remembered in the database until its changes are integrated.
+pub(crate) fn checkout(ctx: &mut Context, stack_id: StackId) -> Result<SessionWorktree> {
+    let target = top_branch(ctx, stack_id)?;
+    let id = db::get_worktree(ctx, stack_id)?;
+    let worktree = {
+        let guard = ctx.exclusive_worktree_access();
+        let existing = match id {
+            Some(id) => but_worktrees::list::worktree_list(ctx, guard.read_permission())?
+                .entries
+                .into_iter()
+                .find(|worktree| worktree.id == id && worktree.path.exists()),
+            None => None,
+        };
+        match existing {
+            Some(worktree) => worktree,
+            None => {
+                but_worktrees::new::worktree_new(ctx, guard.read_permission(), target.as_ref())?
+                    .created
+            }
+        }
+    };
+    db::save_worktree(ctx, stack_id, &worktree.id)?;
+    Ok(SessionWorktree {
+        id: worktree.id,
+        stack_id,
+        path: worktree.path,
+        target,
+        base: worktree.base,
+    })
+}
+
+/// List the worktrees of sessions whose changes weren't integrated yet.
+/// Worktrees that were removed in the meantime are forgotten.
+pub fn list_pending(ctx: &mut Context) -> Result<Vec<PendingWorktree>> {
+    let remembered = db::list_worktrees(ctx)?;
+    if remembered.is_empty() {
+        return Ok(Vec::new());
+    }
+    let existing = {
+        let guard = ctx.exclusive_worktree_access();
+        but_worktrees::list::worktree_list(ctx, guard.read_permission())?.entries
+    };
+
+    let mut pending = Vec::new();
+    for (stack_id, worktree_id) in remembered {
+        let Some(worktree) = existing
+            .iter()
+            .find(|worktree| worktree.id == worktree_id && worktree.path.exists())
+        else {
+            db::delete_worktree(ctx, stack_id)?;
+            continue;
+        };
+        let branch = match &worktree.created_from_ref {
+            Some(name) => Some(name.clone()),
+            None => top_branch(ctx, stack_id).ok(),
+        };
+        pending.push(PendingWorktree {
+            stack_id,
+            worktree_id,
+            branch_name: branch.map(|name| name.shorten().to_str_lossy().into_owned()),
+            has_changes: has_changes(&worktree.path, worktree.base)?,
+            path: worktree.path.clone(),
+        });
+    }
+    Ok(pending)
+}
+
+/// Determine if the changes in `worktree` can be integrated into its branch, and if `apply` is true,
+/// integrate them if that can be done without any conflicts.
+/// Return `None` if there are no changes to integrate.
+pub(crate) fn integrate(
+    ctx: &mut Context,
+    worktree: &SessionWorktree,
+    apply: bool,
+) -> Result<Option<WorktreeIntegration>> {
+    if !has_changes(&worktree.path, worktree.base)? {
+        return Ok(None);
+    }
+
+    let mut guard = ctx.exclusive_worktree_access();
+    let status = worktree_integration_status(
+        ctx,
+        guard.read_permission(),
+        &worktree.id,
+        worktree.target.as_ref(),
+    )?;
+    let integrated = apply
+        && matches!(
+            status,
+            WorktreeIntegrationStatus::Integratable {
+                cherry_pick_conflicts: false,
+                commits_above_conflict: false,
+                working_dir_conflicts: false,
+            }
+        );
+    if integrated {
+        worktree_integrate(
+            ctx,
+            guard.write_permission(),
+            &worktree.id,
+            worktree.target.as_ref(),
+        )?;
+        db::delete_worktree(ctx, worktree.stack_id)?;
+    }
+    Ok(Some(WorktreeIntegration {
+        worktree_id: worktree.id.clone(),
+        branch_name: worktree.target.shorten().to_str_lossy().into_owned(),
+        status,
+        integrated,
+    }))
+}
+
+/// Copy the transcripts of `session` from the Claude project at `from` to the one at `to`
+/// if they are newer, so the session can be resumed in `to`.
+pub(crate) async fn copy_transcripts(
+    session: &ClaudeSession,
+    from: &Path,
+    to: &Path,
+) -> Result<()> {
+    for session_id in &session.session_ids {
+        copy_if_newer(
+            &Transcript::get_transcript_path(from, *session_id)?,
+            &Transcript::get_transcript_path(to, *session_id)?,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Copy `source` to `destination` if it exists and was modified after `destination`.
+async fn copy_if_newer(source: &Path, destination: &Path) -> Result<()> {
+    let Ok(source_meta) = fs::metadata(source).await else {
+        return Ok(());
+    };
+    let is_newer = match fs::metadata(destination).await {
+        Ok(destination_meta) => source_meta.modified()? > destination_meta.modified()?,
+        Err(_) => true,
+    };
+    if !is_newer {
+        return Ok(());
+    }
+    if let Some(dir) = destination.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    fs::copy(source, destination).await?;
+    Ok(())
+}
+
+/// The instructions for Claude when it runs in `worktree`.
+pub(crate) fn system_prompt(worktree: &SessionWorktree) -> String {
+    format!(
+        "<worktree-info>
+This session runs in an isolated git worktree at `{path}`, which was checked out from the branch `{branch}`.
+Only read and modify files inside of this directory.
+
+Do not create commits, neither with git nor with the GitButler CLI, which doesn't work in this worktree.
+When you are done, all changes in this worktree are integrated into `{branch}` for you.
+</worktree-info>",
+        path = worktree.path.display(),
+        branch = worktree.target.shorten(),
+    )
+}
+
+/// The reference of the top-most branch of the stack with `stack_id`.
+fn top_branch(ctx: &Context, stack_id: StackId) -> Result<gix::refs::FullName> {
+    let branches = but_workspace::legacy::stack_branches(stack_id, ctx)?;
+    let branch = branches
+        .first()
+        .with_context(|| format!("Stack {stack_id} has no branches to check out"))?;
+    Ok(format!("refs/heads/{}", branch.name.to_str_lossy()).try_into()?)
+}
+
+/// Returns true if the worktree at `path` has uncommitted changes, or commits on top of `base`.
+fn has_changes(path: &Path, base: Option<gix::ObjectId>) -> Result<bool> {
+    let repo = gix::open(path)?;
+    let head_id = repo.head_id().ok().map(|id| id.detach());
+    if head_id.is_none() || head_id != base {
+        return Ok(true);
+    }
+    let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(path.to_owned())?.changes;
+    Ok(!changes.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use but_testsupport::invoke_bash_at_dir;
+    use gitbutler_stack::VirtualBranchesHandle;
+
+    use super::*;
+
+    /// A context for the `stacked-branches` scenario, where `feature-b` is stacked on `feature-a`,
+    /// along with the ID of that stack.
+    fn stacked_branches() -> Result<(Context, StackId, tempfile::TempDir)> {
+        let (repo, tmp) = but_testsupport::writable_scenario("stacked-branches");
+        let mut ctx = Context::from_repo(repo)?;
+        ctx.settings.feature_flags.cv3 = false;
+        {
+            let guard = ctx.shared_worktree_access();
+            let meta = ctx.legacy_meta(guard.read_permission())?;
+            meta.write_reconciled(&*ctx.repo.get()?)?;
+        }
+        let stack_id = VirtualBranchesHandle::new(ctx.project_data_dir())
+            .list_stacks_in_workspace()?
+            .first()
+            .expect("the scenario has one stack")
+            .id;
+        Ok((ctx, stack_id, tmp))
+    }
+
+    #[test]
+    fn checkout_reuses_the_worktree_of_the_stack() -> Result<()> {
+        let (mut ctx, stack_id, _tmp) = stacked_branches()?;
+
+        let worktree = checkout(&mut ctx, stack_id)?;
+        assert!(worktree.path.is_dir());
+        assert_eq!(worktree.target.as_bstr(), "refs/heads/feature-b");
+        assert_eq!(
+            db::get_worktree(&mut ctx, stack_id)?,
+            Some(worktree.id.clone())
+        );
+
+        let again = checkout(&mut ctx, stack_id)?;
+        assert_eq!(again.id, worktree.id, "the worktree is remembered");
+
+        std::fs::remove_dir_all(&worktree.path)?;
+        let replacement = checkout(&mut ctx, stack_id)?;
+        assert_ne!(
+            replacement.id, worktree.id,
+            "a worktree that was removed is replaced"
+        );
+        assert_eq!(db::get_worktree(&mut ctx, stack_id)?, Some(replacement.id));
+        Ok(())
+    }
+
+    #[test]
+    fn changes_are_integrated_into_the_top_branch() -> Result<()> {
+        let (mut ctx, stack_id, _tmp) = stacked_branches()?;
+        let worktree = checkout(&mut ctx, stack_id)?;
+        assert!(
+            integrate(&mut ctx, &worktree, true)?.is_none(),
+            "there is nothing to integrate yet"
+        );
+
+        invoke_bash_at_dir(r#"echo "qux" > qux.txt"#, &worktree.path);
+        let pending = list_pending(&mut ctx)?;
+        assert_eq!(pending.len(), 1);
+        assert!(pending[0].has_changes);
+        assert_eq!(pending[0].branch_name.as_deref(), Some("feature-b"));
+
+        let integration = integrate(&mut ctx, &worktree, true)?.expect("there are changes");
+        assert!(integration.integrated);
+        assert_eq!(integration.branch_name, "feature-b");
+        assert!(!worktree.path.exists(), "integrated worktrees are removed");
+        assert_eq!(db::get_worktree(&mut ctx, stack_id)?, None);
+        assert!(list_pending(&mut ctx)?.is_empty());
+
+        let repo = ctx.repo.get()?;
+        let tree = repo
+            .find_reference("refs/heads/feature-b")?
+            .peel_to_commit()?
+            .tree()?;
+        assert!(tree.find_entry("qux.txt").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn conflicting_changes_are_kept_in_the_worktree() -> Result<()> {
+        let (mut ctx, stack_id, _tmp) = stacked_branches()?;
+        let main_worktree_dir = ctx.workdir()?.expect("non-bare");
+        let worktree = checkout(&mut ctx, stack_id)?;
+
+        invoke_bash_at_dir(r#"echo "qux" > foo.txt"#, &main_worktree_dir);
+        invoke_bash_at_dir(r#"echo "foo" > foo.txt"#, &worktree.path);
+
+        let integration = integrate(&mut ctx, &worktree, true)?.expect("there are changes");
+        assert_eq!(
+            integration.status,
+            WorktreeIntegrationStatus::Integratable {
+                cherry_pick_conflicts: false,
+                commits_above_conflict: false,
+                working_dir_conflicts: true,
+            }
+        );
+        assert!(!integration.integrated);
+        assert!(worktree.path.exists());
+        assert_eq!(
+            std::fs::read_to_string(worktree.path.join("foo.txt"))?,
+            "foo\n",
+            "the changes remain in the worktree"
+        );
+        assert_eq!(
+            db::get_worktree(&mut ctx, stack_id)?,
+            Some(worktree.id.clone()),
+            "the next turn continues in the same worktree"
+        );
+        assert_eq!(checkout(&mut ctx, stack_id)?.id, worktree.id);
+        Ok(())
+    }
+
+    #[test]
+    fn changes_are_not_integrated_unless_applied() -> Result<()> {
+        let (mut ctx, stack_id, _tmp) = stacked_branches()?;
+        let worktree = checkout(&mut ctx, stack_id)?;
+        invoke_bash_at_dir(r#"echo "qux" > qux.txt"#, &worktree.path);
+
+        let integration = integrate(&mut ctx, &worktree, false)?.expect("there are changes");
+        assert!(!integration.integrated);
+        assert!(worktree.path.exists());
+        assert_eq!(list_pending(&mut ctx)?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transcripts_are_copied_if_they_are_newer() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let source = tmp.path().join("from/session.jsonl");
+        let destination = tmp.path().join("to/session.jsonl");
+
+        copy_if_newer(&source, &destination).await?;
+        assert!(!destination.exists(), "missing transcripts are skipped");
+
+        std::fs::create_dir_all(source.parent().unwrap())?;
+        std::fs::write(&source, "first")?;
+        copy_if_newer(&source, &destination).await?;
+        assert_eq!(std::fs::read_to_string(&destination)?, "first");
+
+        let now = SystemTime::now();
+        std::fs::write(&destination, "continued elsewhere")?;
+        std::fs::File::options()
+            .write(true)
+            .open(&destination)?
+            .set_modified(now)?;
+        std::fs::File::options()
+            .write(true)
+            .open(&source)?
+            .set_modified(now - Duration::from_secs(60))?;
+        copy_if_newer(&source, &destination).await?;
+        assert_eq!(
+            std::fs::read_to_string(&destination)?,
+            "continued elsewhere",
+            "newer transcripts are kept"
+        );
+
+        std::fs::write(&source, "second")?;
+        std::fs::File::options()
+            .write(true)
+            .open(&source)?
+            .set_modified(now + Duration::from_secs(60))?;
+        copy_if_newer(&source, &destination).await?;
+        assert_eq!(std::fs::read_to_string(&destination)?, "second");
+        Ok(())
+    }
+}
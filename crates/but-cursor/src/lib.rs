@@ -1,12 +1,8 @@
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::path::PathBuf;
 
-use but_action::{ActionHandler, OpenAiProvider, Source, reword::CommitEvent};
+use but_action::Source;
 use but_ctx::Context;
-use but_hunk_assignment::HunkAssignmentRequest;
-use but_meta::VirtualBranchesTomlMetadata;
-use but_workspace::legacy::StacksFilter;
 use gitbutler_project::Project;
-use gitbutler_stack::VirtualBranchesHandle;
 use gix::diff::blob::{
     Algorithm, UnifiedDiff,
     unified_diff::{ConsumeBinaryHunk, ContextSize},
@@ -147,49 +143,13 @@ pub async fn handle_after_edit(read: impl std::io::Read) -> anyhow::Result<Curso
         repo.workdir()
             .ok_or(anyhow::anyhow!("No worktree found for repo"))?,
     )?;
-    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-    let meta = VirtualBranchesTomlMetadata::from_path(
-        ctx.project_data_dir().join("virtual_branches.toml"),
+    let ctx = &mut Context::new_from_legacy_project(project)?;
+    but_claude::hooks::assign_edit_to_session(
+        ctx,
+        &input.conversation_id,
+        &input.file_path,
+        &hook_headers,
     )?;
-    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
-
-    let stacks = but_workspace::legacy::stacks_v3(&repo, &meta, StacksFilter::default(), None)?;
-    let stack_id =
-        but_claude::hooks::get_or_create_session(ctx, &input.conversation_id, stacks, vb_state)?;
-
-    let changes =
-        but_core::diff::ui::worktree_changes_by_worktree_dir(project.worktree_dir()?.into())?
-            .changes;
-    let (assignments, _assignments_error) =
-        but_hunk_assignment::assignments_with_fallback(ctx, true, Some(changes.clone()), None)?;
-
-    let assignment_reqs: Vec<HunkAssignmentRequest> = assignments
-        .into_iter()
-        .filter(|a| a.stack_id.is_none())
-        .filter(|a| {
-            // If the hook_headers is empty, we probably created a file.
-            if hook_headers.is_empty() {
-                a.path.to_lowercase() == input.file_path.to_lowercase()
-            } else if a.path.to_lowercase() == input.file_path.to_lowercase() {
-                if let Some(a) = a.hunk_header {
-                    hook_headers
-                        .iter()
-                        .any(|h| h.new_range().intersects(a.new_range()))
-                } else {
-                    true // If no header is present, then the whole file is considered, in which case intersection is true
-                }
-            } else {
-                false
-            }
-        })
-        .map(|a| HunkAssignmentRequest {
-            hunk_header: a.hunk_header,
-            path_bytes: a.path_bytes,
-            stack_id: Some(stack_id),
-        })
-        .collect();
-
-    let _rejections = but_hunk_assignment::assign(ctx, assignment_reqs, None)?;
 
     Ok(CursorHookOutput::default())
 }
@@ -219,17 +179,8 @@ pub async fn handle_stop(
         return Ok(CursorHookOutput::default());
     }
 
-    let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-
-    let meta = VirtualBranchesTomlMetadata::from_path(
-        ctx.project_data_dir().join("virtual_branches.toml"),
-    )?;
-    let vb_state = &VirtualBranchesHandle::new(ctx.project_data_dir());
-    let stacks = but_workspace::legacy::stacks_v3(&repo, &meta, StacksFilter::default(), None)?;
-    let stack_id =
-        but_claude::hooks::get_or_create_session(ctx, &input.conversation_id, stacks, vb_state)?;
+    let ctx = &mut Context::new_from_legacy_project(project)?;
 
-    let summary = "".to_string();
     let prompt = crate::db::get_generations(&dir, nightly)
         .map(|gens| {
             gens.iter()
@@ -239,75 +190,14 @@ pub async fn handle_stop(
         })
         .unwrap_or_default();
 
-    let (id, outcome) = but_action::handle_changes(
+    but_claude::hooks::commit_session_changes(
         ctx,
-        &summary,
-        Some(prompt.clone()),
-        ActionHandler::HandleChangesSimple,
-        Source::Cursor(input.conversation_id),
-        Some(stack_id),
-    )?;
-
-    let stacks = but_workspace::legacy::stacks_v3(&repo, &meta, StacksFilter::default(), None)?;
-
-    // Trigger commit message generation for newly created commits
-    // TODO: Maybe this can be done in the main app process i.e. the GitButler GUI, if available
-    // Alternatively, and probably better - we could spawn a new process to do this
-
-    if let Some(openai_client) =
-        OpenAiProvider::with(None).and_then(|provider| provider.client().ok())
-    {
-        for branch in &outcome.updated_branches {
-            let mut commit_message_mapping = HashMap::new();
-
-            let eligibility =
-                but_claude::hooks::is_branch_eligible_for_rename(ctx, &stacks, branch)?;
-
-            for commit in &branch.new_commits {
-                if let Ok(commit_id) = gix::ObjectId::from_str(commit) {
-                    let commit_event = CommitEvent {
-                        external_summary: summary.clone(),
-                        external_prompt: prompt.clone(),
-                        branch_name: branch.branch_name.clone(),
-                        commit_id,
-                        project: project.clone(),
-                        app_settings: ctx.settings().clone(),
-                        trigger: id,
-                    };
-                    let reword_result = but_action::reword::commit(&openai_client, commit_event)
-                        .await
-                        .ok()
-                        .unwrap_or_default();
-
-                    // Update the commit mapping with the new commit ID
-                    if let Some(reword_result) = reword_result {
-                        commit_message_mapping.insert(commit_id, reword_result);
-                    }
-                }
-            }
-
-            match eligibility {
-                but_claude::hooks::RenameEligibility::Eligible { commit_id } => {
-                    let reword_result = commit_message_mapping.get(&commit_id).cloned();
-
-                    if let Some((commit_id, commit_message)) = reword_result {
-                        let params = but_action::rename_branch::RenameBranchParams {
-                            commit_id,
-                            commit_message,
-                            stack_id: branch.stack_id,
-                            current_branch_name: branch.branch_name.clone(),
-                        };
-                        but_action::rename_branch::rename_branch(ctx, &openai_client, params, id)
-                            .await
-                            .ok();
-                    }
-                }
-                but_claude::hooks::RenameEligibility::NotEligible => {
-                    // Do nothing, branch is not eligible for renaming
-                }
-            }
-        }
-    }
+        &input.conversation_id,
+        "",
+        &prompt,
+        Source::Cursor(input.conversation_id.clone()),
+    )
+    .await?;
 
     Ok(CursorHookOutput::default())
 }
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `idx_cli_ids_kind_key`;
+DROP TABLE IF EXISTS `cli_ids`;
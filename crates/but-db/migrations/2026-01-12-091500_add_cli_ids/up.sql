@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE `cli_ids`(
+	`id` TEXT NOT NULL PRIMARY KEY,
+	`kind` TEXT NOT NULL,
+	`key` TEXT NOT NULL,
+	`retired_at` TIMESTAMP
+);
+
+CREATE UNIQUE INDEX `idx_cli_ids_kind_key` ON `cli_ids`(`kind`, `key`);
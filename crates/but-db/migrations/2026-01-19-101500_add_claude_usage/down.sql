@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE claude_sessions DROP COLUMN budget_usd;
+DROP INDEX IF EXISTS `idx_claude_usage_stack_id`;
+DROP INDEX IF EXISTS `idx_claude_usage_session_id`;
+DROP TABLE IF EXISTS `claude_usage`;
@@ -0,0 +1,18 @@
+-- Track token usage and cost per Claude message, and an optional budget per session
+CREATE TABLE `claude_usage`(
+	`message_id` TEXT NOT NULL PRIMARY KEY,
+	`session_id` TEXT NOT NULL,
+	`stack_id` TEXT,
+	`model` TEXT,
+	`created_at` TIMESTAMP NOT NULL,
+	`input_tokens` BIGINT NOT NULL,
+	`output_tokens` BIGINT NOT NULL,
+	`cache_creation_input_tokens` BIGINT NOT NULL,
+	`cache_read_input_tokens` BIGINT NOT NULL,
+	`cost_usd` DOUBLE NOT NULL
+);
+
+CREATE INDEX `idx_claude_usage_session_id` ON `claude_usage`(`session_id`);
+CREATE INDEX `idx_claude_usage_stack_id` ON `claude_usage`(`stack_id`);
+
+ALTER TABLE claude_sessions ADD COLUMN budget_usd DOUBLE;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `idx_claude_permission_decisions_session_id`;
+DROP TABLE IF EXISTS `claude_permission_decisions`;
@@ -0,0 +1,12 @@
+-- Audit log of every decision about a tool call that Claude asked permission for
+CREATE TABLE `claude_permission_decisions`(
+	`id` TEXT NOT NULL PRIMARY KEY,
+	`session_id` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL,
+	`tool_name` TEXT NOT NULL,
+	`input` TEXT NOT NULL,
+	`decision` TEXT NOT NULL,
+	`source` TEXT NOT NULL
+);
+
+CREATE INDEX `idx_claude_permission_decisions_session_id` ON `claude_permission_decisions`(`session_id`);
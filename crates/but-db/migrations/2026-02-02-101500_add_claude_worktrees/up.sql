@@ -0,0 +1,6 @@
+-- The worktrees that isolated Claude sessions run in, one per stack, until their changes are integrated
+CREATE TABLE `claude_worktrees`(
+	`stack_id` TEXT NOT NULL PRIMARY KEY,
+	`worktree_id` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL
+);
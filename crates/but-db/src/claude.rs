@@ -23,6 +23,8 @@ pub struct ClaudeSession {
     pub in_gui: bool,
     pub approved_permissions: String,
     pub denied_permissions: String,
+    /// The maximum cost in USD the session may incur before it is stopped, or `None` if unlimited.
+    pub budget_usd: Option<f64>,
 }
 
 #[derive(
@@ -63,6 +65,56 @@ pub struct ClaudePermissionRequest {
     pub use_wildcard: bool,
 }
 
+/// The token usage and cost of a single message Claude produced in a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::claude_usage)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ClaudeUsage {
+    /// The ID of the message as assigned by the Claude API.
+    pub message_id: String,
+    /// The stable ID of the session the message belongs to.
+    pub session_id: String,
+    /// The stack the session was working on, if known.
+    pub stack_id: Option<String>,
+    /// The model that produced the message.
+    pub model: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_input_tokens: i64,
+    pub cache_read_input_tokens: i64,
+    pub cost_usd: f64,
+}
+
+/// A decision about a tool call that Claude asked permission for, as recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::claude_permission_decisions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ClaudePermissionDecision {
+    /// The ID of the tool call as assigned by Claude.
+    pub id: String,
+    /// The stable ID of the session the tool call belongs to.
+    pub session_id: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub tool_name: String,
+    /// The input of the tool call, as JSON.
+    pub input: String,
+    /// Whether the tool call was allowed or denied.
+    pub decision: String,
+    /// Who or what made the decision.
+    pub source: String,
+}
+
+/// The worktree that the isolated Claude session of a stack runs in, until its changes are integrated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::claude_worktrees)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ClaudeWorktree {
+    pub stack_id: String,
+    pub worktree_id: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
 impl DbHandle {
     pub fn claude_sessions(&mut self) -> ClaudeSessionsHandle<'_> {
         ClaudeSessionsHandle { db: self }
@@ -76,6 +128,18 @@ impl DbHandle {
         ClaudePermissionRequestsHandle { db: self }
     }
 
+    pub fn claude_usage(&mut self) -> ClaudeUsageHandle<'_> {
+        ClaudeUsageHandle { db: self }
+    }
+
+    pub fn claude_permission_decisions(&mut self) -> ClaudePermissionDecisionsHandle<'_> {
+        ClaudePermissionDecisionsHandle { db: self }
+    }
+
+    pub fn claude_worktrees(&mut self) -> ClaudeWorktreesHandle<'_> {
+        ClaudeWorktreesHandle { db: self }
+    }
+
     pub fn delete_session_and_messages(
         &mut self,
         session_id: &str,
@@ -108,6 +172,105 @@ pub struct ClaudePermissionRequestsHandle<'a> {
     db: &'a mut DbHandle,
 }
 
+pub struct ClaudeUsageHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+pub struct ClaudePermissionDecisionsHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+pub struct ClaudeWorktreesHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl ClaudeWorktreesHandle<'_> {
+    /// Insert `worktree`, replacing the worktree previously recorded for the same stack.
+    pub fn upsert(&mut self, worktree: ClaudeWorktree) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(crate::schema::claude_worktrees::table)
+            .values(worktree)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    pub fn get(&mut self, stack_id: &str) -> Result<Option<ClaudeWorktree>, diesel::result::Error> {
+        crate::schema::claude_worktrees::table
+            .filter(crate::schema::claude_worktrees::stack_id.eq(stack_id))
+            .select(ClaudeWorktree::as_select())
+            .first(&mut self.db.conn)
+            .optional()
+    }
+
+    pub fn list(&mut self) -> Result<Vec<ClaudeWorktree>, diesel::result::Error> {
+        crate::schema::claude_worktrees::table
+            .order(crate::schema::claude_worktrees::created_at.asc())
+            .select(ClaudeWorktree::as_select())
+            .load(&mut self.db.conn)
+    }
+
+    pub fn delete(&mut self, stack_id: &str) -> Result<(), diesel::result::Error> {
+        diesel::delete(
+            crate::schema::claude_worktrees::table
+                .filter(crate::schema::claude_worktrees::stack_id.eq(stack_id)),
+        )
+        .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+}
+
+impl ClaudePermissionDecisionsHandle<'_> {
+    /// Insert `decision`, replacing an earlier decision about the same tool call.
+    pub fn upsert(
+        &mut self,
+        decision: ClaudePermissionDecision,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(crate::schema::claude_permission_decisions::table)
+            .values(decision)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    pub fn list_by_session(
+        &mut self,
+        session_id: &str,
+    ) -> Result<Vec<ClaudePermissionDecision>, diesel::result::Error> {
+        crate::schema::claude_permission_decisions::table
+            .filter(crate::schema::claude_permission_decisions::session_id.eq(session_id))
+            .order(crate::schema::claude_permission_decisions::created_at.asc())
+            .select(ClaudePermissionDecision::as_select())
+            .load(&mut self.db.conn)
+    }
+}
+
+impl ClaudeUsageHandle<'_> {
+    /// Insert `usage`, replacing the usage previously recorded for the same message.
+    /// Claude reports the usage of a message repeatedly while it streams, the last report wins.
+    pub fn upsert(&mut self, usage: ClaudeUsage) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(crate::schema::claude_usage::table)
+            .values(usage)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    pub fn list(&mut self) -> Result<Vec<ClaudeUsage>, diesel::result::Error> {
+        crate::schema::claude_usage::table
+            .order(crate::schema::claude_usage::created_at.asc())
+            .select(ClaudeUsage::as_select())
+            .load(&mut self.db.conn)
+    }
+
+    pub fn list_by_session(
+        &mut self,
+        session_id: &str,
+    ) -> Result<Vec<ClaudeUsage>, diesel::result::Error> {
+        crate::schema::claude_usage::table
+            .filter(crate::schema::claude_usage::session_id.eq(session_id))
+            .order(crate::schema::claude_usage::created_at.asc())
+            .select(ClaudeUsage::as_select())
+            .load(&mut self.db.conn)
+    }
+}
+
 impl ClaudePermissionRequestsHandle<'_> {
     pub fn insert(
         &mut self,
@@ -246,6 +409,20 @@ impl ClaudeSessionsHandle<'_> {
         Ok(())
     }
 
+    pub fn update_budget(
+        &mut self,
+        id: &str,
+        budget_usd: Option<f64>,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(claude_sessions.filter(crate::schema::claude_sessions::id.eq(id)))
+            .set((
+                crate::schema::claude_sessions::budget_usd.eq(budget_usd),
+                crate::schema::claude_sessions::updated_at.eq(chrono::Local::now().naive_local()),
+            ))
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
     /// If you intend delete the messages AND the session, you should use `delete_session_and_messages` instead, which does it all in a single transaction.
     pub fn delete(&mut self, id: &str) -> Result<(), diesel::result::Error> {
         diesel::delete(claude_sessions.filter(crate::schema::claude_sessions::id.eq(id)))
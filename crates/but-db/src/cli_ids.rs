@@ -0,0 +1,57 @@
+use diesel::{
+    ExpressionMethods, QueryDsl, RunQueryDsl,
+    prelude::{Insertable, Queryable, Selectable},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{DbHandle, schema::cli_ids::dsl};
+
+/// A short ID the CLI assigned to an entity, so it remains the same across invocations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::cli_ids)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CliId {
+    /// The short ID as shown to the user.
+    pub id: String,
+    /// The kind of entity the ID was assigned to.
+    pub kind: String,
+    /// The key identifying the entity among all entities of its `kind`.
+    pub key: String,
+    /// The time at which the entity was found to not exist anymore, or `None` if it still exists.
+    pub retired_at: Option<chrono::NaiveDateTime>,
+}
+
+impl DbHandle {
+    pub fn cli_ids(&mut self) -> CliIdsHandle<'_> {
+        CliIdsHandle { db: self }
+    }
+}
+
+pub struct CliIdsHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl CliIdsHandle<'_> {
+    /// Lists all assigned IDs.
+    pub fn list(&mut self) -> anyhow::Result<Vec<CliId>> {
+        let ids = dsl::cli_ids.load::<CliId>(&mut self.db.conn)?;
+        Ok(ids)
+    }
+
+    /// Replace all IDs of the given `kinds` with `ids`, leaving IDs of all other kinds untouched.
+    /// IDs of other kinds that collide with any of `ids` are replaced as well.
+    pub fn set_for_kinds(&mut self, kinds: &[&str], ids: Vec<CliId>) -> anyhow::Result<()> {
+        use diesel::prelude::*;
+
+        self.db.conn.transaction(|conn| {
+            diesel::delete(dsl::cli_ids.filter(dsl::kind.eq_any(kinds))).execute(conn)?;
+            for id in ids {
+                diesel::replace_into(dsl::cli_ids)
+                    .values(&id)
+                    .execute(conn)?;
+            }
+            diesel::result::QueryResult::Ok(())
+        })?;
+        Ok(())
+    }
+}
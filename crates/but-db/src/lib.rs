@@ -13,7 +13,10 @@ mod schema;
 mod workflows;
 pub use workflows::Workflow;
 mod claude;
-pub use claude::{ClaudeMessage, ClaudePermissionRequest, ClaudeSession};
+pub use claude::{
+    ClaudeMessage, ClaudePermissionDecision, ClaudePermissionRequest, ClaudeSession, ClaudeUsage,
+    ClaudeWorktree,
+};
 mod file_write_locks;
 pub use file_write_locks::FileWriteLock;
 mod workspace_rules;
@@ -25,6 +28,8 @@ pub use ci_checks::CiCheck;
 mod gerrit_metadata;
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 pub use gerrit_metadata::GerritMeta;
+mod cli_ids;
+pub use cli_ids::CliId;
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
 pub struct DbHandle {
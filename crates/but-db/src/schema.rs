@@ -65,6 +65,7 @@ diesel::table! {
         in_gui -> Bool,
         approved_permissions -> Text,
         denied_permissions -> Text,
+        budget_usd -> Nullable<Double>,
     }
 }
 
@@ -147,3 +148,47 @@ diesel::table! {
         struct_version -> Integer,
     }
 }
+
+diesel::table! {
+    cli_ids (id) {
+        id -> Text,
+        kind -> Text,
+        key -> Text,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    claude_usage (message_id) {
+        message_id -> Text,
+        session_id -> Text,
+        stack_id -> Nullable<Text>,
+        model -> Nullable<Text>,
+        created_at -> Timestamp,
+        input_tokens -> BigInt,
+        output_tokens -> BigInt,
+        cache_creation_input_tokens -> BigInt,
+        cache_read_input_tokens -> BigInt,
+        cost_usd -> Double,
+    }
+}
+
+diesel::table! {
+    claude_permission_decisions (id) {
+        id -> Text,
+        session_id -> Text,
+        created_at -> Timestamp,
+        tool_name -> Text,
+        input -> Text,
+        decision -> Text,
+        source -> Text,
+    }
+}
+
+diesel::table! {
+    claude_worktrees (stack_id) {
+        stack_id -> Text,
+        worktree_id -> Text,
+        created_at -> Timestamp,
+    }
+}
@@ -47,3 +47,122 @@ fn init_in_parallel() -> anyhow::Result<()> {
     });
     Ok(())
 }
+
+#[test]
+fn cli_ids_are_replaced_by_kind() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let id = |id: &str, kind: &str, key: &str| but_db::CliId {
+        id: id.into(),
+        kind: kind.into(),
+        key: key.into(),
+        retired_at: None,
+    };
+    db.cli_ids().set_for_kinds(
+        &["branch", "stack"],
+        vec![id("g0", "branch", "A"), id("h0", "stack", "1")],
+    )?;
+    db.cli_ids()
+        .set_for_kinds(&["branch"], vec![id("i0", "branch", "B")])?;
+
+    let mut ids = db.cli_ids().list()?;
+    ids.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(
+        ids,
+        [id("h0", "stack", "1"), id("i0", "branch", "B")],
+        "only the IDs of the given kinds are replaced"
+    );
+    Ok(())
+}
+
+#[test]
+fn claude_usage_is_replaced_by_message() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let usage = |message_id: &str, session_id: &str, output_tokens: i64| but_db::ClaudeUsage {
+        message_id: message_id.into(),
+        session_id: session_id.into(),
+        stack_id: None,
+        model: Some("claude-sonnet-4-5".into()),
+        created_at: chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+        input_tokens: 10,
+        output_tokens,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 0,
+        cost_usd: 0.5,
+    };
+    db.claude_usage().upsert(usage("msg_1", "a", 1))?;
+    db.claude_usage().upsert(usage("msg_1", "a", 20))?;
+    db.claude_usage().upsert(usage("msg_2", "b", 5))?;
+
+    assert_eq!(
+        db.claude_usage().list_by_session("a")?,
+        [usage("msg_1", "a", 20)],
+        "the last report of a message wins"
+    );
+    assert_eq!(db.claude_usage().list()?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn claude_permission_decisions_are_listed_by_session() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let decision = |id: &str, session_id: &str, seconds: i64, decision: &str| {
+        but_db::ClaudePermissionDecision {
+            id: id.into(),
+            session_id: session_id.into(),
+            created_at: chrono::DateTime::from_timestamp(seconds, 0)
+                .unwrap()
+                .naive_utc(),
+            tool_name: "Bash".into(),
+            input: r#"{"command":"cargo test"}"#.into(),
+            decision: decision.into(),
+            source: "policy".into(),
+        }
+    };
+    db.claude_permission_decisions()
+        .upsert(decision("toolu_2", "a", 2, "allow"))?;
+    db.claude_permission_decisions()
+        .upsert(decision("toolu_1", "a", 1, "deny"))?;
+    db.claude_permission_decisions()
+        .upsert(decision("toolu_1", "a", 1, "allow"))?;
+    db.claude_permission_decisions()
+        .upsert(decision("toolu_3", "b", 0, "allow"))?;
+
+    assert_eq!(
+        db.claude_permission_decisions().list_by_session("a")?,
+        [
+            decision("toolu_1", "a", 1, "allow"),
+            decision("toolu_2", "a", 2, "allow")
+        ],
+        "decisions are ordered by time, and the last decision about a tool call wins"
+    );
+    Ok(())
+}
+
+#[test]
+fn claude_worktrees_are_replaced_by_stack() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let worktree = |stack_id: &str, worktree_id: &str| but_db::ClaudeWorktree {
+        stack_id: stack_id.into(),
+        worktree_id: worktree_id.into(),
+        created_at: chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+    };
+    db.claude_worktrees().upsert(worktree("a", "wt-1"))?;
+    db.claude_worktrees().upsert(worktree("a", "wt-2"))?;
+    db.claude_worktrees().upsert(worktree("b", "wt-3"))?;
+
+    assert_eq!(
+        db.claude_worktrees().get("a")?,
+        Some(worktree("a", "wt-2")),
+        "each stack has a single worktree"
+    );
+    assert_eq!(db.claude_worktrees().list()?.len(), 2);
+
+    db.claude_worktrees().delete("a")?;
+    assert_eq!(db.claude_worktrees().get("a")?, None);
+    assert_eq!(db.claude_worktrees().list()?, [worktree("b", "wt-3")]);
+    Ok(())
+}
@@ -2,6 +2,7 @@ use std::path::{self};
 
 use anyhow::{Error, Result};
 use but_fs::list_files;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::forge::ForgeName;
@@ -171,7 +172,7 @@ fn is_valid_review_template_path_azure(_path: &path::Path) -> bool {
     false
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ForgeReviewLabel {
     pub name: String,
@@ -189,7 +190,7 @@ impl From<but_github::GitHubPrLabel> for ForgeReviewLabel {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Represents a user from a forge platform (e.g., GitHub, GitLab).
 ///
@@ -223,7 +224,7 @@ impl From<but_github::GitHubUser> for ForgeUser {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Represents a review (pull request/merge request) from a forge platform (GitHub, GitLab, etc.).
 ///
@@ -273,6 +274,7 @@ pub struct ForgeReview {
     /// The platform-specific symbol for this review type (e.g., "#" for GitHub pull requests and "!" for MRs).
     pub unit_symbol: String,
     /// The timestamp when this review was last fetched from the forge.
+    #[schemars(with = "String")]
     pub last_sync_at: chrono::NaiveDateTime,
 }
 
@@ -0,0 +1,280 @@
+//! A serializable and stable copy of the graph, meant to be converted to JSON.
+//!
+//! All names use `camelCase`, hashes are full hex strings and segments are referred to by their
+//! [index](crate::SegmentIndex), which is only stable within a single export.
+use petgraph::{prelude::EdgeRef, visit::IntoEdgeReferences};
+use serde::Serialize;
+
+use crate::{
+    SegmentMetadata,
+    projection::{self, WorkspaceKind},
+};
+
+/// The version of the format, which is incremented whenever a change may break consumers.
+/// Adding fields is not considered a breaking change.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The graph with all of its segments, their connections and optionally its workspace projection.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Graph {
+    /// The [format version](FORMAT_VERSION) of this instance.
+    pub version: u32,
+    /// Where the traversal started, usually the position of `HEAD`.
+    pub entrypoint: Option<Entrypoint>,
+    /// If `true`, the traversal was stopped unconditionally, so the graph is incomplete.
+    pub hard_limit_hit: bool,
+    /// All segments, ordered by their id.
+    pub segments: Vec<Segment>,
+    /// All connections between segments, pointing from a segment to the segment below it.
+    pub edges: Vec<Edge>,
+    /// The workspace projection of the graph, if it was provided.
+    pub workspace: Option<Workspace>,
+}
+
+/// The location of the traversal start.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entrypoint {
+    /// The id of the segment the traversal started at.
+    pub segment: usize,
+    /// The index into the commits of `segment`, or `None` if the segment doesn't contain the tip of the traversal.
+    pub commit: Option<usize>,
+}
+
+/// A segment of commits, exclusively owning them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    /// The id of the segment, unique within this graph.
+    pub id: usize,
+    /// The distance to the root segments of the graph, with `0` being a root.
+    pub generation: usize,
+    /// The full name of the reference at the tip of the segment, if the segment is named.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub ref_name: Option<gix::refs::FullName>,
+    /// The full name of the remote tracking branch of `ref_name`, if there is one.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub remote_tracking_ref_name: Option<gix::refs::FullName>,
+    /// The id of the related segment, for instance the one of `remote_tracking_ref_name`.
+    pub sibling_segment_id: Option<usize>,
+    /// The kind of metadata associated with the segment, if any.
+    pub metadata: Option<MetadataKind>,
+    /// If `true`, the traversal was stopped at the last commit of this segment, so its parents are missing.
+    pub is_early_end: bool,
+    /// The commits owned by this segment, from the top-most commit downwards.
+    pub commits: Vec<Commit>,
+}
+
+/// The kind of metadata stored for a segment.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MetadataKind {
+    /// The segment is the tip of a workspace.
+    Workspace,
+    /// The segment is a branch known to a workspace.
+    Branch,
+}
+
+/// A commit in a segment.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Commit {
+    /// The hash of the commit.
+    #[serde(with = "but_serde::object_id")]
+    pub id: gix::ObjectId,
+    /// The hashes of all parents, even those that aren't part of the graph.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub parent_ids: Vec<gix::ObjectId>,
+    /// The names of the flags set on the commit, i.e. `NotInRemote`, `InWorkspace` or `Integrated`.
+    pub flags: Vec<&'static str>,
+    /// The full names of all references pointing to this commit.
+    #[serde(with = "but_serde::fullname_vec_lossy")]
+    pub refs: Vec<gix::refs::FullName>,
+}
+
+/// A connection from a commit in one segment to its parent in another segment.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Edge {
+    /// The id of the segment containing the child.
+    pub src: usize,
+    /// The index of the child commit in `src`, or `None` if `src` has no commits.
+    pub src_commit: Option<usize>,
+    /// The id of the segment containing the parent.
+    pub dst: usize,
+    /// The index of the parent commit in `dst`, or `None` if `dst` has no commits.
+    pub dst_commit: Option<usize>,
+}
+
+/// The workspace projection, which sees the graph as stacks of branches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    /// The id of the segment at the tip of the workspace.
+    pub segment: usize,
+    /// The kind of workspace.
+    pub kind: WorkspaceKindName,
+    /// The full name of the reference at the tip of the workspace, if it is named.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub ref_name: Option<gix::refs::FullName>,
+    /// The full name of the target branch, typically `refs/remotes/origin/main`.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub target_ref: Option<gix::refs::FullName>,
+    /// The commit the target branch was last seen at, if known.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub target_commit: Option<gix::ObjectId>,
+    /// The commit below which nothing is considered part of the workspace anymore.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub lower_bound: Option<gix::ObjectId>,
+    /// The stacks in the workspace, in order.
+    pub stacks: Vec<Stack>,
+}
+
+/// The classification of a workspace.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkspaceKindName {
+    /// A workspace reference pointing to the workspace commit.
+    Managed,
+    /// A workspace reference that doesn't point to a workspace commit.
+    ManagedMissingWorkspaceCommit,
+    /// A branch or detached `HEAD` outside of a managed workspace.
+    AdHoc,
+}
+
+/// A stack of branches within the workspace.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stack {
+    /// The persistent id of the stack, only set in managed workspaces.
+    pub id: Option<but_core::ref_metadata::StackId>,
+    /// The branches of the stack, from the top-most one downwards.
+    pub segments: Vec<StackSegment>,
+}
+
+/// A branch within a stack.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackSegment {
+    /// The id of the first graph segment this branch was created from.
+    pub segment: usize,
+    /// The full name of the branch, if it is named.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub ref_name: Option<gix::refs::FullName>,
+    /// The full name of the remote tracking branch, if there is one.
+    #[serde(with = "but_serde::fullname_opt_lossy")]
+    pub remote_tracking_ref_name: Option<gix::refs::FullName>,
+    /// The commits of the branch along the first parent, from the top-most commit downwards.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub commits: Vec<gix::ObjectId>,
+    /// The commits that are only reachable from the remote tracking branch.
+    #[serde(with = "but_serde::object_id_vec")]
+    pub commits_on_remote: Vec<gix::ObjectId>,
+    /// The commit the branch is resting on, if known.
+    #[serde(with = "but_serde::object_id_opt")]
+    pub base: Option<gix::ObjectId>,
+    /// If `true`, this branch is where the traversal started.
+    pub is_entrypoint: bool,
+}
+
+impl Graph {
+    /// Create a copy of `graph`, along with its `workspace` projection if it is provided.
+    pub fn new(graph: &crate::Graph, workspace: Option<&projection::Workspace<'_>>) -> Self {
+        let mut segments: Vec<_> = graph
+            .segments()
+            .map(|sidx| {
+                let s = &graph[sidx];
+                Segment {
+                    id: sidx.index(),
+                    generation: s.generation,
+                    ref_name: s.ref_info.as_ref().map(|ri| ri.ref_name.clone()),
+                    remote_tracking_ref_name: s.remote_tracking_ref_name.clone(),
+                    sibling_segment_id: s.sibling_segment_id.map(|id| id.index()),
+                    metadata: s.metadata.as_ref().map(|md| match md {
+                        SegmentMetadata::Workspace(_) => MetadataKind::Workspace,
+                        SegmentMetadata::Branch(_) => MetadataKind::Branch,
+                    }),
+                    is_early_end: graph.is_early_end_of_traversal(sidx),
+                    commits: s
+                        .commits
+                        .iter()
+                        .map(|c| Commit {
+                            id: c.id,
+                            parent_ids: c.parent_ids.clone(),
+                            flags: (c.flags & crate::CommitFlags::all())
+                                .iter_names()
+                                .map(|(name, _)| name)
+                                .collect(),
+                            refs: c.ref_iter().cloned().collect(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        segments.sort_by_key(|s| s.id);
+
+        let mut edges: Vec<_> = graph
+            .inner
+            .edge_references()
+            .map(|e| Edge {
+                src: e.source().index(),
+                src_commit: e.weight().src,
+                dst: e.target().index(),
+                dst_commit: e.weight().dst,
+            })
+            .collect();
+        edges.sort_by_key(|e| (e.src, e.src_commit, e.dst));
+
+        Graph {
+            version: FORMAT_VERSION,
+            entrypoint: graph.entrypoint.map(|(sidx, commit)| Entrypoint {
+                segment: sidx.index(),
+                commit,
+            }),
+            hard_limit_hit: graph.hard_limit_hit,
+            segments,
+            edges,
+            workspace: workspace.map(Workspace::new),
+        }
+    }
+}
+
+impl Workspace {
+    fn new(ws: &projection::Workspace<'_>) -> Self {
+        Workspace {
+            segment: ws.id.index(),
+            kind: match ws.kind {
+                WorkspaceKind::Managed { .. } => WorkspaceKindName::Managed,
+                WorkspaceKind::ManagedMissingWorkspaceCommit { .. } => {
+                    WorkspaceKindName::ManagedMissingWorkspaceCommit
+                }
+                WorkspaceKind::AdHoc => WorkspaceKindName::AdHoc,
+            },
+            ref_name: ws.graph[ws.id].ref_name().map(ToOwned::to_owned),
+            target_ref: ws.target_ref.as_ref().map(|t| t.ref_name.clone()),
+            target_commit: ws.target_commit.as_ref().map(|t| t.commit_id),
+            lower_bound: ws.lower_bound,
+            stacks: ws
+                .stacks
+                .iter()
+                .map(|stack| Stack {
+                    id: stack.id,
+                    segments: stack
+                        .segments
+                        .iter()
+                        .map(|s| StackSegment {
+                            segment: s.id.index(),
+                            ref_name: s.ref_name().map(ToOwned::to_owned),
+                            remote_tracking_ref_name: s.remote_tracking_ref_name.clone(),
+                            commits: s.commits.iter().map(|c| c.id).collect(),
+                            commits_on_remote: s.commits_on_remote.iter().map(|c| c.id).collect(),
+                            base: s.base,
+                            is_entrypoint: s.is_entrypoint,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use bstr::{BStr, ByteSlice};
+use petgraph::{Direction, prelude::EdgeRef};
+
+use crate::{Graph, SegmentIndex};
+
+/// Export
+impl Graph {
+    /// Produce a Mermaid [`gitGraph`](https://mermaid.js.org/syntax/gitgraph.html) diagram of all commits in the graph.
+    ///
+    /// Each segment with commits becomes a branch named after its reference, or `anon-<segment-id>` if it's unnamed.
+    /// Tags, other references and the names of segments without commits are shown as tags on the commit they point to,
+    /// and the entrypoint commit is highlighted.
+    ///
+    /// Note that `gitGraph` can't represent everything: merges only show their first two parents, and additional
+    /// roots branch off the previous branch.
+    pub fn mermaid_graph(&self) -> String {
+        let mut lane_names = BTreeMap::<SegmentIndex, String>::new();
+        let mut used_names = BTreeSet::<String>::new();
+        let mut labels = HashMap::<gix::ObjectId, Vec<String>>::new();
+        for sidx in self.segments() {
+            let segment = &self[sidx];
+            if segment.commits.is_empty() {
+                if let Some((ri, tip)) = segment.ref_info.as_ref().zip(self.tip_skip_empty(sidx)) {
+                    labels
+                        .entry(tip.id)
+                        .or_default()
+                        .push(label(ri.ref_name.shorten()));
+                }
+                continue;
+            }
+            let mut name = match segment.ref_info.as_ref() {
+                Some(ri) => sanitized_branch_name(ri.ref_name.shorten()),
+                None => format!("anon-{}", sidx.index()),
+            };
+            if !used_names.insert(name.clone()) {
+                name = format!("{name}-{}", sidx.index());
+                used_names.insert(name.clone());
+            }
+            lane_names.insert(sidx, name);
+        }
+
+        let highlighted = self.entrypoint_commit().map(|c| c.id);
+        let mut lines = Vec::new();
+        let mut main_lane = None;
+        let mut created = BTreeSet::new();
+        let mut current = None;
+        let mut lane_of_commit = HashMap::<gix::ObjectId, SegmentIndex>::new();
+        for sidx in self.segments_bottom_up() {
+            let Some(lane) = lane_names.get(&sidx) else {
+                continue;
+            };
+            for commit in self[sidx].commits.iter().rev() {
+                let parent_lanes: Vec<_> = commit
+                    .parent_ids
+                    .iter()
+                    .filter_map(|id| lane_of_commit.get(id).copied())
+                    .collect();
+                if created.insert(sidx) {
+                    match parent_lanes.first() {
+                        Some(parent_lane) => {
+                            if current != Some(*parent_lane) {
+                                lines.push(format!("checkout {}", lane_names[parent_lane]));
+                            }
+                            lines.push(format!("branch {lane}"));
+                        }
+                        None if main_lane.is_none() => main_lane = Some(lane.clone()),
+                        None => lines.push(format!("branch {lane}")),
+                    }
+                    current = Some(sidx);
+                }
+                if current != Some(sidx) {
+                    lines.push(format!("checkout {lane}"));
+                    current = Some(sidx);
+                }
+
+                let mut attributes = format!(" id: \"{}\"", commit.id.to_hex_with_len(7));
+                let tags: Vec<_> = commit
+                    .refs
+                    .iter()
+                    .map(|ri| label(ri.ref_name.shorten()))
+                    .chain(labels.remove(&commit.id).into_iter().flatten())
+                    .collect();
+                if !tags.is_empty() {
+                    write!(attributes, " tag: \"{}\"", tags.join(", ")).ok();
+                }
+                if highlighted == Some(commit.id) {
+                    attributes.push_str(" type: HIGHLIGHT");
+                }
+                match parent_lanes.get(1).filter(|other| **other != sidx) {
+                    Some(other) => lines.push(format!("merge {}{attributes}", lane_names[other])),
+                    None => lines.push(format!("commit{attributes}")),
+                }
+                lane_of_commit.insert(commit.id, sidx);
+            }
+        }
+
+        let mut out = String::new();
+        if let Some(main_lane) = main_lane {
+            writeln!(
+                out,
+                "%%{{init: {{ 'gitGraph': {{ 'mainBranchName': '{main_lane}' }} }} }}%%"
+            )
+            .ok();
+        }
+        out.push_str("gitGraph\n");
+        for line in lines {
+            writeln!(out, "  {line}").ok();
+        }
+        out
+    }
+
+    /// Return all segments so that each segment comes after all segments below it,
+    /// ordered by segment index where there is a choice.
+    fn segments_bottom_up(&self) -> Vec<SegmentIndex> {
+        let mut edges_below: BTreeMap<_, _> = self
+            .segments()
+            .map(|sidx| {
+                (
+                    sidx,
+                    self.inner.edges_directed(sidx, Direction::Outgoing).count(),
+                )
+            })
+            .collect();
+        let mut ready: BTreeSet<_> = edges_below
+            .iter()
+            .filter_map(|(sidx, count)| (*count == 0).then_some(*sidx))
+            .collect();
+        let mut out = Vec::with_capacity(edges_below.len());
+        while let Some(sidx) = ready.pop_first() {
+            out.push(sidx);
+            for edge in self.inner.edges_directed(sidx, Direction::Incoming) {
+                let above = edge.source();
+                if let Some(count) = edges_below.get_mut(&above) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(above);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Mermaid branch names may only contain word characters, `-`, `.` and `/`,
+/// and must start with a word character and not end in `.` or `/`.
+fn sanitized_branch_name(name: &BStr) -> String {
+    let mut out: String = name
+        .to_str_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if !out.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+        out.insert(0, '_');
+    }
+    if out.ends_with(['.', '/']) {
+        out.push('_');
+    }
+    out
+}
+
+/// Tags are quoted, so they can't contain quotes themselves.
+fn label(name: &BStr) -> String {
+    name.to_str_lossy().replace('"', "'")
+}
@@ -0,0 +1,13 @@
+//! Representations of the [`Graph`](crate::Graph) for consumption by tools outside of GitButler.
+//!
+//! Unlike the [dot-graph](crate::Graph::dot_graph()), which is meant for debugging and may change at any time,
+//! these formats are stable:
+//!
+//! * [JSON](json::Graph) - a serializable copy of segments, commits, edges and references, along with the
+//!   workspace projection. Changes that break consumers increment [`json::FORMAT_VERSION`].
+//! * [Mermaid](crate::Graph::mermaid_graph()) - a `gitGraph` diagram for rendering in dashboards or pull request comments.
+//!
+//! Call [`Graph::anonymize()`](crate::Graph::anonymize()) before exporting to remove branch names from the output.
+
+pub mod json;
+mod mermaid;
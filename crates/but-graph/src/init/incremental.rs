@@ -0,0 +1,239 @@
+use std::collections::BTreeSet;
+
+use bstr::{BStr, BString, ByteSlice};
+use but_core::RefMetadata;
+use gix::refs::Category;
+
+use crate::{
+    CommitFlags, Graph,
+    init::{
+        Overlay,
+        overlay::{OverlayMetadata, OverlayRepo},
+        remotes,
+        walk::try_refname_to_id,
+    },
+};
+
+/// Incremental updates
+impl Graph {
+    /// Like [`Self::redo_traversal_with_changed_refs_and_overlay()`], but without an overlay.
+    pub fn redo_traversal_with_changed_refs<'a>(
+        &self,
+        repo: &gix::Repository,
+        meta: &impl RefMetadata,
+        changed_refs: impl IntoIterator<Item = &'a gix::refs::FullNameRef>,
+    ) -> anyhow::Result<Option<Self>> {
+        self.redo_traversal_with_changed_refs_and_overlay(
+            repo,
+            meta,
+            changed_refs,
+            Overlay::default(),
+        )
+    }
+
+    /// Given the names of `changed_refs` which were created, deleted or moved in `repo` since this graph was created,
+    /// return `None` if none of them can affect the graph so it can be kept as is, or the graph of a new traversal
+    /// with `repo` and `meta` otherwise.
+    /// All references in `overlay` are considered changed as well.
+    ///
+    /// This is useful to avoid traversals for reference changes picked up by a file watcher, as in repositories with
+    /// many remotes most of these are unrelated to the graph.
+    ///
+    /// A reference is considered unrelated if…
+    ///
+    /// * …the graph doesn't know it by name, neither as segment, commit reference, remote tracking branch or target,
+    ///   nor from workspace metadata.
+    /// * …it doesn't point to a commit in the graph, or if it's a remote tracking branch, to a local commit in the graph.
+    /// * …it can't be the remote tracking branch of a local branch in the graph, or vice versa.
+    /// * …it isn't a tag while tags are [collected](crate::init::Options::collect_tags).
+    ///
+    /// If the reference of the [entrypoint](Self::lookup_entrypoint()) changed, the new traversal starts at its new position.
+    /// Note that changes to `HEAD` itself, i.e. checking out another branch, always cause a new traversal from the previous
+    /// entrypoint, so [`Self::from_head()`] should be used instead.
+    /// Changes to metadata aren't detected either, and metadata overrides in `overlay` always cause a new traversal.
+    pub fn redo_traversal_with_changed_refs_and_overlay<'a>(
+        &self,
+        repo: &gix::Repository,
+        meta: &impl RefMetadata,
+        changed_refs: impl IntoIterator<Item = &'a gix::refs::FullNameRef>,
+        overlay: Overlay,
+    ) -> anyhow::Result<Option<Self>> {
+        if overlay.entrypoint.is_some()
+            || overlay.workspace.is_some()
+            || !overlay.meta_branches.is_empty()
+        {
+            return self
+                .redo_traversal_with_overlay(repo, meta, overlay)
+                .map(Some);
+        }
+        let changed_refs: Vec<gix::refs::FullName> = changed_refs
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .chain(
+                overlay
+                    .overriding_references
+                    .iter()
+                    .chain(&overlay.nonoverriding_references)
+                    .map(|r| r.name.clone()),
+            )
+            .collect();
+
+        let mut new_entrypoint_tip = None;
+        {
+            let (overlay_repo, overlay_meta, _entrypoint) = overlay.clone().into_parts(repo, meta);
+            let known = KnownRefs::new(self, &overlay_repo, &overlay_meta)?;
+            let mut is_affected = false;
+            for ref_name in &changed_refs {
+                if !known.is_affected_by(self, &overlay_repo, ref_name.as_ref())? {
+                    continue;
+                }
+                is_affected = true;
+                if self.entrypoint_ref.as_ref() == Some(ref_name) {
+                    new_entrypoint_tip = try_refname_to_id(&overlay_repo, ref_name.as_ref())?;
+                }
+            }
+            if !is_affected {
+                return Ok(None);
+            }
+        }
+
+        let overlay = match new_entrypoint_tip {
+            Some(tip) => overlay.with_entrypoint(tip, self.entrypoint_ref.clone()),
+            None => overlay,
+        };
+        self.redo_traversal_with_overlay(repo, meta, overlay)
+            .map(Some)
+    }
+}
+
+/// All references the graph depends on, one way or another.
+struct KnownRefs {
+    /// The full names of all references mentioned in the graph or in workspace metadata,
+    /// along with the configured remote tracking branches of local branches.
+    names: BTreeSet<gix::refs::FullName>,
+    /// The short names of all branches, with all possible remote names stripped from remote tracking branches,
+    /// to be able to find remote tracking branches by deduction.
+    branch_names: BTreeSet<BString>,
+    /// The flags of all commits in the graph.
+    commits: gix::hashtable::HashMap<gix::ObjectId, CommitFlags>,
+}
+
+impl KnownRefs {
+    fn new<T: RefMetadata>(
+        graph: &Graph,
+        repo: &OverlayRepo<'_>,
+        meta: &OverlayMetadata<'_, T>,
+    ) -> anyhow::Result<Self> {
+        let mut names = BTreeSet::new();
+        let mut commits = gix::hashtable::HashMap::default();
+        names.extend(graph.entrypoint_ref.clone());
+        for sidx in graph.segments() {
+            let segment = &graph[sidx];
+            names.extend(segment.ref_info.as_ref().map(|ri| ri.ref_name.clone()));
+            names.extend(segment.remote_tracking_ref_name.clone());
+            for commit in &segment.commits {
+                names.extend(commit.ref_iter().cloned());
+                commits.insert(commit.id, commit.flags);
+            }
+        }
+        for (ws_ref_name, ws) in meta.iter_workspaces() {
+            names.insert(ws_ref_name);
+            if let Some(target_ref) = ws.target_ref {
+                if let Some((local_tracking_branch, _remote)) = repo
+                    .upstream_branch_and_remote_for_tracking_branch(target_ref.as_ref())
+                    .ok()
+                    .flatten()
+                {
+                    names.insert(local_tracking_branch);
+                }
+                names.insert(target_ref);
+            }
+            names.extend(
+                ws.stacks
+                    .into_iter()
+                    .flat_map(|s| s.branches.into_iter().map(|b| b.ref_name)),
+            );
+        }
+
+        let mut configured_remote_tracking_branches = Vec::new();
+        for local_branch in names
+            .iter()
+            .filter(|rn| rn.category() == Some(Category::LocalBranch))
+        {
+            configured_remote_tracking_branches.extend(remotes::lookup_remote_tracking_branch(
+                repo,
+                local_branch.as_ref(),
+            )?);
+        }
+        names.extend(configured_remote_tracking_branches);
+
+        let branch_names = names
+            .iter()
+            .flat_map(|rn| branch_name_candidates(rn.as_ref()))
+            .map(ToOwned::to_owned)
+            .collect();
+        Ok(KnownRefs {
+            names,
+            branch_names,
+            commits,
+        })
+    }
+
+    fn is_affected_by(
+        &self,
+        graph: &Graph,
+        repo: &OverlayRepo<'_>,
+        ref_name: &gix::refs::FullNameRef,
+    ) -> anyhow::Result<bool> {
+        if self.names.contains(ref_name) || is_head(ref_name) {
+            return Ok(true);
+        }
+        let points_to_graph_commit = |is_interesting: fn(CommitFlags) -> bool| {
+            anyhow::Ok(
+                try_refname_to_id(repo, ref_name)?
+                    .and_then(|id| self.commits.get(&id))
+                    .is_some_and(|flags| is_interesting(*flags)),
+            )
+        };
+        Ok(match ref_name.category() {
+            Some(Category::LocalBranch) => {
+                branch_name_candidates(ref_name).any(|name| self.branch_names.contains(name))
+                    || remotes::lookup_remote_tracking_branch(repo, ref_name)?
+                        .is_some_and(|rn| self.names.contains(&rn))
+                    || points_to_graph_commit(|_| true)?
+            }
+            Some(Category::RemoteBranch) => {
+                branch_name_candidates(ref_name).any(|name| self.branch_names.contains(name))
+                    // Remote tracking branches on local commits are removed, they only split remote segments.
+                    || points_to_graph_commit(|flags| flags.is_remote())?
+            }
+            Some(Category::Tag) => graph.options.collect_tags && points_to_graph_commit(|_| true)?,
+            _ => false,
+        })
+    }
+}
+
+/// Return `true` if `ref_name` is `HEAD` of any worktree.
+fn is_head(ref_name: &gix::refs::FullNameRef) -> bool {
+    matches!(
+        ref_name.category(),
+        Some(Category::PseudoRef | Category::MainPseudoRef | Category::LinkedPseudoRef { .. })
+    ) && ref_name.shorten().as_bytes() == b"HEAD"
+}
+
+/// Return the short name of local branches, or all possible branch names of remote tracking branches
+/// as we don't know which part of the name is the remote.
+fn branch_name_candidates(ref_name: &gix::refs::FullNameRef) -> impl Iterator<Item = &BStr> {
+    let (local, remote) = match ref_name.category() {
+        Some(Category::LocalBranch) => (Some(ref_name.shorten()), None),
+        Some(Category::RemoteBranch) => (None, Some(ref_name.shorten())),
+        _ => (None, None),
+    };
+    local
+        .into_iter()
+        .chain(remote.into_iter().flat_map(|short_name| {
+            short_name
+                .find_iter("/")
+                .map(move |pos| short_name[pos + 1..].as_bstr())
+        }))
+}
@@ -22,6 +22,7 @@ use crate::init::overlay::{OverlayMetadata, OverlayRepo};
 
 mod remotes;
 
+mod incremental;
 mod overlay;
 mod post;
 
@@ -212,6 +212,7 @@ mod statistics;
 pub use statistics::Statistics;
 
 mod debug;
+pub mod export;
 
 /// Edges to other segments are the index into the list of local commits of the parent segment.
 /// That way we can tell where a segment branches off, despite the graph only connecting segments, and not commits.
@@ -0,0 +1,200 @@
+use but_graph::{CommitFlags, Graph, RefInfo, Segment, export};
+
+use crate::vis::{commit, id};
+
+/// An empty branch on top of a merge of two unrelated histories, one of which is tagged.
+fn merge_of_unrelated_histories() -> anyhow::Result<Graph> {
+    let mut graph = Graph::default();
+    let empty = graph.insert_segment_set_entrypoint(Segment {
+        ref_info: Some(RefInfo {
+            ref_name: "refs/heads/empty".try_into()?,
+            worktree: None,
+        }),
+        ..Default::default()
+    });
+    let merged = graph.connect_new_segment(
+        empty,
+        None,
+        Segment {
+            ref_info: Some(RefInfo {
+                ref_name: "refs/heads/merged".try_into()?,
+                worktree: None,
+            }),
+            commits: vec![commit(id("e"), [id("a"), id("b")], CommitFlags::empty())],
+            ..Default::default()
+        },
+        0,
+        None,
+    );
+    graph.connect_new_segment(
+        merged,
+        0,
+        Segment {
+            ref_info: Some(RefInfo {
+                ref_name: "refs/heads/A".try_into()?,
+                worktree: None,
+            }),
+            commits: vec![commit(id("a"), None, CommitFlags::InWorkspace)],
+            ..Default::default()
+        },
+        0,
+        None,
+    );
+    let mut tagged = commit(id("b"), None, CommitFlags::empty());
+    tagged.refs.push(RefInfo {
+        ref_name: "refs/tags/v1".try_into()?,
+        worktree: None,
+    });
+    graph.connect_new_segment(
+        merged,
+        0,
+        Segment {
+            commits: vec![tagged],
+            ..Default::default()
+        },
+        0,
+        None,
+    );
+    Ok(graph)
+}
+
+#[test]
+fn mermaid() -> anyhow::Result<()> {
+    let graph = merge_of_unrelated_histories()?;
+    insta::assert_snapshot!(graph.mermaid_graph(), @r#"
+    %%{init: { 'gitGraph': { 'mainBranchName': 'A' } } }%%
+    gitGraph
+      commit id: "aaaaaaa"
+      branch anon-3
+      commit id: "bbbbbbb" tag: "v1"
+      checkout A
+      branch merged
+      merge anon-3 id: "eeeeeee" tag: "empty" type: HIGHLIGHT
+    "#);
+    Ok(())
+}
+
+#[test]
+fn mermaid_of_empty_graph() {
+    insta::assert_snapshot!(Graph::default().mermaid_graph(), @"gitGraph");
+}
+
+#[test]
+fn json() -> anyhow::Result<()> {
+    let graph = merge_of_unrelated_histories()?;
+    let export = export::json::Graph::new(&graph, None);
+    insta::assert_snapshot!(serde_json::to_string_pretty(&export)?, @r#"
+    {
+      "version": 1,
+      "entrypoint": {
+        "segment": 0,
+        "commit": null
+      },
+      "hardLimitHit": false,
+      "segments": [
+        {
+          "id": 0,
+          "generation": 0,
+          "refName": "refs/heads/empty",
+          "remoteTrackingRefName": null,
+          "siblingSegmentId": null,
+          "metadata": null,
+          "isEarlyEnd": false,
+          "commits": []
+        },
+        {
+          "id": 1,
+          "generation": 0,
+          "refName": "refs/heads/merged",
+          "remoteTrackingRefName": null,
+          "siblingSegmentId": null,
+          "metadata": null,
+          "isEarlyEnd": false,
+          "commits": [
+            {
+              "id": "eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee",
+              "parentIds": [
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+              ],
+              "flags": [],
+              "refs": []
+            }
+          ]
+        },
+        {
+          "id": 2,
+          "generation": 0,
+          "refName": "refs/heads/A",
+          "remoteTrackingRefName": null,
+          "siblingSegmentId": null,
+          "metadata": null,
+          "isEarlyEnd": false,
+          "commits": [
+            {
+              "id": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+              "parentIds": [],
+              "flags": [
+                "InWorkspace"
+              ],
+              "refs": []
+            }
+          ]
+        },
+        {
+          "id": 3,
+          "generation": 0,
+          "refName": null,
+          "remoteTrackingRefName": null,
+          "siblingSegmentId": null,
+          "metadata": null,
+          "isEarlyEnd": false,
+          "commits": [
+            {
+              "id": "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+              "parentIds": [],
+              "flags": [],
+              "refs": [
+                "refs/tags/v1"
+              ]
+            }
+          ]
+        }
+      ],
+      "edges": [
+        {
+          "src": 0,
+          "srcCommit": null,
+          "dst": 1,
+          "dstCommit": 0
+        },
+        {
+          "src": 1,
+          "srcCommit": 0,
+          "dst": 2,
+          "dstCommit": 0
+        },
+        {
+          "src": 1,
+          "srcCommit": 0,
+          "dst": 3,
+          "dstCommit": 0
+        }
+      ],
+      "workspace": null
+    }
+    "#);
+    Ok(())
+}
+
+#[test]
+fn anonymized_names_are_exported() -> anyhow::Result<()> {
+    let mut graph = merge_of_unrelated_histories()?;
+    graph.anonymize(&Default::default())?;
+    let mermaid = graph.mermaid_graph();
+    assert!(
+        !mermaid.contains("merged") && !mermaid.contains("v1"),
+        "branch and tag names are replaced: {mermaid}"
+    );
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use but_graph::{Graph, init::Overlay};
+use but_testsupport::graph_tree;
+use gix::refs::{FullName, Reference, Target};
+
+use crate::init::{add_workspace, read_only_in_memory_scenario, standard_options};
+
+#[test]
+fn unrelated_references_keep_the_graph() -> anyhow::Result<()> {
+    let (repo, meta) = read_only_in_memory_scenario("four-diamond")?;
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?;
+    let unreachable = unreachable_commit(&repo, repo.head_id()?.detach())?;
+
+    for name in [
+        "refs/heads/unrelated",
+        "refs/remotes/other/unrelated",
+        "refs/notes/commits",
+        "refs/stash",
+    ] {
+        let overlay = Overlay::default().with_references([reference(name, unreachable)?]);
+        assert!(
+            graph
+                .redo_traversal_with_changed_refs_and_overlay(
+                    &repo,
+                    &*meta,
+                    std::iter::empty(),
+                    overlay
+                )?
+                .is_none(),
+            "{name} can't affect the graph"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn related_references_cause_a_new_traversal() -> anyhow::Result<()> {
+    let (repo, meta) = read_only_in_memory_scenario("four-diamond")?;
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?;
+    let unreachable = unreachable_commit(&repo, repo.head_id()?.detach())?;
+    let base = repo.rev_parse_single("main")?.detach();
+
+    for (name, id, reason) in [
+        ("refs/heads/B", unreachable, "it's the name of a segment"),
+        ("refs/heads/new", base, "it points to a commit in the graph"),
+        ("refs/tags/new", base, "tags are collected"),
+        (
+            "refs/remotes/origin/D",
+            unreachable,
+            "it could be the remote tracking branch of a segment",
+        ),
+    ] {
+        let overlay = Overlay::default().with_references([reference(name, id)?]);
+        assert!(
+            graph
+                .redo_traversal_with_changed_refs_and_overlay(
+                    &repo,
+                    &*meta,
+                    std::iter::empty(),
+                    overlay
+                )?
+                .is_some(),
+            "{name} affects the graph as {reason}"
+        );
+    }
+
+    let head = repo.head_name()?.expect("not detached");
+    assert!(
+        graph
+            .redo_traversal_with_changed_refs(&repo, &*meta, [head.as_ref()])?
+            .is_some(),
+        "the entrypoint reference is always relevant, even if it didn't actually change"
+    );
+    Ok(())
+}
+
+#[test]
+fn moved_entrypoint_reference_is_followed() -> anyhow::Result<()> {
+    let (repo, meta) = read_only_in_memory_scenario("four-diamond")?;
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?;
+    let new_tip = repo.rev_parse_single("C")?.detach();
+
+    let overlay = Overlay::default().with_references([reference("refs/heads/merged", new_tip)?]);
+    let graph = graph
+        .redo_traversal_with_changed_refs_and_overlay(&repo, &*meta, std::iter::empty(), overlay)?
+        .expect("the entrypoint changed");
+    assert_eq!(
+        graph.entrypoint_commit().map(|c| c.id),
+        Some(new_tip),
+        "the traversal starts at the new position of the entrypoint reference"
+    );
+    Ok(())
+}
+
+/// For each scenario, change each reference individually and assure that incremental updates
+/// produce the same graph as a full traversal.
+#[test]
+fn incremental_updates_match_full_traversals_in_all_scenarios() -> anyhow::Result<()> {
+    let root = gix_testtools::scripted_fixture_read_only("scenarios.sh")
+        .map_err(anyhow::Error::from_boxed)?;
+    let mut scenarios = Vec::new();
+    collect_repositories(&root, &root, &mut scenarios)?;
+    scenarios.sort();
+    assert!(!scenarios.is_empty(), "the fixture must contain scenarios");
+
+    let (mut num_kept, mut num_retraversed) = (0, 0);
+    for scenario in scenarios {
+        let (repo, mut meta) = read_only_in_memory_scenario(&scenario)?;
+        let with_workspace: &[bool] = if scenario.starts_with("ws/") {
+            &[false, true]
+        } else {
+            &[false]
+        };
+        for add_workspace_metadata in with_workspace {
+            if *add_workspace_metadata {
+                add_workspace(&mut meta);
+            }
+            let graph = Graph::from_head(&repo, &*meta, standard_options())
+                .with_context(|| format!("{scenario}: initial traversal"))?;
+            let entrypoint = graph.lookup_entrypoint()?;
+            let Some(tip) = graph.tip_skip_empty(entrypoint.segment_index).map(|c| c.id) else {
+                // Unborn, there is nothing to traverse.
+                continue;
+            };
+            // Normalize the graph so it's created just like the ones to compare it with.
+            let graph = graph.redo_traversal_with_overlay(&repo, &*meta, Overlay::default())?;
+            let entrypoint_ref = repo.head_name()?;
+            let unreachable = unreachable_commit(&repo, tip)?;
+
+            let mut changes: Vec<_> = repo
+                .references()?
+                .all()?
+                .filter_map(Result::ok)
+                .map(|r| Reference {
+                    name: r.inner.name,
+                    target: Target::Object(unreachable),
+                    peeled: None,
+                })
+                .collect();
+            changes.extend([
+                reference("refs/heads/incremental/new", unreachable)?,
+                reference("refs/remotes/origin/incremental/new", unreachable)?,
+                reference("refs/tags/incremental/new", tip)?,
+            ]);
+
+            for change in changes {
+                let name = change.name.clone();
+                let overlay = Overlay::default().with_references([change]);
+                let expected_overlay = if entrypoint_ref.as_ref() == Some(&name) {
+                    overlay
+                        .clone()
+                        .with_entrypoint(unreachable, Some(name.clone()))
+                } else {
+                    overlay.clone()
+                };
+                let context = || format!("{scenario}: {name} moved to {unreachable}");
+                let Ok(expected) =
+                    graph.redo_traversal_with_overlay(&repo, &*meta, expected_overlay)
+                else {
+                    // Some changes can't be traversed, which isn't what's tested here.
+                    continue;
+                };
+                let actual = graph
+                    .redo_traversal_with_changed_refs_and_overlay(
+                        &repo,
+                        &*meta,
+                        std::iter::empty(),
+                        overlay,
+                    )
+                    .with_context(context)?;
+                let actual = match actual {
+                    Some(actual) => {
+                        num_retraversed += 1;
+                        actual
+                    }
+                    None => {
+                        num_kept += 1;
+                        graph.clone()
+                    }
+                };
+                assert_eq!(
+                    graph_tree(&actual).to_string(),
+                    graph_tree(&expected).to_string(),
+                    "{}",
+                    context()
+                );
+            }
+        }
+    }
+    assert!(
+        num_kept > 0 && num_retraversed > 0,
+        "both paths are exercised: kept = {num_kept}, retraversed = {num_retraversed}"
+    );
+    Ok(())
+}
+
+fn collect_repositories(root: &Path, dir: &Path, out: &mut Vec<String>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.join(".git").is_dir() {
+            let name = path.strip_prefix(root)?.to_str().context("UTF-8 paths")?;
+            out.push(name.replace('\\', "/"));
+        } else {
+            collect_repositories(root, &path, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a commit on top of `parent`, which isn't reachable from any reference.
+fn unreachable_commit(
+    repo: &gix::Repository,
+    parent: gix::ObjectId,
+) -> anyhow::Result<gix::ObjectId> {
+    let mut commit = repo.find_commit(parent)?.decode()?.to_owned()?;
+    commit.parents = [parent].into_iter().collect();
+    commit.message = "not reachable from any reference".into();
+    Ok(repo.write_object(&commit)?.detach())
+}
+
+fn reference(name: &str, id: gix::ObjectId) -> anyhow::Result<Reference> {
+    Ok(Reference {
+        name: FullName::try_from(name)?,
+        target: Target::Object(id),
+        peeled: None,
+    })
+}
@@ -872,6 +872,7 @@ fn ambiguous_worktrees() -> anyhow::Result<()> {
     Ok(())
 }
 
+mod incremental;
 mod with_workspace;
 
 mod utils;
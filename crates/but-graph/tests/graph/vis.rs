@@ -125,7 +125,7 @@ fn detached_head() {
     ");
 }
 
-fn id(hex: &str) -> ObjectId {
+pub(crate) fn id(hex: &str) -> ObjectId {
     let hash_len = gix::hash::Kind::Sha1.len_in_hex();
     if hex.len() != hash_len {
         ObjectId::from_str(
@@ -139,7 +139,7 @@ fn id(hex: &str) -> ObjectId {
     .unwrap()
 }
 
-fn commit(
+pub(crate) fn commit(
     id: ObjectId,
     parent_ids: impl IntoIterator<Item = ObjectId>,
     flags: CommitFlags,
@@ -133,9 +133,9 @@ impl From<HunkAssignment> for but_core::DiffSpec {
 /// Indicates that the assignment request was rejected due to locking - the hunk depends on a commit in the stack it is currently in.
 pub struct AssignmentRejection {
     /// The request that was rejected.
-    request: HunkAssignmentRequest,
+    pub request: HunkAssignmentRequest,
     /// The locks that caused the rejection.
-    locks: Vec<HunkLock>,
+    pub locks: Vec<HunkLock>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -0,0 +1,182 @@
+//! Route the changed lines of worktree hunks to the commits whose changes they modify, similar to `git absorb`.
+//!
+//! This works purely with the [hunk ranges](HunkRange) of the workspace, which are in the coordinates of the old
+//! side of a worktree hunk, so no language model or content heuristic is involved.
+
+use but_core::HunkHeader;
+use gix::bstr::{BStr, ByteSlice};
+
+use crate::{HunkRange, ui::HunkLock};
+
+/// A run of changed lines in a worktree hunk, along with the commit they should be absorbed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineAbsorption {
+    /// The commit whose changes are modified by the lines, or `None` if no single commit could be determined.
+    pub target: Option<HunkLock>,
+    /// The lines as hunk header that only has lines on one side and is anchored at `0` on the other,
+    /// the way the commit engine expects a selection of lines.
+    pub header: HunkHeader,
+}
+
+/// Split the worktree hunk with `header` and unified `diff` into runs of changed lines, and determine the commit
+/// each run should be absorbed into. `ranges` are the hunk ranges of the path in the workspace,
+/// and context lines in `diff` are ignored.
+///
+/// * A removed line goes to the commit that last changed it.
+/// * Added lines that replace removed lines go to the commit of the removed lines, if it's the same for all of them.
+///   Otherwise, none of the lines of the replacement are absorbed.
+/// * Added lines that are inserted between two lines go to the commit that changed the lines around them,
+///   as long as there is only one such commit.
+///
+/// Lines that can't be associated with exactly one commit have no target.
+pub fn absorb_hunk(ranges: &[HunkRange], header: &HunkHeader, diff: &BStr) -> Vec<LineAbsorption> {
+    // The number of the next line on each side. Headers without lines on a side point to the line before.
+    let mut old = header.old_start + u32::from(header.old_lines == 0);
+    let mut new = header.new_start + u32::from(header.new_lines == 0);
+    let mut block = Block::default();
+    let mut out = Vec::new();
+    for line in diff.lines() {
+        match line.first() {
+            Some(b'-') => {
+                if !block.added.is_empty() {
+                    block.take().route(ranges, old, &mut out);
+                }
+                block.removed.push(old);
+                old += 1;
+            }
+            Some(b'+') => {
+                block.added.push(new);
+                new += 1;
+            }
+            Some(b' ') => {
+                block.take().route(ranges, old, &mut out);
+                old += 1;
+                new += 1;
+            }
+            _ => {}
+        }
+    }
+    block.route(ranges, old, &mut out);
+    out
+}
+
+/// Consecutive removed and added lines, by their line number in the old and new version respectively.
+#[derive(Default)]
+struct Block {
+    removed: Vec<u32>,
+    added: Vec<u32>,
+}
+
+impl Block {
+    fn take(&mut self) -> Block {
+        std::mem::take(self)
+    }
+
+    /// Add the lines of this block to `out`, with `next_old_line` being the line in the old version right after it.
+    fn route(self, ranges: &[HunkRange], next_old_line: u32, out: &mut Vec<LineAbsorption>) {
+        if self.removed.is_empty() {
+            let before = next_old_line - 1;
+            let target = single(
+                owners(ranges, before)
+                    .chain(owners(ranges, next_old_line))
+                    .chain(
+                        ranges
+                            .iter()
+                            .filter(|range| range.lines == 0 && range.start == before)
+                            .map(lock),
+                    ),
+            );
+            for line in self.added {
+                push(out, target, Side::New, line);
+            }
+        } else if self.added.is_empty() {
+            for line in self.removed {
+                push(out, single(owners(ranges, line)), Side::Old, line);
+            }
+        } else {
+            let targets: Vec<_> = self
+                .removed
+                .iter()
+                .map(|line| single(owners(ranges, *line)))
+                .collect();
+            let target = if targets.iter().all(Option::is_some) {
+                single(targets.into_iter().flatten())
+            } else {
+                None
+            };
+            for line in self.removed {
+                push(out, target, Side::Old, line);
+            }
+            for line in self.added {
+                push(out, target, Side::New, line);
+            }
+        }
+    }
+}
+
+/// The commits of all ranges that contain `line` of the old version.
+fn owners(ranges: &[HunkRange], line: u32) -> impl Iterator<Item = HunkLock> + '_ {
+    ranges
+        .iter()
+        .filter(move |range| {
+            range.lines > 0 && (range.start..range.start + range.lines).contains(&line)
+        })
+        .map(lock)
+}
+
+fn lock(range: &HunkRange) -> HunkLock {
+    HunkLock {
+        stack_id: range.stack_id,
+        commit_id: range.commit_id,
+    }
+}
+
+/// Return the only lock in `locks`, or `None` if there is none or more than one.
+fn single(mut locks: impl Iterator<Item = HunkLock>) -> Option<HunkLock> {
+    let first = locks.next()?;
+    locks.all(|lock| lock == first).then_some(first)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Old,
+    New,
+}
+
+/// Add `line` on `side` to `out`, extending the last run if it's adjacent and goes to the same `target`.
+fn push(out: &mut Vec<LineAbsorption>, target: Option<HunkLock>, side: Side, line: u32) {
+    if let Some(last) = out.last_mut()
+        && last.target == target
+    {
+        let header = &mut last.header;
+        match side {
+            Side::Old if header.new_lines == 0 && header.old_start + header.old_lines == line => {
+                header.old_lines += 1;
+                return;
+            }
+            Side::New if header.old_lines == 0 && header.new_start + header.new_lines == line => {
+                header.new_lines += 1;
+                return;
+            }
+            _ => {}
+        }
+    }
+    let header = match side {
+        Side::Old => HunkHeader {
+            old_start: line,
+            old_lines: 1,
+            new_start: 0,
+            new_lines: 0,
+        },
+        Side::New => HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: line,
+            new_lines: 1,
+        },
+    };
+    out.push(LineAbsorption { target, header });
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,174 @@
+use but_core::{HunkHeader, TreeStatusKind, ref_metadata::StackId};
+use gix::bstr::BString;
+
+use crate::{
+    HunkRange, InputCommit, InputDiffHunk, InputFile, InputStack, WorkspaceRanges,
+    absorb::{LineAbsorption, absorb_hunk},
+    ui::HunkLock,
+};
+
+/// Lines 2 and 6-7 of a file with 10 lines were changed by two commits of the same stack.
+fn ranges() -> anyhow::Result<(Vec<HunkRange>, HunkLock, HunkLock)> {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let first = HunkLock {
+        stack_id,
+        commit_id: id_from_hex_char('1'),
+    };
+    let second = HunkLock {
+        stack_id,
+        commit_id: id_from_hex_char('2'),
+    };
+    let commit = |lock: HunkLock, old_start, old_lines| InputCommit {
+        commit_id: lock.commit_id,
+        files: vec![InputFile {
+            path: path.clone(),
+            change_type: TreeStatusKind::Modification,
+            hunks: vec![InputDiffHunk {
+                old_start,
+                old_lines,
+                new_start: old_start,
+                new_lines: old_lines,
+            }],
+        }],
+    };
+    let ranges = WorkspaceRanges::try_from_stacks(vec![InputStack {
+        stack_id,
+        commits_from_base_to_tip: vec![commit(first, 2, 1), commit(second, 6, 2)],
+    }])?;
+    Ok((ranges.ranges_by_path_map()[&path].clone(), first, second))
+}
+
+#[test]
+fn modifications_go_to_the_commit_that_changed_the_lines() -> anyhow::Result<()> {
+    let (ranges, first, second) = ranges()?;
+    let parts = absorb_hunk(
+        &ranges,
+        &header(1, 8, 1, 8),
+        "@@ -1,8 +1,8 @@\n 1\n-2\n+two\n 3\n 4\n 5\n 6\n-7\n+seven\n 8\n".into(),
+    );
+    assert_eq!(
+        parts,
+        [
+            part(first, header(2, 1, 0, 0)),
+            part(first, header(0, 0, 2, 1)),
+            part(second, header(7, 1, 0, 0)),
+            part(second, header(0, 0, 7, 1)),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn removals_are_split_by_line() -> anyhow::Result<()> {
+    let (ranges, first, _) = ranges()?;
+    let parts = absorb_hunk(
+        &ranges,
+        &header(1, 3, 0, 0),
+        "@@ -1,3 +0,0 @@\n-1\n-2\n-3\n".into(),
+    );
+    assert_eq!(
+        parts,
+        [
+            unabsorbed(header(1, 1, 0, 0)),
+            part(first, header(2, 1, 0, 0)),
+            unabsorbed(header(3, 1, 0, 0)),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn replacements_of_lines_of_multiple_commits_are_not_absorbed() -> anyhow::Result<()> {
+    let (ranges, _, _) = ranges()?;
+    let parts = absorb_hunk(
+        &ranges,
+        &header(5, 2, 5, 1),
+        "@@ -5,2 +5,1 @@\n-5\n-6\n+56\n".into(),
+    );
+    assert_eq!(
+        parts,
+        [
+            unabsorbed(header(5, 2, 0, 0)),
+            unabsorbed(header(0, 0, 5, 1)),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn insertions_go_to_the_commit_of_adjacent_lines() -> anyhow::Result<()> {
+    let (ranges, _, second) = ranges()?;
+    let parts = absorb_hunk(
+        &ranges,
+        &header(7, 0, 8, 2),
+        "@@ -7,0 +8,2 @@\n+a\n+b\n".into(),
+    );
+    assert_eq!(parts, [part(second, header(0, 0, 8, 2))]);
+
+    let parts = absorb_hunk(&ranges, &header(3, 0, 4, 1), "@@ -3,0 +4,1 @@\n+a\n".into());
+    assert_eq!(
+        parts,
+        [unabsorbed(header(0, 0, 4, 1))],
+        "lines between unchanged lines don't belong to any commit"
+    );
+    Ok(())
+}
+
+#[test]
+fn insertions_between_lines_of_different_commits_are_not_absorbed() -> anyhow::Result<()> {
+    let (_, first, second) = ranges()?;
+    let path = BString::from("file");
+    let ranges = WorkspaceRanges::try_from_stacks(vec![InputStack {
+        stack_id: first.stack_id,
+        commits_from_base_to_tip: [(first, 2), (second, 3)]
+            .into_iter()
+            .map(|(lock, line)| InputCommit {
+                commit_id: lock.commit_id,
+                files: vec![InputFile {
+                    path: path.clone(),
+                    change_type: TreeStatusKind::Modification,
+                    hunks: vec![InputDiffHunk {
+                        old_start: line,
+                        old_lines: 1,
+                        new_start: line,
+                        new_lines: 1,
+                    }],
+                }],
+            })
+            .collect(),
+    }])?;
+    let ranges = &ranges.ranges_by_path_map()[&path];
+
+    let parts = absorb_hunk(ranges, &header(2, 0, 3, 1), "@@ -2,0 +3,1 @@\n+a\n".into());
+    assert_eq!(parts, [unabsorbed(header(0, 0, 3, 1))]);
+    Ok(())
+}
+
+fn header(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> HunkHeader {
+    HunkHeader {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+    }
+}
+
+fn part(target: HunkLock, header: HunkHeader) -> LineAbsorption {
+    LineAbsorption {
+        target: Some(target),
+        header,
+    }
+}
+
+fn unabsorbed(header: HunkHeader) -> LineAbsorption {
+    LineAbsorption {
+        target: None,
+        header,
+    }
+}
+
+fn id_from_hex_char(hex_char: char) -> gix::ObjectId {
+    gix::ObjectId::from_hex(String::from_iter(std::iter::repeat_n(hex_char, 40)).as_bytes())
+        .expect("input char is hex-only")
+}
@@ -125,6 +125,7 @@
 //!
 //! In theory, would have to merge the parents, and diff it against the commit. That bears the risk of a conflict (that has been resolved in the commit),
 //! so in that case it should be fine to fallback to using the first parent.
+pub mod absorb;
 mod input;
 
 use anyhow::Context as _;
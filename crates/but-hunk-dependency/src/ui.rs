@@ -11,18 +11,26 @@ pub fn hunk_dependencies_for_changes(
 ) -> anyhow::Result<HunkDependencies> {
     // accelerate tree-tree-diffs
     let repo = ctx.clone_repo_for_merging_non_persisting()?;
+    let ranges = workspace_ranges(ctx, &repo)?;
+    HunkDependencies::try_from_workspace_ranges(&repo, ranges, changes)
+}
+
+/// Compute the ranges of all commits in all stacks of the workspace, reading commits from `repo`.
+pub fn workspace_ranges(
+    ctx: &Context,
+    repo: &gix::Repository,
+) -> anyhow::Result<crate::WorkspaceRanges> {
     let project_data_dir = &ctx.project_data_dir();
     let meta = but_meta::VirtualBranchesTomlMetadata::from_path(
         ctx.legacy_project.gb_dir().join("virtual_branches.toml"),
     )?;
-    let stacks = but_workspace::legacy::stacks_v3(&repo, &meta, Default::default(), None)?;
+    let stacks = but_workspace::legacy::stacks_v3(repo, &meta, Default::default(), None)?;
     let common_merge_base = gitbutler_stack::VirtualBranchesHandle::new(project_data_dir)
         .get_default_target()?
         .sha;
     let input_stacks =
-        crate::workspace_stacks_to_input_stacks(&repo, &stacks, common_merge_base.to_gix())?;
-    let ranges = crate::WorkspaceRanges::try_from_stacks(input_stacks)?;
-    HunkDependencies::try_from_workspace_ranges(&repo, ranges, changes)
+        crate::workspace_stacks_to_input_stacks(repo, &stacks, common_merge_base.to_gix())?;
+    crate::WorkspaceRanges::try_from_stacks(input_stacks)
 }
 
 /// Compute hunk-dependencies for the UI knowing the `worktree_dir` for changes
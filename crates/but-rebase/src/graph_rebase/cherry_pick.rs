@@ -1,6 +1,6 @@
 //! Cherry picking generalised for N to M cases.
 
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf};
 
 use anyhow::{Context as _, Result, bail};
 use bstr::BString;
@@ -43,6 +43,10 @@ pub enum CherryPickOutcome {
 ///
 /// Except in the case where X is conflicted. In that case we then make use of
 /// X's "base" sub-tree as the base.
+///
+/// If X is a merge commit, it is re-merged instead so conflicts
+/// between its parents that were resolved in X don't prevent it from being
+/// picked.
 pub fn cherry_pick(
     repo: &gix::Repository,
     target: gix::ObjectId,
@@ -55,6 +59,10 @@ pub fn cherry_pick(
         return Ok(CherryPickOutcome::Identity(target.id.detach()));
     }
 
+    if target.parents.len() > 1 {
+        return remerge(repo, target, ontos, sign_if_configured);
+    }
+
     let base_t = find_base_tree(&target)?;
     // We always want the "theirs-ist" side of the target if it's conflicted.
     let target_t = find_real_tree(&target, TreeKind::Theirs)?;
@@ -94,12 +102,12 @@ pub fn cherry_pick(
 
             let conflict_kind = gix::merge::tree::TreatAsUnresolved::forced_resolution();
             if outcome.has_unresolved_conflicts(conflict_kind) {
+                let conflicted_files = extract_conflicted_files(tree_id, outcome, conflict_kind)?;
                 let conflicted_commit = commit_from_conflicted_tree(
                     ontos,
                     target,
                     tree_id,
-                    outcome,
-                    conflict_kind,
+                    conflicted_files,
                     base_t,
                     onto_t,
                     target_t.detach(),
@@ -118,6 +126,94 @@ pub fn cherry_pick(
     }
 }
 
+/// Re-create the merge commit `target` on top of `ontos`.
+///
+/// The author of a merge commit typically resolved conflicts between its
+/// parents, which is why the parents can't be merged with the fail-fast
+/// strategy a regular cherry-pick uses. Instead, both the original parents and
+/// `ontos` are merged with conflicts forcefully resolved. The difference between
+/// the forced merge of the original parents and `target` is the resolution of the
+/// author, which is then applied to the forced merge of `ontos`.
+///
+/// If `ontos` conflict in paths that didn't conflict between the original parents,
+/// there is no resolution to re-apply, and a conflicted commit is produced to
+/// let the user resolve them.
+fn remerge(
+    repo: &gix::Repository,
+    target: but_core::Commit<'_>,
+    ontos: &[gix::ObjectId],
+    sign_if_configured: bool,
+) -> Result<CherryPickOutcome> {
+    let empty_tree = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    // A conflicted merge already knows what it was based on, but not which of
+    // the conflicts of its parents were resolved. Treat all of them as new.
+    let (base_t, resolved_paths) = if target.is_conflicted() {
+        (
+            find_real_tree(&target, TreeKind::Base)?.detach(),
+            BTreeSet::new(),
+        )
+    } else {
+        let base = tree_from_force_merging_commits(repo, &target.parents)?;
+        (base.tree.unwrap_or(empty_tree), base.conflicting_paths)
+    };
+    let target_t = find_real_tree(&target, TreeKind::Theirs)?;
+    let onto = tree_from_force_merging_commits(repo, ontos)?;
+    let onto_t = onto.tree.unwrap_or(empty_tree);
+    let unresolved_paths = onto
+        .conflicting_paths
+        .difference(&resolved_paths)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut outcome = repo.merge_trees(
+        base_t,
+        onto_t,
+        target_t,
+        repo.default_merge_labels(),
+        repo.merge_options_force_ours()?,
+    )?;
+    let tree_id = outcome.tree.write()?;
+
+    let conflict_kind = gix::merge::tree::TreatAsUnresolved::forced_resolution();
+    let has_conflicts = outcome.has_unresolved_conflicts(conflict_kind);
+    if !has_conflicts && unresolved_paths.is_empty() {
+        return Ok(CherryPickOutcome::Commit(
+            commit_from_unconflicted_tree(ontos, target, tree_id, sign_if_configured)?.detach(),
+        ));
+    }
+
+    let mut conflicted_files = if has_conflicts {
+        extract_conflicted_files(tree_id, outcome, conflict_kind)?
+    } else {
+        ConflictEntries::default()
+    };
+    for path in unresolved_paths {
+        let path = gix::path::from_bstring(path);
+        for entries in [
+            &mut conflicted_files.our_entries,
+            &mut conflicted_files.their_entries,
+        ] {
+            if !entries.contains(&path) {
+                entries.push(path.clone());
+            }
+        }
+    }
+
+    let conflicted_commit = commit_from_conflicted_tree(
+        ontos,
+        target,
+        tree_id,
+        conflicted_files,
+        base_t,
+        onto_t,
+        target_t.detach(),
+        sign_if_configured,
+    )?;
+    Ok(CherryPickOutcome::ConflictedCommit(
+        conflicted_commit.detach(),
+    ))
+}
+
 #[derive(Debug, Clone, Copy)]
 enum MergeOutcome {
     Success(gix::ObjectId),
@@ -156,20 +252,7 @@ fn tree_from_merging_commits(
         return Ok(MergeOutcome::NoCommit);
     };
     let mut sum = find_real_tree(&but_core::Commit::from_id(sum.attach(repo))?, preference)?;
-
-    let base_tree = match repo.merge_base_octopus(commits.to_owned()) {
-        Ok(oid) => {
-            let commit = but_core::Commit::from_id(oid)?;
-            find_real_tree(&commit, TreeKind::AutoResolution)?.detach()
-        }
-        // It's very possible we'll see scenarios where there are two parents
-        // that have no common ancestor. We should handle that well by using the
-        // empty tree as the base.
-        Err(gix::repository::merge_base_octopus::Error::MergeBaseOctopus(
-            gix::repository::merge_base_octopus_with_graph::Error::NoMergeBase,
-        )) => gix::ObjectId::empty_tree(gix::hash::Kind::Sha1),
-        Err(e) => bail!(e),
-    };
+    let base_tree = merge_base_tree(repo, commits)?;
 
     while let Some(commit) = to_merge.pop() {
         let commit = but_core::Commit::from_id(commit.attach(repo))?;
@@ -189,6 +272,82 @@ fn tree_from_merging_commits(
     Ok(MergeOutcome::Success(sum.detach()))
 }
 
+/// The result of merging many commits with conflicts forcefully resolved.
+struct ForcedMerge {
+    /// The merged tree, or `None` if there was nothing to merge.
+    tree: Option<gix::ObjectId>,
+    /// The paths that conflicted and were resolved in favor of ours.
+    conflicting_paths: BTreeSet<BString>,
+}
+
+/// Like [`tree_from_merging_commits()`], but resolves conflicts in favor of
+/// ours instead of failing, and keeps track of which paths were affected.
+fn tree_from_force_merging_commits(
+    repo: &gix::Repository,
+    commits: &[gix::ObjectId],
+) -> Result<ForcedMerge> {
+    let mut conflicting_paths = BTreeSet::new();
+    let mut to_merge = commits.to_vec();
+    let Some(sum) = to_merge.pop() else {
+        return Ok(ForcedMerge {
+            tree: None,
+            conflicting_paths,
+        });
+    };
+    let mut sum = find_real_tree(
+        &but_core::Commit::from_id(sum.attach(repo))?,
+        TreeKind::AutoResolution,
+    )?;
+    let base_tree = merge_base_tree(repo, commits)?;
+
+    let conflict_kind = gix::merge::tree::TreatAsUnresolved::forced_resolution();
+    while let Some(commit) = to_merge.pop() {
+        let commit = but_core::Commit::from_id(commit.attach(repo))?;
+        let tree = find_real_tree(&commit, TreeKind::AutoResolution)?;
+
+        let mut output = repo.merge_trees(
+            base_tree,
+            sum,
+            tree,
+            repo.default_merge_labels(),
+            repo.merge_options_force_ours()?,
+        )?;
+        for conflict in output
+            .conflicts
+            .iter()
+            .filter(|c| c.is_unresolved(conflict_kind))
+        {
+            let (ours, theirs) = conflict.changes_in_resolution();
+            conflicting_paths.insert(ours.location().to_owned());
+            conflicting_paths.insert(theirs.location().to_owned());
+        }
+
+        sum = output.tree.write()?;
+    }
+
+    Ok(ForcedMerge {
+        tree: Some(sum.detach()),
+        conflicting_paths,
+    })
+}
+
+/// Find the tree of the merge-base of all `commits`.
+fn merge_base_tree(repo: &gix::Repository, commits: &[gix::ObjectId]) -> Result<gix::ObjectId> {
+    Ok(match repo.merge_base_octopus(commits.to_owned()) {
+        Ok(oid) => {
+            let commit = but_core::Commit::from_id(oid)?;
+            find_real_tree(&commit, TreeKind::AutoResolution)?.detach()
+        }
+        // It's very possible we'll see scenarios where there are two parents
+        // that have no common ancestor. We should handle that well by using the
+        // empty tree as the base.
+        Err(gix::repository::merge_base_octopus::Error::MergeBaseOctopus(
+            gix::repository::merge_base_octopus_with_graph::Error::NoMergeBase,
+        )) => gix::ObjectId::empty_tree(gix::hash::Kind::Sha1),
+        Err(e) => bail!(e),
+    })
+}
+
 fn find_real_tree<'repo>(
     commit: &but_core::Commit<'repo>,
     side: TreeKind,
@@ -248,8 +407,7 @@ fn commit_from_conflicted_tree<'repo>(
     parents: &[gix::ObjectId],
     mut to_rebase: but_core::Commit<'repo>,
     resolved_tree_id: gix::Id<'repo>,
-    cherry_pick: gix::merge::tree::Outcome<'_>,
-    treat_as_unresolved: gix::merge::tree::TreatAsUnresolved,
+    conflicted_files: ConflictEntries,
     base_tree_id: gix::ObjectId,
     ours_tree_id: gix::ObjectId,
     theirs_tree_id: gix::ObjectId,
@@ -261,9 +419,6 @@ fn commit_from_conflicted_tree<'repo>(
         b"You have checked out a GitButler Conflicted commit. You probably didn't mean to do this.";
     let readme_blob = repo.write_blob(readme_content)?;
 
-    let conflicted_files =
-        extract_conflicted_files(resolved_tree_id, cherry_pick, treat_as_unresolved)?;
-
     // convert files into a string and save as a blob
     let conflicted_files_string = toml::to_string(&conflicted_files)?;
     let conflicted_files_blob = repo.write_blob(conflicted_files_string.as_bytes())?;
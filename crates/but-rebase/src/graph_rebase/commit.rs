@@ -5,7 +5,7 @@ use gix::prelude::ObjectIdExt;
 
 use crate::{
     commit::{DateMode, create},
-    graph_rebase::Editor,
+    graph_rebase::{Editor, SuccessfulRebase},
 };
 
 impl Editor {
@@ -63,3 +63,11 @@ impl Editor {
         })
     }
 }
+
+impl SuccessfulRebase {
+    /// Returns a reference to the in-memory repository, which has all objects of the rebase
+    /// even before it's [materialized](Self::materialize()).
+    pub fn repo(&self) -> &gix::Repository {
+        &self.repo
+    }
+}
@@ -87,18 +87,28 @@ impl GraphExt for Graph {
 
         let mut head_selectors = vec![];
 
+        // Parents of merge commits that weren't traversed while some of their
+        // siblings were.
+        let mut boundary_parents = Vec::new();
+
         for c in &commits {
             let has_no_parents = c.parent_ids.is_empty();
-            let missing_parent_steps = c.parent_ids.iter().any(|p| !commit_ids.contains(p));
+            let missing_parents = c
+                .parent_ids
+                .iter()
+                .filter(|p| !commit_ids.contains(p))
+                .collect::<Vec<_>>();
 
             // If the commit has parents in the commit graph, but none of
             // them are in the graph, this means but-graph did a partial
             // traversal and we want to preserve the commit as it is.
-            let preserved_parents = if !has_no_parents && missing_parent_steps {
-                Some(c.parent_ids.clone())
-            } else {
-                None
-            };
+            let preserved_parents =
+                if !has_no_parents && missing_parents.len() == c.parent_ids.len() {
+                    Some(c.parent_ids.clone())
+                } else {
+                    boundary_parents.extend(missing_parents.into_iter().copied());
+                    None
+                };
 
             let mut pick = if Some(c.id) == workspace_commit_id {
                 Pick::new_workspace_pick(c.id)
@@ -136,6 +146,30 @@ impl GraphExt for Graph {
             );
         }
 
+        // If only some parents of a merge commit are in the graph, the merge
+        // must still be re-merged with the other parents as they are. These are
+        // represented as picks that keep their own parents, so they never change.
+        for id in boundary_parents {
+            if steps_for_commits.contains_key(&id) {
+                continue;
+            }
+            let parents = repo
+                .find_commit(id)?
+                .parent_ids()
+                .map(|id| id.detach())
+                .collect();
+            let mut pick = Pick::new_pick(id);
+            pick.preserved_parents = Some(parents);
+            let ni = graph.add_node(Step::Pick(pick));
+            steps_for_commits.insert(
+                id,
+                StepChain {
+                    top: ni,
+                    bottom: ni,
+                },
+            );
+        }
+
         for c in commits {
             for (i, p) in c.parent_ids.iter().enumerate() {
                 if let (Some(StepChain { bottom, .. }), Some(StepChain { top, .. })) =
@@ -15,6 +15,7 @@ pub mod cherry_pick;
 pub mod commit;
 pub mod materialize;
 pub mod mutate;
+pub mod todo;
 pub(crate) mod util;
 
 /// Utilities for testing
@@ -88,8 +88,9 @@ impl Editor {
                             new_idx
                         }
                         CherryPickOutcome::FailedToMergeBases => {
-                            // Exit early - the rebase failed because it encountered a commit it couldn't pick
-                            // TODO(CTO): Detect if this was the merge commit itself & signal that seperatly
+                            // Exit early - the rebase failed because it encountered a commit it couldn't pick.
+                            // Merge commits are re-merged instead, so this can only be a commit that
+                            // gained conflicting parents.
                             bail!("Failed to merge bases for commit {}", pick.id);
                         }
                     }
@@ -0,0 +1,522 @@
+//! A git-style todo list to edit a first-parent chain of [steps](Step) as text, like `git rebase -i` does.
+//!
+//! The list is [obtained](Editor::todo_list()) from the editor, edited by the user, and then [parsed](TodoList::parse())
+//! and validated against the original list before it's [applied](Editor::apply_todo_list()) to the editor.
+//! Merge commits are picked as a whole, and keep all of their parents but the first one.
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteVec};
+use gix::prelude::ObjectIdExt;
+use petgraph::{Direction, visit::EdgeRef};
+
+use crate::{
+    commit::DateMode,
+    graph_rebase::{
+        Edge, Editor, Pick, Selector, Step, StepGraph, StepGraphIndex,
+        cherry_pick::{CherryPickOutcome, cherry_pick},
+    },
+};
+
+/// A single line in a [`TodoList`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TodoItem {
+    /// Use the commit as is.
+    Pick {
+        /// The commit to pick.
+        commit_id: gix::ObjectId,
+    },
+    /// Use the commit, but change its message.
+    Reword {
+        /// The commit to pick.
+        commit_id: gix::ObjectId,
+        /// The new message, or `None` if it wasn't provided yet, which keeps the current message.
+        new_message: Option<BString>,
+    },
+    /// Meld the commit into the previous one, combining both messages.
+    Squash {
+        /// The commit to squash into the previous one.
+        commit_id: gix::ObjectId,
+    },
+    /// Meld the commit into the previous one, keeping only the message of the previous one.
+    Fixup {
+        /// The commit to squash into the previous one.
+        commit_id: gix::ObjectId,
+    },
+    /// Run a shell command on the commit produced by the steps before it.
+    Exec {
+        /// The command to run, as passed to the shell.
+        command: String,
+    },
+    /// Let the reference point to the commit produced by the steps before it.
+    UpdateRef(gix::refs::FullName),
+}
+
+impl TodoItem {
+    /// Return the commit this item operates on, if there is one.
+    pub fn commit_id(&self) -> Option<gix::ObjectId> {
+        match self {
+            TodoItem::Pick { commit_id }
+            | TodoItem::Reword { commit_id, .. }
+            | TodoItem::Squash { commit_id }
+            | TodoItem::Fixup { commit_id } => Some(*commit_id),
+            TodoItem::Exec { .. } | TodoItem::UpdateRef(_) => None,
+        }
+    }
+}
+
+/// A list of instructions, from the parent-most commit to the top-most one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TodoList {
+    /// The instructions to execute, in order.
+    pub items: Vec<TodoItem>,
+}
+
+/// A command to run on a commit once the rebase is done, as returned by [`Editor::apply_todo_list()`].
+#[derive(Debug, Clone)]
+pub struct Exec {
+    /// The pick of the commit to run the command on, to be looked up in the rebase outcome.
+    pub commit: Selector,
+    /// The command to run, as passed to the shell.
+    pub command: String,
+}
+
+const HELP: &str = "
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but keep only the previous commit's message
+# x, exec <command> = run command (the rest of the line) using shell
+# d, drop <commit> = remove commit
+# u, update-ref <branch> = let <branch> point to the commit above it
+#
+# These lines can be re-ordered; they are executed from top to bottom.
+#
+# If you remove a line here THAT COMMIT WILL BE LOST.
+# Branches can be moved, but not removed.
+";
+
+/// Lifecycle
+impl TodoList {
+    /// Parse `text` as edited version of `original`, and validate it.
+    ///
+    /// Commits may be referred to by any unambiguous prefix of their hash, but they must be part of `original`,
+    /// and each of them may only be used once. Commits of `original` that aren't mentioned are dropped.
+    /// All references of `original` must remain in the list, exactly once.
+    pub fn parse(text: &str, original: &TodoList) -> Result<Self> {
+        let known_commits: Vec<_> = original
+            .items
+            .iter()
+            .filter_map(TodoItem::commit_id)
+            .collect();
+        let known_refs: Vec<_> = original
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                TodoItem::UpdateRef(r) => Some(r),
+                _ => None,
+            })
+            .collect();
+
+        let mut items = Vec::new();
+        let mut seen_commits = BTreeSet::new();
+        let mut seen_refs = BTreeSet::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (command, rest) = line
+                .split_once(char::is_whitespace)
+                .map(|(cmd, rest)| (cmd, rest.trim()))
+                .unwrap_or((line, ""));
+
+            let mut commit = || -> Result<gix::ObjectId> {
+                let token = rest.split_whitespace().next().with_context(|| {
+                    format!("Line {line_no}: '{command}' needs a commit to work on")
+                })?;
+                let id = resolve_commit(token, &known_commits)
+                    .with_context(|| format!("Line {line_no}: invalid commit"))?;
+                if !seen_commits.insert(id) {
+                    bail!("Line {line_no}: commit {token} is used more than once");
+                }
+                Ok(id)
+            };
+
+            let item = match command {
+                "p" | "pick" => TodoItem::Pick {
+                    commit_id: commit()?,
+                },
+                "r" | "reword" => TodoItem::Reword {
+                    commit_id: commit()?,
+                    new_message: None,
+                },
+                "s" | "squash" | "f" | "fixup" => {
+                    let commit_id = commit()?;
+                    let has_target = items
+                        .last()
+                        .is_some_and(|item: &TodoItem| item.commit_id().is_some());
+                    if !has_target {
+                        bail!(
+                            "Line {line_no}: '{command}' needs a commit right before it to meld into"
+                        );
+                    }
+                    if command.starts_with('s') {
+                        TodoItem::Squash { commit_id }
+                    } else {
+                        TodoItem::Fixup { commit_id }
+                    }
+                }
+                "d" | "drop" => {
+                    commit()?;
+                    continue;
+                }
+                "x" | "exec" => {
+                    if rest.is_empty() {
+                        bail!("Line {line_no}: 'exec' needs a command to run");
+                    }
+                    TodoItem::Exec {
+                        command: rest.to_owned(),
+                    }
+                }
+                "u" | "update-ref" => {
+                    let Some(reference) = known_refs
+                        .iter()
+                        .find(|r| r.shorten() == rest || r.as_bstr() == rest)
+                    else {
+                        bail!("Line {line_no}: '{rest}' isn't a branch of this stack");
+                    };
+                    if !seen_refs.insert((*reference).clone()) {
+                        bail!("Line {line_no}: branch '{rest}' is used more than once");
+                    }
+                    TodoItem::UpdateRef((*reference).clone())
+                }
+                unknown => bail!("Line {line_no}: unknown command '{unknown}'"),
+            };
+            items.push(item);
+        }
+
+        if let Some(missing) = known_refs.iter().copied().find(|r| !seen_refs.contains(*r)) {
+            bail!(
+                "Branch '{missing}' must remain in the todo list",
+                missing = missing.shorten()
+            );
+        }
+        Ok(TodoList { items })
+    }
+}
+
+/// Conversions
+impl TodoList {
+    /// Render this list as text for editing by the user, with commits abbreviated and annotated
+    /// with their subject, followed by instructions in comments.
+    pub fn render(&self, repo: &gix::Repository) -> Result<String> {
+        let mut out = String::new();
+        for item in &self.items {
+            let (command, commit_id) = match item {
+                TodoItem::Pick { commit_id } => ("pick", commit_id),
+                TodoItem::Reword { commit_id, .. } => ("reword", commit_id),
+                TodoItem::Squash { commit_id } => ("squash", commit_id),
+                TodoItem::Fixup { commit_id } => ("fixup", commit_id),
+                TodoItem::Exec { command } => {
+                    out.push_str(&format!("exec {command}\n"));
+                    continue;
+                }
+                TodoItem::UpdateRef(reference) => {
+                    out.push_str(&format!("update-ref {}\n", reference.shorten()));
+                    continue;
+                }
+            };
+            let commit = repo.find_commit(*commit_id)?;
+            out.push_str(&format!(
+                "{command} {short_id} {subject}\n",
+                short_id = commit_id.attach(repo).shorten_or_id(),
+                subject = commit.message()?.summary()
+            ));
+        }
+        out.push_str(HELP);
+        Ok(out)
+    }
+}
+
+/// Todo lists
+impl Editor {
+    /// Return the todo list of the first-parent chain from the reference `tip` down to the commit `base`,
+    /// which is excluded as it isn't rewritten. References that point to `base` are excluded as well.
+    pub fn todo_list(&self, tip: &gix::refs::FullNameRef, base: gix::ObjectId) -> Result<TodoList> {
+        let mut items = Vec::new();
+        let mut node = Some(self.select_reference(tip)?.id);
+        loop {
+            let current = node.with_context(|| {
+                format!(
+                    "Commit {base} isn't reachable from '{tip}' by following first parents",
+                    tip = tip.shorten()
+                )
+            })?;
+            match &self.graph[current] {
+                Step::Pick(Pick { id, .. }) if *id == base => break,
+                Step::Pick(Pick { id, .. }) => items.push(TodoItem::Pick { commit_id: *id }),
+                Step::Reference { refname } => items.push(TodoItem::UpdateRef(refname.clone())),
+                Step::None => {}
+            }
+            node = first_parent(&self.graph, current);
+        }
+
+        let refs_on_base = items
+            .iter()
+            .rev()
+            .take_while(|item| matches!(item, TodoItem::UpdateRef(_)))
+            .count();
+        items.truncate(items.len() - refs_on_base);
+        items.reverse();
+        Ok(TodoList { items })
+    }
+
+    /// Rearrange the chain of steps that `original` was [obtained](Self::todo_list()) from so it matches `edited`,
+    /// which was [parsed](TodoList::parse()) from `original`.
+    ///
+    /// Squashed and reworded commits are created right away, while the rearranged commits are picked once
+    /// the editor is [rebased](Self::rebase()). Whatever built on top of the chain then builds on its new top.
+    ///
+    /// Return the commands to run, along with the commit they run on after the rebase.
+    ///
+    /// # Errors
+    /// - If the chain changed since `original` was obtained.
+    /// - If a dropped or squashed commit is the parent of a commit outside the chain, or if it's a merge commit.
+    /// - If a squash conflicts with the commit it's melded into.
+    pub fn apply_todo_list(&mut self, original: &TodoList, edited: &TodoList) -> Result<Vec<Exec>> {
+        let chain = original
+            .items
+            .iter()
+            .filter_map(|item| self.select_item(item).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        let (Some(&bottom), Some(&top)) = (chain.first(), chain.last()) else {
+            bail!("There is nothing to rebase");
+        };
+        if chain
+            .windows(2)
+            .any(|pair| first_parent(&self.graph, pair[1]) != Some(pair[0]))
+        {
+            bail!("The todo list doesn't match the commits to rebase");
+        }
+        let below = first_parent(&self.graph, bottom);
+
+        // Detach the chain, but keep all other parents of merge commits, and whatever builds on commits in the chain.
+        let above = self
+            .graph
+            .edges_directed(top, Direction::Incoming)
+            .map(|e| (e.id(), e.source(), e.weight().clone()))
+            .collect::<Vec<_>>();
+        for (edge_id, _, _) in &above {
+            self.graph.remove_edge(*edge_id);
+        }
+        for node in &chain {
+            let first_parent_edges = self
+                .graph
+                .edges_directed(*node, Direction::Outgoing)
+                .filter(|e| e.weight().order == 0)
+                .map(|e| e.id())
+                .collect::<Vec<_>>();
+            for edge_id in first_parent_edges {
+                self.graph.remove_edge(edge_id);
+            }
+        }
+
+        let kept: HashSet<_> = edited
+            .items
+            .iter()
+            .filter(|item| !matches!(item, TodoItem::Squash { .. } | TodoItem::Fixup { .. }))
+            .filter_map(TodoItem::commit_id)
+            .collect();
+        for item in &original.items {
+            if let Some(commit_id) = item.commit_id()
+                && !kept.contains(&commit_id)
+            {
+                self.remove_pick(commit_id)?;
+            }
+        }
+
+        let mut execs = Vec::new();
+        let mut prev = below;
+        let mut last_pick = below.and_then(|node| self.first_pick_at_or_below(node));
+        let mut items = edited.items.iter().peekable();
+        while let Some(item) = items.next() {
+            let node = match item {
+                TodoItem::Exec { command } => {
+                    let commit = last_pick
+                        .with_context(|| format!("There is no commit to run '{command}' on"))?;
+                    execs.push(Exec {
+                        commit: self.selector(commit),
+                        command: command.clone(),
+                    });
+                    continue;
+                }
+                TodoItem::UpdateRef(refname) => self.select_reference(refname.as_ref())?.id,
+                TodoItem::Squash { .. } | TodoItem::Fixup { .. } => {
+                    bail!(
+                        "BUG: squashes and fixups are consumed by the commit they are melded into"
+                    )
+                }
+                TodoItem::Pick { commit_id } | TodoItem::Reword { commit_id, .. } => {
+                    let node = self.select_commit(*commit_id)?.id;
+                    let mut message = match item {
+                        TodoItem::Reword {
+                            new_message: Some(message),
+                            ..
+                        } => Some(message.clone()),
+                        _ => None,
+                    };
+                    let mut melded = Vec::new();
+                    while let Some(next @ (TodoItem::Squash { .. } | TodoItem::Fixup { .. })) =
+                        items.peek()
+                    {
+                        let melded_id = next.commit_id().context("BUG: squashes have commits")?;
+                        if let TodoItem::Squash { .. } = next {
+                            let mut combined = match message.take() {
+                                Some(message) => message,
+                                None => self.find_commit(*commit_id)?.message.clone(),
+                            };
+                            if !combined.ends_with(b"\n") {
+                                combined.push(b'\n');
+                            }
+                            combined.push(b'\n');
+                            combined.push_str(&self.find_commit(melded_id)?.message);
+                            message = Some(combined);
+                        }
+                        melded.push(melded_id);
+                        items.next();
+                    }
+                    self.rewrite_pick(node, &melded, message)?;
+                    last_pick = Some(node);
+                    node
+                }
+            };
+            if let Some(prev) = prev {
+                self.graph.add_edge(node, prev, Edge { order: 0 });
+            }
+            prev = Some(node);
+        }
+
+        if let Some(new_top) = prev {
+            for (_, source, weight) in above {
+                self.graph.add_edge(source, new_top, weight);
+            }
+        }
+        if petgraph::algo::is_cyclic_directed(&self.graph) {
+            bail!("The todo list would make a commit its own ancestor");
+        }
+        Ok(execs)
+    }
+}
+
+impl Editor {
+    fn select_item(&self, item: &TodoItem) -> Result<Option<StepGraphIndex>> {
+        Ok(match item {
+            TodoItem::Exec { .. } => None,
+            TodoItem::UpdateRef(refname) => Some(self.select_reference(refname.as_ref())?.id),
+            TodoItem::Pick { commit_id }
+            | TodoItem::Reword { commit_id, .. }
+            | TodoItem::Squash { commit_id }
+            | TodoItem::Fixup { commit_id } => Some(self.select_commit(*commit_id)?.id),
+        })
+    }
+
+    fn selector(&self, id: StepGraphIndex) -> Selector {
+        Selector {
+            id,
+            revision: self.history.current_revision(),
+        }
+    }
+
+    fn first_pick_at_or_below(&self, mut node: StepGraphIndex) -> Option<StepGraphIndex> {
+        loop {
+            if let Step::Pick(_) = self.graph[node] {
+                return Some(node);
+            }
+            node = first_parent(&self.graph, node)?;
+        }
+    }
+
+    /// Replace the pick of `commit_id` with a placeholder, which fails if anything still builds on it.
+    fn remove_pick(&mut self, commit_id: gix::ObjectId) -> Result<()> {
+        let node = self.select_commit(commit_id)?.id;
+        if self
+            .graph
+            .edges_directed(node, Direction::Incoming)
+            .next()
+            .is_some()
+        {
+            bail!("Commit {commit_id} can't be removed as other commits build on it");
+        }
+        if self
+            .graph
+            .edges_directed(node, Direction::Outgoing)
+            .next()
+            .is_some()
+        {
+            bail!("Merge commit {commit_id} can't be squashed or dropped");
+        }
+        self.graph[node] = Step::None;
+        Ok(())
+    }
+
+    /// Meld the commits in `melded` into the pick at `node`, and use `message` for the result if it's set.
+    fn rewrite_pick(
+        &mut self,
+        node: StepGraphIndex,
+        melded: &[gix::ObjectId],
+        message: Option<BString>,
+    ) -> Result<()> {
+        if melded.is_empty() && message.is_none() {
+            return Ok(());
+        }
+        let Step::Pick(mut pick) = self.graph[node].clone() else {
+            bail!("BUG: expected a pick");
+        };
+        let mut tree = self.find_commit(pick.id)?.tree;
+        let mut onto = pick.id;
+        for commit_id in melded {
+            let picked = match cherry_pick(&self.repo, *commit_id, &[onto], false)? {
+                CherryPickOutcome::Commit(id) | CherryPickOutcome::Identity(id) => id,
+                CherryPickOutcome::ConflictedCommit(_) | CherryPickOutcome::FailedToMergeBases => {
+                    bail!(
+                        "Commit {commit_id} can't be melded into {into} without conflicts",
+                        into = pick.id
+                    )
+                }
+            };
+            tree = self.find_commit(picked)?.tree;
+            onto = picked;
+        }
+
+        let mut commit = self.find_commit(pick.id)?;
+        commit.tree = tree;
+        if let Some(message) = message {
+            commit.message = message;
+        }
+        pick.id = self.new_commit(commit, DateMode::CommitterUpdateAuthorKeep)?;
+        self.graph[node] = Step::Pick(pick);
+        Ok(())
+    }
+}
+
+/// Return the step `node` is based on, following the first parent.
+fn first_parent(graph: &StepGraph, node: StepGraphIndex) -> Option<StepGraphIndex> {
+    graph
+        .edges_directed(node, Direction::Outgoing)
+        .find(|e| e.weight().order == 0)
+        .map(|e| e.target())
+}
+
+fn resolve_commit(token: &str, candidates: &[gix::ObjectId]) -> Result<gix::ObjectId> {
+    let prefix = gix::hash::Prefix::from_hex(token)
+        .with_context(|| format!("'{token}' isn't a valid commit hash"))?;
+    let mut matches = candidates.iter().filter(|id| prefix.cmp_oid(id).is_eq());
+    match (matches.next(), matches.next()) {
+        (Some(id), None) => Ok(*id),
+        (None, _) => bail!("Commit {token} isn't part of the stack"),
+        (Some(_), Some(_)) => bail!("Commit {token} is ambiguous"),
+    }
+}
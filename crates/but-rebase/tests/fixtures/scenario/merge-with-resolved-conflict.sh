@@ -0,0 +1,21 @@
+#!/bin/bash
+
+set -eu -o pipefail
+
+git init
+
+echo "base" >file && git add . && git commit -m "base"
+
+git checkout -b feature
+echo "feature" >feature-file && git add . && git commit -m "feature: add a file"
+echo "feature" >file && git add . && git commit -m "feature: change file"
+
+git checkout main
+echo "main" >file && git add . && git commit -m "main: change file"
+
+git checkout feature
+set +e
+git merge main --no-edit
+set -e
+echo "resolved" >file && git add . && git commit -m "Merge branch 'main' into feature"
+echo "on top" >on-top && git add . && git commit -m "on top of merge"
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use anyhow::{Result, bail};
 use but_rebase::graph_rebase::cherry_pick::{CherryPickOutcome, cherry_pick};
 use but_testsupport::visualize_tree;
@@ -291,7 +293,12 @@ fn multiple_parents_to_single_parent_parents_conflict() -> Result<()> {
 
     let result = cherry_pick(&repo, target, &[onto], true)?;
 
-    insta::assert_debug_snapshot!(result, @"FailedToMergeBases");
+    // The resolution of the merge can't be applied as `clean-f` doesn't exist in `onto`.
+    let CherryPickOutcome::ConflictedCommit(id) = result else {
+        bail!("expected a conflicted commit, got {result:?}");
+    };
+
+    assert_eq!(&get_parents(&id.attach(&repo))?, &[onto]);
 
     Ok(())
 }
@@ -392,7 +399,50 @@ fn multiple_parents_to_multiple_parents_base_parents_conflict() -> Result<()> {
 
     let result = cherry_pick(&repo, target, &[onto, onto2], true)?;
 
-    insta::assert_debug_snapshot!(result, @"FailedToMergeBases");
+    // The resolution of the merge can't be applied as `clean-f` doesn't exist in the new parents.
+    let CherryPickOutcome::ConflictedCommit(id) = result else {
+        bail!("expected a conflicted commit, got {result:?}");
+    };
+
+    assert_eq!(&get_parents(&id.attach(&repo))?, &[onto, onto2]);
+
+    Ok(())
+}
+
+// multiple parents to multiple parents - parents conflict, but were resolved by the merge
+#[test]
+fn multiple_parents_to_multiple_parents_resolved_parents_conflict() -> Result<()> {
+    let (repo, _tmpdir, _meta) = fixture_writable("cherry-pick")?;
+
+    let target = repo.rev_parse_single("merge-clean-commit-conflicting-parents")?;
+    let parents = get_parents(&target)?;
+
+    // Move the first parent, which still conflicts with the second parent in the same way.
+    let onto = repo.rev_parse_single("single-target")?.detach();
+    let CherryPickOutcome::Commit(first_parent) = cherry_pick(&repo, parents[0], &[onto], true)?
+    else {
+        bail!("impossible");
+    };
+
+    let result = cherry_pick(&repo, target.detach(), &[first_parent, parents[1]], true)?;
+
+    // The previous resolution of `clean-f` is re-applied.
+    let CherryPickOutcome::Commit(id) = result else {
+        bail!("expected a clean commit, got {result:?}");
+    };
+
+    assert_eq!(
+        &get_parents(&id.attach(&repo))?,
+        &[first_parent, parents[1]]
+    );
+
+    insta::assert_snapshot!(visualize_tree(id.attach(&repo)), @r#"
+    221b6a7
+    ├── base-f:100644:7898192 "a\n"
+    ├── clean-commit-f:100644:20a3acd "clean-commit\n"
+    ├── clean-f:100644:2ab19ae "resolved\n"
+    └── target-f:100644:eb5a316 "target\n"
+    "#);
 
     Ok(())
 }
@@ -407,7 +457,19 @@ fn multiple_parents_to_multiple_parents_target_parents_conflict() -> Result<()>
 
     let result = cherry_pick(&repo, target, &[onto, onto2], true)?;
 
-    insta::assert_debug_snapshot!(result, @"FailedToMergeBases");
+    // The new parents conflict in a way the merge didn't resolve, so it's conflicted.
+    let CherryPickOutcome::ConflictedCommit(id) = result else {
+        bail!("expected a conflicted commit, got {result:?}");
+    };
+
+    assert_eq!(&get_parents(&id.attach(&repo))?, &[onto, onto2]);
+
+    let commit = but_core::Commit::from_id(id.attach(&repo))?;
+    let entries = commit
+        .conflict_entries()?
+        .expect("the commit is conflicted");
+    assert_eq!(entries.our_entries, [PathBuf::from("target-f")]);
+    assert_eq!(entries.their_entries, [PathBuf::from("target-f")]);
 
     Ok(())
 }
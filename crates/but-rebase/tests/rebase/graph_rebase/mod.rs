@@ -7,4 +7,5 @@ mod parents_must_be_references_restriction;
 mod rebase_identities;
 mod replace;
 mod signing_preferences;
+mod todo;
 mod workspace_commit_behaviour;
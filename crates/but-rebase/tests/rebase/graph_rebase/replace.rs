@@ -3,6 +3,7 @@ use anyhow::{Context, Result};
 use but_graph::Graph;
 use but_rebase::graph_rebase::{GraphExt, Step};
 use but_testsupport::{git_status, visualize_commit_graph_all, visualize_tree};
+use gix::prelude::ObjectIdExt as _;
 
 use crate::utils::{fixture_writable, standard_options};
 
@@ -150,6 +151,60 @@ fn amend_a_commit() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn reword_a_commit_below_merge_with_resolved_conflict() -> Result<()> {
+    let (repo, _tmpdir, meta) = fixture_writable("merge-with-resolved-conflict")?;
+
+    insta::assert_snapshot!(visualize_commit_graph_all(&repo)?, @r"
+    * f85448a (HEAD -> feature) on top of merge
+    *   4dd4e8b Merge branch 'main' into feature
+    |\  
+    | * 0d84623 (main) main: change file
+    * | 2d4a92c feature: change file
+    * | 67ce795 feature: add a file
+    |/  
+    * 7674a5e base
+    ");
+    insta::assert_snapshot!(git_status(&repo)?, @"");
+
+    let head_tree = repo.head_tree()?.id;
+    let main = repo.rev_parse_single("main")?.detach();
+
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?.validated()?;
+
+    let mut editor = graph.to_editor(&repo)?;
+
+    // reword the first commit of the feature branch, below the merge
+    let first = repo.rev_parse_single("feature~3")?.detach();
+    let mut first_obj = but_core::Commit::from_id(first.attach(&repo))?;
+    first_obj.message = "feature: add a file, reworded".into();
+    let first_new = repo.write_object(first_obj.inner)?.detach();
+
+    let first_selector = editor
+        .select_commit(first)
+        .context("Failed to find the first commit in editor graph")?;
+    editor.replace(first_selector, Step::new_pick(first_new))?;
+
+    // Even though the parents of the merge conflict, the resolution is re-applied.
+    let outcome = editor.rebase()?;
+    outcome.materialize()?;
+
+    assert_eq!(head_tree, repo.head_tree()?.id);
+
+    let merge = but_core::Commit::from_id(repo.rev_parse_single("feature~1")?)?;
+    assert!(!merge.is_conflicted());
+    assert_eq!(merge.parents.len(), 2, "the merge is still a merge");
+    assert_eq!(merge.parents[1], main, "the merged branch is untouched");
+    assert_eq!(
+        repo.rev_parse_single("feature~3")?.detach(),
+        first_new,
+        "the first parent leads to the reworded commit"
+    );
+    insta::assert_snapshot!(git_status(&repo)?, @"");
+
+    Ok(())
+}
+
 #[test]
 #[ignore]
 fn replaces_violating_fp_protection_should_cause_rebase_failure() -> Result<()> {
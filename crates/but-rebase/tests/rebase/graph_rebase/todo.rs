@@ -0,0 +1,291 @@
+//! These tests exercise todo lists, along with applying them to the editor.
+use anyhow::Result;
+use but_graph::Graph;
+use but_rebase::graph_rebase::{
+    GraphExt, LookupStep as _,
+    todo::{TodoItem, TodoList},
+};
+use but_testsupport::{git_status, visualize_commit_graph_all};
+
+use crate::utils::{Commits, fixture_writable, four_commits, standard_options};
+
+fn anchor() -> gix::refs::FullName {
+    "refs/heads/anchor".try_into().expect("valid")
+}
+
+fn original(commits: &Commits) -> TodoList {
+    TodoList {
+        items: vec![
+            TodoItem::Pick {
+                commit_id: commits.a,
+            },
+            TodoItem::Pick {
+                commit_id: commits.b,
+            },
+            TodoItem::UpdateRef(anchor()),
+            TodoItem::Pick {
+                commit_id: commits.c,
+            },
+        ],
+    }
+}
+
+#[test]
+fn render_and_parse_roundtrip() -> Result<()> {
+    let (repo, commits) = four_commits()?;
+    let original = original(&commits);
+    let text = original.render(&repo)?;
+    insta::assert_snapshot!(text, @r#"
+    pick d591dfe a
+    pick a96434e b
+    update-ref anchor
+    pick 120e3a9 c
+
+    # Commands:
+    # p, pick <commit> = use commit
+    # r, reword <commit> = use commit, but edit the commit message
+    # s, squash <commit> = use commit, but meld into previous commit
+    # f, fixup <commit> = like "squash", but keep only the previous commit's message
+    # x, exec <command> = run command (the rest of the line) using shell
+    # d, drop <commit> = remove commit
+    # u, update-ref <branch> = let <branch> point to the commit above it
+    #
+    # These lines can be re-ordered; they are executed from top to bottom.
+    #
+    # If you remove a line here THAT COMMIT WILL BE LOST.
+    # Branches can be moved, but not removed.
+    "#);
+    assert_eq!(TodoList::parse(&text, &original)?, original);
+    Ok(())
+}
+
+#[test]
+fn parse_edited_list() -> Result<()> {
+    let (_repo, commits) = four_commits()?;
+    let original = original(&commits);
+    let edited = TodoList::parse(
+        "r a96434e b\n\
+         f 120e3a9 c\n\
+         exec cargo test\n\
+         update-ref refs/heads/anchor\n\
+         drop d591dfe",
+        &original,
+    )?;
+    assert_eq!(
+        edited.items,
+        [
+            TodoItem::Reword {
+                commit_id: commits.b,
+                new_message: None
+            },
+            TodoItem::Fixup {
+                commit_id: commits.c
+            },
+            TodoItem::Exec {
+                command: "cargo test".into()
+            },
+            TodoItem::UpdateRef(anchor()),
+        ],
+        "branches may also be referred to by their full name"
+    );
+    Ok(())
+}
+
+#[test]
+fn squash_combines_messages_and_exec_runs_on_rewritten_commit() -> Result<()> {
+    let (repo, _tmpdir, meta) = fixture_writable("four-commits")?;
+    let (_, commits) = four_commits()?;
+
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?.validated()?;
+    let mut editor = graph.to_editor(&repo)?;
+
+    let original = editor.todo_list("refs/heads/main".try_into()?, commits.base)?;
+    assert_eq!(
+        original.items,
+        [
+            TodoItem::Pick {
+                commit_id: commits.a
+            },
+            TodoItem::Pick {
+                commit_id: commits.b
+            },
+            TodoItem::Pick {
+                commit_id: commits.c
+            },
+            TodoItem::UpdateRef("refs/heads/main".try_into()?),
+        ]
+    );
+
+    let mut edited = TodoList::parse(
+        "reword d591dfe\n\
+         squash a96434e\n\
+         exec make\n\
+         pick 120e3a9\n\
+         update-ref main\n",
+        &original,
+    )?;
+    if let TodoItem::Reword { new_message, .. } = &mut edited.items[0] {
+        *new_message = Some("reworded a\n".into());
+    }
+
+    let execs = editor.apply_todo_list(&original, &edited)?;
+    let outcome = editor.rebase()?;
+
+    assert_eq!(execs.len(), 1);
+    assert_eq!(execs[0].command, "make");
+    let exec_commit = outcome.lookup_pick(execs[0].commit)?;
+    let exec_commit = outcome.repo().find_commit(exec_commit)?;
+    assert_eq!(exec_commit.message_raw()?.to_string(), "reworded a\n\nb\n");
+    assert_eq!(
+        exec_commit.parent_ids().next().map(|id| id.detach()),
+        Some(commits.base),
+        "the squash result sits right on the base"
+    );
+    let exec_commit = exec_commit.id;
+
+    outcome.materialize()?;
+
+    let top = repo.head_commit()?;
+    assert_eq!(top.message_raw()?.to_string(), "c\n");
+    assert_eq!(
+        top.parent_ids().next().map(|id| id.detach()),
+        Some(exec_commit)
+    );
+    insta::assert_snapshot!(git_status(&repo)?, @"");
+    Ok(())
+}
+
+#[test]
+fn moving_a_commit_below_a_merge_remerges_it() -> Result<()> {
+    let (repo, _tmpdir, meta) = fixture_writable("merge-with-resolved-conflict")?;
+
+    insta::assert_snapshot!(visualize_commit_graph_all(&repo)?, @r"
+    * f85448a (HEAD -> feature) on top of merge
+    *   4dd4e8b Merge branch 'main' into feature
+    |\  
+    | * 0d84623 (main) main: change file
+    * | 2d4a92c feature: change file
+    * | 67ce795 feature: add a file
+    |/  
+    * 7674a5e base
+    ");
+
+    let head_tree = repo.head_tree()?.id;
+    let main = repo.rev_parse_single("main")?.detach();
+    let base = repo.rev_parse_single("main~1")?.detach();
+
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?.validated()?;
+    let mut editor = graph.to_editor(&repo)?;
+
+    let original = editor.todo_list("refs/heads/feature".try_into()?, base)?;
+    let edited = TodoList::parse(
+        "pick 67ce795\n\
+         pick 2d4a92c\n\
+         pick f85448a\n\
+         pick 4dd4e8b\n\
+         update-ref feature\n",
+        &original,
+    )?;
+    let execs = editor.apply_todo_list(&original, &edited)?;
+    assert!(execs.is_empty());
+
+    // The conflict between the parents of the merge is resolved just like before.
+    editor.rebase()?.materialize()?;
+
+    assert_eq!(head_tree, repo.head_tree()?.id);
+
+    let merge = but_core::Commit::from_id(repo.rev_parse_single("feature")?)?;
+    assert!(!merge.is_conflicted());
+    assert_eq!(merge.parents.len(), 2, "the merge is still a merge");
+    assert_eq!(merge.parents[1], main, "the merged branch is untouched");
+    assert_eq!(
+        repo.find_commit(merge.parents[0])?
+            .message_raw()?
+            .to_string(),
+        "on top of merge\n",
+        "the first parent leads to the moved commit"
+    );
+    insta::assert_snapshot!(git_status(&repo)?, @"");
+    Ok(())
+}
+
+mod validation {
+    use but_rebase::graph_rebase::todo::TodoList;
+
+    use super::original;
+    use crate::utils::four_commits;
+
+    fn parse_err(text: &str) -> anyhow::Result<String> {
+        let (_repo, commits) = four_commits()?;
+        Ok(format!(
+            "{:#}",
+            TodoList::parse(text, &original(&commits)).unwrap_err()
+        ))
+    }
+
+    #[test]
+    fn unknown_command() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("edit d591dfe\nupdate-ref anchor")?,
+            "Line 1: unknown command 'edit'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn commit_outside_of_stack() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("pick 35b8235\nupdate-ref anchor")?,
+            "Line 1: invalid commit: Commit 35b8235 isn't part of the stack"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn commit_used_twice() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("pick d591dfe\nupdate-ref anchor\ndrop d591dfe")?,
+            "Line 3: commit d591dfe is used more than once"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fixup_without_commit_to_meld_into() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("fixup d591dfe\nupdate-ref anchor")?,
+            "Line 1: 'fixup' needs a commit right before it to meld into"
+        );
+        assert_eq!(
+            parse_err("pick d591dfe\nupdate-ref anchor\nsquash a96434e")?,
+            "Line 3: 'squash' needs a commit right before it to meld into"
+        );
+        assert_eq!(
+            parse_err("pick d591dfe\nexec true\nsquash a96434e\nupdate-ref anchor")?,
+            "Line 3: 'squash' needs a commit right before it to meld into"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn exec_without_command() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("exec\nupdate-ref anchor")?,
+            "Line 1: 'exec' needs a command to run"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn branches_must_remain() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_err("pick d591dfe")?,
+            "Branch 'anchor' must remain in the todo list"
+        );
+        assert_eq!(
+            parse_err("update-ref other\n")?,
+            "Line 1: 'other' isn't a branch of this stack"
+        );
+        Ok(())
+    }
+}
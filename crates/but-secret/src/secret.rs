@@ -3,12 +3,15 @@
 //! These are stateless and global, while discouraging storing secrets
 //! in memory beyond their use.
 
-use std::sync::Mutex;
+use std::{fmt, str::FromStr, sync::Mutex};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
 
 use crate::Sensitive;
 
+pub mod encrypted_file;
+
 /// Determines how a secret's name should be modified to produce a namespace.
 ///
 /// Namespaces can be used to partition secrets, depending on some criteria.
@@ -23,26 +26,207 @@ pub enum Namespace {
     Global,
 }
 
+/// The backend that secrets are persisted in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Store {
+    /// The keychain of the operating system, or the Secret Service on Linux.
+    #[default]
+    Keychain,
+    /// A file in the application config directory, encrypted with a passphrase.
+    /// See [`encrypted_file`] for details.
+    EncryptedFile,
+    /// Environment variables, which can only be read.
+    /// The secret known as `handle` is read from `GITBUTLER_SECRET_<HANDLE>`, see [`environment_variable_for()`].
+    Environment,
+}
+
+impl Store {
+    /// All available stores.
+    pub const ALL: [Store; 3] = [Store::Keychain, Store::EncryptedFile, Store::Environment];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Store::Keychain => "keychain",
+            Store::EncryptedFile => "encrypted-file",
+            Store::Environment => "environment",
+        }
+    }
+}
+
+impl fmt::Display for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Store {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Store::ALL
+            .into_iter()
+            .find(|store| store.as_str() == s)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown secret store '{s}', expected one of: {}",
+                    Store::ALL.map(|store| store.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+/// The environment variable that overrides the [store](set_store()) configured in the settings.
+pub const STORE_ENV: &str = "GITBUTLER_SECRETS_STORE";
+
+/// Use `store` for all subsequent secret operations, unless it's overridden by [`STORE_ENV`].
+pub fn set_store(store: Store) {
+    *STORE.lock().unwrap() = store;
+}
+
+/// Return the store that secrets are currently persisted in, which is the one in [`STORE_ENV`] if set,
+/// or the one [configured](set_store()) otherwise.
+pub fn store() -> Result<Store> {
+    match std::env::var(STORE_ENV) {
+        Ok(store) if !store.is_empty() => store.parse(),
+        _ => Ok(*STORE.lock().unwrap()),
+    }
+}
+
+/// Return the name of the environment variable that provides the secret known as `handle`
+/// when using [`Store::Environment`].
+///
+/// Note that namespaces don't apply, so the same variable is used for all of them.
+pub fn environment_variable_for(handle: &str) -> String {
+    let handle: String = handle
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("GITBUTLER_SECRET_{handle}")
+}
+
 /// Persist `secret` in `namespace` so that it can be retrieved by the given `handle`.
 pub fn persist(handle: &str, secret: &Sensitive<String>, namespace: Namespace) -> Result<()> {
-    let entry = entry_for(handle, namespace)?;
-    if secret.0.is_empty() {
-        entry.delete_credential()?;
-    } else {
-        entry.set_password(&secret.0)?;
-    }
-    Ok(())
+    persist_in(store()?, handle, secret, namespace)
 }
 
 /// Obtain the previously [stored](persist()) secret known as `handle` from `namespace`.
 pub fn retrieve(handle: &str, namespace: Namespace) -> Result<Option<Sensitive<String>>> {
-    match entry_for(handle, namespace)
-        .map_err(annotate_linux_keychain)?
-        .get_password()
-    {
-        Ok(secret) => Ok(Some(Sensitive(secret))),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(err) => Err(annotate_linux_keychain(err.into())),
+    retrieve_in(store()?, handle, namespace)
+}
+
+/// Delete the secret at `handle` permanently from `namespace`.
+pub fn delete(handle: &str, namespace: Namespace) -> Result<()> {
+    delete_in(store()?, handle, namespace)
+}
+
+/// Move all secrets known by their `handles` and namespace from the `from` store to the `to` store,
+/// and return the handles of the secrets that were moved.
+///
+/// Secrets are deleted from `from` once they were persisted in `to`, unless `from` is [`Store::Environment`].
+/// Handles without a secret in `from` are skipped.
+pub fn migrate<'a>(
+    handles: impl IntoIterator<Item = (&'a str, Namespace)>,
+    from: Store,
+    to: Store,
+) -> Result<Vec<String>> {
+    if from == to {
+        bail!("Secrets are already stored in '{to}'");
+    }
+    if to == Store::Environment {
+        bail!(
+            "Secrets can't be migrated to '{to}' as it can only be read - set the environment variables instead"
+        );
+    }
+    let mut moved = Vec::new();
+    for (handle, namespace) in handles {
+        let Some(secret) = retrieve_in(from, handle, namespace)? else {
+            continue;
+        };
+        persist_in(to, handle, &secret, namespace)?;
+        if from != Store::Environment {
+            delete_in(from, handle, namespace)?;
+        }
+        moved.push(handle.to_owned());
+    }
+    Ok(moved)
+}
+
+fn persist_in(
+    store: Store,
+    handle: &str,
+    secret: &Sensitive<String>,
+    namespace: Namespace,
+) -> Result<()> {
+    match store {
+        Store::Keychain => {
+            let entry = entry_for(handle, namespace)?;
+            if secret.0.is_empty() {
+                entry.delete_credential()?;
+            } else {
+                entry.set_password(&secret.0)?;
+            }
+        }
+        Store::EncryptedFile => {
+            let file = encrypted_file::EncryptedFile::from_environment()?;
+            let key = namespaced_handle(handle, namespace);
+            if secret.0.is_empty() {
+                file.delete(&key)?;
+            } else {
+                file.set(&key, secret)?;
+            }
+        }
+        Store::Environment => {
+            bail!(
+                "Secrets are read from the environment and can't be stored - set {} instead",
+                environment_variable_for(handle)
+            )
+        }
+    }
+    Ok(())
+}
+
+fn retrieve_in(
+    store: Store,
+    handle: &str,
+    namespace: Namespace,
+) -> Result<Option<Sensitive<String>>> {
+    match store {
+        Store::Keychain => match entry_for(handle, namespace)
+            .map_err(annotate_linux_keychain)?
+            .get_password()
+        {
+            Ok(secret) => Ok(Some(Sensitive(secret))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(annotate_linux_keychain(err.into())),
+        },
+        Store::EncryptedFile => encrypted_file::EncryptedFile::from_environment()?
+            .get(&namespaced_handle(handle, namespace)),
+        Store::Environment => Ok(std::env::var(environment_variable_for(handle))
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(Sensitive)),
+    }
+}
+
+fn delete_in(store: Store, handle: &str, namespace: Namespace) -> Result<()> {
+    match store {
+        Store::Keychain => Ok(entry_for(handle, namespace)?.delete_credential()?),
+        Store::EncryptedFile => encrypted_file::EncryptedFile::from_environment()?
+            .delete(&namespaced_handle(handle, namespace)),
+        Store::Environment => {
+            tracing::warn!(
+                "Secrets are read from the environment and can't be deleted - unset {} instead",
+                environment_variable_for(handle)
+            );
+            Ok(())
+        }
     }
 }
 
@@ -69,11 +253,6 @@ fn annotate_linux_keychain(err: anyhow::Error) -> anyhow::Error {
     }
 }
 
-/// Delete the secret at `handle` permanently from `namespace`.
-pub fn delete(handle: &str, namespace: Namespace) -> Result<()> {
-    Ok(entry_for(handle, namespace)?.delete_credential()?)
-}
-
 /// Use this `identifier` as 'namespace' for identifying secrets.
 /// Each namespace has its own set of secrets, useful for different application versions.
 ///
@@ -93,22 +272,29 @@ pub fn set_application_namespace(identifier: impl Into<String>) {
 }
 
 fn entry_for(handle: &str, namespace: Namespace) -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(
+        &namespaced_handle(handle, namespace),
+        "GitButler",
+    )?)
+}
+
+fn namespaced_handle(handle: &str, namespace: Namespace) -> String {
     let ns = match namespace {
         Namespace::BuildKind => NAMESPACE.lock().unwrap().clone(),
         Namespace::Global => "gitbutler".into(),
     };
-    Ok(keyring::Entry::new(
-        &format!(
-            "{prefix}-{handle}",
-            prefix = if ns.is_empty() { "development" } else { &ns }
-        ),
-        "GitButler",
-    )?)
+    format!(
+        "{prefix}-{handle}",
+        prefix = if ns.is_empty() { "development" } else { &ns }
+    )
 }
 
 /// How to further specialize secrets to avoid name clashes in the globally shared keystore.
 static NAMESPACE: Mutex<String> = Mutex::new(String::new());
 
+/// The store to persist secrets in, unless overridden by [`STORE_ENV`].
+static STORE: Mutex<Store> = Mutex::new(Store::Keychain);
+
 /// A keystore that uses git-credentials under to hood. It's useful on Systems that nag the user
 /// with popups if the underlying binary changes, and is available if `git` can be found and executed.
 pub mod git_credentials {
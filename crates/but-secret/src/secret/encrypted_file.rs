@@ -0,0 +1,116 @@
+//! A store for secrets in a single file, encrypted with a passphrase using [age](https://age-encryption.org).
+//!
+//! It's meant for systems without a keychain, like headless CI runners or machines accessed via SSH.
+
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use age::secrecy::SecretString;
+use anyhow::{Context as _, Result};
+
+use crate::Sensitive;
+
+/// The environment variable to read the passphrase from when using [`EncryptedFile::from_environment()`].
+pub const PASSPHRASE_ENV: &str = "GITBUTLER_SECRETS_PASSPHRASE";
+
+/// The name of the file in the application config directory.
+pub const FILE_NAME: &str = "secrets.age";
+
+/// A file that contains all secrets, encrypted with a passphrase.
+///
+/// The file is decrypted for each access so secrets aren't kept in memory.
+pub struct EncryptedFile {
+    path: PathBuf,
+    passphrase: Sensitive<String>,
+}
+
+/// Lifecycle
+impl EncryptedFile {
+    /// Store secrets in the file at `path`, encrypted with `passphrase`.
+    /// The file doesn't have to exist yet.
+    pub fn new(path: impl Into<PathBuf>, passphrase: Sensitive<String>) -> Self {
+        EncryptedFile {
+            path: path.into(),
+            passphrase,
+        }
+    }
+
+    /// Use the [file](FILE_NAME) in the application config directory, encrypted with the passphrase
+    /// in the [`PASSPHRASE_ENV`] environment variable.
+    pub fn from_environment() -> Result<Self> {
+        let passphrase = std::env::var(PASSPHRASE_ENV)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+            .with_context(|| {
+                format!(
+                    "Secrets are stored in an encrypted file, but {PASSPHRASE_ENV} isn't set to its passphrase"
+                )
+            })?;
+        Ok(Self::new(
+            but_path::app_config_dir()?.join(FILE_NAME),
+            Sensitive(passphrase),
+        ))
+    }
+}
+
+/// Access
+impl EncryptedFile {
+    /// Return the secret stored under `key`, or `None` if there is none.
+    pub fn get(&self, key: &str) -> Result<Option<Sensitive<String>>> {
+        Ok(self.read()?.remove(key).map(Sensitive))
+    }
+
+    /// Store `secret` under `key`, replacing any previous value.
+    pub fn set(&self, key: &str, secret: &Sensitive<String>) -> Result<()> {
+        let mut secrets = self.read()?;
+        secrets.insert(key.to_owned(), secret.0.clone());
+        self.write(&secrets)
+    }
+
+    /// Remove the secret stored under `key`, which does nothing if it doesn't exist.
+    pub fn delete(&self, key: &str) -> Result<()> {
+        let mut secrets = self.read()?;
+        if secrets.remove(key).is_some() {
+            self.write(&secrets)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, String>> {
+        let encrypted = match std::fs::read(&self.path) {
+            Ok(encrypted) => encrypted,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let identity = age::scrypt::Identity::new(self.secret_passphrase());
+        let mut reader = age::Decryptor::new(encrypted.as_slice())?
+            .decrypt(std::iter::once(&identity as &dyn age::Identity))
+            .with_context(|| {
+                format!(
+                    "Could not decrypt secrets in '{}' - is the passphrase correct?",
+                    self.path.display()
+                )
+            })?;
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let plaintext = serde_json::to_vec(secrets)?;
+        let mut encrypted = Vec::new();
+        let mut writer = age::Encryptor::with_user_passphrase(self.secret_passphrase())
+            .wrap_output(&mut encrypted)?;
+        writer.write_all(&plaintext)?;
+        writer.finish()?;
+        but_fs::create_dirs_then_write(&self.path, encrypted)?;
+        Ok(())
+    }
+
+    fn secret_passphrase(&self) -> SecretString {
+        SecretString::from(self.passphrase.0.clone())
+    }
+}
@@ -5,3 +5,57 @@ fn sensitive_does_not_debug_print_itself() {
     let s = Sensitive("password");
     assert_eq!(format!("{s:?}"), "\"<redacted>\"");
 }
+
+mod encrypted_file {
+    use but_secret::{Sensitive, secret::encrypted_file::EncryptedFile};
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let path = tmp.path().join("nested").join("secrets.age");
+        let file = EncryptedFile::new(&path, Sensitive("passphrase".into()));
+        assert!(file.get("key")?.is_none(), "a missing file has no secrets");
+
+        file.set("key", &Sensitive("secret".into()))?;
+        file.set("other", &Sensitive("other-secret".into()))?;
+        assert_eq!(file.get("key")?.map(|s| s.0).as_deref(), Some("secret"));
+        assert!(
+            !std::fs::read_to_string(&path)
+                .unwrap_or_default()
+                .contains("secret"),
+            "secrets are encrypted on disk"
+        );
+
+        file.delete("key")?;
+        assert!(file.get("key")?.is_none());
+        assert_eq!(
+            file.get("other")?.map(|s| s.0).as_deref(),
+            Some("other-secret"),
+            "other secrets are kept"
+        );
+        file.delete("key")?;
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() -> anyhow::Result<()> {
+        let tmp = tempfile::TempDir::new()?;
+        let path = tmp.path().join("secrets.age");
+        EncryptedFile::new(&path, Sensitive("passphrase".into()))
+            .set("key", &Sensitive("secret".into()))?;
+
+        let err = EncryptedFile::new(&path, Sensitive("wrong".into()))
+            .get("key")
+            .unwrap_err();
+        assert!(err.to_string().contains("is the passphrase correct?"));
+        Ok(())
+    }
+}
+
+#[test]
+fn environment_variable_names_are_derived_from_handles() {
+    assert_eq!(
+        but_secret::secret::environment_variable_for("github_oauth-token.1"),
+        "GITBUTLER_SECRET_GITHUB_OAUTH_TOKEN_1"
+    );
+}
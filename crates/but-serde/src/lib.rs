@@ -36,6 +36,31 @@ pub mod fullname_lossy {
     }
 }
 
+pub mod fullname_opt_lossy {
+    use bstr::ByteSlice;
+    use serde::Serialize;
+
+    pub fn serialize<S>(v: &Option<gix::refs::FullName>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        v.as_ref().map(|v| v.as_bstr().to_str_lossy()).serialize(s)
+    }
+}
+
+pub mod fullname_vec_lossy {
+    use bstr::ByteSlice;
+    use serde::Serialize;
+
+    pub fn serialize<S>(v: &[gix::refs::FullName], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let vec: Vec<_> = v.iter().map(|v| v.as_bstr().to_str_lossy()).collect();
+        vec.serialize(s)
+    }
+}
+
 pub mod bstring_vec_lossy {
     use bstr::{BString, ByteSlice};
     use serde::Serialize;
@@ -72,6 +72,13 @@ pub async fn run() {
     };
     let app_settings = AppSettingsWithDiskSync::new_with_customization(config_dir.clone(), None)
         .expect("failed to create app settings");
+    but_secret::secret::set_store(
+        app_settings
+            .get()
+            .expect("settings were just loaded")
+            .secrets
+            .store,
+    );
 
     let app = Claude {
         broadcaster: broadcaster.clone(),
@@ -193,6 +200,10 @@ async fn handle_command(
             diff::commit_details_with_line_stats_cmd(request.params)
         }
         "branch_diff" => but_api::branch::branch_diff_cmd(request.params),
+        // Graph commands
+        "graph_json" => but_api::graph::graph_json_cmd(request.params),
+        "graph_mermaid" => but_api::graph::graph_mermaid_cmd(request.params),
+        "secret_migrate" => but_api::secret::secret_migrate_cmd(request.params),
         "changes_in_worktree" => legacy::diff::changes_in_worktree_cmd(request.params),
         "assign_hunk" => legacy::diff::assign_hunk_cmd(request.params),
         // Cherry apply commands
@@ -385,6 +396,7 @@ async fn handle_command(
         "update_branch_name" => legacy::stack::update_branch_name_cmd(request.params),
         "update_branch_pr_number" => legacy::stack::update_branch_pr_number_cmd(request.params),
         "push_stack" => legacy::stack::push_stack_cmd(request.params),
+        "push_stacks" => legacy::stack::push_stacks_cmd(request.params),
         "push_stack_to_review" => legacy::stack::push_stack_to_review_cmd(request.params),
         // Undo/Snapshot commands
         "list_snapshots" => legacy::oplog::list_snapshots_cmd(request.params),
@@ -580,6 +592,23 @@ async fn handle_command(
         "claude_update_permission_request" => {
             legacy::claude::claude_update_permission_request_cmd(request.params)
         }
+        "claude_get_usage" => legacy::claude::claude_get_usage_cmd(request.params),
+        "claude_set_session_budget" => {
+            legacy::claude::claude_set_session_budget_cmd(request.params)
+        }
+        "claude_get_permission_decisions" => {
+            legacy::claude::claude_get_permission_decisions_cmd(request.params)
+        }
+        "claude_trust_permission_policy" => {
+            legacy::claude::claude_trust_permission_policy_cmd(request.params)
+        }
+        "claude_list_turns" => legacy::claude::claude_list_turns_cmd(request.params),
+        "claude_export_session_markdown" => {
+            legacy::claude::claude_export_session_markdown_cmd(request.params)
+        }
+        "claude_list_pending_worktrees" => {
+            legacy::claude::claude_list_pending_worktrees_cmd(request.params)
+        }
         "claude_cancel_session" => {
             let params = deserialize_json(request.params);
             match params {
@@ -614,6 +643,16 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "claude_restore_turn" => {
+            let params = deserialize_json(request.params);
+            match params {
+                Ok(params) => {
+                    let result = legacy::claude::claude_restore_turn(&app, params).await;
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         "claude_list_prompt_templates" => {
             legacy::claude::claude_list_prompt_templates_cmd(request.params)
         }
@@ -32,6 +32,7 @@ pub struct ClaudeUpdate {
     pub dangerously_allow_all_permissions: Option<bool>,
     pub auto_commit_after_completion: Option<bool>,
     pub use_configured_model: Option<bool>,
+    pub isolate_sessions_in_worktrees: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -129,6 +130,9 @@ impl AppSettingsWithDiskSync {
         if let Some(use_configured_model) = update.use_configured_model {
             settings.claude.use_configured_model = use_configured_model;
         }
+        if let Some(isolate_sessions_in_worktrees) = update.isolate_sessions_in_worktrees {
+            settings.claude.isolate_sessions_in_worktrees = isolate_sessions_in_worktrees;
+        }
         settings.save()
     }
 
@@ -79,6 +79,8 @@ pub struct Claude {
     pub auto_commit_after_completion: bool,
     /// Whether to use the configured model in .claude/settings.json instead of passing --model.
     pub use_configured_model: bool,
+    /// Whether to run the session of each stack in a worktree of its own, and integrate its changes when it finishes.
+    pub isolate_sessions_in_worktrees: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -99,3 +101,10 @@ pub struct UiSettings {
     /// The duration between two update checks in seconds. If `0`, no update checks will be performed.
     pub check_for_updates_interval_in_seconds: u64,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Secrets {
+    /// The backend to store secrets in. Can be overridden with the `GITBUTLER_SECRETS_STORE` environment variable.
+    pub store: but_secret::secret::Store,
+}
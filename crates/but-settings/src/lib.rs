@@ -24,6 +24,8 @@ pub struct AppSettings {
     pub reviews: app_settings::Reviews,
     /// UI settings.
     pub ui: app_settings::UiSettings,
+    /// Settings related to storing secrets.
+    pub secrets: app_settings::Secrets,
 }
 
 impl Default for AppSettings {
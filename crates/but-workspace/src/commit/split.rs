@@ -0,0 +1,154 @@
+//! An action to split a commit into multiple commits by selecting hunks
+
+use but_core::DiffSpec;
+
+/// One of the commits a commit should be split into.
+#[derive(Debug, Clone)]
+pub struct SplitPiece {
+    /// The message of the new commit.
+    pub message: String,
+    /// The changes of the source commit that should be contained in this piece.
+    ///
+    /// If a spec has hunk headers, only these hunks are selected, otherwise the whole file is selected.
+    pub changes: Vec<DiffSpec>,
+}
+
+pub(crate) mod function {
+    use std::collections::BTreeMap;
+
+    use anyhow::{Result, bail};
+    use bstr::BString;
+    use but_core::{DiffSpec, commit::HeadersV2};
+    use but_rebase::{
+        commit::DateMode,
+        graph_rebase::{Editor, Selector, Step, SuccessfulRebase, mutate::InsertSide},
+    };
+
+    use super::SplitPiece;
+    use crate::tree_manipulation::{ChangesSource, create_tree_without_diff};
+
+    /// The result of a [split_commit()] operation.
+    #[derive(Debug)]
+    pub struct SplitCommitOutcome {
+        /// The successful rebase result
+        pub rebase: SuccessfulRebase,
+        /// Selectors pointing to the commits created from each piece, in the order of the pieces,
+        /// i.e. from the bottom-most to the top-most commit.
+        pub selectors: Vec<Selector>,
+    }
+
+    /// Split the commit with `source_commit_id` into as many commits as there are `pieces`, in place.
+    ///
+    /// ## Parameters
+    ///
+    /// - `editor`: The rebase editor to use
+    /// - `source_commit_id`: The commit to split
+    /// - `pieces`: The commits to create, from the bottom-most to the top-most one. Each piece selects
+    ///   the changes of the source commit it should contain via its [`DiffSpec`]s, which may contain hunk headers.
+    /// - `context_lines`: Number of context lines for hunk matching
+    ///
+    /// The first piece re-uses the change-id of the source commit, while all other pieces get a new one.
+    /// Changes that aren't selected by any piece remain in the last piece, so the tree of the top-most
+    /// commit is always the tree of the source commit.
+    ///
+    /// ## Returns
+    ///
+    /// Returns the rebase outcome along with selectors pointing to the commits created for each piece.
+    /// The caller should call `outcome.rebase.materialize()` to persist the changes.
+    pub fn split_commit(
+        mut editor: Editor,
+        source_commit_id: gix::ObjectId,
+        pieces: Vec<SplitPiece>,
+        context_lines: u32,
+    ) -> Result<SplitCommitOutcome> {
+        if pieces.len() < 2 {
+            bail!("A commit must be split into at least two pieces");
+        }
+
+        let source_selector = editor.select_commit(source_commit_id)?;
+        let source_commit = editor.find_commit(source_commit_id)?;
+        if source_commit.is_conflicted() {
+            bail!("Source commit must not be conflicted")
+        }
+        if source_commit.parents.len() > 1 {
+            bail!("Merge commits can't be split")
+        }
+        let source_commit = source_commit.detach();
+
+        let mut selectors = Vec::with_capacity(pieces.len());
+        let mut previous: Option<(gix::ObjectId, Selector)> = None;
+        for (idx, piece) in pieces.iter().enumerate() {
+            // Each piece contains everything but the changes of the pieces above it.
+            let tree = if idx + 1 == pieces.len() {
+                source_commit.inner.tree
+            } else {
+                let changes_above = merge_specs_by_path(
+                    pieces[idx + 1..]
+                        .iter()
+                        .flat_map(|piece| piece.changes.iter().cloned()),
+                );
+                let (tree, dropped_diffs) = create_tree_without_diff(
+                    editor.repo(),
+                    ChangesSource::Commit {
+                        id: source_commit_id,
+                    },
+                    changes_above,
+                    context_lines,
+                )?;
+                if !dropped_diffs.is_empty() {
+                    bail!("Failed to extract described changes from source commit");
+                }
+                tree
+            };
+
+            let mut commit = source_commit.clone().attach(editor.repo());
+            commit.tree = tree;
+            commit.message = piece.message.as_str().into();
+            if let Some((parent_id, _)) = previous {
+                commit.parents = vec![parent_id].into();
+                commit.set_headers(&HeadersV2::from_config(&editor.repo().config_snapshot()));
+            }
+            let new_id = editor.new_commit(commit, DateMode::CommitterUpdateAuthorKeep)?;
+
+            let selector = match previous {
+                None => {
+                    editor.replace(source_selector, Step::new_pick(new_id))?;
+                    source_selector
+                }
+                Some((_, below)) => {
+                    editor.insert(below, Step::new_pick(new_id), InsertSide::Above)?
+                }
+            };
+            selectors.push(selector);
+            previous = Some((new_id, selector));
+        }
+
+        let rebase = editor.rebase()?;
+        Ok(SplitCommitOutcome { rebase, selectors })
+    }
+
+    /// Merge all `specs` that refer to the same path so each path is only subtracted once.
+    /// Whole-file selections win over hunk selections.
+    fn merge_specs_by_path(specs: impl IntoIterator<Item = DiffSpec>) -> Vec<DiffSpec> {
+        let mut by_path = BTreeMap::<BString, DiffSpec>::new();
+        for spec in specs {
+            match by_path.get_mut(&spec.path) {
+                None => {
+                    by_path.insert(spec.path.clone(), spec);
+                }
+                Some(existing) => {
+                    if existing.hunk_headers.is_empty() || spec.hunk_headers.is_empty() {
+                        existing.hunk_headers.clear();
+                    } else {
+                        for hunk in spec.hunk_headers {
+                            if !existing.hunk_headers.contains(&hunk) {
+                                existing.hunk_headers.push(hunk);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        by_path.into_values().collect()
+    }
+}
@@ -1,18 +1,24 @@
 //! The machinery used to alter and mutate commits in various ways whilst adjusting descendant commits within a [reference frame](ReferenceFrame).
 
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
 
 use anyhow::{Context as _, bail};
 use bstr::BString;
 use but_core::{DiffSpec, ref_metadata::StackId, tree::create_tree::RejectionReason};
 use but_ctx::{Context, access::WorktreeWritePermission};
 use but_rebase::merge::ConflictErrorContext;
+use gitbutler_repo::hooks::{self, GitHook, RewriteCommand};
 use gitbutler_stack::{VirtualBranchesHandle, VirtualBranchesState};
 use gix::{prelude::ObjectIdExt, refs::transaction::PreviousValue};
 
 use crate::{
     WorkspaceCommit,
-    commit_engine::{CreateCommitOutcome, Destination, StackSegmentId, create_commit},
+    commit_engine::{
+        CreateCommitOutcome, Destination, StackSegmentId, UpdatedReference, create_commit,
+    },
     legacy::commit_engine::reference_frame::InferenceMode,
 };
 
@@ -135,54 +141,7 @@ pub fn create_commit_and_update_refs(
     };
 
     if let Some(commit_in_graph) = commit_to_find {
-        let mut all_refs_by_id = gix::hashtable::HashMap::<_, Vec<_>>::default();
-        let mut checked_out_ref_name = None;
-        let checked_out_ref = repo.head_ref()?.and_then(|mut r| {
-            let id = r.peel_to_id().ok()?.detach();
-            checked_out_ref_name = Some(r.inner.name.clone());
-            Some((id, r.inner.name))
-        });
-        let (platform_storage, platform_storage_2);
-        let checked_out_and_gitbutler_refs =
-            checked_out_ref
-                .into_iter()
-                // TODO: remove this as `refs/gitbutler/` won't contain relevant refs anymore.
-                .chain({
-                    platform_storage_2 = repo.references()?;
-                    platform_storage_2
-                        .prefixed("refs/gitbutler/")?
-                        .filter_map(Result::ok)
-                        .filter_map(|r| r.try_id().map(|id| (id.detach(), r.inner.name)))
-                })
-                .chain(
-                    // When amending, we want to update all branches that pointed to the old commit to now point to the new commit.
-                    if is_amend {
-                        Box::new({
-                            platform_storage = repo.references()?;
-                            platform_storage
-                                .prefixed("refs/heads/")?
-                                .filter_map(Result::ok)
-                                .filter_map(|r| {
-                                    let is_checked_out = checked_out_ref_name.as_ref().is_some_and(
-                                        |checked_out_ref| checked_out_ref == &r.inner.name,
-                                    );
-                                    if is_checked_out {
-                                        None
-                                    } else {
-                                        r.try_id().map(|id| (id.detach(), r.inner.name))
-                                    }
-                                })
-                        }) as Box<dyn Iterator<Item = _>>
-                    } else {
-                        Box::new(std::iter::empty())
-                    },
-                );
-        for (commit_id, git_reference) in checked_out_and_gitbutler_refs {
-            all_refs_by_id
-                .entry(commit_id)
-                .or_default()
-                .push(git_reference);
-        }
+        let all_refs_by_id = refs_by_commit_id(repo, is_amend)?;
 
         // Special case: commit/amend on top of `HEAD` and no merge above: no rebase necessary
         if frame.workspace_tip.is_none()
@@ -239,29 +198,7 @@ pub fn create_commit_and_update_refs(
                 ) -> anyhow::Result<()> {
                     outcome
                         .rejected_specs
-                        .extend(conflicts.iter().map(|conflicting_rela_path| {
-                            changes
-                                .iter()
-                                .find_map(|spec| {
-                                    (spec.path == *conflicting_rela_path
-                                        || spec.previous_path.as_ref()
-                                            == Some(conflicting_rela_path))
-                                    .then_some((
-                                        RejectionReason::WorkspaceMergeConflict,
-                                        spec.to_owned(),
-                                    ))
-                                })
-                                .unwrap_or_else(|| {
-                                    (
-                                        RejectionReason::WorkspaceMergeConflictOfUnrelatedFile,
-                                        DiffSpec {
-                                            previous_path: None,
-                                            path: conflicting_rela_path.to_owned(),
-                                            hunk_headers: vec![],
-                                        },
-                                    )
-                                })
-                        }));
+                        .extend(conflicting_specs(conflicts, changes));
                     outcome.new_commit = None;
                     outcome.changed_tree_pre_cherry_pick = None;
                     Ok(())
@@ -275,29 +212,8 @@ pub fn create_commit_and_update_refs(
                     }
                 }))?;
                 if let Some(workspace_tip) = workspace_tip {
-                    // Special Hack (https://github.com/gitbutlerapp/gitbutler/pull/7976)
-                    // See if `branch_tip` isn't yet in the workspace-tip if it is managed, and if so, add it
-                    // so it's going to be re-merged.
-                    let wsc = WorkspaceCommit::from_id(workspace_tip.attach(repo))?;
-                    let commit_id = if wsc.is_managed() /* we can change the commit */
-                        && !wsc.inner.parents.contains(&branch_tip) /* the branch tip we know isn't yet merged */
-                        // but the tip is known to the workspace
-                        && vb.branches.values().any(|s| {
-                        s.head_oid(&ctx)
-                            .is_ok_and(|head_id| head_id == branch_tip)
-                    }) {
-                        let mut stacks: Vec<_> = vb
-                            .branches
-                            .values()
-                            .filter(|stack| stack.in_workspace)
-                            .map(|stack| crate::legacy::ui::StackEntryNoOpt::try_new(repo, stack))
-                            .collect::<Result<_, _>>()?;
-                        stacks.sort_by(|a, b| a.name().cmp(&b.name()));
-                        let new_wc = WorkspaceCommit::new_from_stacks(stacks, repo.object_hash());
-                        repo.write_object(&new_wc)?.detach()
-                    } else {
-                        workspace_tip
-                    };
+                    let commit_id =
+                        workspace_commit_to_pick(repo, &ctx, vb, workspace_tip, branch_tip)?;
                     // We can assume the workspace tip is connected to a pick (or else the rebase will fail)
                     builder.steps([but_rebase::RebaseStep::Pick {
                         commit_id,
@@ -361,16 +277,7 @@ pub fn create_commit_and_update_refs(
             )?;
             out.rebase_output = Some(rebase);
         }
-        // Assume an index to be present and adjust it to match the new tree.
-
-        let tree_index = repo.index_from_tree(&repo.head_tree_id()?)?;
-        let mut disk_index = repo.open_index()?;
-        index::apply_lhs_to_rhs(
-            repo.workdir().expect("non-bare"),
-            &tree_index,
-            &mut disk_index,
-        )?;
-        out.index = disk_index.into();
+        out.index = index_matching_head(repo)?.into();
     } else {
         // unborn branch special case.
         repo.reference(
@@ -436,13 +343,51 @@ pub fn create_commit_and_update_refs_with_project(
         }
         Some(stack_id) => ReferenceFrame::infer(repo, &vb, InferenceMode::StackId(stack_id))?,
     };
+    let amended_commit_id = match &destination {
+        Destination::AmendCommit { commit_id, .. } => Some(*commit_id),
+        Destination::NewCommit { .. } => None,
+    };
     let out =
         create_commit_and_update_refs(repo, frame, &mut vb, destination, changes, context_lines)?;
 
     vbh.write_file(&vb)?;
+    let disabled_hooks = hooks::disabled_hooks_at(repo.workdir());
+    if let (Some(old), Some(new)) = (amended_commit_id, out.new_commit) {
+        hooks::log_failure(
+            GitHook::PostRewrite,
+            hooks::post_rewrite(repo, RewriteCommand::Amend, &[(old, new)], &disabled_hooks),
+        );
+    }
+    run_reference_transaction_hook(repo, &out.references, &disabled_hooks);
     Ok(out)
 }
 
+/// Run the `reference-transaction` hook for the Git references among `references`, which were already updated.
+///
+/// References are written while commits are rewritten, so the hook can only be notified and not veto them.
+fn run_reference_transaction_hook(
+    repo: &gix::Repository,
+    references: &[UpdatedReference],
+    disabled_hooks: &BTreeSet<GitHook>,
+) {
+    let updates: Vec<_> = references
+        .iter()
+        .filter_map(|updated| match &updated.reference {
+            but_core::Reference::Git(name) => {
+                Some((updated.old_commit_id, updated.new_commit_id, name.as_ref()))
+            }
+            but_core::Reference::Virtual(_) => None,
+        })
+        .collect();
+    if updates.is_empty() {
+        return;
+    }
+    hooks::log_failure(
+        GitHook::ReferenceTransaction,
+        hooks::reference_transaction_committed(repo, &updates, disabled_hooks),
+    );
+}
+
 impl Destination {
     pub(crate) fn stack_segment(&self) -> Option<&StackSegmentId> {
         match self {
@@ -451,3 +396,414 @@ impl Destination {
         }
     }
 }
+
+/// The outcome of [`amend_commits_and_update_refs()`].
+#[derive(Debug, Default)]
+pub struct AmendCommitsOutcome {
+    /// Changes that couldn't be amended to their commit, for the reasons also seen in [`CreateCommitOutcome::rejected_specs`].
+    pub rejected_specs: Vec<(RejectionReason, DiffSpec)>,
+    /// All commits that were rewritten as `(old, new)`, in the order of the rebase.
+    /// It doesn't contain commits whose changes couldn't be amended, for instance because rebasing them caused conflicts.
+    pub commit_mapping: Vec<(gix::ObjectId, gix::ObjectId)>,
+    /// The references that were changed to point to the rewritten commits.
+    pub references: Vec<UpdatedReference>,
+    /// `Some(_)` if commits were rewritten, with an index that matches the tree at `HEAD` as described
+    /// in [`CreateCommitOutcome::index`]. It was already written.
+    pub index: Option<gix::index::File>,
+}
+
+/// Like [`create_commit_and_update_refs()`] with [`Destination::AmendCommit`], but amends each commit in `amendments`
+/// with its respective `changes`, keeping all commit messages.
+///
+/// Instead of rebasing the descendants once per amended commit, they are rebased only once on top of the bottom-most
+/// amended commit, with the amended versions of all other commits picked in place of the original ones.
+/// This requires all commits to be in the first-parent ancestry of the branch tip of `frame`, or of `HEAD` if there is none.
+pub fn amend_commits_and_update_refs(
+    repo: &gix::Repository,
+    frame: ReferenceFrame,
+    vb: &mut VirtualBranchesState,
+    amendments: Vec<(gix::ObjectId, Vec<DiffSpec>)>,
+    context_lines: u32,
+) -> anyhow::Result<AmendCommitsOutcome> {
+    let mut out = AmendCommitsOutcome::default();
+    let mut changes = Vec::new();
+    let mut amended_by_id = gix::hashtable::HashMap::default();
+    for (commit_id, commit_changes) in amendments {
+        changes.extend(commit_changes.iter().cloned());
+        if let Some(amended) = amend_commit(
+            repo,
+            commit_id,
+            commit_changes,
+            context_lines,
+            &mut out.rejected_specs,
+        )? {
+            amended_by_id.insert(commit_id, amended);
+        }
+    }
+    rebase_amended_commits(repo, frame, vb, &amended_by_id, &changes, &mut out)?;
+    write_index_if_rewritten(repo, &mut out)?;
+    Ok(out)
+}
+
+/// Like [`amend_commits_and_update_refs()`], but integrates with the GitButler project whose data is in `project_data_dir`,
+/// and amends commits in multiple stacks, each identified by the stack id that comes with them in `amendments`.
+///
+/// All commits are amended before the first stack is rebased, so all `changes` remain relative to the worktree
+/// and `HEAD` as they are now. Note that virtual branches will be updated and written back after this call.
+pub fn amend_commits_and_update_refs_with_project(
+    repo: &gix::Repository,
+    project_data_dir: &Path,
+    amendments: Vec<(StackId, gix::ObjectId, Vec<DiffSpec>)>,
+    context_lines: u32,
+    _perm: &mut WorktreeWritePermission,
+) -> anyhow::Result<AmendCommitsOutcome> {
+    let vbh = VirtualBranchesHandle::new(project_data_dir);
+    let mut vb = vbh.read_file()?;
+    let mut out = AmendCommitsOutcome::default();
+    let mut amended_by_stack = BTreeMap::<StackId, (gix::hashtable::HashMap<_, _>, Vec<_>)>::new();
+    for (stack_id, commit_id, changes) in amendments {
+        let (amended_by_id, stack_changes) = amended_by_stack.entry(stack_id).or_default();
+        stack_changes.extend(changes.iter().cloned());
+        if let Some(amended) = amend_commit(
+            repo,
+            commit_id,
+            changes,
+            context_lines,
+            &mut out.rejected_specs,
+        )? {
+            amended_by_id.insert(commit_id, amended);
+        }
+    }
+    for (stack_id, (amended_by_id, changes)) in amended_by_stack {
+        if amended_by_id.is_empty() {
+            continue;
+        }
+        let frame = ReferenceFrame::infer(repo, &vb, InferenceMode::StackId(stack_id))?;
+        rebase_amended_commits(repo, frame, &mut vb, &amended_by_id, &changes, &mut out)?;
+    }
+    write_index_if_rewritten(repo, &mut out)?;
+
+    vbh.write_file(&vb)?;
+    let disabled_hooks = hooks::disabled_hooks_at(repo.workdir());
+    if !out.commit_mapping.is_empty() {
+        hooks::log_failure(
+            GitHook::PostRewrite,
+            hooks::post_rewrite(
+                repo,
+                RewriteCommand::Rebase,
+                &out.commit_mapping,
+                &disabled_hooks,
+            ),
+        );
+    }
+    run_reference_transaction_hook(repo, &out.references, &disabled_hooks);
+    Ok(out)
+}
+
+/// Create an amended copy of `commit_id` with `changes`, without rewriting anything, and collect rejected changes
+/// in `rejected_specs`.
+fn amend_commit(
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+    changes: Vec<DiffSpec>,
+    context_lines: u32,
+    rejected_specs: &mut Vec<(RejectionReason, DiffSpec)>,
+) -> anyhow::Result<Option<gix::ObjectId>> {
+    let outcome = create_commit(
+        repo,
+        Destination::AmendCommit {
+            commit_id,
+            new_message: None,
+        },
+        changes,
+        context_lines,
+    )?;
+    rejected_specs.extend(outcome.rejected_specs);
+    Ok(outcome.new_commit)
+}
+
+/// Rebase the branch in `frame` so the amended copies in `amended_by_id` take the place of the commits they were made from,
+/// and update all references accordingly.
+/// If the rebase fails with conflicts, nothing is rewritten and the `changes` at the conflicting paths are rejected.
+fn rebase_amended_commits(
+    repo: &gix::Repository,
+    frame: ReferenceFrame,
+    vb: &mut VirtualBranchesState,
+    amended_by_id: &gix::hashtable::HashMap<gix::ObjectId, gix::ObjectId>,
+    changes: &[DiffSpec],
+    out: &mut AmendCommitsOutcome,
+) -> anyhow::Result<()> {
+    if amended_by_id.is_empty() {
+        return Ok(());
+    }
+    let ctx = but_ctx::Context::try_from(repo.clone())?;
+    let all_refs_by_id = refs_by_commit_id(repo, true /* is amend */)?;
+
+    let branch_tip = match frame.branch_tip {
+        Some(branch_tip) => branch_tip,
+        None => repo.head_id()?.detach(),
+    };
+    let mut commits_to_rebase = Vec::new();
+    let mut num_amended_to_find = amended_by_id.len();
+    for info in branch_tip
+        .attach(repo)
+        .ancestors()
+        .first_parent_only()
+        .all()?
+    {
+        let commit_id = info?.id;
+        commits_to_rebase.push(commit_id);
+        if amended_by_id.contains_key(&commit_id) {
+            num_amended_to_find -= 1;
+            if num_amended_to_find == 0 {
+                break;
+            }
+        }
+    }
+    if num_amended_to_find != 0 {
+        bail!("Branch tip at {branch_tip} didn't contain all commits to amend - cannot rebase");
+    }
+
+    // The bottom-most amended commit is the base, everything above is picked, using the amended version if there is one.
+    let bottom = commits_to_rebase
+        .pop()
+        .expect("the walk ends with an amended commit");
+    let picks: Vec<_> = commits_to_rebase
+        .into_iter()
+        .rev()
+        .map(|commit_id| {
+            let pick = amended_by_id.get(&commit_id).copied().unwrap_or(commit_id);
+            (commit_id, pick)
+        })
+        .collect();
+    let mut commit_mapping = vec![(bottom, amended_by_id[&bottom])];
+    let workspace_tip = frame
+        .workspace_tip
+        .filter(|ws_tip| *ws_tip != bottom && !picks.iter().any(|(id, _)| id == ws_tip));
+
+    if !picks.is_empty() || workspace_tip.is_some() {
+        let mut builder = but_rebase::Rebase::new(repo, amended_by_id[&bottom], Some(bottom))?;
+        builder.steps(
+            picks
+                .iter()
+                .map(|(_, commit_id)| but_rebase::RebaseStep::Pick {
+                    commit_id: *commit_id,
+                    new_message: None,
+                }),
+        )?;
+        let mut workspace_pick = None;
+        if let Some(workspace_tip) = workspace_tip {
+            let mut commit_id =
+                workspace_commit_to_pick(repo, &ctx, vb, workspace_tip, branch_tip)?;
+            // The merge can only be repeated if it refers to the commit that was picked in place of the branch tip.
+            if let Some((_, amended_tip)) =
+                picks.last().filter(|(tip, amended_tip)| tip != amended_tip)
+            {
+                let mut commit = repo.find_commit(commit_id)?.decode()?.to_owned()?;
+                for parent in &mut commit.parents {
+                    if *parent == branch_tip {
+                        *parent = *amended_tip;
+                    }
+                }
+                commit_id = repo.write_object(&commit)?.detach();
+            }
+            builder.steps([but_rebase::RebaseStep::Pick {
+                commit_id,
+                new_message: None,
+            }])?;
+            workspace_pick = Some((commit_id, workspace_tip));
+        }
+        let mut rebase = match builder.rebase() {
+            Ok(rebase) => rebase,
+            Err(err) => {
+                return if let Some(conflicts) = err.downcast_ref::<ConflictErrorContext>() {
+                    out.rejected_specs
+                        .extend(conflicting_specs(&conflicts.paths, changes));
+                    Ok(())
+                } else {
+                    Err(err)
+                };
+            }
+        };
+        if let Some((commit_id, workspace_tip)) = workspace_pick
+            && commit_id != workspace_tip
+        {
+            let Some(rewritten_old) = rebase
+                .commit_mapping
+                .iter_mut()
+                .find_map(|(_base, old, _new)| (old == &commit_id).then_some(old))
+            else {
+                bail!(
+                    "BUG: Needed to find modified {commit_id} to set it back its previous value, but couldn't find it"
+                );
+            };
+            *rewritten_old = workspace_tip;
+        }
+        commit_mapping.extend(rebase.commit_mapping.iter().map(|(_base, old, new)| {
+            let original = picks
+                .iter()
+                .find_map(|(commit_id, pick)| (pick == old).then_some(*commit_id))
+                .unwrap_or(*old);
+            (original, *new)
+        }));
+    }
+
+    refs::rewrite(
+        repo,
+        vb,
+        all_refs_by_id,
+        commit_mapping.iter().copied(),
+        &mut out.references,
+        None,
+    )?;
+    out.commit_mapping.extend(commit_mapping);
+    Ok(())
+}
+
+/// Write an index that matches `HEAD` into `out` and to disk, if commits were rewritten.
+fn write_index_if_rewritten(
+    repo: &gix::Repository,
+    out: &mut AmendCommitsOutcome,
+) -> anyhow::Result<()> {
+    if out.commit_mapping.is_empty() {
+        return Ok(());
+    }
+    let mut index = index_matching_head(repo)?;
+    index.write(Default::default())?;
+    out.index = Some(index);
+    Ok(())
+}
+
+/// Collect the references that point to commits that are about to be rewritten, by the commit they point to.
+/// These are the checked-out reference and GitButler references, along with all local branches if `is_amend` is `true`.
+fn refs_by_commit_id(
+    repo: &gix::Repository,
+    is_amend: bool,
+) -> anyhow::Result<gix::hashtable::HashMap<gix::ObjectId, Vec<gix::refs::FullName>>> {
+    let mut all_refs_by_id = gix::hashtable::HashMap::<_, Vec<_>>::default();
+    let mut checked_out_ref_name = None;
+    let checked_out_ref = repo.head_ref()?.and_then(|mut r| {
+        let id = r.peel_to_id().ok()?.detach();
+        checked_out_ref_name = Some(r.inner.name.clone());
+        Some((id, r.inner.name))
+    });
+    let (platform_storage, platform_storage_2);
+    let checked_out_and_gitbutler_refs = checked_out_ref
+        .into_iter()
+        // TODO: remove this as `refs/gitbutler/` won't contain relevant refs anymore.
+        .chain({
+            platform_storage_2 = repo.references()?;
+            platform_storage_2
+                .prefixed("refs/gitbutler/")?
+                .filter_map(Result::ok)
+                .filter_map(|r| r.try_id().map(|id| (id.detach(), r.inner.name)))
+        })
+        .chain(
+            // When amending, we want to update all branches that pointed to the old commit to now point to the new commit.
+            if is_amend {
+                Box::new({
+                    platform_storage = repo.references()?;
+                    platform_storage
+                        .prefixed("refs/heads/")?
+                        .filter_map(Result::ok)
+                        .filter_map(|r| {
+                            let is_checked_out = checked_out_ref_name
+                                .as_ref()
+                                .is_some_and(|checked_out_ref| checked_out_ref == &r.inner.name);
+                            if is_checked_out {
+                                None
+                            } else {
+                                r.try_id().map(|id| (id.detach(), r.inner.name))
+                            }
+                        })
+                }) as Box<dyn Iterator<Item = _>>
+            } else {
+                Box::new(std::iter::empty())
+            },
+        );
+    for (commit_id, git_reference) in checked_out_and_gitbutler_refs {
+        all_refs_by_id
+            .entry(commit_id)
+            .or_default()
+            .push(git_reference);
+    }
+
+    Ok(all_refs_by_id)
+}
+
+/// Turn the `conflicts` of a rebase into rejections of the `changes` at the conflicting paths.
+fn conflicting_specs(
+    conflicts: &[BString],
+    changes: &[DiffSpec],
+) -> Vec<(RejectionReason, DiffSpec)> {
+    conflicts
+        .iter()
+        .map(|conflicting_rela_path| {
+            changes
+                .iter()
+                .find_map(|spec| {
+                    (spec.path == *conflicting_rela_path
+                        || spec.previous_path.as_ref() == Some(conflicting_rela_path))
+                    .then_some((RejectionReason::WorkspaceMergeConflict, spec.to_owned()))
+                })
+                .unwrap_or_else(|| {
+                    (
+                        RejectionReason::WorkspaceMergeConflictOfUnrelatedFile,
+                        DiffSpec {
+                            previous_path: None,
+                            path: conflicting_rela_path.to_owned(),
+                            hunk_headers: vec![],
+                        },
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Return the commit to pick to re-merge the workspace at `workspace_tip` after `branch_tip` was rebased.
+fn workspace_commit_to_pick(
+    repo: &gix::Repository,
+    ctx: &Context,
+    vb: &VirtualBranchesState,
+    workspace_tip: gix::ObjectId,
+    branch_tip: gix::ObjectId,
+) -> anyhow::Result<gix::ObjectId> {
+    // Special Hack (https://github.com/gitbutlerapp/gitbutler/pull/7976)
+    // See if `branch_tip` isn't yet in the workspace-tip if it is managed, and if so, add it
+    // so it's going to be re-merged.
+    let wsc = WorkspaceCommit::from_id(workspace_tip.attach(repo))?;
+    Ok(
+        if wsc.is_managed() /* we can change the commit */
+        && !wsc.inner.parents.contains(&branch_tip) /* the branch tip we know isn't yet merged */
+        // but the tip is known to the workspace
+        && vb.branches.values().any(|s| {
+        s.head_oid(ctx)
+            .is_ok_and(|head_id| head_id == branch_tip)
+    }) {
+            let mut stacks: Vec<_> = vb
+                .branches
+                .values()
+                .filter(|stack| stack.in_workspace)
+                .map(|stack| crate::legacy::ui::StackEntryNoOpt::try_new(repo, stack))
+                .collect::<Result<_, _>>()?;
+            stacks.sort_by(|a, b| a.name().cmp(&b.name()));
+            let new_wc = WorkspaceCommit::new_from_stacks(stacks, repo.object_hash());
+            repo.write_object(&new_wc)?.detach()
+        } else {
+            workspace_tip
+        },
+    )
+}
+
+/// Produce an index based on the one on disk that matches the tree at `HEAD`.
+fn index_matching_head(repo: &gix::Repository) -> anyhow::Result<gix::index::File> {
+    // Assume an index to be present and adjust it to match the new tree.
+    let tree_index = repo.index_from_tree(&repo.head_tree_id()?)?;
+    let mut disk_index = repo.open_index()?;
+    index::apply_lhs_to_rhs(
+        repo.workdir().expect("non-bare"),
+        &tree_index,
+        &mut disk_index,
+    )?;
+    Ok(disk_index)
+}
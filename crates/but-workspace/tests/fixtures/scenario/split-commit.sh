@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+
+set -eu -o pipefail
+
+git init
+
+git checkout -b main
+seq 10 > file.txt
+git add .
+git commit -m "base"
+
+git checkout -b feature
+seq 10 | sed -e 's/^1$/one/' -e 's/^10$/ten/' > file.txt
+echo "new" > new.txt
+git add .
+git commit -m "to split"
+
+echo "top" > top.txt
+git add .
+git commit -m "on top"
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use but_core::{DiffSpec, HunkHeader, commit::HeadersV2};
+use but_rebase::graph_rebase::GraphExt;
+use but_testsupport::visualize_commit_graph_all;
+use but_workspace::commit::{split::SplitPiece, split_commit};
+use gix::prelude::ObjectIdExt;
+
+use crate::ref_info::with_workspace_commit::utils::named_writable_scenario_with_description_and_graph as writable_scenario;
+
+fn diff_spec_for_file(path: &str) -> DiffSpec {
+    DiffSpec {
+        previous_path: None,
+        path: path.into(),
+        hunk_headers: vec![],
+    }
+}
+
+fn diff_spec_for_hunk(path: &str, old_start: u32, new_start: u32) -> DiffSpec {
+    DiffSpec {
+        previous_path: None,
+        path: path.into(),
+        hunk_headers: vec![HunkHeader {
+            old_start,
+            old_lines: 1,
+            new_start,
+            new_lines: 1,
+        }],
+    }
+}
+
+fn piece(message: &str, changes: Vec<DiffSpec>) -> SplitPiece {
+    SplitPiece {
+        message: message.into(),
+        changes,
+    }
+}
+
+fn visualize_tree(repo: &gix::Repository, spec: &str) -> Result<String> {
+    let tree_id = repo.rev_parse_single(spec)?.object()?.peel_to_tree()?.id();
+    Ok(but_testsupport::visualize_tree(tree_id).to_string())
+}
+
+#[test]
+fn split_by_file() -> Result<()> {
+    let (_tmp, graph, repo, mut _meta, _description) = writable_scenario("split-commit", |_| {})?;
+    insta::assert_snapshot!(visualize_commit_graph_all(&repo)?, @r"
+    * 3680a1b (HEAD -> feature) on top
+    * 9d049e5 to split
+    * 9c3bddf (main) base
+    ");
+
+    let source_id = repo.rev_parse_single("feature~1")?.detach();
+    let editor = graph.to_editor(&repo)?;
+    let outcome = split_commit(
+        editor,
+        source_id,
+        vec![
+            piece("first", vec![diff_spec_for_file("file.txt")]),
+            piece("second", vec![diff_spec_for_file("new.txt")]),
+        ],
+        0,
+    )?;
+    assert_eq!(outcome.selectors.len(), 2);
+    outcome.rebase.materialize()?;
+
+    let messages: Vec<_> = ["feature", "feature~1", "feature~2", "feature~3"]
+        .into_iter()
+        .map(|spec| -> Result<_> {
+            Ok(repo
+                .rev_parse_single(spec)?
+                .object()?
+                .into_commit()
+                .message_raw()?
+                .to_string())
+        })
+        .collect::<Result<_>>()?;
+    assert_eq!(messages, ["on top\n", "second", "first", "base\n"]);
+
+    insta::assert_snapshot!(visualize_tree(&repo, "feature~2")?, @r#"
+    69b56fb
+    └── file.txt:100644:c261f35 "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n"
+    "#);
+    insta::assert_snapshot!(visualize_tree(&repo, "feature~1")?, @r#"
+    0e24a79
+    ├── file.txt:100644:c261f35 "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n"
+    └── new.txt:100644:3e75765 "new\n"
+    "#);
+    Ok(())
+}
+
+#[test]
+fn split_by_hunk_keeps_unselected_changes_in_last_piece() -> Result<()> {
+    let (_tmp, graph, repo, mut _meta, _description) = writable_scenario("split-commit", |_| {})?;
+
+    let source_id = repo.rev_parse_single("feature~1")?.detach();
+    let editor = graph.to_editor(&repo)?;
+    let outcome = split_commit(
+        editor,
+        source_id,
+        vec![
+            piece("first", vec![diff_spec_for_hunk("file.txt", 1, 1)]),
+            piece("second", vec![diff_spec_for_hunk("file.txt", 10, 10)]),
+        ],
+        0,
+    )?;
+    outcome.rebase.materialize()?;
+
+    insta::assert_snapshot!(visualize_tree(&repo, "feature~2")?, @r#"
+    6b51bbd
+    └── file.txt:100644:1b6662b "one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n"
+    "#);
+    // `new.txt` wasn't selected by any piece, so it stays in the last one.
+    insta::assert_snapshot!(visualize_tree(&repo, "feature~1")?, @r#"
+    0e24a79
+    ├── file.txt:100644:c261f35 "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n"
+    └── new.txt:100644:3e75765 "new\n"
+    "#);
+    insta::assert_snapshot!(visualize_tree(&repo, "main")?, @r#"
+    b430787
+    └── file.txt:100644:f00c965 "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n"
+    "#);
+    Ok(())
+}
+
+#[test]
+fn only_the_first_piece_keeps_the_change_id() -> Result<()> {
+    let (_tmp, graph, repo, mut _meta, _description) = writable_scenario("split-commit", |_| {})?;
+
+    let source_id = repo.rev_parse_single("feature~1")?.detach();
+    let source_headers = but_core::Commit::from_id(source_id.attach(&repo))?.headers();
+
+    let editor = graph.to_editor(&repo)?;
+    let outcome = split_commit(
+        editor,
+        source_id,
+        vec![
+            piece("first", vec![diff_spec_for_file("file.txt")]),
+            piece("second", vec![diff_spec_for_file("new.txt")]),
+        ],
+        0,
+    )?;
+    outcome.rebase.materialize()?;
+
+    let headers_of = |spec: &str| -> Result<Option<HeadersV2>> {
+        let commit = but_core::Commit::from_id(repo.rev_parse_single(spec)?)?;
+        Ok(commit.headers())
+    };
+    assert_eq!(
+        headers_of("feature~2")?.map(|h| h.change_id),
+        source_headers.map(|h| h.change_id),
+        "the first piece is the source commit, just with fewer changes"
+    );
+    let second = headers_of("feature~1")?.expect("new pieces get new headers");
+    assert_ne!(
+        Some(second.change_id),
+        headers_of("feature~2")?.map(|h| h.change_id)
+    );
+    Ok(())
+}
+
+#[test]
+fn needs_at_least_two_pieces() -> Result<()> {
+    let (_tmp, graph, repo, mut _meta, _description) = writable_scenario("split-commit", |_| {})?;
+
+    let source_id = repo.rev_parse_single("feature~1")?.detach();
+    let editor = graph.to_editor(&repo)?;
+    let err = split_commit(
+        editor,
+        source_id,
+        vec![piece("only", vec![diff_spec_for_file("file.txt")])],
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "A commit must be split into at least two pieces"
+    );
+    Ok(())
+}
@@ -5,7 +5,7 @@ use but_workspace::{
     legacy::commit_engine::ReferenceFrame,
 };
 use gitbutler_stack::VirtualBranchesState;
-use gix::{prelude::ObjectIdExt, refs::transaction::PreviousValue};
+use gix::{bstr::ByteSlice, prelude::ObjectIdExt, refs::transaction::PreviousValue};
 
 use crate::{
     commit_engine::{
@@ -1634,6 +1634,87 @@ fn amend_edit_message_only() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn amend_two_commits_of_branch_in_workspace() -> anyhow::Result<()> {
+    let (repo, _tmp) = writable_scenario("merge-with-two-branches-two-commits-line-offset");
+
+    let head_commit_id = repo.head_id()?.detach();
+    let branch_a = repo.rev_parse_single("A")?.detach();
+    let branch_a_parent = repo.rev_parse_single("A~1")?.detach();
+    let mut vb = VirtualBranchesState::default();
+    let stack = stack_with_branches("s1", branch_a, [("s1-b/top", branch_a)], &repo);
+    vb.branches.insert(stack.id, stack);
+
+    // Line 2 was added by the top commit of A, and line 7 by the one below it.
+    let content: String = (1..=30)
+        .map(|line| match line {
+            2 | 7 => format!("{line}a\n"),
+            _ => format!("{line}\n"),
+        })
+        .collect();
+    write_worktree_file(&repo, "file", &content)?;
+    let change_line = |line: &str| DiffSpec {
+        previous_path: None,
+        path: "file".into(),
+        hunk_headers: vec![
+            hunk_header(&format!("-{line},1"), "+0,0"),
+            hunk_header("-0,0", &format!("+{line},1")),
+        ],
+    };
+    let outcome = but_workspace::legacy::commit_engine::amend_commits_and_update_refs(
+        &repo,
+        ReferenceFrame {
+            workspace_tip: Some(head_commit_id),
+            branch_tip: Some(branch_a),
+        },
+        &mut vb,
+        vec![
+            (branch_a, vec![change_line("2")]),
+            (branch_a_parent, vec![change_line("7")]),
+        ],
+        CONTEXT_LINES,
+    )?;
+    assert_eq!(outcome.rejected_specs, vec![]);
+    assert_eq!(
+        outcome
+            .commit_mapping
+            .iter()
+            .map(|(old, _new)| *old)
+            .collect::<Vec<_>>(),
+        [branch_a_parent, branch_a, head_commit_id],
+        "both commits were amended, and the workspace commit was re-merged with a single rebase"
+    );
+
+    write_vrbranches_to_refs(&vb, &repo)?;
+    let blob = |spec: &str| -> anyhow::Result<String> {
+        Ok(repo
+            .rev_parse_single(spec)?
+            .object()?
+            .data
+            .as_bstr()
+            .to_string())
+    };
+    assert_eq!(
+        blob("A~1:file")?,
+        "5\n6\n7a\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n"
+    );
+    assert_eq!(
+        blob("A:file")?,
+        "1\n2a\n3\n4\n5\n6\n7a\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n"
+    );
+    assert_eq!(blob("HEAD:file")?, content);
+    assert_eq!(
+        repo.head_commit()?
+            .parent_ids()
+            .nth(1)
+            .map(|id| id.detach()),
+        Some(repo.rev_parse_single("A")?.detach()),
+        "the workspace merges the rewritten branch"
+    );
+    assert_eq!(but_core::diff::worktree_changes(&repo)?.changes.len(), 0);
+    Ok(())
+}
+
 mod utils {
     use but_testsupport::visualize_commit_graph;
     use gitbutler_stack::VirtualBranchesState;
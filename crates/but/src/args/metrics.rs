@@ -1,11 +1,22 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, strum::Display, clap::ValueEnum, Default)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    clap::ValueEnum,
+    Default,
+    schemars::JsonSchema,
+)]
 #[serde(rename_all = "camelCase")]
 pub enum CommandName {
     Init,
     Absorb,
     Discard,
+    Batch,
     Status,
     Stf,
     Rub,
@@ -15,11 +26,17 @@ pub enum CommandName {
     Push,
     New,
     Reword,
+    Split,
+    Rebase,
     OplogList,
     OplogSnapshot,
     Restore,
     Undo,
     Gui,
+    Tui,
+    Graph,
+    Log,
+    SecretMigrate,
     BaseFetch,
     BaseCheck,
     Pull,
@@ -32,8 +49,14 @@ pub enum CommandName {
     ClaudePreTool,
     ClaudePostTool,
     ClaudeStop,
+    ClaudeUsage,
+    ClaudeBudget,
+    ClaudeAudit,
+    ClaudeTrustPolicy,
+    ClaudeLast,
     CursorAfterEdit,
     CursorStop,
+    AgentHook,
     Worktree,
     Mark,
     Unmark,
@@ -48,6 +71,9 @@ pub enum CommandName {
     AliasRemove,
     RefreshRemoteData,
     Resolve,
+    StagePatch,
+    ActionsList,
+    ActionsHandleChanges,
     #[default]
     Unknown,
 }
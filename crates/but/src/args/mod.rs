@@ -34,6 +34,9 @@ pub struct Args {
     )]
     pub format: OutputFormat,
     /// Whether to use JSON output format.
+    ///
+    /// Each value is an object with the `schemaVersion`, the `kind` of output and its `data`.
+    /// The JSON Schemas of `data` for each kind are published in `crates/but/schemas`.
     #[clap(long, short = 'j', global = true)]
     pub json: bool,
     /// Source entity for rub operation (when no subcommand is specified).
@@ -225,13 +228,21 @@ pub enum Subcommands {
     /// changes from the main development line.
     ///
     /// You can run `but pull --check` first to see if your branches can be cleanly
-    /// merged into the target branch before running the update.
+    /// merged into the target branch before running the update, and which of their
+    /// commits would conflict in which files otherwise.
+    ///
+    /// With `but pull --auto`, each stack is updated according to the project's
+    /// upstream integration policy instead, and each decision is reported. If the
+    /// policy leaves any stack to you, nothing is updated.
     ///
     #[cfg(feature = "legacy")]
     Pull {
         /// Only check the status without updating (equivalent to the old `but base check`)
         #[clap(long, short = 'c')]
         check: bool,
+        /// Update stacks as decided by the project's upstream integration policy
+        #[clap(long, conflicts_with = "check")]
+        auto: bool,
     },
 
     /// Commands for managing branches.
@@ -296,6 +307,95 @@ pub enum Subcommands {
     #[clap(visible_alias = ".")]
     Gui,
 
+    /// Open a full-screen terminal interface to the workspace.
+    ///
+    /// It shows the unassigned changes and each stack side by side, with their branches,
+    /// commits and uncommitted files and hunks, all labelled with their CLI IDs.
+    ///
+    /// Use the arrow keys to select, `s` to stage a file or hunk to a stack, `m` to move
+    /// anything onto anything else like `but rub` would, `enter` to view a diff and `u` to undo
+    /// the last operation.
+    ///
+    #[cfg(feature = "legacy")]
+    Tui,
+
+    /// Export the commit graph for use by other tools.
+    ///
+    /// Writes the graph traversed from `HEAD` as a Mermaid `gitGraph` diagram
+    /// for rendering in dashboards or pull request comments, or as versioned JSON
+    /// with all segments, commits, edges and the workspace projection.
+    ///
+    /// ## Examples
+    ///
+    /// Render the graph as Mermaid diagram:
+    ///
+    /// ```text
+    /// but graph > graph.mmd
+    /// ```
+    ///
+    /// Export the graph as JSON without branch names:
+    ///
+    /// ```text
+    /// but graph --format json --anonymize
+    /// ```
+    ///
+    Graph {
+        /// The format to export the graph in.
+        #[clap(long, value_enum, default_value = "mermaid")]
+        format: graph::Format,
+        /// Replace branch, tag and remote names with generic ones before exporting.
+        #[clap(long)]
+        anonymize: bool,
+    },
+
+    /// Show the history of the workspace, or of a branch and the stack it is in.
+    ///
+    /// Unlike `git log --graph`, the workspace commit is never shown. Instead, each
+    /// stack is shown with its branches and their push status, and each commit
+    /// with its change-id and whether it was pushed or integrated.
+    ///
+    /// ## Examples
+    ///
+    /// Show all stacks in the workspace:
+    ///
+    /// ```text
+    /// but log
+    /// ```
+    ///
+    /// Show the last 10 commits of the stack containing `my-feature` that changed `src/db.rs`:
+    ///
+    /// ```text
+    /// but log my-feature --limit 10 -- src/db.rs
+    /// ```
+    ///
+    Log {
+        /// The branch to show along with the stack it is in, which may also be outside of the workspace.
+        branch: Option<String>,
+        /// The maximum number of commits to show.
+        #[clap(long, short = 'n')]
+        limit: Option<usize>,
+        /// Only show commits that changed these paths, relative to the repository root.
+        #[clap(last = true)]
+        paths: Vec<std::path::PathBuf>,
+    },
+
+    /// Manage where secrets like access tokens are stored.
+    ///
+    /// Secrets are stored in the system keychain by default. On systems without one,
+    /// like headless Linux machines, they can be stored in a passphrase-encrypted file
+    /// or be read from environment variables instead. The store is configured with
+    /// `secrets.store` in the settings, or with the `GITBUTLER_SECRETS_STORE` environment variable.
+    ///
+    /// ## Examples
+    ///
+    /// Move all secrets from the keychain into the encrypted file:
+    ///
+    /// ```text
+    /// GITBUTLER_SECRETS_PASSPHRASE=... but secret migrate --from keychain --to encrypted-file
+    /// ```
+    ///
+    Secret(secret::Platform),
+
     /// Commit changes to a stack.
     ///
     /// The `but commit` command allows you to create a new commit
@@ -391,6 +491,68 @@ pub enum Subcommands {
         message: Option<String>,
     },
 
+    /// Split a commit into two commits by selecting hunks.
+    ///
+    /// Shows all changes of the commit hunk by hunk and lets you interactively
+    /// pick the ones that should go into the first commit. Everything else goes
+    /// into a second commit on top of it. Both commits replace the original one
+    /// in place, and dependent commits are rebased on top of them.
+    ///
+    /// The first commit keeps the change-id of the original commit.
+    ///
+    /// ## Examples
+    ///
+    /// Split a commit, providing both messages in the editor:
+    ///
+    /// ```text
+    /// but split c5
+    /// ```
+    ///
+    /// Split a commit with the messages given on the command-line:
+    ///
+    /// ```text
+    /// but split c5 -m "refactor parser" -m "add new syntax"
+    /// ```
+    ///
+    #[cfg(feature = "legacy")]
+    Split {
+        /// Commit ID to split
+        commit: String,
+        /// The messages of the first and the second commit, in that order.
+        /// Opens an editor for each message that isn't provided.
+        #[clap(short = 'm', long = "message")]
+        messages: Vec<String>,
+    },
+
+    /// Rebase the commits of a branch interactively.
+    ///
+    /// Opens an editor with a git-style todo list of all commits in the stack of
+    /// the branch, from the oldest to the newest. Commits can be re-ordered,
+    /// reworded, squashed, fixed up or dropped, and the branches of the stack can
+    /// be moved with `update-ref` lines. `exec` lines run a shell command on a
+    /// checkout of the commit right above them, for instance to run tests in
+    /// between picks.
+    ///
+    /// The rebase is atomic: if validation or any `exec` command fails, nothing
+    /// changes. Otherwise a snapshot is recorded so it can be undone with `but undo`.
+    ///
+    /// ## Examples
+    ///
+    /// Edit the stack of a branch:
+    ///
+    /// ```text
+    /// but rebase -i my-feature
+    /// ```
+    ///
+    #[cfg(feature = "legacy")]
+    Rebase {
+        /// Edit the commits of the stack in an editor. This is currently the only mode.
+        #[clap(short = 'i', long, required = true)]
+        interactive: bool,
+        /// The name of the branch whose stack should be rebased.
+        branch: String,
+    },
+
     /// Commands for viewing and managing operation history.
     ///
     /// Displays a list of past operations performed in the repository,
@@ -475,6 +637,23 @@ pub enum Subcommands {
         id: String,
     },
 
+    /// Run a sequence of operations read from stdin as a single transaction.
+    ///
+    /// Each line is an operation written like the arguments of a `but` invocation,
+    /// or as JSON array of strings. Empty lines and lines starting with `#` are ignored.
+    /// Only operations that change the workspace, like `rub`, `commit` or `branch`, are supported.
+    ///
+    /// A single oplog snapshot is taken before the first operation. If any operation fails,
+    /// the workspace is restored to it so that either all operations apply, or none.
+    ///
+    /// ## Examples
+    ///
+    /// ```text
+    /// printf 'stage h0 g0\ncommit -m "add the feature" g0\n' | but batch
+    /// ```
+    #[cfg(feature = "legacy")]
+    Batch,
+
     /// Commands for interacting with forges like GitHub, GitLab (coming soon), etc.
     ///
     /// The `but forge` tools allow you to authenticate with a forge from the CLI,
@@ -526,8 +705,12 @@ pub enum Subcommands {
     /// This is the local MCP server that can be used by coding agents to invoke
     /// automatic GitButler commits after code generation or edits.
     ///
-    /// By default, there is only one tool exposed, which is to simply commit changes
-    /// and generate a commit message based on the provided prompt.
+    /// Its main tool is to simply commit changes and generate a commit message based
+    /// on the provided prompt. Next to it, there are tools to inspect and edit the workspace,
+    /// like moving changes between commits, squashing, reordering and splitting, undoing
+    /// operations and pushing. What these tools may do is limited by `--allow`, and pushing
+    /// has to be allowed explicitly. Committing with the main tool is always possible.
+    /// Diffs and commit details are exposed as resources.
     ///
     /// If you invoke with `--internal`, it starts the internal MCP server with
     /// more granular tools, allowing you to ask your agent to do more specific
@@ -539,6 +722,10 @@ pub enum Subcommands {
         /// Starts the internal MCP server which has more granular tools.
         #[clap(long, short = 'i', hide = true)]
         internal: bool,
+        /// What the tools of the MCP server are allowed to do. They only read by default,
+        /// except for `gitbutler_update_branches`, which can always commit.
+        #[clap(long, value_enum, default_value = "read")]
+        allow: mcp::Access,
     },
 
     /// AI: Claude hooks
@@ -561,6 +748,15 @@ pub enum Subcommands {
     #[clap(hide = true)]
     Cursor(cursor::Platform),
 
+    /// AI: Generic agent hooks
+    ///
+    /// Provides lifecycle hooks handlers for any coding agent that can run a shell command,
+    /// assigning the changes of each agent session to its own branch.
+    ///
+    #[cfg(feature = "legacy")]
+    #[clap(hide = true)]
+    Agent(agent::Platform),
+
     /// INTERNAL: GitButler Actions are automated tasks (like macros) that can be performed on a repository.
     #[cfg(feature = "legacy")]
     #[clap(hide = true)]
@@ -677,12 +873,33 @@ pub enum Subcommands {
     /// Stages a file or hunk to a specific branch.
     ///
     /// Wrapper for `but rub <file-or-hunk> <branch>`.
+    ///
+    /// With `--patch`, walks the unassigned hunks, or those of the given file or hunk,
+    /// and asks for each whether to stage it, like `git add -p`.
+    /// Hunks can be split or edited to pick single lines. As only whole hunks can be staged,
+    /// picked lines are committed to the branch right away.
+    ///
+    /// ## Examples
+    ///
+    /// Pick the hunks to stage to a branch:
+    ///
+    /// ```text
+    /// but stage -p my-feature
+    /// ```
     #[cfg(feature = "legacy")]
     Stage {
-        /// File or hunk ID to stage
-        file_or_hunk: String,
+        /// File or hunk ID to stage, or to pick hunks from with `--patch`
+        #[clap(required_unless_present = "patch")]
+        file_or_hunk: Option<String>,
         /// Branch ID to stage to
-        branch: String,
+        #[clap(required_unless_present = "patch")]
+        branch: Option<String>,
+        /// Interactively pick the hunks and lines to stage
+        #[clap(short = 'p', long = "patch")]
+        patch: bool,
+        /// The message of the commit with lines picked from within hunks, instead of asking for it in the editor
+        #[clap(short = 'm', long = "message", requires = "patch")]
+        message: Option<String>,
     },
 
     /// Unstages a file or hunk from a branch.
@@ -736,6 +953,35 @@ pub mod actions {
 }
 
 pub mod forge;
+
+pub mod graph {
+    /// The formats the commit graph can be exported in.
+    #[derive(Debug, Copy, Clone, clap::ValueEnum)]
+    pub enum Format {
+        /// A versioned JSON document for tool consumption.
+        Json,
+        /// A Mermaid `gitGraph` diagram.
+        Mermaid,
+    }
+}
+
+#[cfg(feature = "legacy")]
+pub mod mcp {
+    /// What the tools of the MCP server are allowed to do, with each level including the ones before it.
+    #[derive(
+        Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, strum::Display,
+    )]
+    #[strum(serialize_all = "lowercase")]
+    pub enum Access {
+        /// Only inspect the workspace.
+        Read,
+        /// Also change the workspace, its branches and commits, and undo operations.
+        Write,
+        /// Also push branches to their remote.
+        Push,
+    }
+}
+
 pub mod metrics;
 #[cfg(feature = "legacy")]
 pub mod oplog;
@@ -743,6 +989,51 @@ pub mod push;
 #[cfg(feature = "legacy")]
 pub mod resolve;
 
+pub mod secret {
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        #[clap(subcommand)]
+        pub cmd: Subcommands,
+    }
+    #[derive(Debug, clap::Subcommand)]
+    pub enum Subcommands {
+        /// Move all known secrets from one store to another.
+        ///
+        /// Secrets are removed from the source store once they were written to the target,
+        /// unless the source is the environment. Configure the target store afterwards
+        /// for the secrets to be found there.
+        Migrate {
+            /// The store to read secrets from.
+            #[clap(long, value_enum)]
+            from: Store,
+            /// The store to write secrets to.
+            #[clap(long, value_enum)]
+            to: Store,
+        },
+    }
+
+    /// The stores secrets can be kept in.
+    #[derive(Debug, Copy, Clone, clap::ValueEnum)]
+    pub enum Store {
+        /// The keychain of the operating system.
+        Keychain,
+        /// A file encrypted with the passphrase in `GITBUTLER_SECRETS_PASSPHRASE`.
+        EncryptedFile,
+        /// Environment variables named `GITBUTLER_SECRET_<HANDLE>`, which can only be read.
+        Environment,
+    }
+
+    impl From<Store> for but_secret::secret::Store {
+        fn from(store: Store) -> Self {
+            match store {
+                Store::Keychain => but_secret::secret::Store::Keychain,
+                Store::EncryptedFile => but_secret::secret::Store::EncryptedFile,
+                Store::Environment => but_secret::secret::Store::Environment,
+            }
+        }
+    }
+}
+
 pub mod claude {
     #[derive(Debug, clap::Parser)]
     pub struct Platform {
@@ -761,7 +1052,32 @@ pub mod claude {
             /// The Claude session ID for this MCP server instance
             #[clap(long)]
             session_id: String,
+            /// The directory the Claude session runs in, which paths of the permission policy are relative to.
+            /// It's the project directory if unset.
+            #[clap(long)]
+            workdir: Option<std::path::PathBuf>,
+        },
+        /// Show the tokens used and the estimated cost of Claude sessions, per stack and per session.
+        Usage,
+        /// Set the budget of a Claude session, or remove it if no amount is given.
+        ///
+        /// A session is stopped once its estimated cost reaches its budget.
+        Budget {
+            /// The ID of the Claude session, as shown by `but claude usage`.
+            session_id: String,
+            /// The budget in USD.
+            amount: Option<f64>,
+        },
+        /// Show the permission decisions made during a Claude session, and what made them.
+        Audit {
+            /// The ID of the Claude session, as shown by `but claude usage`.
+            session_id: String,
         },
+        /// Trust the permission policy of the repository as it is now.
+        ///
+        /// The `allow` rules of the policy in `.gitbutler/claude-permissions.json` only pre-approve
+        /// tool calls once it was trusted, and until it changes. Its `deny` rules always apply.
+        TrustPolicy,
         /// Get the last user message (for testing purposes)
         #[clap(hide = true)]
         Last {
@@ -788,6 +1104,29 @@ pub mod cursor {
     }
 }
 
+pub mod agent {
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        #[clap(subcommand)]
+        pub cmd: Subcommands,
+    }
+    #[derive(Debug, clap::Subcommand)]
+    pub enum Subcommands {
+        /// Handle a lifecycle event of an agent, read as JSON from stdin.
+        Hook {
+            /// The protocol of the event.
+            #[clap(long, value_enum, default_value_t = Protocol::Generic)]
+            protocol: Protocol,
+        },
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+    pub enum Protocol {
+        /// Session start, file edit and stop events, see `but_claude::hooks::agent`.
+        Generic,
+    }
+}
+
 pub mod worktree {
     #[derive(Debug, clap::Parser)]
     pub struct Platform {
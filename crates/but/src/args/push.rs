@@ -2,6 +2,12 @@
 pub struct Command {
     /// Branch name or CLI ID to push. If not specified, will list all branches and prompt for selection in interactive mode
     pub branch_id: Option<String>,
+    /// Push all branches of the stack containing the given branch in a single push, which is atomic if the remote supports it
+    #[clap(long, requires = "branch_id", conflicts_with_all = ["all", "dry_run"])]
+    pub stack: bool,
+    /// Push all branches of all applied stacks in a single push, which is atomic if the remote supports it
+    #[clap(long, conflicts_with_all = ["branch_id", "dry_run"])]
+    pub all: bool,
     /// Force push even if it's not fast-forward
     #[clap(long, short = 'f', default_value_t = true)]
     pub with_force: bool,
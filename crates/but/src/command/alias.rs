@@ -2,18 +2,19 @@
 //!
 //! Provides subcommands to list, add, and remove aliases stored in git config.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::Result;
 use bstr::ByteSlice;
 use but_ctx::Context;
 use colored::Colorize;
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::utils::OutputChannel;
 
 /// Represents where an alias is configured
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum AliasScope {
     Local,
@@ -32,13 +33,51 @@ impl std::fmt::Display for AliasScope {
 }
 
 /// An alias entry with its name, value, and scope
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct AliasEntry {
     pub name: String,
     pub value: String,
     pub scope: AliasScope,
 }
 
+/// The JSON output of `but alias`.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub(crate) struct AliasListOutput {
+    /// The aliases configured in Git config.
+    user: Vec<AliasEntry>,
+    /// The values of the built-in aliases by name, which are overridden by user aliases of the same name.
+    default: BTreeMap<String, String>,
+}
+
+/// The JSON output of `but alias add`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct AliasAddOutput {
+    name: String,
+    value: String,
+    /// Either `local` or `global`.
+    scope: AliasScope,
+}
+
+/// The JSON output of `but alias remove`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct AliasRemoveOutput {
+    name: String,
+    /// Either `local` or `global`.
+    scope: AliasScope,
+    /// `false` if there was no alias to remove.
+    removed: bool,
+}
+
+impl AliasScope {
+    fn of_config(global: bool) -> Self {
+        if global {
+            AliasScope::Global
+        } else {
+            AliasScope::Local
+        }
+    }
+}
+
 /// List all configured `but` aliases
 pub fn list(out: &mut OutputChannel) -> Result<()> {
     let user_aliases = get_all_aliases()?;
@@ -54,10 +93,7 @@ pub fn list(out: &mut OutputChannel) -> Result<()> {
             writeln!(out, "Create an alias with:")?;
             writeln!(out, "  but alias add stup 'status --upstream'")?;
         } else if let Some(out) = out.for_json() {
-            out.write_value(serde_json::json!({
-                "user": {},
-                "default": {}
-            }))?;
+            out.write_value(AliasListOutput::default())?;
         }
         return Ok(());
     }
@@ -132,26 +168,10 @@ pub fn list(out: &mut OutputChannel) -> Result<()> {
             }
         }
     } else if let Some(out) = out.for_json() {
-        let user_json: Vec<serde_json::Value> = user_aliases
-            .iter()
-            .map(|a| {
-                serde_json::json!({
-                    "name": a.name,
-                    "value": a.value,
-                    "scope": a.scope
-                })
-            })
-            .collect();
-
-        let default_json: serde_json::Map<String, serde_json::Value> = default_aliases
-            .into_iter()
-            .map(|(k, v)| (k, serde_json::Value::String(v)))
-            .collect();
-
-        out.write_value(serde_json::json!({
-            "user": user_json,
-            "default": default_json
-        }))?;
+        out.write_value(AliasListOutput {
+            user: user_aliases,
+            default: default_aliases.into_iter().collect(),
+        })?;
     }
 
     Ok(())
@@ -281,11 +301,11 @@ pub fn add(
             writeln!(out, "  (configured globally)")?;
         }
     } else if let Some(out) = out.for_json() {
-        out.write_value(serde_json::json!({
-            "name": name,
-            "value": value,
-            "scope": if global { "global" } else { "local" }
-        }))?;
+        out.write_value(AliasAddOutput {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            scope: AliasScope::of_config(global),
+        })?;
     }
 
     Ok(())
@@ -323,11 +343,11 @@ pub fn remove(ctx: &mut Context, out: &mut OutputChannel, name: &str, global: bo
             }
         }
     } else if let Some(out) = out.for_json() {
-        out.write_value(serde_json::json!({
-            "name": name,
-            "scope": if global { "global" } else { "local" },
-            "removed": success
-        }))?;
+        out.write_value(AliasRemoveOutput {
+            name: name.to_owned(),
+            scope: AliasScope::of_config(global),
+            removed: success,
+        })?;
     }
 
     Ok(())
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use but_ctx::Context;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{args::graph::Format, utils::OutputChannel};
+
+/// The JSON output of `but graph`, which depends on its `--format`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum GraphOutput {
+    /// The export of the graph with `--format json`.
+    /// It's meant for debugging, so its layout isn't described and may change at any time.
+    Export(serde_json::Value),
+    /// The Mermaid diagram of the graph with `--format mermaid`.
+    Mermaid(String),
+}
+
+/// Write the commit graph of the project in `ctx` in the given `format`, with all names
+/// replaced if `anonymize` is set.
+///
+/// Human and shell output receive the export as is so it can be redirected into a file,
+/// while JSON output wraps Mermaid diagrams into a string.
+pub fn export(
+    ctx: &Context,
+    out: &mut OutputChannel,
+    format: Format,
+    anonymize: bool,
+) -> Result<()> {
+    let text = match format {
+        Format::Json => {
+            let graph = but_api::graph::graph_json(ctx, anonymize)?;
+            if let Some(out) = out.for_json() {
+                out.write_value(GraphOutput::Export(serde_json::to_value(graph)?))?;
+                return Ok(());
+            }
+            let mut text = serde_json::to_string_pretty(&graph)?;
+            text.push('\n');
+            text
+        }
+        Format::Mermaid => {
+            let mermaid = but_api::graph::graph_mermaid(ctx, anonymize)?;
+            if let Some(out) = out.for_json() {
+                out.write_value(GraphOutput::Mermaid(mermaid))?;
+                return Ok(());
+            }
+            mermaid
+        }
+    };
+
+    if let Some(out) = out.for_human() {
+        write!(out, "{text}")?;
+    } else if let Some(out) = out.for_shell() {
+        write!(out, "{text}")?;
+    }
+    Ok(())
+}
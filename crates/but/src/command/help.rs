@@ -32,7 +32,10 @@ pub fn print_grouped(out: &mut dyn std::fmt::Write) -> std::fmt::Result {
 
     // Define command groupings and their order (excluding MISC)
     let groups = [
-        ("Inspection".yellow(), vec!["status", "diff"]),
+        (
+            "Inspection".yellow(),
+            vec!["status", "log", "diff", "graph", "tui"],
+        ),
         (
             "Branching and Committing".yellow(),
             vec![
@@ -45,11 +48,13 @@ pub fn print_grouped(out: &mut dyn std::fmt::Write) -> std::fmt::Result {
         ),
         (
             "Editing Commits".yellow(),
-            vec!["rub", "absorb", "reword", "uncommit", "amend"],
+            vec![
+                "rub", "absorb", "reword", "split", "rebase", "uncommit", "amend",
+            ],
         ),
         (
             "Operation History".yellow(),
-            vec!["oplog", "undo", "restore"],
+            vec!["oplog", "undo", "restore", "batch"],
         ),
     ];
 
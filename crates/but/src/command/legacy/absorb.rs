@@ -1,20 +1,18 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use bstr::{BString, ByteSlice};
-use but_api::{
-    json::HexHash,
-    legacy::{diff, virtual_branches},
-};
-use but_core::DiffSpec;
+use but_api::legacy::{diff, virtual_branches};
+use but_core::{DiffSpec, HunkHeader, ref_metadata::StackId};
 use but_ctx::Context;
 use but_hunk_assignment::HunkAssignment;
-use but_hunk_dependency::ui::HunkDependencies;
+use but_hunk_dependency::{HunkRange, absorb::absorb_hunk};
 use colored::Colorize;
 use gitbutler_oplog::{
     OplogExt,
     entry::{OperationKind, SnapshotDetails},
 };
 use gitbutler_project::Project;
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::{
@@ -22,10 +20,10 @@ use crate::{
 };
 
 /// Reason why a file is being absorbed to a particular commit
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum AbsorptionReason {
-    /// File has hunk range overlap with this commit
+    /// The changed lines were last changed by this commit
     HunkDependency,
     /// File is assigned to this stack and this is the topmost commit
     StackAssignment,
@@ -47,13 +45,15 @@ impl AbsorptionReason {
 #[derive(Debug, Clone)]
 struct FileAbsorption {
     path: String,
-    assignment: HunkAssignment,
+    path_bytes: BString,
+    /// The hunks or lines to absorb, or all changes in the file if empty, as with binary files.
+    hunk_headers: Vec<HunkHeader>,
 }
 
 /// Information about absorptions grouped by commit
 #[derive(Debug)]
 struct CommitAbsorption {
-    stack_id: but_core::ref_metadata::StackId,
+    stack_id: StackId,
     commit_id: gix::ObjectId,
     commit_summary: String,
     files: Vec<FileAbsorption>,
@@ -61,14 +61,14 @@ struct CommitAbsorption {
 }
 
 /// JSON output structure for a file being absorbed
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct JsonFileAbsorption {
     path: String,
     hunks: Vec<String>,
 }
 
 /// JSON output structure for a commit absorption
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct JsonCommitAbsorption {
     commit_id: String,
     commit_summary: String,
@@ -78,17 +78,17 @@ struct JsonCommitAbsorption {
 }
 
 /// JSON output structure for the entire absorb operation
-#[derive(Debug, Serialize)]
-struct JsonAbsorbOutput {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct JsonAbsorbOutput {
     total_files: usize,
     commits: Vec<JsonCommitAbsorption>,
 }
 
 /// Type alias for grouped changes by commit
-type GroupedChanges = BTreeMap<
-    (but_core::ref_metadata::StackId, gix::ObjectId),
-    (Vec<HunkAssignment>, AbsorptionReason),
->;
+type GroupedChanges = BTreeMap<(StackId, gix::ObjectId), (Vec<FileAbsorption>, AbsorptionReason)>;
+
+/// The hunk ranges of all commits in the workspace, by path
+type RangesByPath = HashMap<BString, Vec<HunkRange>>;
 
 /// Amends changes into the appropriate commits where they belong.
 ///
@@ -97,6 +97,10 @@ type GroupedChanges = BTreeMap<
 /// - If a change is assigned to a particular lane (branch), it will be amended into a commit there
 ///     - If there are no commits in this branch, a new commit is created
 /// - If a change has a dependency to a particular commit, it will be amended into that particular commit
+///     - Hunks are split into lines, so each line is amended into the commit that last changed it
+///     - Lines that no commit changed are amended like unassigned changes, or changes assigned to a lane
+///
+/// All commits of a lane are amended with a single rebase.
 ///
 /// Optionally an identifier to an Uncommitted File or a Branch (stack) may be provided.
 ///
@@ -118,10 +122,10 @@ pub(crate) fn handle(
             })
         });
 
-    // Get all worktree changes, assignments, and dependencies
+    // Get all worktree changes and assignments, along with the ranges of all commits to find the ones changes belong to
     let worktree_changes = diff::changes_in_worktree(ctx)?;
     let assignments = worktree_changes.assignments;
-    let dependencies = worktree_changes.dependencies;
+    let ranges = workspace_ranges(ctx);
 
     // Create a snapshot before performing absorb operations
     // This allows the user to undo with `but undo` if needed
@@ -134,15 +138,15 @@ pub(crate) fn handle(
             }) => {
                 // Absorb this particular file
                 absorb_assignments(
-                    &ctx.legacy_project,
+                    ctx,
                     hunk_assignments.into_iter().collect::<Vec<_>>().as_slice(),
-                    &dependencies,
+                    &ranges,
                     out,
                 )?;
             }
             CliId::Branch { name, .. } => {
                 // Absorb everything that is assigned to this lane
-                absorb_branch(&ctx.legacy_project, &name, &assignments, &dependencies, out)?;
+                absorb_branch(ctx, &name, &assignments, &ranges, out)?;
             }
             _ => {
                 anyhow::bail!("Invalid source: expected an uncommitted file or branch");
@@ -150,78 +154,45 @@ pub(crate) fn handle(
         }
     } else {
         // Try to absorb everything uncommitted
-        absorb_all(&ctx.legacy_project, &assignments, &dependencies, out)?;
+        absorb_all(ctx, &assignments, &ranges, out)?;
     }
     Ok(())
 }
 
+/// Compute the hunk ranges of all commits in the workspace, or none if that fails,
+/// in which case changes are absorbed by their assignment only.
+fn workspace_ranges(ctx: &Context) -> RangesByPath {
+    ctx.clone_repo_for_merging_non_persisting()
+        .and_then(|repo| but_hunk_dependency::ui::workspace_ranges(ctx, &repo))
+        .map(|ranges| ranges.ranges_by_path_map().clone())
+        .inspect_err(|err| tracing::warn!("Failed to compute hunk ranges for absorb: {err:#}"))
+        .unwrap_or_default()
+}
+
 /// Absorb a single file into the appropriate commit
 fn absorb_assignments(
-    project: &Project,
+    ctx: &mut Context,
     assignments: &[HunkAssignment],
-    dependencies: &Option<HunkDependencies>,
+    ranges: &RangesByPath,
     out: &mut OutputChannel,
 ) -> anyhow::Result<()> {
     // Group changes by their target commit
-    let changes_by_commit = group_changes_by_target_commit(project.id, assignments, dependencies)?;
-
-    // Prepare commit absorptions for display
-    let commit_absorptions = prepare_commit_absorptions(project, changes_by_commit)?;
-
-    // Display the plan
-    display_absorption_plan(&commit_absorptions, out)?;
-
-    // Apply each group to its target commit and track failures
-    let mut total_rejected = 0;
-    for absorption in commit_absorptions {
-        let diff_specs = convert_assignments_to_diff_specs(
-            &absorption
-                .files
-                .iter()
-                .map(|f| f.assignment.clone())
-                .collect::<Vec<_>>(),
-        )?;
-        let rejected = amend_commit_and_count_failures(
-            project,
-            absorption.stack_id,
-            absorption.commit_id,
-            diff_specs,
-        )?;
-        total_rejected += rejected;
-    }
-
-    // Display completion message
-    if let Some(out) = out.for_human() {
-        writeln!(out)?;
-        if total_rejected > 0 {
-            writeln!(
-                out,
-                "{}: Failed to absorb {} file{}",
-                "Warning".yellow(),
-                total_rejected,
-                if total_rejected == 1 { "" } else { "s" }
-            )?;
-        }
-        writeln!(
-            out,
-            "{}: you can run `but undo` to undo these changes",
-            "Hint".cyan()
-        )?;
-    }
-
-    Ok(())
+    let changes_by_commit =
+        group_changes_by_target_commit(ctx.legacy_project.id, assignments, ranges)?;
+    absorb_changes(ctx, changes_by_commit, out)
 }
 
 /// Absorb all files assigned to a specific branch/stack
 fn absorb_branch(
-    project: &Project,
+    ctx: &mut Context,
     branch_name: &str,
     assignments: &[HunkAssignment],
-    dependencies: &Option<HunkDependencies>,
+    ranges: &RangesByPath,
     out: &mut OutputChannel,
 ) -> anyhow::Result<()> {
+    let project_id = ctx.legacy_project.id;
     // Get the stack ID for this branch
-    let stacks = but_api::legacy::workspace::stacks(project.id, None)?;
+    let stacks = but_api::legacy::workspace::stacks(project_id, None)?;
 
     // Find the stack that contains this branch
     let stack = stacks
@@ -247,61 +218,15 @@ fn absorb_branch(
     }
 
     // Group changes by their target commit
-    let changes_by_commit =
-        group_changes_by_target_commit(project.id, &stack_assignments, dependencies)?;
-
-    // Prepare commit absorptions for display
-    let commit_absorptions = prepare_commit_absorptions(project, changes_by_commit)?;
-
-    // Display the plan
-    display_absorption_plan(&commit_absorptions, out)?;
-
-    // Apply each group to its target commit and track failures
-    let mut total_rejected = 0;
-    for absorption in commit_absorptions {
-        let diff_specs = convert_assignments_to_diff_specs(
-            &absorption
-                .files
-                .iter()
-                .map(|f| f.assignment.clone())
-                .collect::<Vec<_>>(),
-        )?;
-        let rejected = amend_commit_and_count_failures(
-            project,
-            absorption.stack_id,
-            absorption.commit_id,
-            diff_specs,
-        )?;
-        total_rejected += rejected;
-    }
-
-    // Display completion message
-    if let Some(out) = out.for_human() {
-        writeln!(out)?;
-        if total_rejected > 0 {
-            writeln!(
-                out,
-                "{}: Failed to absorb {} file{}",
-                "Warning".yellow(),
-                total_rejected,
-                if total_rejected == 1 { "" } else { "s" }
-            )?;
-        }
-        writeln!(
-            out,
-            "{}: you can run `but undo` to undo these changes",
-            "Hint".cyan()
-        )?;
-    }
-
-    Ok(())
+    let changes_by_commit = group_changes_by_target_commit(project_id, &stack_assignments, ranges)?;
+    absorb_changes(ctx, changes_by_commit, out)
 }
 
 /// Absorb all uncommitted changes
 fn absorb_all(
-    project: &Project,
+    ctx: &mut Context,
     assignments: &[HunkAssignment],
-    dependencies: &Option<HunkDependencies>,
+    ranges: &RangesByPath,
     out: &mut OutputChannel,
 ) -> anyhow::Result<()> {
     if assignments.is_empty() {
@@ -312,32 +237,25 @@ fn absorb_all(
     }
 
     // Group all changes by their target commit
-    let changes_by_commit = group_changes_by_target_commit(project.id, assignments, dependencies)?;
+    let changes_by_commit =
+        group_changes_by_target_commit(ctx.legacy_project.id, assignments, ranges)?;
+    absorb_changes(ctx, changes_by_commit, out)
+}
 
+/// Display the plan for absorbing `changes_by_commit`, and amend all commits with their changes
+fn absorb_changes(
+    ctx: &mut Context,
+    changes_by_commit: GroupedChanges,
+    out: &mut OutputChannel,
+) -> anyhow::Result<()> {
     // Prepare commit absorptions for display
-    let commit_absorptions = prepare_commit_absorptions(project, changes_by_commit)?;
+    let commit_absorptions = prepare_commit_absorptions(&ctx.legacy_project, changes_by_commit)?;
 
     // Display the plan
     display_absorption_plan(&commit_absorptions, out)?;
 
-    // Apply each group to its target commit and track failures
-    let mut total_rejected = 0;
-    for absorption in commit_absorptions {
-        let diff_specs = convert_assignments_to_diff_specs(
-            &absorption
-                .files
-                .iter()
-                .map(|f| f.assignment.clone())
-                .collect::<Vec<_>>(),
-        )?;
-        let rejected = amend_commit_and_count_failures(
-            project,
-            absorption.stack_id,
-            absorption.commit_id,
-            diff_specs,
-        )?;
-        total_rejected += rejected;
-    }
+    // Apply all groups to their target commits and track failures
+    let total_rejected = amend_commits_and_count_failures(ctx, &commit_absorptions)?;
 
     // Display completion message
     if let Some(out) = out.for_human() {
@@ -361,65 +279,95 @@ fn absorb_all(
     Ok(())
 }
 
-/// Group changes by their target commit based on dependencies and assignments
+/// Group changes by their target commit based on the lines they change and assignments
 fn group_changes_by_target_commit(
     project_id: gitbutler_project::ProjectId,
     assignments: &[HunkAssignment],
-    dependencies: &Option<HunkDependencies>,
+    ranges: &RangesByPath,
 ) -> anyhow::Result<GroupedChanges> {
     let mut changes_by_commit: GroupedChanges = BTreeMap::new();
-
-    // Process each assignment
-    for assignment in assignments {
-        // Determine the target commit for this assignment
-        let (stack_id, commit_id, reason) =
-            determine_target_commit(project_id, assignment, dependencies)?;
-
+    let mut add = |(stack_id, commit_id, reason): (StackId, gix::ObjectId, AbsorptionReason),
+                   assignment: &HunkAssignment,
+                   hunk_headers: Vec<HunkHeader>| {
         let entry = changes_by_commit
             .entry((stack_id, commit_id))
             .or_insert_with(|| (Vec::new(), reason.clone()));
 
-        entry.0.push(assignment.clone());
+        entry.0.push(FileAbsorption {
+            path: assignment.path.clone(),
+            path_bytes: assignment.path_bytes.clone(),
+            hunk_headers,
+        });
         // If we have any hunk dependencies, that takes precedence as the reason for this commit group
         if reason == AbsorptionReason::HunkDependency {
             entry.1 = reason;
         }
+    };
+
+    // Process each assignment
+    for assignment in assignments {
+        let whole_hunk: Vec<_> = assignment.hunk_header.into_iter().collect();
+        // Split the hunk into lines, and find the commit that last changed each of them
+        let mut headers_by_commit = BTreeMap::<_, Vec<_>>::new();
+        let mut unowned_headers = Vec::new();
+        if let (Some(header), Some(diff), Some(path_ranges)) = (
+            &assignment.hunk_header,
+            &assignment.diff,
+            ranges.get(&assignment.path_bytes),
+        ) {
+            for part in absorb_hunk(path_ranges, header, diff.as_bstr()) {
+                match part.target {
+                    Some(lock) => headers_by_commit
+                        .entry((lock.stack_id, lock.commit_id))
+                        .or_default()
+                        .push(part.header),
+                    None => unowned_headers.push(part.header),
+                }
+            }
+        }
+
+        if headers_by_commit.is_empty() {
+            // No line was changed by a commit, so absorb the whole hunk by its assignment
+            add(
+                fallback_target_commit(project_id, assignment)?,
+                assignment,
+                whole_hunk,
+            );
+        } else if headers_by_commit.len() == 1 && unowned_headers.is_empty() {
+            // All lines belong to the same commit, so there is no need to split the hunk
+            let (stack_id, commit_id) = headers_by_commit.into_keys().next().expect("one commit");
+            add(
+                (stack_id, commit_id, AbsorptionReason::HunkDependency),
+                assignment,
+                whole_hunk,
+            );
+        } else {
+            for ((stack_id, commit_id), headers) in headers_by_commit {
+                add(
+                    (stack_id, commit_id, AbsorptionReason::HunkDependency),
+                    assignment,
+                    headers,
+                );
+            }
+            if !unowned_headers.is_empty() {
+                add(
+                    fallback_target_commit(project_id, assignment)?,
+                    assignment,
+                    unowned_headers,
+                );
+            }
+        }
     }
 
     Ok(changes_by_commit)
 }
 
-/// Determine the target commit for an assignment based on dependencies and assignments
-fn determine_target_commit(
+/// Determine the target commit for an assignment whose changes don't belong to any commit, based on its assignment
+fn fallback_target_commit(
     project_id: gitbutler_project::ProjectId,
     assignment: &HunkAssignment,
-    dependencies: &Option<HunkDependencies>,
-) -> anyhow::Result<(
-    but_core::ref_metadata::StackId,
-    gix::ObjectId,
-    AbsorptionReason,
-)> {
-    // Priority 1: Check if there's a dependency lock for this hunk
-    if let Some(deps) = dependencies
-        && let Some(_hunk_id) = assignment.id
-    {
-        // Find the dependency for this hunk
-        for (path, _hunk, locks) in &deps.diffs {
-            // Match by path and hunk content
-            if path == &assignment.path {
-                // If there's a lock (dependency), use the topmost commit
-                if let Some(lock) = locks.first() {
-                    return Ok((
-                        lock.stack_id,
-                        lock.commit_id,
-                        AbsorptionReason::HunkDependency,
-                    ));
-                }
-            }
-        }
-    }
-
-    // Priority 2: Use the assignment's stack ID if available
+) -> anyhow::Result<(StackId, gix::ObjectId, AbsorptionReason)> {
+    // Priority 1: Use the assignment's stack ID if available
     if let Some(stack_id) = assignment.stack_id {
         // We need to find the topmost commit in this stack
         let stack_details = but_api::legacy::workspace::stack_details(project_id, Some(stack_id))?;
@@ -445,7 +393,7 @@ fn determine_target_commit(
         anyhow::bail!("Failed to create blank commit in stack: {:?}", stack_id);
     }
 
-    // Priority 3: If no assignment, find the topmost commit of the leftmost lane
+    // Priority 2: If no assignment, find the topmost commit of the leftmost lane
     let stacks = but_api::legacy::workspace::stacks(project_id, None)?;
     if let Some(stack) = stacks.first()
         && let Some(stack_id) = stack.id
@@ -478,38 +426,27 @@ fn determine_target_commit(
     );
 }
 
-/// Convert HunkAssignments to DiffSpecs
-fn convert_assignments_to_diff_specs(
-    assignments: &[HunkAssignment],
-) -> anyhow::Result<Vec<DiffSpec>> {
-    let mut specs_by_path: BTreeMap<BString, Vec<HunkAssignment>> = BTreeMap::new();
+/// Convert file absorptions to DiffSpecs
+fn convert_files_to_diff_specs(files: &[FileAbsorption]) -> Vec<DiffSpec> {
+    let mut hunks_by_path: BTreeMap<BString, Vec<HunkHeader>> = BTreeMap::new();
 
-    // Group assignments by file path
-    for assignment in assignments {
-        specs_by_path
-            .entry(assignment.path_bytes.clone())
+    // Group hunks by file path
+    for file in files {
+        hunks_by_path
+            .entry(file.path_bytes.clone())
             .or_default()
-            .push(assignment.clone());
+            .extend(file.hunk_headers.iter().copied());
     }
 
     // Convert to DiffSpecs
-    let mut diff_specs = Vec::new();
-    for (path, hunks) in specs_by_path {
-        let mut hunk_headers = Vec::new();
-        for hunk in hunks {
-            if let Some(header) = hunk.hunk_header {
-                hunk_headers.push(header);
-            }
-        }
-
-        diff_specs.push(DiffSpec {
+    hunks_by_path
+        .into_iter()
+        .map(|(path, hunk_headers)| DiffSpec {
             previous_path: None, // TODO: Handle renames
-            path: path.clone(),
+            path,
             hunk_headers,
-        });
-    }
-
-    Ok(diff_specs)
+        })
+        .collect()
 }
 
 /// Prepare commit absorptions with commit summaries
@@ -522,18 +459,10 @@ fn prepare_commit_absorptions(
     // Open the repository to read commit messages
     let repo = project.open_repo()?;
 
-    for ((stack_id, commit_id), (assignments, reason)) in changes_by_commit {
+    for ((stack_id, commit_id), (files, reason)) in changes_by_commit {
         // Get commit summary from the git commit
         let commit_summary = get_commit_summary(&repo, commit_id)?;
 
-        let mut files = Vec::new();
-        for assignment in assignments {
-            files.push(FileAbsorption {
-                path: assignment.path.clone(),
-                assignment,
-            });
-        }
-
         commit_absorptions.push(CommitAbsorption {
             stack_id,
             commit_id,
@@ -574,12 +503,12 @@ fn format_hunk_range(hunk_header: &but_core::HunkHeader) -> String {
 }
 
 /// Get all hunk ranges for a file
-fn get_hunk_ranges(assignment: &HunkAssignment) -> Vec<String> {
-    if let Some(hunk_header) = &assignment.hunk_header {
-        vec![format_hunk_range(hunk_header)]
-    } else {
+fn get_hunk_ranges(file: &FileAbsorption) -> Vec<String> {
+    if file.hunk_headers.is_empty() {
         // Binary file or file too large - no hunk information
         vec!["(binary or large file)".to_string()]
+    } else {
+        file.hunk_headers.iter().map(format_hunk_range).collect()
     }
 }
 
@@ -613,7 +542,7 @@ fn display_absorption_plan(
                     .files
                     .iter()
                     .map(|file| {
-                        let hunks = get_hunk_ranges(&file.assignment);
+                        let hunks = get_hunk_ranges(file);
 
                         JsonFileAbsorption {
                             path: file.path.clone(),
@@ -659,7 +588,7 @@ fn display_absorption_plan(
             writeln!(out, "  ({})", absorption.reason.description().dimmed())?;
 
             for file in &absorption.files {
-                let hunks = get_hunk_ranges(&file.assignment);
+                let hunks = get_hunk_ranges(file);
                 let hunk_display = hunks.join(", ");
 
                 writeln!(out, "    {} {}", file.path, hunk_display.dimmed())?;
@@ -671,21 +600,40 @@ fn display_absorption_plan(
     Ok(())
 }
 
-/// Amend a commit with the given changes and return the number of rejected files
-fn amend_commit_and_count_failures(
-    project: &Project,
-    stack_id: but_core::ref_metadata::StackId,
-    commit_id: gix::ObjectId,
-    diff_specs: Vec<DiffSpec>,
+/// Amend all commits with their changes, with a single rebase per stack, and return the number of rejected files
+fn amend_commits_and_count_failures(
+    ctx: &mut Context,
+    commit_absorptions: &[CommitAbsorption],
 ) -> anyhow::Result<usize> {
-    // Convert commit_id to HexHash
-    let hex_hash = HexHash::from(commit_id);
+    let amendments: Vec<_> = commit_absorptions
+        .iter()
+        .map(|absorption| {
+            (
+                absorption.stack_id,
+                absorption.commit_id,
+                convert_files_to_diff_specs(&absorption.files),
+            )
+        })
+        .collect();
+    if amendments.is_empty() {
+        return Ok(0);
+    }
+    let num_specs: usize = amendments.iter().map(|(_, _, specs)| specs.len()).sum();
 
-    let outcome = but_api::legacy::workspace::amend_commit_from_worktree_changes(
-        project.id, stack_id, hex_hash, diff_specs,
+    let mut guard = ctx.exclusive_worktree_access();
+    let outcome = but_workspace::legacy::commit_engine::amend_commits_and_update_refs_with_project(
+        &ctx.clone_repo_for_merging()?,
+        &ctx.project_data_dir(),
+        amendments,
+        ctx.settings().context_lines,
+        guard.write_permission(),
     )?;
 
-    Ok(outcome.paths_to_rejected_changes.len())
+    // Nothing was rewritten, so none of the changes were absorbed.
+    if outcome.commit_mapping.is_empty() {
+        return Ok(num_specs);
+    }
+    Ok(outcome.rejected_specs.len())
 }
 
 /// Create a snapshot in the oplog before performing an operation
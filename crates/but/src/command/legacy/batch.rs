@@ -0,0 +1,255 @@
+//! Run a sequence of operations read from stdin as a single transaction.
+//!
+//! Each operation is the arguments of a `but` invocation, either on a line of its own like
+//! `rub h0 g0` or `commit -m "fix: the thing"`, or as JSON array of strings like `["rub", "h0", "g0"]`.
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! All operations share one [`Context`] and are covered by a single oplog snapshot taken before the first one.
+//! If one of them fails, the workspace is restored to that snapshot.
+
+use std::{ffi::OsString, io::BufRead};
+
+use anyhow::{Context as _, bail};
+use but_ctx::Context;
+use clap::Parser as _;
+use colored::Colorize;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    args::{Args, Subcommands, branch, metrics::CommandName},
+    command::legacy::{absorb, commit, discard, mark, reword, rub},
+    utils::OutputChannel,
+};
+
+/// An operation as read from the input.
+struct Operation {
+    /// The line of the input the operation was read from, 1-based.
+    line_number: usize,
+    /// The operation as written in the input.
+    text: String,
+    /// What to do.
+    cmd: Subcommands,
+}
+
+/// The JSON output of `but batch`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BatchOutput {
+    /// The amount of operations that were applied.
+    operations: usize,
+    /// The oplog snapshot taken before the first operation, which can be restored to undo all of them.
+    snapshot: String,
+}
+
+/// Read operations from `input` and apply them in order, or none of them if one fails.
+pub(crate) fn handle(
+    ctx: &mut Context,
+    out: &mut OutputChannel,
+    input: impl BufRead,
+) -> anyhow::Result<()> {
+    let operations = parse(input)?;
+    if operations.is_empty() {
+        if let Some(out) = out.for_human() {
+            writeln!(out, "No operations to run.")?;
+        } else if let Some(out) = out.for_json() {
+            out.write_value(BatchOutput {
+                operations: 0,
+                snapshot: String::new(),
+            })?;
+        }
+        return Ok(());
+    }
+
+    let snapshot = {
+        let mut details = SnapshotDetails::new(OperationKind::OnDemandSnapshot);
+        details.body = Some(format!(
+            "Before running a batch of {} operations",
+            operations.len()
+        ));
+        let mut guard = ctx.exclusive_worktree_access();
+        ctx.create_snapshot(details, guard.write_permission())
+            .context("Failed to create the snapshot to roll back to")?
+    };
+
+    let num_operations = operations.len();
+    let res = {
+        // The batch snapshot covers all operations.
+        let _suppression = gitbutler_oplog::suppress_snapshots();
+        operations.into_iter().try_for_each(
+            |Operation {
+                 line_number,
+                 text,
+                 cmd,
+             }| {
+                apply(ctx, out, cmd)
+                    .with_context(|| format!("Operation `{text}` on line {line_number} failed"))
+            },
+        )
+    };
+    out.set_kind(CommandName::Batch);
+
+    if let Err(err) = res {
+        let mut guard = ctx.exclusive_worktree_access();
+        let rollback = ctx.restore_snapshot(snapshot, guard.write_permission());
+        return Err(match rollback {
+            Ok(_) => err.context("Rolled back all operations of the batch."),
+            Err(rollback_err) => err.context(format!(
+                "Failed to roll back the batch, restore snapshot {snapshot} with `but restore`: {rollback_err:#}"
+            )),
+        });
+    }
+
+    if let Some(out) = out.for_human() {
+        writeln!(
+            out,
+            "{} Run `but restore {}` to revert them all.",
+            format!("Applied {num_operations} operations.").green(),
+            &snapshot.to_string()[..7]
+        )?;
+    } else if let Some(out) = out.for_json() {
+        out.write_value(BatchOutput {
+            operations: num_operations,
+            snapshot: snapshot.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Read all operations from `input`, failing if any of them is invalid so nothing runs.
+fn parse(input: impl BufRead) -> anyhow::Result<Vec<Operation>> {
+    let mut operations = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.context("Failed to read operations from stdin")?;
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let cmd = parse_operation(text)
+            .with_context(|| format!("Invalid operation on line {line_number}: `{text}`"))?;
+        operations.push(Operation {
+            line_number,
+            text: text.to_owned(),
+            cmd,
+        });
+    }
+    Ok(operations)
+}
+
+/// Parse `text` into the subcommand it invokes, like the command-line of `but` would be.
+fn parse_operation(text: &str) -> anyhow::Result<Subcommands> {
+    let mut words: Vec<String> = if text.starts_with('[') {
+        serde_json::from_str(text).context("Expected a JSON array of strings")?
+    } else {
+        shell_words::split(text)?
+    };
+    if words.first().is_some_and(|word| word == "but") {
+        words.remove(0);
+    }
+    let name = words.first().cloned().unwrap_or_default();
+    let args = std::iter::once("but".into())
+        .chain(words.into_iter().map(OsString::from))
+        .collect();
+    let args = crate::alias::expand_aliases(args)?;
+    let args = Args::try_parse_from(args)?;
+    if args.current_dir != std::path::Path::new(".") {
+        bail!("All operations run in the same repository, so `-C` can't be used");
+    }
+    let cmd = match (args.cmd, args.source_or_path, args.target) {
+        (Some(cmd), _, _) => cmd,
+        (None, Some(source), Some(target)) => Subcommands::Rub { source, target },
+        (None, _, _) => bail!("Expected a command"),
+    };
+    if !is_supported(&cmd) {
+        bail!("`but {name}` can't be used in a batch");
+    }
+    Ok(cmd)
+}
+
+/// Return `true` if `cmd` changes the workspace in a way that can be rolled back.
+fn is_supported(cmd: &Subcommands) -> bool {
+    matches!(
+        cmd,
+        Subcommands::Rub { .. }
+            | Subcommands::Stage { patch: false, .. }
+            | Subcommands::Unstage { .. }
+            | Subcommands::Amend { .. }
+            | Subcommands::Uncommit { .. }
+            | Subcommands::Squash { .. }
+            | Subcommands::Commit { .. }
+            | Subcommands::New { .. }
+            | Subcommands::Reword { .. }
+            | Subcommands::Absorb { .. }
+            | Subcommands::Discard { .. }
+            | Subcommands::Mark { .. }
+            | Subcommands::Unmark
+            | Subcommands::Branch(_)
+    )
+}
+
+/// Apply `op` just like its `but` invocation would.
+fn apply(ctx: &mut Context, out: &mut OutputChannel, cmd: Subcommands) -> anyhow::Result<()> {
+    out.set_kind(cmd.to_metrics_command());
+    match cmd {
+        Subcommands::Rub { source, target } => rub::handle(ctx, out, &source, &target),
+        Subcommands::Stage {
+            file_or_hunk: Some(file_or_hunk),
+            branch: Some(branch),
+            patch: false,
+            ..
+        } => rub::handle_stage(ctx, out, &file_or_hunk, &branch),
+        Subcommands::Unstage {
+            file_or_hunk,
+            branch,
+        } => rub::handle_unstage(ctx, out, &file_or_hunk, branch.as_deref()),
+        Subcommands::Amend { file, commit } => rub::handle_amend(ctx, out, &file, &commit),
+        Subcommands::Uncommit { source } => rub::handle_uncommit(ctx, out, &source),
+        Subcommands::Squash {
+            commit1,
+            commit2,
+            drop_message,
+        } => rub::handle_squash(ctx, out, &commit1, &commit2, drop_message),
+        Subcommands::Commit {
+            message,
+            file,
+            branch,
+            create,
+            only,
+        } => {
+            let message = match file {
+                Some(path) => Some(std::fs::read_to_string(&path).with_context(|| {
+                    format!(
+                        "Failed to read commit message from file: {}",
+                        path.display()
+                    )
+                })?),
+                None => message,
+            };
+            commit::commit(
+                ctx,
+                out,
+                message.as_deref(),
+                branch.as_deref(),
+                only,
+                create,
+            )
+        }
+        Subcommands::New { target } => commit::insert_blank_commit(ctx, out, &target),
+        Subcommands::Reword { target, message } => {
+            reword::reword_target(ctx, out, &target, message.as_deref())
+        }
+        Subcommands::Absorb { source } => absorb::handle(ctx, out, source.as_deref()),
+        Subcommands::Discard { id } => discard::handle(ctx, out, &id),
+        Subcommands::Mark { target, delete } => mark::handle(ctx, out, &target, delete),
+        Subcommands::Unmark => mark::unmark(ctx, out),
+        Subcommands::Branch(branch::Platform { cmd }) => {
+            crate::command::legacy::branch::handle(cmd, ctx, out)
+        }
+        _ => bail!("This operation can't be used in a batch"),
+    }
+}
@@ -1,13 +1,14 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BranchNewOutput {
     pub branch: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub anchor: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BranchListOutput {
     pub applied_stacks: Vec<StackOutput>,
@@ -16,7 +17,7 @@ pub struct BranchListOutput {
     pub more_branches: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StackOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -24,7 +25,7 @@ pub struct StackOutput {
     pub heads: Vec<BranchHeadOutput>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BranchHeadOutput {
     pub name: String,
@@ -39,7 +40,7 @@ pub struct BranchHeadOutput {
     pub merges_cleanly: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BranchOutput {
     pub name: String,
@@ -55,14 +56,14 @@ pub struct BranchOutput {
     pub merges_cleanly: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthorOutput {
     pub name: Option<String>,
     pub email: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReviewOutput {
     pub number: u64,
@@ -7,9 +7,9 @@ use but_workspace::legacy::ui::StackEntry;
 use crate::{CliId, IdMap, args::branch, utils::OutputChannel};
 
 mod apply;
-mod json;
+pub(crate) mod json;
 mod list;
-mod show;
+pub(crate) mod show;
 
 pub fn handle(
     cmd: Option<Subcommands>,
@@ -2,6 +2,8 @@ use but_ctx::Context;
 use but_oxidize::OidExt;
 use colored::Colorize;
 use gitbutler_project::Project;
+use schemars::JsonSchema;
+use serde::Serialize;
 use tracing::instrument;
 
 use super::list::load_id_map;
@@ -58,7 +60,11 @@ pub fn show(
 
     // Generate AI summary if requested
     let ai_summary = if generate_ai_summary {
-        Some(generate_branch_summary(&branch_name, &commits)?)
+        Some(generate_branch_summary(
+            &ctx.legacy_project,
+            &branch_name,
+            &commits,
+        )?)
     } else {
         None
     };
@@ -71,15 +77,15 @@ pub fn show(
     };
 
     if let Some(out) = out.for_json() {
-        output_json(
-            &branch_name,
-            &commits,
-            &unassigned_files,
-            &reviews,
-            ai_summary.as_deref(),
-            merge_check.as_ref(),
-            out,
-        )?;
+        out.write_value(BranchShowOutput {
+            branch: branch_name,
+            commits_ahead: commits.len(),
+            commits,
+            unassigned_files,
+            reviews: reviews.iter().map(Into::into).collect(),
+            ai_summary,
+            merge_check,
+        })?;
     } else if let Some(out) = out.for_human() {
         output_human(
             &branch_name,
@@ -95,20 +101,68 @@ pub fn show(
     Ok(())
 }
 
-#[derive(Debug, serde::Serialize)]
+/// JSON output for `but branch show`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BranchShowOutput {
+    /// The name of the branch.
+    branch: String,
+    /// The number of commits the branch is ahead of the base branch.
+    commits_ahead: usize,
+    /// The commits the branch is ahead of the base branch.
+    commits: Vec<CommitInfo>,
+    /// Uncommitted files assigned to the branch.
+    unassigned_files: Vec<String>,
+    /// The reviews of the branch, only listed with `--review`.
+    reviews: Vec<BranchReviewOutput>,
+    /// A summary of the changes of the branch, only generated with `--ai`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ai_summary: Option<String>,
+    /// Whether the branch merges cleanly into upstream, only checked with `--check`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_check: Option<MergeCheck>,
+}
+
+/// A review of a branch, like a pull request.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct BranchReviewOutput {
+    number: i64,
+    url: String,
+    unit_symbol: String,
+    title: String,
+    body: Option<String>,
+    draft: bool,
+}
+
+impl From<&but_forge::ForgeReview> for BranchReviewOutput {
+    fn from(review: &but_forge::ForgeReview) -> Self {
+        BranchReviewOutput {
+            number: review.number,
+            url: review.html_url.clone(),
+            unit_symbol: review.unit_symbol.clone(),
+            title: review.title.clone(),
+            body: review.body.clone(),
+            draft: review.draft,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 struct MergeCheck {
     merges_cleanly: bool,
     conflicting_files: Vec<ConflictingFile>,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct ConflictingFile {
     path: String,
     branch_commits: Vec<CommitRef>,
     upstream_commits: Vec<CommitRef>,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct CommitRef {
     sha: String,
     short_sha: String,
@@ -496,7 +550,7 @@ fn get_unassigned_files(
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct FileChange {
     path: String,
     status: String, // "modified", "added", "deleted"
@@ -504,7 +558,7 @@ struct FileChange {
     deletions: usize,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct CommitInfo {
     sha: String,
     short_sha: String,
@@ -518,20 +572,15 @@ struct CommitInfo {
     files: Vec<FileChange>,
 }
 
-#[instrument(skip(commits))]
-fn generate_branch_summary(branch_name: &str, commits: &[CommitInfo]) -> anyhow::Result<String> {
-    use async_openai::types::chat::{
-        ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs,
-    };
-    use but_action::OpenAiProvider;
+#[instrument(skip(project, commits))]
+fn generate_branch_summary(
+    project: &Project,
+    branch_name: &str,
+    commits: &[CommitInfo],
+) -> anyhow::Result<String> {
+    use but_action::ChatMessage;
 
-    // Get OpenAI provider (tries GitButler proxied, own key, then env var)
-    let provider = OpenAiProvider::with(None).ok_or_else(|| {
-        anyhow::anyhow!(
-            "No AI credentials found. Configure in GitButler settings or set OPENAI_API_KEY environment variable."
-        )
-    })?;
+    let llm = but_action::provider_for_project(project, None)?;
 
     // Build the prompt with commit information
     let mut prompt = format!(
@@ -559,92 +608,22 @@ fn generate_branch_summary(branch_name: &str, commits: &[CommitInfo]) -> anyhow:
     prompt.push_str(
         "\nHere is a good example:\n\nAdd an --ai flag to the branch show command to allow generating an \nAI-powered summary of a branch's commits. This allows the user to see\nat a glance what the branch is about without reading all commit messages.\n");
 
-    // Create OpenAI client and make request
-    let client = provider.client()?;
-
-    let messages = vec![
-        ChatCompletionRequestSystemMessage::from(
-            "You are a helpful assistant that summarizes Git branch changes.",
-        )
-        .into(),
-        ChatCompletionRequestUserMessageArgs::default()
-            .content(prompt)
-            .build()?
-            .into(),
-    ];
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-5-mini")
-        .messages(messages)
-        .max_completion_tokens(500u32)
-        .build()?;
-
-    let response = std::thread::spawn(move || {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(client.chat().create(request))
-    })
-    .join()
-    .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))??;
+    let response = llm.chat(
+        "You are a helpful assistant that summarizes Git branch changes.",
+        vec![ChatMessage::User(prompt)],
+        &[],
+        None,
+        std::sync::Arc::new(|_| {}),
+    )?;
 
     // Extract the summary from the response
     let summary = response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.content.clone())
+        .text
         .ok_or_else(|| anyhow::anyhow!("No response content from AI"))?;
 
     Ok(summary)
 }
 
-fn output_json(
-    branch_name: &str,
-    commits: &[CommitInfo],
-    unassigned_files: &[String],
-    reviews: &[but_forge::ForgeReview],
-    ai_summary: Option<&str>,
-    merge_check: Option<&MergeCheck>,
-    out: &mut OutputChannel,
-) -> anyhow::Result<()> {
-    let reviews_json: Vec<_> = reviews
-        .iter()
-        .map(|r| {
-            serde_json::json!({
-                "number": r.number,
-                "url": r.html_url,
-                "unitSymbol": r.unit_symbol,
-                "title": r.title,
-                "body": r.body,
-                "draft": r.draft,
-            })
-        })
-        .collect();
-
-    let mut output = serde_json::json!({
-        "branch": branch_name,
-        "commitsAhead": commits.len(),
-        "commits": commits,
-        "unassignedFiles": unassigned_files,
-        "reviews": reviews_json,
-    });
-
-    // Add AI summary if available
-    if let Some(summary) = ai_summary {
-        output["aiSummary"] = serde_json::json!(summary);
-    }
-
-    // Add merge check if available
-    if let Some(check) = merge_check {
-        output["mergeCheck"] = serde_json::json!({
-            "mergesCleanly": check.merges_cleanly,
-            "conflictingFiles": check.conflicting_files,
-        });
-    }
-
-    out.write_value(output)?;
-    Ok(())
-}
-
 fn output_human(
     branch_name: &str,
     commits: &[CommitInfo],
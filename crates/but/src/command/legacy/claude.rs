@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use but_claude::{permissions::audit::Decision, usage::Usage};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use colored::Colorize;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::utils::OutputChannel;
+
+/// JSON output for `but claude budget`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetOutput {
+    /// The ID of the session whose budget was set.
+    session_id: String,
+    /// The new budget of the session in USD, or `None` if it was removed.
+    budget_usd: Option<f64>,
+}
+
+/// JSON output for `but claude trust-policy`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrustPolicyOutput {
+    /// The hash of the permission policy that is now trusted, or `None` if there is no policy.
+    trusted_hash: Option<String>,
+}
+
+/// JSON output for `but claude last`, or `None` if there is no user message at the offset.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LastMessageOutput {
+    /// When the message was sent, like `2025-01-31 12:00:00`.
+    timestamp: String,
+    /// The message, or an empty string if it wasn't a user message.
+    message: String,
+}
+
+/// Show the usage of all Claude sessions of the project, per stack and per session.
+pub fn usage(ctx: &mut Context, out: &mut OutputChannel) -> Result<()> {
+    let report = but_claude::usage::report(ctx)?;
+    if let Some(out) = out.for_json() {
+        out.write_value(report)?;
+        return Ok(());
+    }
+    let Some(out) = out.for_human() else {
+        return Ok(());
+    };
+    if report.sessions.is_empty() {
+        writeln!(out, "No Claude usage was recorded yet.")?;
+        return Ok(());
+    }
+
+    let names = stack_names(ctx);
+    let stack_name = |stack_id: Option<StackId>| match stack_id {
+        Some(stack_id) => names
+            .get(&stack_id)
+            .cloned()
+            .unwrap_or_else(|| format!("{stack_id} (not applied)")),
+        None => "(no stack)".to_string(),
+    };
+
+    writeln!(out, "{}", "Stacks:".bold())?;
+    for stack in &report.stacks {
+        writeln!(
+            out,
+            "  {} {}",
+            format_cost(stack.usage.cost_usd),
+            stack_name(Some(stack.stack_id)).green(),
+        )?;
+        writeln!(out, "        {}", format_tokens(&stack.usage).dimmed())?;
+    }
+
+    writeln!(out, "\n{}", "Sessions:".bold())?;
+    for session in &report.sessions {
+        let budget = session
+            .budget_usd
+            .map(|budget| format!(" of {} budget", format_cost(budget)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "  {}{} {} {}",
+            format_cost(session.usage.cost_usd),
+            budget,
+            session.session_id.to_string().cyan(),
+            stack_name(session.stack_id).green(),
+        )?;
+        writeln!(out, "        {}", format_tokens(&session.usage).dimmed())?;
+    }
+
+    writeln!(
+        out,
+        "\n{} {} {}",
+        "Total:".bold(),
+        format_cost(report.total.cost_usd),
+        format_tokens(&report.total).dimmed()
+    )?;
+    writeln!(
+        out,
+        "{}",
+        "Costs are estimated from the tokens used and the list prices of each model.".dimmed()
+    )?;
+    Ok(())
+}
+
+/// Set the budget of the Claude session with `session_id` to `amount` USD, or remove it.
+pub fn budget(
+    ctx: &mut Context,
+    out: &mut OutputChannel,
+    session_id: &str,
+    amount: Option<f64>,
+) -> Result<()> {
+    let session_id = session_id
+        .parse()
+        .with_context(|| format!("'{session_id}' is not a valid Claude session ID"))?;
+    but_claude::usage::set_budget(ctx, session_id, amount)?;
+
+    if let Some(out) = out.for_json() {
+        out.write_value(BudgetOutput {
+            session_id: session_id.to_string(),
+            budget_usd: amount,
+        })?;
+    } else if let Some(out) = out.for_human() {
+        match amount {
+            Some(amount) => writeln!(
+                out,
+                "Set the budget of session {} to {}",
+                session_id.to_string().cyan(),
+                format_cost(amount)
+            )?,
+            None => writeln!(
+                out,
+                "Removed the budget of session {}",
+                session_id.to_string().cyan()
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// Show the permission decisions made during the Claude session with `session_id`, oldest first.
+pub fn audit(ctx: &mut Context, out: &mut OutputChannel, session_id: &str) -> Result<()> {
+    let session_id = session_id
+        .parse()
+        .with_context(|| format!("'{session_id}' is not a valid Claude session ID"))?;
+    let decisions = but_claude::permissions::audit::list(ctx, session_id)?;
+    if let Some(out) = out.for_json() {
+        out.write_value(decisions)?;
+        return Ok(());
+    }
+    let Some(out) = out.for_human() else {
+        return Ok(());
+    };
+    if decisions.is_empty() {
+        writeln!(
+            out,
+            "No permission decisions were recorded for session {}.",
+            session_id.to_string().cyan()
+        )?;
+        return Ok(());
+    }
+
+    for record in decisions {
+        let decision = match record.decision {
+            Decision::Allow => "allow".green(),
+            Decision::Deny => "deny".red(),
+        };
+        writeln!(
+            out,
+            "{} {} {} {}",
+            record
+                .created_at
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+                .dimmed(),
+            decision,
+            format!("by {}", record.source).dimmed(),
+            record.tool_name.bold(),
+        )?;
+        if let Some(subject) = audit_subject(&record.input) {
+            writeln!(out, "    {subject}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Trust the permission policy of the repository as it is now, so its `allow` rules apply.
+pub fn trust_policy(ctx: &Context, out: &mut OutputChannel) -> Result<()> {
+    let hash = but_claude::permissions::policy::trust(&ctx.legacy_project)?;
+    if let Some(out) = out.for_json() {
+        out.write_value(TrustPolicyOutput { trusted_hash: hash })?;
+    } else if let Some(out) = out.for_human() {
+        match hash {
+            Some(_) => writeln!(
+                out,
+                "Trusted the permission policy at {}, until it changes.",
+                but_claude::permissions::policy::POLICY_PATH.green()
+            )?,
+            None => writeln!(
+                out,
+                "There is no permission policy at {} to trust.",
+                but_claude::permissions::policy::POLICY_PATH
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// Show the user message sent to Claude `offset` messages ago.
+pub fn last(ctx: &mut Context, out: &mut OutputChannel, offset: i64) -> Result<()> {
+    let message = but_claude::db::get_user_message(ctx, Some(offset))?;
+    let message = message.map(|msg| LastMessageOutput {
+        timestamp: msg.created_at().format("%Y-%m-%d %H:%M:%S").to_string(),
+        message: match msg.content() {
+            but_claude::MessagePayload::User(input) => input.message.clone(),
+            _ => String::new(),
+        },
+    });
+    if let Some(out) = out.for_json() {
+        out.write_value(message)?;
+    } else if let Some(out) = out.for_human() {
+        match message {
+            Some(msg) => {
+                writeln!(out, "{} {}", "Timestamp:".bold(), msg.timestamp.cyan())?;
+                if msg.message.is_empty() {
+                    writeln!(out, "{}", "Not a user input message".red())?;
+                } else {
+                    writeln!(out, "{}", msg.message)?;
+                }
+            }
+            None => writeln!(out, "No user message found at offset {offset}")?,
+        }
+    }
+    Ok(())
+}
+
+/// The part of the input of a tool call that its permission is about, if there is one.
+fn audit_subject(input: &serde_json::Value) -> Option<&str> {
+    ["command", "file_path", "url"]
+        .into_iter()
+        .find_map(|key| input[key].as_str())
+}
+
+/// Map the IDs of all applied stacks to their names, or return an empty map if they can't be listed.
+fn stack_names(ctx: &Context) -> HashMap<StackId, String> {
+    match crate::legacy::commits::stacks(ctx) {
+        Ok(stacks) => stacks
+            .into_iter()
+            .filter_map(|stack| Some((stack.id?, stack.name()?.to_string())))
+            .collect(),
+        Err(err) => {
+            tracing::warn!("Failed to list stacks: {err}");
+            HashMap::new()
+        }
+    }
+}
+
+fn format_cost(usd: f64) -> String {
+    format!("${usd:>7.2}")
+}
+
+fn format_tokens(usage: &Usage) -> String {
+    format!(
+        "{} messages, {} input, {} output, {} cache write, {} cache read tokens",
+        usage.messages,
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens
+    )
+}
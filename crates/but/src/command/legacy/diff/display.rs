@@ -134,7 +134,7 @@ fn format_patch(patch: &UnifiedPatch, render_header: impl Fn(&mut String)) -> St
     output
 }
 
-fn fmt_hunk(hunk: &DiffHunk) -> String {
+pub(crate) fn fmt_hunk(hunk: &DiffHunk) -> String {
     use bstr::ByteSlice;
 
     let mut output = String::new();
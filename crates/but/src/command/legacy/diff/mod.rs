@@ -1,16 +1,17 @@
 use but_ctx::Context;
 
-use crate::{CliId, IdMap, command::legacy::diff::show::Filter, utils::OutputChannel};
+use crate::{CliId, IdMap, command::legacy::diff::show::Filter};
 
-mod display;
+pub(crate) mod display;
 mod show;
 
 // Note: To use the DiffDisplay trait in other modules,
 // import it with: use crate::command::diff::display::DiffDisplay;
 
+/// Write the diff of `target_str`, or of all uncommitted changes if `None`, to `out`.
 pub fn handle(
     ctx: &mut Context,
-    out: &mut OutputChannel,
+    out: &mut dyn std::fmt::Write,
     target_str: Option<&str>,
 ) -> anyhow::Result<()> {
     let wt_changes = but_api::legacy::diff::changes_in_worktree(ctx)?;
@@ -7,7 +7,7 @@ use but_hunk_assignment::WorktreeChanges;
 use gitbutler_stack::StackId;
 
 use super::display::{DiffDisplay, TreeChangeWithPatch};
-use crate::{IdMap, id::UncommittedCliId, utils::OutputChannel};
+use crate::{IdMap, id::UncommittedCliId};
 
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum Filter {
@@ -19,7 +19,7 @@ pub(crate) enum Filter {
 pub(crate) fn worktree(
     wt_changes: WorktreeChanges,
     id_map: IdMap,
-    out: &mut OutputChannel,
+    out: &mut dyn Write,
     filter: Option<Filter>,
 ) -> anyhow::Result<()> {
     let assignments: Vec<_> = wt_changes
@@ -52,7 +52,7 @@ pub(crate) fn worktree(
 
 pub(crate) fn commit(
     ctx: &mut Context,
-    out: &mut OutputChannel,
+    out: &mut dyn Write,
     id: gix::ObjectId,
     path: Option<BString>,
 ) -> anyhow::Result<()> {
@@ -69,11 +69,7 @@ pub(crate) fn commit(
     Ok(())
 }
 
-pub(crate) fn branch(
-    ctx: &Context,
-    out: &mut OutputChannel,
-    short_name: String,
-) -> anyhow::Result<()> {
+pub(crate) fn branch(ctx: &Context, out: &mut dyn Write, short_name: String) -> anyhow::Result<()> {
     let result = but_api::branch::branch_diff(ctx, short_name)?;
     for change in result.changes {
         let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone())
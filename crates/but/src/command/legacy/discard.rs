@@ -11,9 +11,20 @@ use gitbutler_oplog::{
     OplogExt,
     entry::{OperationKind, SnapshotDetails},
 };
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::{CliId, IdMap, command::legacy::rub::parse_sources, utils::OutputChannel};
 
+/// The JSON output of `but discard`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct DiscardOutput {
+    /// The number of files and hunks whose changes were discarded.
+    discarded: usize,
+    /// The number of files and hunks whose changes couldn't be discarded.
+    failed: usize,
+}
+
 /// Handle the `but discard <id>` command.
 ///
 /// Discards changes to files or hunks identified by the given ID.
@@ -174,20 +185,20 @@ pub fn handle(ctx: &mut Context, out: &mut OutputChannel, id: &str) -> Result<()
             )?;
         }
         if let Some(out) = out.for_json() {
-            out.write_value(serde_json::json!({
-                "discarded": discarded_count,
-                "failed": dropped.len(),
-            }))?;
+            out.write_value(DiscardOutput {
+                discarded: discarded_count,
+                failed: dropped.len(),
+            })?;
         }
     } else {
         if let Some(out) = out.for_human() {
             writeln!(out, "No changes were discarded.")?;
         }
         if let Some(out) = out.for_json() {
-            out.write_value(serde_json::json!({
-                "discarded": 0,
-                "failed": dropped.len(),
-            }))?;
+            out.write_value(DiscardOutput {
+                discarded: 0,
+                failed: dropped.len(),
+            })?;
         }
     }
 
@@ -8,6 +8,7 @@ use but_workspace::ui::{BranchDetails, Commit};
 use cli_prompts::DisplayPrompt;
 use colored::{ColoredString, Colorize};
 use gitbutler_project::Project;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
@@ -522,10 +523,13 @@ fn print_existing_pr_info(
     Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// JSON output for `but pr`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-struct PublishReviewsOutcome {
+pub(crate) struct PublishReviewsOutcome {
+    /// The reviews that were created.
     published: Vec<but_forge::ForgeReview>,
+    /// The reviews that already existed for the selected branches.
     already_existing: Vec<but_forge::ForgeReview>,
 }
 
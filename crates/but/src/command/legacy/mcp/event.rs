@@ -1,4 +1,7 @@
-use but_action::{CredentialsKind, OpenAiProvider, reword::CommitEvent};
+use std::{path::Path, sync::Arc};
+
+use but_action::{CredentialsKind, LlmProvider, reword::CommitEvent};
+use gitbutler_project::Project;
 
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -12,26 +15,31 @@ pub struct Handler {
 }
 
 impl Handler {
-    pub fn new_in_background() -> Self {
-        let (credentials_kind, sender) = OpenAiProvider::with(None)
-            .and_then(|openai| {
-                openai
-                    .client()
-                    .ok()
-                    .map(|client| (openai.credentials_kind(), client))
-            })
-            .map(|(credentials_kind, client)| {
+    /// Process events with the language model configured for the project at `project_dir`,
+    /// or ignore them if there is none.
+    pub fn new_in_background(project_dir: &Path) -> Self {
+        let (credentials_kind, sender) = Project::from_path(project_dir)
+            .and_then(|project| but_action::provider_for_project(&project, None))
+            .inspect_err(|err| tracing::warn!("No language model to reword commits with: {err:#}"))
+            .ok()
+            .map(|llm| {
+                let llm: Arc<dyn LlmProvider> = llm.into();
+                let credentials_kind = llm.credentials_kind();
                 let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
                 tokio::task::spawn(async move {
                     while let Some(event) = receiver.recv().await {
                         match event {
                             Event::Commit(c) => {
-                                let _ = but_action::reword::commit(&client, c).await;
+                                let llm = llm.clone();
+                                let _ = tokio::task::spawn_blocking(move || {
+                                    but_action::reword::commit(&*llm, c)
+                                })
+                                .await;
                             }
                         }
                     }
                 });
-                (Some(credentials_kind), Some(sender))
+                (credentials_kind, Some(sender))
             })
             .unwrap_or((None, None));
 
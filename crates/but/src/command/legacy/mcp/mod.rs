@@ -5,29 +5,49 @@ use std::{
 };
 
 mod event;
+mod workspace;
 use anyhow::Result;
+use bstr::ByteSlice;
 use but_action::{ActionHandler, Outcome, Source, reword::CommitEvent};
 use but_ctx::Context;
 use but_settings::AppSettings;
 use gitbutler_project::Project;
 use rmcp::{
-    ServerHandler, ServiceExt,
+    RoleServer, ServerHandler, ServiceExt,
     handler::server::{tool::ToolRouter, wrapper::Parameters},
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        AnnotateAble, CallToolResult, Content, Implementation, ListResourcesResult,
+        PaginatedRequestParam, ProtocolVersion, RawResource, ReadResourceRequestParam,
+        ReadResourceResult, ResourceContents, ServerCapabilities, ServerInfo,
     },
-    schemars, tool, tool_handler, tool_router,
+    schemars,
+    service::RequestContext,
+    tool, tool_handler, tool_router,
 };
 
-use crate::utils::{BackgroundMetrics, metrics};
+use crate::{
+    args::mcp::Access,
+    command::legacy::mcp_internal::status,
+    utils::{BackgroundMetrics, metrics},
+};
+
+/// The resource with the diff of all uncommitted changes.
+const WORKTREE_DIFF_URI: &str = "gitbutler://worktree/diff";
+/// The prefix of resources with the details of a commit, followed by its hash.
+/// With a `/diff` suffix, the resource is the diff of the commit instead.
+const COMMIT_URI_PREFIX: &str = "gitbutler://commit/";
 
-pub(crate) async fn start(app_settings: AppSettings) -> Result<()> {
+pub(crate) async fn start(
+    app_settings: AppSettings,
+    project_dir: PathBuf,
+    allow: Access,
+) -> Result<()> {
     // Use `-t` to enable logging
     tracing::info!("Starting MCP server");
 
     let client_info = Arc::new(Mutex::new(None));
     let transport = (tokio::io::stdin(), tokio::io::stdout());
-    let service = Mcp::new(app_settings, client_info.clone())
+    let service = Mcp::new(app_settings, client_info.clone(), project_dir, allow)
         .serve(transport)
         .await?;
     let info = service.peer_info();
@@ -44,19 +64,30 @@ pub struct Mcp {
     metrics: BackgroundMetrics,
     client_info: Arc<Mutex<Option<Implementation>>>,
     event_handler: event::Handler,
+    /// The directory the server was started in, which determines the project of the tools and resources.
+    project_dir: PathBuf,
+    /// What the tools are allowed to do.
+    allow: Access,
     tool_router: ToolRouter<Self>,
 }
 
 #[tool_router(vis = "pub")]
 impl Mcp {
-    pub fn new(app_settings: AppSettings, client_info: Arc<Mutex<Option<Implementation>>>) -> Self {
+    pub fn new(
+        app_settings: AppSettings,
+        client_info: Arc<Mutex<Option<Implementation>>>,
+        project_dir: PathBuf,
+        allow: Access,
+    ) -> Self {
         let metrics = BackgroundMetrics::new_in_background(&app_settings);
-        let event_handler = event::Handler::new_in_background();
+        let event_handler = event::Handler::new_in_background(&project_dir);
         Self {
             app_settings,
             metrics,
             client_info,
             event_handler,
+            project_dir,
+            allow,
             tool_router: Self::tool_router(),
         }
     }
@@ -68,11 +99,9 @@ impl Mcp {
         &self,
         request: Parameters<GitButlerUpdateBranchesRequest>,
     ) -> Result<CallToolResult, rmcp::ErrorData> {
-        let client_info = self
-            .client_info
-            .lock()
-            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?
-            .clone();
+        // This tool predates `--allow` and is the contract of existing setups,
+        // so it stays available no matter what the other tools are allowed to do.
+        let client_info = self.client_info()?;
         let start_time = std::time::Instant::now();
         let result = self.gitbutler_update_branches_inner(request.0.clone(), &client_info);
         let error = result.as_ref().err().map(|e| e.to_string());
@@ -102,6 +131,284 @@ impl Mcp {
         result.map(|outcome| Ok(CallToolResult::success(vec![Content::json(outcome)?])))?
     }
 
+    #[tool(
+        description = "Get the status of the project: the applied stacks and their branches and commits, and the uncommitted changes along with the branches they are assigned to.",
+        annotations(read_only_hint = true)
+    )]
+    pub fn gitbutler_status(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_status", Access::Read, || {
+            status::project_status(&self.project_dir)
+        })
+    }
+
+    #[tool(
+        description = "List the stacks applied to the workspace, with their IDs and the names and tips of their branches.",
+        annotations(read_only_hint = true)
+    )]
+    pub fn gitbutler_list_stacks(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_list_stacks", Access::Read, || {
+            workspace::stacks(&self.project()?)
+        })
+    }
+
+    #[tool(
+        description = "Get the branches of a stack, top-most first, along with their commits, newest first.",
+        annotations(read_only_hint = true)
+    )]
+    pub fn gitbutler_stack_details(
+        &self,
+        params: Parameters<StackParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_stack_details", Access::Read, || {
+            let params = params.0;
+            workspace::stack_details(&self.project()?, &params.stack_id)
+        })
+    }
+
+    #[tool(
+        description = "Get the message, author and changed files of a commit.",
+        annotations(read_only_hint = true)
+    )]
+    pub fn gitbutler_commit_details(
+        &self,
+        params: Parameters<CommitParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_commit_details", Access::Read, || {
+            let params = params.0;
+            workspace::commit_details(&self.project()?, &params.commit_id)
+        })
+    }
+
+    #[tool(
+        description = "List the most recent operations in the operations log, newest first. Each of them can be restored with gitbutler_restore_snapshot.",
+        annotations(read_only_hint = true)
+    )]
+    pub fn gitbutler_list_snapshots(
+        &self,
+        params: Parameters<ListSnapshotsParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_list_snapshots", Access::Read, || {
+            let params = params.0;
+            workspace::snapshots(&self.project()?, params.limit.unwrap_or(20))
+        })
+    }
+
+    #[tool(description = "Commit uncommitted changes, or some of their hunks, on top of a branch.")]
+    pub fn gitbutler_commit(
+        &self,
+        params: Parameters<CommitChangesParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_commit", Access::Write, || {
+            let params = params.0;
+            workspace::commit(
+                &self.project()?,
+                &params.stack_id,
+                params.branch_name,
+                params.message,
+                params.changes,
+            )
+        })
+    }
+
+    #[tool(
+        description = "Amend uncommitted changes, or some of their hunks, to an existing commit, keeping its message."
+    )]
+    pub fn gitbutler_amend(
+        &self,
+        params: Parameters<AmendParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_amend", Access::Write, || {
+            let params = params.0;
+            workspace::amend(
+                &self.project()?,
+                &params.stack_id,
+                &params.commit_id,
+                params.changes,
+            )
+        })
+    }
+
+    #[tool(description = "Change the message of a commit.")]
+    pub fn gitbutler_reword_commit(
+        &self,
+        params: Parameters<RewordCommitParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_reword_commit", Access::Write, || {
+            let params = params.0;
+            workspace::reword(
+                &self.project()?,
+                &params.stack_id,
+                &params.commit_id,
+                params.message,
+            )
+        })
+    }
+
+    #[tool(description = "Create a new stack with a single, empty branch.")]
+    pub fn gitbutler_create_branch(
+        &self,
+        params: Parameters<CreateBranchParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_create_branch", Access::Write, || {
+            let params = params.0;
+            crate::command::legacy::mcp_internal::stack::create_stack_with_branch(
+                &params.branch_name,
+                &self.project_dir,
+            )
+        })
+    }
+
+    #[tool(
+        description = "Move changes, or some of their hunks, from one commit to another, which may be in another stack."
+    )]
+    pub fn gitbutler_move_changes(
+        &self,
+        params: Parameters<MoveChangesParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_move_changes", Access::Write, || {
+            let params = params.0;
+            workspace::move_changes(
+                &self.project()?,
+                &params.source_stack_id,
+                &params.source_commit_id,
+                &params.destination_stack_id,
+                &params.destination_commit_id,
+                params.changes,
+            )
+        })
+    }
+
+    #[tool(
+        description = "Take changes, or some of their hunks, out of a commit and leave them uncommitted."
+    )]
+    pub fn gitbutler_uncommit_changes(
+        &self,
+        params: Parameters<UncommitChangesParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_uncommit_changes", Access::Write, || {
+            let params = params.0;
+            workspace::uncommit_changes(
+                &self.project()?,
+                &params.stack_id,
+                &params.commit_id,
+                params.changes,
+            )
+        })
+    }
+
+    #[tool(description = "Squash commits of a stack into another commit of the same stack.")]
+    pub fn gitbutler_squash_commits(
+        &self,
+        params: Parameters<SquashCommitsParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_squash_commits", Access::Write, || {
+            let params = params.0;
+            workspace::squash_commits(
+                &self.project()?,
+                &params.stack_id,
+                &params.source_commit_ids,
+                &params.target_commit_id,
+            )
+        })
+    }
+
+    #[tool(description = "Move a commit to the top of another stack.")]
+    pub fn gitbutler_move_commit(
+        &self,
+        params: Parameters<MoveCommitParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_move_commit", Access::Write, || {
+            let params = params.0;
+            workspace::move_commit(
+                &self.project()?,
+                &params.commit_id,
+                &params.source_stack_id,
+                &params.target_stack_id,
+            )
+        })
+    }
+
+    #[tool(
+        description = "Reorder the commits of a stack, also across its branches. All branches of the stack must be listed with all their commits."
+    )]
+    pub fn gitbutler_reorder_stack(
+        &self,
+        params: Parameters<ReorderStackParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_reorder_stack", Access::Write, || {
+            let params = params.0;
+            workspace::reorder_stack(&self.project()?, &params.stack_id, params.branches)
+        })
+    }
+
+    #[tool(
+        description = "Split the changes to some files out of a branch into a new branch in a new stack."
+    )]
+    pub fn gitbutler_split_branch(
+        &self,
+        params: Parameters<SplitBranchParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_split_branch", Access::Write, || {
+            let params = params.0;
+            workspace::split_branch(
+                &self.project()?,
+                &params.stack_id,
+                params.branch_name,
+                params.new_branch_name,
+                params.file_paths,
+            )
+        })
+    }
+
+    #[tool(description = "Remove a commit from its branch, leaving its changes uncommitted.")]
+    pub fn gitbutler_undo_commit(
+        &self,
+        params: Parameters<StackCommitParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_undo_commit", Access::Write, || {
+            let params = params.0;
+            workspace::undo_commit(&self.project()?, &params.stack_id, &params.commit_id)
+        })
+    }
+
+    #[tool(description = "Undo the last operation on the workspace, like `but undo`.")]
+    pub fn gitbutler_undo(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_undo", Access::Write, || {
+            workspace::undo(&self.project()?)
+        })
+    }
+
+    #[tool(
+        description = "Restore the workspace to the state of a snapshot of the operations log, as listed by gitbutler_list_snapshots."
+    )]
+    pub fn gitbutler_restore_snapshot(
+        &self,
+        params: Parameters<RestoreSnapshotParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_restore_snapshot", Access::Write, || {
+            let params = params.0;
+            workspace::restore_snapshot(&self.project()?, &params.snapshot_id)
+        })
+    }
+
+    #[tool(
+        description = "Push a branch of a stack, along with the branches below it, to the remote. Needs the server to be started with `--allow push`."
+    )]
+    pub fn gitbutler_push(
+        &self,
+        params: Parameters<PushParams>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run("gitbutler_push", Access::Push, || {
+            let params = params.0;
+            workspace::push(
+                &self.project()?,
+                &params.stack_id,
+                params.branch_name,
+                params.with_force,
+            )
+        })
+    }
+
     fn gitbutler_update_branches_inner(
         &self,
         request: GitButlerUpdateBranchesRequest,
@@ -160,6 +467,57 @@ impl Mcp {
         }
         Ok(outcome)
     }
+
+    /// Find the project the server was started in, which all tools and resources operate on.
+    fn project(&self) -> anyhow::Result<workspace::Project> {
+        workspace::Project::discover(&self.project_dir)
+    }
+
+    /// Fail unless the server was started to allow `access`, which `endpoint` needs.
+    fn ensure_allowed(&self, endpoint: &str, access: Access) -> Result<(), rmcp::ErrorData> {
+        if access <= self.allow {
+            return Ok(());
+        }
+        Err(rmcp::ErrorData::invalid_request(
+            format!(
+                "{endpoint} isn't allowed, as the MCP server was started with `--allow {}`. Start it with `--allow {access}` to enable it.",
+                self.allow
+            ),
+            None,
+        ))
+    }
+
+    fn client_info(&self) -> Result<Option<Implementation>, rmcp::ErrorData> {
+        Ok(self
+            .client_info
+            .lock()
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?
+            .clone())
+    }
+
+    /// Run the tool `endpoint` with `f` if `access` is allowed, and return its outcome as JSON.
+    fn run<T: serde::Serialize>(
+        &self,
+        endpoint: &'static str,
+        access: Access,
+        f: impl FnOnce() -> anyhow::Result<T>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.ensure_allowed(endpoint, access)?;
+        let client_info = self.client_info()?;
+        let start_time = std::time::Instant::now();
+        let result = f();
+
+        let mut event: metrics::Event = metrics::EventKind::Mcp.into();
+        event.insert_prop("endpoint", endpoint);
+        event.insert_prop("durationMs", start_time.elapsed().as_millis());
+        event.insert_prop("error", result.as_ref().err().map(|e| e.to_string()));
+        event.insert_prop("clientName", client_info.clone().map(|i| i.name));
+        event.insert_prop("clientVersion", client_info.map(|i| i.version));
+        self.metrics.capture(event);
+
+        let outcome = result.map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+        Ok(CallToolResult::success(vec![Content::json(outcome)?]))
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
@@ -177,12 +535,187 @@ pub struct GitButlerUpdateBranchesRequest {
     pub current_working_directory: String,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StackParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitParams {
+    #[schemars(description = "The hash of the commit, which may be abbreviated")]
+    pub commit_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StackCommitParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The hash of a commit in the stack, which may be abbreviated")]
+    pub commit_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSnapshotsParams {
+    #[schemars(description = "The maximum number of snapshots to list, 20 by default")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitChangesParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The name of the branch in the stack to commit on top of")]
+    pub branch_name: String,
+    #[schemars(description = "The commit message")]
+    pub message: String,
+    #[schemars(description = "The uncommitted changes to commit")]
+    pub changes: Vec<workspace::Change>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmendParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The hash of the commit to amend, which may be abbreviated")]
+    pub commit_id: String,
+    #[schemars(description = "The uncommitted changes to amend the commit with")]
+    pub changes: Vec<workspace::Change>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RewordCommitParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The hash of the commit, which may be abbreviated")]
+    pub commit_id: String,
+    #[schemars(description = "The new commit message")]
+    pub message: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBranchParams {
+    #[schemars(description = "The name of the new branch")]
+    pub branch_name: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveChangesParams {
+    #[schemars(description = "The ID of the stack with the commit to take the changes from")]
+    pub source_stack_id: String,
+    #[schemars(description = "The hash of the commit to take the changes from")]
+    pub source_commit_id: String,
+    #[schemars(description = "The ID of the stack with the commit to move the changes to")]
+    pub destination_stack_id: String,
+    #[schemars(description = "The hash of the commit to move the changes to")]
+    pub destination_commit_id: String,
+    #[schemars(description = "The changes of the source commit to move")]
+    pub changes: Vec<workspace::Change>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UncommitChangesParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The hash of the commit, which may be abbreviated")]
+    pub commit_id: String,
+    #[schemars(description = "The changes of the commit to uncommit")]
+    pub changes: Vec<workspace::Change>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SquashCommitsParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The hashes of the commits to squash into the target commit")]
+    pub source_commit_ids: Vec<String>,
+    #[schemars(description = "The hash of the commit to squash into")]
+    pub target_commit_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveCommitParams {
+    #[schemars(description = "The hash of the commit, which may be abbreviated")]
+    pub commit_id: String,
+    #[schemars(description = "The ID of the stack the commit is in")]
+    pub source_stack_id: String,
+    #[schemars(description = "The ID of the stack to move the commit to")]
+    pub target_stack_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorderStackParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "All branches of the stack in the desired order, top-most first")]
+    pub branches: Vec<workspace::BranchOrder>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitBranchParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The name of the branch to split")]
+    pub branch_name: String,
+    #[schemars(description = "The name of the new branch")]
+    pub new_branch_name: String,
+    #[schemars(description = "The paths of the files whose changes move to the new branch")]
+    pub file_paths: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSnapshotParams {
+    #[schemars(description = "The ID of the snapshot to restore")]
+    pub snapshot_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PushParams {
+    #[schemars(description = "The ID of the stack")]
+    pub stack_id: String,
+    #[schemars(description = "The name of the branch to push")]
+    pub branch_name: String,
+    #[schemars(
+        description = "Whether to force-push, which is needed if the commits were rewritten"
+    )]
+    #[serde(default)]
+    pub with_force: bool,
+}
+
 #[tool_handler]
 impl ServerHandler for Mcp {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some("GitButler MCP server".into()),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some(format!(
+                "GitButler MCP server.
+            Its tools inspect and edit the GitButler workspace of the project: its stacks, branches and commits, and the operations log to undo changes.
+            Tools are allowed to {}. Diffs of the uncommitted changes and of commits are available as resources.",
+                match self.allow {
+                    Access::Read => "only read",
+                    Access::Write => "read and write, but not push",
+                    Access::Push => "read, write and push",
+                }
+            )),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .build(),
             server_info: Implementation {
                 name: "GitButler MCP Server".into(),
                 title: None,
@@ -193,4 +726,100 @@ impl ServerHandler for Mcp {
             protocol_version: ProtocolVersion::LATEST,
         }
     }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, rmcp::ErrorData> {
+        let resources = self
+            .resources()
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+        Ok(ListResourcesResult {
+            next_cursor: None,
+            resources,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::ErrorData> {
+        let uri = request.uri;
+        let project = self
+            .project()
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+        let text = match uri.strip_prefix(COMMIT_URI_PREFIX) {
+            _ if uri == WORKTREE_DIFF_URI => workspace::worktree_diff(&project),
+            Some(commit) => match commit.strip_suffix("/diff") {
+                Some(commit) => workspace::commit_diff(&project, commit),
+                None => workspace::commit_details(&project, commit)
+                    .and_then(|details| serde_json::to_string_pretty(&details).map_err(Into::into)),
+            },
+            None => {
+                return Err(rmcp::ErrorData::resource_not_found(
+                    format!("Unknown resource: {uri}"),
+                    None,
+                ));
+            }
+        };
+        let text = text.map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, uri)],
+        })
+    }
+}
+
+impl Mcp {
+    /// List the diff of the uncommitted changes, and the details and diff of each commit in the workspace.
+    fn resources(&self) -> anyhow::Result<Vec<rmcp::model::Resource>> {
+        let resource = |uri: String, name: String, description: String, mime_type: &str| {
+            let mut resource = RawResource::new(uri, name);
+            resource.description = Some(description);
+            resource.mime_type = Some(mime_type.into());
+            resource.no_annotation()
+        };
+
+        let project = self.project()?;
+        let mut resources = vec![resource(
+            WORKTREE_DIFF_URI.into(),
+            "Uncommitted changes".into(),
+            "The diff of all uncommitted changes in the worktree".into(),
+            "text/x-diff",
+        )];
+        for stack in workspace::stacks(&project)? {
+            let Some(stack_id) = stack.id else {
+                continue;
+            };
+            let details = workspace::stack_details(&project, &stack_id.to_string())?;
+            for branch in details.branch_details {
+                for commit in branch.commits {
+                    let title = commit
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_str_lossy();
+                    let short_id = commit.id.to_hex_with_len(7);
+                    resources.push(resource(
+                        format!("{COMMIT_URI_PREFIX}{}", commit.id),
+                        format!("Commit {short_id}"),
+                        format!(
+                            "The details of commit {short_id} '{title}' on {}",
+                            branch.name
+                        ),
+                        "application/json",
+                    ));
+                    resources.push(resource(
+                        format!("{COMMIT_URI_PREFIX}{}/diff", commit.id),
+                        format!("Diff of commit {short_id}"),
+                        format!("The diff of commit {short_id} '{title}' on {}", branch.name),
+                        "text/x-diff",
+                    ));
+                }
+            }
+        }
+        Ok(resources)
+    }
 }
@@ -0,0 +1,404 @@
+//! The workspace tools of the MCP server, each mapped onto a `but-api` function.
+//!
+//! All of them operate on the project that contains the directory the server was started in,
+//! and accept commits by (possibly abbreviated) hash, and stacks by their ID as listed by [`stacks()`].
+
+use std::{path::Path, str::FromStr};
+
+use anyhow::{Context as _, Result, bail};
+use bstr::{BString, ByteSlice};
+use but_api::{diff::ComputeLineStats, json::HexHash};
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+use but_oxidize::ObjectIdExt;
+use gitbutler_oplog::entry::Snapshot;
+use gitbutler_project::ProjectId;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    args::OutputFormat,
+    command::legacy::{mcp_internal::project, oplog},
+    utils::OutputChannel,
+};
+
+/// The project the tools operate on.
+pub struct Project {
+    id: ProjectId,
+    repo: gix::Repository,
+}
+
+impl Project {
+    /// Find the GitButler project that contains `dir`.
+    pub fn discover(dir: &Path) -> Result<Self> {
+        let (repo, project) =
+            project::repo_and_maybe_project(dir, project::RepositoryOpenMode::Merge)?;
+        let project = project.with_context(|| {
+            format!(
+                "No GitButler project found at {}, run `but init` in it first",
+                dir.display()
+            )
+        })?;
+        Ok(Project {
+            id: project.id,
+            repo,
+        })
+    }
+
+    /// Open a context to call functions that aren't keyed by project ID.
+    pub fn ctx(&self) -> Result<Context> {
+        Context::new_from_legacy_project_id(self.id)
+    }
+
+    /// Resolve `rev`, like an abbreviated hash, to the full ID of a commit.
+    pub fn commit_id(&self, rev: &str) -> Result<gix::ObjectId> {
+        Ok(self
+            .repo
+            .rev_parse_single(rev)
+            .with_context(|| format!("Could not find commit '{rev}'"))?
+            .object()?
+            .peel_to_commit()?
+            .id)
+    }
+
+    fn commit_ids(&self, revs: &[String]) -> Result<Vec<gix::ObjectId>> {
+        revs.iter().map(|rev| self.commit_id(rev)).collect()
+    }
+}
+
+/// A change in the worktree or in a commit, optionally limited to some of its hunks.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    #[schemars(description = "The previous path of the file, if it was renamed")]
+    pub previous_path: Option<String>,
+    #[schemars(description = "The path of the file, relative to the root of the project")]
+    pub path: String,
+    #[schemars(
+        description = "The hunks of the file to include, as listed in the diff. Leave empty to include the whole file"
+    )]
+    #[serde(default)]
+    pub hunks: Vec<Hunk>,
+}
+
+/// The header of a hunk, as in `@@ -old_start,old_lines +new_start,new_lines @@`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+impl From<Change> for but_core::DiffSpec {
+    fn from(change: Change) -> Self {
+        but_core::DiffSpec {
+            previous_path: change.previous_path.map(BString::from),
+            path: BString::from(change.path),
+            hunk_headers: change
+                .hunks
+                .into_iter()
+                .map(
+                    |Hunk {
+                         old_start,
+                         old_lines,
+                         new_start,
+                         new_lines,
+                     }| but_core::HunkHeader {
+                        old_start,
+                        old_lines,
+                        new_start,
+                        new_lines,
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+/// The desired order of the commits of a branch in a stack.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchOrder {
+    #[schemars(description = "The name of the branch")]
+    pub name: String,
+    #[schemars(description = "All commits of the branch, newest first")]
+    pub commit_ids: Vec<String>,
+}
+
+fn stack_id(id: &str) -> Result<StackId> {
+    StackId::from_str(id).with_context(|| format!("Invalid stack ID '{id}'"))
+}
+
+/// List the stacks applied to the workspace, with the names and tips of their branches.
+pub fn stacks(project: &Project) -> Result<Vec<but_workspace::legacy::ui::StackEntry>> {
+    but_api::legacy::workspace::stacks(project.id, None)
+}
+
+/// Get the branches of the stack with `stack_id`, along with their commits.
+pub fn stack_details(project: &Project, stack_id: &str) -> Result<but_workspace::ui::StackDetails> {
+    but_api::legacy::workspace::stack_details(project.id, Some(self::stack_id(stack_id)?))
+}
+
+/// Get the metadata of `commit` and the files it changed.
+pub fn commit_details(
+    project: &Project,
+    commit: &str,
+) -> Result<but_api::diff::json::CommitDetails> {
+    let commit_id = project.commit_id(commit)?;
+    let ctx = project.ctx()?;
+    Ok(but_api::diff::commit_details(&ctx, commit_id, ComputeLineStats::Yes)?.into())
+}
+
+/// List the most recent `limit` entries of the operations log, newest first.
+pub fn snapshots(project: &Project, limit: usize) -> Result<Vec<Snapshot>> {
+    but_api::legacy::oplog::list_snapshots(project.id, limit, None, None, None)
+}
+
+/// Commit `changes` from the worktree on top of `branch` in the stack with `stack_id`.
+pub fn commit(
+    project: &Project,
+    stack_id: &str,
+    branch: String,
+    message: String,
+    changes: Vec<Change>,
+) -> Result<but_workspace::commit_engine::ui::CreateCommitOutcome> {
+    but_api::legacy::workspace::create_commit_from_worktree_changes(
+        project.id,
+        self::stack_id(stack_id)?,
+        None,
+        changes.into_iter().map(Into::into).collect(),
+        message,
+        branch,
+    )
+}
+
+/// Amend `changes` from the worktree to `commit` in the stack with `stack_id`.
+pub fn amend(
+    project: &Project,
+    stack_id: &str,
+    commit: &str,
+    changes: Vec<Change>,
+) -> Result<but_workspace::commit_engine::ui::CreateCommitOutcome> {
+    but_api::legacy::workspace::amend_commit_from_worktree_changes(
+        project.id,
+        self::stack_id(stack_id)?,
+        HexHash::from(project.commit_id(commit)?),
+        changes.into_iter().map(Into::into).collect(),
+    )
+}
+
+/// Replace the message of `commit` in the stack with `stack_id`, and return the ID of the rewritten commit.
+pub fn reword(project: &Project, stack_id: &str, commit: &str, message: String) -> Result<String> {
+    but_api::legacy::virtual_branches::update_commit_message(
+        project.id,
+        self::stack_id(stack_id)?,
+        project.commit_id(commit)?.to_string(),
+        message,
+    )
+}
+
+/// Move `changes` out of `source_commit` into `destination_commit`, which may be in another stack.
+pub fn move_changes(
+    project: &Project,
+    source_stack_id: &str,
+    source_commit: &str,
+    destination_stack_id: &str,
+    destination_commit: &str,
+    changes: Vec<Change>,
+) -> Result<but_api::json::UIMoveChangesResult> {
+    but_api::legacy::workspace::move_changes_between_commits(
+        project.id,
+        stack_id(source_stack_id)?,
+        HexHash::from(project.commit_id(source_commit)?),
+        stack_id(destination_stack_id)?,
+        HexHash::from(project.commit_id(destination_commit)?),
+        changes.into_iter().map(Into::into).collect(),
+    )
+}
+
+/// Move `changes` out of `commit` back into the worktree.
+pub fn uncommit_changes(
+    project: &Project,
+    stack_id: &str,
+    commit: &str,
+    changes: Vec<Change>,
+) -> Result<but_api::json::UIMoveChangesResult> {
+    let stack_id = self::stack_id(stack_id)?;
+    but_api::legacy::workspace::uncommit_changes(
+        project.id,
+        stack_id,
+        HexHash::from(project.commit_id(commit)?),
+        changes.into_iter().map(Into::into).collect(),
+        Some(stack_id),
+    )
+}
+
+/// Squash `sources` into `target`, all in the stack with `stack_id`, and return the stack afterwards.
+pub fn squash_commits(
+    project: &Project,
+    stack_id: &str,
+    sources: &[String],
+    target: &str,
+) -> Result<but_workspace::ui::StackDetails> {
+    but_api::legacy::virtual_branches::squash_commits(
+        project.id,
+        self::stack_id(stack_id)?,
+        project
+            .commit_ids(sources)?
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        project.commit_id(target)?.to_string(),
+    )?;
+    self::stack_details(project, stack_id)
+}
+
+/// Move `commit` from the stack with `source_stack_id` to the top of the stack with `target_stack_id`.
+pub fn move_commit(
+    project: &Project,
+    commit: &str,
+    source_stack_id: &str,
+    target_stack_id: &str,
+) -> Result<but_workspace::ui::StackDetails> {
+    if let Some(illegal) = but_api::legacy::virtual_branches::move_commit(
+        project.id,
+        project.commit_id(commit)?.to_string(),
+        stack_id(target_stack_id)?,
+        stack_id(source_stack_id)?,
+    )? {
+        bail!("The commit can't be moved: {illegal:?}");
+    }
+    stack_details(project, target_stack_id)
+}
+
+/// Reorder the commits of the stack with `stack_id` to match `branches`, and return the stack afterwards.
+///
+/// `branches` must list all branches of the stack with all their commits, top-most branch first.
+pub fn reorder_stack(
+    project: &Project,
+    stack_id: &str,
+    branches: Vec<BranchOrder>,
+) -> Result<but_workspace::ui::StackDetails> {
+    let series = branches
+        .into_iter()
+        .map(|branch| -> Result<_> {
+            Ok(gitbutler_branch_actions::SeriesOrder {
+                commit_ids: project
+                    .commit_ids(&branch.commit_ids)?
+                    .into_iter()
+                    .map(|id| id.to_git2())
+                    .collect(),
+                name: branch.name,
+            })
+        })
+        .collect::<Result<_>>()?;
+    but_api::legacy::virtual_branches::reorder_stack(
+        project.id,
+        self::stack_id(stack_id)?,
+        gitbutler_branch_actions::StackOrder { series },
+    )?;
+    self::stack_details(project, stack_id)
+}
+
+/// Move the changes to `files` out of `branch` into a new stack with a branch named `new_branch`.
+pub fn split_branch(
+    project: &Project,
+    stack_id: &str,
+    branch: String,
+    new_branch: String,
+    files: Vec<String>,
+) -> Result<but_api::json::UIMoveChangesResult> {
+    but_api::legacy::workspace::split_branch(
+        project.id,
+        self::stack_id(stack_id)?,
+        branch,
+        new_branch,
+        files,
+    )
+}
+
+/// Remove `commit` from the stack with `stack_id`, leaving its changes in the worktree.
+pub fn undo_commit(
+    project: &Project,
+    stack_id: &str,
+    commit: &str,
+) -> Result<but_workspace::ui::StackDetails> {
+    but_api::legacy::virtual_branches::undo_commit(
+        project.id,
+        self::stack_id(stack_id)?,
+        project.commit_id(commit)?.to_string(),
+    )?;
+    self::stack_details(project, stack_id)
+}
+
+/// Restore the workspace to the snapshot with `snapshot_id`, and return that snapshot.
+pub fn restore_snapshot(project: &Project, snapshot_id: &str) -> Result<Snapshot> {
+    let snapshot = but_api::legacy::oplog::get_snapshot(project.id, snapshot_id.to_owned())?;
+    but_api::legacy::oplog::restore_snapshot(project.id, snapshot.commit_id.to_string())?;
+    Ok(snapshot)
+}
+
+/// Undo the last operation, like `but undo`, and return the snapshot that was restored.
+pub fn undo(project: &Project) -> Result<Snapshot> {
+    oplog::undo_last_operation(
+        &mut project.ctx()?,
+        &mut OutputChannel::new_without_pager_non_json(OutputFormat::None),
+    )?
+    .context("No previous operations to undo")
+}
+
+/// Push `branch` of the stack with `stack_id`, along with the branches below it.
+pub fn push(
+    project: &Project,
+    stack_id: &str,
+    branch: String,
+    with_force: bool,
+) -> Result<gitbutler_branch_actions::internal::PushResult> {
+    but_api::legacy::stack::push_stack(
+        project.id,
+        self::stack_id(stack_id)?,
+        with_force,
+        false,
+        branch,
+        true,
+        Vec::new(),
+    )
+}
+
+/// Render all changes in the worktree as a unified diff.
+pub fn worktree_diff(project: &Project) -> Result<String> {
+    let changes = but_core::diff::worktree_changes(&project.repo)?.changes;
+    unified_diff(project, changes)
+}
+
+/// Render the changes of `commit` compared to its first parent as a unified diff.
+pub fn commit_diff(project: &Project, commit: &str) -> Result<String> {
+    let commit_id = project.commit_id(commit)?;
+    let ctx = project.ctx()?;
+    let details = but_api::diff::commit_details(&ctx, commit_id, ComputeLineStats::No)?;
+    unified_diff(project, details.diff_with_first_parent)
+}
+
+fn unified_diff(project: &Project, changes: Vec<but_core::TreeChange>) -> Result<String> {
+    let context_lines = project.ctx()?.settings().context_lines;
+    let mut out = String::new();
+    for change in changes {
+        match change.unified_diff(&project.repo, context_lines)? {
+            Some(diff) => {
+                out.push_str(&diff.to_str_lossy());
+                if !diff.ends_with(b"\n") {
+                    out.push('\n');
+                }
+            }
+            None => {
+                out.push_str(&format!(
+                    "Binary file {} changed\n",
+                    change.path.to_str_lossy()
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
@@ -3,7 +3,9 @@
 
 pub mod absorb;
 pub mod actions;
+pub mod batch;
 pub mod branch;
+pub mod claude;
 pub mod commit;
 pub mod diff;
 pub mod discard;
@@ -13,12 +15,16 @@ pub mod mark;
 pub mod mcp;
 pub mod mcp_internal;
 pub mod oplog;
+pub mod patch;
 pub mod pull;
 pub mod push;
+pub mod rebase;
 pub mod refresh;
 pub mod resolve;
 pub mod reword;
 pub mod rub;
 pub mod show;
+pub mod split;
 pub mod status;
+pub mod tui;
 pub mod worktree;
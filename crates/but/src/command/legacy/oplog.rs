@@ -3,6 +3,8 @@ use but_oxidize::TimeExt;
 use colored::Colorize;
 use gitbutler_oplog::entry::{OperationKind, Snapshot};
 use gix::date::time::CustomFormat;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::utils::OutputChannel;
 
@@ -24,6 +26,75 @@ impl OplogFilter {
     }
 }
 
+/// An entry of the operations history as listed by `but oplog`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotOutput {
+    /// The ID of the snapshot, which can be passed to `but restore`.
+    id: String,
+    /// The time the snapshot was created, in seconds since the Unix epoch.
+    created_at: i64,
+    /// The details of the snapshot, or `None` if they couldn't be parsed.
+    details: Option<SnapshotDetailsOutput>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotDetailsOutput {
+    /// The version of the snapshot format.
+    version: u32,
+    /// The kind of operation performed just before the snapshot was taken, like `CreateCommit`.
+    #[schemars(with = "String")]
+    operation: OperationKind,
+    title: String,
+    body: Option<String>,
+    /// Additional information about the operation as key-value pairs.
+    trailers: Vec<TrailerOutput>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct TrailerOutput {
+    key: String,
+    value: String,
+}
+
+impl From<&Snapshot> for SnapshotOutput {
+    fn from(snapshot: &Snapshot) -> Self {
+        SnapshotOutput {
+            id: snapshot.commit_id.to_string(),
+            created_at: snapshot.created_at.seconds(),
+            details: snapshot
+                .details
+                .as_ref()
+                .map(|details| SnapshotDetailsOutput {
+                    version: details.version.0,
+                    operation: details.operation,
+                    title: details.title.clone(),
+                    body: details.body.clone(),
+                    trailers: details
+                        .trailers
+                        .iter()
+                        .map(|trailer| TrailerOutput {
+                            key: trailer.key.clone(),
+                            value: trailer.value.clone(),
+                        })
+                        .collect(),
+                }),
+        }
+    }
+}
+
+/// The JSON output of `but oplog snapshot`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct SnapshotCreatedOutput {
+    /// The ID of the new snapshot.
+    snapshot_id: String,
+    /// The message of the snapshot, which is empty if none was given.
+    message: String,
+    /// Always `create_snapshot`.
+    operation: &'static str,
+}
+
 pub(crate) fn show_oplog(
     ctx: &mut but_ctx::Context,
     out: &mut OutputChannel,
@@ -54,7 +125,7 @@ pub(crate) fn show_oplog(
 
     if snapshots.is_empty() {
         if let Some(out) = out.for_json() {
-            out.write_value(&snapshots)?;
+            out.write_value(Vec::<SnapshotOutput>::new())?;
         } else if let Some(out) = out.for_human() {
             writeln!(out, "No operations found in history.")?;
         }
@@ -62,7 +133,12 @@ pub(crate) fn show_oplog(
     }
 
     if let Some(out) = out.for_json() {
-        out.write_value(&snapshots)?;
+        out.write_value(
+            snapshots
+                .iter()
+                .map(SnapshotOutput::from)
+                .collect::<Vec<_>>(),
+        )?;
     } else if let Some(out) = out.for_human() {
         writeln!(out, "{}", "Operations History".blue().bold())?;
         writeln!(out, "{}", "─".repeat(50).dimmed())?;
@@ -89,6 +165,8 @@ pub(crate) fn show_oplog(
                     OperationKind::RestoreFromSnapshot => "RESTORE",
                     OperationKind::ReorderCommit => "REORDER",
                     OperationKind::InsertBlankCommit => "INSERT",
+                    OperationKind::SplitCommit => "SPLIT",
+                    OperationKind::InteractiveRebase => "REBASE",
                     OperationKind::MoveHunk => "MOVE_HUNK",
                     OperationKind::ReorderBranches => "REORDER_BRANCH",
                     OperationKind::UpdateWorkspaceBase => "UPDATE_BASE",
@@ -100,11 +178,15 @@ pub(crate) fn show_oplog(
                     OperationKind::DiscardChanges => "DISCARD",
                     OperationKind::Discard => "DISCARD",
                     OperationKind::OnDemandSnapshot => "SNAPSHOT",
+                    OperationKind::ClaudeTurn => "CLAUDE",
                     _ => "OTHER",
                 };
-                // For OnDemandSnapshot, show the message (body) if available
+                // For OnDemandSnapshot and ClaudeTurn, show the message (body) if available
                 // For Discard, show file names from trailers if available
-                let display_title = if details.operation == OperationKind::OnDemandSnapshot {
+                let display_title = if matches!(
+                    details.operation,
+                    OperationKind::OnDemandSnapshot | OperationKind::ClaudeTurn
+                ) {
                     details
                         .body
                         .as_ref()
@@ -254,23 +336,24 @@ pub(crate) fn restore_to_oplog(
     Ok(())
 }
 
+/// Restore the snapshot before the last operation, and return it, or `None` if there is nothing to undo.
 pub(crate) fn undo_last_operation(
     ctx: &mut but_ctx::Context,
     out: &mut OutputChannel,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Option<Snapshot>> {
     // Get the last two snapshots - restore to the second one back
-    let snapshots =
+    let mut snapshots =
         but_api::legacy::oplog::list_snapshots(ctx.legacy_project.id, 2, None, None, None)?;
 
     if snapshots.len() < 2 {
         if let Some(out) = out.for_human() {
             writeln!(out, "{}", "No previous operations to undo.".yellow())?;
         }
-        return Ok(());
+        return Ok(None);
     }
 
     // TODO: Why the second most recent one, and not use the most recent one?
-    let target_snapshot = &snapshots[1];
+    let target_snapshot = snapshots.swap_remove(1);
 
     let target_operation = target_snapshot
         .details
@@ -278,7 +361,7 @@ pub(crate) fn undo_last_operation(
         .map(|d| d.title.as_str())
         .unwrap_or("Unknown operation");
 
-    let target_time = snapshot_time_string(target_snapshot);
+    let target_time = snapshot_time_string(&target_snapshot);
 
     if let Some(out) = out.for_human() {
         writeln!(out, "{}", "Undoing operation...".blue().bold())?;
@@ -314,7 +397,7 @@ pub(crate) fn undo_last_operation(
         )?;
     }
 
-    Ok(())
+    Ok(Some(target_snapshot))
 }
 
 pub(crate) fn create_snapshot(
@@ -326,11 +409,11 @@ pub(crate) fn create_snapshot(
         but_api::legacy::oplog::create_snapshot(ctx.legacy_project.id, message.map(String::from))?;
 
     if let Some(out) = out.for_json() {
-        out.write_value(serde_json::json!({
-            "snapshot_id": snapshot_id.to_string(),
-            "message": message.unwrap_or(""),
-            "operation": "create_snapshot"
-        }))?;
+        out.write_value(SnapshotCreatedOutput {
+            snapshot_id: snapshot_id.to_string(),
+            message: message.unwrap_or_default().to_owned(),
+            operation: "create_snapshot",
+        })?;
     } else if let Some(out) = out.for_human() {
         writeln!(out, "{}", "Snapshot created successfully!".green().bold())?;
 
@@ -0,0 +1,628 @@
+//! Interactively stage uncommitted hunks and lines, like `git add -p`.
+//!
+//! Whole hunks are staged by assigning them to the stack of the branch. Hunk assignments can't represent
+//! parts of a hunk though, so lines picked by splitting or editing a hunk are committed to the branch right away,
+//! passing one hunk header per run of picked lines to the commit engine.
+
+use std::ops::Range;
+
+use anyhow::{Context as _, bail};
+use bstr::{BStr, BString, ByteSlice};
+use but_api::{json::HexHash, legacy::workspace};
+use but_core::{DiffSpec, HunkHeader};
+use but_ctx::Context;
+use but_hunk_assignment::{HunkAssignment, HunkAssignmentRequest};
+use colored::Colorize;
+use gitbutler_oplog::{
+    OplogExt,
+    entry::{OperationKind, SnapshotDetails},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    CliId, IdMap,
+    command::legacy::rub::branch_name_to_stack_id,
+    tui,
+    utils::{InputOutputChannel, OutputChannel},
+};
+
+/// JSON output for `but stage --patch`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PatchOutput {
+    /// The amount of whole hunks that were assigned to the branch.
+    staged_hunks: usize,
+    /// The commit with the individually picked lines, if there were any.
+    commit: Option<String>,
+}
+
+/// Walk the unassigned hunks, or only those of `source`, and stage what the user picks to `branch`.
+/// Lines picked from within a hunk are committed to `branch` with `message`, or a message from the editor.
+pub(crate) fn handle(
+    ctx: &mut Context,
+    out: &mut OutputChannel,
+    source: Option<&str>,
+    branch: &str,
+    message: Option<&str>,
+) -> anyhow::Result<()> {
+    let id_map = IdMap::new_from_context(ctx, None)?;
+    let branch_name = match id_map.resolve_entity_to_ids(branch)?.as_slice() {
+        [CliId::Branch { name, .. }] => name.clone(),
+        [] => bail!("Branch '{}' not found.", branch),
+        [other] => bail!(
+            "Cannot stage to {} - it is {}. Target must be a branch.",
+            other.to_short_string().blue().underline(),
+            other.kind_for_humans().yellow()
+        ),
+        _ => bail!("Branch '{}' is ambiguous.", branch),
+    };
+    let stack_id = branch_name_to_stack_id(ctx, Some(branch_name.as_str()))?
+        .with_context(|| format!("Branch '{branch_name}' isn't in the workspace"))?;
+
+    let only = match source {
+        Some(source) => {
+            let mut only = Vec::new();
+            for id in id_map.resolve_entity_to_ids(source)? {
+                let CliId::Uncommitted(uncommitted) = id else {
+                    bail!(
+                        "Cannot stage {} - it is {}. Only uncommitted files and hunks can be staged.",
+                        id.to_short_string().blue().underline(),
+                        id.kind_for_humans().yellow()
+                    );
+                };
+                only.extend(
+                    uncommitted
+                        .hunk_assignments
+                        .into_iter()
+                        .map(|a| (a.path_bytes, a.hunk_header)),
+                );
+            }
+            if only.is_empty() {
+                bail!("Source '{}' not found.", source);
+            }
+            Some(only)
+        }
+        None => None,
+    };
+
+    let changes = but_core::diff::ui::worktree_changes_by_worktree_dir(
+        ctx.legacy_project.worktree_dir()?.into(),
+    )?
+    .changes;
+    let (assignments, _assignments_error) =
+        but_hunk_assignment::assignments_with_fallback(ctx, false, Some(changes), None)?;
+    let hunks = assignments
+        .into_iter()
+        .filter(|a| a.stack_id.is_none())
+        .filter(|a| {
+            only.as_ref().is_none_or(|only| {
+                only.iter()
+                    .any(|(path, header)| *path == a.path_bytes && *header == a.hunk_header)
+            })
+        })
+        .map(Hunk::from_assignment)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if hunks.is_empty() {
+        if let Some(out) = out.for_human() {
+            writeln!(out, "No unassigned changes to stage.")?;
+        }
+        return Ok(());
+    }
+
+    let selections = {
+        let Some(inout) = out.prepare_for_terminal_input() else {
+            bail!(
+                "Picking hunks needs an interactive terminal. Use `but stage <file-or-hunk> <branch>` instead."
+            );
+        };
+        pick(&hunks, &mut Terminal { inout })?
+    };
+
+    let mut reqs = Vec::new();
+    let mut diff_specs: Vec<DiffSpec> = Vec::new();
+    for (hunk, selected) in hunks.iter().zip(&selections) {
+        if hunk.is_fully_selected(selected) {
+            reqs.push(HunkAssignmentRequest {
+                hunk_header: hunk.header,
+                path_bytes: hunk.path.clone(),
+                stack_id: Some(stack_id),
+            });
+            continue;
+        }
+        let hunk_headers = hunk.line_headers(selected);
+        if hunk_headers.is_empty() {
+            continue;
+        }
+        match diff_specs.iter_mut().find(|spec| spec.path == hunk.path) {
+            Some(spec) => spec.hunk_headers.extend(hunk_headers),
+            None => diff_specs.push(DiffSpec {
+                previous_path: None,
+                path: hunk.path.clone(),
+                hunk_headers,
+            }),
+        }
+    }
+
+    let mut staged_hunks = reqs.len();
+    if !reqs.is_empty() {
+        create_snapshot(ctx);
+        let rejections = but_hunk_assignment::assign(ctx, reqs, None)?;
+        staged_hunks -= rejections.len();
+        if let Some(out) = out.for_human() {
+            for rejection in &rejections {
+                let commits: Vec<_> = rejection
+                    .locks
+                    .iter()
+                    .map(|lock| lock.commit_id.to_hex_with_len(7).to_string())
+                    .collect();
+                writeln!(
+                    out,
+                    "{} A hunk in {} wasn't staged as it depends on {} {} of another stack.",
+                    "!".yellow(),
+                    rejection.request.path_bytes.to_str_lossy().bold(),
+                    if commits.len() == 1 {
+                        "commit"
+                    } else {
+                        "commits"
+                    },
+                    commits.join(", ")
+                )?;
+            }
+        }
+    }
+
+    let commit = if diff_specs.is_empty() {
+        None
+    } else {
+        let message = match message {
+            Some(message) => message.to_owned(),
+            None => tui::get_text::from_editor_no_comments(
+                "commit_msg",
+                "\n# Please enter the commit message for the picked lines. Lines starting\n\
+                 # with '#' will be ignored, and an empty message aborts the commit.\n",
+            )?
+            .to_string(),
+        };
+        if message.trim().is_empty() {
+            bail!("Aborting commit of the picked lines due to empty commit message.");
+        }
+        let project_id = ctx.legacy_project.id;
+        let stack = workspace::stack_details(project_id, Some(stack_id))?;
+        let target_branch = stack
+            .branch_details
+            .iter()
+            .find(|b| b.name == branch_name)
+            .with_context(|| format!("Branch '{branch_name}' not found in its stack"))?;
+        let outcome = workspace::create_commit_from_worktree_changes(
+            project_id,
+            stack_id,
+            Some(HexHash::from(target_branch.tip)),
+            diff_specs,
+            message,
+            target_branch.name.to_string(),
+        )?;
+        outcome.new_commit.map(|id| id.to_string())
+    };
+
+    if let Some(out) = out.for_human() {
+        if staged_hunks == 0 && commit.is_none() {
+            writeln!(out, "Nothing was staged.")?;
+        }
+        if staged_hunks > 0 {
+            writeln!(
+                out,
+                "Staged {staged_hunks} {} → {}.",
+                if staged_hunks == 1 { "hunk" } else { "hunks" },
+                format!("[{branch_name}]").green()
+            )?;
+        }
+        if let Some(commit) = &commit {
+            writeln!(
+                out,
+                "Committed the picked lines as {} on branch {}, as single lines can't be staged.",
+                &commit[..7],
+                branch_name
+            )?;
+        }
+    } else if let Some(out) = out.for_json() {
+        out.write_value(PatchOutput {
+            staged_hunks,
+            commit,
+        })?;
+    }
+    Ok(())
+}
+
+fn create_snapshot(ctx: &mut Context) {
+    let mut guard = ctx.exclusive_worktree_access();
+    let _snapshot = ctx
+        .create_snapshot(
+            SnapshotDetails::new(OperationKind::MoveHunk),
+            guard.write_permission(),
+        )
+        .ok();
+}
+
+/// Asks the user what to do with a part of a hunk.
+trait Prompt {
+    /// Show `question` and return the answer, which is empty if the user didn't type anything,
+    /// or `None` if the input ended.
+    fn ask(&mut self, question: &str) -> anyhow::Result<Option<String>>;
+    /// Let the user edit `text` and return the result without comment lines.
+    fn edit(&mut self, text: &str) -> anyhow::Result<BString>;
+}
+
+struct Terminal<'a> {
+    inout: InputOutputChannel<'a>,
+}
+
+impl Prompt for Terminal<'_> {
+    fn ask(&mut self, question: &str) -> anyhow::Result<Option<String>> {
+        self.inout.prompt_line(question)
+    }
+
+    fn edit(&mut self, text: &str) -> anyhow::Result<BString> {
+        tui::get_text::from_editor_no_comments("hunk_edit", text)
+    }
+}
+
+const HELP: &str = "y - stage this hunk
+n - do not stage this hunk
+s - split this hunk into smaller hunks
+e - edit this hunk to pick single lines
+a - stage this hunk and all later hunks in the file
+d - do not stage this hunk or any of the later hunks in the file
+q - quit, staging only what was picked so far
+? - print help";
+
+/// Ask about each hunk, or the parts it was split into, and return which of its lines were picked.
+fn pick(hunks: &[Hunk], prompt: &mut impl Prompt) -> anyhow::Result<Vec<Vec<bool>>> {
+    // Hunks without lines can only be picked as a whole, which is tracked in their first and only slot.
+    let mut selections: Vec<_> = hunks
+        .iter()
+        .map(|h| vec![false; h.lines.len().max(1)])
+        .collect();
+    let mut pieces: Vec<_> = hunks
+        .iter()
+        .enumerate()
+        .map(|(hunk, h)| Piece {
+            hunk,
+            lines: 0..h.lines.len(),
+        })
+        .collect();
+    let mut note = None::<String>;
+    let mut idx = 0;
+    while idx < pieces.len() {
+        let piece = pieces[idx].clone();
+        let hunk = &hunks[piece.hunk];
+        let parts = piece.split(hunk);
+        let mut question = String::new();
+        if let Some(note) = note.take() {
+            question.push_str(&format!("{}\n", note.yellow()));
+        }
+        question.push_str(&format!(
+            "{}\n{}Stage this hunk [{}/{}] ({})?",
+            hunk.path.to_str_lossy().bold(),
+            piece.display(hunk),
+            idx + 1,
+            pieces.len(),
+            match (parts.len() > 1, !hunk.lines.is_empty()) {
+                (true, _) => "y,n,s,e,a,d,q,?",
+                (false, true) => "y,n,e,a,d,q,?",
+                (false, false) => "y,n,a,d,q,?",
+            }
+        ));
+        let Some(answer) = prompt.ask(&question)? else {
+            // The input ended, so nobody is left to answer.
+            break;
+        };
+        let same_file = || {
+            idx..idx
+                + pieces[idx..]
+                    .iter()
+                    .take_while(|p| hunks[p.hunk].path == hunk.path)
+                    .count()
+        };
+        match answer.as_str() {
+            "y" => piece.select(&mut selections[piece.hunk], true),
+            "n" => {}
+            "s" if parts.len() > 1 => {
+                pieces.splice(idx..=idx, parts);
+                continue;
+            }
+            "e" if !hunk.lines.is_empty() => {
+                let edited = prompt.edit(&piece.edit_text(hunk))?;
+                if let Err(err) =
+                    piece.apply_edit(hunk, edited.as_bstr(), &mut selections[piece.hunk])
+                {
+                    note = Some(format!("{err:#}"));
+                    continue;
+                }
+            }
+            "a" => {
+                let rest = same_file();
+                for piece in &pieces[rest.clone()] {
+                    piece.select(&mut selections[piece.hunk], true);
+                }
+                idx = rest.end;
+                continue;
+            }
+            "d" => {
+                idx = same_file().end;
+                continue;
+            }
+            "q" => break,
+            // Ask again, like `git add -p` does.
+            "" => continue,
+            _ => {
+                note = Some(HELP.into());
+                continue;
+            }
+        }
+        idx += 1;
+    }
+    Ok(selections)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+impl LineKind {
+    fn prefix(&self) -> char {
+        match self {
+            LineKind::Context => ' ',
+            LineKind::Added => '+',
+            LineKind::Removed => '-',
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    kind: LineKind,
+    /// The line number in the old version of the file for removed lines, and in the new version otherwise.
+    number: u32,
+    text: BString,
+}
+
+/// An unassigned hunk along with its diff.
+#[derive(Debug)]
+struct Hunk {
+    path: BString,
+    /// The header of the hunk, or `None` if the whole file is one change that can't be shown, like binary files.
+    header: Option<HunkHeader>,
+    /// The lines of the diff, empty if it can't be shown.
+    lines: Vec<Line>,
+}
+
+impl Hunk {
+    fn from_assignment(assignment: HunkAssignment) -> anyhow::Result<Self> {
+        let lines = match (&assignment.hunk_header, &assignment.diff) {
+            (Some(header), Some(diff)) => parse_lines(header, diff.as_bstr())?,
+            _ => Vec::new(),
+        };
+        Ok(Hunk {
+            path: assignment.path_bytes,
+            header: assignment.hunk_header,
+            lines,
+        })
+    }
+
+    /// Return `true` if all changed lines are `selected`.
+    fn is_fully_selected(&self, selected: &[bool]) -> bool {
+        if self.lines.is_empty() {
+            return selected.first() == Some(&true);
+        }
+        self.lines
+            .iter()
+            .zip(selected)
+            .filter(|(line, _)| line.kind != LineKind::Context)
+            .all(|(_, selected)| *selected)
+    }
+
+    /// Return one header for each run of selected lines of the same kind, anchored at `0` as the commit engine expects.
+    fn line_headers(&self, selected: &[bool]) -> Vec<HunkHeader> {
+        let mut headers = Vec::new();
+        let mut run = None::<(LineKind, u32, u32)>;
+        for (line, selected) in self.lines.iter().zip(selected) {
+            let is_change = line.kind != LineKind::Context;
+            match &mut run {
+                Some((kind, _, count)) if *selected && is_change && *kind == line.kind => {
+                    *count += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            headers.extend(run.take().map(to_header));
+            if *selected && is_change {
+                run = Some((line.kind, line.number, 1));
+            }
+        }
+        headers.extend(run.map(to_header));
+        headers
+    }
+}
+
+fn to_header((kind, start, count): (LineKind, u32, u32)) -> HunkHeader {
+    match kind {
+        LineKind::Added => HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: start,
+            new_lines: count,
+        },
+        LineKind::Removed | LineKind::Context => HunkHeader {
+            old_start: start,
+            old_lines: count,
+            new_start: 0,
+            new_lines: 0,
+        },
+    }
+}
+
+/// Parse the lines of `diff`, a unified diff of a single hunk with `header`.
+fn parse_lines(header: &HunkHeader, diff: &BStr) -> anyhow::Result<Vec<Line>> {
+    let mut old = header.old_start;
+    let mut new = header.new_start;
+    let mut lines = Vec::new();
+    for line in diff.lines() {
+        let Some((&prefix, text)) = line.split_first() else {
+            continue;
+        };
+        let (kind, number) = match prefix {
+            b'@' | b'\\' => continue,
+            b' ' => {
+                old += 1;
+                new += 1;
+                (LineKind::Context, new - 1)
+            }
+            b'+' => {
+                new += 1;
+                (LineKind::Added, new - 1)
+            }
+            b'-' => {
+                old += 1;
+                (LineKind::Removed, old - 1)
+            }
+            _ => bail!("Unexpected line in diff: {}", line.as_bstr()),
+        };
+        lines.push(Line {
+            kind,
+            number,
+            text: text.into(),
+        });
+    }
+    Ok(lines)
+}
+
+/// A part of a hunk to ask about, as range into its lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    hunk: usize,
+    lines: Range<usize>,
+}
+
+impl Piece {
+    /// Split into one piece per run of changed lines, each with the context around it.
+    fn split(&self, hunk: &Hunk) -> Vec<Piece> {
+        let lines = &hunk.lines[self.lines.clone()];
+        let mut runs: Vec<Range<usize>> = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            let idx = self.lines.start + idx;
+            if line.kind == LineKind::Context {
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run) if run.end == idx => run.end += 1,
+                _ => runs.push(idx..idx + 1),
+            }
+        }
+        (0..runs.len())
+            .map(|idx| Piece {
+                hunk: self.hunk,
+                lines: idx
+                    .checked_sub(1)
+                    .map_or(self.lines.start, |prev| runs[prev].end)
+                    ..runs.get(idx + 1).map_or(self.lines.end, |next| next.start),
+            })
+            .collect()
+    }
+
+    fn select(&self, selected: &mut [bool], value: bool) {
+        selected[self.lines.clone()]
+            .iter_mut()
+            .for_each(|s| *s = value);
+        if selected.is_empty() {
+            return;
+        }
+        if self.lines.is_empty() {
+            selected[0] = value;
+        }
+    }
+
+    fn display(&self, hunk: &Hunk) -> String {
+        let mut out = String::new();
+        for line in &hunk.lines[self.lines.clone()] {
+            let text = format!("{}{}", line.kind.prefix(), line.text.to_str_lossy());
+            let text = match line.kind {
+                LineKind::Context => text.normal(),
+                LineKind::Added => text.green(),
+                LineKind::Removed => text.red(),
+            };
+            out.push_str(&format!("{text}\n"));
+        }
+        if hunk.lines.is_empty() {
+            out.push_str("(the whole file, as its diff can't be shown)\n");
+        }
+        out
+    }
+
+    fn edit_text(&self, hunk: &Hunk) -> String {
+        let mut text = String::from(
+            "# Pick the lines to stage:\n\
+             # To not stage a '-' line, turn it into a ' ' line.\n\
+             # To not stage a '+' line, delete it.\n\
+             # Lines starting with '#' will be removed.\n",
+        );
+        for line in &hunk.lines[self.lines.clone()] {
+            text.push(line.kind.prefix());
+            text.push_str(&line.text.to_str_lossy());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Select the changed lines that were kept in `edited`, the result of editing [`Self::edit_text()`].
+    fn apply_edit(&self, hunk: &Hunk, edited: &BStr, selected: &mut [bool]) -> anyhow::Result<()> {
+        let mut edited = edited.lines().peekable();
+        let mut picked = Vec::new();
+        for (idx, line) in hunk.lines[self.lines.clone()].iter().enumerate() {
+            let unchanged = |prefix: u8, edited: &[u8]| {
+                (edited.first() == Some(&prefix) && edited[1..] == line.text[..])
+                    || (prefix == b' ' && edited.is_empty() && line.text.is_empty())
+            };
+            match line.kind {
+                LineKind::Added => {
+                    if edited.next_if(|e| unchanged(b'+', *e)).is_some() {
+                        picked.push(self.lines.start + idx);
+                    }
+                }
+                LineKind::Removed => match edited.next() {
+                    Some(e) if unchanged(b'-', e) => picked.push(self.lines.start + idx),
+                    Some(e) if unchanged(b' ', e) => {}
+                    _ => bail!(
+                        "The removed line '{}' must be kept, either as is or turned into a context line.",
+                        line.text.to_str_lossy()
+                    ),
+                },
+                LineKind::Context => {
+                    if edited.next_if(|e| unchanged(b' ', *e)).is_none() {
+                        bail!(
+                            "The context line '{}' must not be changed.",
+                            line.text.to_str_lossy()
+                        );
+                    }
+                }
+            }
+        }
+        if let Some(extra) = edited.next() {
+            bail!(
+                "Lines can't be added or changed, only removed: '{}'",
+                extra.as_bstr()
+            );
+        }
+        self.select(selected, false);
+        for idx in picked {
+            selected[idx] = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+
+use anyhow::bail;
+use bstr::BString;
+use but_core::HunkHeader;
+
+use super::{Hunk, LineKind, Piece, Prompt, parse_lines, pick};
+
+#[test]
+fn lines_are_numbered_by_the_side_they_are_on() -> anyhow::Result<()> {
+    let hunk = two_changes("a.txt");
+    let lines: Vec<_> = hunk
+        .lines
+        .iter()
+        .map(|l| (l.kind, l.number, l.text.to_string()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (LineKind::Context, 1, "a".into()),
+            (LineKind::Removed, 2, "b".into()),
+            (LineKind::Added, 2, "B".into()),
+            (LineKind::Context, 3, "c".into()),
+            (LineKind::Removed, 4, "d".into()),
+            (LineKind::Added, 4, "D".into()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn picked_lines_become_anchored_hunk_headers() {
+    let hunk = two_changes("a.txt");
+    assert_eq!(
+        hunk.line_headers(&[false, true, false, false, false, true]),
+        [header(2, 1, 0, 0), header(0, 0, 4, 1)]
+    );
+    assert_eq!(
+        hunk.line_headers(&[true, true, true, true, true, false]),
+        [header(2, 1, 0, 0), header(0, 0, 2, 1), header(4, 1, 0, 0)],
+        "context lines are ignored, and runs are split by kind"
+    );
+    assert!(
+        hunk.line_headers(&[true, false, false, true, false, false])
+            .is_empty()
+    );
+}
+
+#[test]
+fn splitting_keeps_the_context_around_each_change() {
+    let hunk = two_changes("a.txt");
+    let piece = Piece {
+        hunk: 0,
+        lines: 0..hunk.lines.len(),
+    };
+    assert_eq!(
+        piece.split(&hunk),
+        [
+            Piece {
+                hunk: 0,
+                lines: 0..4
+            },
+            Piece {
+                hunk: 0,
+                lines: 3..6
+            }
+        ]
+    );
+}
+
+#[test]
+fn split_hunks_can_be_picked_partially() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt")];
+    let mut prompt = Script::new(["s", "y", "n"], []);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert_eq!(selections, [[true, true, true, true, false, false]]);
+    assert!(!hunks[0].is_fully_selected(&selections[0]));
+    assert_eq!(
+        hunks[0].line_headers(&selections[0]),
+        [header(2, 1, 0, 0), header(0, 0, 2, 1)]
+    );
+    assert!(prompt.questions[0].contains("(y,n,s,e,a,d,q,?)"));
+    assert!(
+        prompt.questions[1].contains("[1/2]"),
+        "the parts are asked about in turn"
+    );
+    Ok(())
+}
+
+#[test]
+fn all_and_done_apply_to_the_rest_of_the_file() -> anyhow::Result<()> {
+    let hunks = [
+        two_changes("a.txt"),
+        two_changes("a.txt"),
+        two_changes("b.txt"),
+    ];
+    let mut prompt = Script::new(["a", "d"], []);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(hunks[0].is_fully_selected(&selections[0]));
+    assert!(hunks[1].is_fully_selected(&selections[1]));
+    assert!(!hunks[2].is_fully_selected(&selections[2]));
+    assert_eq!(prompt.questions.len(), 2, "b.txt was asked about once");
+    Ok(())
+}
+
+#[test]
+fn edits_pick_the_remaining_changed_lines() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt")];
+    let mut prompt = Script::new(["e"], [" a\n b\n+B\n c\n-d\n"]);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert_eq!(
+        hunks[0].line_headers(&selections[0]),
+        [header(0, 0, 2, 1), header(4, 1, 0, 0)]
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_edits_are_explained_and_asked_again() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt")];
+    let mut prompt = Script::new(["e", "q"], [" a\n+X\n"]);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(selections[0].iter().all(|s| !s), "nothing was picked");
+    assert!(
+        prompt.questions[1].contains("The removed line 'b' must be kept"),
+        "{}",
+        prompt.questions[1]
+    );
+    Ok(())
+}
+
+#[test]
+fn unknown_answers_show_the_help() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt")];
+    let mut prompt = Script::new(["x", "y"], []);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(hunks[0].is_fully_selected(&selections[0]));
+    assert!(prompt.questions[1].contains("s - split this hunk"));
+    Ok(())
+}
+
+#[test]
+fn empty_answers_ask_again() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt")];
+    let mut prompt = Script::new(["", "y"], []);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(hunks[0].is_fully_selected(&selections[0]));
+    assert_eq!(prompt.questions.len(), 2);
+    assert!(
+        !prompt.questions[1].contains("s - split this hunk"),
+        "it's not a mistake, so there is no help"
+    );
+    Ok(())
+}
+
+#[test]
+fn the_end_of_input_quits() -> anyhow::Result<()> {
+    let hunks = [two_changes("a.txt"), two_changes("b.txt")];
+    let mut prompt = Script::new(["y"], []).then_end_of_input();
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(hunks[0].is_fully_selected(&selections[0]));
+    assert!(
+        !selections[1].contains(&true),
+        "what wasn't answered isn't picked"
+    );
+    assert_eq!(
+        prompt.questions.len(),
+        2,
+        "nothing is asked after the input ended"
+    );
+    Ok(())
+}
+
+#[test]
+fn hunks_without_diff_can_only_be_picked_whole() -> anyhow::Result<()> {
+    let hunks = [Hunk {
+        path: "image.png".into(),
+        header: None,
+        lines: Vec::new(),
+    }];
+    let mut prompt = Script::new(["e", "y"], []);
+    let selections = pick(&hunks, &mut prompt)?;
+    assert!(prompt.questions[0].contains("(y,n,a,d,q,?)"));
+    assert!(hunks[0].is_fully_selected(&selections[0]));
+    Ok(())
+}
+
+/// Two separate changes with one context line between them.
+fn two_changes(path: &str) -> Hunk {
+    let header = header(1, 4, 1, 4);
+    Hunk {
+        path: path.into(),
+        header: Some(header),
+        lines: parse_lines(&header, "@@ -1,4 +1,4 @@\n a\n-b\n+B\n c\n-d\n+D\n".into())
+            .expect("valid diff"),
+    }
+}
+
+fn header(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> HunkHeader {
+    HunkHeader {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+    }
+}
+
+/// Answers questions and edits in order, recording the questions.
+struct Script {
+    answers: VecDeque<&'static str>,
+    edits: VecDeque<&'static str>,
+    questions: Vec<String>,
+    /// If `true`, the input ends after the last answer, instead of failing the test.
+    end_of_input: bool,
+}
+
+impl Script {
+    fn new<const A: usize, const E: usize>(
+        answers: [&'static str; A],
+        edits: [&'static str; E],
+    ) -> Self {
+        Script {
+            answers: answers.into(),
+            edits: edits.into(),
+            questions: Vec::new(),
+            end_of_input: false,
+        }
+    }
+
+    fn then_end_of_input(mut self) -> Self {
+        self.end_of_input = true;
+        self
+    }
+}
+
+impl Prompt for Script {
+    fn ask(&mut self, question: &str) -> anyhow::Result<Option<String>> {
+        self.questions.push(question.to_owned());
+        let Some(answer) = self.answers.pop_front() else {
+            if self.end_of_input {
+                return Ok(None);
+            }
+            bail!("ran out of answers at: {question}");
+        };
+        Ok(Some(answer.into()))
+    }
+
+    fn edit(&mut self, _text: &str) -> anyhow::Result<BString> {
+        let Some(edit) = self.edits.pop_front() else {
+            bail!("ran out of edits");
+        };
+        Ok(edit.into())
+    }
+}
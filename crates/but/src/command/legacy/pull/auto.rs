@@ -0,0 +1,197 @@
+//! `but pull --auto`, which integrates upstream changes according to the project's
+//! [upstream integration policy](gitbutler_project::UpstreamIntegrationPolicy).
+
+use std::fmt::Write;
+
+use but_ctx::Context;
+use colored::Colorize;
+use gitbutler_branch_actions::upstream_integration::{
+    PolicyDecision, PolicyStackState, Resolution, ResolutionApproach,
+    StackStatuses::{UpToDate, UpdatesRequired},
+};
+use gitbutler_project::IntegrationAction;
+
+use super::json::{AutoPullOutput, PullOutput, StackDecision};
+use crate::utils::OutputChannel;
+
+/// Fetch, then decide for each stack how to integrate the new upstream commits.
+///
+/// `integrate_upstream` needs a resolution for every stack in the workspace, so nothing is
+/// integrated if the policy leaves even a single stack to the user. All decisions are reported
+/// either way, so the user knows which stacks need their attention.
+pub(super) async fn handle(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<()> {
+    let mut progress = out.progress_channel();
+    if out.for_human().is_some() {
+        writeln!(
+            progress,
+            "{}",
+            "Fetching newest data from remotes...".bright_cyan()
+        )?;
+    }
+    let base_branch = but_api::legacy::virtual_branches::fetch_from_remotes(
+        ctx.legacy_project.id,
+        Some("pull".to_string()),
+    )?;
+
+    if out.for_human().is_some() {
+        writeln!(progress, "   Checking integration statuses...")?;
+    }
+    let status = but_api::legacy::virtual_branches::upstream_integration_statuses(
+        ctx.legacy_project.id,
+        None,
+    )
+    .await?;
+
+    let policy = ctx.legacy_project.upstream_integration_policy;
+    let mut output = AutoPullOutput {
+        status: "up_to_date",
+        upstream_commits_found: base_branch.behind,
+        decisions: vec![],
+        undo_command: None,
+    };
+    let mut resolutions = vec![];
+    if let UpdatesRequired {
+        worktree_conflicts,
+        statuses,
+    } = status
+    {
+        output.status = if worktree_conflicts.is_empty() {
+            "updating"
+        } else {
+            "worktree_conflicts"
+        };
+        for (maybe_stack_id, status) in &statuses {
+            // Without a stack ID we are in single-branch mode, which has nothing to decide.
+            let Some(stack_id) = maybe_stack_id else {
+                continue;
+            };
+            let decision = if worktree_conflicts.is_empty() {
+                status.decide(&policy)
+            } else {
+                PolicyDecision {
+                    state: PolicyStackState::WorktreeConflicted,
+                    action: IntegrationAction::Leave,
+                    approach: None,
+                }
+            };
+            if let Some(approach) = decision.approach {
+                resolutions.push(Resolution {
+                    stack_id: *stack_id,
+                    approach,
+                    delete_integrated_branches: true,
+                });
+            }
+            output.decisions.push(StackDecision {
+                stack_id: *stack_id,
+                branches: status
+                    .branch_statuses
+                    .iter()
+                    .map(|branch| branch.name.clone())
+                    .collect(),
+                state: decision.state,
+                action: decision.action,
+                approach: decision.approach,
+            });
+        }
+    }
+
+    let needs_attention = output
+        .decisions
+        .iter()
+        .any(|decision| decision.approach.is_none());
+    if output.status == "updating" {
+        if needs_attention {
+            output.status = "needs_attention";
+        } else {
+            but_api::legacy::virtual_branches::integrate_upstream(
+                ctx.legacy_project.id,
+                resolutions,
+                None,
+            )
+            .await?;
+            output.status = "completed";
+            output.undo_command = Some("but undo".to_string());
+        }
+    }
+
+    if let Some(out) = out.for_human() {
+        if output.status == "up_to_date" {
+            writeln!(out, "\n{}", "Everything is up to date".green())?;
+        } else {
+            writeln!(
+                out,
+                "\n{} upstream commits on {}",
+                base_branch.behind.to_string().bright_yellow(),
+                base_branch.branch_name.bright_cyan()
+            )?;
+            writeln!(out, "\n{}", "Decisions".bold())?;
+            writeln!(out, "─────────")?;
+            for decision in &output.decisions {
+                writeln!(
+                    out,
+                    "  {} ({}) - {}",
+                    decision.branches.join(", ").bright_cyan(),
+                    format_state(decision.state),
+                    format_decision(decision.action, decision.approach)
+                )?;
+            }
+            match output.status {
+                "completed" => {
+                    writeln!(out, "\n{}", "Upstream changes were integrated".green())?;
+                    writeln!(out, "To undo this operation, run `but undo`")?;
+                }
+                "worktree_conflicts" => {
+                    writeln!(
+                        out,
+                        "\n{}",
+                        "There are uncommitted changes in the worktree that may conflict with the updates.".red()
+                    )?;
+                    writeln!(
+                        out,
+                        "   {}",
+                        "Please commit or stash them and try again.".yellow()
+                    )?;
+                }
+                _ => {
+                    writeln!(
+                        out,
+                        "\n{}",
+                        "Nothing was integrated as some stacks need your decision.".yellow()
+                    )?;
+                    writeln!(
+                        out,
+                        "   Run {} to update them interactively, or adjust the project's upstream integration policy.",
+                        "`but pull`".bright_cyan()
+                    )?;
+                }
+            }
+        }
+    }
+    if let Some(out) = out.for_json() {
+        out.write_value(PullOutput::Auto(output))?;
+    }
+    Ok(())
+}
+
+fn format_state(state: PolicyStackState) -> &'static str {
+    match state {
+        PolicyStackState::SafelyUpdatable => "updatable",
+        PolicyStackState::Conflicted => "conflicted",
+        PolicyStackState::Integrated => "integrated",
+        PolicyStackState::WorktreeConflicted => "conflicts with uncommitted changes",
+    }
+}
+
+fn format_decision(action: IntegrationAction, approach: Option<ResolutionApproach>) -> String {
+    match approach {
+        Some(ResolutionApproach::Rebase) => "rebase".green().to_string(),
+        Some(ResolutionApproach::Merge) => "merge".green().to_string(),
+        Some(ResolutionApproach::Unapply) => "unapply".bright_purple().to_string(),
+        Some(ResolutionApproach::Delete) => "delete".bright_purple().to_string(),
+        None if action == IntegrationAction::Leave => "left for you to decide".yellow().to_string(),
+        None => format!("{action:?} isn't possible, left for you to decide")
+            .to_lowercase()
+            .yellow()
+            .to_string(),
+    }
+}
@@ -1,11 +1,24 @@
 //! JSON output structures for `but pull` commands.
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
+/// JSON output for `but pull`, whose shape depends on the mode it ran in.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum PullOutput {
+    /// The output of `but pull --check`.
+    Check(PullCheckOutput),
+    /// The output of `but pull --auto`.
+    Auto(AutoPullOutput),
+    /// The output of a plain `but pull`.
+    Pull(PullResult),
+}
+
 /// JSON output for `but pull --check`
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct PullCheckOutput {
+pub(crate) struct PullCheckOutput {
     pub base_branch: BaseBranchInfo,
     pub upstream_commits: UpstreamInfo,
     pub branch_statuses: Vec<BranchStatusInfo>,
@@ -13,34 +26,137 @@ pub(super) struct PullCheckOutput {
     pub has_worktree_conflicts: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct BaseBranchInfo {
+pub(crate) struct BaseBranchInfo {
     pub name: String,
     pub remote_name: String,
     pub base_sha: String,
     pub current_sha: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct UpstreamInfo {
+pub(crate) struct UpstreamInfo {
     pub count: usize,
     pub commits: Vec<UpstreamCommit>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct UpstreamCommit {
+pub(crate) struct UpstreamCommit {
     pub id: String,
     pub description: String,
     pub author_name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct BranchStatusInfo {
+pub(crate) struct BranchStatusInfo {
     pub name: String,
+    /// One of `updatable`, `integrated`, `conflicted` or `empty`.
     pub status: String,
     pub rebasable: Option<bool>,
+    pub conflicts: Vec<CommitConflictInfo>,
+}
+
+/// A commit that would conflict when rebased onto the new upstream commits.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommitConflictInfo {
+    pub commit_id: String,
+    pub subject: String,
+    pub paths: Vec<String>,
+}
+
+/// JSON output for `but pull --auto`
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AutoPullOutput {
+    /// One of `up_to_date`, `worktree_conflicts`, `needs_attention` or `completed`.
+    pub status: &'static str,
+    pub upstream_commits_found: usize,
+    pub decisions: Vec<StackDecision>,
+    pub undo_command: Option<String>,
+}
+
+/// What the upstream integration policy decided for one stack.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StackDecision {
+    #[schemars(with = "String")]
+    pub stack_id: gitbutler_stack::StackId,
+    pub branches: Vec<String>,
+    /// One of `safelyUpdatable`, `conflicted`, `integrated` or `worktreeConflicted`.
+    #[schemars(with = "String")]
+    pub state: gitbutler_branch_actions::upstream_integration::PolicyStackState,
+    /// One of `rebase`, `merge`, `unapply`, `delete` or `leave`.
+    #[schemars(with = "String")]
+    pub action: gitbutler_project::IntegrationAction,
+    /// The approach used to integrate the stack, or `None` if it was left for the user to decide.
+    #[schemars(schema_with = "approach_schema")]
+    pub approach: Option<gitbutler_branch_actions::upstream_integration::ResolutionApproach>,
+}
+
+/// The approach is serialized as `{"type": "rebase"}`, with `type` being one of `rebase`, `merge`, `unapply` or `delete`.
+fn approach_schema(_generate: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({
+        "type": ["object", "null"],
+        "properties": {
+            "type": {
+                "type": "string",
+                "enum": ["rebase", "merge", "unapply", "delete"]
+            }
+        },
+        "required": ["type"]
+    })
+}
+
+/// JSON output for a plain `but pull`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PullResult {
+    /// One of `up_to_date`, `worktree_conflicts`, `updating` or `error`.
+    pub status: String,
+    pub upstream_url: Option<String>,
+    pub upstream_commits_found: usize,
+    pub recent_commits: Vec<CommitInfo>,
+    pub branches_to_update: Vec<BranchUpdateInfo>,
+    pub integrated_branches: Vec<String>,
+    pub conflicts: Vec<ConflictInfo>,
+    pub summary: PullSummary,
+    pub undo_command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommitInfo {
+    pub id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BranchUpdateInfo {
+    pub name: String,
+    pub status: String,
+    pub commit_count: usize,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConflictInfo {
+    pub branch: String,
+    pub files: Vec<String>,
+    pub upstream_commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PullSummary {
+    pub branches_updated: usize,
+    pub branches_conflicted: usize,
+    pub branches_integrated: usize,
+    pub branches_unchanged: usize,
 }
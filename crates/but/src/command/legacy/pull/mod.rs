@@ -1,4 +1,5 @@
-mod json;
+mod auto;
+pub(crate) mod json;
 
 use but_ctx::Context;
 use colored::Colorize;
@@ -8,67 +9,26 @@ use gitbutler_branch_actions::upstream_integration::{
     StackStatuses::{UpToDate, UpdatesRequired},
     TreeStatus,
 };
-use json::{BaseBranchInfo, BranchStatusInfo, PullCheckOutput, UpstreamCommit, UpstreamInfo};
-use serde::{Deserialize, Serialize};
+use json::{
+    BaseBranchInfo, BranchStatusInfo, BranchUpdateInfo, CommitConflictInfo, CommitInfo,
+    ConflictInfo, PullCheckOutput, PullOutput, PullResult, PullSummary, UpstreamCommit,
+    UpstreamInfo,
+};
 use std::collections::HashMap;
 use std::fmt::Write;
 
 use crate::utils::OutputChannel;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PullResult {
-    status: String,
-    upstream_url: Option<String>,
-    upstream_commits_found: usize,
-    recent_commits: Vec<CommitInfo>,
-    branches_to_update: Vec<BranchUpdateInfo>,
-    integrated_branches: Vec<String>,
-    conflicts: Vec<ConflictInfo>,
-    summary: PullSummary,
-    undo_command: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct CommitInfo {
-    id: String,
-    message: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct BranchUpdateInfo {
-    name: String,
-    status: String,
-    commit_count: usize,
-    conflicts: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ConflictInfo {
-    branch: String,
-    files: Vec<String>,
-    upstream_commit: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PullSummary {
-    branches_updated: usize,
-    branches_conflicted: usize,
-    branches_integrated: usize,
-    branches_unchanged: usize,
-}
-
 pub async fn handle(
     ctx: &Context,
     out: &mut OutputChannel,
     check_only: bool,
+    auto: bool,
 ) -> anyhow::Result<()> {
     if check_only {
         handle_check(ctx, out).await
+    } else if auto {
+        auto::handle(ctx, out).await
     } else {
         handle_pull(ctx, out).await
     }
@@ -117,6 +77,19 @@ async fn handle_check(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<
                                 name: bs.name.clone(),
                                 status: status_str.to_string(),
                                 rebasable,
+                                conflicts: bs
+                                    .conflicts
+                                    .iter()
+                                    .map(|conflict| CommitConflictInfo {
+                                        commit_id: conflict.commit_id.to_string(),
+                                        subject: conflict.subject.clone(),
+                                        paths: conflict
+                                            .paths
+                                            .iter()
+                                            .map(|path| path.display().to_string())
+                                            .collect(),
+                                    })
+                                    .collect(),
                             }
                         })
                     })
@@ -148,7 +121,7 @@ async fn handle_check(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<
             up_to_date,
             has_worktree_conflicts,
         };
-        out.write_value(output)?;
+        out.write_value(PullOutput::Check(output))?;
     } else if let Some(out) = out.for_human() {
         writeln!(progress, "{}", "Checking base branch status...".bold())?;
         writeln!(
@@ -230,6 +203,21 @@ async fn handle_check(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<
                                 Empty => "[empty]".dimmed(),
                             };
                             writeln!(out, "  {} {}", status_text, bs.name)?;
+                            for conflict in &bs.conflicts {
+                                let paths = conflict
+                                    .paths
+                                    .iter()
+                                    .map(|path| path.display().to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                writeln!(
+                                    out,
+                                    "      commit {} {} will conflict in {}",
+                                    conflict.commit_id.to_hex_with_len(7).to_string().yellow(),
+                                    format!("({})", conflict.subject).dimmed(),
+                                    paths.red()
+                                )?;
+                            }
                         }
                     }
                 }
@@ -347,7 +335,7 @@ async fn handle_pull(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<(
                 writeln!(out, "\n{}", "Everything is up to date".green())?;
             }
             if let Some(out) = out.for_json() {
-                out.write_value(&pull_result)?;
+                out.write_value(PullOutput::Pull(pull_result.clone()))?;
             }
             None
         }
@@ -370,7 +358,7 @@ async fn handle_pull(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<(
                     )?;
                 }
                 if let Some(out) = out.for_json() {
-                    out.write_value(&pull_result)?;
+                    out.write_value(PullOutput::Pull(pull_result.clone()))?;
                 }
                 None
             } else {
@@ -682,7 +670,7 @@ async fn handle_pull(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<(
 
                 // Output JSON result
                 if let Some(out) = out.for_json() {
-                    out.write_value(&pull_result)?;
+                    out.write_value(PullOutput::Pull(pull_result.clone()))?;
                 }
             }
             Err(e) => {
@@ -691,7 +679,7 @@ async fn handle_pull(ctx: &Context, out: &mut OutputChannel) -> anyhow::Result<(
                     writeln!(out, "\n{} {}", "Error during integration:".red(), e)?;
                 }
                 if let Some(out) = out.for_json() {
-                    out.write_value(&pull_result)?;
+                    out.write_value(PullOutput::Pull(pull_result.clone()))?;
                 }
                 return Err(e);
             }
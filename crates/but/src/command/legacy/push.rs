@@ -4,8 +4,12 @@ use but_core::{RepositoryExt, ref_metadata::StackId};
 use but_ctx::Context;
 use cli_prompts::DisplayPrompt;
 use colored::Colorize;
-use gitbutler_branch_actions::internal::PushResult;
+use gitbutler_branch_actions::{
+    internal::PushResult,
+    stack::{NonAtomicPushReason, StacksPushResult},
+};
 use gitbutler_project::Project;
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::fmt::Write;
 
@@ -23,17 +27,57 @@ enum BranchSelection {
     All,
 }
 
+/// JSON output for `but push`, whose shape depends on what was pushed.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum PushOutput {
+    /// The output of `but push --dry-run`.
+    DryRun(DryRunResult),
+    /// The output of pushing a single branch.
+    Branch(BranchPushOutput),
+    /// The output of pushing all branches with unpushed commits one after another.
+    Batch(BatchPushResult),
+    /// The output of `but push --stack` and `but push --all`.
+    Stacks(StacksPushOutput),
+}
+
+/// The outcome of pushing a single branch.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BranchPushOutput {
+    /// The name of the remote to which the branches were pushed.
+    remote: String,
+    /// The pushed branches along with the remote refnames they were pushed to.
+    branch_to_remote: Vec<(String, String)>,
+    /// The pushed branches along with the commits they pointed to on the remote before and after the push.
+    branch_sha_updates: Vec<(String, String, String)>,
+}
+
+impl From<&PushResult> for BranchPushOutput {
+    fn from(result: &PushResult) -> Self {
+        BranchPushOutput {
+            remote: result.remote.clone(),
+            branch_to_remote: result
+                .branch_to_remote
+                .iter()
+                .map(|(branch, refname)| (branch.clone(), refname.to_string()))
+                .collect(),
+            branch_sha_updates: result.branch_sha_updates.clone(),
+        }
+    }
+}
+
 /// Batch push result for JSON output
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-struct BatchPushResult {
+pub(crate) struct BatchPushResult {
     /// Successfully pushed branches
-    pushed: Vec<PushResult>,
+    pushed: Vec<BranchPushOutput>,
     /// Failed branches with error messages
     failed: Vec<FailedBranch>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct FailedBranch {
     branch_name: String,
@@ -61,6 +105,29 @@ pub fn handle(
         return handle_dry_run(ctx, project_id, &project_gb_dir, &args.branch_id, out);
     }
 
+    if args.stack || args.all {
+        let stack_ids = if args.all {
+            but_api::legacy::workspace::stacks(
+                ctx.legacy_project.id,
+                Some(but_workspace::legacy::StacksFilter::InWorkspace),
+            )?
+            .into_iter()
+            .filter_map(|stack| stack.id)
+            .collect()
+        } else {
+            let branch_id = args
+                .branch_id
+                .as_deref()
+                .expect("clap requires a branch for --stack");
+            let branch_name = resolve_branch_name(ctx, &id_map, branch_id)?;
+            vec![find_stack_id_by_branch_name(
+                &ctx.legacy_project,
+                &branch_name,
+            )?]
+        };
+        return push_stacks(ctx, stack_ids, &args, gerrit_mode, out);
+    }
+
     // If no branch_id is provided, show all branches and prompt or push all
     let branch_selection = if let Some(ref branch_id) = args.branch_id {
         // Resolve branch_id to actual branch name
@@ -86,8 +153,63 @@ pub fn handle(
     }
 }
 
+/// The outcome of pushing all branches of one or more stacks at once.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StacksPushOutput {
+    /// The name of the remote to which the branches were pushed.
+    remote: String,
+    /// `true` if all branches were pushed in a single atomic push.
+    atomic: bool,
+    /// Why the branches weren't pushed atomically, one of `gitExecutableNotUsed` or `remoteUnsupported`.
+    #[schemars(with = "Option<String>")]
+    non_atomic_reason: Option<NonAtomicPushReason>,
+    /// All branches that were pushed, or attempted to be pushed, in stack order.
+    branches: Vec<StackBranchPushOutput>,
+}
+
+/// The outcome of pushing one branch of a stack.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct StackBranchPushOutput {
+    /// The stack the branch belongs to.
+    stack_id: String,
+    /// The name of the pushed branch.
+    branch_name: String,
+    /// The remote tracking branch that was updated.
+    remote_refname: String,
+    /// The commit the remote branch pointed to before the push, or the null hash if it didn't exist.
+    before_sha: String,
+    /// The commit that was pushed.
+    after_sha: String,
+    /// The reason the push failed, which is only set if the branches weren't pushed atomically.
+    error: Option<String>,
+}
+
+impl From<&StacksPushResult> for StacksPushOutput {
+    fn from(result: &StacksPushResult) -> Self {
+        StacksPushOutput {
+            remote: result.remote.clone(),
+            atomic: result.atomic,
+            non_atomic_reason: result.non_atomic_reason,
+            branches: result
+                .branches
+                .iter()
+                .map(|branch| StackBranchPushOutput {
+                    stack_id: branch.stack_id.to_string(),
+                    branch_name: branch.branch_name.clone(),
+                    remote_refname: branch.remote_refname.to_string(),
+                    before_sha: branch.before_sha.clone(),
+                    after_sha: branch.after_sha.clone(),
+                    error: branch.error.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Information about what would be pushed for a branch
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DryRunBranchInfo {
     /// The branch name
@@ -115,7 +237,7 @@ struct DryRunBranchInfo {
     stacked_on: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DryRunCommit {
     /// Short SHA
@@ -126,7 +248,7 @@ struct DryRunCommit {
     message: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct DryRunUpstreamCommit {
     /// Short SHA
@@ -138,9 +260,9 @@ struct DryRunUpstreamCommit {
 }
 
 /// Batch dry-run result for JSON output
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-struct DryRunResult {
+pub(crate) struct DryRunResult {
     /// Branches that would be pushed
     branches: Vec<DryRunBranchInfo>,
 }
@@ -185,7 +307,7 @@ fn handle_dry_run(
 
     if branches_to_show.is_empty() {
         if let Some(out) = out.for_json() {
-            out.write_value(&DryRunResult { branches: vec![] })?;
+            out.write_value(PushOutput::DryRun(DryRunResult { branches: vec![] }))?;
         }
 
         if out.for_human().is_some() {
@@ -333,9 +455,9 @@ fn handle_dry_run(
 
     // Output JSON if requested
     if let Some(out) = out.for_json() {
-        out.write_value(&DryRunResult {
+        out.write_value(PushOutput::DryRun(DryRunResult {
             branches: dry_run_infos.clone(),
-        })?;
+        }))?;
     }
 
     // Output human-readable format
@@ -606,7 +728,7 @@ fn push_single_branch(
     let mut progress = out.progress_channel();
 
     if let Some(out) = out.for_json() {
-        out.write_value(&result)?;
+        out.write_value(PushOutput::Branch((&result).into()))?;
     }
 
     if out.for_human().is_some() {
@@ -691,11 +813,10 @@ fn push_all_branches(
     if branches_to_push.is_empty() {
         // Output empty result for JSON
         if let Some(out) = out.for_json() {
-            let batch_result = BatchPushResult {
+            out.write_value(PushOutput::Batch(BatchPushResult {
                 pushed: vec![],
                 failed: vec![],
-            };
-            out.write_value(&batch_result)?;
+            }))?;
         }
 
         if out.for_human().is_some() {
@@ -751,11 +872,10 @@ fn push_all_branches(
 
     // Output JSON if requested
     if let Some(out) = out.for_json() {
-        let batch_result = BatchPushResult {
-            pushed: pushed_results.clone(),
+        out.write_value(PushOutput::Batch(BatchPushResult {
+            pushed: pushed_results.iter().map(Into::into).collect(),
             failed: failed_branches.clone(),
-        };
-        out.write_value(&batch_result)?;
+        }))?;
     }
 
     if out.for_human().is_some() {
@@ -828,6 +948,133 @@ fn push_all_branches(
     Ok(())
 }
 
+fn push_stacks(
+    ctx: &Context,
+    stack_ids: Vec<StackId>,
+    args: &Command,
+    gerrit_mode: bool,
+    out: &mut OutputChannel,
+) -> anyhow::Result<()> {
+    if gerrit_mode {
+        anyhow::bail!(
+            "--stack and --all can't be used in Gerrit mode, push each branch individually instead"
+        );
+    }
+    // Gerrit flags can't be used outside of Gerrit mode, so this rejects all of them.
+    get_gerrit_flags(args, "", gerrit_mode)?;
+    let result = but_api::legacy::stack::push_stacks(
+        ctx.legacy_project.id,
+        stack_ids,
+        args.with_force,
+        args.skip_force_push_protection,
+        args.run_hooks,
+    )?;
+
+    let num_failed = result
+        .branches
+        .iter()
+        .filter(|branch| branch.error.is_some())
+        .count();
+    if let Some(out) = out.for_json() {
+        out.write_value(PushOutput::Stacks((&result).into()))?;
+    } else if out.for_human().is_some() {
+        print_stacks_push_result(&result, num_failed, out)?;
+    }
+    if num_failed > 0 {
+        anyhow::bail!(
+            "Failed to push {num_failed} of {} branches",
+            result.branches.len()
+        );
+    }
+    Ok(())
+}
+
+fn print_stacks_push_result(
+    result: &StacksPushResult,
+    num_failed: usize,
+    out: &OutputChannel,
+) -> anyhow::Result<()> {
+    let mut progress = out.progress_channel();
+    if result.branches.is_empty() {
+        writeln!(
+            progress,
+            "{}",
+            "No branches have unpushed commits.".dimmed()
+        )?;
+        return Ok(());
+    }
+
+    writeln!(progress)?;
+    for branch in &result.branches {
+        let before_str = if branch.before_sha == "0000000000000000000000000000000000000000" {
+            "(new branch)".to_string()
+        } else {
+            branch.before_sha.chars().take(7).collect()
+        };
+        let after_str: String = branch.after_sha.chars().take(7).collect();
+        let remote_ref = format!("{}/{}", result.remote, branch.branch_name);
+        match &branch.error {
+            None => writeln!(
+                progress,
+                "  {} {} -> {} ({} -> {})",
+                "✓".green(),
+                branch.branch_name.cyan(),
+                remote_ref.dimmed(),
+                before_str.dimmed(),
+                after_str.green()
+            )?,
+            Some(err) => writeln!(
+                progress,
+                "  {} {} -> {} {}",
+                "✗".red(),
+                branch.branch_name.red(),
+                remote_ref.dimmed(),
+                err.dimmed()
+            )?,
+        }
+    }
+    writeln!(progress)?;
+
+    let non_atomic_reason = result
+        .non_atomic_reason
+        .map(|reason| reason.to_string())
+        .unwrap_or_default();
+    if num_failed > 0 {
+        writeln!(
+            progress,
+            "{} Failed to push {} of {} branches, which were pushed one by one as {non_atomic_reason}",
+            "✗".red().bold(),
+            num_failed.to_string().red().bold(),
+            result.branches.len()
+        )?;
+    } else if result.atomic {
+        writeln!(
+            progress,
+            "{} Pushed {} {} atomically",
+            "✓".green().bold(),
+            result.branches.len().to_string().yellow().bold(),
+            if result.branches.len() == 1 {
+                "branch"
+            } else {
+                "branches"
+            }
+        )?;
+    } else {
+        writeln!(
+            progress,
+            "{} Pushed {} {} one by one as {non_atomic_reason}",
+            "✓".green().bold(),
+            result.branches.len().to_string().yellow().bold(),
+            if result.branches.len() == 1 {
+                "branch"
+            } else {
+                "branches"
+            }
+        )?;
+    }
+    Ok(())
+}
+
 fn handle_no_branch_specified(
     ctx: &Context,
     project: &Project,
@@ -0,0 +1,105 @@
+use anyhow::{Context as _, Result, bail};
+use bstr::ByteSlice;
+use but_ctx::Context;
+use but_rebase::graph_rebase::todo::{TodoItem, TodoList};
+use gitbutler_branch_actions::interactive_rebase;
+use gitbutler_stack::StackId;
+use gix::prelude::ObjectIdExt;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{tui, utils::OutputChannel};
+
+/// The JSON output of `but rebase`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RebaseOutput {
+    /// The name of the rebased branch.
+    branch: String,
+    /// The commit the branch points to after the rebase.
+    new_head: String,
+}
+
+pub(crate) fn interactive(
+    ctx: &mut Context,
+    out: &mut OutputChannel,
+    branch_name: &str,
+) -> Result<()> {
+    let stack_id = stack_id_by_branch_name(ctx, branch_name)?;
+    let original = interactive_rebase::todo_list(ctx, stack_id)?;
+    let text = original.render(&*ctx.repo.get()?)?;
+
+    let edited_text = tui::get_text::from_editor("git-rebase-todo", &text)?;
+    let mut edited = TodoList::parse(
+        edited_text
+            .to_str()
+            .context("The todo list must be valid UTF-8")?,
+        &original,
+    )?;
+    if edited == original {
+        if let Some(out) = out.for_human() {
+            writeln!(out, "Nothing to do")?;
+        }
+        return Ok(());
+    }
+
+    for item in &mut edited.items {
+        if let TodoItem::Reword {
+            commit_id,
+            new_message,
+        } = item
+        {
+            *new_message = Some(get_commit_message_from_editor(ctx, *commit_id)?.into());
+        }
+    }
+
+    let new_head =
+        gitbutler_branch_actions::rebase_stack_interactively(ctx, stack_id, &original, &edited)?;
+
+    if let Some(out) = out.for_human() {
+        let repo = ctx.repo.get()?;
+        writeln!(
+            out,
+            "Successfully rebased {branch_name}, now at {}",
+            new_head.attach(&repo).shorten_or_id()
+        )?;
+    } else if let Some(out) = out.for_json() {
+        out.write_value(RebaseOutput {
+            branch: branch_name.to_owned(),
+            new_head: new_head.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn stack_id_by_branch_name(ctx: &Context, branch_name: &str) -> Result<StackId> {
+    let stacks = but_api::legacy::workspace::stacks(
+        ctx.legacy_project.id,
+        Some(but_workspace::legacy::StacksFilter::InWorkspace),
+    )?;
+    stacks
+        .into_iter()
+        .find(|stack| stack.heads.iter().any(|head| head.name == branch_name))
+        .and_then(|stack| stack.id)
+        .with_context(|| format!("Branch '{branch_name}' not found in any applied stack"))
+}
+
+fn get_commit_message_from_editor(ctx: &Context, commit_id: gix::ObjectId) -> Result<String> {
+    let repo = ctx.repo.get()?;
+    let commit = repo.find_commit(commit_id)?;
+    let mut template = commit.message_raw()?.to_str_lossy().into_owned();
+    if !template.ends_with('\n') {
+        template.push('\n');
+    }
+    template.push_str(&format!(
+        "\n# Please enter the new commit message for {}. Lines starting\n",
+        commit_id.attach(&repo).shorten_or_id()
+    ));
+    template.push_str("# with '#' will be ignored, and an empty message aborts the rebase.\n");
+
+    let message = tui::get_text::from_editor_no_comments("commit_msg", &template)?.to_string();
+    if message.trim().is_empty() {
+        bail!("Aborting due to empty commit message");
+    }
+    Ok(message)
+}
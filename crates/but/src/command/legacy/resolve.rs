@@ -10,10 +10,96 @@ use but_ctx::Context;
 use colored::Colorize;
 use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_operating_modes::OperatingMode;
+use schemars::JsonSchema;
+use serde::Serialize;
 use std::fmt::Write;
 
 use crate::{IdMap, args::resolve::Subcommands, id::CliId, utils::OutputChannel};
 
+/// The JSON output of `but resolve`, which depends on the subcommand and the resolution mode.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum ResolveOutput {
+    /// The state of the files of the commit that is being resolved, with `but resolve status`.
+    Status(ResolveStatusOutput),
+    /// The commits that became conflicted while finishing the resolution, with `but resolve finish`.
+    NewConflicts(NewConflictsOutput),
+    /// The conflicted commits in the workspace, with `but resolve` outside of resolution mode.
+    ConflictedCommits(ConflictedCommitsOutput),
+    /// How to resolve conflicts, if there is nothing to resolve.
+    Workflow(WorkflowOutput),
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ResolveStatusOutput {
+    /// The paths of the files that still contain conflict markers.
+    conflicted_files: Vec<String>,
+    /// The paths of the files that don't contain conflict markers anymore.
+    resolved_files: Vec<String>,
+    conflicted_count: usize,
+    resolved_count: usize,
+    all_resolved: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct NewConflictsOutput {
+    newly_conflicted_commits: Vec<ConflictedCommitOutput>,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ConflictedCommitsOutput {
+    /// The conflicted commits by the name of the branch that contains them.
+    conflicted_commits_by_branch: BTreeMap<String, Vec<ConflictedCommitOutput>>,
+    total_conflicted_commits: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ConflictedCommitOutput {
+    commit_id: String,
+    commit_short_id: String,
+    /// The first line of the commit message.
+    commit_message: String,
+}
+
+impl ConflictedCommitOutput {
+    fn new(commit: &ConflictedCommit) -> Self {
+        ConflictedCommitOutput {
+            commit_id: commit.commit_oid.to_string(),
+            commit_short_id: commit.commit_short_id.clone(),
+            commit_message: commit.commit_message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct WorkflowOutput {
+    /// The steps to resolve a conflicted commit, in order.
+    workflow: Vec<WorkflowStep>,
+    other_commands: OtherCommands,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct WorkflowStep {
+    /// The 1-based number of the step.
+    step: usize,
+    description: &'static str,
+    /// The command to run, if the step is done with a command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'static str>,
+    /// How to do the step, if it isn't done with a command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct OtherCommands {
+    /// The command to cancel the resolution.
+    cancel: &'static str,
+    /// The command to show the state of the workspace.
+    view_status: &'static str,
+}
+
 pub(crate) fn handle(
     ctx: &mut Context,
     out: &mut OutputChannel,
@@ -306,12 +392,12 @@ fn show_conflicted_files(ctx: &mut Context, out: &mut OutputChannel) -> Result<b
             .iter()
             .map(|change| change.path.to_str_lossy().to_string())
             .collect();
-        out.write_value(serde_json::json!({
-            "conflicted_files": conflicted_list,
-            "resolved_files": resolved_list,
-            "conflicted_count": conflicted_list.len(),
-            "resolved_count": resolved_list.len(),
-            "all_resolved": all_resolved
+        out.write_value(ResolveOutput::Status(ResolveStatusOutput {
+            conflicted_count: conflicted_list.len(),
+            resolved_count: resolved_list.len(),
+            conflicted_files: conflicted_list,
+            resolved_files: resolved_list,
+            all_resolved,
         }))?;
     }
 
@@ -448,20 +534,13 @@ fn check_for_new_conflicts_after_rebase(
                 "but resolve <commit>".green()
             )?;
         } else if let Some(json_out) = out.for_json() {
-            let newly_conflicted_json: Vec<serde_json::Value> = newly_conflicted
-                .iter()
-                .map(|c| {
-                    serde_json::json!({
-                        "commit_id": c.commit_oid.to_string(),
-                        "commit_short_id": c.commit_short_id,
-                        "commit_message": c.commit_message,
-                    })
-                })
-                .collect();
-
-            json_out.write_value(serde_json::json!({
-                "newly_conflicted_commits": newly_conflicted_json,
-                "count": newly_conflicted.len(),
+            json_out.write_value(ResolveOutput::NewConflicts(NewConflictsOutput {
+                newly_conflicted_commits: newly_conflicted
+                    .iter()
+                    .copied()
+                    .map(ConflictedCommitOutput::new)
+                    .collect(),
+                count: newly_conflicted.len(),
             }))?;
         }
     }
@@ -585,27 +664,17 @@ fn check_and_prompt_for_conflicts(ctx: &mut Context, out: &mut OutputChannel) ->
             return enter_resolution(ctx, out, &commit_id_to_resolve);
         }
     } else if let Some(json_out) = out.for_json() {
-        // JSON output mode
-        let mut json_conflicts = serde_json::Map::new();
-
-        for (branch_name, commits) in &conflicts_by_branch {
-            let commits_array: Vec<serde_json::Value> = commits
+        json_out.write_value(ResolveOutput::ConflictedCommits(ConflictedCommitsOutput {
+            conflicted_commits_by_branch: conflicts_by_branch
                 .iter()
-                .map(|c| {
-                    serde_json::json!({
-                        "commit_id": c.commit_oid.to_string(),
-                        "commit_short_id": c.commit_short_id,
-                        "commit_message": c.commit_message,
-                    })
+                .map(|(branch_name, commits)| {
+                    (
+                        branch_name.clone(),
+                        commits.iter().map(ConflictedCommitOutput::new).collect(),
+                    )
                 })
-                .collect();
-
-            json_conflicts.insert(branch_name.clone(), serde_json::Value::Array(commits_array));
-        }
-
-        json_out.write_value(serde_json::json!({
-            "conflicted_commits_by_branch": json_conflicts,
-            "total_conflicted_commits": conflicts_by_branch.values().map(|v| v.len()).sum::<usize>(),
+                .collect(),
+            total_conflicted_commits: conflicts_by_branch.values().map(|v| v.len()).sum(),
         }))?;
     }
 
@@ -674,33 +743,43 @@ fn show_workflow_help(out: &mut OutputChannel) -> Result<()> {
         )?;
         writeln!(out, "  {} (finalize)", "but resolve finish".green())?;
     } else if let Some(out) = out.for_json() {
-        out.write_value(serde_json::json!({
-            "workflow": [
-                {
-                    "step": 1,
-                    "description": "Enter resolution mode for a conflicted commit",
-                    "command": "but resolve <commit>"
-                },
-                {
-                    "step": 2,
-                    "description": "Resolve conflicts in the conflicted files",
-                    "details": "Edit the files to remove conflict markers (<<<<<<<, =======, >>>>>>>)"
-                },
-                {
-                    "step": 3,
-                    "description": "Check which files are still conflicted",
-                    "command": "but resolve status"
-                },
-                {
-                    "step": 4,
-                    "description": "Finalize the resolution",
-                    "command": "but resolve finish"
-                }
+        let step = |step, description, command, details| WorkflowStep {
+            step,
+            description,
+            command,
+            details,
+        };
+        out.write_value(ResolveOutput::Workflow(WorkflowOutput {
+            workflow: vec![
+                step(
+                    1,
+                    "Enter resolution mode for a conflicted commit",
+                    Some("but resolve <commit>"),
+                    None,
+                ),
+                step(
+                    2,
+                    "Resolve conflicts in the conflicted files",
+                    None,
+                    Some("Edit the files to remove conflict markers (<<<<<<<, =======, >>>>>>>)"),
+                ),
+                step(
+                    3,
+                    "Check which files are still conflicted",
+                    Some("but resolve status"),
+                    None,
+                ),
+                step(
+                    4,
+                    "Finalize the resolution",
+                    Some("but resolve finish"),
+                    None,
+                ),
             ],
-            "other_commands": {
-                "cancel": "but resolve cancel",
-                "view_status": "but status"
-            }
+            other_commands: OtherCommands {
+                cancel: "but resolve cancel",
+                view_status: "but status",
+            },
         }))?;
     }
 
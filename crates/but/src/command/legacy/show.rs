@@ -2,12 +2,60 @@ use anyhow::{Result, bail};
 use bstr::ByteSlice;
 use but_ctx::Context;
 use colored::Colorize;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::{
     CLI_DATE, CliId, IdMap,
     utils::{OutputChannel, time::format_relative_time},
 };
 
+/// JSON output for `but show`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ShowOutput {
+    /// The full hash of the commit.
+    commit: String,
+    /// The change-id of the commit, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_id: Option<String>,
+    /// Who wrote the commit.
+    author: SignatureOutput,
+    /// Who committed the commit.
+    committer: SignatureOutput,
+    /// When the commit was made.
+    date: String,
+    /// The full commit message.
+    message: String,
+    /// The files changed by the commit.
+    files: Vec<ChangedFileOutput>,
+}
+
+/// The identity of an author or committer.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct SignatureOutput {
+    name: String,
+    email: String,
+}
+
+impl From<gix::actor::SignatureRef<'_>> for SignatureOutput {
+    fn from(signature: gix::actor::SignatureRef<'_>) -> Self {
+        SignatureOutput {
+            name: signature.name.to_str_lossy().into_owned(),
+            email: signature.email.to_str_lossy().into_owned(),
+        }
+    }
+}
+
+/// A file changed by a commit.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ChangedFileOutput {
+    /// The path of the file, relative to the root of the repository.
+    path: String,
+    /// One of `added`, `deleted`, `modified` or `renamed`.
+    status: &'static str,
+}
+
 pub(crate) fn show_commit(
     ctx: &mut Context,
     out: &mut OutputChannel,
@@ -150,47 +198,29 @@ pub(crate) fn show_commit(
         }
     } else if let Some(out) = out.for_json() {
         // JSON output
-        let changes = tree_changes.into_tree_changes();
-        let mut files = Vec::new();
-        for change in &changes {
-            let status = match &change.status {
-                but_core::TreeStatus::Addition { .. } => "added",
-                but_core::TreeStatus::Deletion { .. } => "deleted",
-                but_core::TreeStatus::Modification { .. } => "modified",
-                but_core::TreeStatus::Rename { .. } => "renamed",
-            };
-
-            files.push(serde_json::json!({
-                "path": change.path.to_str_lossy(),
-                "status": status
-            }));
-        }
-
-        let author_sig = decoded.author()?;
-        let committer_sig = decoded.committer()?;
-        let date_str = raw_commit.time()?.format(CLI_DATE)?;
-
-        let mut json_output = serde_json::json!({
-            "commit": commit_id.to_string(),
-            "author": {
-                "name": author_sig.name.to_str_lossy(),
-                "email": author_sig.email.to_str_lossy()
-            },
-            "committer": {
-                "name": committer_sig.name.to_str_lossy(),
-                "email": committer_sig.email.to_str_lossy()
-            },
-            "date": date_str,
-            "message": decoded.message.to_str_lossy(),
-            "files": files
-        });
-
-        // Add change-id if present
-        if let Some(ref change_id) = change_id {
-            json_output["changeId"] = serde_json::json!(change_id);
-        }
-
-        out.write_value(json_output)?;
+        let files = tree_changes
+            .into_tree_changes()
+            .iter()
+            .map(|change| ChangedFileOutput {
+                path: change.path.to_str_lossy().into_owned(),
+                status: match &change.status {
+                    but_core::TreeStatus::Addition { .. } => "added",
+                    but_core::TreeStatus::Deletion { .. } => "deleted",
+                    but_core::TreeStatus::Modification { .. } => "modified",
+                    but_core::TreeStatus::Rename { .. } => "renamed",
+                },
+            })
+            .collect();
+
+        out.write_value(ShowOutput {
+            commit: commit_id.to_string(),
+            change_id,
+            author: decoded.author()?.into(),
+            committer: decoded.committer()?.into(),
+            date: raw_commit.time()?.format(CLI_DATE)?,
+            message: decoded.message.to_str_lossy().into_owned(),
+            files,
+        })?;
     }
 
     Ok(())
@@ -0,0 +1,199 @@
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, Result, bail};
+use bstr::ByteSlice;
+use but_api::{commit::ui::SplitPiece, diff::ComputeLineStats};
+use but_core::{DiffSpec, HunkHeader, TreeStatus, UnifiedPatch};
+use but_ctx::Context;
+use cli_prompts::DisplayPrompt;
+use colored::Colorize;
+use gix::prelude::ObjectIdExt;
+
+use crate::{CliId, IdMap, command::legacy::diff::display::fmt_hunk, tui, utils::OutputChannel};
+
+/// A change of the commit to split that can be selected on its own.
+struct Selectable {
+    label: String,
+    diff: String,
+    spec: DiffSpec,
+}
+
+pub(crate) fn handle(
+    ctx: &mut Context,
+    out: &mut OutputChannel,
+    target: &str,
+    messages: Vec<String>,
+) -> Result<()> {
+    if messages.len() > 2 {
+        bail!(
+            "A commit is split into two commits, but {} messages were provided",
+            messages.len()
+        );
+    }
+    let commit_id = resolve_commit(ctx, target)?;
+    let selectables = selectable_changes(ctx, commit_id)?;
+    if selectables.len() < 2 {
+        bail!("Commit {commit_id} has only a single change and can't be split");
+    }
+
+    let mut inout = out
+        .prepare_for_terminal_input()
+        .context("Human input required to select changes - run this in a terminal")?;
+    for selectable in &selectables {
+        writeln!(inout, "{}", selectable.label.bold())?;
+        write!(inout, "{}", selectable.diff)?;
+    }
+    let selected = cli_prompts::prompts::Multiselect::new_transformed(
+        "Which changes should go into the first commit? The rest goes into the second one.",
+        selectables.iter().map(|s| s.label.clone()),
+        |label| label.clone(),
+    )
+    .display()
+    .map_err(|_| anyhow::anyhow!("Could not determine which changes to split off"))?;
+    drop(inout);
+
+    let (first, second): (Vec<_>, Vec<_>) = selectables
+        .into_iter()
+        .partition(|s| selected.contains(&s.label));
+    if first.is_empty() || second.is_empty() {
+        bail!("Select at least one change, but not all of them");
+    }
+
+    let original_message = {
+        let repo = ctx.repo.get()?;
+        repo.find_commit(commit_id)?
+            .message_raw()?
+            .to_str_lossy()
+            .into_owned()
+    };
+    let mut messages = messages.into_iter();
+    let pieces = [first, second]
+        .into_iter()
+        .map(|changes| -> Result<_> {
+            let message = match messages.next() {
+                Some(message) if message.trim().is_empty() => {
+                    bail!("Aborting due to empty commit message")
+                }
+                Some(message) => message.trim().to_owned(),
+                None => get_commit_message_from_editor(&original_message, &changes)?,
+            };
+            Ok(SplitPiece {
+                message,
+                changes: changes.into_iter().map(|s| s.spec).collect(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let outcome = but_api::commit::commit_split(ctx, commit_id.into(), pieces)?;
+
+    if let Some(out) = out.for_human() {
+        let repo = ctx.repo.get()?;
+        let new_commits = outcome
+            .new_commits
+            .iter()
+            .map(|id| {
+                gix::ObjectId::from(*id)
+                    .attach(&repo)
+                    .shorten_or_id()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "Split {} into {}",
+            commit_id.attach(&repo).shorten_or_id(),
+            new_commits.join(" and ")
+        )?;
+    } else if let Some(out) = out.for_json() {
+        out.write_value(&outcome)?;
+    }
+    Ok(())
+}
+
+fn resolve_commit(ctx: &mut Context, target: &str) -> Result<gix::ObjectId> {
+    let mut id_map = IdMap::new_from_context(ctx, None)?;
+    id_map.add_committed_file_info_from_context(ctx)?;
+
+    let cli_ids = id_map.resolve_entity_to_ids(target)?;
+    match cli_ids.as_slice() {
+        [] => bail!("ID '{}' not found", target),
+        [CliId::Commit { commit_id, .. }] => Ok(*commit_id),
+        [cli_id] => bail!(
+            "Target must be a commit ID, not {}",
+            cli_id.kind_for_humans()
+        ),
+        _ => bail!(
+            "Target ID '{}' is ambiguous. Found {} matches",
+            target,
+            cli_ids.len()
+        ),
+    }
+}
+
+/// Return each hunk of modified files as its own change, and whole files for everything else
+/// as hunks of additions and deletions can't be split.
+fn selectable_changes(ctx: &Context, commit_id: gix::ObjectId) -> Result<Vec<Selectable>> {
+    let details = but_api::diff::commit_details(ctx, commit_id, ComputeLineStats::No)?;
+    let mut out = Vec::new();
+    for change in details.diff_with_first_parent {
+        let path = change.path.to_str_lossy().into_owned();
+        let patch = but_api::legacy::diff::tree_change_diffs(ctx, change.clone().into())?;
+        let is_addition_or_deletion = matches!(
+            change.status,
+            TreeStatus::Addition { .. } | TreeStatus::Deletion { .. }
+        );
+        match patch {
+            Some(UnifiedPatch::Patch { hunks, .. }) if !is_addition_or_deletion => {
+                for hunk in &hunks {
+                    let header = HunkHeader::from(hunk);
+                    out.push(Selectable {
+                        label: format!(
+                            "{path} @@ -{},{} +{},{} @@",
+                            header.old_start, header.old_lines, header.new_start, header.new_lines
+                        ),
+                        diff: fmt_hunk(hunk),
+                        spec: DiffSpec {
+                            hunk_headers: vec![header],
+                            ..DiffSpec::from(&change)
+                        },
+                    });
+                }
+            }
+            patch => out.push(Selectable {
+                label: path,
+                diff: match patch {
+                    Some(UnifiedPatch::Patch { hunks, .. }) => hunks.iter().map(fmt_hunk).collect(),
+                    _ => String::new(),
+                },
+                spec: DiffSpec::from(&change),
+            }),
+        }
+    }
+    Ok(out)
+}
+
+fn get_commit_message_from_editor(
+    original_message: &str,
+    changes: &[Selectable],
+) -> Result<String> {
+    let mut template = String::new();
+    template.push_str(original_message);
+    if !original_message.is_empty() && !original_message.ends_with('\n') {
+        template.push('\n');
+    }
+    template
+        .push_str("\n# Please enter the commit message for the split-off commit. Lines starting\n");
+    template.push_str("# with '#' will be ignored, and an empty message aborts the split.\n");
+    template.push_str("#\n");
+    template.push_str("# Changes in this commit:\n");
+    for change in changes {
+        template.push_str(&format!("#\t{}\n", change.label));
+    }
+    template.push_str("#\n");
+
+    let message = tui::get_text::from_editor_no_comments("commit_msg", &template)?.to_string();
+    if message.is_empty() {
+        bail!("Aborting due to empty commit message");
+    }
+    Ok(message)
+}
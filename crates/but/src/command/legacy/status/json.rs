@@ -13,11 +13,12 @@ use std::collections::BTreeMap;
 
 use but_api::diff::ComputeLineStats;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 
 /// JSON output for the `but status` command
 /// This represents the status of the GitButler "workspace".
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspaceStatus {
     /// Represents uncommitted changes that are not assigned to any stack
@@ -31,7 +32,7 @@ pub(crate) struct WorkspaceStatus {
 }
 
 /// Represents the state of the upstream branch compared to the merge base
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct UpstreamState {
     /// The number of commits the upstream is ahead of the merge base
@@ -62,7 +63,7 @@ impl WorkspaceStatus {
 }
 
 /// Represents a stack of branches applied in the current workspace
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Stack {
     /// A unique ID specific to the current state of the workspace, to be used by other CLI operations (e.g `rub`)
@@ -84,7 +85,7 @@ impl Stack {
 }
 
 /// Represents a branch in the GitButler workspace
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Branch {
     /// A unique ID specific to the current state of the workspace, to be used by other CLI operations (e.g `rub`)
@@ -110,7 +111,7 @@ pub(crate) struct Branch {
 }
 
 /// The aggregated status of CI checks associated with a branch.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Ci {
     /// Titles of CI checks that are currently pending or still running
@@ -125,7 +126,7 @@ pub(crate) struct Ci {
     pub conclusion: CiConclusion,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum CiStatus {
     /// All CI checks have finished running, regardless of whether they passed or failed.
@@ -134,7 +135,7 @@ pub(crate) enum CiStatus {
     InProgress,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum CiConclusion {
     /// At least one required CI check failed or reported an error.
@@ -147,7 +148,7 @@ pub(crate) enum CiConclusion {
 }
 
 /// The merge status of a branch with the upstream branch
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum MergeStatus {
     /// The branch can be cleanly merged or rebased onto the upstream
@@ -164,7 +165,7 @@ pub(crate) enum MergeStatus {
 }
 
 /// The status of a branch with respect to its upstream
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum BranchStatus {
     /// Can push, but there are no changes to be pushed
@@ -180,7 +181,7 @@ pub(crate) enum BranchStatus {
 }
 
 /// A commit that is in the GitButler workspace
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Commit {
     /// A unique ID specific to the current state of the workspace, to be used by other CLI operations (e.g `rub`)
@@ -204,7 +205,7 @@ pub(crate) struct Commit {
 }
 
 /// A change to a file in the repository
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct FileChange {
     /// A unique ID specific to the current state of the workspace, to be used by other CLI operations (e.g `rub`)
@@ -216,7 +217,7 @@ pub(crate) struct FileChange {
 }
 
 /// The type of change that happened to a file
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum ChangeType {
     /// The file was newly added (it was not tracked before)
@@ -31,7 +31,7 @@ pub(crate) mod assignment;
 pub(crate) mod json;
 
 use crate::command::legacy::forge::review;
-use crate::{IdMap, utils::OutputChannel};
+use crate::{IdMap, id::PersistedIds, utils::OutputChannel};
 
 type StackDetail = (Option<StackDetails>, Vec<FileAssignment>);
 type StackEntry = (Option<gitbutler_stack::StackId>, StackDetail);
@@ -63,7 +63,9 @@ struct UpstreamState {
 }
 
 fn show_edit_mode_status(ctx: &mut Context, out: &mut OutputChannel) -> anyhow::Result<()> {
-    // Delegate to the resolve status logic to show actual conflict details
+    // Delegate to the resolve status logic to show actual conflict details,
+    // which is described by the schema of `but resolve`.
+    out.set_kind(crate::args::metrics::CommandName::Resolve);
     crate::command::legacy::resolve::show_resolve_status(ctx, out)
 }
 
@@ -114,7 +116,14 @@ pub(crate) async fn worktree(
     // Store the count of stacks for hint logic later
     let has_branches = !stacks.is_empty();
 
-    let mut id_map = IdMap::new(&head_info.stacks, worktree_changes.assignments.clone())?;
+    // Reuse the IDs shown previously, so IDs the user still has at hand keep referring to the same entity.
+    let persisted = PersistedIds::load(&mut *ctx.db.get_mut()?)?;
+    let mut id_map = IdMap::new_with_persisted_ids(
+        &head_info.stacks,
+        worktree_changes.assignments.clone(),
+        persisted,
+    )?;
+    // Also persists the IDs for use by later invocations.
     id_map.add_committed_file_info_from_context(ctx)?;
 
     let assignments_by_file: BTreeMap<BString, FileAssignment> =
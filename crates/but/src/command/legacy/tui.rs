@@ -0,0 +1,158 @@
+use std::io::IsTerminal;
+
+use anyhow::bail;
+use bstr::ByteSlice;
+use but_core::ref_metadata::StackId;
+use but_ctx::Context;
+
+use crate::{
+    IdMap,
+    args::OutputFormat,
+    command::legacy::{diff, oplog, rub},
+    id::PersistedIds,
+    tui::workspace::{App, Entry, EntryKind, Lane, Operations, Snapshot},
+    utils::OutputChannel,
+};
+
+/// Show the workspace in a full-screen interface until the user quits.
+pub(crate) fn handle(ctx: &mut Context, out: &mut OutputChannel) -> anyhow::Result<()> {
+    if out.for_human().is_none() || !std::io::stdout().is_terminal() {
+        bail!("`but tui` needs an interactive terminal");
+    }
+    let mut app = App::new(Workspace { ctx })?;
+
+    // Diffs are colored when shown, so the escape codes of the CLI would only be in the way.
+    colored::control::set_override(false);
+    let mut terminal = ratatui::init();
+    let res = crate::tui::workspace::run(&mut terminal, &mut app, ratatui::crossterm::event::read);
+    ratatui::restore();
+    colored::control::unset_override();
+    res
+}
+
+/// The workspace of `ctx`, changed exactly like the equivalent `but` commands would.
+struct Workspace<'a> {
+    ctx: &'a mut Context,
+}
+
+impl Workspace<'_> {
+    /// Operations print their results for humans, which would garble the screen.
+    fn quiet_output() -> OutputChannel {
+        OutputChannel::new_without_pager_non_json(OutputFormat::None)
+    }
+}
+
+impl Operations for Workspace<'_> {
+    fn snapshot(&mut self) -> anyhow::Result<Snapshot> {
+        let ctx = &mut *self.ctx;
+        let wt_changes = but_api::legacy::diff::changes_in_worktree(ctx)?;
+        let head_info = {
+            let guard = ctx.shared_worktree_access();
+            let meta = ctx.meta(guard.read_permission())?;
+            but_workspace::head_info(
+                &*ctx.repo.get()?,
+                &meta,
+                but_workspace::ref_info::Options {
+                    expensive_commit_info: false,
+                    ..Default::default()
+                },
+            )?
+        };
+        let persisted = PersistedIds::load(&mut *ctx.db.get_mut()?)?;
+        let id_map = IdMap::new_with_persisted_ids(
+            &head_info.stacks,
+            wt_changes.assignments.clone(),
+            persisted,
+        )?;
+        id_map.persist(ctx)?;
+
+        let mut files: Vec<_> = id_map.uncommitted_files.iter().collect();
+        files.sort_by(|(_, a), (_, b)| a.path().cmp(b.path()));
+        let uncommitted_entries = |stack_id: Option<StackId>| {
+            let mut entries = Vec::new();
+            for (id, file) in files.iter().filter(|(_, file)| file.stack_id() == stack_id) {
+                entries.push(Entry {
+                    id: id.to_string(),
+                    kind: EntryKind::File,
+                    label: file.path().to_str_lossy().into_owned(),
+                });
+                for hunk in &file.hunk_assignments {
+                    let (Some(header), Some(cli_id)) =
+                        (&hunk.hunk_header, id_map.resolve_uncommitted_hunk(hunk))
+                    else {
+                        continue;
+                    };
+                    entries.push(Entry {
+                        id: cli_id.to_short_string(),
+                        kind: EntryKind::Hunk,
+                        label: format!(
+                            "@@ -{},{} +{},{} @@",
+                            header.old_start, header.old_lines, header.new_start, header.new_lines
+                        ),
+                    });
+                }
+            }
+            entries
+        };
+
+        let mut lanes = vec![Lane {
+            id: id_map.unassigned().to_short_string(),
+            title: "Unassigned changes".into(),
+            entries: uncommitted_entries(None),
+        }];
+        for stack in &head_info.stacks {
+            let Some(stack_id) = stack.id else {
+                continue;
+            };
+            let Some(lane_id) = id_map.resolve_stack(stack_id) else {
+                continue;
+            };
+            let mut entries = uncommitted_entries(Some(stack_id));
+            let mut title = None;
+            for segment in &stack.segments {
+                if let Some(ref_info) = &segment.ref_info {
+                    let name = ref_info.ref_name.shorten();
+                    title.get_or_insert_with(|| name.to_string());
+                    entries.push(Entry {
+                        id: id_map.resolve_branch(name).to_short_string(),
+                        kind: EntryKind::Branch,
+                        label: name.to_string(),
+                    });
+                }
+                for commit in &segment.commits {
+                    entries.push(Entry {
+                        id: id_map.resolve_commit(&commit.id).to_short_string(),
+                        kind: EntryKind::Commit,
+                        label: commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .to_str_lossy()
+                            .into_owned(),
+                    });
+                }
+            }
+            lanes.push(Lane {
+                id: lane_id.to_short_string(),
+                title: title.unwrap_or_else(|| "(anonymous)".into()),
+                entries,
+            });
+        }
+        Ok(Snapshot { lanes })
+    }
+
+    fn rub(&mut self, source: &str, target: &str) -> anyhow::Result<()> {
+        rub::handle(self.ctx, &mut Self::quiet_output(), source, target)
+    }
+
+    fn diff(&mut self, id: &str) -> anyhow::Result<String> {
+        let mut out = String::new();
+        diff::handle(self.ctx, &mut out, Some(id))?;
+        Ok(out)
+    }
+
+    fn undo(&mut self) -> anyhow::Result<()> {
+        oplog::undo_last_operation(self.ctx, &mut Self::quiet_output()).map(|_| ())
+    }
+}
@@ -3,9 +3,108 @@ use std::path::PathBuf;
 use anyhow::{Context as _, Result};
 use but_api::legacy::worktree::IntegrationStatus;
 use but_ctx::Context;
-use but_worktrees::WorktreeId;
+use but_worktrees::{Worktree, WorktreeId};
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::{args::worktree::Subcommands, utils::OutputChannel};
+
+/// JSON output for `but worktree`, whose shape depends on the subcommand.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum WorktreeOutput {
+    /// The output of `but worktree new`.
+    New {
+        /// The worktree that was created.
+        created: WorktreeEntry,
+    },
+    /// The output of `but worktree list`.
+    List {
+        /// All worktrees of the project.
+        entries: Vec<WorktreeEntry>,
+    },
+    /// The output of `but worktree integrate --dry`.
+    IntegrationStatus(IntegrationStatusOutput),
+    /// The output of `but worktree integrate`.
+    Integrated {
+        /// Always `success`.
+        status: &'static str,
+    },
+    /// The output of `but worktree destroy`.
+    #[serde(rename_all = "camelCase")]
+    Destroyed {
+        /// The names of the worktrees that were destroyed.
+        destroyed_ids: Vec<String>,
+    },
+}
+
+/// A worktree of the project.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorktreeEntry {
+    /// The name of the worktree, which can be used to refer to it.
+    id: String,
+    /// The path to the worktree.
+    path: String,
+    /// The full name of the reference the worktree was created from.
+    created_from_ref: Option<String>,
+    /// The commit the changes of the worktree are cherry-picked from when integrating it.
+    base: Option<String>,
+}
+
+impl From<&Worktree> for WorktreeEntry {
+    fn from(worktree: &Worktree) -> Self {
+        WorktreeEntry {
+            id: worktree.id.to_string(),
+            path: worktree.path.display().to_string(),
+            created_from_ref: worktree.created_from_ref.as_ref().map(ToString::to_string),
+            base: worktree.base.map(|base| base.to_string()),
+        }
+    }
+}
+
+/// Whether a worktree can be integrated into its target.
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub(crate) enum IntegrationStatusOutput {
+    /// The worktree and the target have no common ancestor.
+    NoMergeBaseFound,
+    /// The worktree has no working tree.
+    WorktreeIsBare,
+    /// Integrating the worktree would conflict the workspace, so it can't be integrated.
+    CausesWorkspaceConflicts,
+    /// The worktree can be integrated.
+    Integratable {
+        /// The cherry-pick of the worktree changes will be conflicted.
+        cherry_pick_conflicts: bool,
+        /// Commits above the integrated changes will be conflicted.
+        commits_above_conflict: bool,
+        /// The uncommitted changes in the main checkout will be conflicted.
+        working_dir_conflicts: bool,
+    },
+}
+
+impl From<&IntegrationStatus> for IntegrationStatusOutput {
+    fn from(status: &IntegrationStatus) -> Self {
+        match *status {
+            IntegrationStatus::NoMergeBaseFound => IntegrationStatusOutput::NoMergeBaseFound,
+            IntegrationStatus::WorktreeIsBare => IntegrationStatusOutput::WorktreeIsBare,
+            IntegrationStatus::CausesWorkspaceConflicts => {
+                IntegrationStatusOutput::CausesWorkspaceConflicts
+            }
+            IntegrationStatus::Integratable {
+                cherry_pick_conflicts,
+                commits_above_conflict,
+                working_dir_conflicts,
+            } => IntegrationStatusOutput::Integratable {
+                cherry_pick_conflicts,
+                commits_above_conflict,
+                working_dir_conflicts,
+            },
+        }
+    }
+}
+
 /// Parse a worktree identifier which can be either:
 /// - A full path to the worktree
 /// - Just the worktree name
@@ -37,7 +136,9 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
             };
             let output = but_api::legacy::worktree::worktree_new(ctx.legacy_project.id, reference)?;
             if let Some(out) = out.for_json() {
-                out.write_value(output)?;
+                out.write_value(WorktreeOutput::New {
+                    created: (&output.created).into(),
+                })?;
             } else if let Some(out) = out.for_human() {
                 writeln!(
                     out,
@@ -53,7 +154,9 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
         Subcommands::List => {
             let output = but_api::legacy::worktree::worktree_list(ctx.legacy_project.id)?;
             if let Some(out) = out.for_json() {
-                out.write_value(output)?;
+                out.write_value(WorktreeOutput::List {
+                    entries: output.entries.iter().map(Into::into).collect(),
+                })?;
             } else if let Some(out) = out.for_human() {
                 if output.entries.is_empty() {
                     writeln!(out, "No worktrees found")?;
@@ -109,7 +212,7 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
                 )?;
 
                 if let Some(out) = out.for_json() {
-                    out.write_value(status)?;
+                    out.write_value(WorktreeOutput::IntegrationStatus((&status).into()))?;
                 } else if let Some(out) = out.for_human() {
                     writeln!(out, "Integration status for worktree: {id}")?;
                     writeln!(out, "Target: {}", target_ref)?;
@@ -159,7 +262,7 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
                 )?;
 
                 if let Some(out) = out.for_json() {
-                    out.write_value(serde_json::json!({"status": "success"}))?;
+                    out.write_value(WorktreeOutput::Integrated { status: "success" })?;
                 } else if let Some(out) = out.for_human() {
                     writeln!(out, "Successfully integrated worktree: {id}")?;
                     writeln!(out, "Target: {}", target_ref)?;
@@ -184,7 +287,13 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
                 )?;
 
                 if let Some(out) = out.for_json() {
-                    out.write_value(output)?;
+                    out.write_value(WorktreeOutput::Destroyed {
+                        destroyed_ids: output
+                            .destroyed_ids
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    })?;
                 } else if let Some(out) = out.for_human() {
                     if output.destroyed_ids.is_empty() {
                         writeln!(out, "No worktrees found for reference: {}", reference)?;
@@ -209,7 +318,13 @@ pub fn handle(cmd: Subcommands, ctx: &Context, out: &mut OutputChannel) -> Resul
                 )?;
 
                 if let Some(out) = out.for_json() {
-                    out.write_value(output)?;
+                    out.write_value(WorktreeOutput::Destroyed {
+                        destroyed_ids: output
+                            .destroyed_ids
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    })?;
                 } else if let Some(out) = out.for_human() {
                     writeln!(out, "Destroyed worktree: {id}")?;
                 }
@@ -0,0 +1,321 @@
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, Result};
+use bstr::{BString, ByteSlice};
+use but_ctx::Context;
+use but_workspace::{
+    branch::Stack,
+    ref_info::{self, LocalCommitRelation},
+    ui::PushStatus,
+};
+use colored::Colorize;
+use schemars::{JsonSchema, Schema};
+use serde::Serialize;
+
+use crate::utils::OutputChannel;
+
+/// The history of the stacks shown by `but log`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct Log {
+    /// The name of the target branch the stacks integrate into, if there is one.
+    target: Option<String>,
+    stacks: Vec<LogStack>,
+    /// `true` if commits were omitted due to the `--limit`.
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LogStack {
+    id: Option<String>,
+    segments: Vec<LogSegment>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LogSegment {
+    /// The short name of the branch, or `None` if the segment is anonymous.
+    name: Option<String>,
+    remote_tracking_branch: Option<String>,
+    /// One of `nothingToPush`, `unpushedCommits`, `unpushedCommitsRequiringForce`,
+    /// `completelyUnpushed` or `integrated`.
+    #[schemars(with = "String")]
+    push_status: PushStatus,
+    /// `true` if this is the segment of the branch passed to `but log`.
+    is_entrypoint: bool,
+    /// The number of commits only present on the remote tracking branch.
+    commits_on_remote: usize,
+    commits: Vec<LogCommit>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LogCommit {
+    id: String,
+    change_id: Option<String>,
+    subject: String,
+    author: String,
+    /// The time the commit was authored, in seconds since the Unix epoch.
+    time: i64,
+    state: CommitState,
+    has_conflicts: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+enum CommitState {
+    /// The commit is only available locally.
+    LocalOnly,
+    /// The commit was pushed as is.
+    Pushed,
+    /// The commit was pushed, but changed locally since then.
+    Modified,
+    /// The commit is part of the target branch.
+    Integrated,
+}
+
+/// The schema of the JSON output of [`show()`].
+pub(crate) fn schema() -> Schema {
+    schemars::schema_for!(Log)
+}
+
+/// Show the history of the workspace, or of the stack containing `branch` if set.
+///
+/// At most `limit` commits are shown if set, and if `paths` isn't empty, only commits
+/// that changed one of the paths, or any path below them, are shown.
+/// Paths are relative to the root of the repository.
+///
+/// The history is obtained from the workspace projection of the commit graph, so the
+/// workspace commit itself never shows up.
+pub fn show(
+    ctx: &Context,
+    out: &mut OutputChannel,
+    branch: Option<&str>,
+    limit: Option<usize>,
+    paths: &[BString],
+) -> Result<()> {
+    let repo = ctx.repo.get()?;
+    let guard = ctx.shared_worktree_access();
+    let meta = ctx.meta(guard.read_permission())?;
+    let opts = ref_info::Options {
+        traversal: but_graph::init::Options::limited(),
+        expensive_commit_info: true,
+    };
+    let info = match branch {
+        None => but_workspace::head_info(&repo, &meta, opts)?,
+        Some(name) => {
+            let reference = repo
+                .find_reference(name)
+                .with_context(|| format!("Could not find branch '{name}'"))?;
+            but_workspace::ref_info(reference, &meta, opts)?
+        }
+    };
+
+    // When looking at a branch that is part of the workspace, only show the stack it's in.
+    let has_entrypoint = |stack: &Stack| stack.segments.iter().any(|s| s.is_entrypoint);
+    let show_all = branch.is_none() || !info.stacks.iter().any(has_entrypoint);
+    let mut log = Log {
+        target: info
+            .target_ref
+            .as_ref()
+            .map(|target| target.ref_name.shorten().to_string()),
+        stacks: Vec::new(),
+        truncated: false,
+    };
+    let mut remaining = limit.unwrap_or(usize::MAX);
+    for stack in info
+        .stacks
+        .iter()
+        .filter(|stack| show_all || has_entrypoint(stack))
+    {
+        let mut segments = Vec::new();
+        for segment in &stack.segments {
+            let mut commits = Vec::new();
+            for commit in &segment.commits {
+                if !paths.is_empty() && !touches_any(&repo, commit, paths)? {
+                    continue;
+                }
+                if remaining == 0 {
+                    log.truncated = true;
+                    break;
+                }
+                remaining -= 1;
+                commits.push(LogCommit {
+                    id: commit.id.to_string(),
+                    change_id: commit.change_id.as_ref().map(ToString::to_string),
+                    subject: commit
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_str_lossy()
+                        .into_owned(),
+                    author: commit.author.name.to_str_lossy().into_owned(),
+                    time: commit.author.time.seconds,
+                    state: match commit.relation {
+                        LocalCommitRelation::LocalOnly => CommitState::LocalOnly,
+                        LocalCommitRelation::LocalAndRemote(remote_id)
+                            if remote_id == commit.id =>
+                        {
+                            CommitState::Pushed
+                        }
+                        LocalCommitRelation::LocalAndRemote(_) => CommitState::Modified,
+                        LocalCommitRelation::Integrated(_) => CommitState::Integrated,
+                    },
+                    has_conflicts: commit.has_conflicts,
+                });
+            }
+            segments.push(LogSegment {
+                name: segment
+                    .ref_info
+                    .as_ref()
+                    .map(|ri| ri.ref_name.shorten().to_string()),
+                remote_tracking_branch: segment
+                    .remote_tracking_ref_name
+                    .as_ref()
+                    .map(|name| name.shorten().to_string()),
+                push_status: segment.push_status,
+                is_entrypoint: segment.is_entrypoint,
+                commits_on_remote: segment.commits_on_remote.len(),
+                commits,
+            });
+        }
+        log.stacks.push(LogStack {
+            id: stack.id.map(|id| id.to_string()),
+            segments,
+        });
+    }
+
+    if let Some(out) = out.for_json() {
+        out.write_value(&log)?;
+    } else if let Some(out) = out.for_human() {
+        print_log(&log, out)?;
+    } else if let Some(out) = out.for_shell() {
+        for commit in log
+            .stacks
+            .iter()
+            .flat_map(|stack| &stack.segments)
+            .flat_map(|segment| &segment.commits)
+        {
+            writeln!(out, "{}", commit.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Return `true` if `commit` changed any of `paths` compared to its first parent.
+fn touches_any(
+    repo: &gix::Repository,
+    commit: &ref_info::LocalCommit,
+    paths: &[BString],
+) -> Result<bool> {
+    let changes =
+        but_core::diff::tree_changes(repo, commit.parent_ids.first().copied(), commit.id)?;
+    Ok(changes.iter().any(|change| {
+        std::iter::once(change.path.as_bstr())
+            .chain(change.previous_path())
+            .any(|changed| paths.iter().any(|path| is_at_or_below(changed, path)))
+    }))
+}
+
+fn is_at_or_below(changed: &bstr::BStr, path: &BString) -> bool {
+    let path = path.trim_end_with(|c| c == '/');
+    changed
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+}
+
+fn print_log(log: &Log, out: &mut dyn std::fmt::Write) -> Result<()> {
+    if log.stacks.is_empty() {
+        writeln!(out, "{}", "No branches to show".dimmed())?;
+        return Ok(());
+    }
+    for stack in &log.stacks {
+        let mut first = true;
+        for segment in &stack.segments {
+            let notch = if first { "╭" } else { "├" };
+            if !first {
+                writeln!(out, "┊│")?;
+            }
+            first = false;
+            let name = segment
+                .name
+                .as_deref()
+                .unwrap_or("anonymous")
+                .green()
+                .bold();
+            let name = if segment.is_entrypoint {
+                name.underline()
+            } else {
+                name
+            };
+            let remote = segment
+                .remote_tracking_branch
+                .as_ref()
+                .map(|name| format!(" ⇄ {name}").dimmed().to_string())
+                .unwrap_or_default();
+            let behind = if segment.commits_on_remote == 0 {
+                String::new()
+            } else {
+                format!(" ⇣{}", segment.commits_on_remote)
+                    .yellow()
+                    .to_string()
+            };
+            writeln!(
+                out,
+                "┊{notch}┄[{name}]{remote}{behind} {}",
+                push_status(segment.push_status)
+            )?;
+            for commit in &segment.commits {
+                let dot = match commit.state {
+                    CommitState::LocalOnly => "●".normal(),
+                    CommitState::Pushed => "●".green(),
+                    CommitState::Modified => "◐".green(),
+                    CommitState::Integrated => "●".purple(),
+                };
+                let change_id = commit
+                    .change_id
+                    .as_deref()
+                    .map(|id| format!(" {}", &id[..id.len().min(8)]).dimmed().to_string())
+                    .unwrap_or_default();
+                let conflicted = if commit.has_conflicts {
+                    " {conflicted}".red().to_string()
+                } else {
+                    String::new()
+                };
+                writeln!(
+                    out,
+                    "┊{dot}   {}{change_id} {}{conflicted} {}",
+                    commit.id[..7].blue(),
+                    commit.subject,
+                    format!("({})", commit.author).dimmed(),
+                )?;
+            }
+        }
+        writeln!(out, "├╯")?;
+        writeln!(out, "┊")?;
+    }
+    if let Some(target) = &log.target {
+        writeln!(out, "┴ {}", target.cyan())?;
+    }
+    if log.truncated {
+        writeln!(
+            out,
+            "\n{}",
+            "More commits are available, increase the --limit to see them.".dimmed()
+        )?;
+    }
+    Ok(())
+}
+
+fn push_status(status: PushStatus) -> String {
+    match status {
+        PushStatus::NothingToPush => "[pushed]".green(),
+        PushStatus::UnpushedCommits => "[unpushed commits]".yellow(),
+        PushStatus::UnpushedCommitsRequiringForce => "[requires force push]".red(),
+        PushStatus::CompletelyUnpushed => "[not pushed]".dimmed(),
+        PushStatus::Integrated => "[integrated]".purple(),
+    }
+    .to_string()
+}
@@ -7,6 +7,9 @@ pub mod alias;
 pub mod branch;
 pub mod completions;
 pub mod forge;
+pub mod graph;
 pub mod gui;
 pub mod help;
+pub mod log;
 pub mod push;
+pub mod secret;
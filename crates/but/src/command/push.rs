@@ -62,6 +62,18 @@ pub mod help {
             out,
             "  -r, --run-hooks                   Run pre-push hooks"
         )?;
+        writeln!(
+            out,
+            "      --stack                       Push all branches of the stack containing BRANCH_ID at once"
+        )?;
+        writeln!(
+            out,
+            "      --all                         Push all branches of all applied stacks at once"
+        )?;
+        writeln!(
+            out,
+            "                                    Both push atomically if the remote supports it"
+        )?;
 
         // Check if gerrit mode is enabled and show gerrit options
         if is_gerrit_enabled() {
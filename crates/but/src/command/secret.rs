@@ -0,0 +1,30 @@
+use anyhow::Result;
+use but_secret::secret::Store;
+
+use crate::utils::OutputChannel;
+
+/// Move all known secrets from the `from` store to the `to` store and list the ones that were moved.
+pub fn migrate(out: &mut OutputChannel, from: Store, to: Store) -> Result<()> {
+    let moved = but_api::secret::secret_migrate(from, to)?;
+    if let Some(out) = out.for_json() {
+        out.write_value(moved)?;
+    } else if let Some(out) = out.for_shell() {
+        for handle in &moved {
+            writeln!(out, "{handle}")?;
+        }
+    } else if let Some(out) = out.for_human() {
+        if moved.is_empty() {
+            writeln!(out, "No secrets found in '{from}'")?;
+        } else {
+            for handle in &moved {
+                writeln!(out, "Moved '{handle}'")?;
+            }
+            writeln!(
+                out,
+                "Moved {count} secret(s) from '{from}' to '{to}'. Set `secrets.store` to '{to}' in the settings to use them.",
+                count = moved.len()
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -18,15 +18,18 @@ use but_hunk_assignment::HunkAssignment;
 use but_workspace::branch::Stack;
 use nonempty::NonEmpty;
 
+pub use crate::id::persisted::PersistedIds;
 use crate::id::{
     file_info::FileInfo,
     id_usage::{IdUsage, UintId},
+    persisted::EntityKind,
     stacks_info::StacksInfo,
     uncommitted_info::UncommittedInfo,
 };
 
 mod file_info;
 mod id_usage;
+mod persisted;
 mod stacks_info;
 mod uncommitted_info;
 
@@ -47,6 +50,14 @@ const UNASSIGNED: &str = "zz";
 /// 3. Use [IdMap::resolve_entity_to_ids] to parse user input into matching IDs
 /// 4. Use specific methods like [IdMap::resolve_branch]
 ///    or [IdMap::resolve_file_changed_in_commit_or_unassigned] to get IDs for specific entities
+///
+/// # Persistence
+///
+/// IDs are assigned in a way that depends on the state of the workspace, so they may change whenever
+/// the workspace changes. To make them survive across invocations, [IdMap::new_from_context] reuses the IDs
+/// of previous invocations for all entities that still exist, and IDs of entities that are gone are
+/// rejected by [IdMap::resolve_entity_to_ids] instead of referring to another entity.
+/// See [IdMap::persist] for details.
 #[derive(Debug)]
 pub struct IdMap {
     /// Maps shortened branch names to their assigned CLI IDs
@@ -73,6 +84,18 @@ pub struct IdMap {
     uncommitted_hunks: HashMap<ShortId, UncommittedHunk>,
     /// Committed files with their assigned IDs
     committed_files: BTreeSet<CommittedFile>,
+    /// The change-ids of workspace commits that have one.
+    change_ids: HashMap<gix::ObjectId, String>,
+
+    /// The IDs assigned by previous invocations.
+    persisted: PersistedIds,
+    /// IDs that can't be reused as they are now ambiguous, like substrings of branch names.
+    occupied_ids: HashSet<ShortId>,
+    /// The IDs assigned to entities that can be found again in later invocations.
+    assigned_ids: HashMap<(EntityKind, String), ShortId>,
+    /// The kinds of entities which were all assigned IDs, so IDs persisted for entities of this kind
+    /// that weren't assigned an ID are known to be stale.
+    kinds_with_ids: BTreeSet<EntityKind>,
 }
 
 /// Returns the length of the longest common *nybble* prefix.
@@ -96,18 +119,41 @@ impl IdMap {
     /// files/hunks. To enable parsing of committed file IDs, call
     /// [IdMap::add_committed_file_info_from_context].
     pub fn new(stacks: &[Stack], hunk_assignments: Vec<HunkAssignment>) -> anyhow::Result<Self> {
+        Self::new_with_persisted_ids(stacks, hunk_assignments, PersistedIds::default())
+    }
+
+    /// Like [IdMap::new], but reuses the IDs in `persisted` for all entities that still exist.
+    pub fn new_with_persisted_ids(
+        stacks: &[Stack],
+        hunk_assignments: Vec<HunkAssignment>,
+        persisted: PersistedIds,
+    ) -> anyhow::Result<Self> {
         let StacksInfo {
             branch_names,
             workspace_commit_and_first_parent_ids,
             remote_commit_ids,
+            change_ids,
         } = StacksInfo::from_stacks(stacks)?;
         let UncommittedInfo {
             partitioned_hunks,
             uncommitted_short_filenames,
         } = UncommittedInfo::from_hunk_assignments(hunk_assignments)?;
 
-        let (mut id_usage, branch_name_to_cli_id, branch_auto_id_to_cli_id) =
-            Self::ids_for_branch_names(branch_names, uncommitted_short_filenames)?;
+        let BranchIds {
+            id_usage,
+            branch_name_to_cli_id,
+            branch_auto_id_to_cli_id,
+            occupied_ids,
+        } = Self::ids_for_branch_names(branch_names, uncommitted_short_filenames, &persisted)?;
+        let assigned_ids = branch_name_to_cli_id
+            .iter()
+            .map(|(name, cli_id)| {
+                (
+                    (EntityKind::Branch, name.to_string()),
+                    cli_id.to_short_string(),
+                )
+            })
+            .collect();
 
         // Sort all commit IDs so that we can check for collisions.
         enum SortedCommit {
@@ -159,61 +205,71 @@ impl IdMap {
             common_with_previous_len = common_with_next_len;
         }
 
-        let mut uncommitted_files = BTreeMap::new();
-        let mut uncommitted_hunks = HashMap::new();
-        for hunk_assignments in partitioned_hunks {
-            uncommitted_files.insert(
-                id_usage.next_available()?.to_short_id(),
-                UncommittedFile { hunk_assignments },
-            );
-        }
-        for uncommitted_file in uncommitted_files.values() {
-            for hunk_assignment in uncommitted_file.hunk_assignments.iter() {
-                uncommitted_hunks.insert(
-                    id_usage.next_available()?.to_short_id(),
-                    UncommittedHunk {
-                        hunk_assignment: hunk_assignment.clone(),
-                    },
-                );
-            }
-        }
-        let mut stack_ids = BTreeMap::new();
-        for stack in stacks {
-            if let Some(id) = stack.id {
-                stack_ids.insert(
-                    id,
-                    CliId::Stack {
-                        id: id_usage.next_available()?.to_short_id(),
-                        stack_id: id,
-                    },
-                );
-            }
-        }
-
-        Ok(Self {
+        let mut map = Self {
             branch_name_to_cli_id,
             branch_auto_id_to_cli_id,
             id_usage,
             workspace_commits,
-            stack_ids,
+            stack_ids: BTreeMap::new(),
             remote_commit_ids,
             unassigned: CliId::Unassigned {
                 id: UNASSIGNED.to_string(),
             },
-            uncommitted_files,
-            uncommitted_hunks,
+            uncommitted_files: BTreeMap::new(),
+            uncommitted_hunks: HashMap::new(),
             committed_files: BTreeSet::new(),
-        })
+            change_ids,
+            persisted,
+            occupied_ids,
+            assigned_ids,
+            kinds_with_ids: BTreeSet::new(),
+        };
+
+        for hunk_assignments in partitioned_hunks {
+            // If hunks of a file are assigned to different stacks, only the first of them is
+            // keyed by its path.
+            let path = hunk_assignments.first().path_bytes.to_string();
+            let id = map.next_id(EntityKind::UncommittedFile, Some(path))?;
+            map.uncommitted_files
+                .insert(id, UncommittedFile { hunk_assignments });
+        }
+        let hunk_assignments: Vec<_> = map
+            .uncommitted_files
+            .values()
+            .flat_map(|uncommitted_file| uncommitted_file.hunk_assignments.iter().cloned())
+            .collect();
+        for hunk_assignment in hunk_assignments {
+            let key = hunk_assignment.id.map(|id| id.to_string());
+            let id = map.next_id(EntityKind::UncommittedHunk, key)?;
+            map.uncommitted_hunks
+                .insert(id, UncommittedHunk { hunk_assignment });
+        }
+        for stack in stacks {
+            if let Some(stack_id) = stack.id {
+                let id = map.next_id(EntityKind::Stack, Some(stack_id.to_string()))?;
+                map.stack_ids
+                    .insert(stack_id, CliId::Stack { id, stack_id });
+            }
+        }
+        map.kinds_with_ids.extend([
+            EntityKind::Branch,
+            EntityKind::UncommittedFile,
+            EntityKind::UncommittedHunk,
+            EntityKind::Stack,
+        ]);
+        Ok(map)
     }
 
     /// Scan short `branch_names` in windows of 2 to 3 (presumed) ascii characters and see if
     /// they resemble [`UintId`]s. If so, use them, otherwise, see if they can be used unambiguously
     /// directly. If not, generate an ID.
-    #[allow(clippy::type_complexity)]
+    ///
+    /// IDs in `persisted` are reserved, and reused for their branches if they are still unambiguous.
     fn ids_for_branch_names(
         branch_names: Vec<BString>,
         uncommitted_short_filenames: HashSet<BString>,
-    ) -> anyhow::Result<(IdUsage, BTreeMap<BString, CliId>, HashMap<ShortId, CliId>)> {
+        persisted: &PersistedIds,
+    ) -> anyhow::Result<BranchIds> {
         // Map from an acceptable short ID to how many times it appears among
         // uncommitted short filenames and substrings of branch names. If a
         // string doesn't appear in this map, it is not an acceptable short ID,
@@ -261,10 +317,36 @@ impl IdMap {
             }
         }
 
+        // Don't hand out IDs of previous invocations to other entities.
+        for id in persisted.ids() {
+            if let Some(uint_id) = UintId::from_name(id.as_bytes()) {
+                id_usage.mark_used(uint_id);
+            }
+        }
+
         let mut branch_name_to_cli_id: BTreeMap<BString, CliId> = BTreeMap::new();
         let mut branch_auto_id_to_cli_id: HashMap<ShortId, CliId> = HashMap::new();
         for branch_name in branch_names {
             let id = 'short_id: {
+                // Reuse the ID of a previous invocation if it's still unambiguous, i.e. it's either
+                // a substring of only this branch, or an autogenerated ID that's not a substring at all.
+                if let Some(id) = persisted.get(EntityKind::Branch, &branch_name.to_str_lossy()) {
+                    let is_substring = branch_name.contains_str(id);
+                    match short_ids_to_count.get(id) {
+                        Some(1) if is_substring => break 'short_id id.clone(),
+                        None if !is_substring => {
+                            branch_auto_id_to_cli_id.insert(
+                                id.clone(),
+                                CliId::Branch {
+                                    name: branch_name.to_string(),
+                                    id: id.clone(),
+                                },
+                            );
+                            break 'short_id id.clone();
+                        }
+                        _ => {}
+                    }
+                }
                 // Find first non-conflicting pair or triple (i.e. used in
                 // exactly one branch) and use it as CliId.
                 for candidate in branch_name.windows(2).chain(branch_name.windows(3)) {
@@ -292,13 +374,32 @@ impl IdMap {
             let name = branch_name.to_string();
             branch_name_to_cli_id.insert(branch_name, CliId::Branch { name, id });
         }
-        Ok((id_usage, branch_name_to_cli_id, branch_auto_id_to_cli_id))
+        Ok(BranchIds {
+            id_usage,
+            branch_name_to_cli_id,
+            branch_auto_id_to_cli_id,
+            occupied_ids: short_ids_to_count.into_keys().collect(),
+        })
     }
 
     /// Creates a new instance from `ctx` for more convenience over calling [IdMap::new].
+    ///
+    /// IDs of previous invocations are reused for all entities that still exist, and all IDs
+    /// are [persisted](IdMap::persist) for use by later invocations.
     pub fn new_from_context(
         ctx: &mut Context,
         assignments: Option<Vec<HunkAssignment>>,
+    ) -> anyhow::Result<Self> {
+        let persisted = PersistedIds::load(&mut *ctx.db.get_mut()?)?;
+        let map = Self::new_from_context_with_persisted_ids(ctx, assignments, persisted)?;
+        map.persist(ctx)?;
+        Ok(map)
+    }
+
+    fn new_from_context_with_persisted_ids(
+        ctx: &mut Context,
+        assignments: Option<Vec<HunkAssignment>>,
+        persisted: PersistedIds,
     ) -> anyhow::Result<Self> {
         let guard = ctx.shared_worktree_access();
         let meta = ctx.meta(guard.read_permission())?;
@@ -331,26 +432,78 @@ impl IdMap {
                 ..Default::default()
             },
         )?;
-        Self::new(&head_info.stacks, hunk_assignments)
+        Self::new_with_persisted_ids(&head_info.stacks, hunk_assignments, persisted)
+    }
+
+    /// Store the IDs of all entities in the project database of `ctx`, so later invocations
+    /// can reuse them while the entities exist.
+    ///
+    /// IDs of entities that don't exist anymore are kept, so that using them fails with a helpful
+    /// error instead of referring to another entity. As instances created with [IdMap::new] don't
+    /// know about the IDs of previous invocations, persisting them starts over.
+    ///
+    /// Nothing is written if the IDs are the same as the ones loaded from the database.
+    pub fn persist(&self, ctx: &mut Context) -> anyhow::Result<()> {
+        let kinds = self.kinds_to_persist();
+        let ids = self.cli_ids_to_persist();
+        if self.persisted.are_stored(&kinds, &ids) {
+            return Ok(());
+        }
+        ctx.db.get_mut()?.cli_ids().set_for_kinds(&kinds, ids)
+    }
+
+    /// Return the names of all kinds of entities that were assigned IDs.
+    fn kinds_to_persist(&self) -> Vec<&'static str> {
+        self.kinds_with_ids.iter().map(EntityKind::as_str).collect()
+    }
+
+    /// Return the IDs to persist for all kinds of entities that were assigned IDs, including the
+    /// ones of entities that are gone, unless they are gone for longer than the grace period.
+    fn cli_ids_to_persist(&self) -> Vec<but_db::CliId> {
+        let now = chrono::Utc::now().naive_utc();
+        let mut ids: Vec<_> = self
+            .assigned_ids
+            .iter()
+            .map(|((kind, key), id)| but_db::CliId {
+                id: id.clone(),
+                kind: kind.as_str().to_owned(),
+                key: key.clone(),
+                retired_at: None,
+            })
+            .collect();
+        for id in self.persisted.ids() {
+            if let Some((kind, key)) = self.stale_entity(id) {
+                ids.push(but_db::CliId {
+                    id: id.clone(),
+                    kind: kind.as_str().to_owned(),
+                    key: key.clone(),
+                    retired_at: Some(self.persisted.retired_at(id).unwrap_or(now)),
+                });
+            }
+        }
+        ids
     }
 }
 
 /// Methods for adding context to enable file ID generation for the entities it contains.
 impl IdMap {
     /// Adds committed file information from a `ctx` to add IDs for all changed
-    /// files of all workspace commits.
+    /// files of all workspace commits, and [persist](IdMap::persist) them.
     pub fn add_committed_file_info_from_context(
         &mut self,
         ctx: &mut Context,
     ) -> anyhow::Result<()> {
-        let repo = &*ctx.repo.get()?;
-        self.add_committed_file_info(|commit_id, parent_id| {
-            let tree_changes = but_core::diff::tree_changes(repo, parent_id, commit_id)?;
-            Ok(tree_changes
-                .into_iter()
-                .map(|tree_change| tree_change.path)
-                .collect::<Vec<_>>())
-        })
+        {
+            let repo = &*ctx.repo.get()?;
+            self.add_committed_file_info(|commit_id, parent_id| {
+                let tree_changes = but_core::diff::tree_changes(repo, parent_id, commit_id)?;
+                Ok(tree_changes
+                    .into_iter()
+                    .map(|tree_change| tree_change.path)
+                    .collect::<Vec<_>>())
+            })?;
+        }
+        self.persist(ctx)
     }
 
     /// Trigger the generation of IDs for committed files and store them in the map.
@@ -375,11 +528,22 @@ impl IdMap {
         )?;
 
         for commit_oid_path in committed_files.into_iter() {
+            let (commit_id, path) = &commit_oid_path;
+            let revision = self
+                .change_ids
+                .get(commit_id)
+                .cloned()
+                .unwrap_or_else(|| commit_id.to_string());
+            let id = self.next_id(
+                EntityKind::CommittedFile,
+                Some(format!("{revision}:{path}")),
+            )?;
             self.committed_files.insert(CommittedFile {
                 commit_oid_path,
-                id: self.id_usage.next_available()?.to_short_id(),
+                id,
             });
         }
+        self.kinds_with_ids.insert(EntityKind::CommittedFile);
 
         Ok(())
     }
@@ -484,6 +648,12 @@ impl IdMap {
             matches.push(self.unassigned.clone());
         }
 
+        if matches.is_empty()
+            && let Some(stale_entity) = self.stale_entity(entity)
+        {
+            return Err(PersistedIds::stale_id_error(entity, stale_entity));
+        }
+
         Ok(matches)
     }
 
@@ -587,6 +757,32 @@ impl IdMap {
 
 /// Private helper methods for `IdMap`.
 impl IdMap {
+    /// Return an ID for the entity of `kind` identified by `key`, which is the ID of a previous
+    /// invocation if it has one that is still unambiguous.
+    /// Entities without `key`, or with a `key` that was seen before, always get a new ID.
+    fn next_id(&mut self, kind: EntityKind, key: Option<String>) -> anyhow::Result<ShortId> {
+        let key = key.filter(|key| !self.assigned_ids.contains_key(&(kind, key.clone())));
+        let id = match key.as_deref().and_then(|key| self.persisted.get(kind, key)) {
+            Some(id) if !self.occupied_ids.contains(id) => id.clone(),
+            _ => self.id_usage.next_available()?.to_short_id(),
+        };
+        if let Some(key) = key {
+            self.assigned_ids.insert((kind, key), id.clone());
+        }
+        Ok(id)
+    }
+
+    /// Return the entity `id` was assigned to by a previous invocation, if it doesn't exist anymore.
+    fn stale_entity(&self, id: &str) -> Option<&(EntityKind, String)> {
+        self.persisted.entity(id).filter(|entity| {
+            self.kinds_with_ids.contains(&entity.0)
+                && self
+                    .assigned_ids
+                    .get(entity)
+                    .is_none_or(|assigned| assigned != id)
+        })
+    }
+
     /// Finds all branches whose names contain the given `substring`.
     ///
     /// A vector of [`CliId::Branch`] instances for all matching branches.
@@ -826,3 +1022,14 @@ impl Borrow<str> for CommittedFile {
 struct UncommittedHunk {
     hunk_assignment: HunkAssignment,
 }
+
+/// The IDs of branches along with everything needed to assign IDs to other entities.
+/// It's really just a named return value.
+struct BranchIds {
+    /// The IDs in use, including all IDs of previous invocations.
+    id_usage: IdUsage,
+    branch_name_to_cli_id: BTreeMap<BString, CliId>,
+    branch_auto_id_to_cli_id: HashMap<ShortId, CliId>,
+    /// Substrings of branch names and short filenames, which are ambiguous as IDs.
+    occupied_ids: HashSet<ShortId>,
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+
+use crate::id::ShortId;
+
+/// The number of days IDs of entities that are gone stay reserved, so using them fails with a helpful
+/// error instead of referring to another entity. After that, they can be assigned again.
+const RETIRED_ID_GRACE_PERIOD_DAYS: i64 = 7;
+
+/// The kind of entity a CLI ID is assigned to, along with how to key it so it can be found again
+/// in later invocations.
+///
+/// Commits are not listed as their IDs are prefixes of their hashes, which are stable by nature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum EntityKind {
+    /// Keyed by the short name of the branch.
+    Branch,
+    /// Keyed by the stack ID.
+    Stack,
+    /// Keyed by the path of the file.
+    UncommittedFile,
+    /// Keyed by the ID of the hunk assignment.
+    UncommittedHunk,
+    /// Keyed by the change-id of the commit, or its hash if it has none, and the path of the file.
+    CommittedFile,
+}
+
+impl EntityKind {
+    /// All kinds of entities.
+    pub(crate) const ALL: [EntityKind; 5] = [
+        EntityKind::Branch,
+        EntityKind::UncommittedFile,
+        EntityKind::UncommittedHunk,
+        EntityKind::Stack,
+        EntityKind::CommittedFile,
+    ];
+
+    /// The name of the kind as stored in the database.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Branch => "branch",
+            EntityKind::Stack => "stack",
+            EntityKind::UncommittedFile => "uncommitted-file",
+            EntityKind::UncommittedHunk => "uncommitted-hunk",
+            EntityKind::CommittedFile => "committed-file",
+        }
+    }
+
+    fn from_str(kind: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == kind)
+    }
+
+    /// Describe the entity of this kind identified by `key` for the user.
+    fn describe(&self, key: &str) -> String {
+        match self {
+            EntityKind::Branch => format!("branch '{key}'"),
+            EntityKind::Stack => format!("stack {key}"),
+            EntityKind::UncommittedFile => format!("uncommitted file '{key}'"),
+            EntityKind::UncommittedHunk => "uncommitted hunk".into(),
+            EntityKind::CommittedFile => {
+                let path = key.split_once(':').map_or(key, |(_rev, path)| path);
+                format!("committed file '{path}'")
+            }
+        }
+    }
+}
+
+/// CLI IDs as assigned by previous invocations, stored in the project database.
+#[derive(Debug, Default)]
+pub struct PersistedIds {
+    /// Maps entities to the ID they were assigned.
+    by_entity: HashMap<(EntityKind, String), ShortId>,
+    /// Maps IDs to the entity they were assigned to.
+    by_id: HashMap<ShortId, (EntityKind, String)>,
+    /// The time at which entities were found to not exist anymore, by ID.
+    retired_at: HashMap<ShortId, chrono::NaiveDateTime>,
+    /// All IDs as stored in the database, or `None` if they weren't loaded from it.
+    stored: Option<Vec<but_db::CliId>>,
+}
+
+impl PersistedIds {
+    /// Load all IDs from `db`.
+    pub fn load(db: &mut but_db::DbHandle) -> anyhow::Result<Self> {
+        let ids = db
+            .cli_ids()
+            .list()
+            .context("Failed to load CLI IDs of previous invocations")?;
+        Ok(Self::from_stored_cli_ids(
+            ids,
+            chrono::Utc::now().naive_utc(),
+        ))
+    }
+
+    /// Create a new instance from `ids` as stored in the database, to know if they changed, at time `now`.
+    pub(crate) fn from_stored_cli_ids(ids: Vec<but_db::CliId>, now: chrono::NaiveDateTime) -> Self {
+        let mut out = Self::from_cli_ids_at(ids.clone(), now);
+        out.stored = Some(ids);
+        out
+    }
+
+    /// Create a new instance from `ids` at time `now`, dropping the ones that were retired
+    /// longer than the grace period ago.
+    fn from_cli_ids_at(
+        ids: impl IntoIterator<Item = but_db::CliId>,
+        now: chrono::NaiveDateTime,
+    ) -> Self {
+        let expired_before = now - chrono::TimeDelta::days(RETIRED_ID_GRACE_PERIOD_DAYS);
+        let mut out = PersistedIds::default();
+        for but_db::CliId {
+            id,
+            kind,
+            key,
+            retired_at,
+        } in ids
+        {
+            // Ignore kinds written by a newer version.
+            let Some(kind) = EntityKind::from_str(&kind) else {
+                continue;
+            };
+            if let Some(retired_at) = retired_at {
+                if retired_at < expired_before {
+                    continue;
+                }
+                out.retired_at.insert(id.clone(), retired_at);
+            }
+            out.by_entity.insert((kind, key.clone()), id.clone());
+            out.by_id.insert(id, (kind, key));
+        }
+        out
+    }
+
+    /// Return `true` if `ids` are exactly the stored IDs of the given `kinds`, so storing them would change nothing.
+    pub(crate) fn are_stored(&self, kinds: &[&str], ids: &[but_db::CliId]) -> bool {
+        let Some(stored) = &self.stored else {
+            return false;
+        };
+        let sort_key = |id: &&but_db::CliId| (id.kind.clone(), id.key.clone(), id.id.clone());
+        let mut stored: Vec<_> = stored
+            .iter()
+            .filter(|id| kinds.contains(&id.kind.as_str()))
+            .collect();
+        let mut ids: Vec<_> = ids.iter().collect();
+        stored.sort_by_key(sort_key);
+        ids.sort_by_key(sort_key);
+        stored == ids
+    }
+
+    /// Return the ID assigned to the entity of `kind` identified by `key`.
+    pub(crate) fn get(&self, kind: EntityKind, key: &str) -> Option<&ShortId> {
+        self.by_entity.get(&(kind, key.to_owned()))
+    }
+
+    /// Return the entity `id` was assigned to.
+    pub(crate) fn entity(&self, id: &str) -> Option<&(EntityKind, String)> {
+        self.by_id.get(id)
+    }
+
+    /// Return all persisted IDs, which are reserved for the entities they were assigned to.
+    pub(crate) fn ids(&self) -> impl Iterator<Item = &ShortId> {
+        self.by_id.keys()
+    }
+
+    /// Return the time at which the entity of `id` was found to be gone, if it was.
+    pub(crate) fn retired_at(&self, id: &str) -> Option<chrono::NaiveDateTime> {
+        self.retired_at.get(id).copied()
+    }
+
+    /// Return an error explaining that `id` was assigned to `entity`, which doesn't have this ID anymore.
+    pub(crate) fn stale_id_error(id: &str, (kind, key): &(EntityKind, String)) -> anyhow::Error {
+        anyhow::anyhow!(
+            "The ID '{id}' is stale as the {} it referred to no longer exists or has a new ID. Run `but status` to see the current IDs.",
+            kind.describe(key)
+        )
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bstr::BString;
 use but_workspace::branch::Stack;
 
@@ -12,6 +14,8 @@ pub(crate) struct StacksInfo {
     pub(crate) workspace_commit_and_first_parent_ids: Vec<(gix::ObjectId, Option<gix::ObjectId>)>,
     /// Commit IDs that are only reachable from remote-tracking branches (not in workspace).
     pub(crate) remote_commit_ids: Vec<gix::ObjectId>,
+    /// The change-ids of workspace commits that have one.
+    pub(crate) change_ids: HashMap<gix::ObjectId, String>,
 }
 
 impl StacksInfo {
@@ -21,6 +25,7 @@ impl StacksInfo {
         let mut workspace_commit_and_first_parent_ids: Vec<(gix::ObjectId, Option<gix::ObjectId>)> =
             Vec::new();
         let mut remote_commit_ids: Vec<gix::ObjectId> = Vec::new();
+        let mut change_ids = HashMap::new();
         for stack in stacks {
             for segment in &stack.segments {
                 if let Some(ref_info) = &segment.ref_info {
@@ -29,6 +34,9 @@ impl StacksInfo {
                 for commit in &segment.commits {
                     workspace_commit_and_first_parent_ids
                         .push((commit.id, commit.parent_ids.first().cloned()));
+                    if let Some(change_id) = &commit.change_id {
+                        change_ids.insert(commit.id, change_id.to_string());
+                    }
                 }
                 for commit in &segment.commits_on_remote {
                     remote_commit_ids.push(commit.id);
@@ -40,6 +48,7 @@ impl StacksInfo {
             branch_names,
             workspace_commit_and_first_parent_ids,
             remote_commit_ids,
+            change_ids,
         })
     }
 }
@@ -3,7 +3,10 @@ use bstr::BString;
 use but_hunk_assignment::HunkAssignment;
 use but_testsupport::{hex_to_id, hunk_header};
 
-use crate::{CliId, IdMap, id::UintId};
+use crate::{
+    CliId, IdMap,
+    id::{PersistedIds, UintId},
+};
 
 #[test]
 fn uint_id_from_short_id() -> anyhow::Result<()> {
@@ -690,6 +693,154 @@ fn committed_files_are_deduplicated_by_commit_oid_path() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn persisted_ids_are_reused_while_entities_exist() -> anyhow::Result<()> {
+    let stacks = &[stack([segment("A", [id(1)], None, [])])];
+    let id_map = IdMap::new(
+        stacks,
+        vec![
+            hunk_assignment("a.txt", None),
+            hunk_assignment("b.txt", None),
+        ],
+    )?;
+    insta::assert_debug_snapshot!(id_map.debug_state(), @r"
+    workspace_and_remote_commits_count: 1
+    branches: [ g0 ]
+    uncommitted_files: [ h0, i0 ]
+    uncommitted_hunks: [ j0, k0 ]
+    ");
+
+    // `a.txt` is gone, and `0.txt` is new and sorts first.
+    let persisted = PersistedIds::from_stored_cli_ids(
+        id_map.cli_ids_to_persist(),
+        chrono::Utc::now().naive_utc(),
+    );
+    let id_map = IdMap::new_with_persisted_ids(
+        stacks,
+        vec![
+            hunk_assignment("0.txt", None),
+            hunk_assignment("b.txt", None),
+        ],
+        persisted,
+    )?;
+    insta::assert_debug_snapshot!(id_map.debug_state(), @r"
+    workspace_and_remote_commits_count: 1
+    branches: [ g0 ]
+    uncommitted_files: [ i0, j0 ]
+    uncommitted_hunks: [ k0, l0 ]
+    ");
+    assert_eq!(
+        id_map.uncommitted_files["i0"].path(),
+        "b.txt",
+        "the file that still exists keeps its ID"
+    );
+    assert_eq!(
+        id_map.uncommitted_files["j0"].path(),
+        "0.txt",
+        "new files don't get IDs of previous invocations, even if their entity is gone"
+    );
+    assert_eq!(
+        id_map.resolve_entity_to_ids("h0").unwrap_err().to_string(),
+        "The ID 'h0' is stale as the uncommitted file 'a.txt' it referred to no longer exists or has a new ID. Run `but status` to see the current IDs.",
+    );
+    Ok(())
+}
+
+#[test]
+fn retired_ids_are_released_after_the_grace_period() -> anyhow::Result<()> {
+    let now = chrono::Utc::now().naive_utc();
+    let cli_id = |id: &str, key: &str, retired_days_ago: Option<i64>| but_db::CliId {
+        id: id.into(),
+        kind: "uncommitted-file".into(),
+        key: key.into(),
+        retired_at: retired_days_ago.map(|days| now - chrono::TimeDelta::days(days)),
+    };
+    let persisted = PersistedIds::from_stored_cli_ids(
+        vec![
+            cli_id("g0", "gone-long-ago.txt", Some(8)),
+            cli_id("h0", "gone-recently.txt", Some(1)),
+            cli_id("i0", "a.txt", None),
+        ],
+        now,
+    );
+    let id_map = IdMap::new_with_persisted_ids(
+        &[stack([segment("A", [id(1)], None, [])])],
+        vec![
+            hunk_assignment("a.txt", None),
+            hunk_assignment("new.txt", None),
+        ],
+        persisted,
+    )?;
+    assert_eq!(id_map.uncommitted_files["i0"].path(), "a.txt");
+    assert!(
+        matches!(
+            id_map.resolve_entity_to_ids("g0")?.as_slice(),
+            [CliId::Branch { name, .. }] if name == "A"
+        ),
+        "the ID of the file that is gone for longer than the grace period is free again"
+    );
+    assert!(
+        id_map.resolve_entity_to_ids("h0").is_err(),
+        "the ID of the file that is gone recently is still reserved"
+    );
+
+    let ids: Vec<_> = id_map
+        .cli_ids_to_persist()
+        .into_iter()
+        .filter(|id| id.kind == "uncommitted-file")
+        .map(|id| (id.id, id.key, id.retired_at.is_some()))
+        .collect();
+    assert!(ids.contains(&("h0".into(), "gone-recently.txt".into(), true)));
+    assert!(
+        !ids.iter().any(|(_, key, _)| key == "gone-long-ago.txt"),
+        "expired IDs are forgotten: {ids:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn ids_are_only_persisted_if_they_changed() -> anyhow::Result<()> {
+    let stacks = &[stack([segment("A", [id(1)], None, [])])];
+    let assignments = vec![
+        hunk_assignment("a.txt", None),
+        hunk_assignment("b.txt", None),
+    ];
+    let id_map = IdMap::new(stacks, assignments.clone())?;
+    assert!(
+        !id_map
+            .persisted
+            .are_stored(&id_map.kinds_to_persist(), &id_map.cli_ids_to_persist()),
+        "IDs that weren't loaded from the database are always persisted"
+    );
+
+    let now = chrono::Utc::now().naive_utc();
+    let stored = id_map.cli_ids_to_persist();
+    let id_map = IdMap::new_with_persisted_ids(
+        stacks,
+        assignments,
+        PersistedIds::from_stored_cli_ids(stored.clone(), now),
+    )?;
+    assert!(
+        id_map
+            .persisted
+            .are_stored(&id_map.kinds_to_persist(), &id_map.cli_ids_to_persist()),
+        "nothing changed, so nothing has to be written"
+    );
+
+    let id_map = IdMap::new_with_persisted_ids(
+        stacks,
+        vec![hunk_assignment("b.txt", None)],
+        PersistedIds::from_stored_cli_ids(stored, now),
+    )?;
+    assert!(
+        !id_map
+            .persisted
+            .are_stored(&id_map.kinds_to_persist(), &id_map.cli_ids_to_persist()),
+        "a file is gone, so its ID has to be retired"
+    );
+    Ok(())
+}
+
 mod util {
     use std::{cmp::Ordering, fmt::Formatter};
 
@@ -806,6 +957,11 @@ mod util {
                 uncommitted_files,
                 uncommitted_hunks,
                 committed_files,
+                change_ids: _,
+                persisted: _,
+                occupied_ids: _,
+                assigned_ids: _,
+                kinds_with_ids: _,
             } = self;
 
             branch_name_to_cli_id
@@ -846,6 +1002,11 @@ mod util {
                 uncommitted_files,
                 uncommitted_hunks,
                 committed_files,
+                change_ids: _,
+                persisted: _,
+                occupied_ids: _,
+                assigned_ids: _,
+                kinds_with_ids: _,
             } = self.inner;
             let commits_count = self.inner.workspace_and_remote_commit_ids().count();
             writeln!(f, "workspace_and_remote_commits_count: {}", &commits_count)?;
@@ -29,8 +29,8 @@ use cfg_if::cfg_if;
 
 pub mod args;
 use args::{
-    Args, OutputFormat, Subcommands, actions, alias as alias_args, branch, claude, cursor, forge,
-    metrics, worktree,
+    Args, OutputFormat, Subcommands, actions, agent, alias as alias_args, branch, claude, cursor,
+    forge, metrics, secret, worktree,
 };
 use but_settings::AppSettings;
 use colored::Colorize;
@@ -107,7 +107,9 @@ pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
     // Determine if pager should be used based on the command
     let use_pager = match args.cmd {
         #[cfg(feature = "legacy")]
-        Some(Subcommands::Status { .. }) | Some(Subcommands::Oplog(..)) => false,
+        Some(Subcommands::Status { .. })
+        | Some(Subcommands::Oplog(..))
+        | Some(Subcommands::Tui) => false,
         Some(Subcommands::Help) => false,
         _ => true,
     };
@@ -119,6 +121,7 @@ pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
 
     let namespace = option_env!("IDENTIFIER").unwrap_or("com.gitbutler.app");
     but_secret::secret::set_application_namespace(namespace);
+    but_secret::secret::set_store(app_settings.secrets.store);
 
     // If no subcommand is provided, but we have source and target, default to rub
     match args.cmd.take() {
@@ -134,6 +137,7 @@ pub async fn handle_args(args: impl Iterator<Item = OsString>) -> Result<()> {
                 .expect("target is checked to be Some in match guard");
             #[cfg(feature = "legacy")]
             {
+                out.set_kind(metrics::CommandName::Rub);
                 let mut ctx = init::init_ctx(&args, Fetch::None, &mut out)?;
                 command::legacy::rub::handle(&mut ctx, &mut out, source, target)
                     .context("Rubbed the wrong way.")
@@ -192,6 +196,7 @@ async fn match_subcommand(
     app_settings: AppSettings,
     mut output: OutputChannel,
 ) -> Result<()> {
+    output.set_kind(cmd.to_metrics_command());
     let out = &mut output;
     let metrics_ctx = cmd.to_metrics_context(&app_settings);
 
@@ -214,6 +219,36 @@ async fn match_subcommand(
                 .show_root_cause_error_then_exit_without_destructors(output)
         }
         Subcommands::Gui => command::gui::open(&args.current_dir).emit_metrics(metrics_ctx),
+        #[cfg(feature = "legacy")]
+        Subcommands::Tui => {
+            let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
+            command::legacy::tui::handle(&mut ctx, out).emit_metrics(metrics_ctx)
+        }
+        Subcommands::Graph { format, anonymize } => {
+            let ctx = but_ctx::Context::discover(&args.current_dir)?;
+            command::graph::export(&ctx, out, format, anonymize).emit_metrics(metrics_ctx)
+        }
+        Subcommands::Log {
+            branch,
+            limit,
+            paths,
+        } => {
+            let ctx = but_ctx::Context::discover(&args.current_dir)?;
+            let paths: Vec<_> = paths
+                .iter()
+                .map(|path| {
+                    gix::path::to_unix_separators_on_windows(gix::path::into_bstr(path))
+                        .into_owned()
+                })
+                .collect();
+            command::log::show(&ctx, out, branch.as_deref(), limit, &paths)
+                .emit_metrics(metrics_ctx)
+        }
+        Subcommands::Secret(secret::Platform { cmd }) => match cmd {
+            secret::Subcommands::Migrate { from, to } => {
+                command::secret::migrate(out, from.into(), to.into()).emit_metrics(metrics_ctx)
+            }
+        },
         Subcommands::Completions { shell } => {
             command::completions::generate_completions(shell).emit_metrics(metrics_ctx)
         }
@@ -252,11 +287,11 @@ async fn match_subcommand(
             }
         }
         #[cfg(feature = "legacy")]
-        Subcommands::Mcp { internal } => {
+        Subcommands::Mcp { internal, allow } => {
             if internal {
                 command::legacy::mcp_internal::start(app_settings).await
             } else {
-                command::legacy::mcp::start(app_settings).await
+                command::legacy::mcp::start(app_settings, args.current_dir.clone(), allow).await
             }
         }
         #[cfg(feature = "legacy")]
@@ -293,53 +328,34 @@ async fn match_subcommand(
                         .output_claude_json()
                         .emit_metrics(metrics_ctx)
                 }
-                claude::Subcommands::PermissionPromptMcp { session_id } => {
-                    but_claude::mcp::start(&args.current_dir, &session_id).await
+                claude::Subcommands::PermissionPromptMcp {
+                    session_id,
+                    workdir,
+                } => {
+                    but_claude::mcp::start(&args.current_dir, &session_id, workdir.as_deref()).await
+                }
+                claude::Subcommands::Usage => {
+                    let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
+                    command::legacy::claude::usage(&mut ctx, out).emit_metrics(metrics_ctx)
+                }
+                claude::Subcommands::Budget { session_id, amount } => {
+                    let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
+                    command::legacy::claude::budget(&mut ctx, out, &session_id, amount)
+                        .emit_metrics(metrics_ctx)
+                }
+                claude::Subcommands::Audit { session_id } => {
+                    let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
+                    command::legacy::claude::audit(&mut ctx, out, &session_id)
+                        .emit_metrics(metrics_ctx)
+                }
+                claude::Subcommands::TrustPolicy => {
+                    let ctx = init::init_ctx(&args, Fetch::None, out)?;
+                    command::legacy::claude::trust_policy(&ctx, out).emit_metrics(metrics_ctx)
                 }
                 claude::Subcommands::Last { offset } => {
                     let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
-                    let message = but_claude::db::get_user_message(&mut ctx, Some(offset as i64))?;
-                    match message {
-                        Some(msg) => {
-                            if args.json {
-                                // For JSON output, include timestamp and message
-                                let output = serde_json::json!({
-                                    "timestamp": msg.created_at().format("%Y-%m-%d %H:%M:%S").to_string(),
-                                    "message": match msg.content() {
-                                        but_claude::MessagePayload::User(input) => &input.message,
-                                        _ => "",
-                                    }
-                                });
-                                println!("{}", serde_json::to_string_pretty(&output)?);
-                            } else {
-                                // For human-readable output, show timestamp and message
-                                println!(
-                                    "{} {}",
-                                    "Timestamp:".bold(),
-                                    msg.created_at()
-                                        .format("%Y-%m-%d %H:%M:%S")
-                                        .to_string()
-                                        .cyan()
-                                );
-                                match msg.content() {
-                                    but_claude::MessagePayload::User(input) => {
-                                        println!("{}", input.message);
-                                    }
-                                    _ => {
-                                        println!("{}", "Not a user input message".red());
-                                    }
-                                }
-                            }
-                        }
-                        None => {
-                            if args.json {
-                                println!("null");
-                            } else {
-                                println!("No user message found at offset {}", offset);
-                            }
-                        }
-                    }
-                    Ok(())
+                    command::legacy::claude::last(&mut ctx, out, offset as i64)
+                        .emit_metrics(metrics_ctx)
                 }
             }
         }
@@ -359,9 +375,21 @@ async fn match_subcommand(
             }
         },
         #[cfg(feature = "legacy")]
-        Subcommands::Pull { check } => {
+        Subcommands::Agent(agent::Platform { cmd }) => match cmd {
+            agent::Subcommands::Hook {
+                protocol: agent::Protocol::Generic,
+            } => {
+                use but_claude::hooks::agent::OutputAgentJson;
+                but_claude::hooks::agent::handle(std::io::stdin().lock())
+                    .await
+                    .output_agent_json()
+                    .emit_metrics(metrics_ctx)
+            }
+        },
+        #[cfg(feature = "legacy")]
+        Subcommands::Pull { check, auto } => {
             let ctx = init::init_ctx(&args, Fetch::None, out)?;
-            command::legacy::pull::handle(&ctx, out, check)
+            command::legacy::pull::handle(&ctx, out, check, auto)
                 .await
                 .emit_metrics(metrics_ctx)
         }
@@ -378,7 +406,7 @@ async fn match_subcommand(
                 )?;
             }
             let ctx = init::init_ctx(&args, Fetch::None, out)?;
-            command::legacy::pull::handle(&ctx, out, true)
+            command::legacy::pull::handle(&ctx, out, true, false)
                 .await
                 .emit_metrics(metrics_ctx)
         }
@@ -489,6 +517,20 @@ async fn match_subcommand(
                 .emit_metrics(metrics_ctx)
         }
         #[cfg(feature = "legacy")]
+        Subcommands::Split { commit, messages } => {
+            let mut ctx = init::init_ctx(&args, Fetch::Auto, out)?;
+            command::legacy::split::handle(&mut ctx, out, &commit, messages)
+                .emit_metrics(metrics_ctx)
+        }
+        #[cfg(feature = "legacy")]
+        Subcommands::Rebase {
+            interactive: _,
+            branch,
+        } => {
+            let mut ctx = init::init_ctx(&args, Fetch::Auto, out)?;
+            command::legacy::rebase::interactive(&mut ctx, out, &branch).emit_metrics(metrics_ctx)
+        }
+        #[cfg(feature = "legacy")]
         Subcommands::Oplog(args::oplog::Platform { cmd }) => {
             let mut ctx = init::init_ctx(&args, Fetch::None, out)?;
             match cmd {
@@ -535,6 +577,12 @@ async fn match_subcommand(
             command::legacy::discard::handle(&mut ctx, out, &id).emit_metrics(metrics_ctx)
         }
         #[cfg(feature = "legacy")]
+        Subcommands::Batch => {
+            let mut ctx = init::init_ctx(&args, Fetch::Auto, out)?;
+            command::legacy::batch::handle(&mut ctx, out, std::io::stdin().lock())
+                .emit_metrics(metrics_ctx)
+        }
+        #[cfg(feature = "legacy")]
         Subcommands::Init { repo } => command::legacy::init::repo(&args.current_dir, out, repo)
             .context("Failed to initialize GitButler project.")
             .emit_metrics(metrics_ctx),
@@ -614,12 +662,32 @@ async fn match_subcommand(
         Subcommands::Stage {
             file_or_hunk,
             branch,
+            patch,
+            message,
         } => {
             let mut ctx = init::init_ctx(&args, Fetch::Auto, out)?;
-            command::legacy::rub::handle_stage(&mut ctx, out, &file_or_hunk, &branch)
-                .context("Failed to stage.")
-                .emit_metrics(metrics_ctx)
-                .show_root_cause_error_then_exit_without_destructors(output)
+            // With `--patch`, a single argument is the branch.
+            match (patch, file_or_hunk, branch) {
+                (true, Some(branch), None) => {
+                    command::legacy::patch::handle(&mut ctx, out, None, &branch, message.as_deref())
+                }
+                (true, source, Some(branch)) => command::legacy::patch::handle(
+                    &mut ctx,
+                    out,
+                    source.as_deref(),
+                    &branch,
+                    message.as_deref(),
+                ),
+                (false, Some(file_or_hunk), Some(branch)) => {
+                    command::legacy::rub::handle_stage(&mut ctx, out, &file_or_hunk, &branch)
+                }
+                _ => Err(anyhow::anyhow!(
+                    "Specify the branch to stage to, see `but stage --help`."
+                )),
+            }
+            .context("Failed to stage.")
+            .emit_metrics(metrics_ctx)
+            .show_root_cause_error_then_exit_without_destructors(output)
         }
         #[cfg(feature = "legacy")]
         Subcommands::Unstage {
@@ -0,0 +1,431 @@
+//! A full-screen view of the workspace, showing each lane with its branches, commits and uncommitted
+//! changes side by side, much like the GUI does.
+//!
+//! Everything that touches the repository goes through [`Operations`], so the [`App`] state machine
+//! and its rendering can be driven headlessly, for instance with [`ratatui::backend::TestBackend`].
+//! Entities are referred to by their CLI IDs, which makes each operation equivalent to a `but` invocation.
+
+use ratatui::{
+    Terminal,
+    backend::Backend,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+};
+
+mod render;
+
+#[cfg(test)]
+mod tests;
+
+/// The operations the [`App`] performs on a workspace.
+pub trait Operations {
+    /// Obtain the current state of the workspace.
+    fn snapshot(&mut self) -> anyhow::Result<Snapshot>;
+    /// Move the entity identified by `source` onto `target`, with the semantics of `but rub <source> <target>`.
+    ///
+    /// Staging a file or hunk is rubbing it onto the ID of a lane.
+    fn rub(&mut self, source: &str, target: &str) -> anyhow::Result<()>;
+    /// Return the diff of the entity identified by `id`, as `but diff <id>` would show it.
+    fn diff(&mut self, id: &str) -> anyhow::Result<String>;
+    /// Undo the last operation by restoring the previous oplog snapshot.
+    fn undo(&mut self) -> anyhow::Result<()>;
+}
+
+/// The state of the workspace as shown to the user.
+#[derive(Debug, Default, Clone)]
+pub struct Snapshot {
+    /// The unassigned area, followed by one lane per stack.
+    pub lanes: Vec<Lane>,
+}
+
+/// A column of the workspace, i.e. the unassigned area or a stack.
+#[derive(Debug, Clone)]
+pub struct Lane {
+    /// The CLI ID of the lane, which is where files and hunks are staged to.
+    pub id: String,
+    /// The name to show for the lane.
+    pub title: String,
+    /// Everything inside the lane, in display order.
+    pub entries: Vec<Entry>,
+}
+
+/// A selectable line within a [`Lane`].
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The CLI ID of the entity.
+    pub id: String,
+    /// What kind of entity this is.
+    pub kind: EntryKind,
+    /// The text to show next to the ID.
+    pub label: String,
+}
+
+/// The kinds of entities in a [`Lane`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// An uncommitted file, followed by its hunks.
+    File,
+    /// An uncommitted hunk of the file before it.
+    Hunk,
+    /// A branch, followed by its commits.
+    Branch,
+    /// A commit of the branch before it.
+    Commit,
+}
+
+impl EntryKind {
+    /// Return `true` if entries of this kind can be staged to a lane.
+    pub fn is_uncommitted(&self) -> bool {
+        matches!(self, EntryKind::File | EntryKind::Hunk)
+    }
+
+    /// The indentation of entries of this kind, to show them below the entry they belong to.
+    fn depth(&self) -> usize {
+        match self {
+            EntryKind::File | EntryKind::Branch => 0,
+            EntryKind::Hunk | EntryKind::Commit => 1,
+        }
+    }
+}
+
+/// What the user is currently doing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Moving the selection around.
+    Browse,
+    /// Choosing the lane to stage the file or hunk identified by `source` to.
+    Stage {
+        /// The CLI ID of the file or hunk to stage.
+        source: String,
+        /// The index of the lane to stage to.
+        target_lane: usize,
+    },
+    /// Choosing the entity to rub the entity identified by `source` onto.
+    Move {
+        /// The CLI ID of the entity to move.
+        source: String,
+    },
+    /// Viewing a diff.
+    Diff {
+        /// The CLI ID of the entity whose diff is shown.
+        id: String,
+        /// The lines of the diff.
+        lines: Vec<String>,
+        /// The index of the first visible line.
+        scroll: usize,
+    },
+}
+
+/// A message about the outcome of the last action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// The action succeeded.
+    Info(String),
+    /// The action failed.
+    Error(String),
+}
+
+/// The state of the full-screen interface, which turns key presses into [`Operations`].
+pub struct App<O> {
+    ops: O,
+    snapshot: Snapshot,
+    /// The index of the focused lane.
+    lane: usize,
+    /// The selected row of the focused lane, with `0` being the lane itself, and `n` being its `n-1`th entry.
+    row: usize,
+    mode: Mode,
+    message: Option<Message>,
+    quit: bool,
+}
+
+/// Lifecycle
+impl<O: Operations> App<O> {
+    /// Create a new instance that shows the current snapshot of `ops`.
+    pub fn new(mut ops: O) -> anyhow::Result<Self> {
+        let snapshot = ops.snapshot()?;
+        Ok(App {
+            ops,
+            snapshot,
+            lane: 0,
+            row: 0,
+            mode: Mode::Browse,
+            message: None,
+            quit: false,
+        })
+    }
+
+    /// Return `true` once the user asked to leave.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+}
+
+/// Access
+impl<O> App<O> {
+    /// The workspace as currently shown.
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// What the user is currently doing.
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    /// The outcome of the last action, if there is one to show.
+    pub fn message(&self) -> Option<&Message> {
+        self.message.as_ref()
+    }
+
+    /// Return the index of the focused lane and the selected row within it.
+    pub fn selection(&self) -> (usize, usize) {
+        (self.lane, self.row)
+    }
+
+    /// Return the CLI ID of the selected lane or entry, along with the kind of the entry,
+    /// which is `None` if the lane itself is selected.
+    pub fn selected(&self) -> Option<(&str, Option<EntryKind>)> {
+        let lane = self.snapshot.lanes.get(self.lane)?;
+        match self.row.checked_sub(1) {
+            None => Some((lane.id.as_str(), None)),
+            Some(idx) => lane
+                .entries
+                .get(idx)
+                .map(|entry| (entry.id.as_str(), Some(entry.kind))),
+        }
+    }
+}
+
+/// Interaction
+impl<O: Operations> App<O> {
+    /// Handle `key`, possibly performing [`Operations`].
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match &self.mode {
+            Mode::Browse => self.handle_browse_key(key.code),
+            Mode::Stage {
+                source,
+                target_lane,
+            } => self.handle_stage_key(key.code, source.clone(), *target_lane),
+            Mode::Move { source } => self.handle_move_key(key.code, source.clone()),
+            Mode::Diff { .. } => self.handle_diff_key(key.code),
+        }
+    }
+
+    fn handle_browse_key(&mut self, code: KeyCode) {
+        if self.handle_navigation_key(code) {
+            return;
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('s') | KeyCode::Char(' ') => match self.selected() {
+                Some((id, Some(kind))) if kind.is_uncommitted() => {
+                    let source = id.to_owned();
+                    let target_lane = self.next_lane(self.lane, 1);
+                    self.message = None;
+                    self.mode = Mode::Stage {
+                        source,
+                        target_lane,
+                    };
+                }
+                _ => {
+                    self.message = Some(Message::Error(
+                        "Only uncommitted files and hunks can be staged.".into(),
+                    ))
+                }
+            },
+            KeyCode::Char('m') => {
+                if let Some((id, _)) = self.selected() {
+                    self.message = None;
+                    self.mode = Mode::Move {
+                        source: id.to_owned(),
+                    };
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('d') => {
+                if let Some((id, _)) = self.selected() {
+                    let id = id.to_owned();
+                    match self.ops.diff(&id) {
+                        Ok(diff) => {
+                            self.message = None;
+                            self.mode = Mode::Diff {
+                                id,
+                                lines: diff.lines().map(ToOwned::to_owned).collect(),
+                                scroll: 0,
+                            };
+                        }
+                        Err(err) => self.message = Some(Message::Error(format!("{err:#}"))),
+                    }
+                }
+            }
+            KeyCode::Char('u') => {
+                let res = self.ops.undo();
+                self.finish(res, || "Undid the last operation.".into());
+            }
+            KeyCode::Char('r') => self.finish(Ok(()), || "Refreshed.".into()),
+            _ => {}
+        }
+    }
+
+    fn handle_stage_key(&mut self, code: KeyCode, source: String, target_lane: usize) {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Browse,
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.mode = Mode::Stage {
+                    source,
+                    target_lane: self.next_lane(target_lane, -1),
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                self.mode = Mode::Stage {
+                    source,
+                    target_lane: self.next_lane(target_lane, 1),
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('s') | KeyCode::Char(' ') => {
+                self.mode = Mode::Browse;
+                let Some(lane) = self.snapshot.lanes.get(target_lane) else {
+                    return;
+                };
+                let (target, title) = (lane.id.clone(), lane.title.clone());
+                let res = self.ops.rub(&source, &target);
+                self.finish(res, || format!("Staged {source} to {title}."));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_move_key(&mut self, code: KeyCode, source: String) {
+        if self.handle_navigation_key(code) {
+            return;
+        }
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Browse,
+            KeyCode::Enter | KeyCode::Char('m') => {
+                self.mode = Mode::Browse;
+                let Some((target, _)) = self.selected() else {
+                    return;
+                };
+                let target = target.to_owned();
+                if target == source {
+                    return;
+                }
+                let res = self.ops.rub(&source, &target);
+                self.finish(res, || format!("Moved {source} to {target}."));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_diff_key(&mut self, code: KeyCode) {
+        let Mode::Diff { lines, scroll, .. } = &mut self.mode else {
+            return;
+        };
+        let last = lines.len().saturating_sub(1);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.mode = Mode::Browse,
+            KeyCode::Down | KeyCode::Char('j') => *scroll = (*scroll + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => *scroll = (*scroll + PAGE).min(last),
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(PAGE),
+            KeyCode::Home | KeyCode::Char('g') => *scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => *scroll = last,
+            _ => {}
+        }
+    }
+
+    /// Move the selection if `code` is a navigation key, and return `true` if it was.
+    fn handle_navigation_key(&mut self, code: KeyCode) -> bool {
+        let num_lanes = self.snapshot.lanes.len();
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(self.max_row()),
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab if num_lanes > 0 => {
+                self.lane = (self.lane + 1) % num_lanes;
+                self.row = self.row.min(self.max_row());
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab if num_lanes > 0 => {
+                self.lane = (self.lane + num_lanes - 1) % num_lanes;
+                self.row = self.row.min(self.max_row());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Reload the snapshot after an operation produced `res`, and tell the user how it went.
+    /// The selection stays on the same entity if it still exists.
+    fn finish(&mut self, res: anyhow::Result<()>, success: impl FnOnce() -> String) {
+        let selected = self.selected().map(|(id, _)| id.to_owned());
+        self.message = Some(match res.and_then(|()| self.ops.snapshot()) {
+            Ok(snapshot) => {
+                self.snapshot = snapshot;
+                Message::Info(success())
+            }
+            Err(err) => Message::Error(format!("{err:#}")),
+        });
+        self.lane = self.lane.min(self.snapshot.lanes.len().saturating_sub(1));
+        let lane = self.snapshot.lanes.get(self.lane);
+        self.row = match (lane, selected) {
+            (Some(lane), Some(id)) if lane.id == id => 0,
+            (Some(lane), Some(id)) => lane
+                .entries
+                .iter()
+                .position(|entry| entry.id == id)
+                .map_or(self.row.min(self.max_row()), |idx| idx + 1),
+            _ => 0,
+        };
+    }
+
+    /// Return the index of the lane `direction` lanes away from `lane`, skipping the focused lane
+    /// unless it's the only one.
+    fn next_lane(&self, lane: usize, direction: isize) -> usize {
+        let num_lanes = self.snapshot.lanes.len() as isize;
+        if num_lanes < 2 {
+            return lane;
+        }
+        let mut next = lane as isize;
+        loop {
+            next = (next + direction).rem_euclid(num_lanes);
+            if next as usize != self.lane {
+                return next as usize;
+            }
+        }
+    }
+
+    fn max_row(&self) -> usize {
+        self.snapshot
+            .lanes
+            .get(self.lane)
+            .map_or(0, |lane| lane.entries.len())
+    }
+}
+
+/// The amount of lines to scroll at once when paging through a diff.
+const PAGE: usize = 20;
+
+/// Show `app` on `terminal` and feed it the key presses obtained from `next_event` until the user quits.
+///
+/// With a [`ratatui::backend::CrosstermBackend`], `next_event` is [`ratatui::crossterm::event::read`].
+pub fn run<B, O>(
+    terminal: &mut Terminal<B>,
+    app: &mut App<O>,
+    mut next_event: impl FnMut() -> std::io::Result<Event>,
+) -> anyhow::Result<()>
+where
+    B: Backend,
+    O: Operations,
+{
+    while !app.should_quit() {
+        terminal.draw(|frame| render::draw(frame, app))?;
+        if let Event::Key(key) = next_event()? {
+            app.handle_key(key);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+};
+
+use super::{App, Entry, EntryKind, Lane, Message, Mode};
+
+/// Draw `app` onto the whole of `frame`.
+pub(super) fn draw<O>(frame: &mut Frame, app: &App<O>) {
+    let [body, footer] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    match app.mode() {
+        Mode::Diff { id, lines, scroll } => draw_diff(frame, body, id, lines, *scroll),
+        _ => draw_lanes(frame, body, app),
+    }
+    frame.render_widget(Paragraph::new(footer_line(app)), footer);
+}
+
+fn draw_lanes<O>(frame: &mut Frame, area: Rect, app: &App<O>) {
+    let lanes = &app.snapshot().lanes;
+    if lanes.is_empty() {
+        frame.render_widget(
+            Paragraph::new("Nothing to show.").block(Block::bordered()),
+            area,
+        );
+        return;
+    }
+    let (focused_lane, row) = app.selection();
+    let (move_source, stage_target) = match app.mode() {
+        Mode::Move { source } => (Some(source.as_str()), None),
+        Mode::Stage {
+            source,
+            target_lane,
+        } => (Some(source.as_str()), Some(*target_lane)),
+        _ => (None, None),
+    };
+    let areas =
+        Layout::horizontal(vec![Constraint::Ratio(1, lanes.len() as u32); lanes.len()]).split(area);
+    for (idx, (lane, area)) in lanes.iter().zip(areas.iter()).enumerate() {
+        let focused = idx == focused_lane;
+        let border_style = if stage_target == Some(idx) {
+            Style::new().fg(Color::Green).add_modifier(Modifier::BOLD)
+        } else if focused {
+            Style::new().fg(Color::Yellow)
+        } else {
+            Style::new().fg(Color::DarkGray)
+        };
+        let mut title = lane_title(lane);
+        if focused && row == 0 {
+            title = title.reversed();
+        }
+        let block = Block::bordered().title(title).border_style(border_style);
+        let items: Vec<_> = lane
+            .entries
+            .iter()
+            .map(|entry| entry_item(entry, move_source == Some(entry.id.as_str())))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected((focused && row > 0).then(|| row - 1));
+        frame.render_stateful_widget(list, *area, &mut state);
+    }
+}
+
+fn lane_title(lane: &Lane) -> Line<'_> {
+    Line::from(vec![
+        Span::raw(" "),
+        Span::raw(lane.id.as_str()).blue().underlined(),
+        Span::raw(" "),
+        Span::raw(lane.title.as_str()).bold(),
+        Span::raw(" "),
+    ])
+}
+
+fn entry_item(entry: &Entry, is_moved: bool) -> ListItem<'_> {
+    let indent = "  ".repeat(entry.kind.depth());
+    let label_style = match entry.kind {
+        EntryKind::Branch => Style::new().fg(Color::Green).add_modifier(Modifier::BOLD),
+        EntryKind::Hunk => Style::new().fg(Color::DarkGray),
+        EntryKind::File | EntryKind::Commit => Style::new(),
+    };
+    let mut line = Line::from(vec![
+        Span::raw(indent),
+        Span::raw(entry.id.as_str()).blue().underlined(),
+        Span::raw(" "),
+        Span::styled(entry.label.as_str(), label_style),
+    ]);
+    if is_moved {
+        line = line.magenta().italic();
+    }
+    ListItem::new(line)
+}
+
+fn draw_diff(frame: &mut Frame, area: Rect, id: &str, lines: &[String], scroll: usize) {
+    let lines: Vec<_> = lines
+        .iter()
+        .skip(scroll)
+        .map(|line| Line::styled(line.as_str(), diff_line_style(line)))
+        .collect();
+    let block = Block::bordered().title(format!(" Diff of {id} "));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Diff lines look like `<old> <new>│+<content>` as produced by `fmt_hunk()`, and are colored by their change.
+fn diff_line_style(line: &str) -> Style {
+    match line
+        .split_once('│')
+        .and_then(|(_, content)| content.chars().next())
+    {
+        Some('+') => Style::new().fg(Color::Green),
+        Some('-') => Style::new().fg(Color::Red),
+        _ => Style::new(),
+    }
+}
+
+fn footer_line<O>(app: &App<O>) -> Line<'_> {
+    if let Some(message) = app.message() {
+        return match message {
+            Message::Info(msg) => Line::from(msg.as_str()).green(),
+            Message::Error(msg) => Line::from(msg.as_str()).red(),
+        };
+    }
+    let hints = match app.mode() {
+        Mode::Browse => "←→↑↓ select  s stage  m move  ⏎ diff  u undo  r refresh  q quit",
+        Mode::Stage { .. } => "←→ choose lane  ⏎ stage here  esc cancel",
+        Mode::Move { .. } => "←→↑↓ choose target  ⏎ move here  esc cancel",
+        Mode::Diff { .. } => "↑↓ scroll  space page down  esc back",
+    };
+    Line::from(hints).dark_gray()
+}
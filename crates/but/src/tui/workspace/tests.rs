@@ -0,0 +1,348 @@
+use anyhow::bail;
+use ratatui::{
+    Terminal,
+    backend::TestBackend,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+};
+
+use super::{App, Entry, EntryKind, Lane, Message, Mode, Operations, Snapshot};
+
+#[test]
+fn lanes_are_rendered_side_by_side() -> anyhow::Result<()> {
+    let app = App::new(Workspace::new())?;
+    let screen = render(&app)?;
+    assert!(screen.contains("zz Unassigned changes"), "{screen}");
+    assert!(screen.contains("g0 feature"), "{screen}");
+    assert!(screen.contains("h0 a.txt"), "{screen}");
+    assert!(screen.contains("i0 @@ -1,1 +1,1 @@"), "{screen}");
+    assert!(screen.contains("k0 feature"), "{screen}");
+    assert!(screen.contains("1a2b add feature"), "{screen}");
+    assert!(
+        screen.contains("s stage"),
+        "the footer shows the keys: {screen}"
+    );
+    Ok(())
+}
+
+#[test]
+fn staging_a_file_rubs_it_onto_the_chosen_lane() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(&mut app, [KeyCode::Char('j'), KeyCode::Char('s')]);
+    assert_eq!(
+        app.mode(),
+        &Mode::Stage {
+            source: "h0".into(),
+            target_lane: 1
+        },
+        "the first other lane is the default target"
+    );
+    assert!(render(&app)?.contains("stage here"));
+
+    press(&mut app, [KeyCode::Enter]);
+    assert_eq!(app.ops.calls, ["rub h0 g0"]);
+    assert_eq!(app.mode(), &Mode::Browse);
+    assert_eq!(
+        app.message(),
+        Some(&Message::Info("Staged h0 to feature.".into()))
+    );
+    assert!(
+        app.snapshot().lanes[0].entries.is_empty(),
+        "the file and its hunk moved"
+    );
+    assert_eq!(
+        entry_ids(&app.snapshot().lanes[1]),
+        ["k0", "1a2b", "h0", "i0"]
+    );
+    assert_eq!(app.selection(), (0, 0), "the selection stays in range");
+    Ok(())
+}
+
+#[test]
+fn staging_a_hunk_can_target_any_other_lane() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(
+        &mut app,
+        [
+            KeyCode::Char('j'),
+            KeyCode::Char('j'),
+            KeyCode::Char(' '),
+            KeyCode::Right,
+        ],
+    );
+    assert_eq!(
+        app.mode(),
+        &Mode::Stage {
+            source: "i0".into(),
+            target_lane: 2
+        }
+    );
+    press(&mut app, [KeyCode::Right]);
+    assert_eq!(
+        app.mode(),
+        &Mode::Stage {
+            source: "i0".into(),
+            target_lane: 1
+        },
+        "the lane of the hunk is skipped"
+    );
+    press(&mut app, [KeyCode::Esc]);
+    assert_eq!(app.mode(), &Mode::Browse);
+    assert!(app.ops.calls.is_empty(), "nothing happened");
+    Ok(())
+}
+
+#[test]
+fn only_uncommitted_changes_can_be_staged() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(
+        &mut app,
+        [KeyCode::Right, KeyCode::Char('j'), KeyCode::Char('s')],
+    );
+    assert_eq!(app.selected(), Some(("k0", Some(EntryKind::Branch))));
+    assert_eq!(app.mode(), &Mode::Browse);
+    assert_eq!(
+        app.message(),
+        Some(&Message::Error(
+            "Only uncommitted files and hunks can be staged.".into()
+        ))
+    );
+    assert!(app.ops.calls.is_empty());
+    Ok(())
+}
+
+#[test]
+fn moving_rubs_the_picked_entry_onto_the_selection() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(&mut app, [KeyCode::Char('j'), KeyCode::Char('m')]);
+    assert_eq!(
+        app.mode(),
+        &Mode::Move {
+            source: "h0".into()
+        }
+    );
+    press(
+        &mut app,
+        [KeyCode::Char('l'), KeyCode::Char('j'), KeyCode::Char('m')],
+    );
+    assert_eq!(
+        app.ops.calls,
+        ["rub h0 1a2b"],
+        "it's like amending the commit"
+    );
+    assert_eq!(
+        app.selected(),
+        Some(("1a2b", Some(EntryKind::Commit))),
+        "the target stays selected"
+    );
+    Ok(())
+}
+
+#[test]
+fn failed_operations_are_shown_without_leaving() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(
+        &mut app,
+        [
+            KeyCode::Right,
+            KeyCode::Char('m'),
+            KeyCode::Left,
+            KeyCode::Enter,
+        ],
+    );
+    assert_eq!(app.ops.calls, ["rub g0 zz"]);
+    assert_eq!(
+        app.message(),
+        Some(&Message::Error("Cannot rub g0 onto zz".into()))
+    );
+    assert!(render(&app)?.contains("Cannot rub g0 onto zz"));
+    assert!(!app.should_quit());
+    Ok(())
+}
+
+#[test]
+fn diffs_can_be_viewed_and_scrolled() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(&mut app, [KeyCode::Char('j'), KeyCode::Enter]);
+    assert_eq!(app.ops.calls, ["diff h0"]);
+    let screen = render(&app)?;
+    assert!(screen.contains("Diff of h0"), "{screen}");
+    assert!(screen.contains("│-old"), "{screen}");
+
+    press(&mut app, [KeyCode::Char('j')]);
+    let Mode::Diff { scroll, .. } = app.mode() else {
+        bail!("still in the diff view");
+    };
+    assert_eq!(*scroll, 1);
+    assert!(!render(&app)?.contains("──╮"), "the header scrolled away");
+
+    press(&mut app, [KeyCode::Esc]);
+    assert_eq!(app.mode(), &Mode::Browse);
+    assert!(!app.should_quit(), "escape only leaves the diff");
+    Ok(())
+}
+
+#[test]
+fn undo_restores_the_previous_state() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    press(
+        &mut app,
+        [KeyCode::Char('j'), KeyCode::Char('s'), KeyCode::Enter],
+    );
+    assert!(app.snapshot().lanes[0].entries.is_empty());
+
+    press(&mut app, [KeyCode::Char('u')]);
+    assert_eq!(app.ops.calls, ["rub h0 g0", "undo"]);
+    assert_eq!(entry_ids(&app.snapshot().lanes[0]), ["h0", "i0"]);
+    assert_eq!(
+        app.message(),
+        Some(&Message::Info("Undid the last operation.".into()))
+    );
+
+    press(&mut app, [KeyCode::Char('u')]);
+    assert_eq!(
+        app.message(),
+        Some(&Message::Error("Nothing to undo".into()))
+    );
+    Ok(())
+}
+
+#[test]
+fn run_until_quit() -> anyhow::Result<()> {
+    let mut app = App::new(Workspace::new())?;
+    let mut terminal = Terminal::new(TestBackend::new(80, 12))?;
+    let mut events = [
+        KeyCode::Char('j'),
+        KeyCode::Char('s'),
+        KeyCode::Enter,
+        KeyCode::Char('q'),
+    ]
+    .into_iter()
+    .map(|code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    super::run(&mut terminal, &mut app, || {
+        events
+            .next()
+            .ok_or_else(|| std::io::Error::other("ran out of events"))
+    })?;
+    assert!(app.should_quit());
+    assert_eq!(app.ops.calls, ["rub h0 g0"]);
+    Ok(())
+}
+
+/// A workspace with an unassigned file and two stacks, which records all operations.
+struct Workspace {
+    snapshot: Snapshot,
+    history: Vec<Snapshot>,
+    calls: Vec<String>,
+}
+
+impl Workspace {
+    fn new() -> Self {
+        let entry = |id: &str, kind, label: &str| Entry {
+            id: id.into(),
+            kind,
+            label: label.into(),
+        };
+        Workspace {
+            snapshot: Snapshot {
+                lanes: vec![
+                    Lane {
+                        id: "zz".into(),
+                        title: "Unassigned changes".into(),
+                        entries: vec![
+                            entry("h0", EntryKind::File, "a.txt"),
+                            entry("i0", EntryKind::Hunk, "@@ -1,1 +1,1 @@"),
+                        ],
+                    },
+                    Lane {
+                        id: "g0".into(),
+                        title: "feature".into(),
+                        entries: vec![
+                            entry("k0", EntryKind::Branch, "feature"),
+                            entry("1a2b", EntryKind::Commit, "add feature"),
+                        ],
+                    },
+                    Lane {
+                        id: "j0".into(),
+                        title: "other".into(),
+                        entries: vec![entry("l0", EntryKind::Branch, "other")],
+                    },
+                ],
+            },
+            history: Vec::new(),
+            calls: Vec::new(),
+        }
+    }
+}
+
+impl Operations for Workspace {
+    fn snapshot(&mut self) -> anyhow::Result<Snapshot> {
+        Ok(self.snapshot.clone())
+    }
+
+    /// Uncommitted changes can be moved to lanes, where they are appended, or to commits, where they vanish.
+    fn rub(&mut self, source: &str, target: &str) -> anyhow::Result<()> {
+        self.calls.push(format!("rub {source} {target}"));
+        let lanes = &mut self.snapshot.lanes;
+        let Some((lane_idx, idx)) = lanes.iter().enumerate().find_map(|(lane_idx, lane)| {
+            lane.entries
+                .iter()
+                .position(|e| e.id == source && e.kind.is_uncommitted())
+                .map(|idx| (lane_idx, idx))
+        }) else {
+            bail!("Cannot rub {source} onto {target}");
+        };
+        self.history.push(self.snapshot.clone());
+        let lanes = &mut self.snapshot.lanes;
+        let entries = &mut lanes[lane_idx].entries;
+        let len = 1 + entries[idx + 1..]
+            .iter()
+            .take_while(|e| entries[idx].kind == EntryKind::File && e.kind == EntryKind::Hunk)
+            .count();
+        let moved: Vec<_> = entries.drain(idx..idx + len).collect();
+        if let Some(target_lane) = lanes.iter_mut().find(|lane| lane.id == target) {
+            target_lane.entries.extend(moved);
+        }
+        Ok(())
+    }
+
+    fn diff(&mut self, id: &str) -> anyhow::Result<String> {
+        self.calls.push(format!("diff {id}"));
+        Ok(format!(
+            "──╮\n{id} a.txt│\n──╯\n   1  │-old\n      1│+new\n"
+        ))
+    }
+
+    fn undo(&mut self) -> anyhow::Result<()> {
+        self.calls.push("undo".into());
+        let Some(previous) = self.history.pop() else {
+            bail!("Nothing to undo");
+        };
+        self.snapshot = previous;
+        Ok(())
+    }
+}
+
+fn press(app: &mut App<Workspace>, codes: impl IntoIterator<Item = KeyCode>) {
+    for code in codes {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+}
+
+fn entry_ids(lane: &Lane) -> Vec<&str> {
+    lane.entries.iter().map(|e| e.id.as_str()).collect()
+}
+
+/// Draw `app` headlessly and return what's on the screen, line by line.
+fn render(app: &App<Workspace>) -> anyhow::Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 12))?;
+    terminal.draw(|frame| super::render::draw(frame, app))?;
+    let buffer = terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..buffer.area.height {
+        for x in 0..buffer.area.width {
+            screen.push_str(buffer[(x, y)].symbol());
+        }
+        screen.push('\n');
+    }
+    Ok(screen)
+}
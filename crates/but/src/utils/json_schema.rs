@@ -0,0 +1,227 @@
+//! The versioned envelope all JSON output of `but` is wrapped in, along with the JSON Schemas
+//! describing the payloads of each command.
+//!
+//! The schemas are published in `crates/but/schemas/v<SCHEMA_VERSION>/`, and a test makes sure
+//! they can't change without bumping [`SCHEMA_VERSION`].
+
+use std::collections::BTreeMap;
+
+use schemars::{JsonSchema, Schema};
+use serde::Serialize;
+
+use crate::args::metrics::CommandName;
+
+/// The version of the JSON output of all commands.
+///
+/// It must be incremented whenever the schema of any payload, or of the envelope itself, changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The envelope each JSON value written by a command is wrapped in.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope<T> {
+    /// The version of the schema of this envelope and its `data`, see [`SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// The command that produced `data`, which determines its schema.
+    ///
+    /// It's described as plain string so new commands don't change the schema of the envelope.
+    #[schemars(with = "String")]
+    pub kind: CommandName,
+    /// The output of the command.
+    pub data: T,
+}
+
+impl<T> Envelope<T> {
+    /// Wrap `data` as produced by the command identified by `kind`.
+    pub fn new(kind: CommandName, data: T) -> Self {
+        Envelope {
+            schema_version: SCHEMA_VERSION,
+            kind,
+            data,
+        }
+    }
+}
+
+/// Return the schemas of the output of all kinds, keyed by the name of the kind,
+/// along with the schema of the envelope itself, keyed by `envelope`.
+///
+/// Each kind of command that writes JSON must be listed here.
+pub fn schemas() -> BTreeMap<String, Schema> {
+    use schemars::schema_for;
+
+    let mut out = BTreeMap::new();
+    out.insert("envelope".into(), schema_for!(Envelope<serde_json::Value>));
+    let mut insert = |kind: CommandName, schema: Schema| {
+        out.insert(kind_name(kind), schema);
+    };
+    {
+        use crate::command::{alias, graph};
+        insert(CommandName::Log, crate::command::log::schema());
+        insert(CommandName::Graph, schema_for!(graph::GraphOutput));
+        insert(CommandName::AliasCheck, schema_for!(alias::AliasListOutput));
+        insert(CommandName::AliasAdd, schema_for!(alias::AliasAddOutput));
+        insert(
+            CommandName::AliasRemove,
+            schema_for!(alias::AliasRemoveOutput),
+        );
+        insert(CommandName::SecretMigrate, schema_for!(Vec<String>));
+        insert(
+            CommandName::BranchApply,
+            schema_for!(but_api::json::Reference),
+        );
+    }
+    #[cfg(feature = "legacy")]
+    {
+        use crate::command::legacy::{
+            absorb, batch, branch, claude, discard, forge, oplog, patch, pull, push, rebase,
+            resolve, show, status, worktree,
+        };
+        insert(
+            CommandName::Status,
+            schema_for!(status::json::WorkspaceStatus),
+        );
+        insert(
+            CommandName::BranchList,
+            schema_for!(branch::json::BranchListOutput),
+        );
+        insert(
+            CommandName::BranchNew,
+            schema_for!(branch::json::BranchNewOutput),
+        );
+        insert(
+            CommandName::BranchShow,
+            schema_for!(branch::show::BranchShowOutput),
+        );
+        insert(CommandName::Show, schema_for!(show::ShowOutput));
+        insert(CommandName::Absorb, schema_for!(absorb::JsonAbsorbOutput));
+        insert(CommandName::Discard, schema_for!(discard::DiscardOutput));
+        insert(CommandName::Rebase, schema_for!(rebase::RebaseOutput));
+        insert(CommandName::Resolve, schema_for!(resolve::ResolveOutput));
+        insert(
+            CommandName::Split,
+            schema_for!(but_api::json::UISplitCommitResult),
+        );
+        insert(CommandName::Batch, schema_for!(batch::BatchOutput));
+        insert(CommandName::StagePatch, schema_for!(patch::PatchOutput));
+        insert(CommandName::Push, schema_for!(push::PushOutput));
+        insert(CommandName::Pull, schema_for!(pull::json::PullOutput));
+        insert(
+            CommandName::OplogList,
+            schema_for!(Vec<oplog::SnapshotOutput>),
+        );
+        insert(
+            CommandName::OplogSnapshot,
+            schema_for!(oplog::SnapshotCreatedOutput),
+        );
+        insert(
+            CommandName::PrNew,
+            schema_for!(forge::review::PublishReviewsOutcome),
+        );
+        insert(CommandName::Worktree, schema_for!(worktree::WorktreeOutput));
+        insert(
+            CommandName::ActionsList,
+            schema_for!(but_action::ActionListing),
+        );
+        insert(
+            CommandName::ActionsHandleChanges,
+            schema_for!((String, but_action::Outcome)),
+        );
+        insert(
+            CommandName::ClaudeUsage,
+            schema_for!(but_claude::usage::UsageReport),
+        );
+        insert(CommandName::ClaudeBudget, schema_for!(claude::BudgetOutput));
+        insert(
+            CommandName::ClaudeAudit,
+            schema_for!(Vec<but_claude::permissions::audit::DecisionRecord>),
+        );
+        insert(
+            CommandName::ClaudeTrustPolicy,
+            schema_for!(claude::TrustPolicyOutput),
+        );
+        insert(
+            CommandName::ClaudeLast,
+            schema_for!(Option<claude::LastMessageOutput>),
+        );
+    }
+    out
+}
+
+/// Return the name of `kind` as it appears in the envelope.
+pub fn kind_name(kind: CommandName) -> String {
+    match serde_json::to_value(kind) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => unreachable!("command names serialize as strings"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn published_schemas_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("schemas")
+            .join(format!("v{SCHEMA_VERSION}"))
+    }
+
+    /// Published schemas are frozen, and changing them requires a version bump.
+    ///
+    /// Set `BUT_UPDATE_SCHEMAS=1` to write the schemas that aren't published yet, to be committed
+    /// along with the change that introduced them.
+    #[test]
+    fn published_schemas_match_the_current_version() -> anyhow::Result<()> {
+        let dir = published_schemas_dir();
+        let update = std::env::var_os("BUT_UPDATE_SCHEMAS").is_some_and(|v| v == "1");
+        for (kind, schema) in schemas() {
+            let path = dir.join(format!("{kind}.schema.json"));
+            let generated = serde_json::to_value(&schema)?;
+            match std::fs::read(&path) {
+                Ok(published) => {
+                    let published: serde_json::Value = serde_json::from_slice(&published)?;
+                    assert_eq!(
+                        generated,
+                        published,
+                        "The schema of '{kind}' changed without a version bump. Increment SCHEMA_VERSION \
+                         and run the tests with BUT_UPDATE_SCHEMAS=1 to publish the schemas of version {}.",
+                        SCHEMA_VERSION + 1
+                    );
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound && update => {
+                    std::fs::create_dir_all(&dir)?;
+                    let mut json = serde_json::to_string_pretty(&generated)?;
+                    json.push('\n');
+                    std::fs::write(&path, json)?;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    panic!(
+                        "The schema of '{kind}' isn't published at '{}'. Run the tests with \
+                         BUT_UPDATE_SCHEMAS=1 to write it, and commit it.",
+                        path.display()
+                    );
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn envelope_layout() -> anyhow::Result<()> {
+        let value = serde_json::to_value(Envelope::new(
+            CommandName::BranchNew,
+            serde_json::json!({ "branch": "feature" }),
+        ))?;
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "schemaVersion": SCHEMA_VERSION,
+                "kind": "branchNew",
+                "data": { "branch": "feature" },
+            })
+        );
+        Ok(())
+    }
+}
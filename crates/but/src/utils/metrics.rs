@@ -53,10 +53,13 @@ impl Subcommands {
         OneshotMetricsContext::new_if_enabled(settings, cmd)
     }
 
-    fn to_metrics_command(&self) -> CommandName {
+    /// Return the name of the command, as used in metrics and as kind of its JSON output.
+    pub(crate) fn to_metrics_command(&self) -> CommandName {
         use CommandName::*;
 
-        use crate::args::{alias as alias_args, branch, claude, cursor, forge, worktree};
+        use crate::args::{
+            actions, agent, alias as alias_args, branch, claude, cursor, forge, secret, worktree,
+        };
         match self {
             #[cfg(feature = "legacy")]
             Subcommands::Status { .. } => Status,
@@ -92,6 +95,13 @@ impl Subcommands {
             Subcommands::Unmark => Unmark,
             Subcommands::Gui => Gui,
             #[cfg(feature = "legacy")]
+            Subcommands::Tui => Tui,
+            Subcommands::Graph { .. } => Graph,
+            Subcommands::Log { .. } => Log,
+            Subcommands::Secret(secret::Platform { cmd }) => match cmd {
+                secret::Subcommands::Migrate { .. } => SecretMigrate,
+            },
+            #[cfg(feature = "legacy")]
             Subcommands::Commit { .. } => Commit,
             #[cfg(feature = "legacy")]
             Subcommands::Push(_) => Push,
@@ -100,6 +110,10 @@ impl Subcommands {
             #[cfg(feature = "legacy")]
             Subcommands::Reword { .. } => Reword,
             #[cfg(feature = "legacy")]
+            Subcommands::Split { .. } => Split,
+            #[cfg(feature = "legacy")]
+            Subcommands::Rebase { .. } => Rebase,
+            #[cfg(feature = "legacy")]
             Subcommands::Oplog(crate::args::oplog::Platform { cmd }) => match cmd {
                 None => OplogList,
                 Some(crate::args::oplog::Subcommands::List { .. }) => OplogList,
@@ -114,8 +128,12 @@ impl Subcommands {
                 claude::Subcommands::PreTool => ClaudePreTool,
                 claude::Subcommands::PostTool => ClaudePostTool,
                 claude::Subcommands::Stop => ClaudeStop,
-                claude::Subcommands::Last { .. }
-                | claude::Subcommands::PermissionPromptMcp { .. } => Unknown,
+                claude::Subcommands::Usage => ClaudeUsage,
+                claude::Subcommands::Budget { .. } => ClaudeBudget,
+                claude::Subcommands::Audit { .. } => ClaudeAudit,
+                claude::Subcommands::TrustPolicy => ClaudeTrustPolicy,
+                claude::Subcommands::Last { .. } => ClaudeLast,
+                claude::Subcommands::PermissionPromptMcp { .. } => Unknown,
             },
             #[cfg(feature = "legacy")]
             Subcommands::Cursor(cursor::Platform { cmd }) => match cmd {
@@ -123,16 +141,27 @@ impl Subcommands {
                 cursor::Subcommands::Stop { .. } => CursorStop,
             },
             #[cfg(feature = "legacy")]
+            Subcommands::Agent(agent::Platform { cmd }) => match cmd {
+                agent::Subcommands::Hook { .. } => AgentHook,
+            },
+            #[cfg(feature = "legacy")]
             Subcommands::Absorb { .. } => Absorb,
             #[cfg(feature = "legacy")]
             Subcommands::Discard { .. } => Discard,
             #[cfg(feature = "legacy")]
+            Subcommands::Batch => Batch,
+            #[cfg(feature = "legacy")]
             Subcommands::Pr(forge::pr::Platform { cmd }) => match cmd {
                 None | Some(forge::pr::Subcommands::New { .. }) => PrNew,
                 Some(forge::pr::Subcommands::Template { .. }) => PrTemplate,
             },
             #[cfg(feature = "legacy")]
-            Subcommands::Actions(_) | Subcommands::Mcp { .. } | Subcommands::Init { .. } => Unknown,
+            Subcommands::Actions(actions::Platform { cmd }) => match cmd {
+                None => ActionsList,
+                Some(actions::Subcommands::HandleChanges { .. }) => ActionsHandleChanges,
+            },
+            #[cfg(feature = "legacy")]
+            Subcommands::Mcp { .. } | Subcommands::Init { .. } => Unknown,
             Subcommands::Forge(forge::integration::Platform { cmd }) => match cmd {
                 forge::integration::Subcommands::Auth => ForgeAuth,
                 forge::integration::Subcommands::Forget { .. } => ForgeForget,
@@ -155,6 +184,8 @@ impl Subcommands {
             #[cfg(feature = "legacy")]
             Subcommands::Amend { .. } => Rub,
             #[cfg(feature = "legacy")]
+            Subcommands::Stage { patch: true, .. } => StagePatch,
+            #[cfg(feature = "legacy")]
             Subcommands::Stage { .. } => Rub,
             #[cfg(feature = "legacy")]
             Subcommands::Unstage { .. } => Rub,
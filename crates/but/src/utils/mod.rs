@@ -1,5 +1,6 @@
 use std::io::Write;
 
+pub mod json_schema;
 mod output_channel;
 pub use output_channel::{InputOutputChannel, OutputChannel};
 
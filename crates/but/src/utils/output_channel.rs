@@ -1,4 +1,7 @@
-use crate::{args::OutputFormat, utils::json_pretty_to_stdout};
+use crate::{
+    args::{OutputFormat, metrics::CommandName},
+    utils::{json_pretty_to_stdout, json_schema::Envelope},
+};
 use minus::ExitStrategy;
 use std::io::{IsTerminal, Write};
 
@@ -6,6 +9,8 @@ use std::io::{IsTerminal, Write};
 pub struct OutputChannel {
     /// How to print the output, one should match on it. Match on this if you prefer this style.
     format: OutputFormat,
+    /// The kind of JSON values written by the current command, see [`Envelope`].
+    kind: CommandName,
     /// The output to use if there is no pager.
     inner: std::io::Stdout,
     /// Possibly a pager we are using. If `Some`, our `inner` is the pager itself which we interact with from here.
@@ -114,11 +119,17 @@ impl OutputChannel {
 
 /// JSON utilities
 impl OutputChannel {
-    /// Write `value` as pretty JSON to the output.
+    /// Write `value` as pretty JSON to the output, wrapped into a versioned [`Envelope`]
+    /// of the kind set with [`Self::set_kind()`].
     ///
     /// Note that it's owned to avoid double-printing with [ResultJsonExt::output_json]
     pub fn write_value(&mut self, value: impl serde::Serialize) -> std::io::Result<()> {
-        json_pretty_to_stdout(&value)
+        json_pretty_to_stdout(&Envelope::new(self.kind, value))
+    }
+
+    /// Set the `kind` of the command whose JSON values are written by [`Self::write_value()`].
+    pub fn set_kind(&mut self, kind: CommandName) {
+        self.kind = kind;
     }
 }
 
@@ -171,6 +182,12 @@ impl std::fmt::Write for InputOutputChannel<'_> {
 impl InputOutputChannel<'_> {
     /// Prompt a non-empty string from the user, or `None` if the input was empty.
     pub fn prompt(&mut self, prompt: impl AsRef<str>) -> anyhow::Result<Option<String>> {
+        Ok(self.prompt_line(prompt)?.filter(|input| !input.is_empty()))
+    }
+
+    /// Prompt a line from the user, which is empty if nothing was typed,
+    /// or `None` if the input ended, e.g. when stdin was closed.
+    pub fn prompt_line(&mut self, prompt: impl AsRef<str>) -> anyhow::Result<Option<String>> {
         use std::fmt::Write;
         let prompt = prompt.as_ref();
         writeln!(self, "{}", prompt)?;
@@ -178,12 +195,10 @@ impl InputOutputChannel<'_> {
         std::io::Write::flush(&mut self.out.inner)?;
 
         let mut input = String::new();
-        self.stdin.read_line(&mut input)?;
-        let input = input.trim().to_owned();
-        if input.is_empty() {
+        if self.stdin.read_line(&mut input)? == 0 {
             return Ok(None);
         }
-        Ok(Some(input))
+        Ok(Some(input.trim().to_owned()))
     }
 }
 
@@ -204,6 +219,7 @@ impl OutputChannel {
     pub fn new_with_optional_pager(format: OutputFormat, use_pager: bool) -> Self {
         OutputChannel {
             format,
+            kind: CommandName::default(),
             inner: std::io::stdout(),
             pager: if !matches!(format, OutputFormat::Human)
                 || std::env::var_os("NOPAGER").is_some()
@@ -228,6 +244,7 @@ impl OutputChannel {
                 OutputFormat::Human | OutputFormat::Shell | OutputFormat::None => format,
                 OutputFormat::Json => OutputFormat::None,
             },
+            kind: CommandName::default(),
             inner: std::io::stdout(),
             pager: None,
         }
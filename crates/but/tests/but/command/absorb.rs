@@ -17,13 +17,16 @@ fn uncommitted_file() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
 ...
 "#]]);
 
@@ -67,7 +70,10 @@ Hint: you can run `but undo` to undo these changes
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
 ...
 
 "#]]);
@@ -145,13 +151,16 @@ Hint: you can run `but undo` to undo these changes
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
 ...
 
 "#]]);
@@ -0,0 +1,113 @@
+use std::{io::Write, process::Stdio};
+
+use anyhow::Context;
+use serde_json::{Value, json};
+
+use crate::utils::Sandbox;
+
+#[test]
+fn session_changes_are_assigned_to_and_committed_on_its_own_branch() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    let session = json!({"agent": "codex", "session_id": "abc", "cwd": env.projects_root()});
+    let event = |name: &str, fields: Value| {
+        let mut event = session.clone();
+        event["event"] = name.into();
+        event
+            .as_object_mut()
+            .expect("object")
+            .extend(fields.as_object().expect("object").clone());
+        event
+    };
+
+    let started = hook(&env, event("session-start", json!({})))?;
+    assert_eq!(started["continue"], true);
+    let stack_id = started["stackId"].clone();
+    assert!(stack_id.is_string(), "the session gets a stack of its own");
+
+    env.file("unrelated.txt", "not written by the agent\n");
+    hook(
+        &env,
+        event(
+            "file-edit",
+            json!({"file_path": "new-file.txt", "stage": "before"}),
+        ),
+    )?;
+    env.file("new-file.txt", "written by the agent\n");
+    let edited = hook(
+        &env,
+        event("file-edit", json!({"file_path": "new-file.txt"})),
+    )?;
+    assert_eq!(
+        edited["stackId"], stack_id,
+        "the edit is assigned to the stack of the session"
+    );
+
+    let stopped = hook(
+        &env,
+        event(
+            "stop",
+            json!({"prompt": "add a new file", "summary": "- add new-file.txt"}),
+        ),
+    )?;
+    assert_eq!(stopped["stackId"], stack_id);
+    let committed = &stopped["committed"];
+    assert_eq!(committed.as_array().map(Vec::len), Some(1), "{stopped}");
+    assert_eq!(committed[0]["stackId"], stack_id);
+    let branch_name = committed[0]["branchName"]
+        .as_str()
+        .context("the branch is named")?;
+    assert_ne!(
+        branch_name, "A",
+        "the session doesn't commit to other branches"
+    );
+
+    let repo = env.open_repo()?;
+    let tip = repo
+        .find_reference(&format!("refs/heads/{branch_name}"))?
+        .peel_to_commit()?;
+    assert_eq!(
+        tip.id.to_string(),
+        committed[0]["commitIds"][0]
+            .as_str()
+            .context("a commit was created")?
+    );
+    assert_eq!(
+        tip.message_raw()?.to_string().trim_end(),
+        "add a new file\n\n- add new-file.txt"
+    );
+    let tree = tip.tree()?;
+    assert!(tree.lookup_entry_by_path("new-file.txt")?.is_some());
+    assert!(
+        tree.lookup_entry_by_path("unrelated.txt")?.is_none(),
+        "changes the session didn't make remain uncommitted"
+    );
+    let tip_of_a = repo.find_reference("refs/heads/A")?.peel_to_commit()?;
+    assert_eq!(tip_of_a.message_raw()?.to_string().trim_end(), "add A");
+    Ok(())
+}
+
+/// Run `but agent hook --protocol generic` with `event` as input, and return its output.
+fn hook(env: &Sandbox, event: Value) -> anyhow::Result<Value> {
+    let mut cmd = std::process::Command::new(snapbox::cmd::cargo_bin!("but"));
+    but_testsupport::isolate_env_std_cmd(&mut cmd)
+        .env("E2E_TEST_APP_DATA_DIR", env.app_data_dir())
+        .current_dir(env.projects_root())
+        .args(["agent", "hook", "--protocol", "generic"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = cmd.spawn()?;
+    child
+        .stdin
+        .take()
+        .context("stdin is piped")?
+        .write_all(event.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert!(
+        output.status.success(),
+        "{event} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
@@ -0,0 +1,79 @@
+use snapbox::str;
+
+use crate::{command::util::commit_file_with_worktree_changes_as_two_hunks, utils::Sandbox};
+
+#[test]
+fn operations_are_applied_in_order() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+    commit_file_with_worktree_changes_as_two_hunks(&env, "A", "a.txt");
+
+    env.but("batch")
+        .stdin(
+            r#"
+# Stage the change first, then commit it.
+stage i0 A
+["commit", "A", "-m", "change a.txt"]
+"#,
+        )
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+...
+Applied 2 operations. Run `but restore [..]` to revert them all.
+
+"#]])
+        .stderr_eq(str![""]);
+
+    let repo = env.open_repo()?;
+    assert!(
+        repo.rev_parse_single(":/change a.txt").is_ok(),
+        "the staged change was committed"
+    );
+    Ok(())
+}
+
+#[test]
+fn failing_operation_rolls_back_the_batch() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+    commit_file_with_worktree_changes_as_two_hunks(&env, "A", "a.txt");
+
+    env.but("batch")
+        .stdin("commit A -m 'change a.txt'\nrub nonexistent1 nonexistent2\n")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Error: Rolled back all operations of the batch.
+...
+"#]]);
+
+    let repo = env.open_repo()?;
+    assert!(
+        repo.rev_parse_single(":/change a.txt").is_err(),
+        "the commit was undone"
+    );
+    Ok(())
+}
+
+#[test]
+fn unsupported_operations_are_rejected_before_running_any() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+
+    env.but("batch")
+        .stdin("commit A -m 'nothing'\npush A\n")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Error: Invalid operation on line 2: `push A`
+...
+"#]]);
+
+    let repo = env.open_repo()?;
+    assert!(repo.rev_parse_single(":/nothing").is_err(), "nothing ran");
+    Ok(())
+}
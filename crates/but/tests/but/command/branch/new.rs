@@ -52,7 +52,11 @@ fn with_json_output() -> anyhow::Result<()> {
         .stderr_eq(str![])
         .stdout_eq(str![[r#"
 {
-  "branch": "my-feature"
+  "schemaVersion": 1,
+  "kind": "branchNew",
+  "data": {
+    "branch": "my-feature"
+  }
 }
 
 "#]]);
@@ -65,8 +69,12 @@ fn with_json_output() -> anyhow::Result<()> {
         .stderr_eq(str![])
         .stdout_eq(str![[r#"
 {
-  "branch": "my-anchored-feature",
-  "anchor": "9477ae7"
+  "schemaVersion": 1,
+  "kind": "branchNew",
+  "data": {
+    "branch": "my-anchored-feature",
+    "anchor": "9477ae7"
+  }
 }
 
 "#]]);
@@ -79,7 +87,11 @@ fn with_json_output() -> anyhow::Result<()> {
         .stderr_eq(str![])
         .stdout_eq(str![[r#"
 {
-  "branch": "my-feature"
+  "schemaVersion": 1,
+  "kind": "branchNew",
+  "data": {
+    "branch": "my-feature"
+  }
 }
 
 "#]]);
@@ -90,8 +102,12 @@ fn with_json_output() -> anyhow::Result<()> {
         .stderr_eq(str![])
         .stdout_eq(str![[r#"
 {
-  "branch": "my-anchored-feature",
-  "anchor": "9477ae7"
+  "schemaVersion": 1,
+  "kind": "branchNew",
+  "data": {
+    "branch": "my-anchored-feature",
+    "anchor": "9477ae7"
+  }
 }
 
 "#]]);
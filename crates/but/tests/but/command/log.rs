@@ -0,0 +1,75 @@
+use snapbox::str;
+
+use crate::utils::Sandbox;
+
+#[test]
+fn workspace_history_without_workspace_commit() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    insta::assert_snapshot!(env.git_log()?, @r"
+* edd3eb7 (HEAD -> gitbutler/workspace) GitButler Workspace Commit
+* 9477ae7 (A) add A
+* 0dc3733 (origin/main, origin/HEAD, main) add M
+");
+
+    env.setup_metadata(&["A"])?;
+
+    env.but("log")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+┊╭┄[A] [..]
+┊●   9477ae7[..]add A[..]
+├╯
+┊
+┴ origin/main
+
+"#]]);
+
+    env.but("log A --limit 0")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+┊╭┄[A] [..]
+├╯
+┊
+┴ origin/main
+
+More commits are available, increase the --limit to see them.
+
+"#]]);
+    Ok(())
+}
+
+#[test]
+fn path_filter() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+
+    env.but("log -- A")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+┊╭┄[A] [..]
+┊●   9477ae7[..]add A[..]
+├╯
+┊
+┴ origin/main
+
+"#]]);
+
+    env.but("log -- M")
+        .assert()
+        .success()
+        .stderr_eq(str![])
+        .stdout_eq(str![[r#"
+┊╭┄[A] [..]
+├╯
+┊
+┴ origin/main
+
+"#]]);
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Stdio},
+};
+
+use anyhow::{Context, bail};
+use serde_json::{Value, json};
+
+use crate::utils::Sandbox;
+
+#[test]
+fn tools_and_resources_are_listed() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    let mut server = Server::start(&env, "")?;
+
+    let tools = server.request("tools/list", json!({}))?;
+    let names: Vec<_> = tools["tools"]
+        .as_array()
+        .context("tools are listed")?
+        .iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    for name in [
+        "gitbutler_update_branches",
+        "gitbutler_status",
+        "gitbutler_commit",
+        "gitbutler_move_changes",
+        "gitbutler_squash_commits",
+        "gitbutler_reorder_stack",
+        "gitbutler_split_branch",
+        "gitbutler_restore_snapshot",
+        "gitbutler_push",
+    ] {
+        assert!(names.contains(&name), "{name} is missing in {names:?}");
+    }
+
+    let resources = server.request("resources/list", json!({}))?;
+    let uris: Vec<_> = resources["resources"]
+        .as_array()
+        .context("resources are listed")?
+        .iter()
+        .filter_map(|resource| resource["uri"].as_str())
+        .collect();
+    assert_eq!(
+        uris.first(),
+        Some(&"gitbutler://worktree/diff"),
+        "the diff of uncommitted changes comes first"
+    );
+    assert!(
+        uris.iter()
+            .any(|uri| uri.starts_with("gitbutler://commit/") && uri.ends_with("/diff")),
+        "each commit in the workspace has a diff: {uris:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn commit_is_undone_by_restoring_its_snapshot() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    let stack_id = env.setup_metadata(&["A"])?[0].to_string();
+    env.file("new-file.txt", "test content\n");
+    let mut server = Server::start(&env, "--allow write")?;
+
+    let diff = server.read_resource("gitbutler://worktree/diff")?;
+    assert!(diff.contains("+test content"), "{diff}");
+
+    let stacks = server.call_tool("gitbutler_list_stacks", json!({}))?;
+    assert_eq!(stacks[0]["id"], stack_id);
+    assert_eq!(stacks[0]["heads"][0]["name"], "A");
+
+    let outcome = server.call_tool(
+        "gitbutler_commit",
+        json!({
+            "stackId": stack_id,
+            "branchName": "A",
+            "message": "add new file",
+            "changes": [{"path": "new-file.txt"}],
+        }),
+    )?;
+    let commit_id = outcome["newCommit"]
+        .as_str()
+        .context("a commit was created")?
+        .to_owned();
+    assert_eq!(tip_message(&env, "A")?, "add new file");
+
+    let details = server.call_tool(
+        "gitbutler_commit_details",
+        json!({"commitId": &commit_id[..7]}),
+    )?;
+    assert_eq!(details["changes"][0]["path"], "new-file.txt");
+    let diff = server.read_resource(&format!("gitbutler://commit/{commit_id}/diff"))?;
+    assert!(diff.contains("+test content"), "{diff}");
+
+    let snapshots = server.call_tool("gitbutler_list_snapshots", json!({}))?;
+    assert_eq!(snapshots[0]["details"]["operation"], "CreateCommit");
+    server.call_tool(
+        "gitbutler_restore_snapshot",
+        json!({"snapshotId": snapshots[0]["id"]}),
+    )?;
+    assert_eq!(tip_message(&env, "A")?, "add A");
+    let diff = server.read_resource("gitbutler://worktree/diff")?;
+    assert!(
+        diff.contains("+test content"),
+        "the change is uncommitted again: {diff}"
+    );
+    Ok(())
+}
+
+#[test]
+fn tools_are_limited_to_the_allowed_access() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    let stack_id = env.setup_metadata(&["A"])?[0].to_string();
+
+    let mut server = Server::start(&env, "")?;
+    server.call_tool("gitbutler_status", json!({}))?;
+    let err = server
+        .call_tool(
+            "gitbutler_undo_commit",
+            json!({"stackId": stack_id, "commitId": "A"}),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("--allow write"),
+        "writing has to be allowed explicitly: {err}"
+    );
+    assert_eq!(tip_message(&env, "A")?, "add A", "nothing was changed");
+
+    let mut server = Server::start(&env, "--allow write")?;
+    let err = server
+        .call_tool(
+            "gitbutler_push",
+            json!({"stackId": stack_id, "branchName": "A"}),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("--allow push"),
+        "pushing has to be allowed explicitly: {err}"
+    );
+    Ok(())
+}
+
+#[test]
+fn update_branches_is_available_without_allowing_writes() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    env.setup_metadata(&["A"])?;
+    env.file("new-file.txt", "test content\n");
+    let mut server = Server::start(&env, "")?;
+
+    let outcome = server.call_tool(
+        "gitbutler_update_branches",
+        json!({
+            "fullPrompt": "add a new file",
+            "changesSummary": "- add new-file.txt",
+            "currentWorkingDirectory": env.projects_root(),
+        }),
+    )?;
+    assert_eq!(outcome["updatedBranches"][0]["branchName"], "A");
+    assert_eq!(
+        tip_message(&env, "A")?,
+        "add a new file\n\n- add new-file.txt",
+        "like before `--allow` existed, the changes are committed"
+    );
+    Ok(())
+}
+
+#[test]
+fn undo_restores_the_snapshot_before_the_last_operation() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("one-stack")?;
+    let stack_id = env.setup_metadata(&["A"])?[0].to_string();
+    let mut server = Server::start(&env, "--allow write")?;
+
+    for file in ["first.txt", "second.txt"] {
+        env.file(file, "test content\n");
+        server.call_tool(
+            "gitbutler_commit",
+            json!({
+                "stackId": stack_id,
+                "branchName": "A",
+                "message": format!("add {file}"),
+                "changes": [{"path": file}],
+            }),
+        )?;
+    }
+    let snapshots = server.call_tool("gitbutler_list_snapshots", json!({"limit": 2}))?;
+
+    let restored = server.call_tool("gitbutler_undo", json!({}))?;
+    assert_eq!(
+        restored["id"], snapshots[1]["id"],
+        "like `but undo`, it restores the snapshot before the last one"
+    );
+    Ok(())
+}
+
+/// The message of the commit at the tip of `branch`, without trailing whitespace.
+fn tip_message(env: &Sandbox, branch: &str) -> anyhow::Result<String> {
+    let repo = env.open_repo()?;
+    let mut reference = repo.find_reference(&format!("refs/heads/{branch}"))?;
+    Ok(reference
+        .peel_to_commit()?
+        .message_raw()?
+        .to_string()
+        .trim_end()
+        .to_owned())
+}
+
+/// A `but mcp` process, spoken to with newline-delimited JSON-RPC over its stdin and stdout.
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Server {
+    /// Start the server with `args` in the project of `env`, and initialize the session.
+    fn start(env: &Sandbox, args: &str) -> anyhow::Result<Self> {
+        let mut cmd = std::process::Command::new(snapbox::cmd::cargo_bin!("but"));
+        but_testsupport::isolate_env_std_cmd(&mut cmd)
+            .env("E2E_TEST_APP_DATA_DIR", env.app_data_dir())
+            .current_dir(env.projects_root())
+            .arg("mcp")
+            .args(shell_words::split(args)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+        let mut child = cmd.spawn()?;
+        let stdin = child.stdin.take().context("stdin is piped")?;
+        let stdout = BufReader::new(child.stdout.take().context("stdout is piped")?);
+        let mut server = Server {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+
+        server.request(
+            "initialize",
+            json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0.0"},
+            }),
+        )?;
+        server.send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))?;
+        Ok(server)
+    }
+
+    fn send(&mut self, message: Value) -> anyhow::Result<()> {
+        writeln!(self.stdin, "{message}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Send a request and return the result of its response.
+    fn request(&mut self, method: &str, params: Value) -> anyhow::Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))?;
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                bail!("The server exited before responding to {method}");
+            }
+            let mut message: Value = serde_json::from_str(&line)?;
+            if message["id"] != id {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                bail!("{method} failed: {}", error["message"]);
+            }
+            return Ok(message["result"].take());
+        }
+    }
+
+    /// Call the tool `name` and return its JSON outcome.
+    fn call_tool(&mut self, name: &str, arguments: Value) -> anyhow::Result<Value> {
+        let result = self.request("tools/call", json!({"name": name, "arguments": arguments}))?;
+        let text = result["content"][0]["text"]
+            .as_str()
+            .with_context(|| format!("{name} returned no content: {result}"))?;
+        if result["isError"] == true {
+            bail!("{name} failed: {text}");
+        }
+        Ok(serde_json::from_str(text)?)
+    }
+
+    fn read_resource(&mut self, uri: &str) -> anyhow::Result<String> {
+        let result = self.request("resources/read", json!({"uri": uri}))?;
+        Ok(result["contents"][0]["text"]
+            .as_str()
+            .with_context(|| format!("{uri} has no text: {result}"))?
+            .to_owned())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
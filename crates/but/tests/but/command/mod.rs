@@ -3,12 +3,19 @@
 //! **Only** test the *happy path* of a typical user journey, while keeping details in unit tests with private module access.
 #[cfg(feature = "legacy")]
 mod absorb;
+#[cfg(feature = "legacy")]
+mod agent;
+#[cfg(feature = "legacy")]
+mod batch;
 mod branch;
 #[cfg(feature = "legacy")]
 mod commit;
 mod format;
 mod gui;
 mod help;
+mod log;
+#[cfg(feature = "legacy")]
+mod mcp;
 #[cfg(feature = "legacy")]
 mod reword;
 #[cfg(feature = "legacy")]
@@ -43,17 +43,20 @@ fn uncommitted_file_to_unassigned() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "l0",
-      "assignedChanges": [
-        {
-          "cliId": "i0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "l0",
+        "assignedChanges": [
+          {
+            "cliId": "i0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
 ...
 
 "#]]);
@@ -89,6 +92,57 @@ Assigned all hunks in a.txt in the unassigned area → [A].
     Ok(())
 }
 
+#[test]
+fn ids_shown_by_status_keep_referring_to_their_file() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+    env.file("a.txt", "a\n");
+    env.file("b.txt", "b\n");
+    let status_output = env
+        .but("--json status")
+        .env_remove("BUT_OUTPUT_FORMAT")
+        .output()?;
+    let status_json: serde_json::Value = serde_json::from_slice(&status_output.stdout)?;
+    let id_of = |path: &str| {
+        status_json["data"]["unassignedChanges"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|change| change["filePath"] == path)
+            .and_then(|change| change["cliId"].as_str())
+            .map(ToOwned::to_owned)
+            .expect("the file is listed")
+    };
+    let (a_id, b_id) = (id_of("a.txt"), id_of("b.txt"));
+
+    // Files are added and removed after the IDs were shown, which must not make them refer to other files.
+    env.file("0.txt", "new\n");
+    env.file("a.txt", "a\nchanged\n");
+    std::fs::remove_file(env.projects_root().join("b.txt"))?;
+
+    env.but(format!("rub {a_id} A"))
+        .assert()
+        .success()
+        .stdout_eq(snapbox::str![[r#"
+Assigned all hunks in a.txt in the unassigned area → [A].
+
+"#]])
+        .stderr_eq(str![""]);
+
+    let output = env.but(format!("rub {b_id} A")).output()?;
+    assert!(!output.status.success(), "the file of the ID is gone");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "The ID '{b_id}' is stale as the uncommitted file 'b.txt' it referred to no longer exists"
+        )),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn committed_file_to_unassigned() -> anyhow::Result<()> {
     let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
@@ -105,76 +159,79 @@ fn committed_file_to_unassigned() -> anyhow::Result<()> {
         .stdout_eq(snapbox::str![[r#"
 ...
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "i0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "g0",
-          "name": "A",
-          "commits": [
-            {
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "i0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "g0",
+            "name": "A",
+            "commits": [
+              {
 ...
-              "changes": [
-                {
-                  "cliId": "o0",
-                  "filePath": "a.txt",
-                  "changeType": "modified"
-                },
-                {
-                  "cliId": "p0",
-                  "filePath": "b.txt",
-                  "changeType": "modified"
-                }
-              ]
-            },
-            {
+                "changes": [
+                  {
+                    "cliId": "o0",
+                    "filePath": "a.txt",
+                    "changeType": "modified"
+                  },
+                  {
+                    "cliId": "p0",
+                    "filePath": "b.txt",
+                    "changeType": "modified"
+                  }
+                ]
+              },
+              {
 ...
-              "changes": [
-                {
-                  "cliId": "k0",
-                  "filePath": "a.txt",
-                  "changeType": "added"
-                },
-                {
-                  "cliId": "l0",
-                  "filePath": "b.txt",
-                  "changeType": "added"
-                }
-              ]
-            },
-            {
+                "changes": [
+                  {
+                    "cliId": "k0",
+                    "filePath": "a.txt",
+                    "changeType": "added"
+                  },
+                  {
+                    "cliId": "l0",
+                    "filePath": "b.txt",
+                    "changeType": "added"
+                  }
+                ]
+              },
+              {
 ...
-              "changes": [
-                {
-                  "cliId": "m0",
-                  "filePath": "A",
-                  "changeType": "added"
-                }
-              ]
-            }
+                "changes": [
+                  {
+                    "cliId": "m0",
+                    "filePath": "A",
+                    "changeType": "added"
+                  }
+                ]
+              }
 ...
-    },
-    {
-      "cliId": "j0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "h0",
-          "name": "B",
-          "commits": [
-            {
+      },
+      {
+        "cliId": "j0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "h0",
+            "name": "B",
+            "commits": [
+              {
 ...
-              "changes": [
-                {
-                  "cliId": "n0",
-                  "filePath": "B",
-                  "changeType": "added"
-                }
-              ]
-            }
+                "changes": [
+                  {
+                    "cliId": "n0",
+                    "filePath": "B",
+                    "changeType": "added"
+                  }
+                ]
+              }
 ...
 
 "#]]);
@@ -196,70 +253,73 @@ Unassigned all [B] changes.
         .stderr_eq(snapbox::str![""])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "i0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "g0",
-          "name": "A",
-          "commits": [
-            {
-              "cliId": "e4",
-              "commitId": "e402775bc9360fa967ed55b98aaa4b5a12da4e20",
-              "createdAt": "2000-01-01T00:00:00+00:00",
-              "message": "create a.txt and b.txt",
-              "authorName": "author",
-              "authorEmail": "author@example.com",
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "i0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "g0",
+            "name": "A",
+            "commits": [
+              {
+                "cliId": "e4",
+                "commitId": "e402775bc9360fa967ed55b98aaa4b5a12da4e20",
+                "createdAt": "2000-01-01T00:00:00+00:00",
+                "message": "create a.txt and b.txt",
+                "authorName": "author",
+                "authorEmail": "author@example.com",
 ...
-              "changes": [
-                {
-                  "cliId": "o0",
-                  "filePath": "a.txt",
-                  "changeType": "modified"
-                },
-                {
-                  "cliId": "p0",
-                  "filePath": "b.txt",
+                "changes": [
+                  {
+                    "cliId": "o0",
+                    "filePath": "a.txt",
+                    "changeType": "modified"
+                  },
+                  {
+                    "cliId": "p0",
+                    "filePath": "b.txt",
 ...
-              "changes": [
-                {
-                  "cliId": "k0",
-                  "filePath": "a.txt",
-                  "changeType": "added"
-                },
-                {
-                  "cliId": "l0",
-                  "filePath": "b.txt",
+                "changes": [
+                  {
+                    "cliId": "k0",
+                    "filePath": "a.txt",
+                    "changeType": "added"
+                  },
+                  {
+                    "cliId": "l0",
+                    "filePath": "b.txt",
 ...
-              "changes": [
-                {
-                  "cliId": "m0",
-                  "filePath": "A",
-                  "changeType": "added"
-                }
+                "changes": [
+                  {
+                    "cliId": "m0",
+                    "filePath": "A",
+                    "changeType": "added"
+                  }
 ...
-    },
-    {
-      "cliId": "j0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "h0",
-          "name": "B",
-          "commits": [
-            {
+      },
+      {
+        "cliId": "j0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "h0",
+            "name": "B",
+            "commits": [
+              {
 ...
-              "changes": [
-                {
-                  "cliId": "n0",
-                  "filePath": "B",
-                  "changeType": "added"
-                }
-              ]
-            }
+                "changes": [
+                  {
+                    "cliId": "n0",
+                    "filePath": "B",
+                    "changeType": "added"
+                  }
+                ]
+              }
 ...
 
 "#]]);
@@ -283,17 +343,20 @@ fn shorthand_uncommitted_hunk_to_unassigned() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "l0",
-      "assignedChanges": [
-        {
-          "cliId": "i0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "l0",
+        "assignedChanges": [
+          {
+            "cliId": "i0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
 ...
 
 "#]]);
@@ -340,27 +403,30 @@ Unassigned a hunk in a.txt in a stack
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
-  "stacks": [
-    {
-      "cliId": "m0",
-      "assignedChanges": [
-        {
-          "cliId": "j0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
-      "branches": [
-        {
-          "cliId": "g0",
-          "name": "A",
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
+    "stacks": [
+      {
+        "cliId": "m0",
+        "assignedChanges": [
+          {
+            "cliId": "j0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
+        "branches": [
+          {
+            "cliId": "g0",
+            "name": "A",
 ...
 
 "#]]);
@@ -419,27 +485,30 @@ Assigned a hunk in a.txt in the unassigned area → [A].
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
-  "stacks": [
-    {
-      "cliId": "m0",
-      "assignedChanges": [
-        {
-          "cliId": "j0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
-      "branches": [
-        {
-          "cliId": "g0",
-          "name": "A",
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
+    "stacks": [
+      {
+        "cliId": "m0",
+        "assignedChanges": [
+          {
+            "cliId": "j0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
+        "branches": [
+          {
+            "cliId": "g0",
+            "name": "A",
 ...
 
 "#]]);
@@ -462,7 +531,7 @@ fn uncommit_command_on_commit() -> anyhow::Result<()> {
         .env_remove("BUT_OUTPUT_FORMAT")
         .output()?;
     let status_json: serde_json::Value = serde_json::from_slice(&status_output.stdout)?;
-    let commit_id = status_json["stacks"][0]["branches"][0]["commits"][0]["cliId"]
+    let commit_id = status_json["data"]["stacks"][0]["branches"][0]["commits"][0]["cliId"]
         .as_str()
         .unwrap();
 
@@ -478,23 +547,26 @@ fn uncommit_command_on_commit() -> anyhow::Result<()> {
         .success()
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "added"
-    },
-    {
-      "cliId": "j0",
-      "filePath": "b.txt",
-      "changeType": "added"
-    }
-  ],
-  "stacks": [
-    {
-      "cliId": "m0",
-      "assignedChanges": [],
-      "branches": [
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "added"
+      },
+      {
+        "cliId": "j0",
+        "filePath": "b.txt",
+        "changeType": "added"
+      }
+    ],
+    "stacks": [
+      {
+        "cliId": "m0",
+        "assignedChanges": [],
+        "branches": [
 ...
 
 "#]]);
@@ -547,17 +619,20 @@ fn stage_command() -> anyhow::Result<()> {
         .success()
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "l0",
-      "assignedChanges": [
-        {
-          "cliId": "i0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "l0",
+        "assignedChanges": [
+          {
+            "cliId": "i0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
 ...
 
 "#]]);
@@ -565,6 +640,24 @@ fn stage_command() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn stage_patch_needs_a_terminal() -> anyhow::Result<()> {
+    let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
+
+    env.setup_metadata(&["A", "B"])?;
+    commit_file_with_worktree_changes_as_two_hunks(&env, "A", "a.txt");
+
+    env.but("stage -p A")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+Failed to stage. Picking hunks needs an interactive terminal. Use `but stage <file-or-hunk> <branch>` instead.
+
+"#]]);
+
+    Ok(())
+}
+
 #[test]
 fn unstage_command() -> anyhow::Result<()> {
     let env = Sandbox::init_scenario_with_target_and_default_settings("two-stacks")?;
@@ -582,17 +675,20 @@ fn unstage_command() -> anyhow::Result<()> {
         .success()
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [],
-  "stacks": [
-    {
-      "cliId": "l0",
-      "assignedChanges": [
-        {
-          "cliId": "i0",
-          "filePath": "a.txt",
-          "changeType": "modified"
-        }
-      ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [],
+    "stacks": [
+      {
+        "cliId": "l0",
+        "assignedChanges": [
+          {
+            "cliId": "i0",
+            "filePath": "a.txt",
+            "changeType": "modified"
+          }
+        ],
 ...
 
 "#]]);
@@ -607,17 +703,20 @@ fn unstage_command() -> anyhow::Result<()> {
         .success()
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
-  "stacks": [
-    {
-      "cliId": "l0",
-      "assignedChanges": [],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
+    "stacks": [
+      {
+        "cliId": "l0",
+        "assignedChanges": [],
 ...
 
 "#]]);
@@ -645,13 +744,16 @@ fn unstage_command_with_branch() -> anyhow::Result<()> {
         .success()
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    }
-  ],
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      }
+    ],
 ...
 
 "#]]);
@@ -672,7 +774,7 @@ fn unstage_command_validation() -> anyhow::Result<()> {
         .env_remove("BUT_OUTPUT_FORMAT")
         .output()?;
     let status_json: serde_json::Value = serde_json::from_slice(&status_output.stdout)?;
-    let commit_id = status_json["stacks"][0]["branches"][0]["commits"][0]["cliId"]
+    let commit_id = status_json["data"]["stacks"][0]["branches"][0]["commits"][0]["cliId"]
         .as_str()
         .unwrap();
 
@@ -91,83 +91,73 @@ fn json_shows_paths_as_strings() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "test-file.txt",
-      "changeType": "added"
-    }
-  ],
-  "stacks": [
-    {
-      "cliId": "k0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "g0",
-          "name": "A",
-          "commits": [
-            {
-              "cliId": "94",
-              "commitId": "9477ae721ab521d9d0174f70e804ce3ff9f6fb56",
-              "createdAt": "2000-01-01T00:00:00+00:00",
-              "message": "add A/n",
-              "authorName": "author",
-              "authorEmail": "author@example.com",
-              "conflicted": false,
-              "reviewId": null,
-              "changes": null
-            }
-          ],
-          "upstreamCommits": [],
-          "branchStatus": "completelyUnpushed",
-          "reviewId": null,
-          "ci": null
-        }
-      ]
-    },
-    {
-      "cliId": "l0",
-      "assignedChanges": [],
-      "branches": [
-        {
-          "cliId": "h0",
-          "name": "B",
-          "commits": [
-            {
-              "cliId": "d3",
-              "commitId": "d3e2ba36c529fbdce8de90593e22aceae21f9b17",
-              "createdAt": "2000-01-01T00:00:00+00:00",
-              "message": "add B/n",
-              "authorName": "author",
-              "authorEmail": "author@example.com",
-              "conflicted": false,
-              "reviewId": null,
-              "changes": null
-            }
-          ],
-          "upstreamCommits": [],
-          "branchStatus": "completelyUnpushed",
-          "reviewId": null,
-          "ci": null
-        }
-      ]
-    }
-  ],
-  "mergeBase": {
-    "cliId": "0d",
-    "commitId": "0dc37334a458df421bf67ea806103bf5004845dd",
-    "createdAt": "2000-01-02T00:00:00+00:00",
-    "message": "add M ",
-    "authorName": "author",
-    "authorEmail": "author@example.com",
-    "conflicted": null,
-    "reviewId": null,
-    "changes": null
-  },
-  "upstreamState": {
-    "behind": 0,
-    "latestCommit": {
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "test-file.txt",
+        "changeType": "added"
+      }
+    ],
+    "stacks": [
+      {
+        "cliId": "k0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "g0",
+            "name": "A",
+            "commits": [
+              {
+                "cliId": "94",
+                "commitId": "9477ae721ab521d9d0174f70e804ce3ff9f6fb56",
+                "createdAt": "2000-01-01T00:00:00+00:00",
+                "message": "add A/n",
+                "authorName": "author",
+                "authorEmail": "author@example.com",
+                "conflicted": false,
+                "reviewId": null,
+                "changes": null
+              }
+            ],
+            "upstreamCommits": [],
+            "branchStatus": "completelyUnpushed",
+            "reviewId": null,
+            "ci": null
+          }
+        ]
+      },
+      {
+        "cliId": "l0",
+        "assignedChanges": [],
+        "branches": [
+          {
+            "cliId": "h0",
+            "name": "B",
+            "commits": [
+              {
+                "cliId": "d3",
+                "commitId": "d3e2ba36c529fbdce8de90593e22aceae21f9b17",
+                "createdAt": "2000-01-01T00:00:00+00:00",
+                "message": "add B/n",
+                "authorName": "author",
+                "authorEmail": "author@example.com",
+                "conflicted": false,
+                "reviewId": null,
+                "changes": null
+              }
+            ],
+            "upstreamCommits": [],
+            "branchStatus": "completelyUnpushed",
+            "reviewId": null,
+            "ci": null
+          }
+        ]
+      }
+    ],
+    "mergeBase": {
       "cliId": "0d",
       "commitId": "0dc37334a458df421bf67ea806103bf5004845dd",
       "createdAt": "2000-01-02T00:00:00+00:00",
@@ -178,7 +168,21 @@ fn json_shows_paths_as_strings() -> anyhow::Result<()> {
       "reviewId": null,
       "changes": null
     },
-    "lastFetched": null
+    "upstreamState": {
+      "behind": 0,
+      "latestCommit": {
+        "cliId": "0d",
+        "commitId": "0dc37334a458df421bf67ea806103bf5004845dd",
+        "createdAt": "2000-01-02T00:00:00+00:00",
+        "message": "add M ",
+        "authorName": "author",
+        "authorEmail": "author@example.com",
+        "conflicted": null,
+        "reviewId": null,
+        "changes": null
+      },
+      "lastFetched": null
+    }
   }
 }
 
@@ -214,49 +218,52 @@ fn uncommitted_and_committed_file_cli_ids() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 {
-  "unassignedChanges": [
-    {
-      "cliId": "i0",
-      "filePath": "a.txt",
-      "changeType": "modified"
-    },
-    {
-      "cliId": "j0",
-      "filePath": "b.txt",
-      "changeType": "modified"
-    }
-  ],
-  "stacks": [
+  "schemaVersion": 1,
+  "kind": "status",
+  "data": {
+    "unassignedChanges": [
+      {
+        "cliId": "i0",
+        "filePath": "a.txt",
+        "changeType": "modified"
+      },
+      {
+        "cliId": "j0",
+        "filePath": "b.txt",
+        "changeType": "modified"
+      }
+    ],
+    "stacks": [
 ...
-              "message": "edit-a-and-b",
+                "message": "edit-a-and-b",
 ...
-              "changes": [
-                {
-                  "cliId": "s0",
-                  "filePath": "a.txt",
-                  "changeType": "modified"
-                },
-                {
-                  "cliId": "t0",
-                  "filePath": "b.txt",
-                  "changeType": "modified"
-                }
-              ]
+                "changes": [
+                  {
+                    "cliId": "s0",
+                    "filePath": "a.txt",
+                    "changeType": "modified"
+                  },
+                  {
+                    "cliId": "t0",
+                    "filePath": "b.txt",
+                    "changeType": "modified"
+                  }
+                ]
 ...
-              "message": "create-a-and-b",
+                "message": "create-a-and-b",
 ...
-              "changes": [
-                {
-                  "cliId": "p0",
-                  "filePath": "a.txt",
-                  "changeType": "added"
-                },
-                {
-                  "cliId": "q0",
-                  "filePath": "b.txt",
-                  "changeType": "added"
-                }
-              ]
+                "changes": [
+                  {
+                    "cliId": "p0",
+                    "filePath": "a.txt",
+                    "changeType": "added"
+                  },
+                  {
+                    "cliId": "q0",
+                    "filePath": "b.txt",
+                    "changeType": "added"
+                  }
+                ]
 ...
 
 "#]]);
@@ -301,40 +308,40 @@ fn long_cli_ids_json() -> anyhow::Result<()> {
         .stderr_eq(snapbox::str![])
         .stdout_eq(snapbox::str![[r#"
 ...
-          "commits": [
-            {
-              "cliId": "5c8",
-              "commitId": "5c88a8ec10067ef547f14b467776d3584cd683ea",
-              "createdAt": "[RFC_TIMESTAMP]",
-              "message": "add A13/n",
+            "commits": [
+              {
+                "cliId": "5c8",
+                "commitId": "5c88a8ec10067ef547f14b467776d3584cd683ea",
+                "createdAt": "[RFC_TIMESTAMP]",
+                "message": "add A13/n",
 ...
-            {
-              "cliId": "a1",
-              "commitId": "a18ea48cd317c7c8fc9317b6f2427be4cdb2585d",
-              "createdAt": "[RFC_TIMESTAMP]",
-              "message": "add A12/n",
+              {
+                "cliId": "a1",
+                "commitId": "a18ea48cd317c7c8fc9317b6f2427be4cdb2585d",
+                "createdAt": "[RFC_TIMESTAMP]",
+                "message": "add A12/n",
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
+              {
 ...
-            {
-              "cliId": "5c7",
-              "commitId": "5c7c6d7f3854bb61978b410b1ae8146be9948b26",
-              "createdAt": "[RFC_TIMESTAMP]",
-              "message": "add A3/n",
+              {
+                "cliId": "5c7",
+                "commitId": "5c7c6d7f3854bb61978b410b1ae8146be9948b26",
+                "createdAt": "[RFC_TIMESTAMP]",
+                "message": "add A3/n",
 ...
 
 "#]]);
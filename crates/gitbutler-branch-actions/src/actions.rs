@@ -2,6 +2,7 @@ use anyhow::{Context as _, Result};
 use but_core::DiffSpec;
 use but_ctx::{Context, access::WorktreeWritePermission};
 use but_oxidize::{ObjectIdExt, OidExt};
+use but_rebase::graph_rebase::todo::TodoList;
 use but_workspace::legacy::{commit_engine, stack_heads_info, ui};
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
 use gitbutler_operating_modes::ensure_open_workspace_mode;
@@ -24,6 +25,7 @@ use crate::{
     branch_upstream_integration,
     branch_upstream_integration::IntegrationStrategy,
     file::RemoteBranchFile,
+    interactive_rebase,
     move_branch::MoveBranchResult,
     move_commits::{self, MoveCommitIllegalAction},
     remote,
@@ -360,6 +362,25 @@ pub fn reorder_stack(ctx: &Context, stack_id: StackId, stack_order: StackOrder)
     Ok(())
 }
 
+/// Rebase the stack with `stack_id` according to the `edited` todo list, which was obtained
+/// via [`interactive_rebase::todo_list()`] as `original`, and return the commit its top-most
+/// branch points to afterwards.
+pub fn rebase_stack_interactively(
+    ctx: &Context,
+    stack_id: StackId,
+    original: &TodoList,
+    edited: &TodoList,
+) -> Result<gix::ObjectId> {
+    let mut guard = ctx.exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Rebasing a stack requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::InteractiveRebase),
+        guard.write_permission(),
+    );
+    interactive_rebase::rebase_stack(ctx, stack_id, original, edited, guard.write_permission())
+}
+
 pub fn find_git_branches(ctx: &Context, branch_name: &str) -> Result<Vec<RemoteBranchData>> {
     remote::find_git_branches(ctx, branch_name)
 }
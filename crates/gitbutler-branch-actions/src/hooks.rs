@@ -1,6 +1,7 @@
 use but_ctx::Context;
+use but_rebase::RebaseOutput;
 use gitbutler_repo::{
-    hooks::{self, HookResult},
+    hooks::{self, GitHook, HookResult, RewriteCommand},
     staging,
 };
 use gitbutler_stack::BranchOwnershipClaims;
@@ -29,3 +30,27 @@ pub fn pre_commit_with_tree(
 ) -> Result<HookResult, anyhow::Error> {
     hooks::pre_commit_with_tree(ctx, tree_id)
 }
+
+/// Run the `post-rewrite` hook for the commits rewritten by the rebase that produced `output`.
+///
+/// The rebase can't be undone by the hook, so failures are only logged.
+pub(crate) fn post_rewrite_after_rebase(ctx: &Context, output: &RebaseOutput) {
+    let rewritten: Vec<_> = output
+        .commit_mapping
+        .iter()
+        .filter(|(_base, old, new)| old != new)
+        .map(|(_base, old, new)| (*old, *new))
+        .collect();
+    if rewritten.is_empty() {
+        return;
+    }
+    let result = ctx.repo.get().and_then(|repo| {
+        hooks::post_rewrite(
+            &repo,
+            RewriteCommand::Rebase,
+            &rewritten,
+            &ctx.legacy_project.disabled_git_hooks,
+        )
+    });
+    hooks::log_failure(GitHook::PostRewrite, result);
+}
@@ -11,6 +11,7 @@ use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_operating_modes::OPEN_WORKSPACE_REFS;
 use gitbutler_repo::{
     RepositoryExt, SignaturePurpose,
+    hooks::{self, GitHook},
     logging::{LogUntil, RepositoryExt as _},
 };
 use gitbutler_stack::{Stack, VirtualBranchesHandle};
@@ -165,18 +166,48 @@ pub fn update_workspace_commit(
     };
 
     // Create or replace the workspace branch reference, then set as HEAD.
+    let workspace_ref_name = GITBUTLER_WORKSPACE_REFERENCE.clone().to_string();
+    let prev_workspace_id = repo
+        .refname_to_id(&workspace_ref_name)
+        .map(|id| id.to_gix())
+        .unwrap_or_else(|_| gix::ObjectId::null(gix_repo.object_hash()));
     repo.reference(
-        &GITBUTLER_WORKSPACE_REFERENCE.clone().to_string(),
+        &workspace_ref_name,
         final_commit,
         true,
         "updated workspace commit",
     )?;
-    repo.set_head(&GITBUTLER_WORKSPACE_REFERENCE.clone().to_string())?;
+    repo.set_head(&workspace_ref_name)?;
 
     let mut index = repo.index()?;
     index.read_tree(&workspace_tree)?;
     index.write()?;
 
+    let workspace_ref_name: gix::refs::FullName = workspace_ref_name.as_str().try_into()?;
+    hooks::log_failure(
+        GitHook::ReferenceTransaction,
+        hooks::reference_transaction_committed(
+            &gix_repo,
+            &[(
+                prev_workspace_id,
+                final_commit.to_gix(),
+                workspace_ref_name.as_ref(),
+            )],
+            &ctx.legacy_project.disabled_git_hooks,
+        ),
+    );
+    if let (Some(Ok(_)), Some(prev_head_id)) = (&checkout_res, prev_head_id) {
+        hooks::log_failure(
+            GitHook::PostCheckout,
+            hooks::post_checkout(
+                &gix_repo,
+                prev_head_id.to_gix(),
+                final_commit.to_gix(),
+                &ctx.legacy_project.disabled_git_hooks,
+            ),
+        );
+    }
+
     // Everything is written out already, so if we fail here, we do so to surface the error
     // that prevented the checkout to be performed. The operation is still successful, on reload.
     if let Some(res) = checkout_res {
@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use anyhow::{Context as _, Result, bail};
+use bstr::ByteSlice;
+use but_ctx::{Context, access::WorktreeWritePermission};
+use but_rebase::graph_rebase::{
+    Editor, GraphExt, LookupStep as _,
+    todo::{Exec, TodoList},
+};
+use gitbutler_stack::StackId;
+use tracing::instrument;
+
+/// Return the todo list of the stack with `stack_id` as it is right now, from the parent-most commit
+/// to the top-most one.
+pub fn todo_list(ctx: &Context, stack_id: StackId) -> Result<TodoList> {
+    let guard = ctx.shared_worktree_access();
+    let (repo, _meta, graph) =
+        ctx.graph_and_meta_and_repo_from_head(ctx.repo.get()?.clone(), guard.read_permission())?;
+    let editor = graph.to_editor(&repo)?;
+    let (_tip, todo_list) = stack_todo_list(&graph, &editor, stack_id)?;
+    Ok(todo_list)
+}
+
+/// Rebase the stack with `stack_id` according to the `edited` todo list, which was derived from `original`,
+/// and return the commit its top-most branch points to afterwards.
+///
+/// All commits are rewritten in memory first, and `exec` commands run on a checkout of the rewritten commit they
+/// follow. Only if all of them succeed are the rewritten commits, the branches of the stack and the workspace
+/// written, so a failing command leaves everything as it was.
+///
+/// # Errors
+/// - If the stack changed since `original` was obtained.
+/// - If an `exec` command fails.
+#[instrument(level = tracing::Level::DEBUG, skip(ctx, original, edited, perm), err(Debug))]
+pub(crate) fn rebase_stack(
+    ctx: &Context,
+    stack_id: StackId,
+    original: &TodoList,
+    edited: &TodoList,
+    perm: &mut WorktreeWritePermission,
+) -> Result<gix::ObjectId> {
+    let (repo, _meta, graph) = ctx.graph_and_meta_mut_and_repo_from_head(perm)?;
+    let mut editor = graph.to_editor(&repo)?;
+    let (tip, current) = stack_todo_list(&graph, &editor, stack_id)?;
+    if current != *original {
+        bail!("The stack changed while its todo list was edited - please try again");
+    }
+
+    let execs = editor.apply_todo_list(original, edited)?;
+    let outcome = editor.rebase()?;
+    for Exec { commit, command } in &execs {
+        let commit_id = outcome.lookup_pick(*commit)?;
+        run_exec(outcome.repo(), commit_id, command)?;
+    }
+    outcome.materialize()?;
+
+    Ok(repo.find_reference(tip.as_ref())?.peel_to_id()?.detach())
+}
+
+/// Return the name of the top-most branch of the stack with `stack_id` along with its todo list.
+fn stack_todo_list(
+    graph: &but_graph::Graph,
+    editor: &Editor,
+    stack_id: StackId,
+) -> Result<(gix::refs::FullName, TodoList)> {
+    let workspace = graph.to_workspace()?;
+    let stack = workspace.try_find_stack_by_id(stack_id)?;
+    let tip = stack
+        .ref_name()
+        .context("The stack has no branch to rebase")?
+        .to_owned();
+    let base = stack
+        .base()
+        .context("The stack has no base to rebase onto")?;
+    let todo_list = editor.todo_list(tip.as_ref(), base)?;
+    Ok((tip, todo_list))
+}
+
+/// Run `command` in a shell within a temporary checkout of `commit_id`.
+fn run_exec(repo: &gix::Repository, commit_id: gix::ObjectId, command: &str) -> Result<()> {
+    let checkout = tempfile::Builder::new()
+        .prefix("but-rebase-exec-")
+        .tempdir()?;
+    write_tree(repo, commit_id, checkout.path())?;
+
+    let output = std::process::Command::from(gix::command::prepare(command).with_shell())
+        .current_dir(checkout.path())
+        .output()
+        .with_context(|| format!("Failed to launch '{command}'"))?;
+    if !output.status.success() {
+        bail!(
+            "'{command}' failed on commit {commit_id} with {status}:\n{stderr}",
+            status = output.status,
+            stderr = output.stderr.to_str_lossy().trim_end()
+        );
+    }
+    Ok(())
+}
+
+/// Write all files in the tree of `commit_id` into `dir`. Submodules are left empty.
+fn write_tree(repo: &gix::Repository, commit_id: gix::ObjectId, dir: &Path) -> Result<()> {
+    let tree = repo.find_commit(commit_id)?.tree()?;
+    let mut recorder = gix::traverse::tree::Recorder::default();
+    tree.traverse().depthfirst(&mut recorder)?;
+
+    for record in recorder.records {
+        let path = dir.join(gix::path::from_bstr(record.filepath.as_bstr()));
+        if record.mode.is_tree() || record.mode.is_commit() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let blob = repo.find_blob(record.oid)?;
+        #[cfg(unix)]
+        if record.mode.is_link() {
+            std::os::unix::fs::symlink(gix::path::from_bstr(blob.data.as_bstr()), &path)?;
+            continue;
+        }
+        std::fs::write(&path, &blob.data)?;
+        #[cfg(unix)]
+        if record.mode.is_executable() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    Ok(())
+}
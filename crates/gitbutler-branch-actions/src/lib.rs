@@ -7,9 +7,10 @@ pub use actions::{
     find_git_branches, get_initial_integration_steps_for_branch, get_uncommitted_files,
     insert_blank_commit, integrate_branch_with_steps, integrate_upstream,
     integrate_upstream_commits, list_commit_files, move_branch, move_commit, push_base_branch,
-    reorder_stack, resolve_upstream_integration, set_base_branch, set_target_push_remote,
-    squash_commits, tear_off_branch, unapply_stack, undo_commit, update_commit_message,
-    update_stack_order, update_virtual_branch, upstream_integration_statuses,
+    rebase_stack_interactively, reorder_stack, resolve_upstream_integration, set_base_branch,
+    set_target_push_remote, squash_commits, tear_off_branch, unapply_stack, undo_commit,
+    update_commit_message, update_stack_order, update_virtual_branch,
+    upstream_integration_statuses,
 };
 mod squash;
 
@@ -46,6 +47,7 @@ mod move_branch;
 mod move_commits;
 pub mod reorder;
 pub use reorder::{SeriesOrder, StackOrder};
+pub mod interactive_rebase;
 mod undo_commit;
 
 mod author;
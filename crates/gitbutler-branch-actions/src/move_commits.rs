@@ -171,6 +171,7 @@ fn take_commit_from_source_stack(
     let output = rebase.rebase()?;
     let new_source_head = output.top_commit.to_git2();
 
+    crate::hooks::post_rewrite_after_rebase(ctx, &output);
     source_stack.set_heads_from_rebase_output(ctx, output.references)?;
     let vb_state = ctx.legacy_project.virtual_branches();
     source_stack.set_stack_head(&vb_state, &gix_repo, new_source_head)?;
@@ -201,6 +202,7 @@ fn move_commit_to_destination_stack(
     let output = rebase.rebase()?;
     let new_destination_head_oid = output.top_commit.to_git2();
 
+    crate::hooks::post_rewrite_after_rebase(ctx, &output);
     destination_stack.set_heads_from_rebase_output(ctx, output.references)?;
     destination_stack.set_stack_head(vb_state, &gix_repo, new_destination_head_oid)?;
     Ok(())
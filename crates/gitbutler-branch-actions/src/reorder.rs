@@ -64,6 +64,7 @@ pub fn reorder_stack(
     stack.set_stack_head(&state, &gix_repo, new_head)?;
 
     stack.set_heads_from_rebase_output(ctx, output.references.clone())?;
+    crate::hooks::post_rewrite_after_rebase(ctx, &output);
 
     let new_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
     // Even if this fails, it's not actionable
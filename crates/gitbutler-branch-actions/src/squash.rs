@@ -260,6 +260,7 @@ fn do_squash_commits(
     update_uncommitted_changes(ctx, old_workspace, new_workspace, perm)?;
     crate::integration::update_workspace_commit(&vb_state, ctx, false)
         .context("failed to update gitbutler workspace")?;
+    crate::hooks::post_rewrite_after_rebase(ctx, &output);
     stack.set_heads_from_rebase_output(ctx, output.references)?;
     Ok(new_commit_oid)
 }
@@ -1,4 +1,6 @@
-use anyhow::{Context as _, Result};
+use std::collections::BTreeSet;
+
+use anyhow::{Context as _, Result, bail};
 use but_core::RepositoryExt;
 use but_ctx::Context;
 use but_oxidize::{ObjectIdExt, OidExt};
@@ -7,10 +9,11 @@ use gitbutler_oplog::{
     OplogExt, SnapshotExt,
     entry::{OperationKind, SnapshotDetails},
 };
-use gitbutler_reference::normalize_branch_name;
-use gitbutler_repo::hooks;
+use gitbutler_project::AuthKey;
+use gitbutler_reference::{Refname, RemoteRefname, normalize_branch_name};
+use gitbutler_repo::hooks::{self, GitHook};
 use gitbutler_repo_actions::RepoActionsExt;
-use gitbutler_stack::{PatchReferenceUpdate, StackBranch, StackId};
+use gitbutler_stack::{PatchReferenceUpdate, StackBranch, StackId, Target};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
@@ -171,60 +174,31 @@ pub fn push_stack(
 
     drop(git2_repo);
     for branch in stack_branches {
-        let git2_repo = ctx.git2_repo.get()?;
-        if branch.archived {
-            // Nothing to push for this one
-            tracing::debug!(branch = branch.name, "skipping archived branch for pushing");
-            continue;
-        }
-        if branch.head_oid(&gix_repo)? == merge_base_id {
-            // Nothing to push for this one
-            tracing::debug!(
-                branch = branch.name,
-                "nothing to push as head_oid == merge_base"
-            );
-            continue;
-        }
-        let mut graph = gix_repo.revision_graph(cache.as_ref());
-        let mut check_commit =
-            IsCommitIntegrated::new(ctx, &default_target, &gix_repo, &mut graph)?;
-        if branch_integrated(&mut check_commit, &branch, &git2_repo, &gix_repo)? {
-            // Already integrated, nothing to push
-            tracing::debug!(branch = branch.name, "Skipping push for integrated branch");
+        if !needs_push(
+            ctx,
+            &branch,
+            merge_base_id,
+            &default_target,
+            &gix_repo,
+            cache.as_ref(),
+        )? {
             continue;
         }
-        drop(graph);
+        let git2_repo = ctx.git2_repo.get()?;
         let push_details = stack.push_details(ctx, branch.name().to_owned())?;
 
         // Capture the SHA before push (remote ref if exists, otherwise zero)
-        let before_sha = git2_repo
-            .find_reference(&push_details.remote_refname.to_string())
-            .and_then(|r| r.peel_to_commit())
-            .map(|c| c.id())
-            .unwrap_or_else(|_| git2::Oid::zero());
+        let before_sha = remote_sha(&git2_repo, &push_details.remote_refname);
         let local_sha = push_details.head;
 
         if run_hooks {
-            let remote_name = default_target.push_remote_name();
-            let remote = git2_repo.find_remote(&remote_name)?;
-            let url = &remote
-                .url()
-                .with_context(|| format!("Remote named {remote_name} didn't have a URL"))?;
-            match hooks::pre_push(
+            run_pre_push_hook(
                 &git2_repo,
-                &remote_name,
-                url,
+                &default_target.push_remote_name(),
                 push_details.head,
                 &push_details.remote_refname,
-            )? {
-                hooks::HookResult::Success | hooks::HookResult::NotConfigured => {}
-                hooks::HookResult::Failure(error_data) => {
-                    return Err(anyhow::anyhow!(
-                        "pre-push hook failed: {}",
-                        error_data.error
-                    ));
-                }
-            }
+                &ctx.legacy_project.disabled_git_hooks,
+            )?;
         }
 
         let refspec = if gerrit_mode {
@@ -284,6 +258,248 @@ pub fn push_stack(
     Ok(result)
 }
 
+/// The outcome of pushing a single branch with [`push_stacks()`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchPushResult {
+    /// The stack the branch belongs to.
+    pub stack_id: StackId,
+    /// The name of the pushed branch.
+    pub branch_name: String,
+    /// The remote tracking branch that was updated.
+    pub remote_refname: Refname,
+    /// The commit the remote branch pointed to before the push, or the null hash if it didn't exist.
+    pub before_sha: String,
+    /// The commit that was pushed.
+    pub after_sha: String,
+    /// The reason the push failed, if it did.
+    /// Only set if the branches weren't pushed atomically, as otherwise the whole push fails.
+    pub error: Option<String>,
+}
+
+/// The outcome of [`push_stacks()`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StacksPushResult {
+    /// The name of the remote to which the branches were pushed.
+    pub remote: String,
+    /// `true` if all branches were pushed in a single atomic push, or `false` if each branch
+    /// was pushed on its own for the reason in `non_atomic_reason`.
+    pub atomic: bool,
+    /// Why the branches weren't pushed atomically, if they weren't.
+    pub non_atomic_reason: Option<NonAtomicPushReason>,
+    /// All branches that were pushed, or attempted to be pushed, in stack order.
+    pub branches: Vec<BranchPushResult>,
+}
+
+/// The reason [`push_stacks()`] had to fall back to pushing each branch on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NonAtomicPushReason {
+    /// The project is configured to push without the Git executable, which is the only way to push atomically.
+    GitExecutableNotUsed,
+    /// The remote doesn't support atomic pushes.
+    RemoteUnsupported,
+}
+
+impl std::fmt::Display for NonAtomicPushReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NonAtomicPushReason::GitExecutableNotUsed => {
+                "the project isn't configured to push with the Git executable"
+            }
+            NonAtomicPushReason::RemoteUnsupported => "the remote doesn't support atomic pushes",
+        })
+    }
+}
+
+/// Pushes all branches of the stacks with `stack_ids` to the remote in a single push.
+///
+/// If the Git executable is used and the remote supports it, the push is atomic so either all branches
+/// are updated or none of them, and any error fails the whole operation.
+/// Otherwise, each branch is pushed on its own and failures are recorded per branch in the returned result,
+/// along with the reason the push wasn't atomic.
+/// If force-push protection is enabled, it applies to each pushed branch individually.
+///
+/// Branches that are archived, integrated or don't have commits of their own are skipped, just like
+/// in [`push_stack()`]. Gerrit repositories aren't supported as they need a push per change.
+pub fn push_stacks(
+    ctx: &mut Context,
+    stack_ids: &[StackId],
+    with_force: bool,
+    skip_force_push_protection: bool,
+    run_hooks: bool,
+) -> Result<StacksPushResult> {
+    ctx.verify(ctx.exclusive_worktree_access().write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let gix_repo = ctx.clone_repo_for_merging_non_persisting()?;
+    if gix_repo
+        .git_settings()?
+        .gitbutler_gerrit_mode
+        .unwrap_or(false)
+    {
+        bail!(
+            "Pushing multiple stacks at once isn't supported in Gerrit mode, push each branch instead"
+        );
+    }
+    let state = ctx.legacy_project.virtual_branches();
+    let default_target = state.get_default_target()?;
+    let remote = default_target.push_remote_name();
+
+    // First fetch, because we dont want to push integrated series
+    ctx.fetch(&remote, Some("push_stacks".into()))?;
+    let cache = gix_repo.commit_graph_if_enabled()?;
+    let force_push_protection =
+        !skip_force_push_protection && ctx.legacy_project.force_push_protection;
+
+    let mut branches = Vec::new();
+    let mut updates = Vec::new();
+    for stack_id in stack_ids {
+        let stack = state.get_stack(*stack_id)?;
+        let merge_base_id = {
+            let git2_repo = ctx.git2_repo.get()?;
+            git2_repo
+                .merge_base(stack.head_oid(ctx)?.to_git2(), default_target.sha)?
+                .to_gix()
+        };
+        for branch in stack.branches() {
+            if !needs_push(
+                ctx,
+                &branch,
+                merge_base_id,
+                &default_target,
+                &gix_repo,
+                cache.as_ref(),
+            )? {
+                continue;
+            }
+            let git2_repo = ctx.git2_repo.get()?;
+            let push_details = stack.push_details(ctx, branch.name().to_owned())?;
+            if run_hooks {
+                run_pre_push_hook(
+                    &git2_repo,
+                    &remote,
+                    push_details.head,
+                    &push_details.remote_refname,
+                    &ctx.legacy_project.disabled_git_hooks,
+                )?;
+            }
+            branches.push(BranchPushResult {
+                stack_id: stack.id,
+                branch_name: branch.name().to_owned(),
+                remote_refname: push_details.remote_refname.clone().into(),
+                before_sha: remote_sha(&git2_repo, &push_details.remote_refname).to_string(),
+                after_sha: push_details.head.to_string(),
+                error: None,
+            });
+            updates.push((push_details.head, push_details.remote_refname));
+        }
+    }
+
+    let non_atomic_reason = if updates.is_empty()
+        || ctx
+            .push_atomic(&updates, with_force, force_push_protection, Some(None))?
+            .is_some()
+    {
+        None
+    } else if ctx.legacy_project.preferred_key != AuthKey::SystemExecutable {
+        Some(NonAtomicPushReason::GitExecutableNotUsed)
+    } else {
+        Some(NonAtomicPushReason::RemoteUnsupported)
+    };
+    if non_atomic_reason.is_some() {
+        for ((head, remote_refname), branch) in updates.iter().zip(&mut branches) {
+            if let Err(err) = ctx.push(
+                *head,
+                remote_refname,
+                with_force,
+                force_push_protection,
+                None,
+                Some(Some(branch.stack_id)),
+                vec![],
+            ) {
+                branch.error = Some(format!("{err:#}"));
+            }
+        }
+    }
+
+    Ok(StacksPushResult {
+        remote,
+        atomic: non_atomic_reason.is_none(),
+        non_atomic_reason,
+        branches,
+    })
+}
+
+/// Return `true` if `branch` has commits that aren't on the target yet and thus should be pushed.
+fn needs_push(
+    ctx: &Context,
+    branch: &StackBranch,
+    merge_base_id: gix::ObjectId,
+    default_target: &Target,
+    gix_repo: &gix::Repository,
+    cache: Option<&gix::commitgraph::Graph>,
+) -> Result<bool> {
+    if branch.archived {
+        // Nothing to push for this one
+        tracing::debug!(branch = branch.name, "skipping archived branch for pushing");
+        return Ok(false);
+    }
+    if branch.head_oid(gix_repo)? == merge_base_id {
+        // Nothing to push for this one
+        tracing::debug!(
+            branch = branch.name,
+            "nothing to push as head_oid == merge_base"
+        );
+        return Ok(false);
+    }
+    let git2_repo = ctx.git2_repo.get()?;
+    let mut graph = gix_repo.revision_graph(cache);
+    let mut check_commit = IsCommitIntegrated::new(ctx, default_target, gix_repo, &mut graph)?;
+    if branch_integrated(&mut check_commit, branch, &git2_repo, gix_repo)? {
+        // Already integrated, nothing to push
+        tracing::debug!(branch = branch.name, "Skipping push for integrated branch");
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Return the commit `remote_refname` points to, or the null hash if it doesn't exist.
+fn remote_sha(git2_repo: &git2::Repository, remote_refname: &RemoteRefname) -> git2::Oid {
+    git2_repo
+        .find_reference(&remote_refname.to_string())
+        .and_then(|r| r.peel_to_commit())
+        .map(|c| c.id())
+        .unwrap_or_else(|_| git2::Oid::zero())
+}
+
+fn run_pre_push_hook(
+    git2_repo: &git2::Repository,
+    remote_name: &str,
+    head: git2::Oid,
+    remote_refname: &RemoteRefname,
+    disabled_hooks: &BTreeSet<GitHook>,
+) -> Result<()> {
+    let remote = git2_repo.find_remote(remote_name)?;
+    let url = &remote
+        .url()
+        .with_context(|| format!("Remote named {remote_name} didn't have a URL"))?;
+    match hooks::pre_push(
+        git2_repo,
+        remote_name,
+        url,
+        head,
+        remote_refname,
+        disabled_hooks,
+    )? {
+        hooks::HookResult::Success | hooks::HookResult::NotConfigured => Ok(()),
+        hooks::HookResult::Failure(error_data) => Err(anyhow::anyhow!(
+            "pre-push hook failed: {}",
+            error_data.error
+        )),
+    }
+}
+
 pub(crate) fn branch_integrated(
     check_commit: &mut IsCommitIntegrated,
     branch: &StackBranch,
@@ -52,6 +52,7 @@ pub(crate) fn undo_commit(
     let new_head = output.top_commit.to_git2();
     stack.set_stack_head(&vb_state, &repo, new_head)?;
 
+    crate::hooks::post_rewrite_after_rebase(ctx, &output);
     stack.set_heads_from_rebase_output(ctx, output.references)?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx, false)
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
 
 use anyhow::{Context as _, Result, anyhow, bail};
 use bstr::ByteSlice;
@@ -9,7 +12,7 @@ use but_oxidize::{ObjectIdExt, OidExt, git2_to_gix_object_id, gix_to_git2_oid};
 use but_rebase::{RebaseOutput, RebaseStep};
 use but_serde::BStringForFrontend;
 use but_workspace::{legacy::stack_ext::StackDetailsExt, ref_info::Options};
-use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_project::{IntegrationAction, UpstreamIntegrationPolicy};
 use gitbutler_repo::{
     RepositoryExt as _,
     logging::{LogUntil, RepositoryExt as _},
@@ -27,6 +30,22 @@ use crate::{BranchManagerExt, VirtualBranchesExt as _};
 pub struct NameAndStatus {
     pub name: String,
     pub status: BranchStatus,
+    /// The commits that would conflict if the branch was rebased onto the new target,
+    /// in the order they would be rebased.
+    pub conflicts: Vec<CommitConflict>,
+}
+
+/// A commit that is forecast to conflict when it's rebased onto the new target.
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitConflict {
+    /// The id of the commit as it is in the workspace.
+    #[serde(with = "but_serde::object_id")]
+    pub commit_id: gix::ObjectId,
+    /// The first line of the commit message.
+    pub subject: String,
+    /// The paths that would conflict, sorted and without duplicates.
+    pub paths: Vec<PathBuf>,
 }
 
 #[derive(Serialize, PartialEq, Debug)]
@@ -152,6 +171,77 @@ pub struct Resolution {
     pub delete_integrated_branches: bool,
 }
 
+/// The state of a whole stack as seen by an [`UpstreamIntegrationPolicy`].
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PolicyStackState {
+    /// All branches can be updated without conflicts.
+    SafelyUpdatable,
+    /// At least one branch conflicts with the new target.
+    Conflicted,
+    /// All branches were integrated into the new target.
+    Integrated,
+    /// The uncommitted changes of the stack would conflict with the new target.
+    WorktreeConflicted,
+}
+
+/// What an [`UpstreamIntegrationPolicy`] decided for a single stack.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyDecision {
+    /// The state of the stack the decision is based on.
+    pub state: PolicyStackState,
+    /// The action the policy asks for in `state`.
+    pub action: IntegrationAction,
+    /// How to integrate the stack, or `None` if it must be left for the user to decide,
+    /// either because the policy says so or because `action` isn't possible for the stack.
+    pub approach: Option<ResolutionApproach>,
+}
+
+impl StackStatus {
+    /// Decide how to integrate this stack according to `policy`.
+    pub fn decide(&self, policy: &UpstreamIntegrationPolicy) -> PolicyDecision {
+        let state = if self.tree_status == TreeStatus::Conflicted {
+            PolicyStackState::WorktreeConflicted
+        } else if self
+            .branch_statuses
+            .iter()
+            .all(|branch| branch.status == BranchStatus::Integrated)
+        {
+            PolicyStackState::Integrated
+        } else if self
+            .branch_statuses
+            .iter()
+            .any(|branch| matches!(branch.status, BranchStatus::Conflicted { .. }))
+        {
+            PolicyStackState::Conflicted
+        } else {
+            PolicyStackState::SafelyUpdatable
+        };
+
+        let action = match state {
+            PolicyStackState::SafelyUpdatable => policy.safely_updatable,
+            PolicyStackState::Conflicted => policy.conflicted,
+            PolicyStackState::Integrated => policy.integrated,
+            PolicyStackState::WorktreeConflicted => IntegrationAction::Leave,
+        };
+        let approach = match action {
+            IntegrationAction::Rebase => Some(ResolutionApproach::Rebase),
+            IntegrationAction::Merge => Some(ResolutionApproach::Merge),
+            IntegrationAction::Unapply => Some(ResolutionApproach::Unapply),
+            IntegrationAction::Delete => Some(ResolutionApproach::Delete),
+            IntegrationAction::Leave => None,
+        }
+        .filter(|approach| self.resolution_acceptable(approach));
+
+        PolicyDecision {
+            state,
+            action,
+            approach,
+        }
+    }
+}
+
 enum IntegrationResult {
     UpdatedObjects {
         head: git2::Oid,
@@ -269,6 +359,7 @@ fn get_stack_status(
             branch_statuses.push(NameAndStatus {
                 name: branch.name.to_string(),
                 status: BranchStatus::Empty,
+                conflicts: vec![],
             });
 
             continue;
@@ -289,6 +380,7 @@ fn get_stack_status(
             branch_statuses.push(NameAndStatus {
                 name: branch.name.to_string(),
                 status: BranchStatus::Integrated,
+                conflicts: vec![],
             });
 
             continue;
@@ -320,29 +412,57 @@ fn get_stack_status(
         let output = rebase.rebase()?;
         let new_head_oid = output.top_commit.to_git2();
 
-        let any_conflicted = output.commit_mapping.iter().any(|(_base, _old, new)| {
-            if let Ok(commit) = gix_repo.find_commit(*new) {
-                commit.is_conflicted()
-            } else {
-                false
-            }
-        });
+        let conflicts = output
+            .commit_mapping
+            .iter()
+            .filter_map(|(_base, old, new)| forecast_conflict(gix_repo, *old, *new).transpose())
+            .collect::<Result<Vec<_>>>()?;
 
         last_head = new_head_oid;
 
         branch_statuses.push(NameAndStatus {
             name: branch.name.to_string(),
-            status: if any_conflicted {
-                BranchStatus::Conflicted { rebasable: false }
-            } else {
+            status: if conflicts.is_empty() {
                 BranchStatus::SaflyUpdatable
+            } else {
+                BranchStatus::Conflicted { rebasable: false }
             },
+            conflicts,
         });
     }
 
     StackStatus::create(TreeStatus::Empty, branch_statuses)
 }
 
+/// Return the conflict of `rebased`, the in-memory result of rebasing `original`, if it is conflicted.
+fn forecast_conflict(
+    repo: &gix::Repository,
+    original: gix::ObjectId,
+    rebased: gix::ObjectId,
+) -> Result<Option<CommitConflict>> {
+    let rebased = but_core::Commit::from_id(rebased.attach(repo))?;
+    let Some(entries) = rebased.conflict_entries()? else {
+        return Ok(None);
+    };
+    let paths: BTreeSet<_> = entries
+        .ancestor_entries
+        .into_iter()
+        .chain(entries.our_entries)
+        .chain(entries.their_entries)
+        .collect();
+    Ok(Some(CommitConflict {
+        commit_id: original,
+        subject: rebased
+            .message
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_str_lossy()
+            .into_owned(),
+        paths: paths.into_iter().collect(),
+    }))
+}
+
 pub fn upstream_integration_statuses(
     context: &UpstreamIntegrationContext,
 ) -> Result<StackStatuses> {
@@ -609,6 +729,7 @@ pub(crate) fn integrate_upstream(
             // Update the branch heads
             if let Some(output) = rebase_output {
                 stack.set_heads_from_rebase_output(ctx, output.references.clone())?;
+                crate::hooks::post_rewrite_after_rebase(ctx, output);
             }
 
             // Dissociate closed reviews
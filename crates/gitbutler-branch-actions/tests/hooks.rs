@@ -5,7 +5,7 @@ mod tests {
     use git2::{Repository, StatusOptions};
     use gitbutler_branch_actions::hooks;
     use gitbutler_diff::Hunk;
-    use gitbutler_repo::hooks::{ErrorData, HookResult, MessageData, MessageHookResult};
+    use gitbutler_repo::hooks::{ErrorData, GitHook, HookResult, MessageData, MessageHookResult};
     use gitbutler_stack::{BranchOwnershipClaims, OwnershipClaim};
     use gitbutler_testsupport::{Case, Suite};
 
@@ -176,6 +176,104 @@ echo 'commit message' > $1
         Ok(())
     }
 
+    #[test]
+    fn prepare_commit_msg_runs_before_commit_msg() -> anyhow::Result<()> {
+        let suite = Suite::default();
+        let Case { ctx, .. } = &suite.new_case();
+
+        let prepare_hook = b"
+#!/bin/sh
+printf 'ABC-1: ' | cat - \"$1\" > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"
+";
+        let commit_msg_hook = b"
+#!/bin/sh
+grep -q '^ABC-1: ' \"$1\" || exit 1
+";
+        let repo = &*ctx.git2_repo.get()?;
+        git2_hooks::create_hook(repo, git2_hooks::HOOK_PREPARE_COMMIT_MSG, prepare_hook);
+        git2_hooks::create_hook(repo, git2_hooks::HOOK_COMMIT_MSG, commit_msg_hook);
+
+        let message = "commit message".to_owned();
+        assert_eq!(
+            gitbutler_repo::hooks::commit_msg(ctx, message)?,
+            MessageHookResult::Message(MessageData {
+                message: "ABC-1: commit message".to_owned()
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prepare_commit_msg_rejection() -> anyhow::Result<()> {
+        let suite = Suite::default();
+        let Case { ctx, .. } = &suite.new_case();
+
+        let hook = b"
+#!/bin/sh
+echo 'rejected'
+exit 1
+";
+        git2_hooks::create_hook(
+            &*ctx.git2_repo.get()?,
+            git2_hooks::HOOK_PREPARE_COMMIT_MSG,
+            hook,
+        );
+
+        let message = "commit message".to_owned();
+        assert_eq!(
+            gitbutler_repo::hooks::commit_msg(ctx, message)?,
+            MessageHookResult::Failure(ErrorData {
+                error: "rejected\n".to_owned()
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_hooks_are_not_run() -> anyhow::Result<()> {
+        let suite = Suite::default();
+        let mut case = suite.new_case();
+        case.ctx.legacy_project.disabled_git_hooks.extend([
+            GitHook::PreCommit,
+            GitHook::PrepareCommitMsg,
+            GitHook::CommitMsg,
+            GitHook::PostCommit,
+        ]);
+        let ctx = &case.ctx;
+
+        let hook = b"
+#!/bin/sh
+echo 'rejected'
+exit 1
+";
+        {
+            let repo = &*ctx.git2_repo.get()?;
+            for name in [
+                git2_hooks::HOOK_PRE_COMMIT,
+                git2_hooks::HOOK_PREPARE_COMMIT_MSG,
+                git2_hooks::HOOK_COMMIT_MSG,
+                git2_hooks::HOOK_POST_COMMIT,
+            ] {
+                git2_hooks::create_hook(repo, name, hook);
+            }
+        }
+
+        let selected_hunks = BranchOwnershipClaims { claims: vec![] };
+        assert_eq!(
+            hooks::pre_commit(ctx, &selected_hunks)?,
+            HookResult::NotConfigured
+        );
+        assert_eq!(
+            gitbutler_repo::hooks::commit_msg(ctx, "commit message".to_owned())?,
+            MessageHookResult::NotConfigured
+        );
+        assert_eq!(
+            gitbutler_repo::hooks::post_commit(ctx)?,
+            HookResult::NotConfigured
+        );
+        Ok(())
+    }
+
     fn is_file_staged(repo: &Repository, file_path: &str) -> Result<bool, git2::Error> {
         let mut opts = StatusOptions::new();
         opts.show(git2::StatusShow::Index);
@@ -0,0 +1,125 @@
+use gitbutler_branch_actions::upstream_integration::{
+    BranchStatus, NameAndStatus, PolicyDecision, PolicyStackState, ResolutionApproach, StackStatus,
+    TreeStatus,
+};
+use gitbutler_project::{IntegrationAction, UpstreamIntegrationPolicy};
+
+fn stack(tree_status: TreeStatus, statuses: impl IntoIterator<Item = BranchStatus>) -> StackStatus {
+    StackStatus {
+        tree_status,
+        branch_statuses: statuses
+            .into_iter()
+            .enumerate()
+            .map(|(idx, status)| NameAndStatus {
+                name: format!("branch-{idx}"),
+                status,
+                conflicts: vec![],
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn default_policy_rebases_safely_updatable_stacks() {
+    let status = stack(
+        TreeStatus::SaflyUpdatable,
+        [BranchStatus::SaflyUpdatable, BranchStatus::Empty],
+    );
+    assert_eq!(
+        status.decide(&UpstreamIntegrationPolicy::default()),
+        PolicyDecision {
+            state: PolicyStackState::SafelyUpdatable,
+            action: IntegrationAction::Rebase,
+            approach: Some(ResolutionApproach::Rebase),
+        }
+    );
+}
+
+#[test]
+fn default_policy_merges_conflicted_single_branch_stacks() {
+    let status = stack(
+        TreeStatus::Empty,
+        [BranchStatus::Conflicted { rebasable: false }],
+    );
+    assert_eq!(
+        status.decide(&UpstreamIntegrationPolicy::default()),
+        PolicyDecision {
+            state: PolicyStackState::Conflicted,
+            action: IntegrationAction::Merge,
+            approach: Some(ResolutionApproach::Merge),
+        }
+    );
+}
+
+#[test]
+fn merge_is_impossible_for_stacks_with_multiple_branches() {
+    let status = stack(
+        TreeStatus::Empty,
+        [
+            BranchStatus::SaflyUpdatable,
+            BranchStatus::Conflicted { rebasable: false },
+        ],
+    );
+    assert_eq!(
+        status.decide(&UpstreamIntegrationPolicy::default()),
+        PolicyDecision {
+            state: PolicyStackState::Conflicted,
+            action: IntegrationAction::Merge,
+            approach: None,
+        },
+        "the stack is left alone instead"
+    );
+}
+
+#[test]
+fn default_policy_deletes_integrated_stacks() {
+    let status = stack(
+        TreeStatus::Empty,
+        [BranchStatus::Integrated, BranchStatus::Integrated],
+    );
+    assert_eq!(
+        status.decide(&UpstreamIntegrationPolicy::default()),
+        PolicyDecision {
+            state: PolicyStackState::Integrated,
+            action: IntegrationAction::Delete,
+            approach: Some(ResolutionApproach::Delete),
+        }
+    );
+}
+
+#[test]
+fn integrated_stacks_with_uncommitted_changes_cannot_be_deleted() {
+    let status = stack(TreeStatus::SaflyUpdatable, [BranchStatus::Integrated]);
+    assert_eq!(
+        status
+            .decide(&UpstreamIntegrationPolicy::default())
+            .approach,
+        None
+    );
+}
+
+#[test]
+fn worktree_conflicts_are_always_left_alone() {
+    let status = stack(TreeStatus::Conflicted, [BranchStatus::SaflyUpdatable]);
+    assert_eq!(
+        status.decide(&UpstreamIntegrationPolicy::default()),
+        PolicyDecision {
+            state: PolicyStackState::WorktreeConflicted,
+            action: IntegrationAction::Leave,
+            approach: None,
+        }
+    );
+}
+
+#[test]
+fn leave_is_honored() {
+    let policy = UpstreamIntegrationPolicy {
+        conflicted: IntegrationAction::Leave,
+        ..Default::default()
+    };
+    let status = stack(
+        TreeStatus::Empty,
+        [BranchStatus::Conflicted { rebasable: false }],
+    );
+    assert_eq!(status.decide(&policy).approach, None);
+}
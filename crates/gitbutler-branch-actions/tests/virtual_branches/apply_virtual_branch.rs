@@ -561,3 +561,68 @@ fn upstream_integration_status_with_different_branch_pr() {
         StackStatuses::UpToDate => panic!("Expected UpdatesRequired status"),
     }
 }
+
+#[test]
+fn upstream_integration_status_forecasts_conflicting_commits() {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    // Setup: Create a remote branch with commits
+    {
+        fs::write(repo.path().join("file.txt"), "initial").unwrap();
+        let first_commit_oid = repo.commit_all("initial commit");
+        fs::write(repo.path().join("file.txt"), "second").unwrap();
+        repo.commit_all("second commit");
+        repo.push();
+        repo.reset_hard(Some(first_commit_oid));
+    }
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    // Create a virtual branch with a non-conflicting and a conflicting commit
+    {
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest {
+                name: Some("feature-branch".to_string()),
+                ..Default::default()
+            },
+            ctx.exclusive_worktree_access().write_permission(),
+        )
+        .unwrap();
+
+        fs::write(repo.path().join("feature-file.txt"), "feature work").unwrap();
+        gitbutler_branch_actions::create_commit(ctx, stack_entry.id, "feature commit", None)
+            .unwrap();
+        fs::write(repo.path().join("file.txt"), "feature").unwrap();
+        gitbutler_branch_actions::create_commit(ctx, stack_entry.id, "change file\n\nbody", None)
+            .unwrap();
+    }
+
+    let statuses =
+        gitbutler_branch_actions::upstream_integration_statuses(ctx, None, &HashMap::new())
+            .unwrap();
+
+    let StackStatuses::UpdatesRequired { statuses, .. } = statuses else {
+        panic!("Expected UpdatesRequired status");
+    };
+    let branch_status = &statuses[0].1.branch_statuses[0];
+    assert_eq!(
+        branch_status.status,
+        BranchStatus::Conflicted { rebasable: false }
+    );
+    assert_eq!(
+        branch_status.conflicts.len(),
+        1,
+        "only the commit touching file.txt conflicts"
+    );
+    assert_eq!(branch_status.conflicts[0].subject, "change file");
+    assert_eq!(
+        branch_status.conflicts[0].paths,
+        [std::path::PathBuf::from("file.txt")]
+    );
+}
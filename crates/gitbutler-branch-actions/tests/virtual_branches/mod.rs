@@ -72,6 +72,7 @@ mod list;
 mod list_details;
 mod move_commit_to_vbranch;
 mod oplog;
+mod push_stacks;
 mod save_and_unapply_virtual_branch;
 mod set_base_branch;
 mod unapply_without_saving_virtual_branch;
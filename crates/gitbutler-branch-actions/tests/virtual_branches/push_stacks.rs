@@ -0,0 +1,183 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_stack::StackId;
+
+use super::*;
+
+#[test]
+fn all_branches_of_all_stacks_are_pushed_at_once() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let stacks = stacks_with_a_commit_each(repo, ctx, ["first", "second"])?;
+
+    let stack_ids: Vec<_> = stacks.iter().map(|(id, _)| *id).collect();
+    let result =
+        gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, false, false, false)?;
+    assert!(result.atomic, "local remotes support atomic pushes");
+    assert_eq!(result.non_atomic_reason, None);
+    assert_eq!(result.remote, "origin");
+    assert_eq!(
+        result
+            .branches
+            .iter()
+            .map(|b| (
+                b.stack_id,
+                b.branch_name.as_str(),
+                b.after_sha.clone(),
+                b.error.is_none()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (stacks[0].0, "first", stacks[0].1.to_string(), true),
+            (stacks[1].0, "second", stacks[1].1.to_string(), true),
+        ],
+        "each branch is reported with the commit it was pushed at"
+    );
+
+    repo.fetch();
+    let remote_refs: Vec<_> = repo
+        .references()
+        .into_iter()
+        .filter_map(|r| r.name().map(ToOwned::to_owned))
+        .filter(|name| name.starts_with("refs/remotes/origin/"))
+        .collect();
+    for name in ["first", "second"] {
+        assert!(
+            remote_refs.contains(&format!("refs/remotes/origin/{name}")),
+            "{name} was pushed: {remote_refs:?}"
+        );
+    }
+
+    let result =
+        gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, false, false, false)?;
+    assert_eq!(
+        result.branches.len(),
+        2,
+        "pushing again is fine as branches aren't integrated yet"
+    );
+    Ok(())
+}
+
+#[test]
+fn nothing_is_pushed_if_the_remote_rejects_one_branch() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let stacks = stacks_with_a_commit_each(repo, ctx, ["first", "second"])?;
+
+    let remote = remote_repo(repo)?;
+    let hooks_dir = remote.path().join("hooks");
+    fs::create_dir_all(&hooks_dir)?;
+    let hook = hooks_dir.join("update");
+    fs::write(&hook, "#!/bin/sh\ntest \"$1\" != refs/heads/second\n")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    }
+
+    let stack_ids: Vec<_> = stacks.iter().map(|(id, _)| *id).collect();
+    let err = gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, false, false, false)
+        .expect_err("the remote rejects 'second'");
+    assert!(
+        format!("{err:#}").contains("hook declined"),
+        "the reason of the remote is passed on: {err:#}"
+    );
+    for name in ["first", "second"] {
+        assert!(
+            remote
+                .find_reference(&format!("refs/heads/{name}"))
+                .is_err(),
+            "{name} isn't on the remote as the push is atomic"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn force_push_protection_applies_to_each_pushed_branch() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let stacks = stacks_with_a_commit_each(repo, ctx, ["first", "second"])?;
+    let stack_ids: Vec<_> = stacks.iter().map(|(id, _)| *id).collect();
+    gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, false, false, false)?;
+
+    // Someone else pushes to 'second', so the local branch doesn't contain the remote commit.
+    let remote = remote_repo(repo)?;
+    let foreign_id = {
+        let tip = remote
+            .find_reference("refs/heads/second")?
+            .peel_to_commit()?;
+        let signature = git2::Signature::now("other", "other@example.com")?;
+        remote.commit(
+            Some("refs/heads/second"),
+            &signature,
+            &signature,
+            "foreign",
+            &tip.tree()?,
+            &[&tip],
+        )?
+    };
+
+    let err = gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, true, false, false)
+        .expect_err("'second' would lose the foreign commit");
+    assert_eq!(
+        err.downcast_ref::<but_error::Code>(),
+        Some(&but_error::Code::GitForcePushProtection)
+    );
+    assert_eq!(
+        remote.refname_to_id("refs/heads/second")?,
+        foreign_id,
+        "the remote branch is left untouched"
+    );
+
+    let result =
+        gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids[..1], true, false, false)?;
+    assert_eq!(
+        result.branches.len(),
+        1,
+        "only the lease of 'first' is checked, and it's up to date"
+    );
+
+    gitbutler_branch_actions::stack::push_stacks(ctx, &stack_ids, true, true, false)?;
+    assert_eq!(
+        remote.refname_to_id("refs/heads/second")?,
+        stacks[1].1,
+        "without protection, the foreign commit is overwritten"
+    );
+    Ok(())
+}
+
+/// Set the base branch and create a stack for each of `names`, each with a commit of its own.
+fn stacks_with_a_commit_each<const N: usize>(
+    repo: &TestProject,
+    ctx: &mut Context,
+    names: [&str; N],
+) -> anyhow::Result<Vec<(StackId, git2::Oid)>> {
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        ctx.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let mut stacks = Vec::new();
+    for name in names {
+        let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+            ctx,
+            &BranchCreateRequest {
+                name: Some(name.into()),
+                ..Default::default()
+            },
+            ctx.exclusive_worktree_access().write_permission(),
+        )?;
+        fs::write(repo.path().join(format!("{name}.txt")), "content")?;
+        let commit_id = gitbutler_branch_actions::create_commit(ctx, stack_entry.id, name, None)?;
+        stacks.push((stack_entry.id, commit_id));
+    }
+    Ok(stacks)
+}
+
+fn remote_repo(repo: &TestProject) -> anyhow::Result<git2::Repository> {
+    let local = git2::Repository::open(repo.path())?;
+    let url = local
+        .find_remote("origin")?
+        .url()
+        .map(ToOwned::to_owned)
+        .expect("valid UTF-8");
+    Ok(git2::Repository::open(url)?)
+}
@@ -36,4 +36,8 @@ pub enum Error<BE: std::error::Error + core::fmt::Debug + Send + Sync + 'static>
         "the force push was blocked because the remote branch contains commits that would be overwritten"
     )]
     ForcePushProtection(BE),
+    /// An atomic push was requested, but the remote doesn't support it.
+    /// Nothing was pushed.
+    #[error("the remote does not support atomic pushes: {0}")]
+    AtomicPushUnsupported(BE),
 }
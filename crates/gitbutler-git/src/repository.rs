@@ -362,18 +362,25 @@ where
     }
 }
 
-/// Pushes a refspec to the given remote in the repository at the given path.
+/// Pushes `refspecs` to the given remote in the repository at the given path.
 /// Any prompts for the user are passed to the asynchronous callback `on_prompt`,
 /// which should return the user's response or `None` if the operation should be
 /// aborted, in which case an `Err` value is returned from this function.
+///
+/// If `force` and `force_push_protection` are set, each destination ref is leased individually
+/// so only the refs that are pushed are checked against their remote tracking branches.
+///
+/// If `atomic` is set, either all refs are updated on the remote or none of them, which fails with
+/// [`crate::Error::AtomicPushUnsupported`] if the remote doesn't support it.
 #[expect(clippy::too_many_arguments)]
 pub async fn push<P, F, Fut, E, Extra>(
     repo_path: P,
     executor: E,
     remote: &str,
-    refspec: RefSpec,
+    refspecs: Vec<RefSpec>,
     force: bool,
     force_push_protection: bool,
+    atomic: bool,
     on_prompt: F,
     extra: Extra,
     push_opts: Vec<String>,
@@ -386,15 +393,25 @@ where
     Extra: Send + Clone,
 {
     let mut args = vec!["push", "--quiet", "--no-verify"];
+    if atomic {
+        args.push("--atomic");
+    }
 
-    let refspec = refspec.to_string();
+    let leases: Vec<String> = refspecs
+        .iter()
+        .map(|refspec| match &refspec.destination {
+            Some(destination) => format!("--force-with-lease={destination}"),
+            None => "--force-with-lease".into(),
+        })
+        .collect();
+    let refspecs: Vec<String> = refspecs.iter().map(ToString::to_string).collect();
 
     args.push(remote);
-    args.push(&refspec);
+    args.extend(refspecs.iter().map(String::as_str));
 
     if force {
         if force_push_protection {
-            args.push("--force-with-lease");
+            args.extend(leases.iter().map(String::as_str));
             args.push("--force-if-includes");
         } else {
             args.push("--force");
@@ -427,6 +444,14 @@ where
         stderr: stderr.clone(),
     };
 
+    if atomic
+        && stderr
+            .to_lowercase()
+            .contains("does not support --atomic push")
+    {
+        return Err(crate::Error::AtomicPushUnsupported(base_error));
+    }
+
     if status == 1 && force && force_push_protection {
         return Err(crate::Error::ForcePushProtection(base_error));
     }
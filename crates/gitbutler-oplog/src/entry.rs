@@ -164,7 +164,11 @@ pub enum OperationKind {
     AutoHandleChangesBefore,
     AutoHandleChangesAfter,
     SplitBranch,
+    SplitCommit,
+    InteractiveRebase,
     OnDemandSnapshot,
+    /// A Claude session is about to work on a prompt of the user.
+    ClaudeTurn,
     #[default]
     Unknown,
 }
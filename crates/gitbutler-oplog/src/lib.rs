@@ -5,6 +5,8 @@ pub mod reflog;
 mod snapshot;
 pub use snapshot::SnapshotExt;
 mod state;
+mod suppression;
+pub use suppression::{SnapshotSuppression, suppress_snapshots};
 
 /// The name of the file holding our state, useful for watching for changes.
 pub const OPLOG_FILE_NAME: &str = "operations-log.toml";
@@ -26,6 +26,7 @@ use super::{
     entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
     reflog::set_reference_to_oplog,
     state::OplogHandle,
+    suppression::snapshots_suppressed,
 };
 use crate::{entry::Version, reflog::ReflogCommits};
 
@@ -138,6 +139,9 @@ pub trait OplogExt {
 
 impl OplogExt for Context {
     fn prepare_snapshot(&self, perm: &WorktreeReadPermission) -> Result<git2::Oid> {
+        if snapshots_suppressed() {
+            return Ok(git2::Oid::zero());
+        }
         prepare_snapshot(self, perm)
     }
 
@@ -147,6 +151,10 @@ impl OplogExt for Context {
         details: SnapshotDetails,
         perm: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid> {
+        // Trees prepared while snapshots were suppressed are null.
+        if snapshots_suppressed() || snapshot_tree_id.is_zero() {
+            return Ok(git2::Oid::zero());
+        }
         commit_snapshot(
             &self.project_data_dir(),
             &*self.git2_repo.get()?,
@@ -162,6 +170,9 @@ impl OplogExt for Context {
         details: SnapshotDetails,
         perm: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid> {
+        if snapshots_suppressed() {
+            return Ok(git2::Oid::zero());
+        }
         let tree_id = prepare_snapshot(self, perm.read_permission())?;
         commit_snapshot(
             &self.project_data_dir(),
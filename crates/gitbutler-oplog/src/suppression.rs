@@ -0,0 +1,38 @@
+use std::{cell::Cell, marker::PhantomData};
+
+thread_local! {
+    /// The amount of [`SnapshotSuppression`] guards alive on this thread.
+    static SUPPRESSIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Stop [`OplogExt`](crate::OplogExt) from creating snapshots on the current thread for as long as the
+/// returned guard is alive.
+///
+/// This is for running many operations which are already covered by a single snapshot taken beforehand,
+/// so they neither pay for creating their own snapshots nor show up in the operations log individually.
+/// While suppressed, snapshot IDs returned by [`OplogExt`](crate::OplogExt) are null.
+/// Restoring snapshots is not affected.
+pub fn suppress_snapshots() -> SnapshotSuppression {
+    SUPPRESSIONS.with(|count| count.set(count.get() + 1));
+    SnapshotSuppression {
+        _not_send: PhantomData,
+    }
+}
+
+/// A guard to re-enable snapshots when dropped, as returned by [`suppress_snapshots()`].
+#[must_use = "snapshots are only suppressed while the guard is alive"]
+pub struct SnapshotSuppression {
+    /// The guard must be dropped on the thread that created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for SnapshotSuppression {
+    fn drop(&mut self) {
+        SUPPRESSIONS.with(|count| count.set(count.get().saturating_sub(1)));
+    }
+}
+
+/// Return `true` if snapshots are currently suppressed on this thread.
+pub(crate) fn snapshots_suppressed() -> bool {
+    SUPPRESSIONS.with(|count| count.get() > 0)
+}
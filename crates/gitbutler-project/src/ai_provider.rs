@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// The language model provider to use for the AI features of a project.
+///
+/// Credentials aren't stored here, but read from the secret store or environment variables
+/// when the provider is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AiProvider {
+    /// OpenAI, either proxied through GitButler or with the user's own key.
+    #[default]
+    OpenAi,
+    /// A server that implements the OpenAI chat completions API, like a self-hosted vLLM or LM Studio.
+    ///
+    /// The `aiOpenAICompatibleKey` secret or the `OPENAI_COMPATIBLE_API_KEY` environment variable
+    /// are sent along if set. OpenAI credentials are never sent.
+    OpenAiCompatible {
+        /// The base URL of the API, e.g. `http://localhost:8000/v1`.
+        base_url: String,
+        /// The model to use for all requests.
+        model: String,
+    },
+    /// The Anthropic Messages API, authenticated with the `aiAnthropicKey` secret
+    /// or the `ANTHROPIC_API_KEY` environment variable.
+    Anthropic {
+        /// The base URL of the API, or `https://api.anthropic.com` if unset.
+        base_url: Option<String>,
+        /// The model to use for all requests.
+        model: String,
+    },
+    /// A local or self-hosted Ollama server.
+    Ollama {
+        /// The base URL of the server, or `http://localhost:11434` if unset.
+        base_url: Option<String>,
+        /// The model to use for all requests.
+        model: String,
+    },
+}
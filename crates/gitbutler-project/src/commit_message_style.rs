@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// How commit messages of a project should look, in addition to what is learned from its history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitMessageStyle {
+    /// A template that generated commit messages follow, like `feat(scope): summary`
+    /// followed by a body and a `Refs: ABC-123` trailer.
+    #[serde(default)]
+    pub template: Option<String>,
+    /// If `true`, the commit message hook rejects messages that don't match the style
+    /// learned from the recent history of the target branch.
+    #[serde(default)]
+    pub validate: bool,
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// A Git hook that GitButler runs at the point of its own operations that is equivalent to where Git would run it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitHook {
+    PreCommit,
+    PrepareCommitMsg,
+    CommitMsg,
+    PostCommit,
+    PrePush,
+    PostRewrite,
+    PostCheckout,
+    /// Only run in the `committed` state after references were changed, so it can't veto updates.
+    ReferenceTransaction,
+}
+
+impl GitHook {
+    /// The name of the hook's executable in the hooks directory.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            GitHook::PreCommit => "pre-commit",
+            GitHook::PrepareCommitMsg => "prepare-commit-msg",
+            GitHook::CommitMsg => "commit-msg",
+            GitHook::PostCommit => "post-commit",
+            GitHook::PrePush => "pre-push",
+            GitHook::PostRewrite => "post-rewrite",
+            GitHook::PostCheckout => "post-checkout",
+            GitHook::ReferenceTransaction => "reference-transaction",
+        }
+    }
+}
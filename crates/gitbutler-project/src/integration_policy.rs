@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// What to do with a stack when integrating upstream changes automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrationAction {
+    /// Rebase the stack onto the new target.
+    Rebase,
+    /// Merge the new target into the stack. Only possible for stacks with a single branch.
+    Merge,
+    /// Unapply the stack from the workspace.
+    Unapply,
+    /// Delete the stack, which is only possible if all of its branches are integrated.
+    Delete,
+    /// Don't touch the stack, and let the user decide instead.
+    Leave,
+}
+
+/// A declarative policy to decide how each stack is updated when integrating upstream changes
+/// without asking the user, e.g. with `but pull --auto`.
+///
+/// Each field is the action for stacks in the respective state. Stacks whose uncommitted changes
+/// would conflict, or for which the action isn't possible, are always left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UpstreamIntegrationPolicy {
+    /// All branches can be updated without conflicts.
+    pub safely_updatable: IntegrationAction,
+    /// At least one branch conflicts with the new target.
+    pub conflicted: IntegrationAction,
+    /// All branches of the stack were integrated into the new target.
+    pub integrated: IntegrationAction,
+}
+
+impl Default for UpstreamIntegrationPolicy {
+    fn default() -> Self {
+        UpstreamIntegrationPolicy {
+            safely_updatable: IntegrationAction::Rebase,
+            conflicted: IntegrationAction::Merge,
+            integrated: IntegrationAction::Delete,
+        }
+    }
+}
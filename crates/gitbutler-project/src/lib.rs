@@ -1,13 +1,21 @@
+mod ai_provider;
 pub mod api;
+mod commit_message_style;
 mod controller;
 mod default_true;
 pub mod gerrit;
+mod git_hook;
+mod integration_policy;
 mod project;
 mod storage;
 
 use std::path::Path;
 
+pub use ai_provider::AiProvider;
+pub use commit_message_style::CommitMessageStyle;
 use controller::Controller;
+pub use git_hook::GitHook;
+pub use integration_policy::{IntegrationAction, UpstreamIntegrationPolicy};
 pub use project::{
     AddProjectOutcome, ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId,
 };
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeSet,
     path::{Path, PathBuf},
     time,
 };
@@ -6,7 +7,9 @@ use std::{
 use anyhow::Context as _;
 use serde::{Deserialize, Serialize};
 
-use crate::default_true::DefaultTrue;
+use crate::{
+    AiProvider, CommitMessageStyle, GitHook, UpstreamIntegrationPolicy, default_true::DefaultTrue,
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -122,6 +125,22 @@ pub struct Project {
         deserialize_with = "but_forge::deserialize_preferred_forge_user_opt"
     )]
     pub preferred_forge_user: Option<but_forge::ForgeUser>,
+    /// Decides how stacks are updated when upstream changes are integrated automatically.
+    #[serde(default)]
+    pub upstream_integration_policy: UpstreamIntegrationPolicy,
+    /// The language model provider used by the AI features of this project.
+    #[serde(default)]
+    pub ai_provider: AiProvider,
+    /// How commit messages of this project should look.
+    #[serde(default)]
+    pub commit_message_style: CommitMessageStyle,
+    /// Git hooks that shouldn't run for this project, even if they are installed.
+    #[serde(default)]
+    pub disabled_git_hooks: BTreeSet<GitHook>,
+    /// The hash of the Claude permission policy checked into the repository that the user trusted.
+    /// Its `allow` rules are only honoured while the policy still has this hash.
+    #[serde(default)]
+    pub trusted_claude_policy: Option<String>,
 }
 
 impl Project {
@@ -144,6 +163,11 @@ impl Project {
             snapshot_lines_threshold: None,
             forge_override: None,
             preferred_forge_user: None,
+            upstream_integration_policy: Default::default(),
+            ai_provider: Default::default(),
+            commit_message_style: Default::default(),
+            disabled_git_hooks: Default::default(),
+            trusted_claude_policy: None,
         }
     }
 
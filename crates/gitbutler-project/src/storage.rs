@@ -1,9 +1,12 @@
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf};
 
 use anyhow::{Context as _, Result};
 use serde::Deserialize;
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{
+    AiProvider, ApiProject, AuthKey, CodePushState, CommitMessageStyle, FetchResult, GitHook,
+    Project, ProjectId, UpstreamIntegrationPolicy,
+};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -35,6 +38,11 @@ pub struct UpdateRequest {
     #[serde(default = "default_false")]
     pub unset_forge_override: bool,
     pub preferred_forge_user: Option<but_forge::ForgeUser>,
+    pub upstream_integration_policy: Option<UpstreamIntegrationPolicy>,
+    pub ai_provider: Option<AiProvider>,
+    pub commit_message_style: Option<CommitMessageStyle>,
+    pub disabled_git_hooks: Option<BTreeSet<GitHook>>,
+    pub trusted_claude_policy: Option<String>,
 }
 
 impl UpdateRequest {
@@ -60,6 +68,11 @@ impl UpdateRequest {
             forge_override: None,
             unset_forge_override: false,
             preferred_forge_user: None,
+            upstream_integration_policy: None,
+            ai_provider: None,
+            commit_message_style: None,
+            disabled_git_hooks: None,
+            trusted_claude_policy: None,
         }
     }
 }
@@ -83,6 +96,11 @@ impl From<Project> for UpdateRequest {
             snapshot_lines_threshold,
             forge_override,
             preferred_forge_user,
+            upstream_integration_policy,
+            ai_provider,
+            commit_message_style,
+            disabled_git_hooks,
+            trusted_claude_policy,
         }: Project,
     ) -> Self {
         UpdateRequest {
@@ -105,6 +123,11 @@ impl From<Project> for UpdateRequest {
             forge_override,
             unset_forge_override: false,
             preferred_forge_user,
+            upstream_integration_policy: Some(upstream_integration_policy),
+            ai_provider: Some(ai_provider),
+            commit_message_style: Some(commit_message_style),
+            disabled_git_hooks: Some(disabled_git_hooks),
+            trusted_claude_policy,
         }
     }
 }
@@ -176,6 +199,11 @@ impl Storage {
             forge_override,
             unset_forge_override,
             preferred_forge_user,
+            upstream_integration_policy,
+            ai_provider,
+            commit_message_style,
+            disabled_git_hooks,
+            trusted_claude_policy,
         }: UpdateRequest,
     ) -> Result<Project> {
         let mut projects = self.list()?;
@@ -252,6 +280,26 @@ impl Storage {
             project.snapshot_lines_threshold = Some(snapshot_lines_threshold);
         }
 
+        if let Some(upstream_integration_policy) = upstream_integration_policy {
+            project.upstream_integration_policy = upstream_integration_policy;
+        }
+
+        if let Some(ai_provider) = ai_provider {
+            project.ai_provider = ai_provider;
+        }
+
+        if let Some(commit_message_style) = commit_message_style {
+            project.commit_message_style = commit_message_style;
+        }
+
+        if let Some(disabled_git_hooks) = disabled_git_hooks {
+            project.disabled_git_hooks = disabled_git_hooks;
+        }
+
+        if let Some(trusted_claude_policy) = trusted_claude_policy {
+            project.trusted_claude_policy = Some(trusted_claude_policy);
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
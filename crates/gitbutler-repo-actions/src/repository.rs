@@ -28,6 +28,19 @@ pub trait RepoActionsExt {
         askpass_broker: Option<Option<StackId>>,
         push_opts: Vec<String>,
     ) -> Result<String>;
+    /// Push each `(head, branch)` in `updates` in a single push that either updates all branches
+    /// on their remote or none of them.
+    /// All branches must be on the same remote.
+    ///
+    /// Returns the stderr output of the git executable, or `None` if nothing was pushed as atomic pushes
+    /// aren't available, either because the Git executable isn't used or because the remote doesn't support them.
+    fn push_atomic(
+        &self,
+        updates: &[(git2::Oid, RemoteRefname)],
+        with_force: bool,
+        force_push_protection: bool,
+        askpass_broker: Option<Option<StackId>>,
+    ) -> Result<Option<String>>;
     fn commit(
         &self,
         message: &str,
@@ -206,9 +219,10 @@ impl RepoActionsExt for Context {
                         repo_path,
                         gitbutler_git::tokio::TokioExecutor,
                         &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                        vec![gitbutler_git::RefSpec::parse(refspec).unwrap()],
                         with_force,
                         force_push_protection,
+                        false,
                         handle_git_prompt_push,
                         askpass_broker,
                         push_opts,
@@ -290,6 +304,67 @@ impl RepoActionsExt for Context {
         }
     }
 
+    fn push_atomic(
+        &self,
+        updates: &[(git2::Oid, RemoteRefname)],
+        with_force: bool,
+        force_push_protection: bool,
+        askpass_broker: Option<Option<StackId>>,
+    ) -> Result<Option<String>> {
+        if self.legacy_project.preferred_key != AuthKey::SystemExecutable {
+            return Ok(None);
+        }
+        let Some(remote) = updates
+            .first()
+            .map(|(_, branch)| branch.remote().to_string())
+        else {
+            return Ok(Some(String::new()));
+        };
+        if let Some((_, branch)) = updates.iter().find(|(_, branch)| branch.remote() != remote) {
+            bail!(
+                "Branches can only be pushed atomically to a single remote, but '{branch}' isn't on '{remote}'"
+            );
+        }
+        let refspecs = updates
+            .iter()
+            .map(|(head, branch)| {
+                gitbutler_git::RefSpec::parse(format!("{head}:refs/heads/{}", branch.branch()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let repo_path = self.workdir_or_gitdir()?;
+        match std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(gitbutler_git::push(
+                    repo_path,
+                    gitbutler_git::tokio::TokioExecutor,
+                    &remote,
+                    refspecs,
+                    with_force,
+                    force_push_protection,
+                    true,
+                    handle_git_prompt_push,
+                    askpass_broker,
+                    vec![],
+                ))
+        })
+        .join()
+        .unwrap()
+        {
+            Ok(result) => Ok(Some(result)),
+            Err(gitbutler_git::Error::AtomicPushUnsupported(err)) => {
+                tracing::info!(?err, "remote doesn't support atomic pushes");
+                Ok(None)
+            }
+            Err(gitbutler_git::Error::ForcePushProtection(e)) => Err(anyhow!(
+                "The force push was blocked because a remote branch contains commits that would be overwritten.\n\n{e}"
+            )
+            .context(Code::GitForcePushProtection)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     fn fetch(&self, remote_name: &str, askpass: Option<String>) -> Result<()> {
         let refspec = format!("+refs/heads/*:refs/remotes/{remote_name}/*");
 
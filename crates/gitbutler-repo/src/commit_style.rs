@@ -0,0 +1,383 @@
+//! Learn the style of commit messages from the recent history of a branch, and check messages against it.
+//!
+//! This covers the parts of a message style that reviewers typically enforce: conventional-commit types and scopes,
+//! ticket references, the length of the title and trailers.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use bstr::{BStr, ByteSlice};
+use but_ctx::Context;
+
+/// The number of commits on the target branch to learn the style from.
+pub const HISTORY_DEPTH: usize = 100;
+
+/// Below this number of messages, nothing is learned as the history isn't meaningful enough.
+const MIN_MESSAGES: usize = 5;
+
+/// A property is considered a convention if at least this percentage of messages have it.
+const CONVENTION_PERCENTAGE: usize = 80;
+
+/// The conventional-commit types that are accepted even if they weren't seen in the history yet.
+const WELL_KNOWN_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+/// The style of commit messages as learned from the history of a branch.
+///
+/// An instance created with [`Default`] has no conventions, and accepts every message.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CommitStyle {
+    /// If `true`, titles follow the conventional-commit format like `feat(scope): description`.
+    pub conventional: bool,
+    /// The conventional-commit types seen in the history, most used first.
+    pub types: Vec<String>,
+    /// The conventional-commit scopes seen in the history, most used first.
+    pub scopes: Vec<String>,
+    /// If `true`, conventional titles are expected to have a scope.
+    pub requires_scope: bool,
+    /// The prefix of ticket references like `ABC-` in `ABC-123` that messages are expected to contain.
+    pub ticket_prefix: Option<String>,
+    /// The maximum number of characters of the title.
+    pub max_title_len: Option<usize>,
+    /// The tokens of trailers like `Signed-off-by` that messages are expected to have.
+    pub trailers: Vec<String>,
+    /// Titles of recent commits, to serve as example.
+    pub examples: Vec<String>,
+}
+
+/// Learning
+impl CommitStyle {
+    /// Learn the style from `messages`, most recent first.
+    ///
+    /// Merges, reverts and fixups don't follow the usual style and should be filtered by the caller.
+    pub fn learn<'a>(messages: impl IntoIterator<Item = &'a BStr>) -> Self {
+        let messages: Vec<_> = messages.into_iter().map(Message::parse).collect();
+        let num_messages = messages.len();
+        if num_messages < MIN_MESSAGES {
+            return CommitStyle::default();
+        }
+        let is_convention = |count: usize| count * 100 >= num_messages * CONVENTION_PERCENTAGE;
+
+        let conventional_titles: Vec<_> = messages
+            .iter()
+            .filter_map(|message| ConventionalTitle::parse(&message.title))
+            .collect();
+        let conventional = is_convention(conventional_titles.len());
+
+        let mut ticket_prefixes = BTreeMap::<_, usize>::new();
+        for message in &messages {
+            let mut prefixes: Vec<_> = ticket_prefixes_in(&message.text).collect();
+            prefixes.sort();
+            prefixes.dedup();
+            for prefix in prefixes {
+                *ticket_prefixes.entry(prefix).or_default() += 1;
+            }
+        }
+
+        let mut trailers = BTreeMap::<_, usize>::new();
+        for message in &messages {
+            let mut tokens = message.trailers.clone();
+            tokens.sort();
+            tokens.dedup();
+            for token in tokens {
+                *trailers.entry(token).or_default() += 1;
+            }
+        }
+
+        let mut title_lengths: Vec<_> = messages
+            .iter()
+            .map(|message| message.title.chars().count())
+            .collect();
+        title_lengths.sort_unstable();
+
+        CommitStyle {
+            conventional,
+            types: by_frequency(conventional_titles.iter().map(|title| title.kind.clone())),
+            scopes: by_frequency(
+                conventional_titles
+                    .iter()
+                    .filter_map(|title| title.scope.clone()),
+            ),
+            requires_scope: conventional
+                && is_convention(
+                    conventional_titles
+                        .iter()
+                        .filter(|title| title.scope.is_some())
+                        .count(),
+                ),
+            ticket_prefix: ticket_prefixes
+                .into_iter()
+                .filter(|(_, count)| is_convention(*count))
+                .max_by_key(|(_, count)| *count)
+                .map(|(prefix, _)| prefix),
+            max_title_len: title_lengths
+                .get(num_messages * CONVENTION_PERCENTAGE / 100)
+                .copied()
+                // Leave some room as the history only shows what was needed so far.
+                .map(|len| len.max(50) + 10),
+            trailers: trailers
+                .into_iter()
+                .filter(|(_, count)| is_convention(*count))
+                .map(|(token, _)| token)
+                .collect(),
+            examples: messages
+                .iter()
+                .take(5)
+                .map(|message| message.title.clone())
+                .collect(),
+        }
+    }
+
+    /// Learn the style from up to `limit` commits reachable from `tip` by following the first parent,
+    /// skipping merges, reverts and fixups.
+    pub fn from_history(repo: &gix::Repository, tip: gix::ObjectId, limit: usize) -> Result<Self> {
+        let mut messages = Vec::new();
+        for info in repo.rev_walk([tip]).first_parent_only().all()? {
+            let commit = info?.object()?;
+            if commit.parent_ids().count() > 1 {
+                continue;
+            }
+            let message = commit.message_raw_sloppy().to_owned();
+            let title = message.lines().next().unwrap_or_default();
+            if ["Merge ", "Revert \"", "fixup!", "squash!", "amend!"]
+                .iter()
+                .any(|prefix| title.starts_with_str(prefix))
+            {
+                continue;
+            }
+            messages.push(message);
+            if messages.len() == limit {
+                break;
+            }
+        }
+        Ok(CommitStyle::learn(
+            messages.iter().map(|message| message.as_bstr()),
+        ))
+    }
+
+    /// Learn the style from the recent history of the target branch of the project in `ctx`,
+    /// or from `HEAD` if there is no target yet.
+    pub fn from_target_branch(ctx: &Context) -> Result<Self> {
+        let repo = ctx.repo.get()?;
+        let meta = but_meta::VirtualBranchesTomlMetadata::from_path(
+            ctx.project_data_dir().join("virtual_branches.toml"),
+        )?;
+        let tip = match meta.data().default_target.as_ref() {
+            Some(target) => target.sha,
+            None => match repo.head_id() {
+                Ok(id) => id.detach(),
+                // Unborn branches have no history to learn from.
+                Err(_) => return Ok(CommitStyle::default()),
+            },
+        };
+        CommitStyle::from_history(&repo, tip, HISTORY_DEPTH)
+    }
+}
+
+/// Validation
+impl CommitStyle {
+    /// Return a description of each way `message` deviates from this style, or nothing if it matches.
+    pub fn check(&self, message: &str) -> Vec<String> {
+        let message = Message::parse(message.into());
+        let mut problems = Vec::new();
+        if self.conventional {
+            match ConventionalTitle::parse(&message.title) {
+                None => problems.push(format!(
+                    "The title should look like `{}: description`",
+                    self.title_format()
+                )),
+                Some(title) => {
+                    if !self.types.contains(&title.kind)
+                        && !WELL_KNOWN_TYPES.contains(&title.kind.as_str())
+                    {
+                        problems.push(format!(
+                            "The type `{}` isn't used in this repository, use one of {}",
+                            title.kind,
+                            self.types.join(", ")
+                        ));
+                    }
+                    if self.requires_scope && title.scope.is_none() {
+                        problems.push(format!(
+                            "The title should have a scope, like `{}`",
+                            self.title_format()
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(prefix) = &self.ticket_prefix
+            && !ticket_prefixes_in(&message.text).any(|found| &found == prefix)
+        {
+            problems.push(format!(
+                "The message should reference a ticket like `{prefix}123`"
+            ));
+        }
+        if let Some(max_len) = self.max_title_len {
+            let len = message.title.chars().count();
+            if len > max_len {
+                problems.push(format!(
+                    "The title has {len} characters, but should have at most {max_len}"
+                ));
+            }
+        }
+        for token in &self.trailers {
+            if !message.trailers.contains(token) {
+                problems.push(format!("The message should have a `{token}:` trailer"));
+            }
+        }
+        problems
+    }
+
+    /// Describe this style as instructions for writing a commit message, or return `None` if nothing was learned.
+    pub fn instructions(&self) -> Option<String> {
+        let mut rules = Vec::new();
+        if self.conventional {
+            rules.push(format!(
+                "The title must follow the conventional-commit format `{}: description`.",
+                self.title_format()
+            ));
+            if !self.types.is_empty() {
+                rules.push(format!(
+                    "Use one of these types: {}.",
+                    self.types.join(", ")
+                ));
+            }
+            if !self.scopes.is_empty() {
+                rules.push(format!(
+                    "Prefer one of these scopes if it fits: {}.",
+                    self.scopes
+                        .iter()
+                        .take(20)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+        if let Some(prefix) = &self.ticket_prefix {
+            rules.push(format!(
+                "Reference the ticket like `{prefix}123` if one is mentioned in the prompt or summary."
+            ));
+        }
+        if let Some(max_len) = self.max_title_len {
+            rules.push(format!(
+                "The title must be no more than {max_len} characters."
+            ));
+        }
+        if !self.trailers.is_empty() {
+            rules.push(format!(
+                "End the message with these trailers: {}.",
+                self.trailers.join(", ")
+            ));
+        }
+        if rules.is_empty() {
+            return None;
+        }
+
+        let mut out: String = rules.iter().map(|rule| format!("- {rule}\n")).collect();
+        if !self.examples.is_empty() {
+            out.push_str("\nThese are titles of recent commits in this repository:\n\n");
+            for example in &self.examples {
+                out.push_str(example);
+                out.push('\n');
+            }
+        }
+        Some(out)
+    }
+
+    fn title_format(&self) -> &'static str {
+        if self.requires_scope {
+            "type(scope)"
+        } else {
+            "type"
+        }
+    }
+}
+
+/// The parts of a commit message that matter for its style.
+struct Message {
+    text: String,
+    title: String,
+    trailers: Vec<String>,
+}
+
+impl Message {
+    fn parse(message: &BStr) -> Self {
+        let text = message.to_str_lossy().trim().to_owned();
+        let parsed = gix::objs::commit::MessageRef::from_bytes(text.as_bytes());
+        Message {
+            title: parsed.title.to_str_lossy().trim().to_owned(),
+            trailers: parsed
+                .body()
+                .map(|body| {
+                    body.trailers()
+                        .map(|trailer| trailer.token.to_str_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            text,
+        }
+    }
+}
+
+/// A title like `feat(scope)!: description`.
+struct ConventionalTitle {
+    kind: String,
+    scope: Option<String>,
+}
+
+impl ConventionalTitle {
+    fn parse(title: &str) -> Option<Self> {
+        let (head, description) = title.split_once(": ")?;
+        if description.trim().is_empty() {
+            return None;
+        }
+        let head = head.strip_suffix('!').unwrap_or(head);
+        let (kind, scope) = match head.split_once('(') {
+            Some((kind, scope)) => {
+                let scope = scope.strip_suffix(')')?;
+                if scope.is_empty() || scope.contains(['(', ')']) {
+                    return None;
+                }
+                (kind, Some(scope.to_owned()))
+            }
+            None => (head, None),
+        };
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+            return None;
+        }
+        Some(ConventionalTitle {
+            kind: kind.to_owned(),
+            scope,
+        })
+    }
+}
+
+/// Return the prefixes of all ticket references like `ABC-123` in `text`, e.g. `ABC-`.
+fn ticket_prefixes_in(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .filter_map(|word| {
+            let (key, number) = word.split_once('-')?;
+            let is_key = key.len() >= 2
+                && key.starts_with(|c: char| c.is_ascii_uppercase())
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+            let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+            (is_key && is_number).then(|| format!("{key}-"))
+        })
+}
+
+/// Return the distinct `items`, most frequent first.
+fn by_frequency(items: impl Iterator<Item = String>) -> Vec<String> {
+    let mut counts = BTreeMap::<_, usize>::new();
+    for item in items {
+        *counts.entry(item).or_default() += 1;
+    }
+    let mut items: Vec<_> = counts.into_iter().collect();
+    items.sort_by(|(a_item, a_count), (b_item, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_item.cmp(b_item))
+    });
+    items.into_iter().map(|(item, _)| item).collect()
+}
@@ -1,13 +1,21 @@
-use std::{io::Write, path::PathBuf, process::Stdio};
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
 use anyhow::Result;
 use bstr::ByteSlice;
 use but_ctx::Context;
 use git2_hooks::{self, HookResult as H};
 use gitbutler_diff::GitHunk;
+pub use gitbutler_project::GitHook;
+use gitbutler_project::Project;
 use serde::Serialize;
 
-use crate::staging;
+use crate::{commit_style::CommitStyle, staging};
 
 #[derive(Serialize, PartialEq, Debug, Clone)]
 pub struct MessageData {
@@ -41,28 +49,71 @@ pub enum MessageHookResult {
     Failure(ErrorData),
 }
 
-pub fn commit_msg(ctx: &Context, mut message: String) -> Result<MessageHookResult> {
+/// Run the `prepare-commit-msg` and then the `commit-msg` hook on `message`, and if the project asks for it,
+/// check the resulting message against the style learned from the target branch.
+pub fn commit_msg(ctx: &Context, message: String) -> Result<MessageHookResult> {
     let original_message = message.clone();
-    match git2_hooks::hooks_commit_msg(&*ctx.git2_repo.get()?, Some(&["../.husky"]), &mut message)?
-    {
-        H::Ok { hook: _ } => match message == original_message {
-            true => Ok(MessageHookResult::Success),
-            false => Ok(MessageHookResult::Message(MessageData { message })),
-        },
-        H::NoHookFound => Ok(MessageHookResult::NotConfigured),
-        H::RunNotSuccessful {
-            stdout,
-            stderr,
-            code,
-            ..
-        } => {
-            let error = join_output(stdout, stderr, code);
-            Ok(MessageHookResult::Failure(ErrorData { error }))
+    let disabled_hooks = &ctx.legacy_project.disabled_git_hooks;
+    let (mut message, mut configured) = match prepare_commit_msg(
+        &*ctx.repo.get()?,
+        message,
+        CommitMessageSource::Message,
+        disabled_hooks,
+    )? {
+        MessageHookResult::Success => (original_message.clone(), true),
+        MessageHookResult::NotConfigured => (original_message.clone(), false),
+        MessageHookResult::Message(MessageData { message }) => (message, true),
+        failure @ MessageHookResult::Failure(_) => return Ok(failure),
+    };
+    if !disabled_hooks.contains(&GitHook::CommitMsg) {
+        match git2_hooks::hooks_commit_msg(
+            &*ctx.git2_repo.get()?,
+            Some(&["../.husky"]),
+            &mut message,
+        )? {
+            H::Ok { hook: _ } => configured = true,
+            H::NoHookFound => {}
+            H::RunNotSuccessful {
+                stdout,
+                stderr,
+                code,
+                ..
+            } => {
+                let error = join_output(stdout, stderr, code);
+                return Ok(MessageHookResult::Failure(ErrorData { error }));
+            }
         }
     }
+    let result = if message != original_message {
+        MessageHookResult::Message(MessageData {
+            message: message.clone(),
+        })
+    } else if configured {
+        MessageHookResult::Success
+    } else {
+        MessageHookResult::NotConfigured
+    };
+
+    if !ctx.legacy_project.commit_message_style.validate {
+        return Ok(result);
+    }
+    let problems = CommitStyle::from_target_branch(ctx)?.check(&message);
+    if problems.is_empty() {
+        return Ok(result);
+    }
+    let error = std::iter::once(
+        "The commit message doesn't match the style of this repository:".to_owned(),
+    )
+    .chain(problems.into_iter().map(|problem| format!("- {problem}")))
+    .collect::<Vec<_>>()
+    .join("\n");
+    Ok(MessageHookResult::Failure(ErrorData { error }))
 }
 
 pub fn pre_commit(ctx: &Context, selected_hunks: &[(PathBuf, Vec<GitHunk>)]) -> Result<HookResult> {
+    if is_disabled(&ctx.legacy_project, GitHook::PreCommit) {
+        return Ok(HookResult::NotConfigured);
+    }
     let repo = &*ctx.git2_repo.get()?;
     let original_tree = repo.index()?.write_tree()?;
 
@@ -93,6 +144,9 @@ pub fn pre_commit(ctx: &Context, selected_hunks: &[(PathBuf, Vec<GitHunk>)]) ->
 }
 
 pub fn pre_commit_with_tree(ctx: &Context, tree_id: git2::Oid) -> Result<HookResult> {
+    if is_disabled(&ctx.legacy_project, GitHook::PreCommit) {
+        return Ok(HookResult::NotConfigured);
+    }
     let repo = &*ctx.git2_repo.get()?;
     let original_tree = repo.index()?.write_tree()?;
 
@@ -126,6 +180,9 @@ pub fn pre_commit_with_tree(ctx: &Context, tree_id: git2::Oid) -> Result<HookRes
 }
 
 pub fn post_commit(ctx: &Context) -> Result<HookResult> {
+    if is_disabled(&ctx.legacy_project, GitHook::PostCommit) {
+        return Ok(HookResult::NotConfigured);
+    }
     match git2_hooks::hooks_post_commit(&*ctx.git2_repo.get()?, Some(&["../.husky"]))? {
         H::Ok { hook: _ } => Ok(HookResult::Success),
         H::NoHookFound => Ok(HookResult::NotConfigured),
@@ -152,6 +209,7 @@ pub fn pre_push(
     remote_url: &str,
     local_commit: git2::Oid,
     remote_tracking_branch: &gitbutler_reference::RemoteRefname,
+    disabled_hooks: &BTreeSet<GitHook>,
 ) -> Result<HookResult> {
     let hooks_dir = repo
         .config()
@@ -173,10 +231,234 @@ pub fn pre_push(
     let Some(hook_path) = hook_path else {
         return Ok(HookResult::NotConfigured);
     };
+    if disabled_hooks.contains(&GitHook::PrePush) {
+        return Ok(HookResult::NotConfigured);
+    }
+
+    let remote_commit = repo
+        .find_reference(&remote_tracking_branch.to_string())
+        .ok()
+        .and_then(|r| r.target())
+        .unwrap_or_else(git2::Oid::zero);
+    // THIS IS WRONG: but is correct in the common case. This also is an issue when the ref is actually pushed,
+    // but we can fix it when moving everything to `gix`.
+    let local_tracking_branch_deduced = format!("refs/heads/{}", remote_tracking_branch.branch());
 
     // Execute the pre-push hook with remote name and URL as arguments
+    run_hook_at(
+        &hook_path,
+        repo.workdir().unwrap_or_else(|| repo.path()),
+        [remote_name, remote_url],
+        Some(format!(
+            "{local_tracking_branch_deduced} {local_commit} {remote_tracking_branch} {remote_commit}\n"
+        )),
+    )
+}
+
+/// Where the message passed to the `prepare-commit-msg` hook comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitMessageSource {
+    /// The message was provided by the user for a new commit, like with `git commit -m`.
+    Message,
+    /// The message is the one of the given commit, which is amended.
+    Commit(gix::ObjectId),
+}
+
+/// Run the `prepare-commit-msg` hook on `message` before a commit is created with it, allowing the hook to edit it.
+///
+/// As with Git, a failure means that the commit shouldn't be created.
+pub fn prepare_commit_msg(
+    repo: &gix::Repository,
+    message: String,
+    source: CommitMessageSource,
+    disabled_hooks: &BTreeSet<GitHook>,
+) -> Result<MessageHookResult> {
+    let Some(hook_path) = find_hook(repo, disabled_hooks, GitHook::PrepareCommitMsg) else {
+        return Ok(MessageHookResult::NotConfigured);
+    };
+
+    let message_path = repo.git_dir().join("COMMIT_EDITMSG");
+    std::fs::write(&message_path, &message)?;
+    let mut args = vec![message_path.clone().into_os_string()];
+    match source {
+        CommitMessageSource::Message => args.push("message".into()),
+        CommitMessageSource::Commit(id) => {
+            args.push("commit".into());
+            args.push(id.to_string().into());
+        }
+    }
+    Ok(
+        match run_hook_at(&hook_path, &workdir_or_git_dir(repo), args, None)? {
+            HookResult::Failure(error) => MessageHookResult::Failure(error),
+            HookResult::Success | HookResult::NotConfigured => {
+                let new_message = std::fs::read_to_string(&message_path)?;
+                if new_message == message {
+                    MessageHookResult::Success
+                } else {
+                    MessageHookResult::Message(MessageData {
+                        message: new_message,
+                    })
+                }
+            }
+        },
+    )
+}
+
+/// The operation that rewrote commits, as passed to the `post-rewrite` hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewriteCommand {
+    /// Commits were amended.
+    Amend,
+    /// Commits were rebased.
+    Rebase,
+}
+
+/// Run the `post-rewrite` hook after `command` rewrote commits, with `rewritten` as `(old, new)` commit ids.
+///
+/// It can't undo the rewrite, so a failure is only for reporting.
+pub fn post_rewrite(
+    repo: &gix::Repository,
+    command: RewriteCommand,
+    rewritten: &[(gix::ObjectId, gix::ObjectId)],
+    disabled_hooks: &BTreeSet<GitHook>,
+) -> Result<HookResult> {
+    let Some(hook_path) = find_hook(repo, disabled_hooks, GitHook::PostRewrite) else {
+        return Ok(HookResult::NotConfigured);
+    };
+    let command = match command {
+        RewriteCommand::Amend => "amend",
+        RewriteCommand::Rebase => "rebase",
+    };
+    let stdin = rewritten
+        .iter()
+        .map(|(old, new)| format!("{old} {new}\n"))
+        .collect();
+    run_hook_at(
+        &hook_path,
+        &workdir_or_git_dir(repo),
+        [command],
+        Some(stdin),
+    )
+}
+
+/// Run the `post-checkout` hook after the worktree was checked out from `previous_head` to `new_head`.
+///
+/// It can't undo the checkout, so a failure is only for reporting.
+pub fn post_checkout(
+    repo: &gix::Repository,
+    previous_head: gix::ObjectId,
+    new_head: gix::ObjectId,
+    disabled_hooks: &BTreeSet<GitHook>,
+) -> Result<HookResult> {
+    let Some(hook_path) = find_hook(repo, disabled_hooks, GitHook::PostCheckout) else {
+        return Ok(HookResult::NotConfigured);
+    };
+    // The last argument indicates a branch checkout, as opposed to a checkout of files.
+    run_hook_at(
+        &hook_path,
+        &workdir_or_git_dir(repo),
+        [previous_head.to_string(), new_head.to_string(), "1".into()],
+        None,
+    )
+}
+
+/// Run the `reference-transaction` hook in the `committed` state after the references in `updates` were changed,
+/// each as `(old, new, name)`.
+///
+/// The hook is only notified: unlike Git, GitButler doesn't run it in the `prepared` state before
+/// references are written, so it can't veto an update, and never runs it in the `aborted` state.
+/// The references were already changed, so a failure is only for reporting.
+pub fn reference_transaction_committed(
+    repo: &gix::Repository,
+    updates: &[(gix::ObjectId, gix::ObjectId, &gix::refs::FullNameRef)],
+    disabled_hooks: &BTreeSet<GitHook>,
+) -> Result<HookResult> {
+    let Some(hook_path) = find_hook(repo, disabled_hooks, GitHook::ReferenceTransaction) else {
+        return Ok(HookResult::NotConfigured);
+    };
+    if updates.is_empty() {
+        return Ok(HookResult::Success);
+    }
+    let stdin = updates
+        .iter()
+        .map(|(old, new, name)| format!("{old} {new} {}\n", name.as_bstr()))
+        .collect();
+    run_hook_at(
+        &hook_path,
+        &workdir_or_git_dir(repo),
+        ["committed"],
+        Some(stdin),
+    )
+}
+
+/// Log the failure of `hook` that ran after an operation, as it doesn't affect the operation.
+pub fn log_failure(hook: GitHook, result: Result<HookResult>) {
+    match result {
+        Ok(HookResult::Success | HookResult::NotConfigured) => {}
+        Ok(HookResult::Failure(ErrorData { error })) => {
+            tracing::warn!("The {} hook failed: {error}", hook.file_name())
+        }
+        Err(err) => tracing::warn!("Could not run the {} hook: {err:#}", hook.file_name()),
+    }
+}
+
+/// Return `true` if `hook` was disabled in the settings of `project`.
+fn is_disabled(project: &Project, hook: GitHook) -> bool {
+    project.disabled_git_hooks.contains(&hook)
+}
+
+/// Return the hooks that were disabled for the project of the worktree at `workdir`, if there is one.
+///
+/// This loads the project, so it should be called once per operation and passed to the hooks it runs.
+pub fn disabled_hooks_at(workdir: Option<&Path>) -> BTreeSet<GitHook> {
+    workdir
+        .and_then(|workdir| Project::find_by_worktree_dir_opt(workdir).ok().flatten())
+        .map(|project| project.disabled_git_hooks)
+        .unwrap_or_default()
+}
+
+/// Find the executable of `hook` in the hooks directory of `repo`, or in `.husky` of its worktree,
+/// unless it is among `disabled_hooks`.
+fn find_hook(
+    repo: &gix::Repository,
+    disabled_hooks: &BTreeSet<GitHook>,
+    hook: GitHook,
+) -> Option<PathBuf> {
+    if disabled_hooks.contains(&hook) {
+        return None;
+    }
+    let workdir = repo.workdir();
+    let hooks_dir = repo
+        .config_snapshot()
+        .trusted_path("core.hooksPath")
+        .and_then(Result::ok)
+        .map(|path| match workdir {
+            Some(workdir) if path.is_relative() => workdir.join(path),
+            _ => path.into_owned(),
+        })
+        .unwrap_or_else(|| repo.common_dir().join("hooks"));
+    [
+        Some(hooks_dir.join(hook.file_name())),
+        workdir.map(|workdir| workdir.join(".husky").join(hook.file_name())),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|path| path.is_file())
+}
+
+fn workdir_or_git_dir(repo: &gix::Repository) -> PathBuf {
+    repo.workdir().unwrap_or_else(|| repo.git_dir()).to_owned()
+}
+
+/// Run the hook executable at `hook_path` in `workdir` with `args`, and write `stdin` to it.
+fn run_hook_at(
+    hook_path: &Path,
+    workdir: &Path,
+    args: impl IntoIterator<Item = impl Into<OsString>>,
+    stdin: Option<String>,
+) -> Result<HookResult> {
     let mut child = std::process::Command::from({
-        let mut prep = gix::command::prepare(&hook_path);
+        let mut prep = gix::command::prepare(hook_path);
         if cfg!(windows) {
             prep.use_shell = true;
             prep.allow_manual_arg_splitting = false;
@@ -186,35 +468,25 @@ pub fn pre_push(
             );
             prep.command = gix::path::from_bstring(with_slashes_for_bash.into_owned()).into();
         }
-        prep.arg(remote_name).arg(remote_url)
+        prep.args(args)
     })
-    .current_dir(repo.workdir().unwrap_or_else(|| repo.path()))
+    .current_dir(workdir)
     .stdin(Stdio::piped())
     .spawn()?;
 
-    {
+    if let Some(stdin) = stdin {
         // Wait for the child process to be ready before writing to stdin.
         // Check if the process has already exited unexpectedly.
         if let Some(status) = child.try_wait()? {
             // Process already exited, don't write to stdin.
-            let error = format!("pre-push hook exited early with status: {}", status);
+            let error = format!("hook exited early with status: {status}");
             return Ok(HookResult::Failure(ErrorData { error }));
         }
-
-        let remote_commit = repo
-            .find_reference(&remote_tracking_branch.to_string())
-            .ok()
-            .and_then(|r| r.target())
-            .unwrap_or_else(git2::Oid::zero);
-        // THIS IS WRONG: but is correct in the common case. This also is an issue when the ref is actually pushed,
-        // but we can fix it when moving everything to `gix`.
-        let local_tracking_branch_deduced =
-            format!("refs/heads/{}", remote_tracking_branch.branch());
-        let stdin = child.stdin.as_mut().expect("configured");
-        stdin.write_all(
-            format!("{local_tracking_branch_deduced} {local_commit} {remote_tracking_branch} {remote_commit}\n")
-                .as_bytes(),
-        )?;
+        child
+            .stdin
+            .as_mut()
+            .expect("configured")
+            .write_all(stdin.as_bytes())?;
     }
 
     let output = child.wait_with_output()?;
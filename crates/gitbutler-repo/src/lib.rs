@@ -25,6 +25,8 @@ pub mod logging;
 
 pub mod commit_message;
 
+pub mod commit_style;
+
 use but_oxidize::gix_to_git2_signature;
 pub const GITBUTLER_COMMIT_AUTHOR_NAME: &str = "GitButler";
 pub const GITBUTLER_COMMIT_AUTHOR_EMAIL: &str = "gitbutler@gitbutler.com";
@@ -0,0 +1,83 @@
+use bstr::BStr;
+use gitbutler_repo::commit_style::CommitStyle;
+
+fn learn(messages: &[&str]) -> CommitStyle {
+    CommitStyle::learn(messages.iter().map(|message| BStr::new(message)))
+}
+
+#[test]
+fn too_few_messages_learn_nothing() {
+    let style = learn(&["feat(ui): add button", "fix(ui): align button"]);
+    assert_eq!(style, CommitStyle::default());
+    assert!(style.check("whatever").is_empty());
+    assert_eq!(style.instructions(), None);
+}
+
+#[test]
+fn conventional_commits_with_scopes_and_tickets() {
+    let style = learn(&[
+        "feat(ui): add button\n\nRefs: ABC-12",
+        "fix(ui): align button\n\nRefs: ABC-13",
+        "feat(api): add endpoint\n\nRefs: ABC-14",
+        "chore(deps): bump gix\n\nRefs: ABC-15",
+        "fix(api): handle empty body\n\nRefs: ABC-16\nSigned-off-by: A <a@example.com>",
+    ]);
+    assert!(style.conventional);
+    assert!(style.requires_scope);
+    assert_eq!(style.types, ["feat", "fix", "chore"]);
+    assert_eq!(style.scopes, ["api", "ui", "deps"]);
+    assert_eq!(style.ticket_prefix.as_deref(), Some("ABC-"));
+    assert_eq!(
+        style.trailers,
+        ["Refs"],
+        "trailers of only some messages aren't required"
+    );
+    assert_eq!(style.max_title_len, Some(60));
+
+    assert!(
+        style
+            .check("feat(cli): add flag\n\nRefs: ABC-99")
+            .is_empty()
+    );
+    assert_eq!(
+        style.check("Add a flag to the CLI that is very long and does a lot of things at once"),
+        [
+            "The title should look like `type(scope): description`",
+            "The message should reference a ticket like `ABC-123`",
+            "The title has 72 characters, but should have at most 60",
+            "The message should have a `Refs:` trailer",
+        ]
+    );
+    assert_eq!(
+        style.check("feet: add flag\n\nRefs: ABC-99"),
+        [
+            "The type `feet` isn't used in this repository, use one of feat, fix, chore",
+            "The title should have a scope, like `type(scope)`",
+        ]
+    );
+    assert!(
+        style
+            .check("revert(ui): remove button\n\nRefs: ABC-99")
+            .is_empty(),
+        "well-known types are accepted even if they weren't used yet"
+    );
+
+    let instructions = style.instructions().expect("a style was learned");
+    assert!(instructions.contains("`type(scope): description`"));
+    assert!(instructions.contains("feat(ui): add button"));
+}
+
+#[test]
+fn free_form_messages_only_limit_the_title() {
+    let style = learn(&[
+        "Add button",
+        "Align the button",
+        "Add an endpoint",
+        "Bump gix",
+        "Handle an empty body",
+    ]);
+    assert!(!style.conventional);
+    assert_eq!(style.ticket_prefix, None);
+    assert!(style.check("do things").is_empty());
+    assert_eq!(style.check(&"a".repeat(61)).len(), 1);
+}
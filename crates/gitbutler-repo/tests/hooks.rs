@@ -1,8 +1,11 @@
-use std::fs;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
+use std::{collections::BTreeSet, fs};
 
-use gitbutler_repo::hooks::{HookResult, pre_push};
+use gitbutler_repo::hooks::{
+    CommitMessageSource, GitHook, HookResult, MessageData, MessageHookResult, RewriteCommand,
+    post_checkout, post_rewrite, pre_push, prepare_commit_msg, reference_transaction_committed,
+};
 use gitbutler_testsupport::TestProject;
 
 #[test]
@@ -15,6 +18,7 @@ fn pre_push_hook_not_configured() -> anyhow::Result<()> {
         "https://github.com/test/repo.git",
         git2::Oid::zero(),
         &gitbutler_reference::RemoteRefname::new("origin", "does-not-matter"),
+        &BTreeSet::new(),
     );
     assert!(result.is_ok());
     assert_eq!(result?, HookResult::NotConfigured);
@@ -41,6 +45,7 @@ fn pre_push_hook_success() -> anyhow::Result<()> {
         "https://github.com/test/repo.git",
         repo.head()?.target().expect("not detached"),
         &gitbutler_reference::RemoteRefname::new("origin", "master"),
+        &BTreeSet::new(),
     )?;
     assert_eq!(result, HookResult::Success);
 
@@ -79,6 +84,7 @@ fn pre_push_hook_failure() -> anyhow::Result<()> {
         "https://github.com/test/repo.git",
         repo.head()?.target().expect("not detached"),
         &gitbutler_reference::RemoteRefname::new("origin", "master"),
+        &BTreeSet::new(),
     );
     match result.expect("success") {
         HookResult::Failure(error_data) => {
@@ -91,3 +97,167 @@ fn pre_push_hook_failure() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn prepare_commit_msg_hook_can_edit_the_message() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+    write_hook(
+        &repo,
+        "prepare-commit-msg",
+        "#!/bin/sh\nprintf 'ABC-1: ' | cat - \"$1\" > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"\necho \"$2\" > source\n",
+    )?;
+
+    let result = prepare_commit_msg(
+        &repo,
+        "message".into(),
+        CommitMessageSource::Message,
+        &BTreeSet::new(),
+    )?;
+    assert_eq!(
+        result,
+        MessageHookResult::Message(MessageData {
+            message: "ABC-1: message".into()
+        })
+    );
+    assert_eq!(
+        fs::read_to_string(repo.workdir().expect("non-bare").join("source"))?,
+        "message\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn prepare_commit_msg_hook_not_configured() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+
+    let result = prepare_commit_msg(
+        &repo,
+        "message".into(),
+        CommitMessageSource::Message,
+        &BTreeSet::new(),
+    )?;
+    assert_eq!(result, MessageHookResult::NotConfigured);
+    Ok(())
+}
+
+#[test]
+fn post_rewrite_hook_receives_rewritten_commits() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+    write_hook(
+        &repo,
+        "post-rewrite",
+        "#!/bin/sh\necho $1 > rewrite.input\ncat >>rewrite.input\n",
+    )?;
+
+    let old = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    let new = gix::ObjectId::empty_blob(gix::hash::Kind::Sha1);
+    let result = post_rewrite(
+        &repo,
+        RewriteCommand::Amend,
+        &[(old, new)],
+        &BTreeSet::new(),
+    )?;
+    assert_eq!(result, HookResult::Success);
+    assert_eq!(
+        fs::read_to_string(repo.workdir().expect("non-bare").join("rewrite.input"))?,
+        format!("amend\n{old} {new}\n")
+    );
+    Ok(())
+}
+
+#[test]
+fn post_checkout_hook_failure() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+    write_hook(&repo, "post-checkout", "#!/bin/sh\necho $@\nexit 1\n")?;
+
+    let old = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    let new = gix::ObjectId::empty_blob(gix::hash::Kind::Sha1);
+    let result = post_checkout(&repo, old, new, &BTreeSet::new())?;
+    assert_eq!(
+        result,
+        HookResult::Failure(gitbutler_repo::hooks::ErrorData {
+            error: format!("{old} {new} 1\n")
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn reference_transaction_hook_receives_committed_updates() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+    write_hook(
+        &repo,
+        "reference-transaction",
+        "#!/bin/sh\necho $1 > transaction.input\ncat >>transaction.input\n",
+    )?;
+
+    let old = gix::ObjectId::null(gix::hash::Kind::Sha1);
+    let new = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    let name: gix::refs::FullName = "refs/heads/gitbutler/workspace".try_into()?;
+    let result =
+        reference_transaction_committed(&repo, &[(old, new, name.as_ref())], &BTreeSet::new())?;
+    assert_eq!(result, HookResult::Success);
+    assert_eq!(
+        fs::read_to_string(repo.workdir().expect("non-bare").join("transaction.input"))?,
+        format!("committed\n{old} {new} refs/heads/gitbutler/workspace\n")
+    );
+    Ok(())
+}
+
+#[test]
+fn disabled_hooks_are_not_run() -> anyhow::Result<()> {
+    let test_project = TestProject::default();
+    let repo = gix::open(test_project.local_repo.path())?;
+    for name in [
+        "prepare-commit-msg",
+        "post-rewrite",
+        "reference-transaction",
+    ] {
+        write_hook(&repo, name, "#!/bin/sh\nexit 1\n")?;
+    }
+    let disabled_hooks = BTreeSet::from([
+        GitHook::PrepareCommitMsg,
+        GitHook::PostRewrite,
+        GitHook::ReferenceTransaction,
+    ]);
+
+    let result = prepare_commit_msg(
+        &repo,
+        "message".into(),
+        CommitMessageSource::Message,
+        &disabled_hooks,
+    )?;
+    assert_eq!(result, MessageHookResult::NotConfigured);
+
+    let old = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    let new = gix::ObjectId::empty_blob(gix::hash::Kind::Sha1);
+    let result = post_rewrite(
+        &repo,
+        RewriteCommand::Rebase,
+        &[(old, new)],
+        &disabled_hooks,
+    )?;
+    assert_eq!(result, HookResult::NotConfigured);
+
+    let name: gix::refs::FullName = "refs/heads/main".try_into()?;
+    let result =
+        reference_transaction_committed(&repo, &[(old, new, name.as_ref())], &disabled_hooks)?;
+    assert_eq!(result, HookResult::NotConfigured);
+    Ok(())
+}
+
+fn write_hook(repo: &gix::Repository, name: &str, script: &str) -> anyhow::Result<()> {
+    let hooks_dir = repo.git_dir().join("hooks");
+    fs::create_dir_all(&hooks_dir)?;
+    let hook_path = hooks_dir.join(name);
+    fs::write(&hook_path, script)?;
+
+    #[cfg(unix)]
+    fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
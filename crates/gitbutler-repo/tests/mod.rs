@@ -1,3 +1,4 @@
+mod commit_style;
 mod create_wd_tree;
 mod credentials;
 mod hooks;
@@ -1,4 +1,3 @@
-use but_action::OpenAiProvider;
 use but_api::json::Error;
 use but_core::ui::TreeChange;
 use but_ctx::Context;
@@ -62,7 +61,10 @@ pub fn auto_commit(
     let changes: Vec<but_core::TreeChange> =
         changes.into_iter().map(|change| change.into()).collect();
     let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-    let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
+    let llm = but_action::provider_for_project(
+        &project,
+        Some(but_action::CredentialsKind::GitButlerProxied),
+    )?;
 
     let emitter = std::sync::Arc::new(move |name: &str, payload: serde_json::Value| {
         app_handle.emit(name, payload).unwrap_or_else(|e| {
@@ -70,13 +72,8 @@ pub fn auto_commit(
         });
     });
 
-    match openai {
-        Some(openai) => but_action::auto_commit(emitter, ctx, &openai, changes)
-            .map_err(|e| Error::from(anyhow::anyhow!(e))),
-        None => Err(Error::from(anyhow::anyhow!(
-            "No valid credentials found for AI provider. Please configure your GitButler account credentials."
-        ))),
-    }
+    but_action::auto_commit(emitter, ctx, &*llm, changes)
+        .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
 
 #[tauri::command(async)]
@@ -90,7 +87,10 @@ pub fn auto_branch_changes(
     let changes: Vec<but_core::TreeChange> =
         changes.into_iter().map(|change| change.into()).collect();
     let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-    let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
+    let llm = but_action::provider_for_project(
+        &project,
+        Some(but_action::CredentialsKind::GitButlerProxied),
+    )?;
 
     let emitter = std::sync::Arc::new(move |name: &str, payload: serde_json::Value| {
         app_handle.emit(name, payload).unwrap_or_else(|e| {
@@ -98,13 +98,8 @@ pub fn auto_branch_changes(
         });
     });
 
-    match openai {
-        Some(openai) => but_action::branch_changes(emitter, ctx, &openai, changes)
-            .map_err(|e| Error::from(anyhow::anyhow!(e))),
-        None => Err(Error::from(anyhow::anyhow!(
-            "No valid credentials found for AI provider. Please configure your GitButler account credentials."
-        ))),
-    }
+    but_action::branch_changes(emitter, ctx, &*llm, changes)
+        .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
 
 #[tauri::command(async)]
@@ -118,7 +113,10 @@ pub fn absorb(
     let changes: Vec<but_core::TreeChange> =
         changes.into_iter().map(|change| change.into()).collect();
     let ctx = &mut Context::new_from_legacy_project(project.clone())?;
-    let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
+    let llm = but_action::provider_for_project(
+        &project,
+        Some(but_action::CredentialsKind::GitButlerProxied),
+    )?;
 
     let emitter = std::sync::Arc::new(move |name: &str, payload: serde_json::Value| {
         app_handle.emit(name, payload).unwrap_or_else(|e| {
@@ -126,13 +124,7 @@ pub fn absorb(
         });
     });
 
-    match openai {
-        Some(openai) => but_action::absorb(emitter, ctx, &openai, changes)
-            .map_err(|e| Error::from(anyhow::anyhow!(e))),
-        None => Err(Error::from(anyhow::anyhow!(
-            "No valid credentials found for AI provider. Please configure your GitButler account credentials."
-        ))),
-    }
+    but_action::absorb(emitter, ctx, &*llm, changes).map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
 
 #[tauri::command(async)]
@@ -153,20 +145,18 @@ pub fn freestyle(
         });
     });
 
-    let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
-    match openai {
-        Some(openai) => but_action::freestyle(
-            project_id,
-            message_id,
-            emitter,
-            ctx,
-            &openai,
-            chat_messages,
-            model,
-        )
-        .map_err(|e| Error::from(anyhow::anyhow!(e))),
-        None => Err(Error::from(anyhow::anyhow!(
-            "No valid credentials found for AI provider. Please configure your GitButler account credentials."
-        ))),
-    }
+    let llm = but_action::provider_for_project(
+        &project,
+        Some(but_action::CredentialsKind::GitButlerProxied),
+    )?;
+    but_action::freestyle(
+        project_id,
+        message_id,
+        emitter,
+        ctx,
+        &*llm,
+        chat_messages,
+        model,
+    )
+    .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
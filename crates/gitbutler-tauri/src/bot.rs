@@ -1,4 +1,3 @@
-use but_action::OpenAiProvider;
 use but_api::json::Error;
 use but_ctx::Context;
 use gitbutler_project::ProjectId;
@@ -22,12 +21,10 @@ pub fn bot(
         });
     });
 
-    let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
-    match openai {
-        Some(openai) => but_bot::bot(project_id, message_id, emitter, ctx, &openai, chat_messages)
-            .map_err(|e| Error::from(anyhow::anyhow!(e))),
-        None => Err(Error::from(anyhow::anyhow!(
-            "No valid credentials found for AI provider. Please configure your GitButler account credentials."
-        ))),
-    }
+    let llm = but_action::provider_for_project(
+        &project,
+        Some(but_action::CredentialsKind::GitButlerProxied),
+    )?;
+    but_bot::bot(project_id, message_id, emitter, ctx, &*llm, chat_messages)
+        .map_err(|e| Error::from(anyhow::anyhow!(e)))
 }
@@ -2,7 +2,7 @@ use but_api::{
     json::Error,
     legacy::claude::{
         self, CancelSessionParams, CompactHistoryParams, GetMessagesParams, IsStackActiveParams,
-        SendMessageParams,
+        RestoreTurnParams, SendMessageParams,
     },
 };
 use but_claude::{
@@ -119,3 +119,25 @@ pub async fn claude_compact_history(
     .await
     .map_err(Into::into)
 }
+
+#[tauri::command(async)]
+#[instrument(skip(claude), err(Debug))]
+pub async fn claude_restore_turn(
+    claude: State<'_, Claude>,
+    project_id: ProjectId,
+    stack_id: StackId,
+    session_id: String,
+    message_id: String,
+) -> Result<(), Error> {
+    claude::claude_restore_turn(
+        &claude,
+        RestoreTurnParams {
+            project_id,
+            stack_id,
+            session_id,
+            message_id,
+        },
+    )
+    .await
+    .map_err(Into::into)
+}
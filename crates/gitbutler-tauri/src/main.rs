@@ -14,7 +14,7 @@
 use std::sync::Arc;
 
 use anyhow::bail;
-use but_api::{commit, diff, github, legacy};
+use but_api::{commit, diff, github, graph, legacy, secret};
 use but_claude::{Broadcaster, Claude};
 use but_settings::AppSettingsWithDiskSync;
 use gitbutler_tauri::{
@@ -60,6 +60,13 @@ fn main() -> anyhow::Result<()> {
     let mut app_settings =
         AppSettingsWithDiskSync::new_with_customization(config_dir.clone(), custom_settings)
             .expect("failed to create app settings");
+    but_secret::secret::set_store(
+        app_settings
+            .get()
+            .expect("settings were just loaded")
+            .secrets
+            .store,
+    );
 
     if let Ok(updated_csp) = csp_with_extras(
         tauri_context.config().app.security.csp.as_ref().cloned(),
@@ -156,6 +163,7 @@ fn main() -> anyhow::Result<()> {
                 app_settings.watch_in_background({
                     let app_handle = app_handle.clone();
                     move |app_settings| {
+                        but_secret::secret::set_store(app_settings.secrets.store);
                         gitbutler_tauri::ChangeForFrontend::from(app_settings).send(&app_handle)
                     }
                 })?;
@@ -298,6 +306,7 @@ fn main() -> anyhow::Result<()> {
                 legacy::stack::tauri_update_branch_name::update_branch_name,
                 legacy::stack::tauri_update_branch_pr_number::update_branch_pr_number,
                 legacy::stack::tauri_push_stack::push_stack,
+                legacy::stack::tauri_push_stacks::push_stacks,
                 legacy::stack::tauri_push_stack_to_review::push_stack_to_review,
                 legacy::secret::tauri_secret_get_global::secret_get_global,
                 legacy::secret::tauri_secret_set_global::secret_set_global,
@@ -354,6 +363,13 @@ fn main() -> anyhow::Result<()> {
                 legacy::claude::tauri_claude_get_session_details::claude_get_session_details,
                 legacy::claude::tauri_claude_list_permission_requests::claude_list_permission_requests,
                 legacy::claude::tauri_claude_update_permission_request::claude_update_permission_request,
+                legacy::claude::tauri_claude_get_usage::claude_get_usage,
+                legacy::claude::tauri_claude_set_session_budget::claude_set_session_budget,
+                legacy::claude::tauri_claude_get_permission_decisions::claude_get_permission_decisions,
+                legacy::claude::tauri_claude_trust_permission_policy::claude_trust_permission_policy,
+                legacy::claude::tauri_claude_list_turns::claude_list_turns,
+                legacy::claude::tauri_claude_export_session_markdown::claude_export_session_markdown,
+                legacy::claude::tauri_claude_list_pending_worktrees::claude_list_pending_worktrees,
                 legacy::claude::tauri_claude_check_available::claude_check_available,
                 legacy::claude::tauri_claude_list_prompt_templates::claude_list_prompt_templates,
                 legacy::claude::tauri_claude_get_prompt_dirs::claude_get_prompt_dirs,
@@ -395,9 +411,14 @@ fn main() -> anyhow::Result<()> {
                 claude::claude_cancel_session,
                 claude::claude_is_stack_active,
                 claude::claude_compact_history,
+                claude::claude_restore_turn,
                 commit::tauri_commit_reword::commit_reword,
                 commit::tauri_commit_insert_blank::commit_insert_blank,
                 commit::tauri_commit_move_changes_between::commit_move_changes_between,
+                commit::tauri_commit_split::commit_split,
+                graph::tauri_graph_json::graph_json,
+                graph::tauri_graph_mermaid::graph_mermaid,
+                secret::tauri_secret_migrate::secret_migrate,
 
             ])
             .menu(move |handle| menu::build(handle, &app_settings_for_menu))
@@ -28,8 +28,10 @@ pub struct User {
 }
 
 impl User {
-    pub(super) const ACCESS_TOKEN_HANDLE: &'static str = "gitbutler_access_token";
-    pub(super) const GITHUB_ACCESS_TOKEN_HANDLE: &'static str = "github_access_token";
+    /// The handle of the secret that holds the GitButler access token.
+    pub const ACCESS_TOKEN_HANDLE: &'static str = "gitbutler_access_token";
+    /// The handle of the secret that holds the GitHub access token.
+    pub const GITHUB_ACCESS_TOKEN_HANDLE: &'static str = "github_access_token";
 
     /// Return the access token of the user after fetching it from the secrets store.
     ///